@@ -1,3 +1,8 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 fn main() {
     // Enable SQLx offline mode by default
     // This uses cached query data from .sqlx/ directory
@@ -5,4 +10,68 @@ fn main() {
     if std::env::var("SQLX_OFFLINE").is_err() {
         println!("cargo:rustc-env=SQLX_OFFLINE=true");
     }
+
+    // Commit the binary was built from, reported by /api/version
+    // MANGO_GIT_COMMIT overrides it for builds without a .git directory (e.g. Docker)
+    let commit = std::env::var("MANGO_GIT_COMMIT")
+        .ok()
+        .filter(|c| !c.is_empty())
+        .or_else(git_commit)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=MANGO_GIT_COMMIT={}", commit);
+
+    // Build time as unix seconds; SOURCE_DATE_EPOCH keeps reproducible builds reproducible
+    let build_timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    println!("cargo:rustc-env=MANGO_BUILD_TIMESTAMP={}", build_timestamp);
+
+    // Hash of the static assets embedded in the binary, used as their ETag
+    let mut hasher = DefaultHasher::new();
+    hash_dir(Path::new("static"), Path::new("static"), &mut hasher);
+    println!("cargo:rustc-env=MANGO_STATIC_HASH={:016x}", hasher.finish());
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=static");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=SQLX_OFFLINE");
+    println!("cargo:rerun-if-env-changed=MANGO_GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
+
+/// Feed the relative path and contents of every file under `dir` to `hasher`,
+/// in a stable order
+fn hash_dir(root: &Path, dir: &Path, hasher: &mut DefaultHasher) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            hash_dir(root, &path, hasher);
+        } else if let Ok(contents) = std::fs::read(&path) {
+            path.strip_prefix(root).unwrap_or(&path).hash(hasher);
+            contents.hash(hasher);
+        }
+    }
+}
+
+/// Short hash of HEAD, or None outside a git checkout
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let commit = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!commit.is_empty()).then_some(commit)
 }
@@ -0,0 +1,8 @@
+-- Per-user daily download accounting
+-- Bytes served by /api/download, aggregated per user per UTC day
+CREATE TABLE IF NOT EXISTS download_usage (
+    username TEXT NOT NULL,
+    day TEXT NOT NULL,                -- UTC date (YYYY-MM-DD)
+    bytes INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (username, day)
+);
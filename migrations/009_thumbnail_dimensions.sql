@@ -0,0 +1,4 @@
+-- Add cover dimensions to thumbnails
+-- Legacy rows stay NULL and are backfilled when the thumbnail is next served
+ALTER TABLE thumbnails ADD COLUMN width INTEGER;
+ALTER TABLE thumbnails ADD COLUMN height INTEGER;
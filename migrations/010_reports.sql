@@ -0,0 +1,19 @@
+-- Problem reports filed by users against an entry (corrupt pages, wrong order, ...)
+-- Resolved reports are kept for history, pruned to a retention cap
+CREATE TABLE IF NOT EXISTS reports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    username TEXT NOT NULL,           -- Reporting user
+    category TEXT NOT NULL,           -- corrupt | misordered | wrong_title | other
+    note TEXT NOT NULL DEFAULT '',
+    created_at INTEGER NOT NULL,      -- Unix timestamp
+    resolved_at INTEGER,              -- NULL while open
+    resolved_by TEXT                  -- Admin who resolved it
+);
+
+CREATE INDEX IF NOT EXISTS reports_resolved_idx ON reports (resolved_at);
+
+-- A user can only have one open report per entry and category
+CREATE UNIQUE INDEX IF NOT EXISTS reports_open_dedup_idx
+    ON reports (entry_id, username, category) WHERE resolved_at IS NULL;
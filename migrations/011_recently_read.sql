@@ -0,0 +1,12 @@
+-- Per-user recently read index, updated on every progress save
+-- Lets continue reading show the latest entries without loading every info.json
+CREATE TABLE IF NOT EXISTS recently_read (
+    username TEXT NOT NULL,
+    title_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    page INTEGER NOT NULL DEFAULT 0,  -- Last saved page
+    last_read INTEGER NOT NULL,       -- Unix timestamp
+    PRIMARY KEY (username, title_id, entry_id)
+);
+
+CREATE INDEX IF NOT EXISTS recently_read_user_idx ON recently_read (username, last_read DESC);
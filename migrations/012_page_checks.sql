@@ -0,0 +1,8 @@
+-- Page validation results (only written when validate_pages is enabled)
+-- One row per checked entry; bad pages are flagged in the reader and excluded from progress
+CREATE TABLE IF NOT EXISTS page_checks (
+    entry_id TEXT PRIMARY KEY,
+    pages INTEGER NOT NULL,                -- Page count when checked (stale if it changed)
+    bad_pages TEXT NOT NULL DEFAULT '[]',  -- JSON array of one-based page numbers
+    checked_at INTEGER NOT NULL            -- Unix timestamp
+);
@@ -0,0 +1,12 @@
+-- Page thumbnail sprites for the reader scrub bar (generated on first request)
+-- A sprite is reused only while the entry signature is unchanged
+CREATE TABLE IF NOT EXISTS page_sprites (
+    entry_id TEXT PRIMARY KEY,
+    signature TEXT NOT NULL,      -- Entry signature when generated
+    data BLOB NOT NULL,           -- Frames concatenated horizontally
+    mime TEXT NOT NULL,
+    height INTEGER NOT NULL,      -- Frame height in pixels
+    step INTEGER NOT NULL,        -- Every step-th page has a frame
+    frames TEXT NOT NULL,         -- JSON array of {page, x, width}
+    created_at INTEGER NOT NULL   -- Unix timestamp
+);
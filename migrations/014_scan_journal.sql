@@ -0,0 +1,11 @@
+-- Journal of the library scan in progress, one row per title directory
+-- Rows are written as each title finishes, so a scan interrupted by a restart
+-- resumes from the pending set. Cleared when a scan completes
+CREATE TABLE IF NOT EXISTS scan_journal (
+    path TEXT PRIMARY KEY,          -- Title directory relative to the library root
+    scan_id TEXT NOT NULL,          -- Scan that created the journal
+    done INTEGER NOT NULL DEFAULT 0,
+    contents_signature TEXT,        -- Contents signature of the directory when scanned
+    result BLOB,                    -- Scanned title (MessagePack), NULL if the scan failed
+    updated_at INTEGER NOT NULL     -- Unix timestamp
+);
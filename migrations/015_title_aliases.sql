@@ -0,0 +1,14 @@
+-- Alternative names of a title (romaji, English, original...) used by search
+-- Aliases are stored normalized (trimmed, single spaces); position keeps the admin's order
+-- and the alias at position 0 is the primary one shown on the book page
+CREATE TABLE IF NOT EXISTS title_aliases (
+    id TEXT NOT NULL,
+    alias TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    UNIQUE (id, alias COLLATE NOCASE),
+    FOREIGN KEY (id) REFERENCES titles (id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS title_aliases_id_idx ON title_aliases (id);
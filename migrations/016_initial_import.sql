@@ -0,0 +1,9 @@
+-- The scan that first imported the library (at most one row)
+-- Entries it discovered were stamped with a date_added inside [started_at, finished_at],
+-- which the date_added backfill uses to tell import stamps from real additions
+CREATE TABLE IF NOT EXISTS initial_import (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    scan_id TEXT NOT NULL,
+    started_at INTEGER NOT NULL,    -- Unix timestamp
+    finished_at INTEGER             -- Unix timestamp, NULL until the scan completes
+);
@@ -0,0 +1,28 @@
+-- Optional TOTP two-factor authentication
+-- totp_secret is set once enrollment is confirmed with a first code; until then the
+-- generated secret waits in totp_pending_secret. totp_last_step is the time step of
+-- the last accepted code, so no code can be used twice
+ALTER TABLE users ADD COLUMN totp_secret TEXT;
+ALTER TABLE users ADD COLUMN totp_pending_secret TEXT;
+ALTER TABLE users ADD COLUMN totp_last_step INTEGER;
+
+-- One-time recovery codes, stored as SHA-256 hashes of the normalized code
+CREATE TABLE IF NOT EXISTS recovery_codes (
+    username TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    used_at INTEGER,                -- Unix timestamp, NULL while unused
+    PRIMARY KEY (username, code_hash),
+    FOREIGN KEY (username) REFERENCES users(username) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- App-specific tokens for HTTP Basic clients (OPDS readers) of two-factor accounts
+CREATE TABLE IF NOT EXISTS app_tokens (
+    id TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE, -- SHA-256 of the token
+    label TEXT NOT NULL,
+    created_at INTEGER NOT NULL,     -- Unix timestamp
+    FOREIGN KEY (username) REFERENCES users(username) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_app_tokens_username ON app_tokens (username);
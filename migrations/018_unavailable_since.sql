@@ -0,0 +1,8 @@
+-- When a title or entry was last marked unavailable (NULL while available)
+-- Lets maintenance wait out a grace period before pruning data of missing entries
+ALTER TABLE titles ADD COLUMN unavailable_since INTEGER;   -- Unix timestamp
+ALTER TABLE ids ADD COLUMN unavailable_since INTEGER;      -- Unix timestamp
+
+-- Already missing rows start their grace period now
+UPDATE titles SET unavailable_since = CAST(strftime('%s', 'now') AS INTEGER) WHERE unavailable = 1;
+UPDATE ids SET unavailable_since = CAST(strftime('%s', 'now') AS INTEGER) WHERE unavailable = 1;
@@ -0,0 +1,13 @@
+-- Per-title read statistics
+-- Page views, reads (entry opens, deduplicated per user) and downloads,
+-- aggregated per title per UTC day
+CREATE TABLE IF NOT EXISTS title_stats (
+    title_id TEXT NOT NULL,
+    day TEXT NOT NULL,                -- UTC date (YYYY-MM-DD)
+    page_views INTEGER NOT NULL DEFAULT 0,
+    reads INTEGER NOT NULL DEFAULT 0,
+    downloads INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (title_id, day)
+);
+
+CREATE INDEX IF NOT EXISTS idx_title_stats_day ON title_stats(day);
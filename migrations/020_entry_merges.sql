@@ -0,0 +1,11 @@
+-- Entries merged into one logical entry (chapters split over several archives)
+-- A merged entry takes the ID of its first part; parts are kept in reading order
+-- with their signatures, so a part whose ID changed is still found by a rescan
+CREATE TABLE IF NOT EXISTS entry_merges (
+    merged_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    title_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL UNIQUE,
+    signature TEXT NOT NULL,
+    PRIMARY KEY (merged_id, position)
+);
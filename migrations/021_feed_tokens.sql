@@ -0,0 +1,10 @@
+-- Per-user tokens authenticating the Atom feeds under /feed, for feed readers that can
+-- neither keep a session nor send Basic auth. Unlike app tokens they are stored as-is:
+-- the feed URLs embed the token and are shown again on the account page, and the token
+-- grants nothing but read access to the feeds
+CREATE TABLE IF NOT EXISTS feed_tokens (
+    username TEXT PRIMARY KEY,
+    token TEXT NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL,    -- Unix timestamp
+    FOREIGN KEY (username) REFERENCES users(username) ON DELETE CASCADE ON UPDATE CASCADE
+);
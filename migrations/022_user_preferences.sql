@@ -0,0 +1,8 @@
+-- Per-user display preferences; a NULL column falls back to the instance default
+-- from config (library_page_size, library_card_density)
+CREATE TABLE IF NOT EXISTS user_preferences (
+    username TEXT PRIMARY KEY,
+    page_size INTEGER,              -- Titles per library page
+    card_density TEXT,              -- comfortable, compact or list
+    FOREIGN KEY (username) REFERENCES users(username) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,2 @@
+-- Whether the library hides titles the user has fully read
+ALTER TABLE user_preferences ADD COLUMN hide_completed INTEGER NOT NULL DEFAULT 0;
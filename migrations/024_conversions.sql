@@ -0,0 +1,13 @@
+-- Entries queued for conversion to CBZ, one row per entry
+-- Each step of a conversion is recorded before the next one starts, so a job stopped
+-- by a restart or a crash is finished by the next run instead of started over
+CREATE TABLE IF NOT EXISTS conversions (
+    entry_id TEXT PRIMARY KEY,
+    title_id TEXT NOT NULL,
+    source_path TEXT NOT NULL,      -- Directory or archive converted, relative to the library root
+    target_path TEXT NOT NULL,      -- CBZ replacing it, relative to the library root
+    size INTEGER NOT NULL,          -- Bytes of the source, for the free space check
+    status TEXT NOT NULL,           -- pending, swapping, converted or failed
+    error TEXT,                     -- Why the last attempt failed
+    updated_at INTEGER NOT NULL     -- Unix timestamp
+);
@@ -0,0 +1,9 @@
+-- Titles a user pinned to the top of the library, whatever the sort
+-- Title IDs survive rescans, so pins do too
+CREATE TABLE IF NOT EXISTS title_pins (
+    username TEXT NOT NULL,
+    title_id TEXT NOT NULL,
+    pinned_at INTEGER NOT NULL,     -- Unix timestamp; pinned titles are listed in pin order
+    PRIMARY KEY (username, title_id),
+    FOREIGN KEY (username) REFERENCES users(username) ON DELETE CASCADE ON UPDATE CASCADE
+);
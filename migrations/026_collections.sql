@@ -0,0 +1,21 @@
+-- Named, ordered groups of titles a user puts together ("Top 10 to recommend")
+-- Collections are private to the user who made them
+CREATE TABLE IF NOT EXISTS collections (
+    id TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created_at INTEGER NOT NULL,    -- Unix timestamp; a user's collections are listed in creation order
+    UNIQUE (username, name),
+    FOREIGN KEY (username) REFERENCES users(username) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- Titles of a collection, in the order the user arranged them
+-- Deleting a title from the database drops it from every collection
+CREATE TABLE IF NOT EXISTS collection_items (
+    collection_id TEXT NOT NULL,
+    title_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (collection_id, title_id),
+    FOREIGN KEY (collection_id) REFERENCES collections(id) ON DELETE CASCADE,
+    FOREIGN KEY (title_id) REFERENCES titles(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,22 @@
+-- Reading order between series kept in separate directories ("Title", "Title Part 2")
+-- A title has at most one sequel and continues at most one title, so sequels form chains;
+-- the API refuses links that would close a chain into a cycle
+CREATE TABLE IF NOT EXISTS title_sequels (
+    title_id TEXT PRIMARY KEY,
+    sequel_id TEXT NOT NULL UNIQUE,
+    FOREIGN KEY (title_id) REFERENCES titles(id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (sequel_id) REFERENCES titles(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- Other related titles (spin-offs, side stories), in no particular order
+-- A pair is stored once, the smaller title ID first
+CREATE TABLE IF NOT EXISTS title_related (
+    title_id TEXT NOT NULL,
+    related_id TEXT NOT NULL,
+    PRIMARY KEY (title_id, related_id),
+    CHECK (title_id < related_id),
+    FOREIGN KEY (title_id) REFERENCES titles(id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (related_id) REFERENCES titles(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS title_related_related_id_idx ON title_related (related_id);
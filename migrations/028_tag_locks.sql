@@ -0,0 +1,9 @@
+-- Tags a user keeps behind a PIN: titles carrying them are listed, but can only be
+-- opened after the PIN was entered in the session (see `routes::TagLocks`)
+CREATE TABLE IF NOT EXISTS tag_locks (
+    username TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    pin_hash TEXT NOT NULL,         -- bcrypt, like user passwords
+    PRIMARY KEY (username, tag),
+    FOREIGN KEY (username) REFERENCES users(username) ON DELETE CASCADE ON UPDATE CASCADE
+);
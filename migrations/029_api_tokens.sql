@@ -0,0 +1,13 @@
+-- Bearer tokens for scripts and third-party clients of the API
+-- Any user can create them; they authenticate /api/ requests as that user
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE, -- SHA-256 of the token
+    name TEXT NOT NULL,
+    created_at INTEGER NOT NULL,     -- Unix timestamp
+    last_used INTEGER,               -- Unix timestamp, NULL until first used
+    FOREIGN KEY (username) REFERENCES users(username) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_tokens_username ON api_tokens (username);
@@ -0,0 +1,20 @@
+-- One row per logged-in browser, so a user can be logged in on several devices and log
+-- out of one without the others. The token is what the browser's session holds.
+-- users.token, which held the single token of the original schema, is no longer used
+CREATE TABLE IF NOT EXISTS login_sessions (
+    token TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    created_at INTEGER NOT NULL,    -- Unix timestamp
+    last_seen INTEGER NOT NULL,     -- Unix timestamp, updated at most once a minute
+    user_agent TEXT,
+    FOREIGN KEY (username) REFERENCES users(username) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_sessions_username ON login_sessions (username);
+
+-- Browsers logged in before the upgrade keep their session
+INSERT OR IGNORE INTO login_sessions (token, username, created_at, last_seen)
+SELECT token, username, CAST(strftime('%s', 'now') AS INTEGER), CAST(strftime('%s', 'now') AS INTEGER)
+FROM users WHERE token IS NOT NULL;
+
+UPDATE users SET token = NULL;
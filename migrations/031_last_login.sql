@@ -0,0 +1,3 @@
+-- When each user last logged in, through the login form or HTTP Basic auth (OPDS)
+-- Unix timestamp, NULL for users who never logged in since it was added
+ALTER TABLE users ADD COLUMN last_login INTEGER;
@@ -0,0 +1,3 @@
+-- Whether a user may change anything, their reading progress included
+-- Users without it (guests) can only browse, read and download; admins can always write
+ALTER TABLE users ADD COLUMN can_write INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,78 @@
+// Static assets for the web UI
+//
+// The static/ directory is built into the binary, so the server runs from any working
+// directory. Files are served from memory with the content type of their extension and
+// an ETag that changes with every build that changes an asset. Setting `static_path`
+// serves a directory from disk instead, for working on themes without rebuilding.
+
+use axum::{
+    extract::Path,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use include_dir::{include_dir, Dir};
+use tower_http::services::ServeDir;
+
+/// static/ as it was when the binary was built
+static STATIC_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/static");
+
+/// Hash of every embedded asset, computed by build.rs
+const STATIC_HASH: &str = match option_env!("MANGO_STATIC_HASH") {
+    Some(hash) => hash,
+    None => "unknown",
+};
+
+/// Cache-Control of embedded assets; once it runs out, clients revalidate with the
+/// ETag and only download again after an upgrade changed the assets
+const ASSET_CACHE: &str = "public, max-age=604800";
+
+/// Router serving the static assets, to be nested under /static
+/// Serves `static_path` from disk when set, the embedded assets otherwise
+pub fn router<S>(static_path: Option<&std::path::Path>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    match static_path {
+        Some(dir) => {
+            tracing::info!("Serving static assets from {}", dir.display());
+            Router::new().fallback_service(ServeDir::new(dir))
+        }
+        None => Router::new().route("/*path", get(serve_embedded)),
+    }
+}
+
+/// ETag shared by all embedded assets
+fn etag() -> String {
+    format!("\"{}\"", STATIC_HASH)
+}
+
+/// GET /static/*path - An embedded asset
+async fn serve_embedded(Path(path): Path<String>, headers: HeaderMap) -> Response {
+    let Some(file) = STATIC_DIR.get_file(path.trim_start_matches('/')) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let etag = etag();
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, ASSET_CACHE.to_string()),
+    ];
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag));
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+
+    let mime = mime_guess::from_path(file.path()).first_or_octet_stream();
+    (
+        [(header::CONTENT_TYPE, mime.to_string())],
+        cache_headers,
+        file.contents(),
+    )
+        .into_response()
+}
@@ -1,13 +1,19 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+};
+
 use axum::{
     async_trait,
-    extract::{FromRequestParts, Request, State},
-    http::{request::Parts, StatusCode},
+    extract::{ConnectInfo, FromRequestParts, Query, Request, State},
+    http::{header, request::Parts, Extensions, HeaderValue, Method, StatusCode, Uri},
     middleware::Next,
     response::{IntoResponse, Redirect, Response},
 };
 use tower_sessions::Session;
 
-use crate::AppState;
+use crate::{config::TwoFactorBasicAuth, routes::paths, AppState};
 
 /// Session key for storing username
 pub const SESSION_USERNAME_KEY: &str = "username";
@@ -15,6 +21,124 @@ pub const SESSION_USERNAME_KEY: &str = "username";
 /// Session key for storing user token
 pub const SESSION_TOKEN_KEY: &str = "token";
 
+/// Session key for a login waiting for its two-factor code
+/// Holds a `PendingTwoFactor`; the session is not authenticated until it is resolved
+pub const SESSION_PENDING_2FA_KEY: &str = "pending_2fa";
+
+/// Session key for the locked tags whose PIN was entered in the session
+/// Holds a `routes::tag_locks::SessionUnlocks`
+pub const SESSION_UNLOCKED_TAGS_KEY: &str = "unlocked_tags";
+
+/// Password step of a two-factor login, kept in the session until the code is checked
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PendingTwoFactor {
+    pub username: String,
+    /// Validated page to return to afterwards
+    pub next: Option<String>,
+    /// Unix timestamp of the password step
+    pub started_at: i64,
+    /// Invalid codes entered so far
+    pub attempts: u32,
+}
+
+/// Shortest password a user can change theirs to, or an admin reset one to
+pub const MIN_PASSWORD_LEN: usize = 6;
+
+/// What a password change logged out and revoked
+pub struct PasswordChanged {
+    /// Login sessions of the user that were logged out
+    pub sessions_revoked: u64,
+    /// App tokens and API tokens of the user that stopped working
+    pub tokens_revoked: u64,
+}
+
+/// Change a user's password with `set`, which is only run once `password` is long enough
+/// Every path that changes a password goes through here, so a stolen password or token
+/// stops working: the user's sessions but `keep` are logged out and their app and API
+/// tokens revoked
+/// Returns: BadRequest for a password shorter than `MIN_PASSWORD_LEN`
+pub async fn change_password(
+    storage: &crate::Storage,
+    username: &str,
+    password: &str,
+    keep: Option<&str>,
+    set: impl std::future::Future<Output = crate::error::Result<()>>,
+) -> crate::error::Result<PasswordChanged> {
+    if password.len() < MIN_PASSWORD_LEN {
+        return Err(crate::error::Error::BadRequest(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        )));
+    }
+    set.await?;
+
+    let sessions_revoked = storage.revoke_sessions(username, keep).await?;
+    let tokens_revoked = storage.revoke_tokens(username).await?;
+    tracing::info!(
+        "Password of {} changed, logged out {} sessions and revoked {} tokens",
+        username,
+        sessions_revoked,
+        tokens_revoked
+    );
+    Ok(PasswordChanged {
+        sessions_revoked,
+        tokens_revoked,
+    })
+}
+
+/// Wrong passwords a client may enter for a username within `LOGIN_FAILURE_WINDOW_SECS`
+/// before that username is refused to it until the window ends
+pub const MAX_LOGIN_FAILURES: u32 = 5;
+const LOGIN_FAILURE_WINDOW_SECS: i64 = 15 * 60;
+
+/// Client address and username a failed login is counted under
+type ThrottleKey = (Option<IpAddr>, String);
+
+/// Recent failed logins per client address and username, shared by the login form and
+/// HTTP Basic auth (`AppState::pin_throttle` counts wrong tag-lock PINs the same way)
+/// Counted per address so that guessing from one client cannot lock the user out
+/// everywhere else
+#[derive(Clone, Default)]
+pub struct LoginThrottle {
+    /// Failures and the Unix timestamp of the first one in the window
+    failures: Arc<Mutex<HashMap<ThrottleKey, (u32, i64)>>>,
+}
+
+impl LoginThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seconds until `client` may try `username` again, None if it may now
+    pub fn retry_after(&self, client: Option<IpAddr>, username: &str, now: i64) -> Option<i64> {
+        let failures = self.lock();
+        let &(count, since) = failures.get(&(client, username.to_string()))?;
+        let ends = since + LOGIN_FAILURE_WINDOW_SECS;
+        (count >= MAX_LOGIN_FAILURES && ends > now).then_some(ends - now)
+    }
+
+    pub fn record_failure(&self, client: Option<IpAddr>, username: &str, now: i64) {
+        let mut failures = self.lock();
+        failures.retain(|_, &mut (_, since)| since + LOGIN_FAILURE_WINDOW_SECS > now);
+        failures
+            .entry((client, username.to_string()))
+            .or_insert((0, now))
+            .0 += 1;
+    }
+
+    /// A successful login starts the count over
+    pub fn clear(&self, client: Option<IpAddr>, username: &str) {
+        self.lock().remove(&(client, username.to_string()));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<ThrottleKey, (u32, i64)>> {
+        match self.failures.lock() {
+            Ok(failures) => failures,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
 /// Authentication middleware that checks if user is logged in
 /// Matches original Mango's AuthHandler
 pub async fn require_auth(
@@ -29,8 +153,50 @@ pub async fn require_auth(
         return next.run(request).await;
     }
 
+    // Without logging in every request acts as default_username, which `Config::validate`
+    // requires; admin routes still check that user's admin flag
+    if state.config.disable_login {
+        if let Some(username) = &state.config.default_username {
+            request.extensions_mut().insert(username.clone());
+            return crate::util::LOGIN_DISABLED
+                .scope(true, next.run(request))
+                .await;
+        }
+    }
+
+    // Feed readers authenticate with the feed token in the URL, nothing else
+    if paths::is_feed(path) {
+        let token = feed_token(request.uri());
+        return match verify_feed_token(&state, token.as_deref()).await {
+            Some(username) => {
+                request.extensions_mut().insert(username);
+                next.run(request).await
+            }
+            None => StatusCode::UNAUTHORIZED.into_response(),
+        };
+    }
+
+    // Scripts call the API with a bearer token instead of a session. A token that does not
+    // check out is refused rather than falling back to the session
+    if path.starts_with("/api/") {
+        if let Some(token) = bearer_token(&request) {
+            let token_hash = crate::totp::hash_secret(token);
+            return match state.storage.verify_api_token(&token_hash).await {
+                Ok(Some(username)) => {
+                    request.extensions_mut().insert(username);
+                    next.run(request).await
+                }
+                Ok(None) => StatusCode::UNAUTHORIZED.into_response(),
+                Err(e) => {
+                    tracing::error!("Error verifying API token: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                }
+            };
+        }
+    }
+
     // Track if this is an OPDS/download path (needs RFC 7235 compliant 401 on auth failure)
-    let is_opds_path = path.starts_with("/opds") || path.starts_with("/api/download");
+    let is_opds_path = paths::is_opds(path);
 
     // For OPDS paths, try Basic Auth first (for e-reader support)
     if is_opds_path {
@@ -40,12 +206,22 @@ pub async fn require_auth(
             if let Ok(auth_str) = auth_header.to_str() {
                 if let Some(stripped) = auth_str.strip_prefix("Basic ") {
                     tracing::debug!("Basic auth detected");
-                    if let Some(username) = verify_basic_auth(&state, stripped).await {
-                        tracing::debug!("Basic auth successful for user: {}", username);
-                        request.extensions_mut().insert(username.clone());
-                        return next.run(request).await;
-                    } else {
-                        tracing::debug!("Basic auth failed");
+                    let client = client_ip(request.extensions());
+                    match verify_basic_auth(&state, client, stripped).await {
+                        BasicAuth::Verified(username) => {
+                            tracing::debug!("Basic auth successful for user: {}", username);
+                            state.storage.record_login(&username).await;
+                            request.extensions_mut().insert(username.clone());
+                            return next.run(request).await;
+                        }
+                        BasicAuth::Throttled(wait) => {
+                            return (
+                                StatusCode::TOO_MANY_REQUESTS,
+                                [(header::RETRY_AFTER, HeaderValue::from(wait))],
+                            )
+                                .into_response();
+                        }
+                        BasicAuth::Failed => tracing::debug!("Basic auth failed"),
                     }
                 }
             }
@@ -54,6 +230,27 @@ pub async fn require_auth(
         }
     }
 
+    // Behind an authenticating proxy the proxy names the user. The header is only read
+    // when configured, so without the option a client cannot log in by sending it
+    if let Some(header_name) = &state.config.auth_proxy_header_name {
+        if let Some(username) = proxy_username(&request, header_name) {
+            return match state.storage.username_exists(&username).await {
+                Ok(true) => {
+                    request.extensions_mut().insert(username);
+                    next.run(request).await
+                }
+                Ok(false) => {
+                    tracing::warn!("Auth proxy named an unknown user: {}", username);
+                    StatusCode::UNAUTHORIZED.into_response()
+                }
+                Err(e) => {
+                    tracing::error!("Error looking up auth proxy user: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                }
+            };
+        }
+    }
+
     // Check if user has valid session
     if let Ok(Some(token)) = session.get::<String>(SESSION_TOKEN_KEY).await {
         // Verify token in database
@@ -77,7 +274,6 @@ pub async fn require_auth(
     if is_opds_path {
         // OPDS/download clients need RFC 7235 compliant response
         // Return 401 Unauthorized with WWW-Authenticate header
-        use axum::http::header;
         return (
             StatusCode::UNAUTHORIZED,
             [(header::WWW_AUTHENTICATE, "Basic realm=\"Mango\"")],
@@ -85,8 +281,17 @@ pub async fn require_auth(
             .into_response();
     }
 
-    // Browser clients get redirect to login page
-    Redirect::to("/login").into_response()
+    // Browser clients get redirect to login page, which sends them back to the page
+    // they asked for (API calls and the home page need no return address). The router
+    // path is put back under base_url by `login_next`
+    let target = request
+        .uri()
+        .path_and_query()
+        .map_or(paths::HOME, |pq| pq.as_str());
+    if request.method() == Method::GET && target != paths::HOME && !target.starts_with("/api/") {
+        return Redirect::to(&paths::login_next(target)).into_response();
+    }
+    Redirect::to(&paths::login()).into_response()
 }
 
 /// Admin authorization middleware - requires authenticated user to be admin
@@ -118,58 +323,163 @@ pub async fn require_admin(
 }
 
 /// Check if a path should skip authentication
-/// Matches original AuthHandler's exclude logic
+/// Matches original AuthHandler's exclude logic. Paths are below base_url: `build_app`
+/// strips it before routing, so "/mango/login" is checked as "/login"
 fn is_public_path(path: &str) -> bool {
-    path == "/login"
-        || path.starts_with("/api/login")
-        || path.starts_with("/static/")
-        || path.starts_with("/img/")
-        || path.starts_with("/css/")
-        || path.starts_with("/js/")
+    paths::is_public(path)
+}
+
+/// Feed token in the `token` query parameter of a request URI
+fn feed_token(uri: &Uri) -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct FeedQuery {
+        token: String,
+    }
+
+    let Query(query) = Query::<FeedQuery>::try_from_uri(uri).ok()?;
+    Some(query.token)
+}
+
+/// Owner of a feed token
+async fn verify_feed_token(state: &AppState, token: Option<&str>) -> Option<String> {
+    match state.storage.verify_feed_token(token?).await {
+        Ok(username) => username,
+        Err(e) => {
+            tracing::error!("Error verifying feed token: {}", e);
+            None
+        }
+    }
+}
+
+/// Token of an `Authorization: Bearer` header
+fn bearer_token(request: &Request) -> Option<&str> {
+    let value = request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?;
+    let token = value.strip_prefix("Bearer ")?.trim();
+    (!token.is_empty()).then_some(token)
+}
+
+/// Username the auth proxy put in `header_name`, if it sent one
+fn proxy_username(request: &Request, header_name: &str) -> Option<String> {
+    let value = request.headers().get(header_name)?.to_str().ok()?.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Outcome of checking HTTP Basic Auth credentials
+enum BasicAuth {
+    Verified(String),
+    Failed,
+    /// The client entered too many wrong passwords for the username; seconds to wait
+    Throttled(i64),
 }
 
 /// Verify HTTP Basic Auth credentials
-/// Returns username if credentials are valid
-async fn verify_basic_auth(state: &AppState, base64_credentials: &str) -> Option<String> {
+/// Wrong passwords count towards `LoginThrottle` like those entered in the login form
+async fn verify_basic_auth(
+    state: &AppState,
+    client: Option<IpAddr>,
+    base64_credentials: &str,
+) -> BasicAuth {
     use base64::{engine::general_purpose, Engine as _};
 
     tracing::debug!("Verifying basic auth credentials");
 
-    // Decode base64
-    let decoded = general_purpose::STANDARD.decode(base64_credentials).ok()?;
-    tracing::debug!("Base64 decoded successfully");
+    // Decode base64 and split into username:password
+    let Some(credentials) = general_purpose::STANDARD
+        .decode(base64_credentials)
+        .ok()
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+    else {
+        return BasicAuth::Failed;
+    };
+    let Some((username, password)) = credentials.split_once(':') else {
+        return BasicAuth::Failed;
+    };
 
-    let credentials = String::from_utf8(decoded).ok()?;
-    tracing::debug!("Credentials string: {}", credentials);
+    tracing::debug!("Attempting to verify user: {}", username);
 
-    // Split into username:password
-    let (username, password) = credentials.split_once(':')?;
+    let now = crate::util::time::now_ts();
+    if let Some(wait) = state.login_throttle.retry_after(client, username, now) {
+        tracing::warn!(
+            "Refused Basic auth for username {}: too many failures",
+            username
+        );
+        return BasicAuth::Throttled(wait);
+    }
 
-    tracing::debug!("Attempting to verify user: {}", username);
+    // App-specific tokens stand in for the password, for any account
+    match state
+        .storage
+        .verify_app_token(username, &crate::totp::hash_secret(password))
+        .await
+    {
+        Ok(true) => {
+            tracing::debug!("App token verified for user: {}", username);
+            state.login_throttle.clear(client, username);
+            return BasicAuth::Verified(username.to_string());
+        }
+        Ok(false) => {}
+        Err(e) => tracing::error!("Error verifying app token: {}", e),
+    }
 
     // Verify credentials against database
     match state.storage.verify_user(username, password).await {
-        Ok(Some(_token)) => {
+        Ok(true) => {
+            // Two-factor accounts can be limited to app tokens
+            if state.config.two_factor_basic_auth == TwoFactorBasicAuth::AppToken
+                && state.storage.totp_enabled(username).await.unwrap_or(true)
+            {
+                tracing::debug!("Password rejected for two-factor user: {}", username);
+                return BasicAuth::Failed;
+            }
             tracing::debug!("User verified successfully: {}", username);
-            Some(username.to_string())
+            state.login_throttle.clear(client, username);
+            BasicAuth::Verified(username.to_string())
         }
-        Ok(None) => {
-            tracing::debug!("User verification failed - invalid credentials");
-            None
+        Ok(false) => {
+            tracing::warn!("Failed Basic auth attempt for username: {}", username);
+            state.login_throttle.record_failure(client, username, now);
+            BasicAuth::Failed
         }
         Err(e) => {
             tracing::error!("Error verifying user: {}", e);
-            None
+            BasicAuth::Failed
         }
     }
 }
 
+/// Address of the client that sent a request, when the server was started with connect
+/// info (not in tests that call the router directly)
+pub fn client_ip(extensions: &Extensions) -> Option<IpAddr> {
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
 /// Helper to get username from request extensions
 /// Injected by require_auth middleware
 pub fn get_username(request: &Request) -> Option<String> {
     request.extensions().get::<String>().cloned()
 }
 
+/// Client address extractor, see `client_ip`
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(ClientIp(client_ip(&parts.extensions)))
+    }
+}
+
 /// Username extractor that can be used as a handler parameter
 /// Extracts username from request extensions (set by require_auth middleware)
 pub struct Username(pub String);
@@ -226,11 +536,37 @@ impl FromRequestParts<AppState> for AdminOnly {
     }
 }
 
-/// User extractor that provides username and admin status
+/// Writer extractor that rejects read-only (guest) users with 403
+/// Similar to Username, for handlers that change anything
+pub struct Writer(pub String);
+
+#[async_trait]
+impl FromRequestParts<AppState> for Writer {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let user = User::from_request_parts(parts, state)
+            .await
+            .map_err(|status| (status, "Failed to verify write access"))?;
+
+        if user.can_write {
+            Ok(Writer(user.username))
+        } else {
+            Err((StatusCode::FORBIDDEN, "Read-only account"))
+        }
+    }
+}
+
+/// User extractor that provides username, admin status and write access
 /// Can be used in any authenticated handler
 pub struct User {
     pub username: String,
     pub is_admin: bool,
+    /// False for read-only (guest) users; admins can always write
+    pub can_write: bool,
 }
 
 #[async_trait]
@@ -255,6 +591,27 @@ impl FromRequestParts<AppState> for User {
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-        Ok(User { username, is_admin })
+        let can_write = is_admin
+            || state
+                .storage
+                .can_write(&username)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        Ok(User {
+            username,
+            is_admin,
+            can_write,
+        })
+    }
+}
+
+impl From<AdminOnly> for User {
+    fn from(AdminOnly(username): AdminOnly) -> Self {
+        User {
+            username,
+            is_admin: true,
+            can_write: true,
+        }
     }
 }
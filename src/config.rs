@@ -3,6 +3,64 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::error::Result;
+use crate::library::ReadingDirection;
+
+/// Instance name used when none is configured
+pub const DEFAULT_INSTANCE_NAME: &str = "Mango";
+
+/// How HTTP Basic clients (OPDS readers) log in to accounts with two-factor enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TwoFactorBasicAuth {
+    /// The account password is accepted without a code
+    #[default]
+    Bypass,
+    /// Only app-specific tokens are accepted, never the account password
+    AppToken,
+}
+
+/// What the scanner makes of archives lying directly in the library root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LooseArchives {
+    /// All of them form one title, named by `unsorted_title_name`
+    #[default]
+    Unsorted,
+    /// Each one is a title of its own with a single entry
+    Title,
+}
+
+/// How titles are laid out on the library page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardDensity {
+    /// Cover cards, four to a row on wide screens
+    #[default]
+    Comfortable,
+    /// Smaller cover cards, more to a row
+    Compact,
+    /// One row per title with its progress, no covers
+    List,
+}
+
+impl CardDensity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CardDensity::Comfortable => "comfortable",
+            CardDensity::Compact => "compact",
+            CardDensity::List => "list",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "comfortable" => Some(CardDensity::Comfortable),
+            "compact" => Some(CardDensity::Compact),
+            "list" => Some(CardDensity::List),
+            _ => None,
+        }
+    }
+}
 
 /// Application configuration matching original Mango's config.yml structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,7 +73,8 @@ pub struct Config {
     #[serde(default = "default_port")]
     pub port: u16,
 
-    /// Base URL path (default: /)
+    /// Base URL path (default: /), e.g. /mango/ to serve every page, API and OPDS route
+    /// under that path behind a reverse proxy
     #[serde(default = "default_base_url")]
     pub base_url: String,
 
@@ -60,12 +119,22 @@ pub struct Config {
     pub download_timeout_seconds: u64,
 
     /// Path to library cache file (Tier 3 performance)
+    /// The cache is kept in the directory of this path with `.d` appended, one file per
+    /// title; a cache file of the old single-file format here is converted on first load
     #[serde(default = "default_library_cache_path")]
     pub library_cache_path: PathBuf,
 
-    /// Enable library caching (Tier 3)
+    /// Deprecated: switches both `library_cache_file_enabled` and `memory_cache_enabled`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_enabled: Option<bool>,
+
+    /// Save the library to `library_cache_path` and load it from there at startup
+    #[serde(default = "default_true")]
+    pub library_cache_file_enabled: bool,
+
+    /// Keep sorted lists and progress sums in the in-memory LRU cache
     #[serde(default = "default_true")]
-    pub cache_enabled: bool,
+    pub memory_cache_enabled: bool,
 
     /// Cache size in megabytes
     #[serde(default = "default_cache_size")]
@@ -83,13 +152,159 @@ pub struct Config {
     #[serde(default)]
     pub default_username: Option<String>,
 
-    /// Header name for auth proxy support
+    /// Header an authenticating reverse proxy (Authelia, Authentik...) names the logged-in
+    /// user in, e.g. `Remote-User`. Only set this when every request passes the proxy
     #[serde(default)]
     pub auth_proxy_header_name: Option<String>,
 
     /// Plugin update interval in hours (Tier 3)
     #[serde(default = "default_plugin_update_interval")]
     pub plugin_update_interval_hours: u32,
+
+    /// Daily per-user download quota in megabytes (0 = unlimited)
+    #[serde(default)]
+    pub download_quota_mbs: u64,
+
+    /// Number of recently read archives kept open in memory (0 = disabled)
+    #[serde(default = "default_archive_cache_entries")]
+    pub archive_cache_entries: usize,
+
+    /// Total size limit of archives kept open in memory, in megabytes
+    #[serde(default = "default_archive_cache_size")]
+    pub archive_cache_size_mbs: usize,
+
+    /// Database queries slower than this are logged and counted, in milliseconds
+    #[serde(default = "default_db_slow_query")]
+    pub db_slow_query_ms: u64,
+
+    /// Waits for a database connection longer than this are logged and counted, in milliseconds
+    #[serde(default = "default_db_slow_acquire")]
+    pub db_slow_acquire_ms: u64,
+
+    /// Check that every page decodes to a known image format and flag bad pages
+    /// Expensive (reads every page of every archive), so disabled by default
+    #[serde(default)]
+    pub validate_pages: bool,
+
+    /// Instance name shown in page titles, the nav bar and the OPDS feed
+    #[serde(default = "default_instance_name")]
+    pub instance_name: String,
+
+    /// Accent color as a hex CSS color (e.g. "#e91e63"), unset keeps the default theme
+    #[serde(default)]
+    pub accent_color: Option<String>,
+
+    /// Custom logo image, relative paths are resolved against the config file directory
+    #[serde(default)]
+    pub logo_path: Option<PathBuf>,
+
+    /// Reading direction (ltr, rtl or vertical) when neither the entry, the title
+    /// nor the reader's own preference sets one
+    #[serde(default = "default_reading_direction")]
+    pub default_reading_direction: ReadingDirection,
+
+    /// Date entries found by the first scan of a new library by their file mtime
+    /// instead of the scan time, so they do not all show up as recently added
+    #[serde(default)]
+    pub date_added_from_mtime: bool,
+
+    /// Basic auth for two-factor accounts: "bypass" (password) or "app_token"
+    #[serde(default)]
+    pub two_factor_basic_auth: TwoFactorBasicAuth,
+
+    /// Days an entry can stay missing before its thumbnails are pruned
+    #[serde(default = "default_thumbnail_prune_grace_days")]
+    pub thumbnail_prune_grace_days: u64,
+
+    /// Image files in a title directory used as the title's cover instead of its
+    /// first entry's, matched case-insensitively; earlier names win
+    #[serde(default = "default_cover_file_names")]
+    pub cover_file_names: Vec<String>,
+
+    /// Archives directly in the library root: one "unsorted" title, or a title each
+    #[serde(default)]
+    pub loose_archives: LooseArchives,
+
+    /// Name of the title holding the library root's archives (`loose_archives: unsorted`)
+    #[serde(default = "default_unsorted_title_name")]
+    pub unsorted_title_name: String,
+
+    /// Leave titles and archives whose names start with a dot or an underscore out
+    /// of scans, like a work-in-progress folder stashed in the library
+    #[serde(default = "default_true")]
+    pub scan_skip_hidden: bool,
+
+    /// Globs of file and directory names scans leave out, in the library root and inside
+    /// titles (e.g. `@eaDir`, `*.sample.zip`), matched case-insensitively
+    #[serde(default)]
+    pub scan_exclude_patterns: Vec<String>,
+
+    /// Whether the library's filesystem tells names apart by case, for matching the
+    /// stored paths of titles and entries; found out at startup when not set
+    #[serde(default)]
+    pub library_case_sensitive: Option<bool>,
+
+    /// Title directories a scan reads at the same time; fewer suit spinning disks
+    /// Values below 1 are raised to 1
+    #[serde(default = "default_scan_workers")]
+    pub scan_workers: usize,
+
+    /// Show entries by a cleaned-up name, without release-group tags and the series name
+    /// Sorting and matching keep using the file name
+    #[serde(default = "default_true")]
+    pub entry_title_cleanup: bool,
+
+    /// Regexes of the parts removed from entry names by the cleanup
+    #[serde(default = "default_entry_title_strip_patterns")]
+    pub entry_title_strip_patterns: Vec<String>,
+
+    /// Serve the Komga-style read progress endpoints under /api/v1 (see docs/KOMGA.md)
+    #[serde(default)]
+    pub komga_compat_enabled: bool,
+
+    /// Titles per library page for users who have not picked a page size
+    /// (0 = all titles on one page)
+    #[serde(default)]
+    pub library_page_size: usize,
+
+    /// Largest page size users can pick for the library
+    #[serde(default = "default_library_max_page_size")]
+    pub library_max_page_size: usize,
+
+    /// Library layout for users who have not picked one
+    #[serde(default)]
+    pub library_card_density: CardDensity,
+
+    /// Directory to serve /static from instead of the assets built into the binary,
+    /// for working on themes without rebuilding
+    #[serde(default)]
+    pub static_path: Option<PathBuf>,
+
+    /// Keep the originals of entries converted to CBZ, renamed to `.bak`, instead of
+    /// deleting them
+    #[serde(default = "default_true")]
+    pub convert_keep_originals: bool,
+
+    /// Watch the library directory and rescan a title as soon as files in it are added,
+    /// renamed or deleted, instead of waiting for the next periodic scan
+    #[serde(default)]
+    pub watch_library: bool,
+
+    /// Let admins see which entries of a title every user has read (the book page's
+    /// Readers tab); turn off on shared servers where reading habits stay private
+    #[serde(default = "default_true")]
+    pub admin_can_view_user_progress: bool,
+
+    /// Origins of web clients hosted elsewhere allowed to call the API with the user's
+    /// session (e.g. `https://reader.example.com`); empty disables CORS
+    /// `*` allows any origin, which lets every website act as a logged-in user
+    #[serde(default)]
+    pub cors_allowed_origins: Vec<String>,
+
+    /// Minutes a title behind a locked tag stays readable after its PIN was entered,
+    /// per session
+    #[serde(default = "default_tag_unlock_minutes")]
+    pub tag_unlock_minutes: u64,
 }
 
 // Default value functions
@@ -141,9 +356,62 @@ fn default_true() -> bool {
 fn default_cache_size() -> usize {
     50
 }
+fn default_scan_workers() -> usize {
+    20
+}
+fn default_library_max_page_size() -> usize {
+    500
+}
+fn default_archive_cache_entries() -> usize {
+    4
+}
+fn default_archive_cache_size() -> usize {
+    256
+}
+fn default_db_slow_query() -> u64 {
+    500
+}
+fn default_db_slow_acquire() -> u64 {
+    100
+}
+fn default_instance_name() -> String {
+    DEFAULT_INSTANCE_NAME.to_string()
+}
+fn default_reading_direction() -> ReadingDirection {
+    ReadingDirection::Vertical
+}
+fn default_thumbnail_prune_grace_days() -> u64 {
+    30
+}
+fn default_cover_file_names() -> Vec<String> {
+    [
+        "cover.jpg",
+        "cover.jpeg",
+        "cover.png",
+        "cover.webp",
+        "poster.jpg",
+        "poster.png",
+        "folder.jpg",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+fn default_entry_title_strip_patterns() -> Vec<String> {
+    crate::library::title_cleanup::DEFAULT_STRIP_PATTERNS
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
+}
+fn default_unsorted_title_name() -> String {
+    "Unsorted".to_string()
+}
 fn default_plugin_update_interval() -> u32 {
     24
 }
+fn default_tag_unlock_minutes() -> u64 {
+    30
+}
 
 impl Config {
     /// Load configuration from file, with fallback to defaults
@@ -166,11 +434,16 @@ impl Config {
             Self::default_config()
         };
 
+        config.apply_deprecated_keys();
+
         // Apply environment variable overrides
         config.apply_env_overrides();
 
         // Expand all path fields
         config.expand_paths();
+        if let Some(config_dir) = expanded_path.parent() {
+            config.resolve_logo_path(config_dir);
+        }
 
         // Validate configuration
         config.validate()?;
@@ -184,7 +457,7 @@ impl Config {
     }
 
     /// Create default configuration
-    fn default_config() -> Self {
+    pub fn default_config() -> Self {
         Config {
             host: default_host(),
             port: default_port(),
@@ -200,13 +473,60 @@ impl Config {
             plugin_path: default_plugin_path(),
             download_timeout_seconds: default_download_timeout(),
             library_cache_path: default_library_cache_path(),
-            cache_enabled: default_true(),
+            cache_enabled: None,
+            library_cache_file_enabled: default_true(),
+            memory_cache_enabled: default_true(),
             cache_size_mbs: default_cache_size(),
             cache_log_enabled: default_true(),
             disable_login: false,
             default_username: None,
             auth_proxy_header_name: None,
             plugin_update_interval_hours: default_plugin_update_interval(),
+            download_quota_mbs: 0,
+            archive_cache_entries: default_archive_cache_entries(),
+            archive_cache_size_mbs: default_archive_cache_size(),
+            db_slow_query_ms: default_db_slow_query(),
+            db_slow_acquire_ms: default_db_slow_acquire(),
+            validate_pages: false,
+            instance_name: default_instance_name(),
+            accent_color: None,
+            logo_path: None,
+            default_reading_direction: default_reading_direction(),
+            date_added_from_mtime: false,
+            two_factor_basic_auth: TwoFactorBasicAuth::default(),
+            thumbnail_prune_grace_days: default_thumbnail_prune_grace_days(),
+            cover_file_names: default_cover_file_names(),
+            loose_archives: LooseArchives::default(),
+            unsorted_title_name: default_unsorted_title_name(),
+            scan_skip_hidden: true,
+            scan_exclude_patterns: Vec::new(),
+            library_case_sensitive: None,
+            scan_workers: default_scan_workers(),
+            entry_title_cleanup: true,
+            entry_title_strip_patterns: default_entry_title_strip_patterns(),
+            komga_compat_enabled: false,
+            library_page_size: 0,
+            library_max_page_size: default_library_max_page_size(),
+            library_card_density: CardDensity::default(),
+            static_path: None,
+            convert_keep_originals: true,
+            watch_library: false,
+            admin_can_view_user_progress: true,
+            cors_allowed_origins: Vec::new(),
+            tag_unlock_minutes: default_tag_unlock_minutes(),
+        }
+    }
+
+    /// Map deprecated keys onto their replacements
+    fn apply_deprecated_keys(&mut self) {
+        if let Some(enabled) = self.cache_enabled.take() {
+            tracing::warn!(
+                "cache_enabled is deprecated, use library_cache_file_enabled and memory_cache_enabled; \
+                 setting both to {}",
+                enabled
+            );
+            self.library_cache_file_enabled = enabled;
+            self.memory_cache_enabled = enabled;
         }
     }
 
@@ -235,6 +555,11 @@ impl Config {
         if let Ok(val) = std::env::var("MANGO_LOG_LEVEL") {
             self.log_level = val;
         }
+        if let Ok(val) = std::env::var("MANGO_SCAN_WORKERS") {
+            if let Ok(workers) = val.parse() {
+                self.scan_workers = workers;
+            }
+        }
     }
 
     /// Expand ~ in all path fields
@@ -245,6 +570,19 @@ impl Config {
         self.upload_path = expand_home_path(&self.upload_path);
         self.plugin_path = expand_home_path(&self.plugin_path);
         self.library_cache_path = expand_home_path(&self.library_cache_path);
+        self.static_path = self.static_path.as_deref().map(expand_home_path);
+    }
+
+    /// Resolve a relative logo_path against the config file directory
+    fn resolve_logo_path(&mut self, config_dir: &Path) {
+        if let Some(logo) = &self.logo_path {
+            let logo = expand_home_path(logo);
+            self.logo_path = Some(if logo.is_relative() {
+                config_dir.join(logo)
+            } else {
+                logo
+            });
+        }
     }
 
     /// Validate configuration
@@ -262,6 +600,55 @@ impl Config {
             url.push('/');
         }
 
+        // accent_color is injected into a <style> block, so only plain hex colors are allowed
+        if let Some(color) = &self.accent_color {
+            if !is_hex_color(color) {
+                return Err(crate::error::Error::Config(format!(
+                    "accent_color must be a hex color like #e91e63, got: {}",
+                    color
+                )));
+            }
+        }
+
+        if self.unsorted_title_name.trim().is_empty() {
+            return Err(crate::error::Error::Config(
+                "unsorted_title_name must not be empty".to_string(),
+            ));
+        }
+
+        crate::library::TitleCleanup::new(
+            self.entry_title_cleanup,
+            &self.entry_title_strip_patterns,
+        )?;
+        crate::library::ScanFilter::new(self.scan_skip_hidden, &self.scan_exclude_patterns)?;
+
+        if self.library_max_page_size == 0 {
+            return Err(crate::error::Error::Config(
+                "library_max_page_size must be at least 1".to_string(),
+            ));
+        }
+        if self.library_page_size > self.library_max_page_size {
+            return Err(crate::error::Error::Config(format!(
+                "library_page_size ({}) must not exceed library_max_page_size ({})",
+                self.library_page_size, self.library_max_page_size
+            )));
+        }
+
+        for origin in &self.cors_allowed_origins {
+            if origin == "*" {
+                tracing::warn!(
+                    "cors_allowed_origins contains '*': any website can use the API as the \
+                     logged-in user; list the origins of your clients instead"
+                );
+            } else if !is_origin(origin) {
+                return Err(crate::error::Error::Config(format!(
+                    "cors_allowed_origins entries must be origins like https://example.com \
+                     (no path), got: {}",
+                    origin
+                )));
+            }
+        }
+
         // If login is disabled, default_username must be set
         if self.disable_login && self.default_username.is_none() {
             return Err(crate::error::Error::Config(
@@ -269,6 +656,15 @@ impl Config {
             ));
         }
 
+        if let Some(name) = &self.auth_proxy_header_name {
+            if axum::http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(crate::error::Error::Config(format!(
+                    "auth_proxy_header_name is not a valid header name: {}",
+                    name
+                )));
+            }
+        }
+
         Ok(())
     }
 
@@ -294,6 +690,29 @@ impl Config {
     }
 }
 
+/// Check for a #rgb or #rrggbb CSS color
+fn is_hex_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => {
+            (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
+
+/// Check for a browser origin: http(s)://host with an optional port and nothing after it
+fn is_origin(origin: &str) -> bool {
+    let host = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"));
+    host.is_some_and(|host| {
+        !host.is_empty()
+            && host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+    })
+}
+
 /// Expand ~ to home directory in a string path
 fn expand_home(path: &str) -> PathBuf {
     if let Some(stripped) = path.strip_prefix("~/") {
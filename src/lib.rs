@@ -1,13 +1,18 @@
 // Mango-Rust Library Root
 // Tier 1 MVP modules
 
+pub mod assets;
 pub mod auth;
 pub mod config;
 pub mod library;
+pub mod quota;
 pub mod routes;
 pub mod server;
+pub mod stats;
 pub mod storage;
+pub mod totp;
 pub mod util;
+pub mod version;
 
 // Re-exports
 pub use config::Config;
@@ -18,8 +23,9 @@ pub use storage::Storage;
 // Common types and utilities
 pub mod error {
     use axum::{
-        http::StatusCode,
+        http::{header, StatusCode},
         response::{IntoResponse, Response},
+        Json,
     };
 
     pub type Result<T> = std::result::Result<T, Error>;
@@ -27,10 +33,10 @@ pub mod error {
     #[derive(Debug, thiserror::Error)]
     pub enum Error {
         #[error("Database error: {0}")]
-        Database(#[from] sqlx::Error),
+        Database(sqlx::Error),
 
         #[error("IO error: {0}")]
-        Io(#[from] std::io::Error),
+        Io(std::io::Error),
 
         #[error("Archive error: {0}")]
         Archive(#[from] compress_tools::Error),
@@ -62,29 +68,206 @@ pub mod error {
         #[error("Forbidden: {0}")]
         Forbidden(String),
 
+        #[error("Too many requests: {0}")]
+        TooManyRequests(String),
+
+        /// The title carries a tag the user locked and its PIN has not been entered in
+        /// this session, see `routes::TagLocks`
+        #[error("Locked: {0}")]
+        Locked(String),
+
+        #[error("Service unavailable: {0}")]
+        ServiceUnavailable(String),
+
+        /// The database or the disk stopped accepting writes (read-only or full)
+        /// Reads keep working, see `storage::StorageHealth`
+        #[error("Storage unavailable: {0}")]
+        StorageUnavailable(String),
+
+        /// A page could not be extracted from its archive (e.g. a corrupted or truncated
+        /// member), answered with a JSON body the reader uses to offer skipping the page
+        #[error("Page {} of {} ({}) is unreadable: {}", .0.page, .0.archive, .0.member, .0.reason)]
+        PageUnreadable(Box<PageUnreadable>),
+
         #[error("Internal server error: {0}")]
         Internal(String),
     }
 
-    impl IntoResponse for Error {
-        fn into_response(self) -> Response {
+    /// Where and why a page extraction failed
+    #[derive(Debug, Clone, serde::Serialize)]
+    pub struct PageUnreadable {
+        /// Archive path relative to the library root
+        pub archive: String,
+        /// Name of the page's file inside the archive
+        pub member: String,
+        /// One-based page number
+        pub page: usize,
+        /// The archive library's error
+        pub reason: String,
+    }
+
+    /// SQLite primary result codes of a database that cannot be written to
+    const SQLITE_READONLY: i32 = 8;
+    const SQLITE_FULL: i32 = 13;
+
+    impl From<sqlx::Error> for Error {
+        fn from(e: sqlx::Error) -> Self {
+            let code = match &e {
+                sqlx::Error::Database(db) => db.code().and_then(|c| c.parse::<i32>().ok()),
+                _ => None,
+            };
+            // Extended result codes (e.g. SQLITE_READONLY_DBMOVED) keep the primary in the low byte
+            match code.map(|c| c & 0xff) {
+                Some(SQLITE_READONLY) => {
+                    Error::StorageUnavailable(format!("the database is read-only ({})", e))
+                }
+                Some(SQLITE_FULL) => Error::StorageUnavailable(format!("the disk is full ({})", e)),
+                _ => Error::Database(e),
+            }
+        }
+    }
+
+    impl From<std::io::Error> for Error {
+        fn from(e: std::io::Error) -> Self {
+            match e.kind() {
+                std::io::ErrorKind::StorageFull => {
+                    Error::StorageUnavailable(format!("the disk is full ({})", e))
+                }
+                std::io::ErrorKind::ReadOnlyFilesystem => {
+                    Error::StorageUnavailable(format!("the file system is read-only ({})", e))
+                }
+                _ => Error::Io(e),
+            }
+        }
+    }
+
+    /// Marks a response as failed by `Error::StorageUnavailable`, so the server can note
+    /// it in `StorageHealth` (see `server::track_storage_health`)
+    #[derive(Debug, Clone)]
+    pub struct StorageUnavailableResponse(pub String);
+
+    /// What failed, attached to every error response so the server can describe the
+    /// failure in the format the client asked for (see `server::structured_errors`)
+    #[derive(Debug, Clone)]
+    pub struct ErrorDetails {
+        /// Stable name of the variant, e.g. "not-found"
+        pub kind: &'static str,
+        /// Short summary of the kind of failure, the same for every error of the variant
+        pub title: &'static str,
+        /// What went wrong, as told to the client
+        pub detail: String,
+    }
+
+    impl Error {
+        /// Stable name and summary of the variant
+        pub fn kind(&self) -> (&'static str, &'static str) {
+            match self {
+                // Answered as a busy server, see `into_response`
+                Error::Database(sqlx::Error::PoolTimedOut) => {
+                    ("service-unavailable", "Service unavailable")
+                }
+                Error::Database(_) => ("database", "Database error"),
+                Error::Io(_) => ("io", "I/O error"),
+                Error::Archive(_) => ("archive", "Archive error"),
+                Error::Json(_) => ("json", "JSON error"),
+                Error::CacheCorrupted(_) => ("cache-corrupted", "Cache corrupted"),
+                Error::CacheSerialization(_) => {
+                    ("cache-serialization", "Cache serialization failed")
+                }
+                Error::Config(_) => ("config", "Configuration error"),
+                Error::AuthFailed => ("auth-failed", "Authentication failed"),
+                Error::NotFound(_) => ("not-found", "Not found"),
+                Error::BadRequest(_) => ("bad-request", "Bad request"),
+                Error::Conflict(_) => ("conflict", "Conflict"),
+                Error::Forbidden(_) => ("forbidden", "Forbidden"),
+                Error::TooManyRequests(_) => ("too-many-requests", "Too many requests"),
+                Error::Locked(_) => ("locked", "Locked"),
+                Error::ServiceUnavailable(_) => ("service-unavailable", "Service unavailable"),
+                Error::StorageUnavailable(_) => ("storage-unavailable", "Storage unavailable"),
+                Error::PageUnreadable(_) => ("page-unreadable", "Page unreadable"),
+                Error::Internal(_) => ("internal", "Internal server error"),
+            }
+        }
+
+        /// The response and the message it tells the client
+        fn response(self) -> (Response, String) {
+            // Pool exhaustion outside Storage::acquire is still a temporary condition
+            if let Error::Database(sqlx::Error::PoolTimedOut) = &self {
+                let detail =
+                    "Service unavailable: the server is busy, please try again in a moment";
+                return (
+                    (StatusCode::SERVICE_UNAVAILABLE, detail).into_response(),
+                    detail.to_string(),
+                );
+            }
+
+            if let Error::StorageUnavailable(reason) = &self {
+                let detail = "Changes cannot be saved right now because the server's storage is \
+                              read-only or full. Reading still works; please try again later.";
+                let mut response = (StatusCode::SERVICE_UNAVAILABLE, detail).into_response();
+                response
+                    .extensions_mut()
+                    .insert(StorageUnavailableResponse(reason.clone()));
+                return (response, detail.to_string());
+            }
+
+            // Not cached, so the page loads again once the file is fixed or replaced
+            if let Error::PageUnreadable(page) = &self {
+                let detail = self.to_string();
+                let body = serde_json::json!({
+                    "success": false,
+                    "code": "page_unreadable",
+                    "error": detail,
+                    "page": page.page,
+                    "member": page.member,
+                    "archive": page.archive,
+                });
+                let response = (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    [(header::CACHE_CONTROL, "no-store")],
+                    Json(body),
+                )
+                    .into_response();
+                return (response, detail);
+            }
+
             let status = match &self {
                 Error::AuthFailed => StatusCode::UNAUTHORIZED,
                 Error::NotFound(_) => StatusCode::NOT_FOUND,
                 Error::BadRequest(_) => StatusCode::BAD_REQUEST,
                 Error::Conflict(_) => StatusCode::CONFLICT,
                 Error::Forbidden(_) => StatusCode::FORBIDDEN,
+                Error::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+                Error::Locked(_) => StatusCode::LOCKED,
+                Error::ServiceUnavailable(_) | Error::StorageUnavailable(_) => {
+                    StatusCode::SERVICE_UNAVAILABLE
+                }
                 Error::Database(_)
                 | Error::Io(_)
                 | Error::Internal(_)
                 | Error::Archive(_)
+                | Error::PageUnreadable(_)
                 | Error::Json(_)
                 | Error::CacheCorrupted(_)
                 | Error::CacheSerialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
                 Error::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
             };
 
-            (status, self.to_string()).into_response()
+            let detail = self.to_string();
+            ((status, detail.clone()).into_response(), detail)
+        }
+    }
+
+    impl IntoResponse for Error {
+        fn into_response(self) -> Response {
+            let (kind, title) = self.kind();
+            let (mut response, detail) = self.response();
+            response.extensions_mut().insert(ErrorDetails {
+                kind,
+                title,
+                detail,
+            });
+            response
         }
     }
 }
@@ -0,0 +1,520 @@
+// Archive Cache - keeps the most recently read archives open in memory
+//
+// Serving a page normally opens the archive file and re-parses it from scratch.
+// While someone is reading an entry every page request hits the same archive, so the
+// N most recently used archives are kept as shared in-memory buffers and pages are
+// extracted from those instead. Buffers are immutable (Arc<[u8]>), so any number of
+// concurrent readers can extract from the same archive without further locking.
+//
+// Archives too large for the size limit are kept as open file handles instead. Pages
+// are still read from disk, but a page turn no longer reopens the file, which is what
+// hurts on network filesystems. Reads through one handle take turns.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::entry::{extract_image_from_buffer, extract_image_from_file};
+use super::{Entry, EntrySource};
+use crate::error::Result;
+
+/// An archive held in memory
+struct CachedArchive {
+    /// Entry signature at load time (a changed signature means the file was replaced)
+    signature: String,
+    data: Arc<[u8]>,
+    /// Value of the access counter at the last hit (for LRU eviction)
+    last_used: u64,
+}
+
+/// An archive too large to hold in memory, kept open
+struct CachedHandle {
+    /// Entry signature at open time
+    signature: String,
+    file: Arc<Mutex<std::fs::File>>,
+    last_used: u64,
+}
+
+/// A cached archive pages can be extracted from
+enum OpenArchive {
+    Buffer(Arc<[u8]>),
+    Handle(Arc<Mutex<std::fs::File>>),
+}
+
+impl OpenArchive {
+    async fn extract(self, image_name: &str) -> Result<Vec<u8>> {
+        match self {
+            OpenArchive::Buffer(data) => extract_image_from_buffer(data, image_name).await,
+            OpenArchive::Handle(file) => extract_image_from_file(file, image_name).await,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ArchiveCacheInner {
+    /// Entry ID -> archive buffer
+    archives: HashMap<String, CachedArchive>,
+    /// Entry ID -> open file, for archives over the size limit
+    handles: HashMap<String, CachedHandle>,
+    size_bytes: usize,
+    access_counter: u64,
+    hit_count: u64,
+    miss_count: u64,
+}
+
+impl ArchiveCacheInner {
+    /// Drop whatever is cached for an entry; returns whether anything was
+    fn remove(&mut self, entry_id: &str) -> bool {
+        let buffer = self.archives.remove(entry_id);
+        if let Some(old) = &buffer {
+            self.size_bytes -= old.data.len();
+        }
+        let handle = self.handles.remove(entry_id);
+        buffer.is_some() || handle.is_some()
+    }
+}
+
+/// Statistics about the archive cache
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ArchiveCacheStats {
+    pub archive_count: usize,
+    pub archive_limit: usize,
+    /// Archives over the size limit kept open as files (at most `archive_limit`)
+    pub handle_count: usize,
+    pub size_bytes: usize,
+    pub size_limit: usize,
+    pub hit_count: u64,
+    pub miss_count: u64,
+}
+
+/// LRU cache of open archives shared across requests
+#[derive(Clone)]
+pub struct ArchiveCache {
+    inner: Arc<Mutex<ArchiveCacheInner>>,
+    /// Maximum number of archives kept open, in memory and as files each (0 = disabled)
+    max_archives: usize,
+    /// Maximum total bytes of all open archives
+    max_bytes: usize,
+}
+
+impl ArchiveCache {
+    /// Create a new archive cache (max_archives = 0 disables caching)
+    pub fn new(max_archives: usize, max_bytes: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ArchiveCacheInner::default())),
+            max_archives,
+            max_bytes,
+        }
+    }
+
+    /// Get page image data (0-indexed), opening and caching the archive on a miss
+    /// Archives too large for the cache are kept open as files; directory entries are
+    /// read directly from disk
+    /// Pages of a merged entry are read from its parts, each cached on its own
+    pub async fn get_page(&self, entry: &Entry, page: usize) -> Result<Vec<u8>> {
+        let (entry, page) = entry.page_source(page)?;
+        let image_name = entry.image_name(page)?;
+
+        let archive = match self.lookup(entry) {
+            Some(archive) => archive,
+            None => match self.load(entry).await? {
+                Some(archive) => archive,
+                None => return entry.get_page(page).await,
+            },
+        };
+
+        archive.extract(image_name).await
+    }
+
+    /// Get page image data (0-indexed) from a cached archive, without caching on a miss
+    /// Used by bulk work (thumbnails) that touches every archive once and would otherwise
+    /// evict the archives people are actually reading
+    pub async fn peek_page(&self, entry: &Entry, page: usize) -> Result<Vec<u8>> {
+        let (entry, page) = entry.page_source(page)?;
+        let image_name = entry.image_name(page)?;
+
+        match self.lookup(entry) {
+            Some(archive) => archive.extract(image_name).await,
+            None => entry.get_page(page).await,
+        }
+    }
+
+    /// Drop a cached archive (e.g. after its entry was removed)
+    pub fn invalidate(&self, entry_id: &str) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.remove(entry_id);
+        }
+    }
+
+    /// Drop all cached archives
+    pub fn clear(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.archives.clear();
+            inner.handles.clear();
+            inner.size_bytes = 0;
+        }
+    }
+
+    /// Get cache statistics
+    pub fn stats(&self) -> ArchiveCacheStats {
+        let inner = match self.inner.lock() {
+            Ok(inner) => inner,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        ArchiveCacheStats {
+            archive_count: inner.archives.len(),
+            archive_limit: self.max_archives,
+            handle_count: inner.handles.len(),
+            size_bytes: inner.size_bytes,
+            size_limit: self.max_bytes,
+            hit_count: inner.hit_count,
+            miss_count: inner.miss_count,
+        }
+    }
+
+    /// Find a cached archive, in memory or open, for this entry
+    /// A cached archive whose signature no longer matches the entry is evicted
+    fn lookup(&self, entry: &Entry) -> Option<OpenArchive> {
+        if self.max_archives == 0 {
+            return None;
+        }
+
+        let mut guard = self.inner.lock().ok()?;
+        let inner = &mut *guard;
+        inner.access_counter += 1;
+        let now = inner.access_counter;
+
+        if let Some(cached) = inner.archives.get_mut(&entry.id) {
+            if cached.signature == entry.signature {
+                cached.last_used = now;
+                inner.hit_count += 1;
+                return Some(OpenArchive::Buffer(cached.data.clone()));
+            }
+        }
+        if let Some(cached) = inner.handles.get_mut(&entry.id) {
+            if cached.signature == entry.signature {
+                cached.last_used = now;
+                inner.hit_count += 1;
+                return Some(OpenArchive::Handle(cached.file.clone()));
+            }
+        }
+
+        // Anything still cached under this ID is from a file that has since been replaced
+        if inner.remove(&entry.id) {
+            tracing::debug!("Archive signature changed for {}, evicting", entry.title);
+        }
+
+        inner.miss_count += 1;
+        None
+    }
+
+    /// Read an archive into memory and insert it, evicting least recently used archives
+    /// Archives too large to cache are opened and kept as files instead
+    /// Returns None if the cache is disabled or the entry is a directory of images
+    async fn load(&self, entry: &Entry) -> Result<Option<OpenArchive>> {
+        if self.max_archives == 0 || entry.source == EntrySource::Directory {
+            return Ok(None);
+        }
+
+        let len = tokio::fs::metadata(&entry.path).await?.len() as usize;
+        if len > self.max_bytes {
+            return self.open(entry).await.map(Some);
+        }
+
+        let data: Arc<[u8]> = tokio::fs::read(&entry.path).await?.into();
+
+        let mut guard = match self.inner.lock() {
+            Ok(guard) => guard,
+            // Still serve the page, just don't cache it
+            Err(_) => return Ok(Some(OpenArchive::Buffer(data))),
+        };
+        let inner = &mut *guard;
+
+        // Another request may have loaded it in the meantime
+        if let Some(old) = inner.archives.remove(&entry.id) {
+            inner.size_bytes -= old.data.len();
+        }
+
+        while !inner.archives.is_empty()
+            && (inner.archives.len() >= self.max_archives
+                || inner.size_bytes + data.len() > self.max_bytes)
+        {
+            let oldest = inner
+                .archives
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(id, _)| id.clone());
+
+            match oldest.and_then(|id| inner.archives.remove(&id)) {
+                Some(evicted) => inner.size_bytes -= evicted.data.len(),
+                None => break,
+            }
+        }
+
+        inner.access_counter += 1;
+        let now = inner.access_counter;
+        inner.size_bytes += data.len();
+        inner.archives.insert(
+            entry.id.clone(),
+            CachedArchive {
+                signature: entry.signature.clone(),
+                data: data.clone(),
+                last_used: now,
+            },
+        );
+
+        Ok(Some(OpenArchive::Buffer(data)))
+    }
+
+    /// Open an archive and keep the handle, closing least recently used handles
+    async fn open(&self, entry: &Entry) -> Result<OpenArchive> {
+        let file = tokio::fs::File::open(&entry.path).await?.into_std().await;
+        let file = Arc::new(Mutex::new(file));
+
+        let mut guard = match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(_) => return Ok(OpenArchive::Handle(file)),
+        };
+        let inner = &mut *guard;
+
+        inner.handles.remove(&entry.id);
+        while inner.handles.len() >= self.max_archives {
+            // Requests still reading from an evicted handle keep it open until they finish
+            let oldest = inner
+                .handles
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(id, _)| id.clone());
+            match oldest {
+                Some(id) => inner.handles.remove(&id),
+                None => break,
+            };
+        }
+
+        inner.access_counter += 1;
+        let now = inner.access_counter;
+        inner.handles.insert(
+            entry.id.clone(),
+            CachedHandle {
+                signature: entry.signature.clone(),
+                file: file.clone(),
+                last_used: now,
+            },
+        );
+
+        Ok(OpenArchive::Handle(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    /// Write an uncompressed (stored) ZIP archive
+    fn write_stored_zip(path: &Path, files: &[(String, Vec<u8>)]) {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+
+        for (name, data) in files {
+            let offset = out.len() as u32;
+            let crc = crc32fast::hash(data);
+            let size = data.len() as u32;
+
+            // Local file header
+            out.extend_from_slice(&0x04034b50u32.to_le_bytes());
+            out.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            out.extend_from_slice(&[0; 8]); // flags, method (stored), time, date
+            out.extend_from_slice(&crc.to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes()); // extra length
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+
+            // Central directory header
+            central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+            central.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            central.extend_from_slice(&[0; 8]); // flags, method, time, date
+            central.extend_from_slice(&crc.to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+
+        let central_offset = out.len() as u32;
+        let central_size = central.len() as u32;
+        out.extend_from_slice(&central);
+
+        // End of central directory record
+        out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        out.extend_from_slice(&[0; 4]); // disk numbers
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&central_size.to_le_bytes());
+        out.extend_from_slice(&central_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // comment length
+
+        std::fs::write(path, out).unwrap();
+    }
+
+    /// Create a fixture entry with `pages` fake images of `page_size` bytes each
+    async fn create_fixture(dir: &Path, name: &str, pages: usize, page_size: usize) -> Entry {
+        let files: Vec<(String, Vec<u8>)> = (0..pages)
+            .map(|i| (format!("{:04}.jpg", i), vec![(i % 251) as u8; page_size]))
+            .collect();
+
+        let path = dir.join(format!("{}.cbz", name));
+        write_stored_zip(&path, &files);
+
+        let mut entry = Entry::from_archive(path).await.unwrap();
+        entry.calculate_signature().unwrap();
+        entry
+    }
+
+    #[tokio::test]
+    async fn test_cached_pages_match_direct_reads() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_fixture(temp_dir.path(), "ch1", 20, 4096).await;
+        let cache = ArchiveCache::new(4, 64 * 1024 * 1024);
+
+        for page in 0..entry.pages {
+            let direct = entry.get_page(page).await.unwrap();
+            let cached = cache.get_page(&entry, page).await.unwrap();
+            assert_eq!(direct, cached);
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.archive_count, 1);
+        assert_eq!(stats.miss_count, 1);
+        assert_eq!(stats.hit_count, entry.pages as u64 - 1);
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction() {
+        let temp_dir = TempDir::new().unwrap();
+        let a = create_fixture(temp_dir.path(), "a", 2, 128).await;
+        let b = create_fixture(temp_dir.path(), "b", 2, 128).await;
+        let c = create_fixture(temp_dir.path(), "c", 2, 128).await;
+        let cache = ArchiveCache::new(2, 64 * 1024 * 1024);
+
+        cache.get_page(&a, 0).await.unwrap();
+        cache.get_page(&b, 0).await.unwrap();
+        cache.get_page(&a, 1).await.unwrap(); // a is now most recently used
+        cache.get_page(&c, 0).await.unwrap(); // evicts b
+
+        assert!(cache.lookup(&a).is_some());
+        assert!(cache.lookup(&b).is_none());
+        assert!(cache.lookup(&c).is_some());
+        assert_eq!(cache.stats().archive_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_signature_change_evicts() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut entry = create_fixture(temp_dir.path(), "ch1", 2, 128).await;
+        let cache = ArchiveCache::new(4, 64 * 1024 * 1024);
+
+        cache.get_page(&entry, 0).await.unwrap();
+        assert!(cache.lookup(&entry).is_some());
+
+        // Rescan found a different file at the same entry
+        entry.signature = format!("{}-changed", entry.signature);
+        assert!(cache.lookup(&entry).is_none());
+        assert_eq!(cache.stats().archive_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_archive_not_cached() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_fixture(temp_dir.path(), "big", 4, 4096).await;
+        let cache = ArchiveCache::new(4, 1024);
+
+        let data = cache.get_page(&entry, 1).await.unwrap();
+        assert_eq!(data, entry.get_page(1).await.unwrap());
+        assert_eq!(cache.stats().archive_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_archive_kept_open() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut entry = create_fixture(temp_dir.path(), "big", 4, 4096).await;
+        let other = create_fixture(temp_dir.path(), "other", 4, 4096).await;
+        let cache = ArchiveCache::new(1, 1024);
+
+        // Pages in any order come from the one open handle
+        for page in [2, 0, 3, 1] {
+            let data = cache.get_page(&entry, page).await.unwrap();
+            assert_eq!(data, entry.get_page(page).await.unwrap());
+        }
+        let stats = cache.stats();
+        assert_eq!(stats.handle_count, 1);
+        assert_eq!(stats.miss_count, 1);
+        assert_eq!(stats.hit_count, 3);
+
+        // A changed signature closes the handle
+        entry.signature = format!("{}-changed", entry.signature);
+        assert!(cache.lookup(&entry).is_none());
+        assert_eq!(cache.stats().handle_count, 0);
+
+        // Handles are bounded like buffers
+        cache.get_page(&entry, 0).await.unwrap();
+        cache.get_page(&other, 0).await.unwrap();
+        assert!(cache.lookup(&entry).is_none());
+        assert!(cache.lookup(&other).is_some());
+        assert_eq!(cache.stats().handle_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_peek_does_not_populate() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_fixture(temp_dir.path(), "ch1", 2, 128).await;
+        let cache = ArchiveCache::new(4, 64 * 1024 * 1024);
+
+        cache.peek_page(&entry, 0).await.unwrap();
+        assert_eq!(cache.stats().archive_count, 0);
+    }
+
+    /// Benchmark-style comparison of reading every page of a many-page archive
+    /// Run with: cargo test --release archive_cache -- --ignored --nocapture
+    #[tokio::test]
+    #[ignore]
+    async fn bench_cached_vs_direct_page_reads() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_fixture(temp_dir.path(), "long", 300, 64 * 1024).await;
+        let cache = ArchiveCache::new(4, 256 * 1024 * 1024);
+
+        // Warm the OS page cache and the archive cache so only per-page work is measured
+        entry.get_page(0).await.unwrap();
+        cache.get_page(&entry, 0).await.unwrap();
+
+        let start = Instant::now();
+        for page in 0..entry.pages {
+            entry.get_page(page).await.unwrap();
+        }
+        let direct = start.elapsed();
+
+        let start = Instant::now();
+        for page in 0..entry.pages {
+            cache.get_page(&entry, page).await.unwrap();
+        }
+        let cached = start.elapsed();
+
+        println!(
+            "{} pages: direct {:?} ({:?}/page), cached {:?} ({:?}/page)",
+            entry.pages,
+            direct,
+            direct / entry.pages as u32,
+            cached,
+            cached / entry.pages as u32
+        );
+        assert!(cached < direct);
+    }
+}
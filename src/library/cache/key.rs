@@ -36,19 +36,25 @@ pub fn sorted_titles_key(
 }
 
 /// Generate cache key for sorted entries
-/// Includes title context, username, and all sort parameters
+/// Includes title context, username, all sort parameters, and the pinned
+/// entry order (if any) so changing the override never serves a stale list
 pub fn sorted_entries_key(
     title_id: &str,
     username: &str,
     entry_ids: &[String],
     sort_method: &str,
     ascending: bool,
+    entry_order: Option<&[String]>,
 ) -> String {
     // Create signature from entry IDs (order matters for validation)
     let ids_signature = entry_ids.join(",");
+    let order_signature = match entry_order {
+        Some(order) => format!("pinned[{}]", order.join(",")),
+        None => String::new(),
+    };
     let data = format!(
-        "{}:{}:{}:{}:{}",
-        title_id, username, ids_signature, sort_method, ascending
+        "{}:{}:{}:{}:{}:{}",
+        title_id, username, ids_signature, sort_method, ascending, order_signature
     );
     hash_key(SORTED_ENTRIES_PREFIX, &data)
 }
@@ -112,22 +118,41 @@ mod tests {
     #[test]
     fn test_sorted_entries_key_determinism() {
         let ids = vec!["entry1".to_string(), "entry2".to_string()];
-        let key1 = sorted_entries_key("title1", "user1", &ids, "name", true);
-        let key2 = sorted_entries_key("title1", "user1", &ids, "name", true);
+        let key1 = sorted_entries_key("title1", "user1", &ids, "name", true, None);
+        let key2 = sorted_entries_key("title1", "user1", &ids, "name", true, None);
         assert_eq!(key1, key2, "Same inputs should produce same key");
     }
 
     #[test]
     fn test_sorted_entries_key_uniqueness() {
         let ids = vec!["entry1".to_string()];
-        let key1 = sorted_entries_key("title1", "user1", &ids, "name", true);
-        let key2 = sorted_entries_key("title2", "user1", &ids, "name", true); // Different title
-        let key3 = sorted_entries_key("title1", "user2", &ids, "name", true); // Different user
+        let key1 = sorted_entries_key("title1", "user1", &ids, "name", true, None);
+        let key2 = sorted_entries_key("title2", "user1", &ids, "name", true, None); // Different title
+        let key3 = sorted_entries_key("title1", "user2", &ids, "name", true, None); // Different user
 
         assert_ne!(key1, key2, "Different titles should produce different keys");
         assert_ne!(key1, key3, "Different users should produce different keys");
     }
 
+    #[test]
+    fn test_sorted_entries_key_entry_order() {
+        let ids = vec!["entry1".to_string(), "entry2".to_string()];
+        let order_a = vec!["entry2".to_string(), "entry1".to_string()];
+        let order_b = vec!["entry1".to_string(), "entry2".to_string()];
+        let unpinned = sorted_entries_key("title1", "user1", &ids, "name", true, None);
+        let pinned_a = sorted_entries_key("title1", "user1", &ids, "name", true, Some(&order_a));
+        let pinned_b = sorted_entries_key("title1", "user1", &ids, "name", true, Some(&order_b));
+
+        assert_ne!(
+            unpinned, pinned_a,
+            "Adding an override should produce a different key"
+        );
+        assert_ne!(
+            pinned_a, pinned_b,
+            "Changing the override should produce a different key"
+        );
+    }
+
     #[test]
     fn test_progress_sum_key_determinism() {
         let key1 = progress_sum_key("title1", "user1", "sig123");
@@ -166,7 +191,7 @@ mod tests {
     fn test_key_prefixes() {
         let ids = vec!["id1".to_string()];
         let titles_key = sorted_titles_key("user", &ids, "name", true);
-        let entries_key = sorted_entries_key("title", "user", &ids, "name", true);
+        let entries_key = sorted_entries_key("title", "user", &ids, "name", true, None);
         let progress_key = progress_sum_key("title", "user", "sig");
         let info_key = info_json_key(Path::new("/path"));
 
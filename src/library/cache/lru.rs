@@ -1,8 +1,11 @@
 // LRU Cache - in-memory cache with Least Recently Used eviction
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::Instant;
 
+use super::scope::{CacheFilter, CacheKind, CacheScope};
+use crate::util::time::instant_to_ts;
+
 /// Statistics about cache performance
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct CacheStats {
@@ -12,18 +15,14 @@ pub struct CacheStats {
     pub hit_count: u64,
     pub miss_count: u64,
     pub eviction_count: u64,
+    /// The same broken down by kind (`sorted_titles`, ..., `other`), for kinds seen so far
+    pub kinds: BTreeMap<String, CacheKindStats>,
 }
 
 impl CacheStats {
     /// Calculate hit rate as a percentage (0-100), rounded to 2 decimal places
     pub fn hit_rate(&self) -> f64 {
-        let total = self.hit_count + self.miss_count;
-        if total == 0 {
-            0.0
-        } else {
-            let rate = (self.hit_count as f64 / total as f64) * 100.0;
-            (rate * 100.0).round() / 100.0
-        }
+        hit_rate(self.hit_count, self.miss_count)
     }
 
     /// Calculate cache usage as a percentage (0-100), rounded to 2 decimal places
@@ -37,22 +36,62 @@ impl CacheStats {
     }
 }
 
+/// Statistics of the cache entries of one kind
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct CacheKindStats {
+    pub size_bytes: usize,
+    pub entry_count: usize,
+    pub hit_count: u64,
+    pub miss_count: u64,
+    pub eviction_count: u64,
+}
+
+impl CacheKindStats {
+    /// Hit rate as a percentage (0-100), rounded to 2 decimal places
+    pub fn hit_rate(&self) -> f64 {
+        hit_rate(self.hit_count, self.miss_count)
+    }
+}
+
+fn hit_rate(hits: u64, misses: u64) -> f64 {
+    let total = hits + misses;
+    if total == 0 {
+        0.0
+    } else {
+        let rate = (hits as f64 / total as f64) * 100.0;
+        (rate * 100.0).round() / 100.0
+    }
+}
+
+/// Kind counted for keys outside the namespaces of key.rs
+const OTHER_KIND: &str = "other";
+
+/// Kind of the value cached under `key`, from the namespace the key starts with
+fn kind_of(key: &str) -> &'static str {
+    key.split_once(':')
+        .and_then(|(namespace, _)| CacheKind::parse(namespace))
+        .map_or(OTHER_KIND, |kind| kind.as_str())
+}
+
 /// Information about a cache entry for debugging
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct CacheEntryInfo {
     pub key: String,
+    /// What the entry is about, if it was stored with a scope
+    pub scope: Option<CacheScope>,
     pub size_bytes: usize,
     pub access_count: u64,
-    #[serde(skip)]
-    pub last_access: Instant,
-    #[serde(skip)]
-    pub created_at: Instant,
+    /// Unix timestamp (UTC seconds)
+    pub last_access: i64,
+    /// Unix timestamp (UTC seconds)
+    pub created_at: i64,
 }
 
 /// Internal cache entry with metadata
 #[derive(Debug, Clone)]
 struct CacheEntry {
     key: String,
+    scope: Option<CacheScope>,
     value: Vec<u8>,       // Serialized data (MessagePack)
     size_bytes: usize,    // Memory footprint
     access_time: Instant, // For LRU tracking
@@ -68,6 +107,8 @@ pub struct LruCache {
     hit_count: u64,
     miss_count: u64,
     eviction_count: u64,
+    /// Hits, misses and evictions by kind; sizes are counted in `stats`
+    kind_counts: HashMap<&'static str, CacheKindStats>,
     logging_enabled: bool,
 }
 
@@ -81,6 +122,7 @@ impl LruCache {
             hit_count: 0,
             miss_count: 0,
             eviction_count: 0,
+            kind_counts: HashMap::new(),
             logging_enabled,
         }
     }
@@ -96,6 +138,7 @@ impl LruCache {
             entry.access_count += 1;
 
             self.hit_count += 1;
+            self.kind_counts.entry(kind_of(key)).or_default().hit_count += 1;
 
             if self.logging_enabled {
                 tracing::debug!("Cache hit: {} (access count: {})", key, entry.access_count);
@@ -111,6 +154,7 @@ impl LruCache {
             }
         } else {
             self.miss_count += 1;
+            self.kind_counts.entry(kind_of(key)).or_default().miss_count += 1;
 
             if self.logging_enabled {
                 tracing::debug!("Cache miss: {}", key);
@@ -121,7 +165,23 @@ impl LruCache {
     }
 
     /// Set cached value (evicts LRU entries if size limit exceeded)
+    #[cfg(test)]
     pub fn set<T>(&mut self, key: String, value: T)
+    where
+        T: serde::Serialize,
+    {
+        self.insert(key, None, value);
+    }
+
+    /// Set cached value along with what it is about, for `invalidate_matching`
+    pub fn set_scoped<T>(&mut self, key: String, scope: CacheScope, value: T)
+    where
+        T: serde::Serialize,
+    {
+        self.insert(key, Some(scope), value);
+    }
+
+    fn insert<T>(&mut self, key: String, scope: Option<CacheScope>, value: T)
     where
         T: serde::Serialize,
     {
@@ -165,6 +225,7 @@ impl LruCache {
         let now = Instant::now();
         let entry = CacheEntry {
             key: key.clone(),
+            scope,
             value: serialized,
             size_bytes: value_size,
             access_time: now,
@@ -189,6 +250,10 @@ impl LruCache {
             if let Some(entry) = self.entries.remove(&lru_key) {
                 self.current_size_bytes -= entry.size_bytes;
                 self.eviction_count += 1;
+                self.kind_counts
+                    .entry(kind_of(&lru_key))
+                    .or_default()
+                    .eviction_count += 1;
 
                 if self.logging_enabled {
                     tracing::debug!(
@@ -213,6 +278,29 @@ impl LruCache {
         }
     }
 
+    /// Invalidate every scoped entry the filter matches, returning their scopes
+    pub fn invalidate_matching(&mut self, filter: &CacheFilter) -> Vec<CacheScope> {
+        let keys: Vec<String> = self
+            .entries
+            .values()
+            .filter(|e| e.scope.as_ref().is_some_and(|s| filter.matches(s)))
+            .map(|e| e.key.clone())
+            .collect();
+
+        let mut removed = Vec::with_capacity(keys.len());
+        for key in keys {
+            if let Some(entry) = self.entries.remove(&key) {
+                self.current_size_bytes -= entry.size_bytes;
+                removed.extend(entry.scope);
+
+                if self.logging_enabled {
+                    tracing::debug!("Cache invalidation: {}", key);
+                }
+            }
+        }
+        removed
+    }
+
     /// Clear all cache entries
     pub fn clear(&mut self) {
         let count = self.entries.len();
@@ -226,6 +314,17 @@ impl LruCache {
 
     /// Get cache statistics
     pub fn stats(&self) -> CacheStats {
+        let mut kinds: BTreeMap<String, CacheKindStats> = self
+            .kind_counts
+            .iter()
+            .map(|(kind, counts)| (kind.to_string(), counts.clone()))
+            .collect();
+        for entry in self.entries.values() {
+            let kind = kinds.entry(kind_of(&entry.key).to_string()).or_default();
+            kind.size_bytes += entry.size_bytes;
+            kind.entry_count += 1;
+        }
+
         CacheStats {
             size_bytes: self.current_size_bytes,
             size_limit: self.size_limit_bytes,
@@ -233,6 +332,7 @@ impl LruCache {
             hit_count: self.hit_count,
             miss_count: self.miss_count,
             eviction_count: self.eviction_count,
+            kinds,
         }
     }
 
@@ -242,10 +342,11 @@ impl LruCache {
             .values()
             .map(|entry| CacheEntryInfo {
                 key: entry.key.clone(),
+                scope: entry.scope.clone(),
                 size_bytes: entry.size_bytes,
                 access_count: entry.access_count,
-                last_access: entry.access_time,
-                created_at: entry.created_at,
+                last_access: instant_to_ts(entry.access_time),
+                created_at: instant_to_ts(entry.created_at),
             })
             .collect()
     }
@@ -503,4 +604,125 @@ mod tests {
         assert!(keys.contains(&"key1"));
         assert!(keys.contains(&"key2"));
     }
+
+    #[test]
+    fn test_invalidate_matching_scopes() {
+        let mut cache = LruCache::new(1000, false);
+        cache.set_scoped("a".to_string(), CacheScope::sorted_titles("user1"), vec![1]);
+        cache.set_scoped(
+            "b".to_string(),
+            CacheScope::sorted_entries("t1", "user2"),
+            vec![2],
+        );
+        cache.set("unscoped".to_string(), vec![3]);
+        let size_before = cache.stats().size_bytes;
+
+        let filter = CacheFilter::parse("user=user1").unwrap();
+        assert_eq!(
+            cache.invalidate_matching(&filter),
+            vec![CacheScope::sorted_titles("user1")]
+        );
+        assert!(cache.get::<Vec<i32>>("a").is_none());
+        assert!(cache.get::<Vec<i32>>("b").is_some());
+        assert!(cache.get::<Vec<i32>>("unscoped").is_some());
+        assert!(cache.stats().size_bytes < size_before);
+    }
+
+    #[test]
+    fn test_entries_timestamps_serialize() {
+        let mut cache = LruCache::new(1000, false);
+        cache.set("key1".to_string(), vec![1, 2, 3]);
+
+        let now = crate::util::time::now_ts();
+        let entries = cache.entries();
+        assert!((entries[0].created_at - now).abs() <= 1);
+        assert!(entries[0].last_access >= entries[0].created_at);
+
+        let json = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(json["created_at"].as_i64(), Some(entries[0].created_at));
+        assert_eq!(json["last_access"].as_i64(), Some(entries[0].last_access));
+    }
+
+    #[test]
+    fn test_stats_by_kind() {
+        use crate::library::cache::key;
+
+        // Room for two of the entries below
+        let mut cache = LruCache::new(130, false);
+        let ids = vec!["t1".to_string()];
+        let titles = key::sorted_titles_key("user1", &ids, "name", true);
+        let entries = key::sorted_entries_key("t1", "user1", &ids, "name", true, None);
+        let sum = key::progress_sum_key("t1", "user1", "sig");
+
+        cache.set(titles.clone(), vec![0u8; 50]);
+        let _: Option<Vec<u8>> = cache.get(&titles); // Hit
+        let _: Option<Vec<u8>> = cache.get(&titles); // Hit
+        let _: Option<Vec<u8>> = cache.get(&entries); // Miss
+        sleep(Duration::from_millis(5));
+        cache.set(entries.clone(), vec![0u8; 50]);
+        let _: Option<Vec<u8>> = cache.get(&sum); // Miss
+        sleep(Duration::from_millis(5));
+        cache.set(sum.clone(), vec![0u8; 30]); // Evicts the sorted titles
+        cache.set("unnamespaced".to_string(), vec![0u8; 1]);
+        let _: Option<Vec<u8>> = cache.get("unnamespaced"); // Hit
+
+        let stats = cache.stats();
+        let kind = |name: &str| stats.kinds.get(name).cloned().unwrap_or_default();
+        let entry_size = |key: &str| {
+            cache
+                .entries()
+                .iter()
+                .find(|e| e.key == key)
+                .map_or(0, |e| e.size_bytes)
+        };
+
+        assert_eq!(
+            kind("sorted_titles"),
+            CacheKindStats {
+                size_bytes: 0,
+                entry_count: 0,
+                hit_count: 2,
+                miss_count: 0,
+                eviction_count: 1,
+            }
+        );
+        assert_eq!(kind("sorted_titles").hit_rate(), 100.0);
+        assert_eq!(
+            kind("sorted_entries"),
+            CacheKindStats {
+                size_bytes: entry_size(&entries),
+                entry_count: 1,
+                hit_count: 0,
+                miss_count: 1,
+                eviction_count: 0,
+            }
+        );
+        assert_eq!(kind("progress_sum").miss_count, 1);
+        assert_eq!(kind("progress_sum").size_bytes, entry_size(&sum));
+        assert_eq!(kind("other").hit_count, 1);
+        assert!(!stats.kinds.contains_key("info_json"));
+
+        // The kinds add up to the totals
+        let kinds = stats.kinds.values();
+        assert_eq!(
+            kinds.clone().map(|k| k.hit_count).sum::<u64>(),
+            stats.hit_count
+        );
+        assert_eq!(
+            kinds.clone().map(|k| k.miss_count).sum::<u64>(),
+            stats.miss_count
+        );
+        assert_eq!(
+            kinds.clone().map(|k| k.eviction_count).sum::<u64>(),
+            stats.eviction_count
+        );
+        assert_eq!(
+            kinds.clone().map(|k| k.size_bytes).sum::<usize>(),
+            stats.size_bytes
+        );
+        assert_eq!(
+            kinds.map(|k| k.entry_count).sum::<usize>(),
+            stats.entry_count
+        );
+    }
 }
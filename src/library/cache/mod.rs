@@ -1,15 +1,17 @@
 // Cache module - unified caching API for library operations
 //
 // Provides two-tier caching:
-// 1. Library Cache File - persistent disk cache for entire library structure
+// 1. Library Cache Store - persistent disk cache for entire library structure, one shard per title
 // 2. LRU Cache - in-memory runtime cache for computed data
 
-mod file;
 pub mod key;
 mod lru;
+mod scope;
+mod store;
 
-pub use file::CachedLibraryData;
-pub use lru::{CacheEntryInfo, CacheStats};
+pub use lru::{CacheEntryInfo, CacheKindStats, CacheStats};
+pub use scope::{CacheFilter, CacheKind, CacheScope};
+pub use store::{CachedLibraryData, CACHE_FORMAT_VERSION};
 
 use crate::{error::Result, Config, Library};
 use std::path::Path;
@@ -17,8 +19,11 @@ use std::path::Path;
 /// Cache facade providing unified caching API
 pub struct Cache {
     lru_cache: lru::LruCache,
-    file_manager: file::CacheFileManager,
-    enabled: bool,
+    store_manager: store::CacheStoreManager,
+    /// Sorted lists and progress sums are kept in the LRU (config `memory_cache_enabled`)
+    memory_enabled: bool,
+    /// The library is saved to and loaded from the cache file (config `library_cache_file_enabled`)
+    file_enabled: bool,
 }
 
 impl Cache {
@@ -26,116 +31,140 @@ impl Cache {
     pub fn new(config: &Config) -> Self {
         let size_bytes = config.cache_size_mbs * 1024 * 1024;
         let lru_cache = lru::LruCache::new(size_bytes, config.cache_log_enabled);
-        let file_manager = file::CacheFileManager::new(config.library_cache_path.clone());
+        let store_manager = store::CacheStoreManager::new(config.library_cache_path.clone());
 
         Self {
             lru_cache,
-            file_manager,
-            enabled: config.cache_enabled,
+            store_manager,
+            memory_enabled: config.memory_cache_enabled,
+            file_enabled: config.library_cache_file_enabled,
         }
     }
 
+    /// Whether the in-memory LRU cache stores anything
+    pub fn memory_enabled(&self) -> bool {
+        self.memory_enabled
+    }
+
+    /// Whether the library cache file is written and read
+    pub fn file_enabled(&self) -> bool {
+        self.file_enabled
+    }
+
     /// Get cached sorted titles
     pub fn get_sorted_titles(&mut self, key: &str) -> Option<Vec<String>> {
-        if !self.enabled {
+        if !self.memory_enabled {
             return None;
         }
         self.lru_cache.get(key)
     }
 
-    /// Cache sorted titles
-    pub fn set_sorted_titles(&mut self, key: String, title_ids: Vec<String>) {
-        if !self.enabled {
+    /// Cache a user's sorted titles
+    pub fn set_sorted_titles(&mut self, key: String, username: &str, title_ids: Vec<String>) {
+        if !self.memory_enabled {
             return;
         }
-        self.lru_cache.set(key, title_ids);
+        self.lru_cache
+            .set_scoped(key, CacheScope::sorted_titles(username), title_ids);
     }
 
     /// Get cached sorted entries
     pub fn get_sorted_entries(&mut self, key: &str) -> Option<Vec<String>> {
-        if !self.enabled {
+        if !self.memory_enabled {
             return None;
         }
         self.lru_cache.get(key)
     }
 
-    /// Cache sorted entries
-    pub fn set_sorted_entries(&mut self, key: String, entry_ids: Vec<String>) {
-        if !self.enabled {
+    /// Cache a user's sorted entries of a title
+    pub fn set_sorted_entries(
+        &mut self,
+        key: String,
+        title_id: &str,
+        username: &str,
+        entry_ids: Vec<String>,
+    ) {
+        if !self.memory_enabled {
             return;
         }
-        self.lru_cache.set(key, entry_ids);
+        self.lru_cache.set_scoped(
+            key,
+            CacheScope::sorted_entries(title_id, username),
+            entry_ids,
+        );
     }
 
     /// Invalidate progress-related caches
     pub fn invalidate_progress(&mut self, title_id: &str, username: &str) {
-        if !self.enabled {
+        if !self.memory_enabled {
             return;
         }
 
         // Invalidate all cached sorted lists for this user that might depend on progress
         // This includes sorted titles with progress sorting
-        let prefix = format!("sorted_titles:{}:", username);
-        self.invalidate_by_prefix(&prefix);
-
-        // Also invalidate sorted entries for this title
-        let entry_prefix = format!("sorted_entries:{}:{}:", title_id, username);
-        self.invalidate_by_prefix(&entry_prefix);
-
-        // Invalidate progress sum cache
-        let progress_prefix = format!("progress_sum:{}:{}:", title_id, username);
-        self.invalidate_by_prefix(&progress_prefix);
+        self.invalidate_matching(&CacheFilter {
+            kind: Some(CacheKind::SortedTitles),
+            username: Some(username.to_string()),
+            title_id: None,
+        });
+
+        // Also invalidate sorted entries and the progress sum for this title
+        for kind in [CacheKind::SortedEntries, CacheKind::ProgressSum] {
+            self.invalidate_matching(&CacheFilter {
+                kind: Some(kind),
+                username: Some(username.to_string()),
+                title_id: Some(title_id.to_string()),
+            });
+        }
     }
 
     /// Invalidate all caches for a title
     pub fn invalidate_sorted_for_title(&mut self, title_id: &str) {
-        if !self.enabled {
+        if !self.memory_enabled {
             return;
         }
 
-        // Invalidate sorted entries for this title (all users)
-        let prefix = format!("sorted_entries:{}:", title_id);
-        self.invalidate_by_prefix(&prefix);
-
-        // Invalidate progress sums for this title (all users)
-        let progress_prefix = format!("progress_sum:{}:", title_id);
-        self.invalidate_by_prefix(&progress_prefix);
+        // Invalidate sorted entries and progress sums for this title (all users)
+        for kind in [CacheKind::SortedEntries, CacheKind::ProgressSum] {
+            self.invalidate_matching(&CacheFilter {
+                kind: Some(kind),
+                username: None,
+                title_id: Some(title_id.to_string()),
+            });
+        }
 
         // Note: We don't invalidate sorted_titles here because title-level
         // changes don't affect title sorting (only progress changes do)
     }
 
-    /// Invalidate all cache entries with the given prefix
-    fn invalidate_by_prefix(&mut self, prefix: &str) {
-        // Get all entries and find those with matching prefix
-        let entries = self.lru_cache.entries();
-        for entry in entries {
-            if entry.key.starts_with(prefix) {
-                self.lru_cache.invalidate(&entry.key);
-            }
+    /// Invalidate every entry the filter matches, returning what was removed
+    pub fn invalidate_matching(&mut self, filter: &CacheFilter) -> Vec<CacheScope> {
+        if !self.memory_enabled {
+            return Vec::new();
         }
+        self.lru_cache.invalidate_matching(filter)
     }
 
     /// Save library to cache file
     pub async fn save_library(&self, library: &Library) -> Result<()> {
-        if !self.enabled {
+        if !self.file_enabled {
             return Ok(());
         }
-        self.file_manager.save(library).await
+        self.store_manager.save(library).await
     }
 
     /// Save library data to cache file (for background tasks)
     /// Takes owned CachedLibraryData to support spawning
-    pub async fn save_library_data(&self, data: file::CachedLibraryData) -> Result<()> {
-        if !self.enabled {
+    pub async fn save_library_data(&self, data: store::CachedLibraryData) -> Result<()> {
+        if !self.file_enabled {
             return Ok(());
         }
-        self.file_manager.save_data(data).await
+        self.store_manager.save_data(data).await
     }
 
-    /// Get cloneable file manager for background save tasks
-    pub fn file_manager(&self) -> file::CacheFileManager {
-        self.file_manager.clone()
+    /// Get cloneable store manager for background save tasks
+    pub fn store_manager(&self) -> store::CacheStoreManager {
+        self.store_manager.clone()
     }
 
     /// Load library from cache file
@@ -143,25 +172,25 @@ impl Cache {
         &self,
         expected_dir: &Path,
         db_title_count: usize,
-    ) -> Result<Option<file::CachedLibraryData>> {
-        if !self.enabled {
+    ) -> Result<Option<store::CachedLibraryData>> {
+        if !self.file_enabled {
             return Ok(None);
         }
 
         // Load cached data
-        let cached_data = match self.file_manager.load(expected_dir).await? {
+        let cached_data = match self.store_manager.load(expected_dir).await? {
             Some(data) => data,
             None => return Ok(None),
         };
 
         // Validate title count
-        if cached_data.titles.len() != db_title_count {
+        if cached_data.title_count() != db_title_count {
             tracing::warn!(
                 "Cache title count mismatch: cache has {}, database has {}. Invalidating cache.",
-                cached_data.titles.len(),
+                cached_data.title_count(),
                 db_title_count
             );
-            let _ = self.file_manager.delete().await;
+            let _ = self.store_manager.delete().await;
             return Ok(None);
         }
 
@@ -180,7 +209,7 @@ impl Cache {
 
     /// Clear all cached data
     pub fn clear(&mut self) {
-        if !self.enabled {
+        if !self.memory_enabled {
             return;
         }
         self.lru_cache.clear();
@@ -188,7 +217,7 @@ impl Cache {
 
     /// Invalidate a specific cache entry by key
     pub fn invalidate(&mut self, key: &str) {
-        if !self.enabled {
+        if !self.memory_enabled {
             return;
         }
         self.lru_cache.invalidate(key);
@@ -215,13 +244,47 @@ mod tests {
             plugin_path: std::path::PathBuf::from("/tmp/plugins"),
             download_timeout_seconds: 30,
             library_cache_path: std::path::PathBuf::from("/tmp/cache.bin"),
-            cache_enabled: true,
+            cache_enabled: None,
+            library_cache_file_enabled: true,
+            memory_cache_enabled: true,
             cache_size_mbs: 100,
             cache_log_enabled: false,
             disable_login: false,
             default_username: None,
             auth_proxy_header_name: None,
             plugin_update_interval_hours: 24,
+            download_quota_mbs: 0,
+            archive_cache_entries: 0,
+            archive_cache_size_mbs: 0,
+            db_slow_query_ms: 500,
+            db_slow_acquire_ms: 100,
+            validate_pages: false,
+            instance_name: "Mango".to_string(),
+            accent_color: None,
+            logo_path: None,
+            default_reading_direction: crate::library::ReadingDirection::Vertical,
+            date_added_from_mtime: false,
+            two_factor_basic_auth: crate::config::TwoFactorBasicAuth::Bypass,
+            thumbnail_prune_grace_days: 30,
+            cover_file_names: Vec::new(),
+            loose_archives: crate::config::LooseArchives::default(),
+            unsorted_title_name: "Unsorted".to_string(),
+            scan_skip_hidden: true,
+            scan_exclude_patterns: Vec::new(),
+            library_case_sensitive: None,
+            scan_workers: 20,
+            entry_title_cleanup: true,
+            entry_title_strip_patterns: Vec::new(),
+            komga_compat_enabled: false,
+            library_page_size: 0,
+            library_max_page_size: 500,
+            library_card_density: crate::config::CardDensity::default(),
+            static_path: None,
+            convert_keep_originals: true,
+            watch_library: false,
+            admin_can_view_user_progress: true,
+            cors_allowed_origins: Vec::new(),
+            tag_unlock_minutes: 30,
         }
     }
 
@@ -238,12 +301,12 @@ mod tests {
     #[test]
     fn test_cache_disabled() {
         let mut config = create_test_config();
-        config.cache_enabled = false;
+        config.memory_cache_enabled = false;
 
         let mut cache = Cache::new(&config);
 
         // Set should be no-op when disabled
-        cache.set_sorted_titles("key".to_string(), vec!["id1".to_string()]);
+        cache.set_sorted_titles("key".to_string(), "user1", vec!["id1".to_string()]);
         assert!(cache.get_sorted_titles("key").is_none());
 
         // Invalidation should be no-op
@@ -251,6 +314,37 @@ mod tests {
         cache.clear();
     }
 
+    #[tokio::test]
+    async fn test_cache_kinds_toggle_separately() {
+        for (file_enabled, memory_enabled) in
+            [(true, true), (true, false), (false, true), (false, false)]
+        {
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let mut config = create_test_config();
+            config.library_cache_path = temp_dir.path().join("cache.bin");
+            config.library_cache_file_enabled = file_enabled;
+            config.memory_cache_enabled = memory_enabled;
+            let mut cache = Cache::new(&config);
+            let data = || CachedLibraryData {
+                path: config.library_path.clone(),
+                titles: Default::default(),
+            };
+
+            // The cache file is only written and read when enabled
+            cache.save_library_data(data()).await.unwrap();
+            let saved = cache.store_manager().metadata().await.unwrap().valid;
+            assert_eq!(saved, file_enabled);
+            cache.store_manager().save_data(data()).await.unwrap();
+            let loaded = cache.load_library(&config.library_path, 0).await.unwrap();
+            assert_eq!(loaded.is_some(), file_enabled);
+
+            // The LRU only stores entries when enabled
+            cache.set_sorted_titles("key".to_string(), "user1", vec!["id1".to_string()]);
+            assert_eq!(cache.get_sorted_titles("key").is_some(), memory_enabled);
+            assert_eq!(cache.stats().entry_count, usize::from(memory_enabled));
+        }
+    }
+
     #[test]
     fn test_sorted_titles_cache() {
         let config = create_test_config();
@@ -262,7 +356,7 @@ mod tests {
         assert!(cache.get_sorted_titles("key1").is_none());
 
         // Cache hit after set
-        cache.set_sorted_titles("key1".to_string(), title_ids.clone());
+        cache.set_sorted_titles("key1".to_string(), "user1", title_ids.clone());
         assert_eq!(cache.get_sorted_titles("key1"), Some(title_ids));
     }
 
@@ -277,50 +371,47 @@ mod tests {
         assert!(cache.get_sorted_entries("key1").is_none());
 
         // Cache hit after set
-        cache.set_sorted_entries("key1".to_string(), entry_ids.clone());
+        cache.set_sorted_entries("key1".to_string(), "title1", "user1", entry_ids.clone());
         assert_eq!(cache.get_sorted_entries("key1"), Some(entry_ids));
     }
 
+    fn titles_key(username: &str) -> String {
+        key::sorted_titles_key(username, &["t1".to_string()], "name", true)
+    }
+
+    fn entries_key(title_id: &str, username: &str) -> String {
+        key::sorted_entries_key(title_id, username, &["e1".to_string()], "name", true, None)
+    }
+
     #[test]
     fn test_invalidate_progress() {
         let config = create_test_config();
         let mut cache = Cache::new(&config);
 
-        // Set up some cached data with proper key format
-        cache.set_sorted_titles(
-            "sorted_titles:user1:abc123:name:true".to_string(),
-            vec!["t1".to_string()],
-        );
+        // Set up some cached data under real (hashed) keys
+        let ids = vec!["x".to_string()];
+        cache.set_sorted_titles(titles_key("user1"), "user1", ids.clone());
+        cache.set_sorted_titles(titles_key("user2"), "user2", ids.clone());
         cache.set_sorted_entries(
-            "sorted_entries:title1:user1:abc123:name:true".to_string(),
-            vec!["e1".to_string()],
-        );
-        cache.set_sorted_titles(
-            "progress_sum:title1:user1:abc123".to_string(),
-            vec!["100".to_string()],
+            entries_key("title1", "user1"),
+            "title1",
+            "user1",
+            ids.clone(),
         );
-
-        // Verify cached
-        assert!(cache
-            .get_sorted_titles("sorted_titles:user1:abc123:name:true")
-            .is_some());
-        assert!(cache
-            .get_sorted_entries("sorted_entries:title1:user1:abc123:name:true")
-            .is_some());
+        cache.set_sorted_entries(entries_key("title2", "user1"), "title2", "user1", ids);
 
         // Invalidate progress for title1, user1
         cache.invalidate_progress("title1", "user1");
 
-        // All related caches should be invalidated
-        assert!(cache
-            .get_sorted_titles("sorted_titles:user1:abc123:name:true")
-            .is_none());
+        // All related caches should be invalidated, other users and titles kept
+        assert!(cache.get_sorted_titles(&titles_key("user1")).is_none());
         assert!(cache
-            .get_sorted_entries("sorted_entries:title1:user1:abc123:name:true")
+            .get_sorted_entries(&entries_key("title1", "user1"))
             .is_none());
+        assert!(cache.get_sorted_titles(&titles_key("user2")).is_some());
         assert!(cache
-            .get_sorted_titles("progress_sum:title1:user1:abc123")
-            .is_none());
+            .get_sorted_entries(&entries_key("title2", "user1"))
+            .is_some());
     }
 
     #[test]
@@ -329,33 +420,71 @@ mod tests {
         let mut cache = Cache::new(&config);
 
         // Set up cached data for a title
+        let ids = vec!["e1".to_string()];
         cache.set_sorted_entries(
-            "sorted_entries:title1:user1:abc:name:true".to_string(),
-            vec!["e1".to_string()],
+            entries_key("title1", "user1"),
+            "title1",
+            "user1",
+            ids.clone(),
         );
         cache.set_sorted_entries(
-            "sorted_entries:title1:user2:def:name:true".to_string(),
-            vec!["e2".to_string()],
+            entries_key("title1", "user2"),
+            "title1",
+            "user2",
+            ids.clone(),
         );
-
-        // Verify cached
-        assert!(cache
-            .get_sorted_entries("sorted_entries:title1:user1:abc:name:true")
-            .is_some());
-        assert!(cache
-            .get_sorted_entries("sorted_entries:title1:user2:def:name:true")
-            .is_some());
+        cache.set_sorted_titles(titles_key("user1"), "user1", ids);
 
         // Invalidate all caches for title1
         cache.invalidate_sorted_for_title("title1");
 
-        // All entries for title1 should be invalidated
+        // All entries for title1 should be invalidated, title sorts kept
+        assert!(cache
+            .get_sorted_entries(&entries_key("title1", "user1"))
+            .is_none());
         assert!(cache
-            .get_sorted_entries("sorted_entries:title1:user1:abc:name:true")
+            .get_sorted_entries(&entries_key("title1", "user2"))
             .is_none());
+        assert!(cache.get_sorted_titles(&titles_key("user1")).is_some());
+    }
+
+    #[test]
+    fn test_invalidate_matching_user() {
+        let config = create_test_config();
+        let mut cache = Cache::new(&config);
+
+        let ids = vec!["x".to_string()];
+        cache.set_sorted_titles(titles_key("user1"), "user1", ids.clone());
+        cache.set_sorted_entries(
+            entries_key("title1", "user1"),
+            "title1",
+            "user1",
+            ids.clone(),
+        );
+        cache.set_sorted_titles(titles_key("user2"), "user2", ids.clone());
+        cache.set_sorted_entries(entries_key("title1", "user2"), "title1", "user2", ids);
+
+        let filter = CacheFilter::parse("user=user1").unwrap();
+        let mut removed = cache.invalidate_matching(&filter);
+        removed.sort_by_key(|s| s.kind.as_str());
+        assert_eq!(
+            removed,
+            vec![
+                CacheScope::sorted_entries("title1", "user1"),
+                CacheScope::sorted_titles("user1"),
+            ]
+        );
+
+        // Only user1's sorted lists are gone
+        assert!(cache.get_sorted_titles(&titles_key("user1")).is_none());
         assert!(cache
-            .get_sorted_entries("sorted_entries:title1:user2:def:name:true")
+            .get_sorted_entries(&entries_key("title1", "user1"))
             .is_none());
+        assert!(cache.get_sorted_titles(&titles_key("user2")).is_some());
+        assert!(cache
+            .get_sorted_entries(&entries_key("title1", "user2"))
+            .is_some());
+        assert_eq!(cache.stats().entry_count, 2);
     }
 
     #[test]
@@ -363,8 +492,13 @@ mod tests {
         let config = create_test_config();
         let mut cache = Cache::new(&config);
 
-        cache.set_sorted_titles("key1".to_string(), vec!["t1".to_string()]);
-        cache.set_sorted_entries("key2".to_string(), vec!["e1".to_string()]);
+        cache.set_sorted_titles("key1".to_string(), "user1", vec!["t1".to_string()]);
+        cache.set_sorted_entries(
+            "key2".to_string(),
+            "title1",
+            "user1",
+            vec!["e1".to_string()],
+        );
 
         assert_eq!(cache.stats().entry_count, 2);
 
@@ -386,7 +520,7 @@ mod tests {
         assert_eq!(stats_before.miss_count, 0);
 
         // Add entry
-        cache.set_sorted_titles("key1".to_string(), vec!["t1".to_string()]);
+        cache.set_sorted_titles("key1".to_string(), "user1", vec!["t1".to_string()]);
 
         // Hit
         let _ = cache.get_sorted_titles("key1");
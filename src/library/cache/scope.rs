@@ -0,0 +1,187 @@
+// Cache Scopes - what a cached value is about, kept beside its hashed key
+//
+// Keys are SHA256 digests (see key.rs), so nothing about a user or a title can be
+// read back from them. Each entry also records its kind and the user and title it
+// belongs to; invalidation filters on these instead of on key prefixes.
+
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+
+/// Kind of cached value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheKind {
+    SortedTitles,
+    SortedEntries,
+    ProgressSum,
+    InfoJson,
+}
+
+impl CacheKind {
+    const ALL: [CacheKind; 4] = [
+        CacheKind::SortedTitles,
+        CacheKind::SortedEntries,
+        CacheKind::ProgressSum,
+        CacheKind::InfoJson,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheKind::SortedTitles => "sorted_titles",
+            CacheKind::SortedEntries => "sorted_entries",
+            CacheKind::ProgressSum => "progress_sum",
+            CacheKind::InfoJson => "info_json",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == s)
+    }
+}
+
+/// Kind, user and title of a cache entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CacheScope {
+    pub kind: CacheKind,
+    #[serde(rename = "user")]
+    pub username: Option<String>,
+    #[serde(rename = "title")]
+    pub title_id: Option<String>,
+}
+
+impl CacheScope {
+    /// A user's sorted title list
+    pub fn sorted_titles(username: &str) -> Self {
+        Self {
+            kind: CacheKind::SortedTitles,
+            username: Some(username.to_string()),
+            title_id: None,
+        }
+    }
+
+    /// A user's sorted entry list of a title
+    pub fn sorted_entries(title_id: &str, username: &str) -> Self {
+        Self {
+            kind: CacheKind::SortedEntries,
+            username: Some(username.to_string()),
+            title_id: Some(title_id.to_string()),
+        }
+    }
+
+    /// A user's progress sum of a title
+    pub fn progress_sum(title_id: &str, username: &str) -> Self {
+        Self {
+            kind: CacheKind::ProgressSum,
+            username: Some(username.to_string()),
+            title_id: Some(title_id.to_string()),
+        }
+    }
+}
+
+/// Which cache entries to invalidate; unset fields match anything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheFilter {
+    pub kind: Option<CacheKind>,
+    pub username: Option<String>,
+    pub title_id: Option<String>,
+}
+
+impl CacheFilter {
+    /// Parse `field=value` pairs separated by commas or spaces, e.g.
+    /// "kind=sorted_entries user=alice title=abc". Fields are kind, user and title
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let mut filter = Self::default();
+        for pair in pattern
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|p| !p.is_empty())
+        {
+            let (field, value) = pair.split_once('=').ok_or_else(|| {
+                Error::BadRequest(format!("Expected field=value, got '{}'", pair))
+            })?;
+            if value.is_empty() {
+                return Err(Error::BadRequest(format!("Empty value for '{}'", field)));
+            }
+            let slot = match field {
+                "kind" => {
+                    let kind = CacheKind::parse(value).ok_or_else(|| {
+                        Error::BadRequest(format!("Unknown cache kind '{}'", value))
+                    })?;
+                    if filter.kind.replace(kind).is_some() {
+                        return Err(Error::BadRequest("Field 'kind' given twice".to_string()));
+                    }
+                    continue;
+                }
+                "user" => &mut filter.username,
+                "title" => &mut filter.title_id,
+                _ => {
+                    return Err(Error::BadRequest(format!(
+                        "Unknown field '{}', expected kind, user or title",
+                        field
+                    )))
+                }
+            };
+            if slot.replace(value.to_string()).is_some() {
+                return Err(Error::BadRequest(format!("Field '{}' given twice", field)));
+            }
+        }
+
+        if filter == Self::default() {
+            return Err(Error::BadRequest(
+                "Filter matches every entry; clear the cache instead".to_string(),
+            ));
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, scope: &CacheScope) -> bool {
+        (self.kind.is_none() || self.kind == Some(scope.kind))
+            && (self.username.is_none() || self.username == scope.username)
+            && (self.title_id.is_none() || self.title_id == scope.title_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filter() {
+        let filter = CacheFilter::parse("kind=sorted_entries, user=user1 title=t1").unwrap();
+        assert_eq!(
+            filter,
+            CacheFilter {
+                kind: Some(CacheKind::SortedEntries),
+                username: Some("user1".to_string()),
+                title_id: Some("t1".to_string()),
+            }
+        );
+
+        for bad in [
+            "",
+            "sorted_titles:user1:",
+            "owner=user1",
+            "kind=thumbnails",
+            "user=",
+            "user=a user=b",
+        ] {
+            assert!(
+                CacheFilter::parse(bad).is_err(),
+                "'{}' should be rejected",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let filter = CacheFilter::parse("user=user1").unwrap();
+        assert!(filter.matches(&CacheScope::sorted_titles("user1")));
+        assert!(filter.matches(&CacheScope::sorted_entries("t1", "user1")));
+        assert!(!filter.matches(&CacheScope::sorted_titles("user2")));
+
+        let filter = CacheFilter::parse("title=t1").unwrap();
+        assert!(filter.matches(&CacheScope::progress_sum("t1", "user2")));
+        assert!(!filter.matches(&CacheScope::sorted_titles("user1")));
+    }
+}
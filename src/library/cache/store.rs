@@ -0,0 +1,879 @@
+// Cache Store Manager - persistent library cache, one shard per title
+//
+// The library is saved to a directory next to `library_cache_path` (the path with `.d`
+// appended). Each top-level title is a gzipped MessagePack shard named by the SHA-256 of
+// the serialized title, and a manifest maps title IDs to their shards. A save only writes
+// the shards of titles that changed since they were last written (a new contents signature,
+// new entries, anything stored for the title), so saving after a rescan of a large library
+// rewrites a few shards instead of the whole cache, which spares SD cards.
+//
+// The manifest is written last. A save that is cut short leaves the previous manifest and
+// its shards as they were, and shards no manifest names are never read; the next save
+// removes them. A cache file of the old single-file format is converted on first load.
+
+use crate::error::{Error, Result};
+use crate::library::Title;
+use crate::Library;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Version of the cache store layout (manifest and gzipped MessagePack shards of `Title`)
+/// Bump when the serialized types change; reported by `/api/version`
+pub const CACHE_FORMAT_VERSION: u32 = 7;
+
+const MANIFEST_NAME: &str = "manifest.msgpack.gz";
+const SHARD_SUFFIX: &str = ".msgpack.gz";
+
+/// Metadata about the cache store
+#[derive(Debug, Clone)]
+pub struct CacheStoreMetadata {
+    /// The store directory
+    pub path: PathBuf,
+    /// Manifest and shards together
+    pub size_bytes: u64,
+    /// When the manifest was last written
+    pub modified: SystemTime,
+    pub shards: usize,
+    pub valid: bool,
+}
+
+/// Manager for the library cache store
+#[derive(Clone)]
+pub struct CacheStoreManager {
+    /// `library_cache_path`, where the old single-file cache was
+    legacy_path: PathBuf,
+    store_dir: PathBuf,
+}
+
+/// Serializable library data (excludes database Storage)
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CachedLibraryData {
+    pub path: PathBuf,
+    pub titles: HashMap<String, Title>,
+}
+
+impl CachedLibraryData {
+    /// Number of titles, nested ones included (the database has a row for each)
+    pub fn title_count(&self) -> usize {
+        self.titles.values().map(|t| t.deep_titles().len()).sum()
+    }
+}
+
+/// Which shards make up the saved library
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    version: u32,
+    path: PathBuf,
+    /// Title ID -> digest of its shard
+    titles: HashMap<String, String>,
+}
+
+/// What a save wrote
+#[derive(Debug, Default)]
+struct SaveStats {
+    shards_written: usize,
+    shards_kept: usize,
+    shards_removed: usize,
+    /// Manifest included
+    bytes_written: u64,
+}
+
+impl CacheStoreManager {
+    /// Create a store manager for the store of `cache_path` (config `library_cache_path`)
+    pub fn new(cache_path: PathBuf) -> Self {
+        let mut store_dir = cache_path.clone().into_os_string();
+        store_dir.push(".d");
+        Self {
+            legacy_path: cache_path,
+            store_dir: store_dir.into(),
+        }
+    }
+
+    /// Save library to the cache store
+    pub async fn save(&self, library: &Library) -> Result<()> {
+        let cached_data = CachedLibraryData {
+            path: library.path().to_path_buf(),
+            titles: library.titles().clone(),
+        };
+        self.save_data(cached_data).await
+    }
+
+    /// Save cached library data, writing only the shards of changed titles
+    pub async fn save_data(&self, cached_data: CachedLibraryData) -> Result<()> {
+        let stats = self.write_store(cached_data).await?;
+        tracing::info!(
+            "Library cache saved: {} ({} of {} titles written, {} stale shards removed, {} bytes written)",
+            self.store_dir.display(),
+            stats.shards_written,
+            stats.shards_written + stats.shards_kept,
+            stats.shards_removed,
+            stats.bytes_written
+        );
+        Ok(())
+    }
+
+    async fn write_store(&self, cached_data: CachedLibraryData) -> Result<SaveStats> {
+        let manager = self.clone();
+        tokio::task::spawn_blocking(move || manager.write_store_blocking(&cached_data))
+            .await
+            .map_err(|e| Error::Internal(format!("Task join error: {}", e)))?
+    }
+
+    fn write_store_blocking(&self, cached_data: &CachedLibraryData) -> Result<SaveStats> {
+        std::fs::create_dir_all(&self.store_dir)?;
+        let mut stats = SaveStats::default();
+        let mut manifest = Manifest {
+            version: CACHE_FORMAT_VERSION,
+            path: cached_data.path.clone(),
+            titles: HashMap::with_capacity(cached_data.titles.len()),
+        };
+
+        for (id, title) in &cached_data.titles {
+            let serialized =
+                rmp_serde::to_vec(title).map_err(|e| Error::CacheSerialization(e.to_string()))?;
+            let digest = format!("{:x}", Sha256::digest(&serialized));
+            // Shards are named by their contents, so an existing one is complete and current
+            let shard_path = self.shard_path(&digest);
+            if shard_path.exists() {
+                stats.shards_kept += 1;
+            } else {
+                stats.bytes_written += write_atomic(&shard_path, &gzip(&serialized)?)?;
+                stats.shards_written += 1;
+            }
+            manifest.titles.insert(id.clone(), digest);
+        }
+
+        // Until the manifest is renamed into place the previous one stays valid
+        let serialized =
+            rmp_serde::to_vec(&manifest).map_err(|e| Error::CacheSerialization(e.to_string()))?;
+        stats.bytes_written += write_atomic(&self.manifest_path(), &gzip(&serialized)?)?;
+
+        // Shards of removed and changed titles, and leftovers of an interrupted save
+        let current: HashSet<String> = manifest
+            .titles
+            .values()
+            .map(|digest| shard_file_name(digest.as_str()))
+            .collect();
+        for file in std::fs::read_dir(&self.store_dir)? {
+            let file = file?;
+            let name = file.file_name().to_string_lossy().into_owned();
+            if name == MANIFEST_NAME || current.contains(&name) {
+                continue;
+            }
+            if std::fs::remove_file(file.path()).is_ok() && name.ends_with(SHARD_SUFFIX) {
+                stats.shards_removed += 1;
+            }
+        }
+
+        // The store replaces the old single-file cache
+        if self.legacy_path.is_file() {
+            std::fs::remove_file(&self.legacy_path)?;
+        }
+
+        Ok(stats)
+    }
+
+    /// Load library from the cache store, converting an old cache file first
+    /// An invalid store (another library directory, a missing or damaged shard) is deleted
+    pub async fn load(&self, expected_dir: &Path) -> Result<Option<CachedLibraryData>> {
+        let manager = self.clone();
+        let expected = expected_dir.to_path_buf();
+
+        if !self.manifest_path().exists() {
+            if !self.legacy_path.is_file() {
+                tracing::debug!("Library cache does not exist: {}", self.store_dir.display());
+                return Ok(None);
+            }
+            let migrated = tokio::task::spawn_blocking(move || manager.migrate(&expected))
+                .await
+                .map_err(|e| Error::Internal(format!("Task join error: {}", e)))?;
+            return match migrated {
+                Ok(cached_data) => Ok(Some(cached_data)),
+                Err(reason) => {
+                    tracing::warn!(
+                        "Old library cache file {} is invalid ({}), deleting it",
+                        self.legacy_path.display(),
+                        reason
+                    );
+                    let _ = tokio::fs::remove_file(&self.legacy_path).await;
+                    Ok(None)
+                }
+            };
+        }
+
+        let loaded = tokio::task::spawn_blocking(move || manager.read_store(&expected))
+            .await
+            .map_err(|e| Error::Internal(format!("Task join error: {}", e)))?;
+        match loaded {
+            Ok(cached_data) => {
+                tracing::info!(
+                    "Library cache loaded: {} titles from {}",
+                    cached_data.titles.len(),
+                    self.store_dir.display()
+                );
+                Ok(Some(cached_data))
+            }
+            Err(reason) => {
+                tracing::warn!(
+                    "Library cache {} is invalid ({}), deleting it",
+                    self.store_dir.display(),
+                    reason
+                );
+                let _ = self.delete().await;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Read the manifest and then every shard it names, spread over the available cores
+    fn read_store(&self, expected_dir: &Path) -> std::result::Result<CachedLibraryData, String> {
+        let manifest: Manifest = read_gzipped(&self.manifest_path())
+            .and_then(|serialized| rmp_serde::from_slice(&serialized).map_err(|e| e.to_string()))
+            .map_err(|e| format!("manifest: {}", e))?;
+        if manifest.version != CACHE_FORMAT_VERSION {
+            return Err(format!(
+                "format version {}, expected {}",
+                manifest.version, CACHE_FORMAT_VERSION
+            ));
+        }
+        if manifest.path != expected_dir {
+            return Err(format!(
+                "saved for {}, expected {}",
+                manifest.path.display(),
+                expected_dir.display()
+            ));
+        }
+
+        let shards: Vec<(String, String)> = manifest.titles.into_iter().collect();
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = shards.len().div_ceil(workers).max(1);
+        let titles = std::thread::scope(|scope| {
+            let readers: Vec<_> = shards
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|(id, digest)| self.read_shard(id, digest))
+                            .collect::<std::result::Result<Vec<_>, String>>()
+                    })
+                })
+                .collect();
+            readers
+                .into_iter()
+                .map(|reader| {
+                    reader
+                        .join()
+                        .unwrap_or_else(|_| Err("shard reader panicked".to_string()))
+                })
+                .collect::<std::result::Result<Vec<_>, String>>()
+        })?;
+
+        Ok(CachedLibraryData {
+            path: manifest.path,
+            titles: titles
+                .into_iter()
+                .flatten()
+                .map(|title| (title.id.clone(), title))
+                .collect(),
+        })
+    }
+
+    fn read_shard(&self, id: &str, digest: &str) -> std::result::Result<Title, String> {
+        let serialized = read_gzipped(&self.shard_path(digest))
+            .map_err(|e| format!("shard of title {}: {}", id, e))?;
+        if format!("{:x}", Sha256::digest(&serialized)) != digest {
+            return Err(format!("shard of title {} does not match the manifest", id));
+        }
+        let title: Title = rmp_serde::from_slice(&serialized)
+            .map_err(|e| format!("shard of title {}: {}", id, e))?;
+        if title.id != id {
+            return Err(format!("shard of title {} holds title {}", id, title.id));
+        }
+        Ok(title)
+    }
+
+    /// Convert the old single-file cache into the store
+    /// The library is returned even if writing the store fails; the old file is kept then
+    fn migrate(&self, expected_dir: &Path) -> std::result::Result<CachedLibraryData, String> {
+        let cached_data: CachedLibraryData = read_gzipped(&self.legacy_path)
+            .and_then(|serialized| rmp_serde::from_slice(&serialized).map_err(|e| e.to_string()))?;
+        if cached_data.path != expected_dir {
+            return Err(format!(
+                "saved for {}, expected {}",
+                cached_data.path.display(),
+                expected_dir.display()
+            ));
+        }
+
+        match self.write_store_blocking(&cached_data) {
+            Ok(stats) => tracing::info!(
+                "Converted library cache file {} into {} ({} titles, {} bytes written)",
+                self.legacy_path.display(),
+                self.store_dir.display(),
+                stats.shards_written + stats.shards_kept,
+                stats.bytes_written
+            ),
+            Err(e) => tracing::warn!(
+                "Failed to convert library cache file {}: {}",
+                self.legacy_path.display(),
+                e
+            ),
+        }
+        Ok(cached_data)
+    }
+
+    /// Validate the cache store against current configuration
+    pub async fn validate(&self, library: &Library, db_title_count: usize) -> Result<()> {
+        // Load cache to validate
+        let cached_data = match self.load(library.path()).await? {
+            Some(data) => data,
+            None => {
+                return Err(Error::CacheCorrupted(
+                    "Cache store does not exist or is invalid".to_string(),
+                ))
+            }
+        };
+
+        // Validate title count matches database
+        if cached_data.title_count() != db_title_count {
+            return Err(Error::CacheCorrupted(format!(
+                "Title count mismatch: cache has {}, database has {}",
+                cached_data.title_count(),
+                db_title_count
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Delete the cache store, and an old cache file if there is one
+    pub async fn delete(&self) -> Result<()> {
+        if self.store_dir.exists() {
+            tokio::fs::remove_dir_all(&self.store_dir).await?;
+            tracing::info!("Cache store deleted: {}", self.store_dir.display());
+        }
+        if self.legacy_path.is_file() {
+            tokio::fs::remove_file(&self.legacy_path).await?;
+        }
+        Ok(())
+    }
+
+    /// Get cache store metadata
+    pub async fn metadata(&self) -> Result<CacheStoreMetadata> {
+        let manifest = match tokio::fs::metadata(self.manifest_path()).await {
+            Ok(manifest) => manifest,
+            Err(_) => {
+                return Ok(CacheStoreMetadata {
+                    path: self.store_dir.clone(),
+                    size_bytes: 0,
+                    modified: SystemTime::now(),
+                    shards: 0,
+                    valid: false,
+                })
+            }
+        };
+
+        let mut size_bytes = 0;
+        let mut shards = 0;
+        let mut files = tokio::fs::read_dir(&self.store_dir).await?;
+        while let Some(file) = files.next_entry().await? {
+            size_bytes += file.metadata().await?.len();
+            let name = file.file_name().to_string_lossy().into_owned();
+            if name != MANIFEST_NAME && name.ends_with(SHARD_SUFFIX) {
+                shards += 1;
+            }
+        }
+
+        Ok(CacheStoreMetadata {
+            path: self.store_dir.clone(),
+            size_bytes,
+            modified: manifest.modified()?,
+            shards,
+            valid: true,
+        })
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.store_dir.join(MANIFEST_NAME)
+    }
+
+    fn shard_path(&self, digest: &str) -> PathBuf {
+        self.store_dir.join(shard_file_name(digest))
+    }
+}
+
+fn shard_file_name(digest: &str) -> String {
+    format!("{}{}", digest, SHARD_SUFFIX)
+}
+
+fn gzip(data: &[u8]) -> Result<Vec<u8>> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .map_err(|e| Error::CacheSerialization(e.to_string()))?;
+    encoder
+        .finish()
+        .map_err(|e| Error::CacheSerialization(e.to_string()))
+}
+
+/// Read and decompress a file of the store
+fn read_gzipped(path: &Path) -> std::result::Result<Vec<u8>, String> {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    let compressed = std::fs::read(path).map_err(|e| e.to_string())?;
+    let mut serialized = Vec::new();
+    GzDecoder::new(&compressed[..])
+        .read_to_end(&mut serialized)
+        .map_err(|e| e.to_string())?;
+    Ok(serialized)
+}
+
+/// Write a file through a temp file and a rename, so it is either complete or absent
+/// Returns the bytes written
+fn write_atomic(path: &Path, data: &[u8]) -> Result<u64> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+    std::fs::write(&temp_path, data)?;
+
+    // Set file permissions to 0600 (owner read/write only)
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = std::fs::Permissions::from_mode(0o600);
+        std::fs::set_permissions(&temp_path, perms)?;
+    }
+
+    std::fs::rename(&temp_path, path)?;
+    Ok(data.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Storage;
+    use tempfile::TempDir;
+
+    async fn create_test_library(path: PathBuf) -> Library {
+        // Create a test storage
+        let temp_db = tempfile::NamedTempFile::new().unwrap();
+        let db_path = temp_db.path().to_str().unwrap();
+        let storage = Storage::new(db_path).await.unwrap();
+
+        // Create test config for cache initialization
+        let config = crate::Config {
+            host: "0.0.0.0".to_string(),
+            port: 9000,
+            base_url: "/".to_string(),
+            session_secret: "test".to_string(),
+            library_path: path.clone(),
+            db_path: PathBuf::from(db_path),
+            queue_db_path: PathBuf::from("/tmp/test_queue.db"),
+            scan_interval_minutes: 0,
+            thumbnail_generation_interval_hours: 0,
+            log_level: "info".to_string(),
+            upload_path: PathBuf::from("/tmp/uploads"),
+            plugin_path: PathBuf::from("/tmp/plugins"),
+            download_timeout_seconds: 30,
+            library_cache_path: PathBuf::from("/tmp/test_cache.bin"),
+            cache_enabled: None,
+            library_cache_file_enabled: true,
+            memory_cache_enabled: true,
+            cache_size_mbs: 100,
+            cache_log_enabled: false,
+            disable_login: false,
+            default_username: None,
+            auth_proxy_header_name: None,
+            plugin_update_interval_hours: 24,
+            download_quota_mbs: 0,
+            archive_cache_entries: 0,
+            archive_cache_size_mbs: 0,
+            db_slow_query_ms: 500,
+            db_slow_acquire_ms: 100,
+            validate_pages: false,
+            instance_name: "Mango".to_string(),
+            accent_color: None,
+            logo_path: None,
+            default_reading_direction: crate::library::ReadingDirection::Vertical,
+            date_added_from_mtime: false,
+            two_factor_basic_auth: crate::config::TwoFactorBasicAuth::Bypass,
+            thumbnail_prune_grace_days: 30,
+            cover_file_names: Vec::new(),
+            loose_archives: crate::config::LooseArchives::default(),
+            unsorted_title_name: "Unsorted".to_string(),
+            scan_skip_hidden: true,
+            scan_exclude_patterns: Vec::new(),
+            library_case_sensitive: None,
+            scan_workers: 20,
+            entry_title_cleanup: true,
+            entry_title_strip_patterns: Vec::new(),
+            komga_compat_enabled: false,
+            library_page_size: 0,
+            library_max_page_size: 500,
+            library_card_density: crate::config::CardDensity::default(),
+            static_path: None,
+            convert_keep_originals: true,
+            watch_library: false,
+            admin_can_view_user_progress: true,
+            cors_allowed_origins: Vec::new(),
+            tag_unlock_minutes: 30,
+        };
+
+        // Create library with test data
+        // Add some test titles (empty for now, but structure is in place)
+        Library::new(path, storage, &config)
+    }
+
+    fn store_dir(cache_path: &Path) -> PathBuf {
+        CacheStoreManager::new(cache_path.to_path_buf()).store_dir
+    }
+
+    fn title(id: &str, contents_signature: &str) -> Title {
+        Title {
+            id: id.to_string(),
+            path: PathBuf::from("/library").join(id),
+            title: id.to_string(),
+            signature: String::new(),
+            contents_signature: contents_signature.to_string(),
+            mtime: 0,
+            entries: Vec::new(),
+            parent_id: None,
+            nested_titles: Vec::new(),
+            cover_path: None,
+            source: crate::library::TitleSource::Directory,
+            unreadable: Vec::new(),
+            broken_entries: Vec::new(),
+        }
+    }
+
+    fn library_data(titles: Vec<Title>) -> CachedLibraryData {
+        CachedLibraryData {
+            path: PathBuf::from("/library"),
+            titles: titles.into_iter().map(|t| (t.id.clone(), t)).collect(),
+        }
+    }
+
+    fn shard_files(cache_path: &Path) -> Vec<String> {
+        std::fs::read_dir(store_dir(cache_path))
+            .unwrap()
+            .map(|f| f.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name != MANIFEST_NAME)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_save_load_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let library_path = temp_dir.path().join("library");
+        let cache_path = temp_dir.path().join("cache.bin");
+
+        // Create test library
+        let library = create_test_library(library_path.clone()).await;
+
+        // Save to cache
+        let manager = CacheStoreManager::new(cache_path.clone());
+        manager.save(&library).await.unwrap();
+
+        // Verify cache file exists
+        assert!(
+            store_dir(&cache_path).join(MANIFEST_NAME).exists(),
+            "Cache manifest should be created"
+        );
+
+        // Load from cache
+        let loaded = manager.load(&library_path).await.unwrap();
+        assert!(loaded.is_some(), "Should load cached data");
+
+        let loaded_data = loaded.unwrap();
+        assert_eq!(loaded_data.path, library_path);
+        assert_eq!(loaded_data.titles.len(), library.titles().len());
+    }
+
+    #[tokio::test]
+    async fn test_load_nonexistent_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("nonexistent.bin");
+        let library_path = temp_dir.path().join("library");
+
+        let manager = CacheStoreManager::new(cache_path);
+        let result = manager.load(&library_path).await.unwrap();
+
+        assert!(result.is_none(), "Should return None for nonexistent cache");
+    }
+
+    #[tokio::test]
+    async fn test_directory_mismatch_invalidates_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let library_path1 = temp_dir.path().join("library1");
+        let library_path2 = temp_dir.path().join("library2");
+        let cache_path = temp_dir.path().join("cache.bin");
+
+        // Create and save library with path1
+        let library = create_test_library(library_path1).await;
+        let manager = CacheStoreManager::new(cache_path.clone());
+        manager.save(&library).await.unwrap();
+
+        // Try to load with path2 (different directory)
+        let result = manager.load(&library_path2).await.unwrap();
+        assert!(
+            result.is_none(),
+            "Should invalidate cache for directory mismatch"
+        );
+
+        // Cache store should be deleted
+        assert!(
+            !store_dir(&cache_path).exists(),
+            "Invalid cache should be deleted"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_file_handling() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.bin");
+        let library_path = temp_dir.path().join("library");
+
+        // Write corrupt data
+        tokio::fs::write(&cache_path, b"corrupt data")
+            .await
+            .unwrap();
+
+        // Try to load corrupt cache
+        let manager = CacheStoreManager::new(cache_path.clone());
+        let result = manager.load(&library_path).await.unwrap();
+
+        assert!(result.is_none(), "Should return None for corrupt cache");
+        assert!(!cache_path.exists(), "Corrupt cache should be deleted");
+    }
+
+    #[tokio::test]
+    async fn test_delete_operation() {
+        let temp_dir = TempDir::new().unwrap();
+        let library_path = temp_dir.path().join("library");
+        let cache_path = temp_dir.path().join("cache.bin");
+
+        // Create and save library
+        let library = create_test_library(library_path).await;
+        let manager = CacheStoreManager::new(cache_path.clone());
+        manager.save(&library).await.unwrap();
+
+        assert!(store_dir(&cache_path).exists(), "Cache store should exist");
+
+        // Delete cache
+        manager.delete().await.unwrap();
+        assert!(
+            !store_dir(&cache_path).exists(),
+            "Cache store should be deleted"
+        );
+
+        // Delete again should not error
+        manager.delete().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_metadata_extraction() {
+        let temp_dir = TempDir::new().unwrap();
+        let library_path = temp_dir.path().join("library");
+        let cache_path = temp_dir.path().join("cache.bin");
+
+        let manager = CacheStoreManager::new(cache_path.clone());
+
+        // Metadata for nonexistent file
+        let meta = manager.metadata().await.unwrap();
+        assert!(!meta.valid);
+        assert_eq!(meta.size_bytes, 0);
+
+        // Create cache store
+        let library = create_test_library(library_path).await;
+        manager.save(&library).await.unwrap();
+
+        // Metadata for existing file
+        let meta = manager.metadata().await.unwrap();
+        assert!(meta.valid);
+        assert!(meta.size_bytes > 0);
+        assert_eq!(meta.path, store_dir(&cache_path));
+    }
+
+    #[tokio::test]
+    async fn test_atomic_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let library_path = temp_dir.path().join("library");
+        let cache_path = temp_dir.path().join("cache.bin");
+
+        let library = create_test_library(library_path).await;
+        let manager = CacheStoreManager::new(cache_path.clone());
+
+        // Save should use atomic write (temp file + rename)
+        manager.save(&library).await.unwrap();
+
+        // Temp files should not exist
+        let files: Vec<String> = std::fs::read_dir(store_dir(&cache_path))
+            .unwrap()
+            .map(|f| f.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(
+            !files.iter().any(|name| name.ends_with(".tmp")),
+            "Temp files should not exist after save"
+        );
+
+        // Final manifest should exist
+        assert!(files.contains(&MANIFEST_NAME.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_validation_success() {
+        let temp_dir = TempDir::new().unwrap();
+        let library_path = temp_dir.path().join("library");
+        let cache_path = temp_dir.path().join("cache.bin");
+
+        let library = create_test_library(library_path).await;
+        let manager = CacheStoreManager::new(cache_path);
+
+        // Save library
+        manager.save(&library).await.unwrap();
+
+        // Validate should succeed (title count matches)
+        let db_title_count = library.titles().len();
+        manager.validate(&library, db_title_count).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_validation_title_count_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+        let library_path = temp_dir.path().join("library");
+        let cache_path = temp_dir.path().join("cache.bin");
+
+        let library = create_test_library(library_path).await;
+        let manager = CacheStoreManager::new(cache_path);
+
+        // Save library
+        manager.save(&library).await.unwrap();
+
+        // Validate with wrong title count should fail
+        let wrong_count = library.titles().len() + 10;
+        let result = manager.validate(&library, wrong_count).await;
+
+        assert!(result.is_err(), "Should error on title count mismatch");
+    }
+
+    #[tokio::test]
+    async fn test_only_changed_shards_written() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.bin");
+        let manager = CacheStoreManager::new(cache_path.clone());
+        let titles = |changed: &str| vec![title("a", changed), title("b", "b1"), title("c", "c1")];
+
+        let first = manager
+            .write_store(library_data(titles("a1")))
+            .await
+            .unwrap();
+        assert_eq!(first.shards_written, 3);
+        assert_eq!(shard_files(&cache_path).len(), 3);
+
+        // Saving an unchanged library only rewrites the manifest
+        let unchanged = manager
+            .write_store(library_data(titles("a1")))
+            .await
+            .unwrap();
+        assert_eq!((unchanged.shards_written, unchanged.shards_kept), (0, 3));
+        assert!(unchanged.bytes_written < first.bytes_written);
+
+        // One changed title rewrites its shard and removes the one it replaces
+        let changed = manager
+            .write_store(library_data(titles("a2")))
+            .await
+            .unwrap();
+        assert_eq!((changed.shards_written, changed.shards_kept), (1, 2));
+        assert_eq!(changed.shards_removed, 1);
+        assert!(changed.bytes_written < first.bytes_written);
+
+        // A removed title's shard is deleted
+        let removed = manager
+            .write_store(library_data(vec![title("a", "a2"), title("b", "b1")]))
+            .await
+            .unwrap();
+        assert_eq!((removed.shards_written, removed.shards_removed), (0, 1));
+        assert_eq!(shard_files(&cache_path).len(), 2);
+
+        let loaded = manager.load(Path::new("/library")).await.unwrap().unwrap();
+        assert_eq!(loaded.titles.len(), 2);
+        assert_eq!(loaded.titles["a"].contents_signature, "a2");
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_save_keeps_previous_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.bin");
+        let manager = CacheStoreManager::new(cache_path.clone());
+        let library = Path::new("/library");
+        manager
+            .save_data(library_data(vec![title("a", "a1"), title("b", "b1")]))
+            .await
+            .unwrap();
+
+        // A save cut short before the manifest: a new shard and a temp file are left behind
+        let serialized = rmp_serde::to_vec(&title("a", "a2")).unwrap();
+        let digest = format!("{:x}", Sha256::digest(&serialized));
+        write_atomic(&manager.shard_path(&digest), &gzip(&serialized).unwrap()).unwrap();
+        std::fs::write(manager.store_dir.join("partial.msgpack.gz.tmp"), b"half").unwrap();
+
+        // The previous manifest and its shards still load; the new shard is ignored
+        let loaded = manager.load(library).await.unwrap().unwrap();
+        assert_eq!(loaded.titles["a"].contents_signature, "a1");
+        assert_eq!(shard_files(&cache_path).len(), 4);
+
+        // The next save cleans up what the interrupted one left
+        manager.save_data(loaded).await.unwrap();
+        assert_eq!(shard_files(&cache_path).len(), 2);
+        assert!(!manager.shard_path(&digest).exists());
+    }
+
+    #[tokio::test]
+    async fn test_missing_shard_invalidates_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.bin");
+        let manager = CacheStoreManager::new(cache_path.clone());
+        manager
+            .save_data(library_data(vec![title("a", "a1"), title("b", "b1")]))
+            .await
+            .unwrap();
+
+        let shard = shard_files(&cache_path).remove(0);
+        std::fs::remove_file(store_dir(&cache_path).join(shard)).unwrap();
+
+        assert!(manager.load(Path::new("/library")).await.unwrap().is_none());
+        assert!(
+            !store_dir(&cache_path).exists(),
+            "Invalid cache should be deleted"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_single_file_cache_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.bin");
+        let manager = CacheStoreManager::new(cache_path.clone());
+
+        // The old format: the whole library in one gzipped MessagePack file
+        let serialized =
+            rmp_serde::to_vec(&library_data(vec![title("a", "a1"), title("b", "b1")])).unwrap();
+        std::fs::write(&cache_path, gzip(&serialized).unwrap()).unwrap();
+
+        let loaded = manager.load(Path::new("/library")).await.unwrap().unwrap();
+        assert_eq!(loaded.titles.len(), 2);
+        assert!(!cache_path.exists(), "Old cache file should be removed");
+        assert_eq!(shard_files(&cache_path).len(), 2);
+
+        // Later loads read the store
+        let loaded = manager.load(Path::new("/library")).await.unwrap().unwrap();
+        assert_eq!(loaded.titles["b"].contents_signature, "b1");
+    }
+}
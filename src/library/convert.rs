@@ -0,0 +1,870 @@
+// Entry conversion - repacks image directories and RAR/7z archives as CBZ
+//
+// Each entry goes through the same steps. Its pages are written in reading order,
+// uncompressed, to `<name>.cbz.part` next to the original, and the part is read back: it
+// must list the same pages. The entry is then marked `swapping`, the part renamed to the
+// CBZ, the original renamed to `.bak` or deleted (config `convert_keep_originals`), and
+// the entry's `ids` row moved to the CBZ under the same ID, so progress, tags and
+// thumbnails stay with it. The swap goes by what is on disk, so an entry left half-swapped
+// by a crash is finished by the next run.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::entry::{Entry, EntrySource};
+use super::manager::Library;
+use crate::error::{Error, Result};
+use crate::util::ArchiveKind;
+use crate::Storage;
+
+/// Whether the app's conversion job is running, shared by the job and the status endpoint
+#[derive(Clone, Default)]
+pub struct Conversions {
+    running: Arc<AtomicBool>,
+}
+
+impl Conversions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+}
+
+/// Which entries a conversion job picks up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConvertFilter {
+    /// Directories of images
+    Directories,
+    /// RAR and 7z archives
+    Archives,
+    /// Both
+    #[default]
+    All,
+}
+
+impl ConvertFilter {
+    fn directories(self) -> bool {
+        matches!(self, Self::Directories | Self::All)
+    }
+
+    fn archives(self) -> bool {
+        matches!(self, Self::Archives | Self::All)
+    }
+}
+
+/// Where an entry's conversion stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConversionStatus {
+    /// Nothing written yet
+    Pending,
+    /// The CBZ is verified; the files or the ID row are still to be swapped
+    Swapping,
+    Converted,
+    /// The original is untouched; `error` says why
+    Failed,
+}
+
+impl ConversionStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Swapping => "swapping",
+            Self::Converted => "converted",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "swapping" => Self::Swapping,
+            "converted" => Self::Converted,
+            "failed" => Self::Failed,
+            _ => Self::Pending,
+        }
+    }
+}
+
+/// An entry queued for conversion
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Conversion {
+    pub entry_id: String,
+    pub title_id: String,
+    /// Directory or archive converted, relative to the library root
+    pub source_path: String,
+    /// CBZ replacing it, relative to the library root
+    pub target_path: String,
+    /// Bytes of the source
+    pub size: u64,
+    pub status: ConversionStatus,
+    pub error: Option<String>,
+}
+
+/// Per-entry results of the current or last conversion job
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConversionReport {
+    pub running: bool,
+    /// Entries not finished yet (pending or swapping)
+    pub pending: usize,
+    pub converted: usize,
+    pub failed: usize,
+    pub entries: Vec<Conversion>,
+}
+
+/// A running conversion job; scans are held off while it moves files
+pub struct ConversionJob {
+    _no_scans: tokio::sync::OwnedRwLockWriteGuard<()>,
+    conversions: Conversions,
+}
+
+impl ConversionJob {
+    /// Claim the job of `conversions`, failing with Conflict while it is running or a scan
+    /// holding `scan_lock` is
+    pub fn start(conversions: &Conversions, scan_lock: &super::ScanLock) -> Result<Self> {
+        if conversions
+            .running
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(Error::Conflict(
+                "A conversion job is already running".to_string(),
+            ));
+        }
+        match scan_lock.block_scans() {
+            Ok(no_scans) => Ok(Self {
+                _no_scans: no_scans,
+                conversions: conversions.clone(),
+            }),
+            Err(e) => {
+                conversions.running.store(false, Ordering::SeqCst);
+                Err(e)
+            }
+        }
+    }
+
+    /// Convert every unfinished entry in the queue, those of interrupted jobs included
+    /// Entries that fail are recorded and skipped; the library needs a rescan afterwards
+    /// to pick up the new archives
+    pub async fn run(
+        self,
+        library: &Library,
+        storage: &Storage,
+        keep_originals: bool,
+    ) -> Result<ConversionReport> {
+        let queued = load(storage, "WHERE status IN ('pending', 'swapping')").await?;
+        tracing::info!("Converting {} entries to CBZ", queued.len());
+
+        for conversion in &queued {
+            match convert(library, storage, conversion, keep_originals).await {
+                Ok(()) => tracing::info!(
+                    "Converted {} to {}",
+                    conversion.source_path,
+                    conversion.target_path
+                ),
+                Err(e) => {
+                    tracing::warn!("Failed to convert {}: {}", conversion.source_path, e)
+                }
+            }
+        }
+
+        let conversions = self.conversions.clone();
+        drop(self);
+        report(storage, &conversions).await
+    }
+}
+
+impl Drop for ConversionJob {
+    fn drop(&mut self) {
+        self.conversions.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Queue the entries `filter` picks in one title (nested titles included) or in the
+/// whole library. Rows converted by earlier jobs are cleared and failed ones retried;
+/// merged entries are left out. Returns how many entries were queued
+pub async fn queue(
+    library: &Library,
+    storage: &Storage,
+    title_id: Option<&str>,
+    filter: ConvertFilter,
+) -> Result<usize> {
+    let titles = match title_id {
+        Some(id) => library
+            .get_title(id)
+            .ok_or_else(|| Error::NotFound(format!("Title not found: {}", id)))?
+            .deep_titles(),
+        None => library.all_titles().collect(),
+    };
+    let entries: Vec<(String, Entry)> = titles
+        .into_iter()
+        .flat_map(|t| t.entries.iter().map(|e| (t.id.clone(), e.clone())))
+        .filter(|(_, e)| e.parts.is_empty())
+        .collect();
+
+    // Archive kinds come from the file headers
+    let root = library.path().to_path_buf();
+    let candidates = blocking(move || {
+        let mut candidates = Vec::new();
+        for (title_id, entry) in entries {
+            let (picked, size, target) = match entry.source {
+                EntrySource::Directory => {
+                    let size = entry
+                        .image_files
+                        .iter()
+                        .filter_map(|name| std::fs::metadata(entry.path.join(name)).ok())
+                        .map(|m| m.len())
+                        .sum();
+                    let target = sibling(&entry.path, ".cbz");
+                    (filter.directories(), size, target)
+                }
+                EntrySource::Archive => {
+                    let legacy = matches!(
+                        ArchiveKind::detect(&entry.path),
+                        ArchiveKind::Rar | ArchiveKind::SevenZip
+                    );
+                    let size = std::fs::metadata(&entry.path).map_or(0, |m| m.len());
+                    (
+                        filter.archives() && legacy,
+                        size,
+                        entry.path.with_extension("cbz"),
+                    )
+                }
+            };
+            if picked {
+                candidates.push(Conversion {
+                    entry_id: entry.id,
+                    title_id,
+                    source_path: crate::util::relative_to_root(&entry.path, &root),
+                    target_path: crate::util::relative_to_root(&target, &root),
+                    size,
+                    status: ConversionStatus::Pending,
+                    error: None,
+                });
+            }
+        }
+        Ok(candidates)
+    })
+    .await?;
+
+    let now = crate::util::time::now_ts();
+    let mut tx = storage.pool().begin().await?;
+    sqlx::query("DELETE FROM conversions WHERE status = 'converted'")
+        .execute(&mut *tx)
+        .await?;
+    for conversion in &candidates {
+        sqlx::query(
+            "INSERT INTO conversions
+             (entry_id, title_id, source_path, target_path, size, status, updated_at)
+             VALUES (?, ?, ?, ?, ?, 'pending', ?)
+             ON CONFLICT(entry_id) DO UPDATE SET source_path = excluded.source_path,
+             target_path = excluded.target_path, size = excluded.size, status = 'pending',
+             error = NULL, updated_at = excluded.updated_at
+             WHERE conversions.status = 'failed'",
+        )
+        .bind(&conversion.entry_id)
+        .bind(&conversion.title_id)
+        .bind(&conversion.source_path)
+        .bind(&conversion.target_path)
+        .bind(conversion.size as i64)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(candidates.len())
+}
+
+/// Fail with Conflict when the disk holding the library has less room than the largest
+/// pending entry, the most a single conversion needs at once
+pub async fn check_free_space(storage: &Storage, library_root: &Path) -> Result<()> {
+    let largest: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(size) FROM conversions WHERE status = 'pending'")
+            .fetch_one(storage.pool())
+            .await?;
+    let Some(largest) = largest else {
+        return Ok(());
+    };
+
+    let root = library_root.to_path_buf();
+    let available = blocking(move || Ok(fs2::available_space(&root)?)).await?;
+    ensure_free_space(available, largest.max(0) as u64)
+}
+
+fn ensure_free_space(available: u64, needed: u64) -> Result<()> {
+    if available < needed {
+        return Err(Error::Conflict(format!(
+            "Not enough free disk space to convert: {} bytes free, the largest entry needs {}",
+            available, needed
+        )));
+    }
+    Ok(())
+}
+
+/// Per-entry results of the current or last job
+pub async fn report(storage: &Storage, conversions: &Conversions) -> Result<ConversionReport> {
+    let entries = load(storage, "").await?;
+    let count = |status: ConversionStatus| entries.iter().filter(|c| c.status == status).count();
+    Ok(ConversionReport {
+        running: conversions.is_running(),
+        pending: count(ConversionStatus::Pending) + count(ConversionStatus::Swapping),
+        converted: count(ConversionStatus::Converted),
+        failed: count(ConversionStatus::Failed),
+        entries,
+    })
+}
+
+async fn load(storage: &Storage, filter: &str) -> Result<Vec<Conversion>> {
+    #[allow(clippy::type_complexity)]
+    let rows: Vec<(String, String, String, String, i64, String, Option<String>)> =
+        sqlx::query_as(&format!(
+            "SELECT entry_id, title_id, source_path, target_path, size, status, error
+             FROM conversions {} ORDER BY source_path",
+            filter
+        ))
+        .fetch_all(storage.pool())
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(entry_id, title_id, source_path, target_path, size, status, error)| Conversion {
+                entry_id,
+                title_id,
+                source_path,
+                target_path,
+                size: size.max(0) as u64,
+                status: ConversionStatus::parse(&status),
+                error,
+            },
+        )
+        .collect())
+}
+
+async fn set_status(
+    storage: &Storage,
+    entry_id: &str,
+    status: ConversionStatus,
+    error: Option<&str>,
+) -> Result<()> {
+    sqlx::query("UPDATE conversions SET status = ?, error = ?, updated_at = ? WHERE entry_id = ?")
+        .bind(status.as_str())
+        .bind(error)
+        .bind(crate::util::time::now_ts())
+        .bind(entry_id)
+        .execute(storage.pool())
+        .await?;
+    Ok(())
+}
+
+/// Convert one queued entry, or finish its swap if an earlier run got that far
+async fn convert(
+    library: &Library,
+    storage: &Storage,
+    conversion: &Conversion,
+    keep_originals: bool,
+) -> Result<()> {
+    let source = library.path().join(&conversion.source_path);
+    let target = library.path().join(&conversion.target_path);
+    let part = sibling(&target, ".part");
+
+    if conversion.status == ConversionStatus::Pending {
+        if let Err(e) = pack(library, conversion, &source, &target, keep_originals).await {
+            let _ = tokio::fs::remove_file(&part).await;
+            let error = e.to_string();
+            set_status(
+                storage,
+                &conversion.entry_id,
+                ConversionStatus::Failed,
+                Some(&error),
+            )
+            .await?;
+            return Err(e);
+        }
+        set_status(
+            storage,
+            &conversion.entry_id,
+            ConversionStatus::Swapping,
+            None,
+        )
+        .await?;
+    }
+
+    // Failures from here on keep the entry swapping, so the next run retries the swap
+    let swapped = async {
+        swap_files(&source, &part, &target, keep_originals).await?;
+        move_id(storage, library.path(), conversion).await
+    }
+    .await;
+    if let Err(e) = &swapped {
+        let error = e.to_string();
+        set_status(
+            storage,
+            &conversion.entry_id,
+            ConversionStatus::Swapping,
+            Some(&error),
+        )
+        .await?;
+    }
+    swapped
+}
+
+/// Write the entry's pages to the part file and check that it reads back the same
+async fn pack(
+    library: &Library,
+    conversion: &Conversion,
+    source: &Path,
+    target: &Path,
+    keep_originals: bool,
+) -> Result<()> {
+    let (_, entry) = library
+        .find_entry(&conversion.entry_id)
+        .ok_or_else(|| Error::NotFound("The entry is no longer in the library".to_string()))?;
+    if entry.path != source {
+        return Err(Error::Conflict(
+            "The entry moved since it was queued".to_string(),
+        ));
+    }
+    if tokio::fs::try_exists(target).await? {
+        return Err(Error::Conflict(format!(
+            "{} already exists",
+            conversion.target_path
+        )));
+    }
+    if keep_originals && tokio::fs::try_exists(&backup_path(source)).await? {
+        return Err(Error::Conflict(format!(
+            "{}.bak already exists",
+            conversion.source_path
+        )));
+    }
+
+    let part = sibling(target, ".part");
+    write_cbz(entry, &part).await?;
+    verify_cbz(&part, entry).await
+}
+
+/// Write an entry's pages, in reading order and under their own names, to a stored ZIP
+/// Pages are read one at a time, so only one is held in memory
+async fn write_cbz(entry: &Entry, path: &Path) -> Result<()> {
+    let file = tokio::fs::File::create(path).await?.into_std().await;
+    let mut writer = CbzWriter::new(std::io::BufWriter::new(file));
+    for (page, name) in entry.image_files.iter().enumerate() {
+        let data = entry.get_page(page).await?;
+        let name = name.clone();
+        writer = blocking(move || {
+            writer.add(&name, &data)?;
+            Ok(writer)
+        })
+        .await?;
+    }
+    blocking(move || writer.finish()).await
+}
+
+/// Open a written archive again; it must list the entry's pages in the same order
+async fn verify_cbz(path: &Path, entry: &Entry) -> Result<()> {
+    let images = super::entry::extract_image_list(path).await?;
+    if images.len() != entry.pages {
+        return Err(Error::Internal(format!(
+            "The converted archive has {} pages instead of {}",
+            images.len(),
+            entry.pages
+        )));
+    }
+    if images != entry.image_files {
+        return Err(Error::Internal(
+            "The converted archive lists the pages in another order".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Put the part file in place of the source. Each step checks the disk first, so
+/// running it again after an interruption finishes what is left
+async fn swap_files(source: &Path, part: &Path, target: &Path, keep_originals: bool) -> Result<()> {
+    if !tokio::fs::try_exists(target).await? {
+        if !tokio::fs::try_exists(part).await? {
+            return Err(Error::Internal(format!(
+                "Neither {} nor its part file exists",
+                target.display()
+            )));
+        }
+        tokio::fs::rename(part, target).await?;
+    }
+
+    let metadata = match tokio::fs::metadata(source).await {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    if keep_originals {
+        tokio::fs::rename(source, backup_path(source)).await?;
+    } else if metadata.is_dir() {
+        // Only the pages; anything else in the directory stays
+        for name in super::entry::list_directory_images(source).await? {
+            tokio::fs::remove_file(source.join(name)).await?;
+        }
+        if let Err(e) = tokio::fs::remove_dir(source).await {
+            tracing::info!("Keeping {}: {}", source.display(), e);
+        }
+    } else {
+        tokio::fs::remove_file(source).await?;
+    }
+    Ok(())
+}
+
+/// Point the entry's ID row at the CBZ and mark it converted, in one transaction
+async fn move_id(storage: &Storage, library_root: &Path, conversion: &Conversion) -> Result<()> {
+    let target = library_root.join(&conversion.target_path);
+    let signature = blocking(move || crate::util::file_signature(&target)).await?;
+
+    let mut tx = storage.pool().begin().await?;
+    // A row left behind by an earlier file of the same name would block the path
+    sqlx::query("DELETE FROM ids WHERE path = ? AND id != ? AND unavailable = 1")
+        .bind(&conversion.target_path)
+        .bind(&conversion.entry_id)
+        .execute(&mut *tx)
+        .await?;
+    let moved = sqlx::query(
+        "UPDATE ids SET path = ?, signature = ?, unavailable = 0, unavailable_since = NULL
+         WHERE id = ?",
+    )
+    .bind(&conversion.target_path)
+    .bind(&signature)
+    .bind(&conversion.entry_id)
+    .execute(&mut *tx)
+    .await?;
+    if moved.rows_affected() == 0 {
+        return Err(Error::NotFound(format!(
+            "No ID row for entry {}",
+            conversion.entry_id
+        )));
+    }
+    sqlx::query(
+        "UPDATE conversions SET status = 'converted', error = NULL, updated_at = ?
+         WHERE entry_id = ?",
+    )
+    .bind(crate::util::time::now_ts())
+    .bind(&conversion.entry_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// `path` with `suffix` appended to its file name
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Where a kept original goes; scans skip directories named like this
+fn backup_path(source: &Path) -> PathBuf {
+    sibling(source, ".bak")
+}
+
+/// Whether a directory is an original kept by a conversion
+pub(super) fn is_backup_dir(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "bak")
+}
+
+async fn blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::Internal(format!("Task join error: {}", e)))?
+}
+
+/// Writes an uncompressed (stored) ZIP archive one file at a time
+/// Without ZIP64, so archives are limited to 4 GiB and 65535 files
+struct CbzWriter<W: Write> {
+    out: W,
+    /// Bytes written so far
+    offset: u64,
+    central: Vec<u8>,
+    files: usize,
+}
+
+impl<W: Write> CbzWriter<W> {
+    fn new(out: W) -> Self {
+        Self {
+            out,
+            offset: 0,
+            central: Vec::new(),
+            files: 0,
+        }
+    }
+
+    fn add(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        let offset = u32::try_from(self.offset)
+            .ok()
+            .filter(|_| self.files < u16::MAX as usize)
+            .ok_or_else(|| {
+                Error::BadRequest("The entry is too large for a CBZ archive".to_string())
+            })?;
+        let size = u32::try_from(data.len()).map_err(|_| {
+            Error::BadRequest(format!("Page {} is too large for a CBZ archive", name))
+        })?;
+        let crc = crc32fast::hash(data);
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        header.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        header.extend_from_slice(&0x0800u16.to_le_bytes()); // UTF-8 names
+        header.extend_from_slice(&[0; 6]); // method (stored), time, date
+        header.extend_from_slice(&crc.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // extra length
+        header.extend_from_slice(name.as_bytes());
+        self.out.write_all(&header)?;
+        self.out.write_all(data)?;
+        self.offset += (header.len() + data.len()) as u64;
+
+        self.central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        self.central.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        self.central.extend_from_slice(&0x0800u16.to_le_bytes()); // UTF-8 names
+        self.central.extend_from_slice(&[0; 6]); // method, time, date
+        self.central.extend_from_slice(&crc.to_le_bytes());
+        self.central.extend_from_slice(&size.to_le_bytes());
+        self.central.extend_from_slice(&size.to_le_bytes());
+        self.central
+            .extend_from_slice(&(name.len() as u16).to_le_bytes());
+        self.central.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+        self.central.extend_from_slice(&offset.to_le_bytes());
+        self.central.extend_from_slice(name.as_bytes());
+        self.files += 1;
+        Ok(())
+    }
+
+    /// Write the central directory and flush
+    fn finish(mut self) -> Result<()> {
+        let central_offset = u32::try_from(self.offset).map_err(|_| {
+            Error::BadRequest("The entry is too large for a CBZ archive".to_string())
+        })?;
+        self.out.write_all(&self.central)?;
+
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        end.extend_from_slice(&[0; 4]); // disk numbers
+        end.extend_from_slice(&(self.files as u16).to_le_bytes());
+        end.extend_from_slice(&(self.files as u16).to_le_bytes());
+        end.extend_from_slice(&(self.central.len() as u32).to_le_bytes());
+        end.extend_from_slice(&central_offset.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        self.out.write_all(&end)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Library with one title holding a directory entry of three pages
+    async fn setup() -> (tempfile::TempDir, Storage, Library) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().join("library");
+        let chapter = root.join("Series").join("Chapter 1");
+        std::fs::create_dir_all(&chapter).unwrap();
+        for (name, data) in [("1.png", "one"), ("2.png", "two"), ("10.png", "ten")] {
+            std::fs::write(chapter.join(name), data).unwrap();
+        }
+
+        let database_url = format!(
+            "sqlite://{}?mode=rwc",
+            temp_dir.path().join("test.db").display()
+        );
+        let storage = Storage::new(&database_url).await.unwrap();
+        let library = scan(&root, &storage).await;
+        (temp_dir, storage, library)
+    }
+
+    async fn scan(root: &Path, storage: &Storage) -> Library {
+        let config = crate::Config::default_config();
+        let mut library = Library::new(root.to_path_buf(), storage.clone(), &config);
+        library.scan().await.unwrap();
+        library
+    }
+
+    async fn queued(storage: &Storage) -> Conversion {
+        load(storage, "").await.unwrap().remove(0)
+    }
+
+    async fn id_path(storage: &Storage, id: &str) -> String {
+        sqlx::query_scalar("SELECT path FROM ids WHERE id = ?")
+            .bind(id)
+            .fetch_one(storage.pool())
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_one_job_per_app() {
+        let (conversions, scan_lock) = (Conversions::new(), super::super::ScanLock::new());
+        let job = ConversionJob::start(&conversions, &scan_lock).unwrap();
+        assert!(conversions.is_running());
+        assert!(matches!(
+            ConversionJob::start(&conversions, &scan_lock),
+            Err(Error::Conflict(_))
+        ));
+
+        // Another app's job does not wait for this one
+        let other = Conversions::new();
+        let other_job = ConversionJob::start(&other, &super::super::ScanLock::new()).unwrap();
+        drop(other_job);
+
+        drop(job);
+        assert!(!conversions.is_running());
+        ConversionJob::start(&conversions, &scan_lock).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_directory_converted_keeping_id() {
+        let (_temp_dir, storage, library) = setup().await;
+        let root = library.path().to_path_buf();
+        let entry_id = library.get_titles()[0].entries[0].id.clone();
+
+        // Archives only: the directory is not picked up
+        let count = queue(&library, &storage, None, ConvertFilter::Archives).await;
+        assert_eq!(count.unwrap(), 0);
+        let count = queue(&library, &storage, None, ConvertFilter::Directories).await;
+        assert_eq!(count.unwrap(), 1);
+
+        let conversion = queued(&storage).await;
+        assert_eq!(conversion.source_path, "Series/Chapter 1");
+        assert_eq!(conversion.target_path, "Series/Chapter 1.cbz");
+        assert_eq!(conversion.size, 9);
+        convert(&library, &storage, &conversion, true)
+            .await
+            .unwrap();
+
+        // Pages keep their names and natural order
+        let cbz = root.join("Series/Chapter 1.cbz");
+        let images = super::super::entry::extract_image_list(&cbz).await.unwrap();
+        assert_eq!(images, ["1.png", "2.png", "10.png"]);
+        assert!(root.join("Series/Chapter 1.bak/10.png").exists());
+        assert!(!root.join("Series/Chapter 1").exists());
+        assert!(!root.join("Series/Chapter 1.cbz.part").exists());
+
+        let report = report(&storage, &Conversions::new()).await.unwrap();
+        assert_eq!((report.converted, report.pending, report.failed), (1, 0, 0));
+        assert_eq!(id_path(&storage, &entry_id).await, "Series/Chapter 1.cbz");
+
+        // A rescan finds the archive under the same ID and skips the backup
+        let library = scan(&root, &storage).await;
+        let entries = &library.get_titles()[0].entries;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, entry_id);
+        assert_eq!(entries[0].source, EntrySource::Archive);
+        assert_eq!(entries[0].get_page(2).await.unwrap(), b"ten");
+    }
+
+    #[tokio::test]
+    async fn test_failure_between_swap_and_id_update_is_finished_later() {
+        let (_temp_dir, storage, library) = setup().await;
+        let root = library.path().to_path_buf();
+        let entry_id = library.get_titles()[0].entries[0].id.clone();
+        queue(&library, &storage, None, ConvertFilter::All)
+            .await
+            .unwrap();
+
+        // Another entry's row holds the target path, so moving the ID row fails
+        sqlx::query("INSERT INTO ids (id, path, signature, unavailable) VALUES (?, ?, ?, 0)")
+            .bind("other")
+            .bind("Series/Chapter 1.cbz")
+            .bind("1")
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        let conversion = queued(&storage).await;
+        assert!(convert(&library, &storage, &conversion, false)
+            .await
+            .is_err());
+
+        // The files are swapped but the database still points at the directory
+        let conversion = queued(&storage).await;
+        assert_eq!(conversion.status, ConversionStatus::Swapping);
+        assert!(conversion.error.is_some());
+        assert!(root.join("Series/Chapter 1.cbz").exists());
+        assert!(!root.join("Series/Chapter 1").exists());
+        assert_eq!(id_path(&storage, &entry_id).await, "Series/Chapter 1");
+
+        // The next run only finishes the swap
+        sqlx::query("DELETE FROM ids WHERE id = 'other'")
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        convert(&library, &storage, &conversion, false)
+            .await
+            .unwrap();
+        let conversion = queued(&storage).await;
+        assert_eq!(conversion.status, ConversionStatus::Converted);
+        assert_eq!(conversion.error, None);
+        assert_eq!(id_path(&storage, &entry_id).await, "Series/Chapter 1.cbz");
+        assert!(!root.join("Series/Chapter 1.bak").exists());
+    }
+
+    #[tokio::test]
+    async fn test_failed_conversion_leaves_original() {
+        let (_temp_dir, storage, library) = setup().await;
+        let root = library.path().to_path_buf();
+        let entry = library.get_titles()[0].entries[0].clone();
+
+        // The written archive must list exactly the entry's pages
+        let part = root.join("check.cbz.part");
+        write_cbz(&entry, &part).await.unwrap();
+        verify_cbz(&part, &entry).await.unwrap();
+        let mut longer = entry.clone();
+        longer.image_files.push("11.png".to_string());
+        longer.pages += 1;
+        assert!(verify_cbz(&part, &longer).await.is_err());
+
+        // A file already at the target path is never replaced
+        std::fs::write(root.join("Series/Chapter 1.cbz"), b"mine").unwrap();
+        queue(&library, &storage, None, ConvertFilter::All)
+            .await
+            .unwrap();
+        let conversion = queued(&storage).await;
+        assert!(convert(&library, &storage, &conversion, true)
+            .await
+            .is_err());
+
+        let conversion = queued(&storage).await;
+        assert_eq!(conversion.status, ConversionStatus::Failed);
+        assert!(conversion.error.unwrap().contains("already exists"));
+        assert!(root.join("Series/Chapter 1/1.png").exists());
+        assert_eq!(
+            std::fs::read(root.join("Series/Chapter 1.cbz")).unwrap(),
+            b"mine"
+        );
+
+        // Queueing again retries it
+        queue(&library, &storage, None, ConvertFilter::All)
+            .await
+            .unwrap();
+        assert_eq!(queued(&storage).await.status, ConversionStatus::Pending);
+    }
+
+    #[test]
+    fn test_free_space_covers_largest_entry() {
+        assert!(ensure_free_space(100, 100).is_ok());
+        assert!(matches!(
+            ensure_free_space(99, 100),
+            Err(Error::Conflict(_))
+        ));
+    }
+}
@@ -1,7 +1,63 @@
+use std::collections::HashMap;
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use uuid::Uuid;
 
+use super::ArchiveCache;
 use crate::error::Result;
+use crate::storage::{PageSprite, SpriteFrame};
+
+/// Height of each page frame in a page sprite
+pub const SPRITE_FRAME_HEIGHT: u32 = 60;
+
+/// Widest frame allowed (keeps 500 landscape frames within JPEG's 65535px limit)
+const SPRITE_MAX_FRAME_WIDTH: u32 = 120;
+
+/// Most frames in a sprite; longer entries only get every Nth page
+pub const SPRITE_MAX_FRAMES: usize = 500;
+
+/// Pages decoded at once while building a sprite
+const SPRITE_CONCURRENCY: usize = 4;
+
+/// Thumbnail stored in the database
+#[derive(Debug, Clone)]
+pub struct StoredThumbnail {
+    pub data: Vec<u8>,
+    pub mime: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// (x offset, width) of a frame in a page sprite
+type FrameSpan = (u32, u32);
+
+/// data, mime, width and height columns of a `thumbnails` row
+type ThumbnailRow = (Vec<u8>, String, Option<i64>, Option<i64>);
+
+/// File name stored with covers uploaded by an admin
+const UPLOADED_COVER: &str = "uploaded_cover";
+
+/// Thumbnail metadata without the image data (for API listings)
+#[derive(Debug, Clone)]
+pub struct ThumbnailMeta {
+    pub mime: String,
+    pub size: usize,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Uploaded by an admin rather than made from the entry's first page
+    pub uploaded: bool,
+}
+
+/// What an entry's pages are read from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum EntrySource {
+    /// An archive file; `path` is the archive
+    #[default]
+    Archive,
+    /// Loose image files; `path` is the directory holding them
+    Directory,
+}
 
 /// Represents a single readable entry (chapter/volume)
 /// Can be a ZIP/CBZ archive or a directory containing images
@@ -13,9 +69,14 @@ pub struct Entry {
     /// Absolute path to the archive file or directory
     pub path: PathBuf,
 
-    /// Display name (filename without extension)
+    /// Name of the archive (filename without extension), used for sorting and matching
     pub title: String,
 
+    /// Name shown to users: `title` without release-group tags (config `entry_title_cleanup`)
+    /// Set for the title as a whole, see `Title::refresh_display_titles`
+    #[serde(default)]
+    pub display_title: String,
+
     /// File signature (inode on Unix, CRC32 on Windows) - stored as TEXT for Mango compatibility
     pub signature: String,
 
@@ -27,6 +88,15 @@ pub struct Entry {
 
     /// List of image filenames (sorted)
     pub image_files: Vec<String>,
+
+    /// An archive or a directory of images
+    #[serde(default)]
+    pub source: EntrySource,
+
+    /// Archives of a merged entry, in reading order (empty for a plain entry)
+    /// Pages are served from these; `path` is the first part's
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<Entry>,
 }
 
 impl Entry {
@@ -52,31 +122,221 @@ impl Entry {
         Ok(Self {
             id: Uuid::new_v4().to_string(),
             path,
+            display_title: title.clone(),
             title,
             signature: String::new(), // Will be set later
             mtime,
             pages,
             image_files,
+            source: EntrySource::Archive,
+            parts: Vec::new(),
         })
     }
 
-    /// Get page image data from archive
+    /// Create a new Entry from a directory of image files
+    /// Only images lying directly in the directory are pages
+    pub async fn from_directory(path: PathBuf) -> Result<Self> {
+        let title = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Unknown")
+            .to_string();
+
+        let metadata = tokio::fs::metadata(&path).await?;
+        let mtime = metadata
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let image_files = list_directory_images(&path).await?;
+        let pages = image_files.len();
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            path,
+            display_title: title.clone(),
+            title,
+            signature: String::new(), // Will be set later
+            mtime,
+            pages,
+            image_files,
+            source: EntrySource::Directory,
+            parts: Vec::new(),
+        })
+    }
+
+    /// Path of the archive relative to the library root, safe to show to users
+    /// (the first part's for a merged entry)
+    pub fn relative_path(&self, library_root: &Path) -> String {
+        crate::util::relative_to_root(&self.path, library_root)
+    }
+
+    /// The archive entry holding a page (0-indexed) and the page's index within it
+    /// That is the entry itself unless it is merged from parts
+    pub fn page_source(&self, page: usize) -> Result<(&Entry, usize)> {
+        if self.parts.is_empty() {
+            return Ok((self, page));
+        }
+
+        super::merge::MergedEntry::from_parts(&self.parts)
+            .locate(page)
+            .map(|(part, inner)| (&self.parts[part], inner))
+            .ok_or_else(|| {
+                crate::error::Error::NotFound(format!(
+                    "Page {} out of range (0-{})",
+                    page,
+                    self.pages.saturating_sub(1)
+                ))
+            })
+    }
+
+    /// Get page image data from the archive, or the image file of a directory entry
     pub async fn get_page(&self, page: usize) -> Result<Vec<u8>> {
-        if page >= self.pages {
-            return Err(crate::error::Error::NotFound(format!(
-                "Page {} out of range (0-{})",
-                page,
-                self.pages - 1
-            )));
+        let (entry, page) = self.page_source(page)?;
+        let image_name = entry.image_name(page)?;
+        match entry.source {
+            EntrySource::Archive => extract_image_from_archive(&entry.path, image_name).await,
+            EntrySource::Directory => Ok(tokio::fs::read(entry.path.join(image_name)).await?),
+        }
+    }
+
+    /// Check every page and return the one-based numbers of pages that fail to decode
+    /// Reads the whole archive, so only used when page validation is enabled
+    pub async fn find_bad_pages(&self, archives: &ArchiveCache) -> Vec<usize> {
+        let mut bad_pages = Vec::new();
+
+        for page_idx in 0..self.pages {
+            let ok = match archives.peek_page(self, page_idx).await {
+                Ok(data) => crate::util::is_decodable_image(&data),
+                Err(e) => {
+                    tracing::debug!("Failed to read page {} of {}: {}", page_idx, self.title, e);
+                    false
+                }
+            };
+
+            if !ok {
+                bad_pages.push(page_idx + 1);
+            }
+        }
+
+        bad_pages
+    }
+
+    /// Build a sprite of small page frames (concatenated horizontally) for the reader
+    /// Pages are decoded a few at a time; dropping the returned future (e.g. when the
+    /// client disconnects) aborts the pending decodes. Entries longer than
+    /// SPRITE_MAX_FRAMES only get a frame for every Nth page.
+    /// Returns None if no page could be decoded
+    pub async fn generate_page_sprite(
+        &self,
+        archives: &ArchiveCache,
+    ) -> Result<Option<PageSprite>> {
+        if self.pages == 0 {
+            return Ok(None);
         }
 
-        let image_name = &self.image_files[page];
-        extract_image_from_archive(&self.path, image_name).await
+        let step = self.pages.div_ceil(SPRITE_MAX_FRAMES);
+        let sampled: Vec<usize> = (0..self.pages).step_by(step).collect();
+        let mut frames: Vec<Option<image::RgbImage>> = vec![None; sampled.len()];
+
+        let entry = Arc::new(self.clone());
+        let mut pending = sampled.iter().copied().enumerate();
+        let mut tasks = tokio::task::JoinSet::new();
+
+        loop {
+            while tasks.len() < SPRITE_CONCURRENCY {
+                let Some((slot, page_idx)) = pending.next() else {
+                    break;
+                };
+                let entry = entry.clone();
+                let archives = archives.clone();
+                tasks.spawn(async move { (slot, entry.sprite_frame(&archives, page_idx).await) });
+            }
+
+            let Some(joined) = tasks.join_next().await else {
+                break;
+            };
+            let (slot, frame) = joined
+                .map_err(|e| crate::error::Error::Internal(format!("Task join error: {}", e)))?;
+            frames[slot] = frame;
+        }
+
+        if frames.iter().all(Option::is_none) {
+            tracing::warn!(
+                "No page of {} could be decoded for its page sprite",
+                self.title
+            );
+            return Ok(None);
+        }
+
+        let (data, offsets) = tokio::task::spawn_blocking(move || compose_sprite(frames))
+            .await
+            .map_err(|e| crate::error::Error::Internal(format!("Task join error: {}", e)))??;
+
+        let frames = sampled
+            .iter()
+            .zip(offsets)
+            .map(|(page_idx, (x, width))| SpriteFrame {
+                page: page_idx + 1,
+                x,
+                width,
+            })
+            .collect();
+
+        Ok(Some(PageSprite {
+            entry_id: self.id.clone(),
+            signature: self.signature.clone(),
+            data,
+            mime: "image/jpeg".to_string(),
+            height: SPRITE_FRAME_HEIGHT,
+            step,
+            frames,
+        }))
+    }
+
+    /// Decode a page (0-indexed) and shrink it to a sprite frame
+    /// Returns None if the page cannot be read or decoded
+    async fn sprite_frame(&self, archives: &ArchiveCache, page: usize) -> Option<image::RgbImage> {
+        let data = match archives.peek_page(self, page).await {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::debug!("Failed to read page {} of {}: {}", page, self.title, e);
+                return None;
+            }
+        };
+
+        tokio::task::spawn_blocking(move || {
+            let img = image::load_from_memory(&data).ok()?;
+            let frame = img.thumbnail(SPRITE_MAX_FRAME_WIDTH, SPRITE_FRAME_HEIGHT);
+            Some(frame.to_rgb8())
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+
+    /// Get the archive member name of a page (0-indexed)
+    pub fn image_name(&self, page: usize) -> Result<&str> {
+        self.image_files
+            .get(page)
+            .map(|s| s.as_str())
+            .ok_or_else(|| {
+                crate::error::Error::NotFound(format!(
+                    "Page {} out of range (0-{})",
+                    page,
+                    self.pages.saturating_sub(1)
+                ))
+            })
     }
 
     /// Generate file signature for change detection
+    /// A directory entry's covers the directory and its image files
     pub fn calculate_signature(&mut self) -> Result<()> {
-        self.signature = crate::util::file_signature(&self.path)?;
+        self.signature = match self.source {
+            EntrySource::Archive => crate::util::file_signature(&self.path)?,
+            EntrySource::Directory => crate::util::dir_signature(&self.path)?,
+        };
         Ok(())
     }
 
@@ -85,9 +345,10 @@ impl Entry {
     pub async fn generate_thumbnail(
         &self,
         db: &sqlx::SqlitePool,
+        archives: &ArchiveCache,
     ) -> Result<Option<(Vec<u8>, String, usize)>> {
-        // Get first page
-        let page_data = match self.get_page(0).await {
+        // Get first page (from an already open archive if possible)
+        let page_data = match archives.peek_page(self, 0).await {
             Ok(data) => data,
             Err(e) => {
                 tracing::warn!(
@@ -112,30 +373,17 @@ impl Entry {
             }
         };
 
-        // Resize based on aspect ratio (matching original Mango logic)
-        let (width, height) = (img.width(), img.height());
-        let thumbnail = if height > width {
-            // Portrait: resize to width 200
-            img.resize(200, u32::MAX, image::imageops::FilterType::Lanczos3)
-        } else {
-            // Landscape: resize to height 300
-            img.resize(u32::MAX, 300, image::imageops::FilterType::Lanczos3)
-        };
-
-        // Encode to JPEG
-        let mut buffer = Vec::new();
-        let mut cursor = std::io::Cursor::new(&mut buffer);
-
-        match thumbnail.write_to(&mut cursor, image::ImageFormat::Jpeg) {
-            Ok(_) => {}
+        let (buffer, thumb_width, thumb_height) = match encode_thumbnail(&img) {
+            Ok(thumbnail) => thumbnail,
             Err(e) => {
                 tracing::warn!("Failed to encode thumbnail for {}: {}", self.title, e);
                 return Ok(None);
             }
-        }
+        };
 
         let size = buffer.len() as i64;
         let mime = "image/jpeg".to_string();
+        let (thumb_width, thumb_height) = (thumb_width as i64, thumb_height as i64);
 
         // Get filename from first image
         let filename = self
@@ -146,14 +394,17 @@ impl Entry {
             .to_string();
 
         // Save to database
-        sqlx::query!(
-            "INSERT OR REPLACE INTO thumbnails (id, data, filename, mime, size) VALUES (?, ?, ?, ?, ?)",
-            self.id,
-            buffer,
-            filename,
-            mime,
-            size
+        sqlx::query(
+            "INSERT OR REPLACE INTO thumbnails (id, data, filename, mime, size, width, height)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
         )
+        .bind(&self.id)
+        .bind(&buffer)
+        .bind(&filename)
+        .bind(&mime)
+        .bind(size)
+        .bind(thumb_width)
+        .bind(thumb_height)
         .execute(db)
         .await?;
 
@@ -161,15 +412,90 @@ impl Entry {
     }
 
     /// Get thumbnail from database
+    /// Legacy thumbnails without stored dimensions get them backfilled here
     pub async fn get_thumbnail(
         entry_id: &str,
         db: &sqlx::SqlitePool,
-    ) -> Result<Option<(Vec<u8>, String)>> {
-        let result = sqlx::query!("SELECT data, mime FROM thumbnails WHERE id = ?", entry_id)
-            .fetch_optional(db)
-            .await?;
+    ) -> Result<Option<StoredThumbnail>> {
+        let row: Option<ThumbnailRow> =
+            sqlx::query_as("SELECT data, mime, width, height FROM thumbnails WHERE id = ?")
+                .bind(entry_id)
+                .fetch_optional(db)
+                .await?;
+
+        let Some((data, mime, width, height)) = row else {
+            return Ok(None);
+        };
+
+        let (width, height) = match (width, height) {
+            (Some(w), Some(h)) => (Some(w as u32), Some(h as u32)),
+            _ => match crate::util::get_image_dimensions(&data) {
+                Some((w, h)) => {
+                    if let Err(e) =
+                        sqlx::query("UPDATE thumbnails SET width = ?, height = ? WHERE id = ?")
+                            .bind(w as i64)
+                            .bind(h as i64)
+                            .bind(entry_id)
+                            .execute(db)
+                            .await
+                    {
+                        tracing::warn!(
+                            "Failed to backfill thumbnail dimensions for {}: {}",
+                            entry_id,
+                            e
+                        );
+                    }
+                    (Some(w), Some(h))
+                }
+                None => (None, None),
+            },
+        };
 
-        Ok(result.map(|row| (row.data, row.mime)))
+        Ok(Some(StoredThumbnail {
+            data,
+            mime,
+            width,
+            height,
+        }))
+    }
+
+    /// Get thumbnail metadata (no image data) for a set of entries
+    /// Entries without a thumbnail are absent from the returned map
+    pub async fn get_thumbnail_meta(
+        entry_ids: &[&str],
+        db: &sqlx::SqlitePool,
+    ) -> Result<HashMap<String, ThumbnailMeta>> {
+        let mut meta = HashMap::with_capacity(entry_ids.len());
+
+        // Stay well below SQLite's bound parameter limit
+        for chunk in entry_ids.chunks(500) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!(
+                "SELECT id, mime, size, width, height, filename FROM thumbnails WHERE id IN ({})",
+                placeholders
+            );
+
+            let mut query =
+                sqlx::query_as::<_, (String, String, i64, Option<i64>, Option<i64>, String)>(&sql);
+            for id in chunk {
+                query = query.bind(*id);
+            }
+
+            for (id, mime, size, width, height, filename) in query.fetch_all(db).await? {
+                meta.insert(
+                    id,
+                    ThumbnailMeta {
+                        mime,
+                        size: size as usize,
+                        width: width.map(|w| w as u32),
+                        height: height.map(|h| h as u32),
+                        uploaded: filename == UPLOADED_COVER,
+                    },
+                );
+            }
+        }
+
+        Ok(meta)
     }
 
     /// Save custom thumbnail to database (for uploaded covers)
@@ -180,15 +506,23 @@ impl Entry {
         db: &sqlx::SqlitePool,
     ) -> Result<()> {
         let size = data.len() as i64;
+        let (width, height) = match crate::util::get_image_dimensions(data) {
+            Some((w, h)) => (Some(w as i64), Some(h as i64)),
+            None => (None, None),
+        };
 
         // Insert or replace thumbnail
-        sqlx::query!(
-            "INSERT OR REPLACE INTO thumbnails (id, data, mime, size) VALUES (?, ?, ?, ?)",
-            entry_id,
-            data,
-            mime,
-            size
+        sqlx::query(
+            "INSERT OR REPLACE INTO thumbnails (id, data, filename, mime, size, width, height)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
         )
+        .bind(entry_id)
+        .bind(data)
+        .bind(UPLOADED_COVER)
+        .bind(mime)
+        .bind(size)
+        .bind(width)
+        .bind(height)
         .execute(db)
         .await?;
 
@@ -196,32 +530,170 @@ impl Entry {
     }
 }
 
+/// Resize an image to cover thumbnail size and encode it as JPEG
+/// Returns the JPEG data with its width and height
+pub fn encode_thumbnail(img: &image::DynamicImage) -> image::ImageResult<(Vec<u8>, u32, u32)> {
+    // Resize based on aspect ratio (matching original Mango logic)
+    let (width, height) = (img.width(), img.height());
+    let thumbnail = if height > width {
+        // Portrait: resize to width 200
+        img.resize(200, u32::MAX, image::imageops::FilterType::Lanczos3)
+    } else {
+        // Landscape: resize to height 300
+        img.resize(u32::MAX, 300, image::imageops::FilterType::Lanczos3)
+    };
+
+    // Encode to JPEG, which has no alpha channel
+    let thumbnail = thumbnail.to_rgb8();
+    let mut buffer = Vec::new();
+    thumbnail.write_to(
+        &mut std::io::Cursor::new(&mut buffer),
+        image::ImageFormat::Jpeg,
+    )?;
+    Ok((buffer, thumbnail.width(), thumbnail.height()))
+}
+
+/// Concatenate sprite frames horizontally and encode the result as JPEG
+/// Pages that failed to decode get a blank placeholder frame
+/// Returns the encoded sprite and each frame's (x offset, width)
+fn compose_sprite(frames: Vec<Option<image::RgbImage>>) -> Result<(Vec<u8>, Vec<FrameSpan>)> {
+    // Placeholder frames use a typical portrait page ratio
+    let blank_width = SPRITE_FRAME_HEIGHT * 7 / 10;
+
+    let mut offsets = Vec::with_capacity(frames.len());
+    let mut total_width = 0;
+    for frame in &frames {
+        let width = frame.as_ref().map_or(blank_width, |f| f.width());
+        offsets.push((total_width, width));
+        total_width += width;
+    }
+
+    let mut sprite =
+        image::RgbImage::from_pixel(total_width, SPRITE_FRAME_HEIGHT, image::Rgb([64, 64, 64]));
+    for (frame, (x, _)) in frames.iter().zip(&offsets) {
+        if let Some(frame) = frame {
+            // Frames shorter than the sprite (wide pages) are centered vertically
+            let y = (SPRITE_FRAME_HEIGHT - frame.height()) / 2;
+            image::imageops::replace(&mut sprite, frame, *x as i64, y as i64);
+        }
+    }
+
+    let mut buffer = Vec::new();
+    image::DynamicImage::ImageRgb8(sprite)
+        .write_to(&mut Cursor::new(&mut buffer), image::ImageFormat::Jpeg)
+        .map_err(|e| crate::error::Error::Internal(format!("Failed to encode sprite: {}", e)))?;
+
+    Ok((buffer, offsets))
+}
+
 /// Extract list of image filenames from an archive (ZIP, RAR, 7z)
 /// Uses spawn_blocking to avoid blocking the async runtime
-async fn extract_image_list(archive_path: &Path) -> Result<Vec<String>> {
+pub(super) async fn extract_image_list(archive_path: &Path) -> Result<Vec<String>> {
     let path = archive_path.to_path_buf();
 
-    tokio::task::spawn_blocking(move || {
-        let file = std::fs::File::open(&path)?;
-        let files = compress_tools::list_archive_files(file)
-            .map_err(|e| crate::error::Error::Internal(format!("Failed to list archive: {}", e)))?;
+    tokio::task::spawn_blocking(move || list_archive_images(std::fs::File::open(&path)?))
+        .await
+        .map_err(|e| crate::error::Error::Internal(format!("Task join error: {}", e)))?
+}
 
-        let mut images: Vec<String> = files
-            .into_iter()
-            .filter(|name| is_image_file(name))
-            .collect();
+/// List the pages of an archive, sorted naturally
+/// Empty images and junk left by the OS that packed the archive are not pages. Telling
+/// empty files apart needs their data, as archive headers may leave sizes out.
+/// A file in no archive format is an error rather than an archive without pages.
+fn list_archive_images<R: std::io::Read + std::io::Seek>(source: R) -> Result<Vec<String>> {
+    use compress_tools::{ArchiveContents, ArchiveIterator};
+
+    let archive = ArchiveIterator::from_read(source)
+        .map_err(|e| crate::error::Error::Internal(format!("Failed to list archive: {}", e)))?;
+
+    let mut images = Vec::new();
+    let mut names = Vec::new();
+    // Image being read and the bytes read of it so far
+    let mut current: Option<(String, usize)> = None;
+    for contents in archive {
+        match contents {
+            ArchiveContents::StartOfEntry(name, _) => {
+                names.push(name.clone());
+                current = (is_image_file(&name) && !is_junk_path(&name)).then_some((name, 0));
+            }
+            ArchiveContents::DataChunk(data) => {
+                if let Some((_, size)) = current.as_mut() {
+                    *size += data.len();
+                }
+            }
+            ArchiveContents::EndOfEntry => {
+                if let Some((name, size)) = current.take() {
+                    if size > 0 {
+                        images.push(name);
+                    }
+                }
+            }
+            ArchiveContents::Err(e) => {
+                return Err(crate::error::Error::Internal(format!(
+                    "Failed to list archive: {}",
+                    e
+                )))
+            }
+        }
+    }
 
-        // Sort naturally (Chapter 2 before Chapter 10)
-        images.sort_by(|a, b| natord::compare(a, b));
+    // compress-tools always enables libarchive's raw format, which reads any other file
+    // as a single entry named "data"
+    if names == ["data"] {
+        return Err(crate::error::Error::Internal(
+            "Failed to list archive: not a supported archive format".to_string(),
+        ));
+    }
+
+    // Sort naturally (Chapter 2 before Chapter 10)
+    images.sort_by(|a, b| natord::compare(a, b));
+
+    Ok(images)
+}
+
+/// Whether an archive path is junk added by macOS rather than content: `__MACOSX/` resource
+/// forks (`__MACOSX/._page01.jpg`), `.DS_Store` and other dot files, or anything inside them
+fn is_junk_path(name: &str) -> bool {
+    name.split(['/', '\\'])
+        .any(|part| part != "." && (part.starts_with('.') || part.starts_with("__MACOSX")))
+}
+
+/// List the image files lying directly in a directory, sorted naturally
+/// Uses spawn_blocking to avoid blocking the async runtime
+pub(super) async fn list_directory_images(dir: &Path) -> Result<Vec<String>> {
+    let path = dir.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let mut images = Vec::new();
+        for file in std::fs::read_dir(&path)? {
+            let file = file?;
+            let name = file.file_name().to_string_lossy().to_string();
+            if !name.starts_with('.') && file.path().is_file() && is_image_file(&name) {
+                images.push(name);
+            }
+        }
 
+        images.sort_by(|a, b| natord::compare(a, b));
         Ok(images)
     })
     .await
     .map_err(|e| crate::error::Error::Internal(format!("Task join error: {}", e)))?
 }
 
+/// Whether a directory directly holds image files, making it an entry of its title
+pub(super) fn is_image_directory(dir: &Path) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|files| {
+        files.flatten().any(|file| {
+            let name = file.file_name();
+            let name = name.to_string_lossy();
+            !name.starts_with('.') && file.path().is_file() && is_image_file(&name)
+        })
+    })
+}
+
 /// Extract a single image from archive (ZIP, RAR, 7z)
 /// Uses spawn_blocking to avoid blocking the async runtime
+/// Fails with `Error::Archive` when the archive is damaged (see `Library::page_unreadable`)
 async fn extract_image_from_archive(archive_path: &Path, image_name: &str) -> Result<Vec<u8>> {
     let path = archive_path.to_path_buf();
     let name = image_name.to_string();
@@ -230,8 +702,52 @@ async fn extract_image_from_archive(archive_path: &Path, image_name: &str) -> Re
         let file = std::fs::File::open(&path)?;
         let mut buffer = Vec::new();
 
-        compress_tools::uncompress_archive_file(file, &mut buffer, &name)
-            .map_err(|e| crate::error::Error::Internal(format!("Failed to extract {}: {}", name, e)))?;
+        compress_tools::uncompress_archive_file(file, &mut buffer, &name)?;
+
+        Ok(buffer)
+    })
+    .await
+    .map_err(|e| crate::error::Error::Internal(format!("Task join error: {}", e)))?
+}
+
+/// Extract a single image from an archive already read into memory
+/// Uses spawn_blocking to avoid blocking the async runtime
+pub(crate) async fn extract_image_from_buffer(
+    archive: Arc<[u8]>,
+    image_name: &str,
+) -> Result<Vec<u8>> {
+    let name = image_name.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        let mut buffer = Vec::new();
+        let source = Cursor::new(archive);
+
+        compress_tools::uncompress_archive_file(source, &mut buffer, &name)?;
+
+        Ok(buffer)
+    })
+    .await
+    .map_err(|e| crate::error::Error::Internal(format!("Task join error: {}", e)))?
+}
+
+/// Extract a single image from an archive file kept open between requests
+/// The handle is rewound first; the lock keeps concurrent reads from sharing its position
+pub(crate) async fn extract_image_from_file(
+    file: Arc<std::sync::Mutex<std::fs::File>>,
+    image_name: &str,
+) -> Result<Vec<u8>> {
+    use std::io::{Seek, SeekFrom};
+
+    let name = image_name.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        let mut file = file.lock().map_err(|_| {
+            crate::error::Error::Internal("Archive handle lock poisoned".to_string())
+        })?;
+        file.seek(SeekFrom::Start(0))?;
+
+        let mut buffer = Vec::new();
+        compress_tools::uncompress_archive_file(&mut *file, &mut buffer, &name)?;
 
         Ok(buffer)
     })
@@ -240,7 +756,7 @@ async fn extract_image_from_archive(archive_path: &Path, image_name: &str) -> Re
 }
 
 /// Check if filename has an image extension
-/// Takes &str because it's used for filenames from inside ZIP archives
+/// Takes &str because it's used for filenames from inside ZIP archives and directories
 fn is_image_file(filename: &str) -> bool {
     if let Some(ext) = filename.rsplit('.').next() {
         let ext_lower = ext.to_lowercase();
@@ -269,3 +785,92 @@ impl super::Sortable for &Entry {
         self.mtime
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An uncompressed (stored) ZIP archive
+    fn stored_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+
+        for (name, data) in files {
+            let offset = out.len() as u32;
+            let crc = crc32fast::hash(data);
+            let size = data.len() as u32;
+
+            // Local file header
+            out.extend_from_slice(&0x04034b50u32.to_le_bytes());
+            out.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            out.extend_from_slice(&[0; 8]); // flags, method (stored), time, date
+            out.extend_from_slice(&crc.to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes()); // extra length
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+
+            // Central directory header
+            central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+            central.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            central.extend_from_slice(&[0; 8]); // flags, method, time, date
+            central.extend_from_slice(&crc.to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+
+        let central_offset = out.len() as u32;
+        let central_size = central.len() as u32;
+        out.extend_from_slice(&central);
+
+        // End of central directory record
+        out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        out.extend_from_slice(&[0; 4]); // disk numbers
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&central_size.to_le_bytes());
+        out.extend_from_slice(&central_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        out
+    }
+
+    #[test]
+    fn test_list_archive_images_skips_junk() {
+        let zip = stored_zip(&[
+            ("Chapter 1/page10.jpg", b"ten"),
+            ("Chapter 1/page02.jpg", b"two"),
+            ("__MACOSX/Chapter 1/._page02.jpg", b"fork"),
+            ("Chapter 1/._page10.jpg", b"fork"),
+            ("Chapter 1/.DS_Store", b"store"),
+            (".hidden/page01.jpg", b"hidden"),
+            ("Chapter 1/empty.png", b""),
+            ("Chapter 1/notes.txt", b"notes"),
+            ("./page01.png", b"one"),
+        ]);
+
+        let images = list_archive_images(Cursor::new(zip)).unwrap();
+        assert_eq!(
+            images,
+            [
+                "./page01.png",
+                "Chapter 1/page02.jpg",
+                "Chapter 1/page10.jpg"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_archive_images_rejects_other_files() {
+        assert!(list_archive_images(Cursor::new(b"not an archive".to_vec())).is_err());
+
+        let zip = stored_zip(&[("notes.txt", b"notes")]);
+        assert!(list_archive_images(Cursor::new(zip)).unwrap().is_empty());
+    }
+}
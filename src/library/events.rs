@@ -0,0 +1,174 @@
+// Library change notifications for open browser pages
+//
+// Every scan that swaps in a new Library compares it with the one it replaces and
+// publishes the differences on a broadcast channel. The `/api/events` SSE endpoint
+// subscribes to it; with no subscribers publishing is a no-op.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::manager::{Library, SharedLibrary};
+
+/// Events buffered per subscriber; a subscriber that falls further behind skips ahead
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Open event streams allowed per user (browser tabs)
+pub const MAX_STREAMS_PER_USER: usize = 4;
+
+/// A change to the library, as pushed to browsers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LibraryEvent {
+    /// A title gained entries
+    TitleUpdated {
+        title_id: String,
+        new_entries: usize,
+    },
+    /// A scan finished; counts are entries across the whole library
+    ScanCompleted { added: usize, removed: usize },
+}
+
+impl LibraryEvent {
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            LibraryEvent::TitleUpdated { .. } => "title_updated",
+            LibraryEvent::ScanCompleted { .. } => "scan_completed",
+        }
+    }
+
+    /// Events describing how `new` differs from `old`
+    fn diff(old: &Library, new: &Library) -> Vec<LibraryEvent> {
+        fn entry_ids(library: &Library) -> HashSet<&str> {
+            library
+                .titles()
+                .values()
+                .flat_map(|t| t.deep_entries())
+                .map(|e| e.id.as_str())
+                .collect()
+        }
+        let old_entries = entry_ids(old);
+        let new_entries = entry_ids(new);
+
+        let mut events = Vec::new();
+        // Everything is new after a cold start; only report titles once there was a library
+        if !old_entries.is_empty() {
+            for title in new.titles().values().flat_map(|t| t.deep_titles()) {
+                let added = title
+                    .entries
+                    .iter()
+                    .filter(|e| !old_entries.contains(e.id.as_str()))
+                    .count();
+                if added > 0 {
+                    events.push(LibraryEvent::TitleUpdated {
+                        title_id: title.id.clone(),
+                        new_entries: added,
+                    });
+                }
+            }
+        }
+
+        events.push(LibraryEvent::ScanCompleted {
+            added: new_entries.difference(&old_entries).count(),
+            removed: old_entries.difference(&new_entries).count(),
+        });
+        events
+    }
+}
+
+/// Broadcast hub for library events, with a per-user cap on open streams
+#[derive(Clone)]
+pub struct LibraryEvents {
+    sender: broadcast::Sender<LibraryEvent>,
+    /// Open streams per username
+    streams: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl Default for LibraryEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LibraryEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            streams: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Swap a freshly scanned library in and notify subscribers of what changed
+    pub fn store_scanned(&self, library: &SharedLibrary, new_lib: Library) {
+        let new_lib = Arc::new(new_lib);
+        let old_lib = library.swap(new_lib.clone());
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        for event in LibraryEvent::diff(&old_lib, &new_lib) {
+            // Only fails when every subscriber has gone away meanwhile
+            let _ = self.sender.send(event);
+        }
+    }
+
+    /// Subscribe on behalf of a user, or None if they already have the maximum open
+    /// Dropping the returned slot frees it for another stream
+    pub fn subscribe(
+        &self,
+        username: &str,
+    ) -> Option<(broadcast::Receiver<LibraryEvent>, StreamSlot)> {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        let open = streams.entry(username.to_string()).or_insert(0);
+        if *open >= MAX_STREAMS_PER_USER {
+            return None;
+        }
+        *open += 1;
+
+        let slot = StreamSlot {
+            username: username.to_string(),
+            streams: self.streams.clone(),
+        };
+        Some((self.sender.subscribe(), slot))
+    }
+}
+
+/// One user's open stream, counted against their cap while alive
+pub struct StreamSlot {
+    username: String,
+    streams: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(open) = streams.get_mut(&self.username) {
+            *open -= 1;
+            if *open == 0 {
+                streams.remove(&self.username);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_cap_per_user() {
+        let events = LibraryEvents::new();
+        let mut slots: Vec<_> = (0..MAX_STREAMS_PER_USER)
+            .map(|_| events.subscribe("alice").unwrap())
+            .collect();
+        assert!(events.subscribe("alice").is_none());
+        // Other users have their own allowance
+        assert!(events.subscribe("bob").is_some());
+
+        slots.pop();
+        assert!(events.subscribe("alice").is_some());
+    }
+}
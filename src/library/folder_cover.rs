@@ -0,0 +1,106 @@
+// Folder Covers - cover images placed in title directories (cover.jpg, poster.png)
+//
+// A title whose directory holds one of the configured cover files shows that image
+// instead of its first entry's cover. The file is resized like any entry thumbnail
+// and the result kept in memory, keyed by path and checked against the file's mtime
+// and size on every request, so replacing the image shows up without a rescan.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::entry::encode_thumbnail;
+use crate::error::{Error, Result};
+
+/// Folder cover thumbnails kept in memory; past this the cache starts over
+const MAX_FOLDER_COVERS: usize = 1024;
+
+/// Largest cover file read, in bytes
+const MAX_FOLDER_COVER_SIZE: u64 = 20 * 1024 * 1024;
+
+/// A resized cover with the file state it was made from
+struct CachedCover {
+    modified: SystemTime,
+    len: u64,
+    thumbnail: Arc<Vec<u8>>,
+}
+
+/// Thumbnails of folder cover images, shared by all requests
+#[derive(Clone, Default)]
+pub struct FolderCovers {
+    covers: Arc<Mutex<HashMap<PathBuf, CachedCover>>>,
+}
+
+impl FolderCovers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// JPEG thumbnail of a cover file, or None if it cannot be read or decoded
+    /// (the caller falls back to the first entry's cover)
+    pub async fn thumbnail(&self, path: &Path) -> Result<Option<Arc<Vec<u8>>>> {
+        let metadata = match tokio::fs::metadata(path).await {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                tracing::warn!("Folder cover {} is unreadable: {}", path.display(), e);
+                return Ok(None);
+            }
+        };
+        let modified = metadata.modified()?;
+        let len = metadata.len();
+
+        if let Some(cached) = self.lock().get(path) {
+            if cached.modified == modified && cached.len == len {
+                return Ok(Some(cached.thumbnail.clone()));
+            }
+        }
+
+        if len > MAX_FOLDER_COVER_SIZE {
+            tracing::warn!(
+                "Folder cover {} is too large ({} bytes)",
+                path.display(),
+                len
+            );
+            return Ok(None);
+        }
+
+        let data = tokio::fs::read(path).await?;
+        let encoded = tokio::task::spawn_blocking(move || {
+            let img = image::load_from_memory(&data)?;
+            encode_thumbnail(&img)
+        })
+        .await
+        .map_err(|e| Error::Internal(format!("Task join error: {}", e)))?;
+
+        let thumbnail = match encoded {
+            Ok((thumbnail, _, _)) => Arc::new(thumbnail),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to make thumbnail of folder cover {}: {}",
+                    path.display(),
+                    e
+                );
+                return Ok(None);
+            }
+        };
+
+        let mut covers = self.lock();
+        if covers.len() >= MAX_FOLDER_COVERS {
+            covers.clear();
+        }
+        covers.insert(
+            path.to_path_buf(),
+            CachedCover {
+                modified,
+                len,
+                thumbnail: thumbnail.clone(),
+            },
+        );
+        Ok(Some(thumbnail))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, CachedCover>> {
+        self.covers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,218 @@
+// Lightweight title index for the quick-switcher, and the A-Z jump bar
+//
+// Built once per library snapshot from data the scan already holds (IDs, names,
+// entry counts and contents signatures), then served as pre-serialized JSON so a
+// request does no per-title work. Scans swap in a new Library, which starts with
+// an empty index, so invalidation needs no bookkeeping.
+
+use serde::Serialize;
+
+use super::title::Title;
+
+/// One title in the index
+#[derive(Debug, Serialize)]
+struct IndexItem<'a> {
+    id: &'a str,
+    name: &'a str,
+    entry_count: usize,
+    #[serde(skip)]
+    contents_signature: &'a str,
+}
+
+/// Serialized title index with its validator
+#[derive(Debug)]
+pub struct LibraryIndex {
+    /// JSON array of `{id, name, entry_count}`, sorted by name
+    pub body: String,
+    /// Quoted strong ETag derived from the titles' contents signatures
+    pub etag: String,
+}
+
+impl LibraryIndex {
+    /// Build the index from the top-level titles
+    pub fn build<'a>(titles: impl IntoIterator<Item = &'a Title>) -> Self {
+        let mut items: Vec<IndexItem> = titles
+            .into_iter()
+            .map(|t| IndexItem {
+                id: &t.id,
+                name: &t.title,
+                entry_count: t.entries.len(),
+                contents_signature: &t.contents_signature,
+            })
+            .collect();
+        items.sort_by(|a, b| natord::compare(a.name, b.name).then_with(|| a.id.cmp(b.id)));
+
+        // Aggregate of every title's contents signature, plus what the index exposes
+        let mut hasher = crc32fast::Hasher::new();
+        for item in &items {
+            hasher.update(item.id.as_bytes());
+            hasher.update(item.name.as_bytes());
+            hasher.update(item.contents_signature.as_bytes());
+            hasher.update(&(item.entry_count as u64).to_le_bytes());
+        }
+
+        let body = serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string());
+        Self {
+            body,
+            etag: format!("\"{:08x}\"", hasher.finalize()),
+        }
+    }
+}
+
+/// Bucket for titles whose name starts with a digit or ASCII symbol
+pub const DIGIT_BUCKET: &str = "#";
+
+/// Bucket for titles whose name starts with anything else (accented or non-Latin)
+pub const OTHER_BUCKET: &str = "…";
+
+/// One group of the A-Z jump bar
+#[derive(Debug, PartialEq, Serialize)]
+pub struct LetterBucket {
+    /// "A" to "Z", `DIGIT_BUCKET` or `OTHER_BUCKET`
+    pub letter: String,
+    /// Titles in the bucket
+    pub count: usize,
+    /// Position of the bucket's first title in the sorted list
+    pub offset: usize,
+}
+
+/// Bucket of a title name
+/// Leading whitespace is skipped, as natural ordering skips it when sorting
+pub fn letter_of(name: &str) -> &'static str {
+    const LETTERS: [&str; 26] = [
+        "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R",
+        "S", "T", "U", "V", "W", "X", "Y", "Z",
+    ];
+    match name.chars().find(|c| !c.is_whitespace()) {
+        Some(c) if c.is_ascii_alphabetic() => {
+            LETTERS[(c.to_ascii_uppercase() as u8 - b'A') as usize]
+        }
+        Some(c) if c.is_ascii() => DIGIT_BUCKET,
+        Some(_) => OTHER_BUCKET,
+        None => DIGIT_BUCKET,
+    }
+}
+
+/// Group names, given in display order, into jump bar buckets
+///
+/// Natural ordering compares code points, so a letter's titles are not always one
+/// run: lowercase names sort after every uppercase one, and symbols fall on both
+/// sides of the digits. Later runs join the bucket of their first run, whose offset
+/// is where a jump lands.
+pub fn letter_buckets<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<LetterBucket> {
+    let mut buckets: Vec<LetterBucket> = Vec::new();
+    for (offset, name) in names.into_iter().enumerate() {
+        let letter = letter_of(name);
+        match buckets.iter_mut().find(|b| b.letter == letter) {
+            Some(bucket) => bucket.count += 1,
+            None => buckets.push(LetterBucket {
+                letter: letter.to_string(),
+                count: 1,
+                offset,
+            }),
+        }
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn title(id: &str, name: &str, contents_signature: &str) -> Title {
+        Title {
+            id: id.to_string(),
+            path: std::path::PathBuf::from(name),
+            title: name.to_string(),
+            signature: String::new(),
+            contents_signature: contents_signature.to_string(),
+            mtime: 0,
+            entries: Vec::new(),
+            parent_id: None,
+            nested_titles: Vec::new(),
+            cover_path: None,
+            source: crate::library::TitleSource::Directory,
+            unreadable: Vec::new(),
+            broken_entries: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_index_body_and_etag() {
+        let titles = vec![title("b", "Vol 10", "x"), title("a", "Vol 2", "y")];
+        let index = LibraryIndex::build(&titles);
+        assert_eq!(
+            index.body,
+            r#"[{"id":"a","name":"Vol 2","entry_count":0},{"id":"b","name":"Vol 10","entry_count":0}]"#
+        );
+
+        // Same contents in another order give the same validator
+        let reordered = vec![title("a", "Vol 2", "y"), title("b", "Vol 10", "x")];
+        assert_eq!(LibraryIndex::build(&reordered).etag, index.etag);
+
+        // A changed directory listing changes it
+        let changed = vec![title("a", "Vol 2", "y"), title("b", "Vol 10", "z")];
+        assert_ne!(LibraryIndex::build(&changed).etag, index.etag);
+    }
+
+    fn bucket(letter: &str, count: usize, offset: usize) -> LetterBucket {
+        LetterBucket {
+            letter: letter.to_string(),
+            count,
+            offset,
+        }
+    }
+
+    #[test]
+    fn test_letter_of() {
+        assert_eq!(letter_of("Berserk"), "B");
+        assert_eq!(letter_of("  berserk"), "B");
+        assert_eq!(letter_of("20th Century Boys"), DIGIT_BUCKET);
+        assert_eq!(letter_of("[Oneshot] Title"), DIGIT_BUCKET);
+        assert_eq!(letter_of("Ékoute"), OTHER_BUCKET);
+        assert_eq!(letter_of("進撃の巨人"), OTHER_BUCKET);
+        assert_eq!(letter_of(""), DIGIT_BUCKET);
+    }
+
+    #[test]
+    fn test_letter_bucket_boundaries() {
+        let mut names = ["Zeta", "20th", "Alpha", "Beta 2", "Beta 10", "Ａ", "Bravo"];
+        names.sort_by(|a, b| natord::compare(a, b));
+        assert_eq!(
+            letter_buckets(names.iter().copied()),
+            vec![
+                bucket("#", 1, 0),
+                bucket("A", 1, 1),
+                bucket("B", 3, 2),
+                bucket("Z", 1, 5),
+                bucket("…", 1, 6),
+            ]
+        );
+
+        // Descending order reverses the buckets, offsets still point at the first title
+        names.reverse();
+        assert_eq!(
+            letter_buckets(names.iter().copied()),
+            vec![
+                bucket("…", 1, 0),
+                bucket("Z", 1, 1),
+                bucket("B", 3, 2),
+                bucket("A", 1, 5),
+                bucket("#", 1, 6),
+            ]
+        );
+
+        assert!(letter_buckets(std::iter::empty()).is_empty());
+    }
+
+    #[test]
+    fn test_lowercase_names_join_their_letter() {
+        let mut names = vec!["alpha", "Beta", "Apple"];
+        names.sort_by(|a, b| natord::compare(a, b));
+        assert_eq!(names, ["Apple", "Beta", "alpha"]);
+        assert_eq!(
+            letter_buckets(names.iter().copied()),
+            vec![bucket("A", 2, 0), bucket("B", 1, 1)]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+// Library maintenance - bulk repairs of stored scan state
+//
+// After a library moves to a new disk every inode changes, so no stored signature matches
+// and scans fall back to path-only matching. Rebuilding recomputes the signature of every
+// title/entry row whose path still exists and overwrites it in place, keeping all IDs.
+//
+// The first scan of a library dates every entry it finds by the scan time. The date_added
+// backfill replaces those import stamps with the archives' mtimes.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::storage::SignatureRow;
+use crate::Storage;
+
+/// A stored signature that no longer matches the file on disk
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SignatureChange {
+    /// "title" or "entry"
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
+    /// Path relative to the library root
+    pub path: String,
+    pub old_signature: Option<String>,
+    pub new_signature: String,
+}
+
+/// Result of a signature rebuild
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SignatureRebuild {
+    /// Nothing was written; `changes` lists what would be updated
+    pub dry_run: bool,
+    pub titles_updated: usize,
+    pub entries_updated: usize,
+    /// Rows whose signature changes (only listed for dry runs)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<SignatureChange>,
+}
+
+/// Result of a date_added backfill
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DateAddedBackfill {
+    pub titles: usize,
+    pub entries_updated: usize,
+}
+
+/// Date entries by their archive mtime, for one title or the whole library
+/// Without `force`, only dates stamped by the initial import (or missing) are replaced,
+/// so entries added since keep their real date. Fails with BadRequest when no initial
+/// import was recorded and `force` is not set, and with Conflict while a scan is running
+pub async fn backfill_date_added(
+    library: &super::Library,
+    storage: &Storage,
+    title_id: Option<&str>,
+    force: bool,
+) -> Result<DateAddedBackfill> {
+    let _no_scans = library.scan_lock().block_scans()?;
+
+    let window = if force {
+        None
+    } else {
+        let (started_at, finished_at) = storage.get_initial_import().await?.ok_or_else(|| {
+            Error::BadRequest(
+                "No initial import was recorded for this library, use force=1".to_string(),
+            )
+        })?;
+        Some((
+            started_at,
+            finished_at.unwrap_or_else(crate::util::time::now_ts),
+        ))
+    };
+
+    let titles = match title_id {
+        Some(id) => vec![library
+            .get_title(id)
+            .ok_or_else(|| Error::NotFound(format!("Title not found: {}", id)))?],
+        None => library.get_titles(),
+    };
+
+    let mut entries_updated = 0;
+    for title in &titles {
+        // Stat the archives now; the scanned mtime may predate a touch-up of the files
+        let mut entries: Vec<(&str, i64)> = Vec::with_capacity(title.entries.len());
+        for entry in &title.entries {
+            match file_mtime(&entry.path).await {
+                Ok(mtime) => entries.push((entry.id.as_str(), mtime)),
+                Err(e) => tracing::warn!("Failed to stat {}: {}", entry.path.display(), e),
+            }
+        }
+        entries_updated += library
+            .progress_cache()
+            .backfill_date_added(&title.id, &title.info_dir(), &entries, window)
+            .await?;
+    }
+
+    tracing::info!(
+        "Backfilled date added from file mtimes: {} entries in {} titles",
+        entries_updated,
+        titles.len()
+    );
+
+    Ok(DateAddedBackfill {
+        titles: titles.len(),
+        entries_updated,
+    })
+}
+
+/// Modification time of a file as a Unix timestamp
+async fn file_mtime(path: &Path) -> Result<i64> {
+    let modified = tokio::fs::metadata(path).await?.modified()?;
+    Ok(modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0))
+}
+
+/// Recompute the signature of every available title and entry row whose path still
+/// exists under the library root, and overwrite the stored value in bulk
+/// Rows are only updated, never created, so no title or entry gets a new ID.
+/// Fails with Conflict while a scan holding `scan_lock` is running (and blocks scans until
+/// done)
+pub async fn rebuild_signatures(
+    library_path: &Path,
+    storage: &Storage,
+    scan_lock: &super::ScanLock,
+    dry_run: bool,
+) -> Result<SignatureRebuild> {
+    let _no_scans = scan_lock.block_scans()?;
+
+    let titles = storage.list_title_signatures().await?;
+    let entries = storage.list_entry_signatures().await?;
+
+    // Signatures stat every file, so compute them off the async runtime
+    let root = library_path.to_path_buf();
+    let changes = tokio::task::spawn_blocking(move || {
+        let mut changes = signature_changes(&root, titles, "title", crate::util::dir_signature);
+        changes.extend(signature_changes(
+            &root,
+            entries,
+            "entry",
+            crate::util::file_signature,
+        ));
+        changes
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Task join error: {}", e)))?;
+
+    let (title_changes, entry_changes): (Vec<_>, Vec<_>) =
+        changes.into_iter().partition(|c| c.kind == "title");
+
+    let rebuild = SignatureRebuild {
+        dry_run,
+        titles_updated: title_changes.len(),
+        entries_updated: entry_changes.len(),
+        changes: Vec::new(),
+    };
+
+    if dry_run {
+        return Ok(SignatureRebuild {
+            changes: title_changes.into_iter().chain(entry_changes).collect(),
+            ..rebuild
+        });
+    }
+
+    let pairs = |changes: Vec<SignatureChange>| -> Vec<(String, String)> {
+        changes
+            .into_iter()
+            .map(|c| (c.id, c.new_signature))
+            .collect()
+    };
+    storage
+        .update_signatures(&pairs(title_changes), &pairs(entry_changes))
+        .await?;
+
+    tracing::info!(
+        "Rebuilt signatures: {} titles, {} entries updated",
+        rebuild.titles_updated,
+        rebuild.entries_updated
+    );
+
+    Ok(rebuild)
+}
+
+/// Compare stored signatures with freshly computed ones
+/// Rows whose path no longer exists are skipped (scans mark those unavailable)
+fn signature_changes(
+    root: &Path,
+    rows: Vec<SignatureRow>,
+    kind: &'static str,
+    signature: fn(&Path) -> Result<String>,
+) -> Vec<SignatureChange> {
+    rows.into_iter()
+        .filter_map(|row| {
+            let path: PathBuf = root.join(&row.path);
+            if !path.exists() {
+                return None;
+            }
+
+            let new_signature = match signature(&path) {
+                Ok(sig) => sig,
+                Err(e) => {
+                    tracing::warn!("Failed to compute signature of {}: {}", path.display(), e);
+                    return None;
+                }
+            };
+
+            if row.signature.as_deref() == Some(new_signature.as_str()) {
+                return None;
+            }
+
+            Some(SignatureChange {
+                kind,
+                id: row.id,
+                path: row.path,
+                old_signature: row.signature,
+                new_signature,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn insert_row(storage: &Storage, table: &str, id: &str, path: &str, signature: &str) {
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, path, signature, unavailable) VALUES (?, ?, ?, 0)",
+            table
+        ))
+        .bind(id)
+        .bind(path)
+        .bind(signature)
+        .execute(storage.pool())
+        .await
+        .unwrap();
+    }
+
+    async fn stored_signature(storage: &Storage, table: &str, id: &str) -> String {
+        sqlx::query_scalar(&format!("SELECT signature FROM {} WHERE id = ?", table))
+            .bind(id)
+            .fetch_one(storage.pool())
+            .await
+            .unwrap()
+    }
+
+    /// Replace a file with a copy, giving it a new inode like a disk migration does
+    fn rewrite(path: &Path) {
+        let copy = path.with_extension("tmp");
+        std::fs::copy(path, &copy).unwrap();
+        std::fs::rename(&copy, path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_signatures() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let library = temp_dir.path().join("library");
+        let series = library.join("Series");
+        std::fs::create_dir_all(&series).unwrap();
+        std::fs::write(series.join("ch1.zip"), b"one").unwrap();
+        std::fs::write(series.join("ch2.zip"), b"two").unwrap();
+
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        let scan_lock = super::super::ScanLock::new();
+
+        let title_sig = crate::util::dir_signature(&series).unwrap();
+        let ch1_sig = crate::util::file_signature(&series.join("ch1.zip")).unwrap();
+        let ch2_sig = crate::util::file_signature(&series.join("ch2.zip")).unwrap();
+        insert_row(&storage, "titles", "t1", "Series", &title_sig).await;
+        insert_row(&storage, "ids", "e1", "Series/ch1.zip", &ch1_sig).await;
+        insert_row(&storage, "ids", "e2", "Series/ch2.zip", &ch2_sig).await;
+        // Gone from disk: left alone
+        insert_row(&storage, "ids", "e3", "Series/ch3.zip", "stale").await;
+
+        // Nothing changed on disk yet
+        let result = rebuild_signatures(&library, &storage, &scan_lock, false)
+            .await
+            .unwrap();
+        assert_eq!((result.titles_updated, result.entries_updated), (0, 0));
+
+        rewrite(&series.join("ch1.zip"));
+
+        // Dry run lists the changes without writing them
+        let result = rebuild_signatures(&library, &storage, &scan_lock, true)
+            .await
+            .unwrap();
+        assert!(result.dry_run);
+        assert_eq!((result.titles_updated, result.entries_updated), (1, 1));
+        let ids: Vec<&str> = result.changes.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["t1", "e1"]);
+        assert_eq!(stored_signature(&storage, "ids", "e1").await, ch1_sig);
+
+        // Real run overwrites in place
+        let result = rebuild_signatures(&library, &storage, &scan_lock, false)
+            .await
+            .unwrap();
+        assert_eq!((result.titles_updated, result.entries_updated), (1, 1));
+        assert!(result.changes.is_empty());
+        assert_eq!(
+            stored_signature(&storage, "ids", "e1").await,
+            crate::util::file_signature(&series.join("ch1.zip")).unwrap()
+        );
+        assert_eq!(stored_signature(&storage, "ids", "e2").await, ch2_sig);
+        assert_eq!(stored_signature(&storage, "ids", "e3").await, "stale");
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ids")
+            .fetch_one(storage.pool())
+            .await
+            .unwrap();
+        assert_eq!(count, 3, "no rows are created");
+
+        // Refused while a scan holds the scan lock
+        let scanning = scan_lock.scan().await;
+        let err = rebuild_signatures(&library, &storage, &scan_lock, true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Conflict(_)));
+        drop(scanning);
+    }
+}
@@ -6,10 +6,63 @@ use arc_swap::ArcSwap;
 use tokio::sync::Mutex;
 
 use super::entry::Entry;
-use super::title::Title;
+use super::index::LibraryIndex;
+use super::progress::{ReadingDirection, TitleInfo};
+use super::scan_filter::ScanFilter;
+use super::scan_journal::{CompletedTitle, ScanJournal};
+use super::title::{NameCollision, Title, TitleSource};
+use crate::config::LooseArchives;
 use crate::error::Result;
+use crate::storage::RecentlyRead;
 use crate::Storage;
 
+/// Title and entry IDs discovered by a scan, not yet in the database
+/// Each is (id, relative path, signature)
+#[derive(Debug, Default)]
+pub(super) struct NewIds {
+    pub titles: Vec<(String, String, String)>,
+    pub entries: Vec<(String, String, String)>,
+}
+
+/// Result of scanning one title directory
+enum ScanOutcome {
+    /// Finished by an interrupted scan and unchanged since
+    Reused(Title),
+    /// Scanned now, with the IDs it added
+    Scanned(Title, NewIds),
+    /// The directory could not be scanned
+    Failed,
+    /// Unchanged since the library cache was written
+    Cached(Title),
+}
+
+/// How the titles of a cached library fared in the scan that reused them
+#[derive(Default)]
+struct CacheReuse {
+    unchanged: usize,
+    changed: usize,
+    added: usize,
+    removed: usize,
+}
+
+impl CacheReuse {
+    fn summary(&self) -> ScanSummary {
+        ScanSummary {
+            reused: self.unchanged,
+            rescanned: self.changed + self.added,
+        }
+    }
+}
+
+/// Rows fetched per query when walking the recently read index
+const RECENTLY_READ_BATCH: usize = 32;
+
+/// Longest extra wait before the next periodic scan while scans keep failing
+const MAX_SCAN_BACKOFF_SECS: u64 = 60 * 60;
+
+/// Unreadable pages of an entry are logged at warn level at most this often
+const PAGE_ERROR_LOG_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 pub struct Library {
     /// Library root directory
     path: PathBuf,
@@ -25,6 +78,47 @@ pub struct Library {
 
     /// In-memory cache for progress data (eliminates O(N) filesystem reads)
     progress_cache: super::progress_cache::ProgressCache,
+
+    /// Bad page numbers (one-based) found by page validation: entry_id -> pages
+    bad_pages: std::sync::RwLock<HashMap<String, Vec<usize>>>,
+
+    /// When an unreadable page of an entry was last logged at warn level: entry_id -> time
+    page_errors_logged: std::sync::Mutex<HashMap<String, std::time::Instant>>,
+
+    /// Title index for the quick-switcher, built on first use
+    index: std::sync::OnceLock<Arc<LibraryIndex>>,
+
+    /// Date entries of the initial import by file mtime (config `date_added_from_mtime`)
+    date_added_from_mtime: bool,
+
+    /// File names of title cover images (config `cover_file_names`)
+    cover_file_names: Arc<[String]>,
+
+    /// What archives in the library root become (config `loose_archives`)
+    loose_archives: LooseArchives,
+
+    /// Name of the title of the library root's archives (config `unsorted_title_name`)
+    unsorted_title_name: String,
+
+    /// Titles and archives left out of scans (config `scan_skip_hidden` and
+    /// `scan_exclude_patterns`)
+    scan_filter: Arc<ScanFilter>,
+
+    /// Title directories scanned at the same time (config `scan_workers`, at least 1)
+    scan_workers: usize,
+
+    /// Whether stored paths are matched case-sensitively, like the library's filesystem
+    /// compares names (config `library_case_sensitive`)
+    case_sensitive_paths: bool,
+
+    /// Sibling title directories the last scan found with colliding names
+    name_collisions: Vec<NameCollision>,
+
+    /// Where scans of this instance report their progress (admin scans only)
+    scan_status: Option<super::ScanStatus>,
+
+    /// Held by scans, shared with the library this one replaces
+    scan_lock: super::ScanLock,
 }
 
 impl Library {
@@ -36,9 +130,38 @@ impl Library {
             storage,
             cache: Mutex::new(super::cache::Cache::new(config)),
             progress_cache: super::progress_cache::ProgressCache::new(),
+            bad_pages: std::sync::RwLock::new(HashMap::new()),
+            page_errors_logged: std::sync::Mutex::new(HashMap::new()),
+            index: std::sync::OnceLock::new(),
+            date_added_from_mtime: config.date_added_from_mtime,
+            cover_file_names: config.cover_file_names.clone().into(),
+            scan_filter: Arc::new(ScanFilter::from_config(config)),
+            scan_workers: config.scan_workers.max(1),
+            case_sensitive_paths: config.library_case_sensitive.unwrap_or(true),
+            name_collisions: Vec::new(),
+            loose_archives: config.loose_archives,
+            unsorted_title_name: config.unsorted_title_name.clone(),
+            scan_status: None,
+            scan_lock: super::ScanLock::new(),
         }
     }
 
+    /// Report the progress of this instance's scans to `status`
+    pub fn report_progress_to(&mut self, status: super::ScanStatus) {
+        self.scan_status = Some(status);
+    }
+
+    /// Use the scan lock of `current`, the library this one is built to replace, so its
+    /// scans and the app's maintenance keep excluding each other
+    pub fn share_scan_lock(&mut self, current: &Library) {
+        self.scan_lock = current.scan_lock.clone();
+    }
+
+    /// Lock held by this library's scans, see `ScanLock`
+    pub fn scan_lock(&self) -> &super::ScanLock {
+        &self.scan_lock
+    }
+
     /// Convert absolute path to relative path (relative to library root)
     /// Example: "/home/user/library/Series/Chapter.zip" -> "Series/Chapter.zip"
     #[allow(dead_code)]
@@ -73,7 +196,16 @@ impl Library {
                 drop(cache); // Release lock before modifying self.titles
 
                 self.titles = cached_data.titles;
-                let entry_count: usize = self.titles.values().map(|t| t.entries.len()).sum();
+                self.index = std::sync::OnceLock::new();
+                self.name_collisions =
+                    super::title::disambiguate_names(self.titles.values_mut(), &self.path);
+
+                // Merges may have changed since the cache was written
+                let merges = self.storage.list_entry_merges().await?;
+                for title in self.titles.values_mut() {
+                    super::merge::apply_merges(title, &merges);
+                }
+                let entry_count: usize = self.all_titles().map(|t| t.entries.len()).sum();
 
                 tracing::info!(
                     "Library loaded from cache: {} titles, {} entries",
@@ -83,6 +215,7 @@ impl Library {
 
                 // Load progress cache for all titles
                 self.load_progress_cache().await;
+                self.load_bad_pages().await;
 
                 Ok(true)
             }
@@ -93,151 +226,386 @@ impl Library {
         }
     }
 
-    /// Scan the library directory for manga titles
+    /// A copy of this library with one title replaced, for changes that only touch one
+    /// title (entry merges) and do not need a rescan
+    /// Progress and bad pages are reloaded, so they reflect what was saved meanwhile
+    pub async fn with_title(&self, config: &crate::Config, title: Title) -> Self {
+        let mut library = Self::new(self.path.clone(), self.storage.clone(), config);
+        library.share_scan_lock(self);
+        library.titles = self.titles.clone();
+        library.titles.insert(title.id.clone(), title);
+        library.name_collisions = self.name_collisions.clone();
+
+        library.load_progress_cache().await;
+        library.load_bad_pages().await;
+        library
+    }
+
+    /// Scan the library directory for manga titles, reading every archive
     /// Uses parallel processing with controlled concurrency for improved performance
-    pub async fn scan(&mut self) -> Result<()> {
+    pub async fn scan(&mut self) -> Result<ScanSummary> {
+        let reuse = self.scan_reusing(HashMap::new()).await?;
+        Ok(reuse.summary())
+    }
+
+    /// Scan the library, reusing the titles of `previous` whose directories are unchanged
+    /// Only the archives of new and changed title directories are read
+    pub async fn scan_incremental(&mut self, previous: &Library) -> Result<ScanSummary> {
+        let reuse = self.scan_reusing(previous.reusable_titles()).await?;
+        Ok(reuse.summary())
+    }
+
+    /// A copy of this library with the title directory `title_path` scanned again, for
+    /// changes reported by the library watcher; the other titles are kept as they are
+    /// The title is dropped when its directory is gone, hidden or cannot be scanned
+    pub async fn rescan_title(&self, config: &crate::Config, title_path: &Path) -> Result<Self> {
+        let _scanning = self.scan_lock.scan().await;
+        let start = std::time::Instant::now();
+
+        let mut titles = self.titles.clone();
+        titles.retain(|_, t| !(t.source == TitleSource::Directory && t.path == title_path));
+
+        let excluded = self.scan_filter.excludes(title_path);
+        if title_path.is_dir() && !excluded {
+            let scanned = Self::scan_title(
+                title_path.to_path_buf(),
+                &self.path,
+                &self.cover_file_names,
+                &self.storage,
+                false,
+                &self.scan_filter,
+                self.case_sensitive_paths,
+            )
+            .await;
+            if let Some((mut title, ids)) = scanned {
+                let mut tx = self.storage.pool().begin().await?;
+                Self::insert_ids(&mut tx, &ids).await?;
+                tx.commit().await?;
+
+                let merges = self.storage.list_entry_merges().await?;
+                super::merge::apply_merges(&mut title, &merges);
+                titles.insert(title.id.clone(), title);
+            }
+        }
+
+        let mut library = Self::new(self.path.clone(), self.storage.clone(), config);
+        library.share_scan_lock(self);
+        library.name_collisions = super::title::disambiguate_names(titles.values_mut(), &self.path);
+        library.log_name_collisions();
+        library.titles = titles;
+        library.load_progress_cache().await;
+        library.load_bad_pages().await;
+
+        let mut tx = self.storage.pool().begin().await?;
+        library.mark_unavailable(&mut tx).await?;
+        tx.commit().await?;
+
+        tracing::info!(
+            "Rescanned {} ({:.2}s)",
+            title_path.display(),
+            start.elapsed().as_secs_f64()
+        );
+        library.save_to_cache_background().await;
+        Ok(library)
+    }
+
+    /// Directory titles by path relative to the library root, for `scan_reusing`
+    fn reusable_titles(&self) -> HashMap<String, Title> {
+        self.titles
+            .values()
+            .filter(|t| t.source == TitleSource::Directory)
+            .map(|t| (t.relative_path(&self.path), t.clone()))
+            .collect()
+    }
+
+    /// Check a library loaded from cache against the disk and apply what changed
+    /// Titles whose directory is unchanged since the cache was written are kept as
+    /// cached, the rest are scanned; the result is logged and returned for the scan status
+    /// (`ScanStatus::set_cache_verification`)
+    pub async fn verify_cache(&mut self, cached: &Library) -> Result<CacheVerification> {
+        let start = std::time::Instant::now();
+        let titles = cached.reusable_titles();
+        let checked = titles.len();
+
+        let reuse = self.scan_reusing(titles).await?;
+        let verification = CacheVerification {
+            finished_at: crate::util::time::now_ts(),
+            checked,
+            unchanged: reuse.unchanged,
+            changed: reuse.changed,
+            added: reuse.added,
+            removed: reuse.removed,
+            stale: reuse.changed + reuse.added + reuse.removed,
+            duration_ms: start.elapsed().as_millis() as u64,
+        };
+
+        if verification.stale == 0 {
+            tracing::info!(
+                "Library cache verified: all {} titles match the disk",
+                checked
+            );
+        } else {
+            tracing::warn!(
+                "Library cache was stale: {} titles changed, {} added, {} removed",
+                verification.changed,
+                verification.added,
+                verification.removed
+            );
+        }
+
+        Ok(verification)
+    }
+
+    /// Scan the library, reusing the given titles (by path relative to the library
+    /// root) whose directories are unchanged since they were scanned
+    async fn scan_reusing(&mut self, mut cached: HashMap<String, Title>) -> Result<CacheReuse> {
+        let _scanning = self.scan_lock.scan().await;
         let scan_start = std::time::Instant::now();
         tracing::info!("Starting library scan: {}", self.path.display());
 
-        // Collect all directory paths first
+        // Collect all directory paths first, and the archives lying next to them
         let mut title_paths = Vec::new();
+        let mut loose_archives = Vec::new();
         let mut dir_entries = tokio::fs::read_dir(&self.path).await?;
         while let Some(entry) = dir_entries.next_entry().await? {
             let entry_path = entry.path();
-            if entry_path.is_dir() {
-                title_paths.push(entry_path);
+            // Hidden and excluded items are not scanned, so titles and entries found before
+            // are marked unavailable and get their IDs back by path once scanned again
+            if self.scan_filter.excludes(&entry_path) {
+                continue;
+            }
+            // Followed like is_dir(), so symlinked series count as directories
+            let is_dir = tokio::fs::metadata(&entry_path)
+                .await
+                .is_ok_and(|m| m.is_dir());
+            if is_dir {
+                if entry.file_name() != super::title::LOOSE_INFO_DIR {
+                    title_paths.push(entry_path);
+                }
+            } else if super::title::is_archive(&entry_path) {
+                if crate::util::symlink_within_roots(&entry_path, &[self.path.as_path()]) {
+                    loose_archives.push(entry_path);
+                } else {
+                    tracing::warn!(
+                        "Skipping {}: symlink points outside the library",
+                        entry_path.display()
+                    );
+                }
             }
         }
 
-        tracing::info!("Found {} directories to scan", title_paths.len());
+        // Symlinked series may point at a directory that is also scanned directly
+        let title_paths = crate::util::dedup_title_paths(title_paths);
+
+        tracing::info!(
+            "Found {} directories to scan with {} workers",
+            title_paths.len(),
+            self.scan_workers
+        );
+        if let Some(status) = &self.scan_status {
+            status.set_remaining(title_paths.len());
+        }
+
+        let relative_paths: Vec<String> = title_paths
+            .iter()
+            .map(|p| {
+                p.strip_prefix(&self.path)
+                    .unwrap_or(p)
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+
+        // Resume an interrupted scan: titles it finished are reused while unchanged
+        let (journal, mut completed) = ScanJournal::open(&self.storage, &relative_paths).await?;
+        if !completed.is_empty() {
+            tracing::info!(
+                "{} titles already scanned by the interrupted scan",
+                completed.len()
+            );
+        }
 
-        // Collections for bulk database inserts (matching original Mango pattern)
-        let new_title_ids = Arc::new(tokio::sync::Mutex::new(Vec::new()));
-        let new_entry_ids = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        // The first scan of an empty library stamps every entry; remember when it ran
+        let initial_import = self
+            .storage
+            .begin_initial_import(journal.scan_id(), crate::util::time::now_ts())
+            .await?;
+        let date_added_from_mtime = initial_import && self.date_added_from_mtime;
 
         // Process titles in parallel with controlled concurrency
-        let concurrency_limit = 20; // Increased from 5 to 20 for better parallelism
-        let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency_limit));
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(self.scan_workers));
         let storage = self.storage.clone();
         let library_path = self.path.clone();
+        let cover_names = self.cover_file_names.clone();
+        let scan_filter = self.scan_filter.clone();
+        let case_sensitive_paths = self.case_sensitive_paths;
 
-        let mut tasks = Vec::new();
+        let mut tasks = tokio::task::JoinSet::new();
 
-        for title_path in title_paths {
+        for (title_path, relative_path) in title_paths.into_iter().zip(relative_paths) {
             let sem = semaphore.clone();
             let storage_clone = storage.clone();
             let lib_path = library_path.clone();
-            let title_ids = new_title_ids.clone();
-            let entry_ids = new_entry_ids.clone();
-
-            let task = tokio::spawn(async move {
+            let cover_names = cover_names.clone();
+            let scan_filter = scan_filter.clone();
+            let cached_title = cached.remove(&relative_path);
+            let in_cache = cached_title.is_some();
+            let previous = match completed.remove(&relative_path) {
+                Some(previous) => Some((previous, false)),
+                None => cached_title.map(|title| {
+                    let contents_signature = title.contents_signature.clone();
+                    let previous = CompletedTitle {
+                        contents_signature,
+                        title,
+                    };
+                    (previous, true)
+                }),
+            };
+
+            tasks.spawn(async move {
                 let _permit = sem.acquire().await.unwrap();
 
-                // Scan title directory
-                let mut title = match Title::from_directory(title_path.clone()).await {
-                    Ok(t) => t,
-                    Err(e) => {
-                        tracing::warn!("Failed to scan title at {}: {}", title_path.display(), e);
-                        return None;
+                if let Some((previous, from_cache)) = previous {
+                    if Self::journaled_title_unchanged(&title_path, &previous).await {
+                        let outcome = if from_cache {
+                            ScanOutcome::Cached(previous.title)
+                        } else {
+                            ScanOutcome::Reused(previous.title)
+                        };
+                        return (relative_path, outcome, in_cache);
                     }
+                }
+
+                let scanned = Self::scan_title(
+                    title_path,
+                    &lib_path,
+                    &cover_names,
+                    &storage_clone,
+                    date_added_from_mtime,
+                    &scan_filter,
+                    case_sensitive_paths,
+                )
+                .await;
+                let outcome = match scanned {
+                    Some((title, ids)) => ScanOutcome::Scanned(title, ids),
+                    None => ScanOutcome::Failed,
                 };
+                (relative_path, outcome, in_cache)
+            });
+        }
 
-                // Find or create title ID
-                let existing_id = Self::find_existing_id_static(&lib_path, &title, &storage_clone)
-                    .await
-                    .ok()?;
-                let is_new_title = existing_id.is_none();
-                if let Some(id) = existing_id {
-                    title.id = id;
-                    tracing::debug!("Matched existing title: {} ({})", title.title, title.id);
-                } else {
-                    // New title - collect for bulk insert
-                    let relative_path = title
-                        .path
-                        .strip_prefix(&lib_path)
-                        .ok()?
-                        .to_string_lossy()
-                        .to_string();
-
-                    title_ids.lock().await.push((
-                        title.id.clone(),
-                        relative_path,
-                        title.signature.clone(),
-                    ));
-                    tracing::info!("Discovered new title: {} ({})", title.title, title.id);
-                }
+        // Cached titles whose directory is gone
+        let mut reuse = CacheReuse {
+            removed: cached.len(),
+            ..Default::default()
+        };
 
-                // Find or create entry IDs
-                for entry in &mut title.entries {
-                    let existing_entry_id =
-                        Self::find_existing_entry_id_static(&lib_path, entry, &storage_clone)
-                            .await
-                            .ok()?;
-                    if let Some(id) = existing_entry_id {
-                        entry.id = id;
-                    } else {
-                        // New entry - collect for bulk insert
-                        let relative_path = entry
-                            .path
-                            .strip_prefix(&lib_path)
-                            .ok()?
-                            .to_string_lossy()
-                            .to_string();
-
-                        entry_ids.lock().await.push((
-                            entry.id.clone(),
-                            relative_path,
-                            entry.signature.clone(),
-                        ));
-
-                        if is_new_title {
-                            tracing::debug!("  New entry: {} ({})", entry.title, entry.id);
-                        }
-                    }
+        // Checkpoint each title as it completes, so an interrupted scan can resume
+        let mut new_titles = HashMap::new();
+        let (mut new_title_count, mut new_entry_count) = (0, 0);
+        while let Some(joined) = tasks.join_next().await {
+            let (relative_path, outcome, in_cache) = match joined {
+                Ok(result) => result,
+                Err(e) => {
+                    tracing::warn!("Title scan task failed: {}", e);
+                    continue;
                 }
+            };
 
-                // Populate date_added
-                if let Err(e) = title.populate_date_added().await {
-                    tracing::warn!("Failed to populate date_added for {}: {}", title.title, e);
-                }
+            if let Some(status) = &self.scan_status {
+                status.directory_done(usize::from(!matches!(outcome, ScanOutcome::Failed)));
+            }
+            match (&outcome, in_cache) {
+                (ScanOutcome::Cached(_), _) => reuse.unchanged += 1,
+                (_, true) => reuse.changed += 1,
+                (_, false) => reuse.added += 1,
+            }
 
-                Some(title)
-            });
+            let title = match outcome {
+                ScanOutcome::Reused(title) => Some(title),
+                ScanOutcome::Cached(title) => {
+                    journal
+                        .checkpoint(&relative_path, Some(&title), &NewIds::default())
+                        .await?;
+                    Some(title)
+                }
+                ScanOutcome::Scanned(title, ids) => {
+                    journal
+                        .checkpoint(&relative_path, Some(&title), &ids)
+                        .await?;
+                    new_title_count += ids.titles.len();
+                    new_entry_count += ids.entries.len();
+                    Some(title)
+                }
+                ScanOutcome::Failed => {
+                    journal
+                        .checkpoint(&relative_path, None, &NewIds::default())
+                        .await?;
+                    None
+                }
+            };
 
-            tasks.push(task);
+            if let Some(title) = title {
+                new_titles.insert(title.id.clone(), title);
+            }
         }
 
-        // Collect results
-        let mut new_titles = HashMap::new();
-        for task in tasks {
-            if let Ok(Some(title)) = task.await {
+        // Archives in the library root are few and quick to scan, so they are not journaled
+        if !loose_archives.is_empty() {
+            let (titles, ids) = self
+                .scan_loose_archives(loose_archives, date_added_from_mtime)
+                .await;
+            let mut tx = self.storage.pool().begin().await?;
+            Self::insert_ids(&mut tx, &ids).await?;
+            tx.commit().await?;
+            new_title_count += ids.titles.len();
+            new_entry_count += ids.entries.len();
+            if let Some(status) = &self.scan_status {
+                status.titles_found(titles.len());
+            }
+            for title in titles {
                 new_titles.insert(title.id.clone(), title);
             }
         }
 
         let title_count = new_titles.len();
-        let entry_count: usize = new_titles.values().map(|t| t.entries.len()).sum();
-
-        // Bulk insert all new IDs in a single transaction
-        let title_ids_vec = new_title_ids.lock().await;
-        let entry_ids_vec = new_entry_ids.lock().await;
+        let entry_count: usize = new_titles.values().map(|t| t.deep_entries().len()).sum();
 
-        if !title_ids_vec.is_empty() || !entry_ids_vec.is_empty() {
-            self.bulk_insert_ids(&title_ids_vec, &entry_ids_vec).await?;
+        if new_title_count > 0 || new_entry_count > 0 {
             tracing::info!(
-                "Bulk inserted {} new titles and {} new entries to database",
-                title_ids_vec.len(),
-                entry_ids_vec.len()
+                "Inserted {} new titles and {} new entries to database",
+                new_title_count,
+                new_entry_count
             );
         }
 
+        // Present multi-part entries merged by an admin as one
+        let merges = self.storage.list_entry_merges().await?;
+        for title in new_titles.values_mut() {
+            super::merge::apply_merges(title, &merges);
+        }
+
+        self.name_collisions =
+            super::title::disambiguate_names(new_titles.values_mut(), &self.path);
+        self.log_name_collisions();
         self.titles = new_titles;
+        self.index = std::sync::OnceLock::new();
 
         // Load progress cache for all titles
         self.load_progress_cache().await;
+        self.load_bad_pages().await;
 
-        // Mark items in database as unavailable if not found during scan
-        self.mark_unavailable().await?;
+        // Mark items in database as unavailable if not found during scan, and close the
+        // journal in the same transaction so the scan completes all at once or not at all
+        let mut tx = self.storage.pool().begin().await?;
+        self.mark_unavailable(&mut tx).await?;
+        journal.finish(&mut tx).await?;
+        tx.commit().await?;
+        if initial_import {
+            self.storage
+                .finish_initial_import(crate::util::time::now_ts())
+                .await?;
+        }
 
         let scan_duration = scan_start.elapsed();
         tracing::info!(
@@ -246,24 +614,274 @@ impl Library {
             entry_count,
             scan_duration.as_secs_f64()
         );
+        let broken_count = self.broken_entries().count();
+        if broken_count > 0 {
+            tracing::warn!(
+                "{} entries have no pages and were left out (listed on the missing items page)",
+                broken_count
+            );
+        }
 
         // Save library to cache in background (non-blocking)
         self.save_to_cache_background().await;
 
-        Ok(())
+        Ok(reuse)
+    }
+
+    /// Scan one title directory and match (or assign) its title and entry IDs
+    /// Returns the title with the IDs that are new and still need inserting,
+    /// or None if the directory could not be scanned
+    async fn scan_title(
+        title_path: PathBuf,
+        lib_path: &Path,
+        cover_names: &[String],
+        storage: &Storage,
+        date_added_from_mtime: bool,
+        filter: &ScanFilter,
+        case_sensitive_paths: bool,
+    ) -> Option<(Title, NewIds)> {
+        // Scan title directory
+        let scanned =
+            Title::from_directory(title_path.clone(), lib_path, cover_names, filter).await;
+        let title = match scanned {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::warn!("Failed to scan title at {}: {}", title_path.display(), e);
+                return None;
+            }
+        };
+
+        Self::assign_ids(
+            title,
+            lib_path,
+            storage,
+            date_added_from_mtime,
+            case_sensitive_paths,
+        )
+        .await
     }
 
-    /// Bulk insert title and entry IDs in a single transaction
-    /// Matches the pattern from original Mango for performance
-    async fn bulk_insert_ids(
+    /// Scan the archives lying directly in the library root into one unsorted title
+    /// or a title each (config `loose_archives`), matching or assigning their IDs
+    async fn scan_loose_archives(
         &self,
-        title_ids: &[(String, String, String)], // (id, path, signature)
-        entry_ids: &[(String, String, String)], // (id, path, signature)
+        archives: Vec<PathBuf>,
+        date_added_from_mtime: bool,
+    ) -> (Vec<Title>, NewIds) {
+        let scanned = match self.loose_archives {
+            LooseArchives::Unsorted => vec![
+                Title::from_loose_archives(&self.path, archives, &self.unsorted_title_name).await,
+            ],
+            LooseArchives::Title => {
+                let mut titles = Vec::with_capacity(archives.len());
+                for archive in archives {
+                    titles.push(Title::from_loose_archive(archive).await);
+                }
+                titles
+            }
+        };
+
+        let mut titles = Vec::new();
+        let mut ids = NewIds::default();
+        for title in scanned {
+            let title = match title {
+                Ok(title)
+                    if !title.entries.is_empty()
+                        || !title.unreadable.is_empty()
+                        || !title.broken_entries.is_empty() =>
+                {
+                    title
+                }
+                Ok(_) => continue,
+                Err(e) => {
+                    tracing::warn!("Failed to scan archive in the library root: {}", e);
+                    continue;
+                }
+            };
+            let assigned = Self::assign_ids(
+                title,
+                &self.path,
+                &self.storage,
+                date_added_from_mtime,
+                self.case_sensitive_paths,
+            )
+            .await;
+            if let Some((title, title_ids)) = assigned {
+                ids.titles.extend(title_ids.titles);
+                ids.entries.extend(title_ids.entries);
+                titles.push(title);
+            }
+        }
+        (titles, ids)
+    }
+
+    /// Match (or assign) the title and entry IDs of a freshly scanned title
+    /// Returns the title with the IDs that are new and still need inserting
+    async fn assign_ids(
+        mut title: Title,
+        lib_path: &Path,
+        storage: &Storage,
+        date_added_from_mtime: bool,
+        case_sensitive_paths: bool,
+    ) -> Option<(Title, NewIds)> {
+        let mut ids = NewIds::default();
+
+        // Find or create title ID
+        let existing_id =
+            Self::find_existing_id_static(lib_path, &title, storage, case_sensitive_paths)
+                .await
+                .ok()?;
+        let is_new_title = existing_id.is_none();
+        if let Some(id) = existing_id {
+            title.id = id;
+            tracing::debug!("Matched existing title: {} ({})", title.title, title.id);
+        } else {
+            // New title - inserted when the title is checkpointed
+            let relative_path = title
+                .path
+                .strip_prefix(lib_path)
+                .ok()?
+                .to_string_lossy()
+                .to_string();
+
+            ids.titles
+                .push((title.id.clone(), relative_path, title.signature.clone()));
+            tracing::info!("Discovered new title: {} ({})", title.title, title.id);
+        }
+
+        // A loose archive title seen for the first time may hold entries that were
+        // scanned under the other `loose_archives` mode, whose info.json is elsewhere
+        let fresh_loose_info = title.source != TitleSource::Directory
+            && !tokio::fs::try_exists(title.info_dir().join("info.json"))
+                .await
+                .unwrap_or(true);
+
+        // Find or create entry IDs; (entry ID, previous relative path) of entries whose
+        // data is to be brought over from another title's info.json
+        let mut moved = Vec::new();
+        for entry in &mut title.entries {
+            let existing_entry_id =
+                Self::find_existing_entry_id_static(lib_path, entry, storage, case_sensitive_paths)
+                    .await
+                    .ok()?;
+            if let Some((id, previous_path)) = existing_entry_id {
+                entry.id = id;
+                if let Some(previous_path) = previous_path {
+                    tracing::info!(
+                        "Entry {} moved from {} to {}",
+                        entry.id,
+                        previous_path,
+                        entry.path.display()
+                    );
+                    moved.push((entry.id.clone(), previous_path));
+                } else if fresh_loose_info {
+                    let relative_path = entry.path.strip_prefix(lib_path).ok()?;
+                    let relative_path = relative_path.to_string_lossy().to_string();
+                    moved.push((entry.id.clone(), relative_path));
+                }
+            } else {
+                // New entry - inserted when the title is checkpointed
+                let relative_path = entry
+                    .path
+                    .strip_prefix(lib_path)
+                    .ok()?
+                    .to_string_lossy()
+                    .to_string();
+
+                ids.entries
+                    .push((entry.id.clone(), relative_path, entry.signature.clone()));
+
+                if is_new_title {
+                    tracing::debug!("  New entry: {} ({})", entry.title, entry.id);
+                }
+            }
+        }
+
+        // Populate date_added
+        if let Err(e) = title.populate_date_added(date_added_from_mtime).await {
+            tracing::warn!("Failed to populate date_added for {}: {}", title.title, e);
+        }
+
+        if !moved.is_empty() {
+            if let Err(e) = Self::carry_over_entry_info(&title, &moved, lib_path, storage).await {
+                tracing::warn!(
+                    "Failed to bring progress of moved entries into {}: {}",
+                    title.title,
+                    e
+                );
+            }
+        }
+
+        // Nested titles get IDs of their own, and point back at this one
+        for nested in std::mem::take(&mut title.nested_titles) {
+            let assigned = Box::pin(Self::assign_ids(
+                nested,
+                lib_path,
+                storage,
+                date_added_from_mtime,
+                case_sensitive_paths,
+            ))
+            .await;
+            if let Some((mut nested, nested_ids)) = assigned {
+                nested.parent_id = Some(title.id.clone());
+                ids.titles.extend(nested_ids.titles);
+                ids.entries.extend(nested_ids.entries);
+                title.nested_titles.push(nested);
+            }
+        }
+
+        Some((title, ids))
+    }
+
+    /// Copy progress, read counts, dates and overrides of entries that moved into
+    /// `title` from the info.json they were recorded in, and point their recently read
+    /// rows at the new title. The old info.json is left as is: another scan task may
+    /// be updating it, and data of entries no longer in a title is never read
+    async fn carry_over_entry_info(
+        title: &Title,
+        moved: &[(String, String)],
+        lib_path: &Path,
+        storage: &Storage,
     ) -> Result<()> {
-        let mut tx = self.storage.pool().begin().await?;
+        let info_dir = title.info_dir();
+        let mut info = TitleInfo::load(&info_dir).await?;
+        for (entry_id, previous_path) in moved {
+            for dir in super::title::entry_info_dirs(lib_path, previous_path) {
+                if dir != info_dir {
+                    info.copy_entry(&TitleInfo::load(&dir).await?, entry_id);
+                }
+            }
+            storage.move_recently_read(entry_id, &title.id).await?;
+        }
+        info.save(&info_dir).await
+    }
+
+    /// Whether a title finished by an interrupted scan can be reused as is
+    /// Its directory must still have the same archive names and file signatures, its
+    /// entries the same modification times, and files that could not be read are retried
+    async fn journaled_title_unchanged(title_path: &Path, previous: &CompletedTitle) -> bool {
+        if previous
+            .title
+            .deep_titles()
+            .iter()
+            .any(|t| !t.unreadable.is_empty())
+        {
+            return false;
+        }
+        let signatures_match = match super::title::title_signatures(title_path).await {
+            Ok((signature, contents)) => {
+                contents == previous.contents_signature && signature == previous.title.signature
+            }
+            Err(_) => false,
+        };
+        signatures_match && super::title::entries_unmodified(&previous.title).await
+    }
 
+    /// Insert new title and entry IDs (run inside the caller's transaction)
+    /// An existing row at the same path is taken over by the new ID
+    pub(super) async fn insert_ids(conn: &mut sqlx::SqliteConnection, ids: &NewIds) -> Result<()> {
         // Insert all title IDs
-        for (id, path, signature) in title_ids {
+        for (id, path, signature) in &ids.titles {
             sqlx::query(
                 "INSERT INTO titles (id, path, signature, unavailable) VALUES (?, ?, ?, 0)
                  ON CONFLICT(path) DO UPDATE SET id = ?, signature = ?, unavailable = 0",
@@ -273,12 +891,12 @@ impl Library {
             .bind(signature)
             .bind(id)
             .bind(signature)
-            .execute(&mut *tx)
+            .execute(&mut *conn)
             .await?;
         }
 
         // Insert all entry IDs
-        for (id, path, signature) in entry_ids {
+        for (id, path, signature) in &ids.entries {
             sqlx::query(
                 "INSERT INTO ids (id, path, signature, unavailable) VALUES (?, ?, ?, 0)
                  ON CONFLICT(path) DO UPDATE SET id = ?, signature = ?, unavailable = 0",
@@ -288,11 +906,10 @@ impl Library {
             .bind(signature)
             .bind(id)
             .bind(signature)
-            .execute(&mut *tx)
+            .execute(&mut *conn)
             .await?;
         }
 
-        tx.commit().await?;
         Ok(())
     }
 
@@ -301,6 +918,7 @@ impl Library {
         library_path: &Path,
         title: &Title,
         storage: &Storage,
+        case_sensitive_paths: bool,
     ) -> Result<Option<String>> {
         let relative_path = title
             .path
@@ -314,25 +932,36 @@ impl Library {
                 ))
             })?;
 
+        let path_eq = Self::path_eq(case_sensitive_paths);
+
         // Tier 1: Exact match
-        if let Some(id) = sqlx::query_scalar::<_, String>(
-            "SELECT id FROM titles WHERE path = ? AND signature = ? AND unavailable = 0",
-        )
-        .bind(&relative_path)
-        .bind(&title.signature)
-        .fetch_optional(storage.pool())
-        .await?
+        let sql = format!(
+            "SELECT id, path FROM titles WHERE {} AND signature = ? AND unavailable = 0
+             ORDER BY path = ? DESC",
+            path_eq
+        );
+        if let Some((id, path)) = sqlx::query_as::<_, (String, String)>(&sql)
+            .bind(&relative_path)
+            .bind(&title.signature)
+            .bind(&relative_path)
+            .fetch_optional(storage.pool())
+            .await?
         {
+            Self::follow_path_case(storage, "titles", &id, &path, &relative_path).await?;
             return Ok(Some(id));
         }
 
-        // Tier 2: Path-only match
-        if let Some(id) = sqlx::query_scalar::<_, String>(
-            "SELECT id FROM titles WHERE path = ? AND unavailable = 0",
-        )
-        .bind(&relative_path)
-        .fetch_optional(storage.pool())
-        .await?
+        // Tier 2: Path-only match; a title hidden or gone for a while gets its ID back,
+        // and is marked available again once the scan finishes
+        let sql = format!(
+            "SELECT id, path FROM titles WHERE {} ORDER BY unavailable, path = ? DESC",
+            path_eq
+        );
+        if let Some((id, path)) = sqlx::query_as::<_, (String, String)>(&sql)
+            .bind(&relative_path)
+            .bind(&relative_path)
+            .fetch_optional(storage.pool())
+            .await?
         {
             // Update signature
             sqlx::query("UPDATE titles SET signature = ? WHERE id = ?")
@@ -340,6 +969,7 @@ impl Library {
                 .bind(&id)
                 .execute(storage.pool())
                 .await?;
+            Self::follow_path_case(storage, "titles", &id, &path, &relative_path).await?;
 
             return Ok(Some(id));
         }
@@ -347,12 +977,46 @@ impl Library {
         Ok(None)
     }
 
+    /// Condition on the `path` column matching a bound relative path; on filesystems that
+    /// ignore case, a directory renamed to other case is the same directory
+    /// SQLite's NOCASE folds ASCII letters only
+    fn path_eq(case_sensitive_paths: bool) -> &'static str {
+        if case_sensitive_paths {
+            "path = ?"
+        } else {
+            "path = ? COLLATE NOCASE"
+        }
+    }
+
+    /// Store the path a row matched ignoring case was found under, so it matches exactly
+    /// from then on
+    async fn follow_path_case(
+        storage: &Storage,
+        table: &str,
+        id: &str,
+        stored: &str,
+        found: &str,
+    ) -> Result<()> {
+        if stored != found {
+            tracing::info!("{} is now spelled {}", stored, found);
+            let sql = format!("UPDATE OR IGNORE {} SET path = ? WHERE id = ?", table);
+            sqlx::query(&sql)
+                .bind(found)
+                .bind(id)
+                .execute(storage.pool())
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Static helper for finding existing entry ID (for use in spawned tasks)
+    /// Returns the ID, and the entry's previous relative path if it was found moved
     async fn find_existing_entry_id_static(
         library_path: &Path,
         entry: &Entry,
         storage: &Storage,
-    ) -> Result<Option<String>> {
+        case_sensitive_paths: bool,
+    ) -> Result<Option<(String, Option<String>)>> {
         let relative_path = entry
             .path
             .strip_prefix(library_path)
@@ -365,24 +1029,35 @@ impl Library {
                 ))
             })?;
 
+        let path_eq = Self::path_eq(case_sensitive_paths);
+
         // Tier 1: Exact match
-        if let Some(id) = sqlx::query_scalar::<_, String>(
-            "SELECT id FROM ids WHERE path = ? AND signature = ? AND unavailable = 0",
-        )
-        .bind(&relative_path)
-        .bind(&entry.signature)
-        .fetch_optional(storage.pool())
-        .await?
+        let sql = format!(
+            "SELECT id, path FROM ids WHERE {} AND signature = ? AND unavailable = 0
+             ORDER BY path = ? DESC",
+            path_eq
+        );
+        if let Some((id, path)) = sqlx::query_as::<_, (String, String)>(&sql)
+            .bind(&relative_path)
+            .bind(&entry.signature)
+            .bind(&relative_path)
+            .fetch_optional(storage.pool())
+            .await?
         {
-            return Ok(Some(id));
+            Self::follow_path_case(storage, "ids", &id, &path, &relative_path).await?;
+            return Ok(Some((id, None)));
         }
 
-        // Tier 2: Path-only match
-        if let Some(id) =
-            sqlx::query_scalar::<_, String>("SELECT id FROM ids WHERE path = ? AND unavailable = 0")
-                .bind(&relative_path)
-                .fetch_optional(storage.pool())
-                .await?
+        // Tier 2: Path-only match, taking back the ID of an entry that was gone
+        let sql = format!(
+            "SELECT id, path FROM ids WHERE {} ORDER BY unavailable, path = ? DESC",
+            path_eq
+        );
+        if let Some((id, path)) = sqlx::query_as::<_, (String, String)>(&sql)
+            .bind(&relative_path)
+            .bind(&relative_path)
+            .fetch_optional(storage.pool())
+            .await?
         {
             // Update signature
             sqlx::query("UPDATE ids SET signature = ? WHERE id = ?")
@@ -390,8 +1065,45 @@ impl Library {
                 .bind(&id)
                 .execute(storage.pool())
                 .await?;
+            Self::follow_path_case(storage, "ids", &id, &path, &relative_path).await?;
 
-            return Ok(Some(id));
+            return Ok(Some((id, None)));
+        }
+
+        // Tier 3: Signature-only match, for a file moved to another directory
+        // Only taken when exactly one known entry has the signature and its file is
+        // gone, so copies and hard links keep IDs of their own
+        let candidates = sqlx::query_as::<_, (String, String)>(
+            "SELECT id, path FROM ids WHERE signature = ? AND path != ?",
+        )
+        .bind(&entry.signature)
+        .bind(&relative_path)
+        .fetch_all(storage.pool())
+        .await?;
+        let mut moved = Vec::new();
+        for (id, path) in candidates {
+            let exists = tokio::fs::try_exists(library_path.join(&path))
+                .await
+                .unwrap_or(true);
+            if !exists {
+                moved.push((id, path));
+            }
+        }
+        if let [(id, previous_path)] = moved.as_slice() {
+            // Another scan task may have claimed the row first
+            let updated = sqlx::query(
+                "UPDATE OR IGNORE ids SET path = ?, signature = ?, unavailable = 0,
+                 unavailable_since = NULL WHERE id = ? AND path = ?",
+            )
+            .bind(&relative_path)
+            .bind(&entry.signature)
+            .bind(id)
+            .bind(previous_path)
+            .execute(storage.pool())
+            .await?;
+            if updated.rows_affected() == 1 {
+                return Ok(Some((id.clone(), Some(previous_path.clone()))));
+            }
         }
 
         Ok(None)
@@ -405,18 +1117,18 @@ impl Library {
             titles: self.titles.clone(),
         };
 
-        // Get file manager for background save
-        let file_manager = {
+        // Get store manager for background save
+        let store_manager = {
             let cache = self.cache.lock().await;
-            if cache.stats().size_limit == 0 {
-                return; // Cache disabled
+            if !cache.file_enabled() {
+                return;
             }
-            cache.file_manager()
+            cache.store_manager()
         };
 
         // Spawn background task to save cache (non-blocking)
         tokio::spawn(async move {
-            match file_manager.save_data(cached_data).await {
+            match store_manager.save_data(cached_data).await {
                 Ok(_) => tracing::info!("Library cache saved successfully in background"),
                 Err(e) => tracing::warn!("Failed to save library cache in background: {}", e),
             }
@@ -543,6 +1255,13 @@ impl Library {
         Ok(())
     }
 
+    /// Title index (id, name, entry count), shared until the next scan swaps the library
+    pub fn index(&self) -> Arc<LibraryIndex> {
+        self.index
+            .get_or_init(|| Arc::new(LibraryIndex::build(self.titles.values())))
+            .clone()
+    }
+
     /// Get all titles (sorted by name)
     pub fn get_titles(&self) -> Vec<&Title> {
         self.get_titles_sorted(SortMethod::default(), true)
@@ -552,14 +1271,20 @@ impl Library {
     pub fn get_titles_sorted(&self, method: SortMethod, ascending: bool) -> Vec<&Title> {
         let mut titles: Vec<&Title> = self.titles.values().collect();
 
-        use super::{sort_by_mtime, sort_by_name};
+        use super::{sort_by_auto, sort_by_mtime, sort_by_name};
 
         match method {
-            SortMethod::Name | SortMethod::Progress | SortMethod::Auto => {
-                // Progress sorting is handled at route level (after calculating progress with username context)
-                // Auto uses name sorting (future: smart chapter detection)
+            SortMethod::Name
+            | SortMethod::Progress
+            | SortMethod::Popularity
+            | SortMethod::Opened => {
+                // Progress and popularity sorting are handled at route level (they need per-user
+                // progress or read statistics); last opened only applies to entries
                 sort_by_name(&mut titles, ascending);
             }
+            SortMethod::Auto => {
+                sort_by_auto(&mut titles, ascending);
+            }
             SortMethod::TimeModified => {
                 sort_by_mtime(&mut titles, ascending);
             }
@@ -585,6 +1310,8 @@ impl Library {
             SortMethod::TimeModified => "modified",
             SortMethod::Progress => "progress",
             SortMethod::Auto => "auto",
+            SortMethod::Popularity => "popularity",
+            SortMethod::Opened => "opened",
         };
 
         // Acquire lock for entire cache operation (check-compute-store)
@@ -620,35 +1347,212 @@ impl Library {
         let sorted_ids: Vec<String> = sorted_titles.iter().map(|t| t.id.clone()).collect();
 
         // Store result (still holding lock)
-        cache.set_sorted_titles(cache_key, sorted_ids);
+        cache.set_sorted_titles(cache_key, username, sorted_ids);
         drop(cache);
 
         sorted_titles
     }
 
-    /// Get a specific title by ID
+    /// Get a specific title by ID, top-level or nested
+    /// Nested titles are found by walking the titles holding them
     pub fn get_title(&self, id: &str) -> Option<&Title> {
-        self.titles.get(id)
+        self.titles
+            .get(id)
+            .or_else(|| self.titles.values().find_map(|t| t.find_title(id)))
+    }
+
+    /// Sibling title directories whose names differ only by case or surrounding
+    /// whitespace, listed under names that tell them apart
+    pub fn name_collisions(&self) -> &[NameCollision] {
+        &self.name_collisions
+    }
+
+    /// Warn about the name collisions found, as they usually are a mistake
+    fn log_name_collisions(&self) {
+        for collision in &self.name_collisions {
+            tracing::warn!(
+                "Title directories named like {:?} differ only by case or whitespace: {}",
+                collision.name,
+                collision.paths.join(", ")
+            );
+        }
+    }
+
+    /// Entries left out of every title because they have no pages
+    pub fn broken_entries(&self) -> impl Iterator<Item = &Entry> {
+        self.all_titles().flat_map(|t| &t.broken_entries)
+    }
+
+    /// Top-level titles and all titles nested in them, in no particular order
+    /// For walking every entry; `get_titles` lists what the library page shows
+    pub fn all_titles(&self) -> impl Iterator<Item = &Title> {
+        self.titles.values().flat_map(|t| t.deep_titles())
     }
 
     /// Get a specific entry by title ID and entry ID
     pub fn get_entry(&self, title_id: &str, entry_id: &str) -> Option<&Entry> {
-        self.titles
-            .get(title_id)?
+        self.get_title(title_id)?
             .entries
             .iter()
             .find(|e| e.id == entry_id)
     }
 
-    /// Get sorted entries for a title with caching
-    pub async fn get_entries_sorted_cached(
+    /// Find an entry by its ID alone, with the title it belongs to
+    /// Walks every title, so prefer `get_entry` when the title ID is known
+    pub fn find_entry(&self, entry_id: &str) -> Option<(&Title, &Entry)> {
+        self.all_titles().find_map(|title| {
+            title
+                .entries
+                .iter()
+                .find(|e| e.id == entry_id)
+                .map(|entry| (title, entry))
+        })
+    }
+
+    /// Get the pinned entry order for a title (None if no override is set)
+    pub fn get_entry_order(&self, title_id: &str) -> Option<Vec<String>> {
+        self.progress_cache.get_entry_order(title_id)
+    }
+
+    /// Set (or clear, with None) the reading direction override of a title or one of its entries
+    /// Persists to the title's info.json
+    pub async fn set_direction(
         &self,
         title_id: &str,
-        username: &str,
-        method: SortMethod,
+        entry_id: Option<&str>,
+        direction: Option<ReadingDirection>,
+    ) -> Result<()> {
+        let title = self.get_title(title_id).ok_or_else(|| {
+            crate::error::Error::NotFound(format!("Title not found: {}", title_id))
+        })?;
+        if let Some(entry_id) = entry_id {
+            if !title.entries.iter().any(|e| e.id == entry_id) {
+                return Err(crate::error::Error::NotFound(format!(
+                    "Entry not found: {}",
+                    entry_id
+                )));
+            }
+        }
+
+        self.progress_cache
+            .save_direction(title_id, &title.info_dir(), entry_id, direction)
+            .await
+    }
+
+    /// Get entries for a title in display order
+    /// A pinned entry order takes precedence over every sort method
+    pub fn get_entries_sorted<'a>(
+        &self,
+        title: &'a Title,
+        method: SortMethod,
+        ascending: bool,
+    ) -> Vec<&'a Entry> {
+        match self.get_entry_order(&title.id) {
+            Some(order) => title.get_entries_pinned(&order),
+            None => title.get_entries_sorted(method, ascending),
+        }
+    }
+
+    /// Get entries for a title in display order for one user
+    /// Same as `get_entries_sorted`, but also resolves last opened sorting
+    pub fn get_entries_sorted_for<'a>(
+        &self,
+        title: &'a Title,
+        username: &str,
+        method: SortMethod,
+        ascending: bool,
+    ) -> Vec<&'a Entry> {
+        match self.get_entry_order(&title.id) {
+            Some(order) => title.get_entries_pinned(&order),
+            None if method == SortMethod::Opened => self.sort_by_opened(title, username, ascending),
+            None => title.get_entries_sorted(method, ascending),
+        }
+    }
+
+    /// A title's entries by when the user last opened them
+    /// Entries never opened follow in name order, whichever the direction
+    fn sort_by_opened<'a>(
+        &self,
+        title: &'a Title,
+        username: &str,
+        ascending: bool,
+    ) -> Vec<&'a Entry> {
+        let mut entries: Vec<_> = title
+            .get_entries_sorted(SortMethod::Name, true)
+            .into_iter()
+            .map(|e| {
+                let opened_at = self
+                    .progress_cache
+                    .get_opened_at(&title.id, username, &e.id);
+                (opened_at, e)
+            })
+            .collect();
+
+        // Stable, so ties keep name order
+        entries.sort_by(|(a, _), (b, _)| match (a, b) {
+            (Some(a), Some(b)) if ascending => a.cmp(b),
+            (Some(a), Some(b)) => b.cmp(a),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+
+        entries.into_iter().map(|(_, e)| e).collect()
+    }
+
+    /// Record a user opening an entry in the reader (throttled, see `TitleInfo::record_opened`)
+    /// Persists to the title's info.json when recorded
+    pub async fn record_opened(
+        &self,
+        title_id: &str,
+        username: &str,
+        entry_id: &str,
+    ) -> Result<()> {
+        let title = self.get_title(title_id).ok_or_else(|| {
+            crate::error::Error::NotFound(format!("Title not found: {}", title_id))
+        })?;
+
+        let recorded = self
+            .progress_cache
+            .record_opened(title_id, &title.info_dir(), username, entry_id)
+            .await?;
+
+        // Cached entry orders don't know about opens
+        if recorded {
+            let mut cache = self.cache.lock().await;
+            cache.invalidate_sorted_for_title(title_id);
+        }
+
+        Ok(())
+    }
+
+    /// Pin (or clear, with an empty list) the entry order for a title
+    /// Persists to the title's info.json
+    pub async fn set_entry_order(&self, title_id: &str, entry_order: Vec<String>) -> Result<()> {
+        let title = self.get_title(title_id).ok_or_else(|| {
+            crate::error::Error::NotFound(format!("Title not found: {}", title_id))
+        })?;
+
+        self.progress_cache
+            .save_entry_order(title_id, &title.info_dir(), entry_order)
+            .await?;
+
+        let mut cache = self.cache.lock().await;
+        cache.invalidate_sorted_for_title(title_id);
+
+        Ok(())
+    }
+
+    /// Get sorted entries for a title with caching
+    pub async fn get_entries_sorted_cached(
+        &self,
+        title_id: &str,
+        username: &str,
+        method: SortMethod,
         ascending: bool,
     ) -> Option<Vec<&Entry>> {
-        let title = self.titles.get(title_id)?;
+        let title = self.get_title(title_id)?;
+        let entry_order = self.get_entry_order(title_id);
 
         // Generate cache key signature from current entry IDs
         let mut all_entry_ids: Vec<String> = title.entries.iter().map(|e| e.id.clone()).collect();
@@ -659,6 +1563,8 @@ impl Library {
             SortMethod::TimeModified => "modified",
             SortMethod::Progress => "progress",
             SortMethod::Auto => "auto",
+            SortMethod::Popularity => "popularity",
+            SortMethod::Opened => "opened",
         };
 
         // Acquire lock for entire cache operation (check-compute-store)
@@ -671,6 +1577,7 @@ impl Library {
             &all_entry_ids,
             sort_method_str,
             ascending,
+            entry_order.as_deref(),
         );
 
         if let Some(cached_ids) = cache.get_sorted_entries(&cache_key) {
@@ -689,18 +1596,372 @@ impl Library {
         // Cache miss - compute sort while holding lock
         // Sorting is fast (<1ms for typical entry counts), so lock contention is acceptable
         // This ensures atomicity of check-compute-store operation
-        let sorted_entries = title.get_entries_sorted(method, ascending);
+        let sorted_entries = self.get_entries_sorted_for(title, username, method, ascending);
 
         // Extract IDs in sorted order
         let sorted_ids: Vec<String> = sorted_entries.iter().map(|e| e.id.clone()).collect();
 
         // Store result (still holding lock)
-        cache.set_sorted_entries(cache_key, sorted_ids);
+        cache.set_sorted_entries(cache_key, title_id, username, sorted_ids);
         drop(cache);
 
         Some(sorted_entries)
     }
 
+    /// Save reading progress for an entry
+    /// Updates the progress cache, the title's info.json and the recently read index
+    pub async fn save_progress(
+        &self,
+        title_id: &str,
+        username: &str,
+        entry_id: &str,
+        page: i32,
+    ) -> Result<()> {
+        let info_dir = self
+            .get_title(title_id)
+            .map(Title::info_dir)
+            .ok_or_else(|| {
+                crate::error::Error::NotFound(format!("Title not found: {}", title_id))
+            })?;
+        let total_pages = self
+            .get_entry(title_id, entry_id)
+            .map(|e| e.pages)
+            .unwrap_or(0);
+
+        self.progress_cache
+            .save_progress(title_id, &info_dir, username, entry_id, page, total_pages)
+            .await?;
+
+        // info.json stays the source of truth, so a failed index update is only logged
+        let last_read = self
+            .progress_cache
+            .get_last_read(title_id, username, entry_id)
+            .unwrap_or_else(crate::util::time::now_ts);
+        if let Err(e) = self
+            .storage
+            .record_recently_read(username, title_id, entry_id, page, last_read)
+            .await
+        {
+            tracing::warn!(
+                "Failed to update recently read index for {} / {}: {}",
+                title_id,
+                entry_id,
+                e
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Mark several entries of one title as read to the last page
+    /// Same bookkeeping as `save_progress`, but info.json is written once for the batch
+    pub async fn mark_entries_read(
+        &self,
+        title_id: &str,
+        username: &str,
+        entry_ids: &[&str],
+    ) -> Result<()> {
+        let info_dir = self
+            .get_title(title_id)
+            .map(Title::info_dir)
+            .ok_or_else(|| {
+                crate::error::Error::NotFound(format!("Title not found: {}", title_id))
+            })?;
+        let updates: Vec<(&str, i32, usize)> = entry_ids
+            .iter()
+            .filter_map(|id| self.get_entry(title_id, id))
+            .map(|e| (e.id.as_str(), e.pages as i32, e.pages))
+            .collect();
+        if updates.is_empty() {
+            return Ok(());
+        }
+
+        self.progress_cache
+            .save_progress_batch(title_id, &info_dir, username, &updates)
+            .await?;
+
+        for (entry_id, page, _) in &updates {
+            let last_read = self
+                .progress_cache
+                .get_last_read(title_id, username, entry_id)
+                .unwrap_or_else(crate::util::time::now_ts);
+            if let Err(e) = self
+                .storage
+                .record_recently_read(username, title_id, entry_id, *page, last_read)
+                .await
+            {
+                tracing::warn!(
+                    "Failed to update recently read index for {} / {}: {}",
+                    title_id,
+                    entry_id,
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get a user's most recently read entries (newest first), keeping only
+    /// those accepted by `filter` (called with the entry and its saved page)
+    /// Served from the recently read index; until the index has been backfilled
+    /// this falls back to walking the progress cache for every title
+    pub async fn get_recently_read<F>(
+        &self,
+        username: &str,
+        limit: usize,
+        filter: F,
+    ) -> Result<Vec<RecentlyReadEntry<'_>>>
+    where
+        F: Fn(&Entry, i32) -> bool,
+    {
+        if !self.storage.has_recently_read().await? {
+            return Ok(self.get_recently_read_uncached(username, limit, filter));
+        }
+
+        let mut result = Vec::with_capacity(limit);
+        let mut offset = 0;
+
+        while result.len() < limit {
+            let rows = self
+                .storage
+                .list_recently_read(username, RECENTLY_READ_BATCH, offset)
+                .await?;
+            let exhausted = rows.len() < RECENTLY_READ_BATCH;
+            offset += rows.len();
+
+            for row in rows {
+                // Rows for titles/entries that have since been removed are skipped
+                let Some(title) = self.get_title(&row.title_id) else {
+                    continue;
+                };
+                let Some(entry) = title.entries.iter().find(|e| e.id == row.entry_id) else {
+                    continue;
+                };
+
+                if filter(entry, row.page) {
+                    result.push(RecentlyReadEntry {
+                        title,
+                        entry,
+                        page: row.page,
+                        last_read: row.last_read,
+                    });
+                    if result.len() == limit {
+                        break;
+                    }
+                }
+            }
+
+            if exhausted {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Get the titles a user read most recently (newest first), by their last read entry
+    pub async fn get_recently_read_titles(
+        &self,
+        username: &str,
+        limit: usize,
+    ) -> Result<Vec<&Title>> {
+        // A title shows up once per entry read, so fetch more entries until enough
+        // distinct titles turn up or the user's history runs out
+        let mut fetch = limit.max(1) * 4;
+        loop {
+            let read = self.get_recently_read(username, fetch, |_, _| true).await?;
+            let exhausted = read.len() < fetch;
+
+            let mut titles: Vec<&Title> = Vec::with_capacity(limit);
+            for item in read {
+                if !titles.iter().any(|t| t.id == item.title.id) {
+                    titles.push(item.title);
+                }
+            }
+
+            if titles.len() >= limit || exhausted {
+                titles.truncate(limit);
+                return Ok(titles);
+            }
+            fetch *= 4;
+        }
+    }
+
+    /// Where to go on once a title is finished: the first entry of its sequel, in the
+    /// sequel's display order (sequels are linked by admins, see `Storage::set_title_relations`)
+    /// With `username`, the first entry the user has not finished is picked, and a sequel
+    /// the user has read to the end makes way for its own sequel
+    /// A sequel missing from the library ends the chain
+    pub async fn sequel_start(
+        &self,
+        title_id: &str,
+        username: Option<&str>,
+    ) -> Result<Option<(&Title, &Entry)>> {
+        let mut current = title_id.to_string();
+        let mut seen = std::collections::HashSet::from([current.clone()]);
+        while let Some(sequel_id) = self.storage.get_sequel(&current).await? {
+            if !seen.insert(sequel_id.clone()) {
+                break;
+            }
+            let Some(sequel) = self.get_title(&sequel_id) else {
+                break;
+            };
+
+            let entries = self.get_entries_sorted(sequel, SortMethod::Name, true);
+            let start = match username {
+                Some(username) => entries.into_iter().find(|entry| {
+                    let page = self
+                        .progress_cache
+                        .get_progress(&sequel.id, username, &entry.id)
+                        .unwrap_or(0);
+                    self.entry_percentage(entry, page) < 100.0
+                }),
+                None => entries.into_iter().next(),
+            };
+            if let Some(entry) = start {
+                return Ok(Some((sequel, entry)));
+            }
+            current = sequel_id;
+        }
+
+        Ok(None)
+    }
+
+    /// Get the titles with the most recently added entries (newest first)
+    /// Entries added to a nested title count for the top-level title holding it
+    pub fn get_recently_added_titles(&self, limit: usize) -> Vec<&Title> {
+        let mut titles: Vec<(&Title, i64)> = self
+            .titles
+            .values()
+            .filter_map(|title| {
+                let added = title
+                    .deep_titles()
+                    .into_iter()
+                    .flat_map(|t| {
+                        t.entries
+                            .iter()
+                            .filter_map(|e| self.progress_cache.get_date_added(&t.id, &e.id))
+                    })
+                    .max()?;
+                Some((title, added))
+            })
+            .collect();
+
+        titles.sort_by(|(a, a_added), (b, b_added)| {
+            b_added.cmp(a_added).then_with(|| a.title.cmp(&b.title))
+        });
+        titles.truncate(limit);
+        titles.into_iter().map(|(title, _)| title).collect()
+    }
+
+    /// Slow path for get_recently_read: checks every entry in the progress cache
+    fn get_recently_read_uncached<F>(
+        &self,
+        username: &str,
+        limit: usize,
+        filter: F,
+    ) -> Vec<RecentlyReadEntry<'_>>
+    where
+        F: Fn(&Entry, i32) -> bool,
+    {
+        let mut result = Vec::new();
+
+        for title in self.all_titles() {
+            for entry in &title.entries {
+                let Some(last_read) = self
+                    .progress_cache
+                    .get_last_read(&title.id, username, &entry.id)
+                else {
+                    continue;
+                };
+                let page = self
+                    .progress_cache
+                    .get_progress(&title.id, username, &entry.id)
+                    .unwrap_or(0);
+
+                if filter(entry, page) {
+                    result.push(RecentlyReadEntry {
+                        title,
+                        entry,
+                        page,
+                        last_read,
+                    });
+                }
+            }
+        }
+
+        result.sort_by(|a, b| {
+            b.last_read
+                .cmp(&a.last_read)
+                .then_with(|| a.entry.id.cmp(&b.entry.id))
+        });
+        result.truncate(limit);
+        result
+    }
+
+    /// Populate the recently read index from the progress cache
+    /// Runs once: does nothing if the index already has rows
+    async fn backfill_recently_read(&self) -> Result<()> {
+        if self.storage.has_recently_read().await? {
+            return Ok(());
+        }
+
+        let mut rows = Vec::new();
+        for title_id in self.all_titles().map(|t| &t.id) {
+            let Some(info) = self.progress_cache.get_title_info(title_id) else {
+                continue;
+            };
+
+            for (username, entries) in &info.last_read {
+                for entry_id in entries.keys() {
+                    let Some(last_read) = info.get_last_read(username, entry_id) else {
+                        continue;
+                    };
+                    rows.push(RecentlyRead {
+                        username: username.clone(),
+                        title_id: title_id.clone(),
+                        entry_id: entry_id.clone(),
+                        page: info.get_progress(username, entry_id).unwrap_or(0),
+                        last_read,
+                    });
+                }
+            }
+        }
+
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let inserted = self.storage.backfill_recently_read(&rows).await?;
+        tracing::info!("Backfilled recently read index with {} entries", inserted);
+        Ok(())
+    }
+
+    /// Get overall title progress for a user (average across all entries, nested
+    /// titles' included)
+    /// Uses the progress cache, so no info.json is read
+    pub fn get_title_progress(&self, title: &Title, username: &str) -> f32 {
+        let percentages: Vec<f32> = title
+            .deep_titles()
+            .into_iter()
+            .flat_map(|t| {
+                t.entries.iter().map(|entry| {
+                    let page = self
+                        .progress_cache
+                        .get_progress(&t.id, username, &entry.id)
+                        .unwrap_or(0);
+                    self.entry_percentage(entry, page)
+                })
+            })
+            .collect();
+
+        if percentages.is_empty() {
+            return 0.0;
+        }
+        percentages.iter().sum::<f32>() / percentages.len() as f32
+    }
+
     /// Get library root path
     pub fn path(&self) -> &Path {
         &self.path
@@ -728,8 +1989,9 @@ impl Library {
         let mut loaded = 0;
         let mut errors = 0;
 
-        for (title_id, title) in &self.titles {
-            match self.progress_cache.load_title(title_id, &title.path).await {
+        for title in self.all_titles() {
+            let (title_id, info_dir) = (&title.id, title.info_dir());
+            match self.progress_cache.load_title(title_id, &info_dir).await {
                 Ok(_) => loaded += 1,
                 Err(e) => {
                     tracing::warn!("Failed to load progress cache for title {}: {}", title_id, e);
@@ -744,18 +2006,188 @@ impl Library {
             start.elapsed().as_secs_f64() * 1000.0,
             errors
         );
+
+        if let Err(e) = self.backfill_recently_read().await {
+            tracing::warn!("Failed to backfill recently read index: {}", e);
+        }
+    }
+
+    /// Load page validation results for entries with bad pages
+    async fn load_bad_pages(&self) {
+        let checks = match self.storage.list_bad_pages().await {
+            Ok(checks) => checks,
+            Err(e) => {
+                tracing::warn!("Failed to load bad pages: {}", e);
+                return;
+            }
+        };
+
+        if let Ok(mut bad_pages) = self.bad_pages.write() {
+            for check in checks {
+                bad_pages.insert(check.entry_id, check.bad_pages);
+            }
+        }
+    }
+
+    /// Get the bad page numbers (one-based) of an entry
+    /// Empty if the entry was never checked or all pages decode
+    pub fn get_bad_pages(&self, entry_id: &str) -> Vec<usize> {
+        self.bad_pages
+            .read()
+            .ok()
+            .and_then(|bad_pages| bad_pages.get(entry_id).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Validate the pages of an entry, reusing the stored result if the page count is unchanged
+    /// Returns the bad page numbers (one-based)
+    pub async fn check_entry_pages(
+        &self,
+        entry: &Entry,
+        archives: &super::ArchiveCache,
+    ) -> Result<Vec<usize>> {
+        if let Some(check) = self.storage.get_page_check(&entry.id).await? {
+            if check.pages == entry.pages {
+                return Ok(check.bad_pages);
+            }
+        }
+
+        let bad = entry.find_bad_pages(archives).await;
+        self.storage
+            .save_page_check(&entry.id, entry.pages, &bad)
+            .await?;
+
+        if !bad.is_empty() {
+            tracing::warn!(
+                "Entry {} has {} bad page(s): {:?}",
+                entry.title,
+                bad.len(),
+                bad
+            );
+        }
+
+        if let Ok(mut bad_pages) = self.bad_pages.write() {
+            if bad.is_empty() {
+                bad_pages.remove(&entry.id);
+            } else {
+                bad_pages.insert(entry.id.clone(), bad.clone());
+            }
+        }
+
+        Ok(bad)
+    }
+
+    /// Turn a failed extraction of page `page` (0-indexed) into `Error::PageUnreadable`,
+    /// recording it as a bad page of the entry
+    /// Other errors (e.g. the archive is gone) are returned as they are
+    pub async fn page_unreadable(
+        &self,
+        entry: &Entry,
+        page: usize,
+        error: crate::error::Error,
+    ) -> crate::error::Error {
+        let error = match error {
+            crate::error::Error::Archive(e) => e,
+            other => return other,
+        };
+        let (part, part_page) = match entry.page_source(page) {
+            Ok(source) => source,
+            Err(_) => return crate::error::Error::Archive(error),
+        };
+        let unreadable = crate::error::PageUnreadable {
+            archive: crate::util::relative_to_root(&part.path, &self.path),
+            member: part.image_name(part_page).unwrap_or_default().to_string(),
+            page: page + 1,
+            reason: error.to_string(),
+        };
+
+        if self.should_log_page_error(&entry.id) {
+            tracing::warn!(
+                "Page {} of {} is unreadable (member {}): {}",
+                unreadable.page,
+                unreadable.archive,
+                unreadable.member,
+                unreadable.reason
+            );
+        } else {
+            tracing::debug!(
+                "Page {} of {} is unreadable: {}",
+                unreadable.page,
+                unreadable.archive,
+                unreadable.reason
+            );
+        }
+        if let Err(e) = self.record_bad_page(entry, page + 1).await {
+            tracing::warn!("Failed to record bad page of entry {}: {}", entry.id, e);
+        }
+
+        crate::error::Error::PageUnreadable(Box::new(unreadable))
     }
 
-    /// Get all titles as a HashMap
+    /// Whether an unreadable page of an entry is due to be logged at warn level
+    fn should_log_page_error(&self, entry_id: &str) -> bool {
+        let mut logged = match self.page_errors_logged.lock() {
+            Ok(logged) => logged,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let now = std::time::Instant::now();
+        match logged.get(entry_id) {
+            Some(last) if now.duration_since(*last) < PAGE_ERROR_LOG_INTERVAL => false,
+            _ => {
+                logged.insert(entry_id.to_string(), now);
+                true
+            }
+        }
+    }
+
+    /// Add a page (one-based) found unreadable while serving it to the entry's bad pages
+    /// Extends a stored check of the current page count; otherwise it is saved with a page
+    /// count of 0, so page validation still checks the whole entry when it runs
+    async fn record_bad_page(&self, entry: &Entry, page: usize) -> Result<()> {
+        let mut bad = self.get_bad_pages(&entry.id);
+        if bad.contains(&page) {
+            return Ok(());
+        }
+        bad.push(page);
+        bad.sort_unstable();
+
+        let pages = match self.storage.get_page_check(&entry.id).await? {
+            Some(check) if check.pages == entry.pages => entry.pages,
+            _ => 0,
+        };
+        self.storage.save_page_check(&entry.id, pages, &bad).await?;
+
+        if let Ok(mut bad_pages) = self.bad_pages.write() {
+            bad_pages.insert(entry.id.clone(), bad);
+        }
+        Ok(())
+    }
+
+    /// Reading progress of an entry as a percentage (0.0 - 100.0)
+    /// Bad pages are left out, so percentages reflect only readable pages
+    pub fn entry_percentage(&self, entry: &Entry, page: i32) -> f32 {
+        let bad = self.get_bad_pages(&entry.id);
+        let good_pages = entry.pages.saturating_sub(bad.len());
+        if good_pages == 0 {
+            return 0.0;
+        }
+
+        let page = page.max(0) as usize;
+        let good_read = page.saturating_sub(bad.iter().filter(|&&p| p <= page).count());
+        (good_read as f32 / good_pages as f32) * 100.0
+    }
+
+    /// Get all top-level titles as a HashMap (nested titles are inside them)
     pub fn titles(&self) -> &HashMap<String, Title> {
         &self.titles
     }
 
     /// Get total library statistics
+    /// Titles are counted at the top level; entries and pages of nested titles count too
     pub fn stats(&self) -> LibraryStats {
         let title_count = self.titles.len();
-        let entry_count: usize = self.titles.values().map(|t| t.entries.len()).sum();
-        let page_count: usize = self.titles.values().map(|t| t.total_pages()).sum();
+        let entry_count: usize = self.all_titles().map(|t| t.entries.len()).sum();
+        let page_count: usize = self.all_titles().map(|t| t.total_pages()).sum();
 
         LibraryStats {
             titles: title_count,
@@ -766,24 +2198,24 @@ impl Library {
 
     /// Mark database entries as unavailable if their files no longer exist
     /// This is called after scan completes to detect missing files
-    async fn mark_unavailable(&self) -> Result<()> {
+    async fn mark_unavailable(&self, tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>) -> Result<()> {
         use std::collections::HashSet;
 
         const CHUNK_SIZE: usize = 500; // Well under SQLite's 999 limit
 
-        let found_title_ids: HashSet<String> = self.titles.keys().cloned().collect();
+        let found_title_ids: HashSet<String> = self.all_titles().map(|t| t.id.clone()).collect();
+        // Parts of merged entries are found too, they are just not listed
         let found_entry_ids: HashSet<String> = self
-            .titles
-            .values()
-            .flat_map(|t| t.entries.iter().map(|e| e.id.clone()))
+            .all_titles()
+            .flat_map(|t| &t.entries)
+            .flat_map(|e| std::iter::once(e).chain(&e.parts))
+            .map(|e| e.id.clone())
             .collect();
 
-        let mut tx = self.storage.pool().begin().await?;
-
         // 1. Find and mark missing titles as unavailable
         let db_title_ids: Vec<String> =
             sqlx::query_scalar::<_, String>("SELECT id FROM titles WHERE unavailable = 0")
-                .fetch_all(&mut *tx)
+                .fetch_all(&mut **tx)
                 .await?;
 
         let missing_titles: Vec<&String> = db_title_ids
@@ -792,13 +2224,13 @@ impl Library {
             .collect();
 
         for chunk in missing_titles.chunks(CHUNK_SIZE) {
-            Self::batch_update_unavailable(&mut tx, "titles", chunk, 1).await?;
+            Self::batch_update_unavailable(tx, "titles", chunk, 1).await?;
         }
 
         // 2. Find and mark missing entries as unavailable
         let db_entry_ids: Vec<String> =
             sqlx::query_scalar::<_, String>("SELECT id FROM ids WHERE unavailable = 0")
-                .fetch_all(&mut *tx)
+                .fetch_all(&mut **tx)
                 .await?;
 
         let missing_entries: Vec<&String> = db_entry_ids
@@ -807,13 +2239,13 @@ impl Library {
             .collect();
 
         for chunk in missing_entries.chunks(CHUNK_SIZE) {
-            Self::batch_update_unavailable(&mut tx, "ids", chunk, 1).await?;
+            Self::batch_update_unavailable(tx, "ids", chunk, 1).await?;
         }
 
         // 3. Restore previously unavailable titles that are now found
         let unavailable_titles: Vec<String> =
             sqlx::query_scalar::<_, String>("SELECT id FROM titles WHERE unavailable = 1")
-                .fetch_all(&mut *tx)
+                .fetch_all(&mut **tx)
                 .await?;
 
         let restored_titles: Vec<&String> = unavailable_titles
@@ -822,13 +2254,13 @@ impl Library {
             .collect();
 
         for chunk in restored_titles.chunks(CHUNK_SIZE) {
-            Self::batch_update_unavailable(&mut tx, "titles", chunk, 0).await?;
+            Self::batch_update_unavailable(tx, "titles", chunk, 0).await?;
         }
 
         // 4. Restore previously unavailable entries that are now found
         let unavailable_entries: Vec<String> =
             sqlx::query_scalar::<_, String>("SELECT id FROM ids WHERE unavailable = 1")
-                .fetch_all(&mut *tx)
+                .fetch_all(&mut **tx)
                 .await?;
 
         let restored_entries: Vec<&String> = unavailable_entries
@@ -837,7 +2269,7 @@ impl Library {
             .collect();
 
         for chunk in restored_entries.chunks(CHUNK_SIZE) {
-            Self::batch_update_unavailable(&mut tx, "ids", chunk, 0).await?;
+            Self::batch_update_unavailable(tx, "ids", chunk, 0).await?;
         }
 
         // Log what we did
@@ -854,7 +2286,6 @@ impl Library {
             tracing::info!("Restored {} entries as available", restored_entries.len());
         }
 
-        tx.commit().await?;
         Ok(())
     }
 
@@ -872,11 +2303,13 @@ impl Library {
 
         let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
         let query_str = format!(
-            "UPDATE {} SET unavailable = {} WHERE id IN ({})",
+            "UPDATE {} SET unavailable = {}, unavailable_since = ? WHERE id IN ({})",
             table, unavailable, placeholders
         );
 
-        let mut query = sqlx::query(&query_str);
+        // Restored rows clear the timestamp
+        let since = (unavailable != 0).then(crate::util::time::now_ts);
+        let mut query = sqlx::query(&query_str).bind(since);
         for id in ids {
             query = query.bind(*id);
         }
@@ -895,8 +2328,12 @@ pub enum SortMethod {
     TimeModified,
     /// Sort by reading progress
     Progress,
-    /// Smart chapter detection (future enhancement)
+    /// Sort by the volume and chapter numbers in names (see `compare_auto`)
     Auto,
+    /// Sort by recent reads and downloads
+    Popularity,
+    /// Sort entries by when the user last opened them in the reader
+    Opened,
 }
 
 impl SortMethod {
@@ -908,6 +2345,8 @@ impl SortMethod {
             "modified" | "time" => SortMethod::TimeModified,
             "progress" => SortMethod::Progress,
             "auto" => SortMethod::Auto,
+            "popularity" | "popular" => SortMethod::Popularity,
+            "opened" => SortMethod::Opened,
             _ => SortMethod::default(),
         }
     }
@@ -924,6 +2363,17 @@ impl SortMethod {
     }
 }
 
+/// An entry from a user's recently read list
+#[derive(Debug, Clone)]
+pub struct RecentlyReadEntry<'a> {
+    pub title: &'a Title,
+    pub entry: &'a Entry,
+    /// Last saved page
+    pub page: i32,
+    /// Unix timestamp of the last progress save
+    pub last_read: i64,
+}
+
 /// Library statistics
 #[derive(Debug, Clone)]
 pub struct LibraryStats {
@@ -932,6 +2382,34 @@ pub struct LibraryStats {
     pub pages: usize,
 }
 
+/// Title directories a scan reused from the previous one and title directories it read
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+pub struct ScanSummary {
+    /// Title directories unchanged since the previous scan
+    pub reused: usize,
+    /// Title directories that were new or changed
+    pub rescanned: usize,
+}
+
+/// How a library loaded from cache compared with the disk when checked at startup
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CacheVerification {
+    /// Unix timestamp of when the check finished
+    pub finished_at: i64,
+    /// Title directories in the cache
+    pub checked: usize,
+    pub unchanged: usize,
+    /// Titles whose directory changed since the cache was written
+    pub changed: usize,
+    /// Title directories missing from the cache
+    pub added: usize,
+    /// Cached titles whose directory is gone
+    pub removed: usize,
+    /// Titles the cache got wrong (changed, added and removed)
+    pub stale: usize,
+    pub duration_ms: u64,
+}
+
 /// Create a shared Library instance that can be used across async tasks
 /// Uses ArcSwap for lock-free reads and atomic swaps during scan
 pub type SharedLibrary = Arc<ArcSwap<Library>>;
@@ -942,11 +2420,18 @@ pub fn spawn_periodic_scanner(
     library: SharedLibrary,
     storage: Storage,
     config: Arc<crate::Config>,
+    events: super::LibraryEvents,
     interval_minutes: u64,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let mut interval =
             tokio::time::interval(std::time::Duration::from_secs(interval_minutes * 60));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // Failed scans (e.g. the database is read-only) wait longer before the next one
+        let mut backoff = crate::util::Backoff::new(
+            std::time::Duration::from_secs(60),
+            std::time::Duration::from_secs(MAX_SCAN_BACKOFF_SECS),
+        );
 
         loop {
             interval.tick().await;
@@ -954,29 +2439,246 @@ pub fn spawn_periodic_scanner(
             tracing::info!("Starting periodic library scan (double-buffer)");
             let periodic_start = std::time::Instant::now();
 
-            // Build new library instance in background (no lock held)
+            // Build new library instance in background (no lock held), reusing the titles
+            // of the current one whose directories are unchanged
             let mut new_lib = Library::new(config.library_path.clone(), storage.clone(), &config);
+            let previous = library.load_full();
+            new_lib.share_scan_lock(&previous);
 
-            match new_lib.scan().await {
-                Ok(_) => {
+            match new_lib.scan_incremental(&previous).await {
+                Ok(summary) => {
                     let periodic_duration = periodic_start.elapsed();
                     let stats = new_lib.stats();
 
                     // Atomically swap the new library in
-                    library.store(Arc::new(new_lib));
+                    events.store_scanned(&library, new_lib);
 
                     tracing::info!(
-                        "Periodic library scan completed ({:.2}s) - {} titles, {} entries",
+                        "Periodic library scan completed ({:.2}s) - {} titles, {} entries \
+                         ({} titles reused, {} rescanned)",
                         periodic_duration.as_secs_f64(),
                         stats.titles,
-                        stats.entries
+                        stats.entries,
+                        summary.reused,
+                        summary.rescanned
                     );
+                    backoff.reset();
                 }
                 Err(e) => {
-                    tracing::error!("Periodic scan failed: {}", e);
                     // Keep the old library on failure
+                    let delay = backoff.next_delay();
+                    tracing::error!(
+                        "Periodic scan failed, next attempt delayed by {}s: {}",
+                        delay.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
                 }
             }
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mark_unavailable_in_bulk() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let chapter = temp_dir
+            .path()
+            .join("library")
+            .join("Alpha")
+            .join("Chapter 1");
+        std::fs::create_dir_all(&chapter).unwrap();
+        std::fs::write(chapter.join("1.png"), "one").unwrap();
+        let database_url = format!(
+            "sqlite://{}?mode=rwc",
+            temp_dir.path().join("test.db").display()
+        );
+        let storage = Storage::new(&database_url).await.unwrap();
+        let config = crate::Config::default_config();
+        let mut library = Library::new(temp_dir.path().join("library"), storage.clone(), &config);
+        library.scan().await.unwrap();
+
+        // Rows of files that are gone, more than fit in one statement, and found rows that
+        // were missing before
+        let pool = storage.pool();
+        for i in 0..600 {
+            sqlx::query("INSERT INTO titles (id, path, signature) VALUES (?, ?, '')")
+                .bind(format!("title-{}", i))
+                .bind(format!("Gone {}", i))
+                .execute(pool)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO ids (id, path, signature) VALUES (?, ?, '')")
+                .bind(format!("entry-{}", i))
+                .bind(format!("Gone {}/Chapter.zip", i))
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+        for table in ["titles", "ids"] {
+            let sql = format!(
+                "UPDATE {} SET unavailable = 1, unavailable_since = 1 \
+                 WHERE id NOT LIKE 'title-%' AND id NOT LIKE 'entry-%'",
+                table
+            );
+            sqlx::query(&sql).execute(pool).await.unwrap();
+        }
+
+        let mut tx = pool.begin().await.unwrap();
+        library.mark_unavailable(&mut tx).await.unwrap();
+        tx.commit().await.unwrap();
+
+        for table in ["titles", "ids"] {
+            let sql = format!(
+                "SELECT unavailable, unavailable_since IS NOT NULL, COUNT(*) FROM {} \
+                 GROUP BY 1, 2 ORDER BY 1",
+                table
+            );
+            let rows: Vec<(i64, bool, i64)> = sqlx::query_as(&sql).fetch_all(pool).await.unwrap();
+            // The scanned row is available again, the synthetic ones are all missing
+            assert_eq!(rows, [(0, false, 1), (1, true, 600)], "{}", table);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_workers() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().join("library");
+        for series in ["Alpha", "Beta", "Gamma"] {
+            let chapter = root.join(series).join("Chapter 1");
+            std::fs::create_dir_all(&chapter).unwrap();
+            std::fs::write(chapter.join("1.png"), "one").unwrap();
+        }
+        let database_url = format!(
+            "sqlite://{}?mode=rwc",
+            temp_dir.path().join("test.db").display()
+        );
+        let storage = Storage::new(&database_url).await.unwrap();
+
+        // A single worker scans every title, and 0 would never start one
+        for workers in [1, 0] {
+            let mut config = crate::Config::default_config();
+            config.scan_workers = workers;
+            let mut library = Library::new(root.clone(), storage.clone(), &config);
+            assert_eq!(library.scan_workers, 1);
+            library.scan().await.unwrap();
+            assert_eq!(library.get_titles().len(), 3);
+        }
+    }
+
+    /// A title of one image-directory chapter
+    fn add_title(root: &Path, title: &str) {
+        let chapter = root.join(title).join("Chapter 1");
+        std::fs::create_dir_all(&chapter).unwrap();
+        std::fs::write(chapter.join("1.png"), "one").unwrap();
+    }
+
+    /// A library of `titles` in `dir`/library
+    async fn fixture(dir: &Path, titles: &[&str]) -> Storage {
+        for title in titles {
+            add_title(&dir.join("library"), title);
+        }
+        let database_url = format!("sqlite://{}?mode=rwc", dir.join("test.db").display());
+        Storage::new(&database_url).await.unwrap()
+    }
+
+    /// (title name, title ID, entry ID) by title path
+    fn ids(library: &Library) -> HashMap<PathBuf, (String, String, String)> {
+        library
+            .get_titles()
+            .into_iter()
+            .map(|t| {
+                let ids = (t.title.clone(), t.id.clone(), t.entries[0].id.clone());
+                (t.path.clone(), ids)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_name_collisions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().join("library");
+        // Only a case-sensitive filesystem holds all three
+        let storage = fixture(temp_dir.path(), &["Naruto", "Bleach", "Naruto "]).await;
+        let case_sensitive = std::fs::create_dir(root.join("naruto")).is_ok();
+        if case_sensitive {
+            add_title(&root, "naruto");
+        }
+        let config = crate::Config::default_config();
+
+        let mut library = Library::new(root.clone(), storage.clone(), &config);
+        library.scan().await.unwrap();
+        let first = ids(&library);
+
+        let mut paths = vec!["Naruto".to_string(), "Naruto ".to_string()];
+        if case_sensitive {
+            paths.push("naruto".to_string());
+        }
+        assert_eq!(
+            library.name_collisions(),
+            [NameCollision {
+                name: "Naruto".to_string(),
+                paths: paths.clone(),
+            }]
+        );
+        assert_eq!(first[&root.join("Naruto")].0, "Naruto (\"Naruto\")");
+        assert_eq!(first[&root.join("Naruto ")].0, "Naruto (\"Naruto \")");
+        assert_eq!(first[&root.join("Bleach")].0, "Bleach");
+
+        // Every title keeps its IDs in the next scan, full or reusing the last one
+        let mut rescanned = Library::new(root.clone(), storage.clone(), &config);
+        rescanned.scan().await.unwrap();
+        assert_eq!(ids(&rescanned), first);
+        let mut reused = Library::new(root.clone(), storage.clone(), &config);
+        reused.scan_incremental(&rescanned).await.unwrap();
+        assert_eq!(ids(&reused), first);
+        let title_ids: std::collections::HashSet<_> = first.values().map(|i| &i.1).collect();
+        assert_eq!(title_ids.len(), paths.len() + 1);
+
+        // Once the other directories are gone the name is plain again
+        std::fs::remove_dir_all(root.join("Naruto ")).unwrap();
+        if case_sensitive {
+            std::fs::remove_dir_all(root.join("naruto")).unwrap();
+        }
+        let mut library = Library::new(root.clone(), storage.clone(), &config);
+        library.scan_incremental(&reused).await.unwrap();
+        assert!(library.name_collisions().is_empty());
+        let (_, title_id, entry_id) = first[&root.join("Naruto")].clone();
+        assert_eq!(
+            ids(&library)[&root.join("Naruto")],
+            ("Naruto".to_string(), title_id, entry_id)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_case_insensitive_paths() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().join("library");
+        let storage = fixture(temp_dir.path(), &["Bleach"]).await;
+        let mut config = crate::Config::default_config();
+        config.library_case_sensitive = Some(false);
+
+        let mut library = Library::new(root.clone(), storage.clone(), &config);
+        library.scan().await.unwrap();
+        let (_, title_id, entry_id) = ids(&library).remove(&root.join("Bleach")).unwrap();
+
+        // A rename that only changes case is the same title, stored under the new name
+        std::fs::rename(root.join("Bleach"), root.join("BLEACH")).unwrap();
+        let mut library = Library::new(root.clone(), storage.clone(), &config);
+        library.scan().await.unwrap();
+        let renamed = ids(&library).remove(&root.join("BLEACH")).unwrap();
+        assert_eq!(renamed, ("BLEACH".to_string(), title_id.clone(), entry_id));
+
+        let (path, unavailable): (String, bool) =
+            sqlx::query_as("SELECT path, unavailable FROM titles WHERE id = ?")
+                .bind(&title_id)
+                .fetch_one(storage.pool())
+                .await
+                .unwrap();
+        assert_eq!((path.as_str(), unavailable), ("BLEACH", false));
+    }
+}
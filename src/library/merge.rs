@@ -0,0 +1,435 @@
+// Entry merging - multi-part chapters presented as one entry
+//
+// Some releases split a chapter over several archives ("Ch.10 part1.cbz", "Ch.10
+// part2.cbz"). An admin can merge such parts: the scan still finds every archive, then
+// replaces the parts with one virtual entry that holds them in reading order. The
+// merged entry takes the ID of its first part, so thumbnails, reports and the ids
+// table need no rows of their own, and its pages are served from the parts in turn.
+
+use std::collections::HashMap;
+
+use super::entry::Entry;
+use super::progress::TitleInfo;
+use super::title::Title;
+use crate::storage::EntryMerge;
+
+/// Page layout of a merged entry: how many pages each part has, in reading order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedEntry {
+    page_counts: Vec<usize>,
+}
+
+impl MergedEntry {
+    pub fn new(page_counts: Vec<usize>) -> Self {
+        Self { page_counts }
+    }
+
+    /// Layout of the given parts
+    pub fn from_parts(parts: &[Entry]) -> Self {
+        Self::new(parts.iter().map(|p| p.pages).collect())
+    }
+
+    /// Pages across all parts
+    pub fn total_pages(&self) -> usize {
+        self.page_counts.iter().sum()
+    }
+
+    /// Part index and page within that part (both 0-indexed) of a merged page
+    pub fn locate(&self, page: usize) -> Option<(usize, usize)> {
+        let mut start = 0;
+        for (part, &count) in self.page_counts.iter().enumerate() {
+            if page < start + count {
+                return Some((part, page - start));
+            }
+            start += count;
+        }
+        None
+    }
+
+    /// Merged progress from progress on each part (pages are one-based, 0 = unread)
+    /// The furthest part with progress counts, with every part before it read;
+    /// None if no part has progress at all
+    pub fn merge_progress(&self, progress: &[Option<i32>]) -> Option<i32> {
+        if progress.iter().all(Option::is_none) {
+            return None;
+        }
+
+        let mut start = 0;
+        let mut merged = 0;
+        for (&count, page) in self.page_counts.iter().zip(progress) {
+            let page = page.unwrap_or(0).clamp(0, count as i32);
+            if page > 0 {
+                merged = start as i32 + page;
+            }
+            start += count;
+        }
+        Some(merged)
+    }
+
+    /// Progress on each part from progress on the merged entry: parts before the page
+    /// are read, the part holding it is read up to it and later parts are unread
+    pub fn split_progress(&self, page: i32) -> Vec<i32> {
+        let mut start = 0i32;
+        self.page_counts
+            .iter()
+            .map(|&count| {
+                let part_page = (page - start).clamp(0, count as i32);
+                start += count as i32;
+                part_page
+            })
+            .collect()
+    }
+}
+
+/// Display name of a merged entry: what the part names have in common, without a
+/// trailing "part"/"pt", or the first part's name if they share nothing
+pub fn merged_name(names: &[&str]) -> String {
+    let Some(first) = names.first() else {
+        return String::new();
+    };
+
+    let mut prefix: &str = first;
+    for name in &names[1..] {
+        let common = prefix
+            .char_indices()
+            .zip(name.chars())
+            .find(|((_, a), b)| a != b)
+            .map(|((i, _), _)| i)
+            .unwrap_or_else(|| prefix.len().min(name.len()));
+        prefix = &prefix[..common];
+    }
+
+    let trim = |s: &str| {
+        s.trim_end_matches(|c: char| c.is_whitespace() || "-_.([#".contains(c))
+            .to_string()
+    };
+    let mut name = trim(prefix);
+    for suffix in ["part", "pt"] {
+        let Some(cut) = name.len().checked_sub(suffix.len()) else {
+            continue;
+        };
+        if !name.is_char_boundary(cut) || !name[cut..].eq_ignore_ascii_case(suffix) {
+            continue;
+        }
+        // A word of its own ("Ch.10 part"), not the end of one ("Counterpart")
+        let own_word = !name[..cut]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric);
+        if own_word {
+            name = trim(&name[..cut]);
+            break;
+        }
+    }
+
+    if name.is_empty() {
+        first.to_string()
+    } else {
+        name
+    }
+}
+
+/// Build the virtual entry for parts given in reading order
+pub fn merge_entries(parts: Vec<Entry>) -> Entry {
+    let names: Vec<&str> = parts.iter().map(|p| p.title.as_str()).collect();
+    let title = merged_name(&names);
+    let first = &parts[0];
+
+    Entry {
+        id: first.id.clone(),
+        path: first.path.clone(),
+        display_title: title.clone(),
+        title,
+        // Changes whenever a part changes (page sprites are keyed on it)
+        signature: parts
+            .iter()
+            .map(|p| p.signature.as_str())
+            .collect::<Vec<_>>()
+            .join("+"),
+        mtime: parts.iter().map(|p| p.mtime).max().unwrap_or(0),
+        pages: MergedEntry::from_parts(&parts).total_pages(),
+        image_files: parts.iter().flat_map(|p| p.image_files.clone()).collect(),
+        source: first.source,
+        parts,
+    }
+}
+
+/// Replace a title's merged parts with their merged entries
+/// Merged entries already in the title are split first, so the result only reflects
+/// `merges`. A part is found by its ID, or by its signature if the ID changed; a merge
+/// with a part missing is left unapplied until the part is back
+pub fn apply_merges(title: &mut Title, merges: &[EntryMerge]) {
+    let mut entries: Vec<Entry> = Vec::with_capacity(title.entries.len());
+    for entry in title.entries.drain(..) {
+        if entry.parts.is_empty() {
+            entries.push(entry);
+        } else {
+            entries.extend(entry.parts);
+        }
+    }
+
+    for merge in merges.iter().filter(|m| m.title_id == title.id) {
+        let positions: Option<Vec<usize>> = merge
+            .parts
+            .iter()
+            .map(|(id, signature)| {
+                // Entries merged by an earlier merge are not parts of another one
+                let find = |matches: &dyn Fn(&Entry) -> bool| {
+                    entries
+                        .iter()
+                        .position(|e| e.parts.is_empty() && matches(e))
+                };
+                find(&|e| e.id == *id).or_else(|| find(&|e| e.signature == *signature))
+            })
+            .collect();
+        let positions = positions.filter(|p| {
+            p.len() >= 2 && p.iter().collect::<std::collections::HashSet<_>>().len() == p.len()
+        });
+        let Some(positions) = positions else {
+            tracing::debug!(
+                "Merge of {} in {} has a missing part, showing parts",
+                merge.parts[0].0,
+                title.title
+            );
+            continue;
+        };
+
+        let mut taken: HashMap<usize, Entry> = HashMap::new();
+        let mut kept = Vec::with_capacity(entries.len());
+        for (i, entry) in entries.into_iter().enumerate() {
+            if positions.contains(&i) {
+                taken.insert(i, entry);
+            } else {
+                kept.push(entry);
+            }
+        }
+        let parts: Vec<Entry> = positions.iter().filter_map(|i| taken.remove(i)).collect();
+        kept.push(merge_entries(parts));
+        entries = kept;
+    }
+
+    entries.sort_by(|a, b| natord::compare(&a.title, &b.title));
+    title.entries = entries;
+    for nested in &mut title.nested_titles {
+        apply_merges(nested, merges);
+    }
+    // Every scan and cache load ends here, so cached names follow cleanup config changes
+    title.refresh_display_titles();
+}
+
+/// Move users' progress on the parts over to the merged entry (the first part's ID)
+pub fn merge_title_progress(info: &mut TitleInfo, part_ids: &[String], layout: &MergedEntry) {
+    let merged_id = &part_ids[0];
+    for (username, progress) in info.progress.iter_mut() {
+        let pages: Vec<Option<i32>> = part_ids
+            .iter()
+            .map(|id| progress.get(id).copied())
+            .collect();
+        let Some(page) = layout.merge_progress(&pages) else {
+            continue;
+        };
+        for id in part_ids {
+            progress.remove(id);
+        }
+        progress.insert(merged_id.clone(), page);
+
+        // Latest read of any part
+        if let Some(last_read) = info.last_read.get_mut(username) {
+            let latest = part_ids
+                .iter()
+                .filter_map(|id| last_read.remove(id))
+                .max_by_key(|iso| chrono::DateTime::parse_from_rfc3339(iso).ok());
+            if let Some(latest) = latest {
+                last_read.insert(merged_id.clone(), latest);
+            }
+        }
+    }
+
+    // Latest open of any part, for every user who opened one
+    for opened_at in info.opened_at.values_mut() {
+        let latest = part_ids
+            .iter()
+            .filter_map(|id| opened_at.remove(id))
+            .max_by_key(|iso| chrono::DateTime::parse_from_rfc3339(iso).ok());
+        if let Some(latest) = latest {
+            opened_at.insert(merged_id.clone(), latest);
+        }
+    }
+}
+
+/// Spread users' progress on a merged entry back over its parts
+pub fn split_title_progress(info: &mut TitleInfo, part_ids: &[String], layout: &MergedEntry) {
+    let merged_id = &part_ids[0];
+    for (username, progress) in info.progress.iter_mut() {
+        let Some(page) = progress.remove(merged_id) else {
+            continue;
+        };
+        let split = layout.split_progress(page);
+        for (id, part_page) in part_ids.iter().zip(split) {
+            if part_page > 0 {
+                progress.insert(id.clone(), part_page);
+            }
+        }
+        if page == 0 {
+            progress.insert(merged_id.clone(), 0);
+        }
+
+        if let Some(last_read) = info.last_read.get_mut(username) {
+            if let Some(latest) = last_read.get(merged_id).cloned() {
+                for id in part_ids.iter().filter(|id| progress.contains_key(*id)) {
+                    last_read.insert(id.clone(), latest.clone());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::EntrySource;
+    use std::path::PathBuf;
+
+    fn entry(id: &str, title: &str, pages: usize) -> Entry {
+        Entry {
+            id: id.to_string(),
+            path: PathBuf::from(format!("{}.cbz", title)),
+            title: title.to_string(),
+            display_title: title.to_string(),
+            signature: format!("sig-{}", id),
+            mtime: 0,
+            pages,
+            image_files: (1..=pages)
+                .map(|p| format!("{}/{:03}.png", id, p))
+                .collect(),
+            source: EntrySource::Archive,
+            parts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_page_mapping() {
+        let layout = MergedEntry::new(vec![3, 0, 2]);
+        assert_eq!(layout.total_pages(), 5);
+        assert_eq!(layout.locate(0), Some((0, 0)));
+        assert_eq!(layout.locate(2), Some((0, 2)));
+        // Empty parts are skipped
+        assert_eq!(layout.locate(3), Some((2, 0)));
+        assert_eq!(layout.locate(4), Some((2, 1)));
+        assert_eq!(layout.locate(5), None);
+
+        let merged = merge_entries(vec![
+            entry("a", "Ch.10 part1", 3),
+            entry("b", "Ch.10 part2", 2),
+        ]);
+        assert_eq!(merged.page_source(4).unwrap().0.id, "b");
+        assert_eq!(merged.page_source(4).unwrap().1, 1);
+        assert_eq!(merged.image_name(3).unwrap(), "b/001.png");
+        assert!(merged.page_source(5).is_err());
+    }
+
+    #[test]
+    fn test_progress_conversion() {
+        let layout = MergedEntry::new(vec![10, 5, 8]);
+
+        assert_eq!(layout.merge_progress(&[None, None, None]), None);
+        assert_eq!(layout.merge_progress(&[Some(0), None, None]), Some(0));
+        assert_eq!(layout.merge_progress(&[Some(10), Some(3), None]), Some(13));
+        // The furthest part wins, even if an earlier one was left unfinished
+        assert_eq!(layout.merge_progress(&[Some(4), None, Some(2)]), Some(17));
+        assert_eq!(layout.merge_progress(&[Some(99), None, None]), Some(10));
+
+        assert_eq!(layout.split_progress(0), [0, 0, 0]);
+        assert_eq!(layout.split_progress(7), [7, 0, 0]);
+        assert_eq!(layout.split_progress(13), [10, 3, 0]);
+        assert_eq!(layout.split_progress(23), [10, 5, 8]);
+
+        // Splitting then merging gives the same page back
+        for page in 0..=23 {
+            let split: Vec<Option<i32>> =
+                layout.split_progress(page).into_iter().map(Some).collect();
+            assert_eq!(layout.merge_progress(&split), Some(page));
+        }
+    }
+
+    #[test]
+    fn test_merged_name() {
+        assert_eq!(merged_name(&["Ch.10 part1", "Ch.10 part2"]), "Ch.10");
+        assert_eq!(merged_name(&["Vol 2 - Pt 1", "Vol 2 - Pt 2"]), "Vol 2");
+        assert_eq!(merged_name(&["Ch.10a", "Ch.10b"]), "Ch.10");
+        assert_eq!(
+            merged_name(&["Counterpart 1", "Counterpart 2"]),
+            "Counterpart"
+        );
+        assert_eq!(merged_name(&["Alpha", "Beta"]), "Alpha");
+    }
+
+    #[test]
+    fn test_apply_merges_round_trip() {
+        let mut title = Title {
+            id: "t".to_string(),
+            path: PathBuf::from("T"),
+            title: "T".to_string(),
+            signature: String::new(),
+            contents_signature: String::new(),
+            mtime: 0,
+            entries: vec![
+                entry("a", "Ch.10 part1", 3),
+                entry("b", "Ch.10 part2", 2),
+                entry("c", "Ch.11", 4),
+            ],
+            parent_id: None,
+            nested_titles: Vec::new(),
+            cover_path: None,
+            source: crate::library::TitleSource::Directory,
+            unreadable: Vec::new(),
+            broken_entries: Vec::new(),
+        };
+        let merge = EntryMerge {
+            title_id: "t".to_string(),
+            parts: vec![
+                ("a".to_string(), "sig-a".to_string()),
+                // Part renamed since, found by its signature
+                ("old-b".to_string(), "sig-b".to_string()),
+            ],
+        };
+
+        apply_merges(&mut title, std::slice::from_ref(&merge));
+        let ids: Vec<&str> = title.entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["a", "c"]);
+        assert_eq!(title.entries[0].title, "Ch.10");
+        assert_eq!(title.entries[0].pages, 5);
+
+        // Applying again is a no-op, and without the merge the parts come back
+        apply_merges(&mut title, std::slice::from_ref(&merge));
+        assert_eq!(title.entries.len(), 2);
+        apply_merges(&mut title, &[]);
+        let ids: Vec<&str> = title.entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_title_progress_round_trip() {
+        let ids = vec!["a".to_string(), "b".to_string()];
+        let layout = MergedEntry::new(vec![3, 2]);
+        let mut info = TitleInfo::default();
+        info.set_progress("alice", "a", 3, 3);
+        info.set_progress("alice", "b", 1, 2);
+        info.set_progress("bob", "c", 2, 4);
+
+        merge_title_progress(&mut info, &ids, &layout);
+        assert_eq!(info.get_progress("alice", "a"), Some(4));
+        assert_eq!(info.get_progress("alice", "b"), None);
+        assert!(info.get_last_read("alice", "a").is_some());
+        assert_eq!(
+            info.get_progress("bob", "c"),
+            Some(2),
+            "other entries untouched"
+        );
+
+        split_title_progress(&mut info, &ids, &layout);
+        assert_eq!(info.get_progress("alice", "a"), Some(3));
+        assert_eq!(info.get_progress("alice", "b"), Some(1));
+        assert!(info.get_last_read("alice", "b").is_some());
+    }
+}
@@ -1,17 +1,45 @@
+pub mod archive_cache;
 pub mod cache;
+pub mod convert;
 pub mod entry;
+pub mod events;
+pub mod folder_cover;
+pub mod index;
+pub mod maintenance;
+pub mod merge;
+pub mod page_flights;
 pub mod progress;
 pub mod progress_cache;
+pub mod scan_filter;
+pub mod scan_lock;
+pub mod scan_status;
 pub mod title;
+pub mod title_cleanup;
+pub mod watcher;
 
 // Library manager module
 mod manager;
+mod scan_journal;
 
-pub use entry::Entry;
-pub use manager::{spawn_periodic_scanner, Library, LibraryStats, SharedLibrary, SortMethod};
-pub use progress::TitleInfo;
+pub use archive_cache::ArchiveCache;
+pub use entry::{Entry, EntrySource};
+pub use events::{LibraryEvent, LibraryEvents};
+pub use folder_cover::FolderCovers;
+pub use index::{letter_buckets, LetterBucket, LibraryIndex};
+pub use manager::{
+    spawn_periodic_scanner, CacheVerification, Library, LibraryStats, RecentlyReadEntry,
+    ScanSummary, SharedLibrary, SortMethod,
+};
+pub use page_flights::PageFlights;
+pub use progress::{ReadingDirection, TitleInfo};
 pub use progress_cache::ProgressCache;
-pub use title::Title;
+pub use scan_filter::ScanFilter;
+pub use scan_journal::{ScanJournal, ScanProgress};
+pub use scan_lock::ScanLock;
+pub use scan_status::{ScanJob, ScanReport, ScanStatus};
+pub use title::{NameCollision, Title, TitleSource, UnreadableFile};
+pub use title_cleanup::TitleCleanup;
+pub use watcher::spawn_library_watcher;
 
 /// Trait for types that can be sorted by name and modification time
 pub trait Sortable {
@@ -41,3 +69,256 @@ pub fn sort_by_mtime<T: Sortable>(items: &mut [T], ascending: bool) {
         items.sort_by_key(|b| std::cmp::Reverse(b.sort_mtime()));
     }
 }
+
+/// Sort a slice of Sortable items by the volume and chapter numbers in their names
+/// See `compare_auto`
+pub fn sort_by_auto<T: Sortable>(items: &mut [T], ascending: bool) {
+    if ascending {
+        items.sort_by(|a, b| compare_auto(a.sort_name(), b.sort_name()));
+    } else {
+        items.sort_by(|a, b| compare_auto(b.sort_name(), a.sort_name()));
+    }
+}
+
+/// Words marking the number after them as a chapter number ("#12" counts too)
+const CHAPTER_MARKERS: [&str; 6] = ["chapter", "chap", "ch", "c", "episode", "ep"];
+
+/// Words marking the number after them as a volume number
+const VOLUME_MARKERS: [&str; 3] = ["volume", "vol", "v"];
+
+/// A number in a name, with the lowercased text between it and the previous number
+struct NumberToken {
+    prefix: String,
+    value: f64,
+}
+
+impl NumberToken {
+    /// Word right before the number: "ch" in "Ch.12", "#" in "#12"
+    fn marker(&self) -> &str {
+        let marker = self
+            .prefix
+            .trim_end_matches(|c: char| c.is_whitespace() || ".:-_".contains(c));
+        if marker.ends_with('#') {
+            return "#";
+        }
+        marker
+            .rsplit(|c: char| !c.is_alphanumeric())
+            .next()
+            .unwrap_or_default()
+    }
+
+    fn is_chapter(&self) -> bool {
+        let marker = self.marker();
+        marker == "#" || CHAPTER_MARKERS.contains(&marker)
+    }
+
+    fn is_volume(&self) -> bool {
+        VOLUME_MARKERS.contains(&self.marker())
+    }
+}
+
+/// Split a name into its numbers ("12", "10.5"), in order
+fn number_tokens(name: &str) -> Vec<NumberToken> {
+    let mut tokens = Vec::new();
+    let mut prefix = String::new();
+    let mut chars = name.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if !c.is_ascii_digit() {
+            prefix.push(c.to_ascii_lowercase());
+            continue;
+        }
+
+        let mut end = start + c.len_utf8();
+        let mut seen_dot = false;
+        while let Some(&(i, next)) = chars.peek() {
+            let decimal = next == '.' && !seen_dot && {
+                let rest = &name[i + 1..];
+                rest.starts_with(|d: char| d.is_ascii_digit())
+            };
+            if !next.is_ascii_digit() && !decimal {
+                break;
+            }
+            seen_dot |= decimal;
+            end = i + next.len_utf8();
+            chars.next();
+        }
+        if let Ok(value) = name[start..end].parse::<f64>() {
+            tokens.push(NumberToken {
+                prefix: std::mem::take(&mut prefix),
+                value,
+            });
+        }
+        prefix.clear();
+    }
+
+    tokens
+}
+
+/// Chapter number of an entry name, as compared by bulk "read up to" actions
+/// Prefers the number after a chapter marker ("Ch.12", "Chapter 12.5", "#12"),
+/// otherwise the first number in the name
+pub fn chapter_number(name: &str) -> Option<f64> {
+    let tokens = number_tokens(name);
+    tokens
+        .iter()
+        .find(|t| t.is_chapter())
+        .or(tokens.first())
+        .map(|t| t.value)
+}
+
+/// What Auto sorting compares a name by, in order
+struct AutoKey {
+    /// Lowercased text before the first number, without a trailing volume or chapter
+    /// marker: "" for "Ch.12" and "Vol.2 Ch.11", "omake" for "Omake" and "Omake 2"
+    stem: String,
+    /// Number after a volume marker, after all volumes when missing
+    volume: f64,
+    /// Number after a chapter marker, or else the first number that is not the
+    /// volume; after all chapters when missing
+    chapter: f64,
+}
+
+impl AutoKey {
+    fn parse(name: &str) -> Self {
+        let tokens = number_tokens(name);
+        let volume = tokens.iter().find(|t| t.is_volume());
+        let chapter = tokens
+            .iter()
+            .find(|t| t.is_chapter())
+            .or_else(|| tokens.iter().find(|t| !t.is_volume()));
+
+        let stem = match tokens.first() {
+            Some(first) if first.is_chapter() || first.is_volume() => {
+                let marker = first.marker();
+                let prefix = first
+                    .prefix
+                    .trim_end_matches(|c: char| c.is_whitespace() || ".:-_".contains(c));
+                prefix[..prefix.len() - marker.len()].to_string()
+            }
+            Some(first) => first.prefix.clone(),
+            None => name.to_ascii_lowercase(),
+        };
+        let stem = stem
+            .trim_matches(|c: char| c.is_whitespace() || ".:-_#".contains(c))
+            .to_string();
+
+        Self {
+            stem,
+            volume: volume.map_or(f64::INFINITY, |t| t.value),
+            chapter: chapter.map_or(f64::INFINITY, |t| t.value),
+        }
+    }
+}
+
+/// Order of two entry names for Auto sorting
+/// Names are grouped by the text before their first number, then ordered by volume
+/// and chapter number, so "Chapter 2" comes before "Ch. 10" and "Ch. 10.5" before
+/// "Ch. 11" whatever the padding. Extras without a chapter number ("Vol.2 Omake")
+/// end their volume, and names without numbers fall back to natural order
+pub fn compare_auto(a: &str, b: &str) -> std::cmp::Ordering {
+    let (key_a, key_b) = (AutoKey::parse(a), AutoKey::parse(b));
+    natord::compare(&key_a.stem, &key_b.stem)
+        .then_with(|| key_a.volume.total_cmp(&key_b.volume))
+        .then_with(|| key_a.chapter.total_cmp(&key_b.chapter))
+        .then_with(|| natord::compare(a, b))
+}
+
+/// Whether an entry name sorts at or before a threshold key
+/// A bare number ("12", "12.5") is compared against `chapter_number`; any other
+/// key is compared by name with the natural ordering used for name sorting
+pub fn at_or_before(name: &str, key: &str) -> bool {
+    let key = key.trim();
+    match key.parse::<f64>() {
+        Ok(threshold) => chapter_number(name).is_some_and(|n| n <= threshold),
+        Err(_) => natord::compare(name, key) != std::cmp::Ordering::Greater,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chapter_number() {
+        assert_eq!(chapter_number("Chapter 12"), Some(12.0));
+        assert_eq!(chapter_number("Ch.012.5"), Some(12.5));
+        assert_eq!(chapter_number("Vol.2 Ch.13 - The End"), Some(13.0));
+        assert_eq!(chapter_number("Vol 3 c007"), Some(7.0));
+        assert_eq!(chapter_number("Series #4"), Some(4.0));
+        assert_eq!(chapter_number("Vol.2 Extra"), Some(2.0));
+        assert_eq!(chapter_number("015"), Some(15.0));
+        assert_eq!(chapter_number("Oneshot"), None);
+    }
+
+    #[test]
+    fn test_at_or_before() {
+        assert!(at_or_before("Chapter 9", "10"));
+        assert!(at_or_before("Chapter 10", "10"));
+        assert!(!at_or_before("Chapter 10.5", "10"));
+        assert!(!at_or_before("Chapter 11", "10"));
+        assert!(!at_or_before("Oneshot", "10"));
+
+        // Non-numeric keys follow the natural name order
+        assert!(at_or_before("Chapter 9", "Chapter 10"));
+        assert!(at_or_before("Chapter 10", "Chapter 10"));
+        assert!(!at_or_before("Chapter 100", "Chapter 10"));
+    }
+
+    fn auto_sorted(names: &[&'static str]) -> Vec<&'static str> {
+        let mut names = names.to_vec();
+        names.sort_by(|a, b| compare_auto(a, b));
+        names
+    }
+
+    #[test]
+    fn test_auto_sort_decimal_chapters() {
+        assert_eq!(
+            auto_sorted(&["Ch. 11", "c103", "Ch. 10.5", "Chapter 2", "Ch. 10"]),
+            ["Chapter 2", "Ch. 10", "Ch. 10.5", "Ch. 11", "c103"]
+        );
+        assert_eq!(
+            auto_sorted(&["Some Manga 10", "Some Manga 9.5", "Some Manga 009"]),
+            ["Some Manga 009", "Some Manga 9.5", "Some Manga 10"]
+        );
+    }
+
+    #[test]
+    fn test_auto_sort_volumes() {
+        assert_eq!(
+            auto_sorted(&[
+                "Vol.10 Ch.50",
+                "Vol.2 Ch.11",
+                "Ch.51",
+                "Vol.1 Ch.10",
+                "v02c009",
+                "Vol.2 Omake",
+            ]),
+            [
+                "Vol.1 Ch.10",
+                "v02c009",
+                "Vol.2 Ch.11",
+                "Vol.2 Omake",
+                "Vol.10 Ch.50",
+                "Ch.51",
+            ]
+        );
+        assert_eq!(
+            auto_sorted(&["Vol 10", "Volume 2", "v1"]),
+            ["v1", "Volume 2", "Vol 10"]
+        );
+    }
+
+    #[test]
+    fn test_auto_sort_extras_and_fallback() {
+        // Extras follow the chapters, ordered among themselves
+        assert_eq!(
+            auto_sorted(&["Omake", "Ch.2", "Extra 2", "Ch.1", "Extra 1"]),
+            ["Ch.1", "Ch.2", "Extra 1", "Extra 2", "Omake"]
+        );
+        // Without numbers it is the natural name order
+        assert_eq!(
+            auto_sorted(&["Oneshot", "Afterword", "Bonus"]),
+            ["Afterword", "Bonus", "Oneshot"]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+// Page Flights - coalesces identical page requests that arrive at the same time
+//
+// When several readers open the same new chapter, or a prefetching client asks for a page
+// twice, the same page would be extracted once per request. The first request for a page
+// extracts it while later requests for the same page wait for its result instead.
+// Waiting is bounded: a request whose leader takes too long, fails or goes away (the
+// client disconnected) extracts the page itself, so one stuck extraction cannot hold up
+// everyone else.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+use crate::error::Result;
+
+/// How long a request waits for an identical one before extracting the page itself
+const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Page data of a finished extraction, None while it runs
+type FlightResult = Option<Arc<Vec<u8>>>;
+
+/// (entry ID, 0-indexed page)
+type PageKey = (String, usize);
+
+/// An extraction in progress
+struct Flight {
+    /// Tells a flight apart from a newer one for the same page
+    id: u64,
+    result: watch::Receiver<FlightResult>,
+}
+
+#[derive(Default)]
+struct PageFlightsInner {
+    flights: Mutex<HashMap<PageKey, Flight>>,
+    next_id: AtomicU64,
+    coalesced: AtomicU64,
+    timeouts: AtomicU64,
+}
+
+/// Counters reported by the admin diagnostics endpoint
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PageFlightStats {
+    /// Requests served by another request's extraction
+    pub coalesced: u64,
+    /// Requests that gave up waiting and extracted the page themselves
+    pub timeouts: u64,
+}
+
+/// Page extractions in progress, shared across requests
+#[derive(Clone, Default)]
+pub struct PageFlights {
+    inner: Arc<PageFlightsInner>,
+}
+
+/// Whether a request extracts the page or waits for another one
+enum Role<'a> {
+    Lead(watch::Sender<FlightResult>, FlightGuard<'a>),
+    Follow(watch::Receiver<FlightResult>),
+}
+
+/// Removes a flight when its leader finishes or is dropped
+struct FlightGuard<'a> {
+    flights: &'a PageFlights,
+    key: PageKey,
+    id: u64,
+}
+
+impl Drop for FlightGuard<'_> {
+    fn drop(&mut self) {
+        let mut flights = self.flights.lock();
+        if flights.get(&self.key).is_some_and(|f| f.id == self.id) {
+            flights.remove(&self.key);
+        }
+    }
+}
+
+impl PageFlights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get page `page` (0-indexed) of an entry with `extract`, or wait for the identical
+    /// request already extracting it
+    pub async fn get<F, Fut>(&self, entry_id: &str, page: usize, extract: F) -> Result<Vec<u8>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Vec<u8>>>,
+    {
+        match self.join(entry_id, page) {
+            Role::Lead(sender, guard) => {
+                let data = Arc::new(extract().await?);
+                let _ = sender.send(Some(data.clone()));
+                // Without waiting requests the data is handed over without a copy
+                drop(guard);
+                drop(sender);
+                Ok(Arc::try_unwrap(data).unwrap_or_else(|data| (*data).clone()))
+            }
+            Role::Follow(mut result) => {
+                let waited = tokio::time::timeout(WAIT_TIMEOUT, result.wait_for(Option::is_some))
+                    .await
+                    .map(|finished| finished.map(|data| data.clone()));
+                match waited {
+                    Ok(Ok(Some(data))) => {
+                        self.inner.coalesced.fetch_add(1, Ordering::Relaxed);
+                        Ok((*data).clone())
+                    }
+                    // The leader failed or went away; extracting again reports our own error
+                    Ok(_) => extract().await,
+                    Err(_) => {
+                        self.inner.timeouts.fetch_add(1, Ordering::Relaxed);
+                        extract().await
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> PageFlightStats {
+        PageFlightStats {
+            coalesced: self.inner.coalesced.load(Ordering::Relaxed),
+            timeouts: self.inner.timeouts.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Follow the flight of a page, or start one
+    fn join(&self, entry_id: &str, page: usize) -> Role<'_> {
+        let key = (entry_id.to_string(), page);
+        let mut flights = self.lock();
+        if let Some(flight) = flights.get(&key) {
+            return Role::Follow(flight.result.clone());
+        }
+
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, result) = watch::channel(None);
+        flights.insert(key.clone(), Flight { id, result });
+        let guard = FlightGuard {
+            flights: self,
+            key,
+            id,
+        };
+        Role::Lead(sender, guard)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PageKey, Flight>> {
+        match self.inner.flights.lock() {
+            Ok(flights) => flights,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::library::{ArchiveCache, Entry};
+    use tempfile::TempDir;
+
+    /// A directory entry of one page, which the archive cache reads from disk every time
+    async fn fixture(dir: &std::path::Path) -> Entry {
+        let chapter = dir.join("Chapter 1");
+        std::fs::create_dir(&chapter).unwrap();
+        std::fs::write(chapter.join("1.png"), b"page one").unwrap();
+        Entry::from_directory(chapter).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_extract_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = Arc::new(fixture(temp_dir.path()).await);
+        let archives = ArchiveCache::new(0, 0);
+        let flights = PageFlights::new();
+        let extractions = Arc::new(AtomicU64::new(0));
+        // Held until every request has joined, so none finds the extraction finished
+        let (release, released) = watch::channel(false);
+
+        let mut requests = tokio::task::JoinSet::new();
+        for _ in 0..10 {
+            let (entry, archives, flights) = (entry.clone(), archives.clone(), flights.clone());
+            let (extractions, mut released) = (extractions.clone(), released.clone());
+            requests.spawn(async move {
+                let entry_id = entry.id.clone();
+                flights
+                    .get(&entry_id, 0, move || async move {
+                        extractions.fetch_add(1, Ordering::SeqCst);
+                        let _ = released.wait_for(|released| *released).await;
+                        archives.get_page(&entry, 0).await
+                    })
+                    .await
+            });
+        }
+        // Every request runs until it waits, the first one on the release
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(flights.lock().len(), 1);
+        release.send(true).unwrap();
+
+        while let Some(page) = requests.join_next().await {
+            assert_eq!(page.unwrap().unwrap(), b"page one");
+        }
+        assert_eq!(extractions.load(Ordering::SeqCst), 1);
+        assert_eq!(flights.stats().coalesced, 9);
+        assert!(flights.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_leader_lets_followers_extract() {
+        let flights = PageFlights::new();
+        let (release, mut released) = watch::channel(false);
+
+        let leader = {
+            let flights = flights.clone();
+            tokio::spawn(async move {
+                flights
+                    .get("entry", 0, || async move {
+                        let _ = released.wait_for(|released| *released).await;
+                        Err(Error::NotFound("page".to_string()))
+                    })
+                    .await
+            })
+        };
+        while flights.lock().is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        let follower = flights.get("entry", 0, || async { Ok(b"retried".to_vec()) });
+        let (_, page) = tokio::join!(async { release.send(true).unwrap() }, follower);
+        assert!(leader.await.unwrap().is_err());
+        assert_eq!(page.unwrap(), b"retried");
+        assert_eq!(flights.stats().coalesced, 0);
+    }
+}
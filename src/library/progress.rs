@@ -2,6 +2,101 @@ use crate::error::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of info.json loads since startup (exposed via admin diagnostics)
+static LOAD_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// How the reader lays out pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadingDirection {
+    /// Paged, left to right
+    Ltr,
+    /// Paged, right to left (manga)
+    Rtl,
+    /// Continuous vertical scroll (webtoons)
+    Vertical,
+}
+
+impl ReadingDirection {
+    /// Value used in info.json, the API and the reader
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReadingDirection::Ltr => "ltr",
+            ReadingDirection::Rtl => "rtl",
+            ReadingDirection::Vertical => "vertical",
+        }
+    }
+
+    /// Short label for the UI
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReadingDirection::Ltr => "LTR",
+            ReadingDirection::Rtl => "RTL",
+            ReadingDirection::Vertical => "Vertical",
+        }
+    }
+
+    /// Parse an API/cookie value (ltr, rtl or vertical)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ltr" => Some(ReadingDirection::Ltr),
+            "rtl" => Some(ReadingDirection::Rtl),
+            "vertical" => Some(ReadingDirection::Vertical),
+            _ => None,
+        }
+    }
+}
+
+/// Opening an entry again within this many seconds of the recorded open is not recorded,
+/// so paging through the reader doesn't rewrite info.json on every page
+pub const OPEN_THROTTLE_SECS: i64 = 3600;
+
+/// Fraction of an entry a reader must go back below after finishing it before reaching
+/// the end again counts as a re-read; smaller jumps back are treated as page jitter
+pub const REREAD_THRESHOLD: f64 = 0.2;
+
+/// Completion tracking for one user and entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadState {
+    /// Times the entry was read to the end, the first read included
+    pub count: u32,
+    /// Went back below the re-read threshold after finishing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub restarted: bool,
+}
+
+impl ReadState {
+    /// State after progress moves to `page` (1-based, 0 = unread) of `total_pages`
+    pub fn advance(self, page: i32, total_pages: usize) -> Self {
+        if total_pages == 0 {
+            return self;
+        }
+
+        let page = page.max(0) as usize;
+        if page >= total_pages {
+            if self.count == 0 || self.restarted {
+                return ReadState {
+                    count: self.count + 1,
+                    restarted: false,
+                };
+            }
+        } else if self.count > 0 && (page as f64) < total_pages as f64 * REREAD_THRESHOLD {
+            return ReadState {
+                restarted: true,
+                ..self
+            };
+        }
+
+        self
+    }
+
+    /// Times the entry was read again after the first read
+    pub fn rereads(&self) -> u32 {
+        self.count.saturating_sub(1)
+    }
+}
 
 /// Structure for storing title metadata and progress in info.json
 /// Compatible with original Mango's info.json format
@@ -15,6 +110,11 @@ pub struct TitleInfo {
     #[serde(default)]
     pub progress: HashMap<String, HashMap<String, i32>>,
 
+    /// Read counts: username -> entry_id -> completion state
+    /// Updated with progress, so it only counts reads made through set_progress
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub read_state: HashMap<String, HashMap<String, ReadState>>,
+
     /// Custom display name for the title
     #[serde(default)]
     pub display_name: String,
@@ -36,6 +136,11 @@ pub struct TitleInfo {
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub last_read: HashMap<String, HashMap<String, String>>,
 
+    /// Last time each user opened an entry in the reader: username -> entry_id -> ISO 8601
+    /// datetime with milliseconds. Unlike last_read, opening without reading counts
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub opened_at: HashMap<String, HashMap<String, String>>,
+
     /// Date added timestamp: entry_id -> ISO 8601 datetime
     /// Matches original Mango format (Time serializes to ISO 8601)
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
@@ -44,6 +149,20 @@ pub struct TitleInfo {
     /// Sorting preferences: username -> (sort_method, ascending)
     #[serde(default)]
     pub sort_by: HashMap<String, (String, bool)>,
+
+    /// Pinned entry order set by an admin (entry IDs, first to last)
+    /// When non-empty it overrides every sort method for this title
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entry_order: Vec<String>,
+
+    /// Reading direction override for the whole title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<ReadingDirection>,
+
+    /// Reading direction overrides for entries: entry_id -> direction
+    /// Take precedence over the title override
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub entry_direction: HashMap<String, ReadingDirection>,
 }
 
 fn default_comment() -> String {
@@ -55,20 +174,31 @@ impl Default for TitleInfo {
         TitleInfo {
             comment: default_comment(),
             progress: HashMap::new(),
+            read_state: HashMap::new(),
             display_name: String::new(),
             entry_display_name: HashMap::new(),
             cover_url: String::new(),
             entry_cover_url: HashMap::new(),
             last_read: HashMap::new(),
+            opened_at: HashMap::new(),
             date_added: HashMap::new(),
             sort_by: HashMap::new(),
+            entry_order: Vec::new(),
+            direction: None,
+            entry_direction: HashMap::new(),
         }
     }
 }
 
 impl TitleInfo {
+    /// Number of times TitleInfo::load has been called since startup
+    pub fn load_count() -> u64 {
+        LOAD_COUNT.load(Ordering::Relaxed)
+    }
+
     /// Load TitleInfo from a directory's info.json file
     pub async fn load(dir: &Path) -> Result<Self> {
+        LOAD_COUNT.fetch_add(1, Ordering::Relaxed);
         let info_path = dir.join("info.json");
 
         if !info_path.exists() {
@@ -102,15 +232,43 @@ impl TitleInfo {
             .copied()
     }
 
-    /// Set progress for a specific user and entry
-    pub fn set_progress(&mut self, username: &str, entry_id: &str, page: i32) {
+    /// Set progress for a specific user and entry of `total_pages` pages
+    /// Also advances the entry's read count (see `ReadState::advance`)
+    pub fn set_progress(&mut self, username: &str, entry_id: &str, page: i32, total_pages: usize) {
         self.progress
             .entry(username.to_string())
             .or_default()
             .insert(entry_id.to_string(), page);
 
+        let states = self.read_state.entry(username.to_string()).or_default();
+        let state = states.get(entry_id).copied().unwrap_or_default();
+        let next = state.advance(page, total_pages);
+        if next != ReadState::default() {
+            states.insert(entry_id.to_string(), next);
+        }
+        if states.is_empty() {
+            self.read_state.remove(username);
+        }
+
         // Update last_read timestamp
-        self.set_last_read(username, entry_id, chrono::Utc::now().timestamp());
+        self.set_last_read(username, entry_id, crate::util::time::now_ts());
+    }
+
+    /// Get the read state for a specific user and entry
+    pub fn get_read_state(&self, username: &str, entry_id: &str) -> ReadState {
+        self.read_state
+            .get(username)
+            .and_then(|states| states.get(entry_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Total re-reads by a user across the title's entries
+    pub fn get_rereads(&self, username: &str) -> u32 {
+        self.read_state
+            .get(username)
+            .map(|states| states.values().map(ReadState::rereads).sum())
+            .unwrap_or(0)
     }
 
     /// Remove progress for a specific user and entry
@@ -151,6 +309,43 @@ impl TitleInfo {
             .insert(entry_id.to_string(), iso_string);
     }
 
+    /// When a user last opened an entry in the reader
+    pub fn get_opened_at(
+        &self,
+        username: &str,
+        entry_id: &str,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.opened_at
+            .get(username)
+            .and_then(|user_opened| user_opened.get(entry_id))
+            .and_then(|iso_string| chrono::DateTime::parse_from_rfc3339(iso_string).ok())
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+    }
+
+    /// Record a user opening an entry at `now`
+    /// Returns false, leaving the recorded time alone, when the last recorded open is less
+    /// than `OPEN_THROTTLE_SECS` before `now`
+    pub fn record_opened(
+        &mut self,
+        username: &str,
+        entry_id: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> bool {
+        if let Some(opened_at) = self.get_opened_at(username, entry_id) {
+            if (now - opened_at).num_seconds() < OPEN_THROTTLE_SECS {
+                return false;
+            }
+        }
+
+        // Milliseconds keep opens made in quick succession in order
+        let iso_string = now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        self.opened_at
+            .entry(username.to_string())
+            .or_default()
+            .insert(entry_id.to_string(), iso_string);
+        true
+    }
+
     /// Get date added timestamp for an entry
     /// Returns Unix timestamp (i64) parsed from ISO 8601 string
     pub fn get_date_added(&self, entry_id: &str) -> Option<i64> {
@@ -185,15 +380,232 @@ impl TitleInfo {
             .or_insert(iso_string);
     }
 
+    /// Replace an entry's date added with its file mtime
+    /// Only dates inside `window` (inclusive) or missing ones are replaced; None
+    /// replaces any date. Returns whether the stored date changed
+    pub fn backfill_date_added(
+        &mut self,
+        entry_id: &str,
+        mtime: i64,
+        window: Option<(i64, i64)>,
+    ) -> bool {
+        let current = self.get_date_added(entry_id);
+        let replace = match (current, window) {
+            (None, _) | (_, None) => true,
+            (Some(date), Some((start, end))) => (start..=end).contains(&date),
+        };
+
+        if !replace || current == Some(mtime) {
+            return false;
+        }
+        self.set_date_added(entry_id, mtime);
+        true
+    }
+
     /// Get sort preference for a specific user
     /// Returns (sort_method, ascending) tuple
     pub fn get_sort_by(&self, username: &str) -> Option<(String, bool)> {
         self.sort_by.get(username).cloned()
     }
 
+    /// Set (Some) or clear (None) the direction override of the title, or of one entry
+    pub fn set_direction(&mut self, entry_id: Option<&str>, direction: Option<ReadingDirection>) {
+        match (entry_id, direction) {
+            (Some(entry_id), Some(direction)) => {
+                self.entry_direction.insert(entry_id.to_string(), direction);
+            }
+            (Some(entry_id), None) => {
+                self.entry_direction.remove(entry_id);
+            }
+            (None, direction) => self.direction = direction,
+        }
+    }
+
     /// Set sort preference for a specific user
     pub fn set_sort_by(&mut self, username: &str, method: &str, ascending: bool) {
         self.sort_by
             .insert(username.to_string(), (method.to_string(), ascending));
     }
+
+    /// Copy everything recorded about an entry from another title's info, for an entry
+    /// that moved between titles; what `from` has replaces what is recorded here
+    pub fn copy_entry(&mut self, from: &TitleInfo, entry_id: &str) {
+        copy_per_user(&mut self.progress, &from.progress, entry_id);
+        copy_per_user(&mut self.read_state, &from.read_state, entry_id);
+        copy_per_user(&mut self.last_read, &from.last_read, entry_id);
+        copy_per_user(&mut self.opened_at, &from.opened_at, entry_id);
+        copy_value(
+            &mut self.entry_display_name,
+            &from.entry_display_name,
+            entry_id,
+        );
+        copy_value(&mut self.entry_cover_url, &from.entry_cover_url, entry_id);
+        copy_value(&mut self.date_added, &from.date_added, entry_id);
+        copy_value(&mut self.entry_direction, &from.entry_direction, entry_id);
+    }
+}
+
+/// Copy an entry's value of every user (username -> entry_id -> value)
+fn copy_per_user<V: Clone>(
+    to: &mut HashMap<String, HashMap<String, V>>,
+    from: &HashMap<String, HashMap<String, V>>,
+    entry_id: &str,
+) {
+    for (username, values) in from {
+        if let Some(value) = values.get(entry_id) {
+            to.entry(username.clone())
+                .or_default()
+                .insert(entry_id.to_string(), value.clone());
+        }
+    }
+}
+
+/// Copy an entry's value (entry_id -> value)
+fn copy_value<V: Clone>(to: &mut HashMap<String, V>, from: &HashMap<String, V>, entry_id: &str) {
+    if let Some(value) = from.get(entry_id) {
+        to.insert(entry_id.to_string(), value.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed a sequence of pages through the state machine
+    fn read(pages: &[i32], total_pages: usize) -> ReadState {
+        pages.iter().fold(ReadState::default(), |state, &page| {
+            state.advance(page, total_pages)
+        })
+    }
+
+    #[test]
+    fn test_first_read_counts_once() {
+        assert_eq!(read(&[1, 5, 9], 10).count, 0);
+        assert_eq!(read(&[1, 5, 10], 10).count, 1);
+        // Staying on the last page is still one read
+        assert_eq!(read(&[1, 10, 10, 10], 10).count, 1);
+    }
+
+    #[test]
+    fn test_jitter_is_not_a_reread() {
+        assert_eq!(read(&[10, 9, 10, 5, 10], 10).count, 1);
+        // The threshold itself is not below the threshold
+        assert_eq!(read(&[10, 2, 10], 10).count, 1);
+    }
+
+    #[test]
+    fn test_reread_after_going_back() {
+        let state = read(&[10, 1], 10);
+        assert_eq!(state.count, 1);
+        assert!(state.restarted);
+
+        let state = read(&[10, 1, 5, 10], 10);
+        assert_eq!(state.count, 2);
+        assert!(!state.restarted);
+        assert_eq!(state.rereads(), 1);
+
+        // Marking as unread (page 0) and reading again is a re-read too
+        assert_eq!(read(&[10, 0, 10, 0, 10], 10).count, 3);
+    }
+
+    #[test]
+    fn test_empty_entry_is_never_read() {
+        assert_eq!(read(&[0, 1, 5], 0), ReadState::default());
+    }
+
+    #[test]
+    fn test_set_progress_tracks_read_state() {
+        let mut info = TitleInfo::default();
+        info.set_progress("alice", "e1", 3, 10);
+        assert!(
+            info.read_state.is_empty(),
+            "unfinished entries store no state"
+        );
+
+        info.set_progress("alice", "e1", 10, 10);
+        info.set_progress("alice", "e1", 1, 10);
+        info.set_progress("alice", "e1", 10, 10);
+        info.set_progress("bob", "e1", 10, 10);
+        assert_eq!(info.get_read_state("alice", "e1").count, 2);
+        assert_eq!(info.get_rereads("alice"), 1);
+        assert_eq!(info.get_rereads("bob"), 0);
+
+        // Read counts survive an info.json round trip
+        let json = serde_json::to_string(&info).unwrap();
+        let loaded: TitleInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.get_read_state("alice", "e1").count, 2);
+    }
+
+    #[test]
+    fn test_copy_entry() {
+        let mut from = TitleInfo::default();
+        from.set_progress("alice", "moved", 10, 10);
+        from.set_progress("bob", "moved", 4, 10);
+        from.set_progress("alice", "stays", 2, 10);
+        from.set_date_added("moved", 1_000);
+        from.entry_display_name
+            .insert("moved".to_string(), "Extra".to_string());
+
+        let mut to = TitleInfo::default();
+        to.set_date_added("moved", 9_000);
+        to.set_progress("carol", "other", 1, 10);
+        to.copy_entry(&from, "moved");
+
+        assert_eq!(to.get_progress("alice", "moved"), Some(10));
+        assert_eq!(to.get_progress("bob", "moved"), Some(4));
+        assert_eq!(to.get_read_state("alice", "moved").count, 1);
+        assert!(to.get_last_read("alice", "moved").is_some());
+        assert_eq!(to.get_date_added("moved"), Some(1_000));
+        assert_eq!(to.entry_display_name["moved"], "Extra");
+        assert_eq!(to.get_progress("alice", "stays"), None);
+        assert_eq!(to.get_progress("carol", "other"), Some(1));
+    }
+
+    #[test]
+    fn test_backfill_date_added() {
+        let mut info = TitleInfo::default();
+        info.set_date_added("imported", 1_000);
+        info.set_date_added("added_later", 5_000);
+
+        // Only import stamps and missing dates are replaced
+        let window = Some((900, 1_100));
+        assert!(info.backfill_date_added("imported", 10, window));
+        assert!(!info.backfill_date_added("added_later", 20, window));
+        assert!(info.backfill_date_added("missing", 30, window));
+        assert_eq!(info.get_date_added("imported"), Some(10));
+        assert_eq!(info.get_date_added("added_later"), Some(5_000));
+        assert_eq!(info.get_date_added("missing"), Some(30));
+
+        // Forced backfills replace everything, and unchanged dates are not reported
+        assert!(info.backfill_date_added("added_later", 20, None));
+        assert!(!info.backfill_date_added("imported", 10, None));
+    }
+
+    #[test]
+    fn test_record_opened_is_throttled() {
+        let start = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+        let mut info = TitleInfo::default();
+
+        assert!(info.record_opened("alice", "e1", start));
+        assert!(!info.record_opened("alice", "e1", at(OPEN_THROTTLE_SECS - 1)));
+        assert_eq!(info.get_opened_at("alice", "e1"), Some(start));
+
+        // Other users and entries have their own throttle
+        assert!(info.record_opened("bob", "e1", at(10)));
+        assert!(info.record_opened("alice", "e2", at(10)));
+
+        assert!(info.record_opened("alice", "e1", at(OPEN_THROTTLE_SECS)));
+        assert_eq!(
+            info.get_opened_at("alice", "e1"),
+            Some(at(OPEN_THROTTLE_SECS))
+        );
+
+        // Sub-second precision survives an info.json round trip
+        let precise = at(2 * OPEN_THROTTLE_SECS) + chrono::Duration::milliseconds(250);
+        assert!(info.record_opened("alice", "e1", precise));
+        let json = serde_json::to_string(&info).unwrap();
+        let loaded: TitleInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.get_opened_at("alice", "e1"), Some(precise));
+    }
 }
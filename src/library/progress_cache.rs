@@ -3,7 +3,7 @@ use std::path::Path;
 use std::sync::RwLock;
 
 use crate::error::{Error, Result};
-use crate::library::progress::TitleInfo;
+use crate::library::progress::{ReadState, ReadingDirection, TitleInfo};
 
 /// In-memory cache for progress data from info.json files
 /// Eliminates O(N) filesystem reads when loading progress
@@ -39,12 +39,37 @@ impl ProgressCache {
         data.get(title_id)?.get_progress(username, entry_id)
     }
 
+    /// Get the read count state for an entry from cache
+    pub fn get_read_state(&self, title_id: &str, username: &str, entry_id: &str) -> ReadState {
+        self.read_data()
+            .and_then(|data| Some(data.get(title_id)?.get_read_state(username, entry_id)))
+            .unwrap_or_default()
+    }
+
+    /// Total re-reads by a user across all cached titles
+    pub fn get_total_rereads(&self, username: &str) -> u32 {
+        self.read_data()
+            .map(|data| data.values().map(|info| info.get_rereads(username)).sum())
+            .unwrap_or(0)
+    }
+
     /// Get last read timestamp from cache
     pub fn get_last_read(&self, title_id: &str, username: &str, entry_id: &str) -> Option<i64> {
         let data = self.read_data()?;
         data.get(title_id)?.get_last_read(username, entry_id)
     }
 
+    /// Get when a user last opened an entry from cache
+    pub fn get_opened_at(
+        &self,
+        title_id: &str,
+        username: &str,
+        entry_id: &str,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        let data = self.read_data()?;
+        data.get(title_id)?.get_opened_at(username, entry_id)
+    }
+
     /// Get date added from cache
     pub fn get_date_added(&self, title_id: &str, entry_id: &str) -> Option<i64> {
         let data = self.read_data()?;
@@ -62,6 +87,38 @@ impl ProgressCache {
         }
     }
 
+    /// Get the pinned entry order for a title (None if no override is set)
+    pub fn get_entry_order(&self, title_id: &str) -> Option<Vec<String>> {
+        let data = self.read_data()?;
+        let info = data.get(title_id)?;
+        if info.entry_order.is_empty() {
+            None
+        } else {
+            Some(info.entry_order.clone())
+        }
+    }
+
+    /// Get the (title, entry) reading direction overrides for an entry
+    pub fn get_direction_overrides(
+        &self,
+        title_id: &str,
+        entry_id: &str,
+    ) -> (Option<ReadingDirection>, Option<ReadingDirection>) {
+        let Some(data) = self.read_data() else {
+            return (None, None);
+        };
+        match data.get(title_id) {
+            Some(info) => (info.direction, info.entry_direction.get(entry_id).copied()),
+            None => (None, None),
+        }
+    }
+
+    /// Get the title-level reading direction override
+    pub fn get_title_direction(&self, title_id: &str) -> Option<ReadingDirection> {
+        let data = self.read_data()?;
+        data.get(title_id)?.direction
+    }
+
     /// Get full TitleInfo for a title (for operations needing full access)
     pub fn get_title_info(&self, title_id: &str) -> Option<TitleInfo> {
         let data = self.read_data()?;
@@ -80,6 +137,7 @@ impl ProgressCache {
     }
 
     /// Save progress and persist to info.json
+    /// This is the only place progress is written, so read counts see every change
     pub async fn save_progress(
         &self,
         title_id: &str,
@@ -87,6 +145,7 @@ impl ProgressCache {
         username: &str,
         entry_id: &str,
         page: i32,
+        total_pages: usize,
     ) -> Result<()> {
         // Update cache and clone for saving in one lock acquisition
         let info_to_save = {
@@ -97,7 +156,54 @@ impl ProgressCache {
             let info = data
                 .entry(title_id.to_string())
                 .or_insert_with(TitleInfo::default);
-            info.set_progress(username, entry_id, page);
+            info.set_progress(username, entry_id, page, total_pages);
+            info.clone()
+        };
+
+        // Persist to file (outside of lock)
+        info_to_save.save(title_path).await?;
+
+        Ok(())
+    }
+
+    /// Save progress for several entries of one title with a single info.json write
+    /// Takes (entry_id, page, total_pages) triples; read counts advance as in `save_progress`
+    pub async fn save_progress_batch(
+        &self,
+        title_id: &str,
+        title_path: &Path,
+        username: &str,
+        updates: &[(&str, i32, usize)],
+    ) -> Result<()> {
+        self.update_title_info(title_id, title_path, |info| {
+            for (entry_id, page, total_pages) in updates {
+                info.set_progress(username, entry_id, *page, *total_pages);
+            }
+        })
+        .await
+    }
+
+    /// Apply a change to a title's info and persist it to info.json
+    /// For admin operations that rewrite progress of several entries at once
+    pub async fn update_title_info(
+        &self,
+        title_id: &str,
+        title_path: &Path,
+        update: impl FnOnce(&mut TitleInfo),
+    ) -> Result<()> {
+        // Update cache and clone for saving in one lock acquisition
+        let info_to_save = {
+            let mut data = self.data.write().map_err(|e| {
+                tracing::error!(
+                    "Progress cache lock poisoned during update_title_info: {}",
+                    e
+                );
+                Error::Internal("Progress cache lock poisoned".to_string())
+            })?;
+            let info = data
+                .entry(title_id.to_string())
+                .or_insert_with(TitleInfo::default);
+            update(info);
             info.clone()
         };
 
@@ -107,6 +213,132 @@ impl ProgressCache {
         Ok(())
     }
 
+    /// Record a user opening an entry and persist to info.json
+    /// Opens throttled by `TitleInfo::record_opened` are not written; returns whether
+    /// the open was recorded
+    pub async fn record_opened(
+        &self,
+        title_id: &str,
+        title_path: &Path,
+        username: &str,
+        entry_id: &str,
+    ) -> Result<bool> {
+        // Update cache and clone for saving in one lock acquisition
+        let info_to_save = {
+            let mut data = self.data.write().map_err(|e| {
+                tracing::error!("Progress cache lock poisoned during record_opened: {}", e);
+                Error::Internal("Progress cache lock poisoned".to_string())
+            })?;
+            let info = data
+                .entry(title_id.to_string())
+                .or_insert_with(TitleInfo::default);
+            if !info.record_opened(username, entry_id, chrono::Utc::now()) {
+                return Ok(false);
+            }
+            info.clone()
+        };
+
+        // Persist to file (outside of lock)
+        info_to_save.save(title_path).await?;
+
+        Ok(true)
+    }
+
+    /// Save a pinned entry order and persist to info.json
+    /// An empty list clears the override
+    pub async fn save_entry_order(
+        &self,
+        title_id: &str,
+        title_path: &Path,
+        entry_order: Vec<String>,
+    ) -> Result<()> {
+        // Update cache and clone for saving in one lock acquisition
+        let info_to_save = {
+            let mut data = self.data.write().map_err(|e| {
+                tracing::error!(
+                    "Progress cache lock poisoned during save_entry_order: {}",
+                    e
+                );
+                Error::Internal("Progress cache lock poisoned".to_string())
+            })?;
+            let info = data
+                .entry(title_id.to_string())
+                .or_insert_with(TitleInfo::default);
+            info.entry_order = entry_order;
+            info.clone()
+        };
+
+        // Persist to file (outside of lock)
+        info_to_save.save(title_path).await?;
+
+        Ok(())
+    }
+
+    /// Save a reading direction override (None clears it) and persist to info.json
+    /// Applies to one entry when entry_id is given, otherwise to the whole title
+    pub async fn save_direction(
+        &self,
+        title_id: &str,
+        title_path: &Path,
+        entry_id: Option<&str>,
+        direction: Option<ReadingDirection>,
+    ) -> Result<()> {
+        // Update cache and clone for saving in one lock acquisition
+        let info_to_save = {
+            let mut data = self.data.write().map_err(|e| {
+                tracing::error!("Progress cache lock poisoned during save_direction: {}", e);
+                Error::Internal("Progress cache lock poisoned".to_string())
+            })?;
+            let info = data
+                .entry(title_id.to_string())
+                .or_insert_with(TitleInfo::default);
+            info.set_direction(entry_id, direction);
+            info.clone()
+        };
+
+        // Persist to file (outside of lock)
+        info_to_save.save(title_path).await?;
+
+        Ok(())
+    }
+
+    /// Backfill date added from file mtimes (see `TitleInfo::backfill_date_added`)
+    /// Takes (entry_id, mtime) pairs and persists to info.json if anything changed
+    /// Returns the number of entries updated
+    pub async fn backfill_date_added(
+        &self,
+        title_id: &str,
+        title_path: &Path,
+        entries: &[(&str, i64)],
+        window: Option<(i64, i64)>,
+    ) -> Result<usize> {
+        // Update cache and clone for saving in one lock acquisition
+        let (updated, info_to_save) = {
+            let mut data = self.data.write().map_err(|e| {
+                tracing::error!(
+                    "Progress cache lock poisoned during backfill_date_added: {}",
+                    e
+                );
+                Error::Internal("Progress cache lock poisoned".to_string())
+            })?;
+            let info = data
+                .entry(title_id.to_string())
+                .or_insert_with(TitleInfo::default);
+            let updated = entries
+                .iter()
+                .filter(|(entry_id, mtime)| info.backfill_date_added(entry_id, *mtime, window))
+                .count();
+            (updated, info.clone())
+        };
+
+        // Persist to file (outside of lock)
+        if updated > 0 {
+            info_to_save.save(title_path).await?;
+        }
+
+        Ok(updated)
+    }
+
     /// Clear cache (for rescans)
     pub fn clear(&self) {
         match self.data.write() {
@@ -0,0 +1,156 @@
+// Scan Filter - files and directories library scans leave out
+//
+// Names starting with a dot or an underscore are skipped (config `scan_skip_hidden`), and
+// so is anything whose name matches a glob of `scan_exclude_patterns`, like the `@eaDir`
+// thumbnail folders a NAS puts everywhere or a `*.sample.zip` next to the real chapters.
+// The filter applies in the library root and inside titles alike. Globs match the whole
+// name, case-insensitively: `*` is any run of characters, `?` one character and `[...]`
+// (or `[!...]`) one of (or none of) a set.
+
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::error::{Error, Result};
+
+/// What scans leave out
+#[derive(Debug, Clone, Default)]
+pub struct ScanFilter {
+    skip_hidden: bool,
+    exclude: Vec<Regex>,
+}
+
+impl ScanFilter {
+    /// Compile the exclude patterns, failing on the first invalid one
+    pub fn new(skip_hidden: bool, patterns: &[String]) -> Result<Self> {
+        let exclude = patterns
+            .iter()
+            .map(|p| {
+                glob_regex(p).map_err(|e| {
+                    Error::Config(format!("Invalid scan exclude pattern {:?}: {}", p, e))
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            skip_hidden,
+            exclude,
+        })
+    }
+
+    /// Filter configured by `scan_skip_hidden` and `scan_exclude_patterns`
+    /// The patterns are checked when the config is loaded
+    pub fn from_config(config: &crate::Config) -> Self {
+        Self::new(config.scan_skip_hidden, &config.scan_exclude_patterns).unwrap_or_else(|e| {
+            tracing::warn!("{}, excluding nothing by pattern", e);
+            Self {
+                skip_hidden: config.scan_skip_hidden,
+                exclude: Vec::new(),
+            }
+        })
+    }
+
+    /// Whether a scan leaves the file or directory at `path` out
+    pub fn excludes(&self, path: &Path) -> bool {
+        if self.skip_hidden && super::title::is_hidden(path) {
+            return true;
+        }
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy()) else {
+            return false;
+        };
+        self.exclude.iter().any(|glob| glob.is_match(&name))
+    }
+}
+
+/// Regex matching the names a glob matches
+fn glob_regex(glob: &str) -> std::result::Result<Regex, String> {
+    if glob.contains('/') {
+        return Err("patterns match names, not paths".to_string());
+    }
+
+    let mut regex = String::from("(?i)^");
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                let mut set = String::from("[");
+                let mut closed = false;
+                let mut first = true;
+                for c in chars.by_ref() {
+                    match c {
+                        ']' if !first => {
+                            closed = true;
+                            break;
+                        }
+                        '!' if first => set.push('^'),
+                        '-' => set.push('-'),
+                        c => set.push_str(&regex::escape(&c.to_string())),
+                    }
+                    first = false;
+                }
+                if !closed {
+                    return Err("unclosed [".to_string());
+                }
+                set.push(']');
+                regex.push_str(&set);
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(globs: &[&str]) -> Vec<String> {
+        globs.iter().map(|g| g.to_string()).collect()
+    }
+
+    #[test]
+    fn test_excludes() {
+        let filter = ScanFilter::new(
+            true,
+            &patterns(&["@eaDir", "*.sample.zip", "tmp-?", "[!a-c]x.cbz"]),
+        )
+        .unwrap();
+        let excluded = |name: &str| filter.excludes(&Path::new("/library/Series").join(name));
+
+        assert!(excluded("@eaDir"));
+        assert!(excluded("@EADIR"));
+        assert!(excluded("Chapter 1.sample.zip"));
+        assert!(excluded("tmp-1"));
+        assert!(excluded("dx.cbz"));
+        assert!(excluded(".stfolder"));
+        assert!(excluded("_incoming"));
+
+        assert!(!excluded("Chapter 1.zip"));
+        assert!(!excluded("sample.zip.bak"));
+        assert!(!excluded("tmp-10"));
+        assert!(!excluded("ax.cbz"));
+        assert!(!excluded("x@eaDir"));
+
+        // Without skip_hidden only the patterns apply
+        let filter = ScanFilter::new(false, &patterns(&["_incoming"])).unwrap();
+        assert!(!filter.excludes(Path::new(".stfolder")));
+        assert!(filter.excludes(Path::new("_incoming")));
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        for glob in ["[abc", "Series/*.zip", "[]"] {
+            assert!(
+                ScanFilter::new(true, &patterns(&[glob])).is_err(),
+                "{:?}",
+                glob
+            );
+        }
+        // Regex syntax in a glob is taken literally
+        let filter = ScanFilter::new(false, &patterns(&["(v01)+.zip"])).unwrap();
+        assert!(filter.excludes(Path::new("(v01)+.zip")));
+        assert!(!filter.excludes(Path::new("v01v01.zip")));
+    }
+}
@@ -0,0 +1,280 @@
+// Scan journal - checkpoints a full library scan title by title
+//
+// A scan of a very large library can take the better part of an hour. Every finished
+// title is written to the journal together with its new IDs, so a scan interrupted by a
+// restart resumes from the pending directories and reuses finished titles whose
+// directory is unchanged. The journal is cleared when the scan completes.
+
+use std::collections::{HashMap, HashSet};
+
+use super::manager::{Library, NewIds};
+use super::title::Title;
+use crate::error::Result;
+use crate::Storage;
+
+/// Progress of the journaled scan
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScanProgress {
+    pub scan_id: String,
+    /// Title directories finished
+    pub done: usize,
+    /// Title directories in the scan
+    pub total: usize,
+    pub percent: f64,
+}
+
+/// A title finished by an earlier, interrupted run of the scan
+#[derive(Debug)]
+pub(super) struct CompletedTitle {
+    /// Contents signature of the directory when it was scanned
+    pub contents_signature: String,
+    pub title: Title,
+}
+
+/// Journal of the scan in progress
+pub struct ScanJournal {
+    storage: Storage,
+    scan_id: String,
+}
+
+impl ScanJournal {
+    /// Start a journal for the given title directories (relative to the library root),
+    /// or resume the unfinished one. Directories that disappeared are dropped and new
+    /// ones are added as pending
+    /// Returns the journal and the titles it already finished, by relative path
+    pub(super) async fn open(
+        storage: &Storage,
+        paths: &[String],
+    ) -> Result<(Self, HashMap<String, CompletedTitle>)> {
+        let now = crate::util::time::now_ts();
+        let mut tx = storage.pool().begin().await?;
+
+        let existing = sqlx::query_scalar::<_, String>("SELECT scan_id FROM scan_journal LIMIT 1")
+            .fetch_optional(&mut *tx)
+            .await?;
+        let scan_id = match existing {
+            Some(scan_id) => {
+                tracing::info!("Resuming interrupted library scan {}", scan_id);
+                scan_id
+            }
+            None => uuid::Uuid::new_v4().to_string(),
+        };
+
+        let wanted: HashSet<&str> = paths.iter().map(String::as_str).collect();
+        let journaled: Vec<String> = sqlx::query_scalar("SELECT path FROM scan_journal")
+            .fetch_all(&mut *tx)
+            .await?;
+        for path in journaled.iter().filter(|p| !wanted.contains(p.as_str())) {
+            sqlx::query("DELETE FROM scan_journal WHERE path = ?")
+                .bind(path)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for path in paths {
+            sqlx::query(
+                "INSERT OR IGNORE INTO scan_journal (path, scan_id, done, updated_at)
+                 VALUES (?, ?, 0, ?)",
+            )
+            .bind(path)
+            .bind(&scan_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let rows: Vec<(String, String, Vec<u8>)> = sqlx::query_as(
+            "SELECT path, contents_signature, result FROM scan_journal
+             WHERE done = 1 AND result IS NOT NULL AND contents_signature IS NOT NULL",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let mut completed = HashMap::new();
+        for (path, contents_signature, result) in rows {
+            match rmp_serde::from_slice::<Title>(&result) {
+                Ok(title) => {
+                    completed.insert(
+                        path,
+                        CompletedTitle {
+                            contents_signature,
+                            title,
+                        },
+                    );
+                }
+                Err(e) => {
+                    // Scanned again like a pending title
+                    tracing::warn!("Discarding unreadable scan journal row for {}: {}", path, e);
+                }
+            }
+        }
+
+        Ok((
+            Self {
+                storage: storage.clone(),
+                scan_id,
+            },
+            completed,
+        ))
+    }
+
+    /// Record a finished title directory together with its new IDs, in one transaction
+    /// `title` is None when the directory could not be scanned
+    pub(super) async fn checkpoint(
+        &self,
+        path: &str,
+        title: Option<&Title>,
+        ids: &NewIds,
+    ) -> Result<()> {
+        let result = match title {
+            Some(title) => Some(rmp_serde::to_vec(title).map_err(|e| {
+                crate::error::Error::Internal(format!("Failed to serialize title: {}", e))
+            })?),
+            None => None,
+        };
+
+        let mut tx = self.storage.pool().begin().await?;
+        Library::insert_ids(&mut tx, ids).await?;
+        sqlx::query(
+            "UPDATE scan_journal SET done = 1, contents_signature = ?, result = ?, updated_at = ?
+             WHERE path = ? AND scan_id = ?",
+        )
+        .bind(title.map(|t| t.contents_signature.as_str()))
+        .bind(result)
+        .bind(crate::util::time::now_ts())
+        .bind(path)
+        .bind(&self.scan_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// ID of the scan, kept when an interrupted scan resumes
+    pub(super) fn scan_id(&self) -> &str {
+        &self.scan_id
+    }
+
+    /// Clear the journal once the scan has completed (in the caller's transaction, with
+    /// the scan's final updates)
+    pub(super) async fn finish(self, conn: &mut sqlx::SqliteConnection) -> Result<()> {
+        sqlx::query("DELETE FROM scan_journal")
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Progress of the journaled scan, None if no scan is running or interrupted
+    pub async fn progress(storage: &Storage) -> Result<Option<ScanProgress>> {
+        let row: Option<(String, i64, i64)> = sqlx::query_as(
+            "SELECT scan_id, COUNT(*), COALESCE(SUM(done), 0) FROM scan_journal GROUP BY scan_id",
+        )
+        .fetch_optional(storage.pool())
+        .await?;
+
+        Ok(row.map(|(scan_id, total, done)| {
+            let (total, done) = (total as usize, done as usize);
+            ScanProgress {
+                scan_id,
+                done,
+                total,
+                percent: if total == 0 {
+                    100.0
+                } else {
+                    done as f64 * 100.0 / total as f64
+                },
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    async fn setup_storage() -> (tempfile::TempDir, Storage) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        (temp_dir, storage)
+    }
+
+    fn title(id: &str, name: &str) -> Title {
+        Title {
+            id: id.to_string(),
+            path: PathBuf::from("/library").join(name),
+            title: name.to_string(),
+            signature: "1".to_string(),
+            contents_signature: format!("contents-{}", name),
+            mtime: 0,
+            entries: Vec::new(),
+            parent_id: None,
+            nested_titles: Vec::new(),
+            cover_path: None,
+            source: crate::library::TitleSource::Directory,
+            unreadable: Vec::new(),
+            broken_entries: Vec::new(),
+        }
+    }
+
+    fn paths(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_journal_resumes_after_interruption() {
+        let (_temp_dir, storage) = setup_storage().await;
+        assert!(ScanJournal::progress(&storage).await.unwrap().is_none());
+
+        let (journal, completed) = ScanJournal::open(&storage, &paths(&["A", "B", "C"]))
+            .await
+            .unwrap();
+        assert!(completed.is_empty());
+
+        let mut ids = NewIds::default();
+        ids.titles
+            .push(("t-a".to_string(), "A".to_string(), "1".to_string()));
+        journal
+            .checkpoint("A", Some(&title("t-a", "A")), &ids)
+            .await
+            .unwrap();
+        journal
+            .checkpoint("B", None, &NewIds::default())
+            .await
+            .unwrap();
+
+        let progress = ScanJournal::progress(&storage).await.unwrap().unwrap();
+        assert_eq!((progress.done, progress.total), (2, 3));
+        let scan_id = progress.scan_id;
+
+        // The new title ID was committed with its checkpoint
+        let id: String = sqlx::query_scalar("SELECT id FROM titles WHERE path = 'A'")
+            .fetch_one(storage.pool())
+            .await
+            .unwrap();
+        assert_eq!(id, "t-a");
+
+        // "Restart": C disappeared and D appeared
+        drop(journal);
+        let (journal, completed) = ScanJournal::open(&storage, &paths(&["A", "B", "D"]))
+            .await
+            .unwrap();
+        assert_eq!(completed.len(), 1, "failed titles are scanned again");
+        assert_eq!(completed["A"].title.id, "t-a");
+        assert_eq!(completed["A"].contents_signature, "contents-A");
+
+        let progress = ScanJournal::progress(&storage).await.unwrap().unwrap();
+        assert_eq!(progress.scan_id, scan_id);
+        assert_eq!((progress.done, progress.total), (2, 3));
+
+        let mut conn = storage.pool().acquire().await.unwrap();
+        journal.finish(&mut conn).await.unwrap();
+        drop(conn);
+        assert!(ScanJournal::progress(&storage).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,80 @@
+// Scan Lock - keeps scans apart from maintenance that rewrites what they match against
+//
+// Every running scan holds the lock shared. Maintenance that rewrites stored signatures,
+// deletes missing rows or moves files takes it exclusively, so it is refused while a scan
+// runs and a scan starting meanwhile waits for it. The lock belongs to the library: a
+// library built to replace the current one shares its lock (`Library::share_scan_lock`),
+// so the scans and maintenance of one app use one lock and other apps are unaffected.
+
+use std::sync::Arc;
+
+use tokio::sync::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
+
+use crate::error::{Error, Result};
+
+#[derive(Clone, Default)]
+pub struct ScanLock {
+    lock: Arc<RwLock<()>>,
+}
+
+impl ScanLock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Held by a scan while it runs; waits for maintenance holding the lock
+    pub(super) async fn scan(&self) -> OwnedRwLockReadGuard<()> {
+        self.lock.clone().read_owned().await
+    }
+
+    /// Whether a scan is currently running (or maintenance is holding scans off)
+    pub fn scan_running(&self) -> bool {
+        self.lock.try_write().is_err()
+    }
+
+    /// Block scans from starting until the guard is dropped
+    /// Returns: Conflict while a scan is running
+    pub fn block_scans(&self) -> Result<OwnedRwLockWriteGuard<()>> {
+        self.lock.clone().try_write_owned().map_err(|_| {
+            Error::Conflict("A library scan is in progress, try again when it finishes".to_string())
+        })
+    }
+
+    /// Whether `other` is the same lock
+    pub fn is_shared_with(&self, other: &ScanLock) -> bool {
+        Arc::ptr_eq(&self.lock, &other.lock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scans_and_maintenance_exclude_each_other() {
+        let lock = ScanLock::new();
+        let shared = lock.clone();
+        assert!(lock.is_shared_with(&shared));
+        assert!(!lock.scan_running());
+
+        let scanning = lock.scan().await;
+        assert!(shared.scan_running());
+        assert!(matches!(shared.block_scans(), Err(Error::Conflict(_))));
+        // Scans run side by side
+        let also_scanning = shared.scan().await;
+        drop(also_scanning);
+        drop(scanning);
+
+        let blocked = shared.block_scans().unwrap();
+        assert!(lock.scan_running());
+
+        // Another app's lock is unaffected
+        let other = ScanLock::new();
+        assert!(!other.is_shared_with(&lock));
+        assert!(!other.scan_running());
+        other.block_scans().unwrap();
+
+        drop(blocked);
+        assert!(!lock.scan_running());
+    }
+}
@@ -0,0 +1,197 @@
+// Scan Status - progress of the scan an admin started
+//
+// POST /api/admin/scan starts the scan in the background and returns at once with a job
+// ID. The scan counts the titles it found and the title directories it has left here, and
+// GET /api/admin/scan/status reports them until the job finishes, then its report.
+// Only one admin scan runs at a time; the library itself is only swapped at the end.
+// The status also keeps how the library cache compared with the disk at startup.
+
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use super::{CacheVerification, NameCollision};
+use crate::error::{Error, Result};
+
+/// A scan started by an admin
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanJob {
+    pub job_id: String,
+    /// Unix timestamp
+    pub started_at: i64,
+    /// Unix timestamp, None while the scan runs
+    pub finished_at: Option<i64>,
+    /// Titles found so far
+    pub titles_discovered: usize,
+    /// Title directories not scanned yet (0 until the library root has been listed)
+    pub directories_remaining: usize,
+    /// What the scan found, once it succeeded
+    pub report: Option<ScanReport>,
+    /// Why the scan failed
+    pub error: Option<String>,
+}
+
+/// Result of a finished scan
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanReport {
+    pub titles: usize,
+    pub milliseconds: u128,
+    /// Title directories unchanged since the last scan, kept without reading them
+    pub reused: usize,
+    /// Title directories read from disk (all of them in a full scan)
+    pub rescanned: usize,
+    /// Titles with files that could not be read, which were left out of them
+    pub unreadable: Vec<UnreadableTitle>,
+    /// Sibling title directories whose names differ only by case or whitespace
+    pub name_collisions: Vec<NameCollision>,
+}
+
+/// A title's files that could not be read in the scan
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreadableTitle {
+    pub title_id: String,
+    pub title: String,
+    pub files: Vec<UnreadableFileInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreadableFileInfo {
+    /// Relative to the library root
+    pub path: String,
+    pub error: String,
+}
+
+/// The current or last admin scan, shared by the scan and the status endpoint
+#[derive(Clone, Default)]
+pub struct ScanStatus {
+    job: Arc<Mutex<Option<ScanJob>>>,
+    cache_verification: Arc<Mutex<Option<CacheVerification>>>,
+}
+
+impl ScanStatus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a job, returning its ID
+    /// Returns: Conflict while another job is running
+    pub fn start(&self) -> Result<String> {
+        let mut job = self.lock();
+        if job.as_ref().is_some_and(|j| j.finished_at.is_none()) {
+            return Err(Error::Conflict(
+                "A library scan is already running".to_string(),
+            ));
+        }
+
+        let job_id = uuid::Uuid::new_v4().to_string();
+        *job = Some(ScanJob {
+            job_id: job_id.clone(),
+            started_at: crate::util::time::now_ts(),
+            finished_at: None,
+            titles_discovered: 0,
+            directories_remaining: 0,
+            report: None,
+            error: None,
+        });
+        Ok(job_id)
+    }
+
+    /// The running job, or the last one that finished
+    pub fn get(&self) -> Option<ScanJob> {
+        self.lock().clone()
+    }
+
+    /// Whether a job is running
+    pub fn is_running(&self) -> bool {
+        self.lock()
+            .as_ref()
+            .is_some_and(|j| j.finished_at.is_none())
+    }
+
+    /// Record the outcome of the running job
+    pub fn finish(&self, result: Result<ScanReport>) {
+        if let Some(job) = self.lock().as_mut() {
+            job.finished_at = Some(crate::util::time::now_ts());
+            match result {
+                Ok(report) => job.report = Some(report),
+                Err(e) => job.error = Some(e.to_string()),
+            }
+        }
+    }
+
+    /// The scan listed the library root and has `directories` title directories to scan
+    pub(super) fn set_remaining(&self, directories: usize) {
+        self.update(|job| job.directories_remaining = directories);
+    }
+
+    /// The scan finished a title directory, finding `titles` titles in it
+    pub(super) fn directory_done(&self, titles: usize) {
+        self.update(|job| {
+            job.directories_remaining = job.directories_remaining.saturating_sub(1);
+            job.titles_discovered += titles;
+        });
+    }
+
+    /// The scan found titles outside title directories (archives in the library root)
+    pub(super) fn titles_found(&self, titles: usize) {
+        self.update(|job| job.titles_discovered += titles);
+    }
+
+    /// Keep the result of the check of the library cache against the disk
+    pub fn set_cache_verification(&self, verification: CacheVerification) {
+        *lock(&self.cache_verification) = Some(verification);
+    }
+
+    /// Result of the check of the library cache against the disk, if one ran
+    pub fn cache_verification(&self) -> Option<CacheVerification> {
+        lock(&self.cache_verification).clone()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ScanJob)) {
+        if let Some(job) = self.lock().as_mut().filter(|j| j.finished_at.is_none()) {
+            change(job);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<ScanJob>> {
+        lock(&self.job)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_job_at_a_time() {
+        let status = ScanStatus::new();
+        assert!(status.get().is_none());
+
+        let job_id = status.start().unwrap();
+        assert!(matches!(status.start(), Err(Error::Conflict(_))));
+        status.set_remaining(3);
+        status.directory_done(2);
+        status.titles_found(1);
+
+        let job = status.get().unwrap();
+        assert_eq!(job.job_id, job_id);
+        assert_eq!(job.titles_discovered, 3);
+        assert_eq!(job.directories_remaining, 2);
+        assert!(status.is_running());
+
+        status.finish(Err(Error::Internal("disk gone".to_string())));
+        let job = status.get().unwrap();
+        assert!(job.finished_at.is_some());
+        assert!(job.error.unwrap().contains("disk gone"));
+
+        // A finished job makes way for the next one
+        assert_ne!(status.start().unwrap(), job_id);
+    }
+}
@@ -1,9 +1,40 @@
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
-use super::entry::Entry;
+use super::entry::{is_image_directory, Entry};
 use super::manager::SortMethod;
-use crate::error::Result;
+use super::scan_filter::ScanFilter;
+use crate::error::{Error, Result};
+
+/// Directory in the library root holding the info.json files of titles made of loose
+/// archives, which have no directory of their own
+pub const LOOSE_INFO_DIR: &str = ".mango-loose";
+
+/// Subdirectory of LOOSE_INFO_DIR for the unsorted title (archive names always have
+/// an extension, so it cannot clash with a single archive's)
+const UNSORTED_INFO_NAME: &str = "unsorted";
+
+/// What a title is made of
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TitleSource {
+    /// A directory of archives; `path` is the directory
+    #[default]
+    Directory,
+    /// All archives lying directly in the library root; `path` is the root
+    Unsorted,
+    /// One archive lying directly in the library root; `path` is the archive
+    LooseArchive,
+}
+
+/// An archive or image directory in a title that could not be read as an entry
+/// The title keeps its other entries; the file is retried on every scan
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UnreadableFile {
+    /// Absolute path to the file
+    pub path: PathBuf,
+    /// Why reading it failed
+    pub error: String,
+}
 
 /// Represents a manga series (directory containing chapters/volumes)
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -34,70 +65,100 @@ pub struct Title {
 
     /// Nested titles (for multi-level organization like "Series > Volume > Chapters")
     pub nested_titles: Vec<Title>,
+
+    /// Cover image file in the title directory (config `cover_file_names`), shown
+    /// instead of the first entry's cover
+    #[serde(default)]
+    pub cover_path: Option<PathBuf>,
+
+    /// A directory, or archives in the library root (config `loose_archives`)
+    #[serde(default)]
+    pub source: TitleSource,
+
+    /// Files left out of `entries` because they could not be read
+    #[serde(default)]
+    pub unreadable: Vec<UnreadableFile>,
+
+    /// Entries left out of `entries` because they have no pages (no images in the archive)
+    #[serde(default)]
+    pub broken_entries: Vec<Entry>,
 }
 
 impl Title {
     /// Create a new Title by scanning a directory
-    pub async fn from_directory(path: PathBuf) -> Result<Self> {
+    /// Symlinked archives are followed only if they resolve inside the library root
+    /// or the title's own directory; the same goes for a cover file named in `cover_names`.
+    /// Files and directories `filter` excludes are left out.
+    /// Subdirectories holding archives become nested titles, scanned the same way
+    pub async fn from_directory(
+        path: PathBuf,
+        library_root: &Path,
+        cover_names: &[String],
+        filter: &ScanFilter,
+    ) -> Result<Self> {
         let title = path
             .file_name()
             .and_then(|s| s.to_str())
             .unwrap_or("Unknown")
             .to_string();
 
-        let nested_titles = Vec::new();
-
-        // Collect all archive paths first
-        let mut archive_paths = Vec::new();
-        let mut dir_entries = tokio::fs::read_dir(&path).await?;
-
-        while let Some(entry) = dir_entries.next_entry().await? {
-            let entry_path = entry.path();
-
-            if entry_path.is_dir() {
-                // For Week 2: treat subdirectories as nested titles (simplified)
-                // TODO Week 5: Add proper nested title support
-                continue;
-            } else if is_archive(&entry_path) {
-                archive_paths.push(entry_path);
-            }
-        }
-
-        // Process all entries in parallel for better performance
-        let entry_tasks: Vec<_> = archive_paths
-            .into_iter()
-            .map(|entry_path| {
-                tokio::spawn(async move {
-                    let mut manga_entry = Entry::from_archive(entry_path).await?;
-                    manga_entry.calculate_signature()?;
-                    Ok::<Entry, crate::error::Error>(manga_entry)
-                })
+        // Sorting the directory out stats (and for symlinks canonicalizes) every file,
+        // so it runs on the blocking pool
+        let TitleDirListing {
+            entry_paths,
+            nested_paths,
+            cover_path,
+        } = {
+            let path = path.clone();
+            let library_root = library_root.to_path_buf();
+            let cover_names = cover_names.to_vec();
+            let filter = filter.clone();
+            tokio::task::spawn_blocking(move || {
+                list_title_dir(&path, &library_root, &cover_names, &filter)
             })
-            .collect();
+            .await
+            .map_err(|e| Error::Internal(format!("Task join error: {}", e)))??
+        };
 
-        // Collect all results
-        let mut entries = Vec::new();
-        for task in entry_tasks {
-            match task.await {
-                Ok(Ok(entry)) => entries.push(entry),
-                Ok(Err(e)) => {
-                    tracing::warn!("Failed to process entry: {}", e);
+        let (entries, broken_entries, unreadable) = scan_entries(entry_paths).await;
+
+        // Boxed: the future of a recursive async call must have a known size
+        let mut nested_titles = Vec::new();
+        for nested_path in nested_paths {
+            let nested = Box::pin(Self::from_directory(
+                nested_path.clone(),
+                library_root,
+                cover_names,
+                filter,
+            ))
+            .await;
+            match nested {
+                // Directories with nothing to read (extras, scans of covers) are left out,
+                // unless they hold files that failed to read or have no pages
+                Ok(nested)
+                    if !nested.deep_entries().is_empty()
+                        || !nested.unreadable.is_empty()
+                        || !nested.broken_entries.is_empty() =>
+                {
+                    nested_titles.push(nested)
                 }
+                Ok(_) => {}
                 Err(e) => {
-                    tracing::warn!("Entry processing task failed: {}", e);
+                    tracing::warn!("Failed to scan title at {}: {}", nested_path.display(), e)
                 }
             }
         }
-
-        // Sort entries by title (natural ordering)
-        entries.sort_by(|a, b| natord::compare(&a.title, &b.title));
+        nested_titles.sort_by(|a, b| natord::compare(&a.title, &b.title));
 
         // Calculate latest mtime
-        let mtime = entries.iter().map(|e| e.mtime).max().unwrap_or(0);
+        let mtime = entries
+            .iter()
+            .map(|e| e.mtime)
+            .chain(nested_titles.iter().map(|t| t.mtime))
+            .max()
+            .unwrap_or(0);
 
-        // Calculate signatures
-        let signature = calculate_dir_signature(&path)?;
-        let contents_signature = calculate_contents_signature(&path)?;
+        let (signature, contents_signature) = title_signatures(&path).await?;
 
         Ok(Self {
             id: Uuid::new_v4().to_string(),
@@ -109,9 +170,105 @@ impl Title {
             entries,
             parent_id: None,
             nested_titles,
+            cover_path,
+            source: TitleSource::Directory,
+            unreadable,
+            broken_entries,
+        })
+    }
+
+    /// Create the title of the archives lying directly in the library root
+    /// (`loose_archives: unsorted`); the caller has already filtered `archive_paths`
+    pub async fn from_loose_archives(
+        library_root: &Path,
+        archive_paths: Vec<PathBuf>,
+        name: &str,
+    ) -> Result<Self> {
+        let (entries, broken_entries, unreadable) = scan_entries(archive_paths).await;
+        let mtime = entries.iter().map(|e| e.mtime).max().unwrap_or(0);
+
+        // Like a directory signature, but over the loose archives only
+        let mut signatures: Vec<&str> = entries.iter().map(|e| e.signature.as_str()).collect();
+        signatures.sort_unstable();
+        let signature = crc32fast::hash(signatures.concat().as_bytes()).to_string();
+        let contents_signature = contents_hash(entries.iter().filter_map(archive_name).collect());
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            path: library_root.to_path_buf(),
+            title: name.to_string(),
+            signature,
+            contents_signature,
+            mtime,
+            entries,
+            parent_id: None,
+            nested_titles: Vec::new(),
+            cover_path: None,
+            source: TitleSource::Unsorted,
+            unreadable,
+            broken_entries,
+        })
+    }
+
+    /// Create a single-entry title of an archive lying directly in the library root
+    /// (`loose_archives: title`), named after the archive
+    pub async fn from_loose_archive(archive_path: PathBuf) -> Result<Self> {
+        let entry = with_signature(Entry::from_archive(archive_path.clone()).await?).await?;
+        let (entries, broken_entries) = if entry.pages == 0 {
+            warn_no_pages(&entry);
+            (Vec::new(), vec![entry.clone()])
+        } else {
+            (vec![entry.clone()], Vec::new())
+        };
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            path: archive_path,
+            title: entry.title.clone(),
+            signature: entry.signature.clone(),
+            contents_signature: contents_hash(archive_name(&entry).into_iter().collect()),
+            mtime: entry.mtime,
+            entries,
+            parent_id: None,
+            nested_titles: Vec::new(),
+            cover_path: None,
+            source: TitleSource::LooseArchive,
+            unreadable: Vec::new(),
+            broken_entries,
         })
     }
 
+    /// Set the entries' display names from their raw titles (config `entry_title_cleanup`)
+    pub fn refresh_display_titles(&mut self) {
+        let cleanup = super::TitleCleanup::current();
+        for entry in &mut self.entries {
+            entry.display_title = cleanup.display_title(&entry.title, &self.title);
+        }
+        for nested in &mut self.nested_titles {
+            nested.refresh_display_titles();
+        }
+    }
+
+    /// Directory holding the title's info.json (progress, display names, sort)
+    /// Titles of loose archives keep theirs under LOOSE_INFO_DIR in the library root
+    pub fn info_dir(&self) -> PathBuf {
+        match self.source {
+            TitleSource::Directory => self.path.clone(),
+            TitleSource::Unsorted => self.path.join(LOOSE_INFO_DIR).join(UNSORTED_INFO_NAME),
+            TitleSource::LooseArchive => {
+                let root = self.path.parent().unwrap_or(&self.path);
+                let name = self.path.file_name().unwrap_or_default();
+                root.join(LOOSE_INFO_DIR).join(name)
+            }
+        }
+    }
+
+    /// Path of the title relative to the library root, safe to show to users
+    /// Empty for the unsorted title, which is the root itself
+    pub fn relative_path(&self, library_root: &Path) -> String {
+        crate::util::relative_to_root(&self.path, library_root)
+    }
+
     /// Get total number of pages across all entries
     pub fn total_pages(&self) -> usize {
         self.entries.iter().map(|e| e.pages).sum()
@@ -121,14 +278,21 @@ impl Title {
     pub fn get_entries_sorted(&self, method: SortMethod, ascending: bool) -> Vec<&Entry> {
         let mut entries: Vec<&Entry> = self.entries.iter().collect();
 
-        use super::{sort_by_mtime, sort_by_name};
+        use super::{sort_by_auto, sort_by_mtime, sort_by_name};
 
         match method {
-            SortMethod::Name | SortMethod::Progress | SortMethod::Auto => {
+            SortMethod::Name
+            | SortMethod::Progress
+            | SortMethod::Popularity
+            | SortMethod::Opened => {
                 // Progress sorting doesn't apply to entries (only at route level with username context)
-                // Auto uses name sorting (future: smart chapter detection)
+                // Last opened needs the username too (see LibraryManager::get_entries_sorted_for)
+                // Popularity is tracked per title, so entries fall back to name order
                 sort_by_name(&mut entries, ascending);
             }
+            SortMethod::Auto => {
+                sort_by_auto(&mut entries, ascending);
+            }
             SortMethod::TimeModified => {
                 sort_by_mtime(&mut entries, ascending);
             }
@@ -137,6 +301,40 @@ impl Title {
         entries
     }
 
+    /// Get entries in a pinned order
+    /// Entries missing from the order (e.g. newly added chapters) follow in Auto order
+    pub fn get_entries_pinned(&self, order: &[String]) -> Vec<&Entry> {
+        let mut entries: Vec<&Entry> = order
+            .iter()
+            .filter_map(|id| self.entries.iter().find(|e| e.id == *id))
+            .collect();
+
+        let rest = self
+            .get_entries_sorted(SortMethod::Auto, true)
+            .into_iter()
+            .filter(|e| !order.contains(&e.id));
+        entries.extend(rest);
+
+        entries
+    }
+
+    /// This title and all titles nested in it, recursively (parents first)
+    pub fn deep_titles(&self) -> Vec<&Title> {
+        let mut titles = vec![self];
+        for nested in &self.nested_titles {
+            titles.extend(nested.deep_titles());
+        }
+        titles
+    }
+
+    /// Find this title or a title nested in it by ID
+    pub fn find_title(&self, id: &str) -> Option<&Title> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.nested_titles.iter().find_map(|t| t.find_title(id))
+    }
+
     /// Get all entries recursively (including nested titles)
     pub fn deep_entries(&self) -> Vec<&Entry> {
         let mut all_entries = Vec::new();
@@ -154,33 +352,11 @@ impl Title {
         all_entries
     }
 
-    /// Save reading progress for an entry
-    pub async fn save_entry_progress(
-        &self,
-        username: &str,
-        entry_id: &str,
-        page: i32,
-    ) -> Result<()> {
-        use super::progress::TitleInfo;
-
-        let mut info = TitleInfo::load(&self.path).await?;
-
-        // If page is 0, remove the progress (mark as unread)
-        if page == 0 {
-            info.remove_progress(username, entry_id);
-        } else {
-            info.set_progress(username, entry_id, page);
-        }
-
-        info.save(&self.path).await?;
-        Ok(())
-    }
-
     /// Load reading progress for an entry
     pub async fn load_entry_progress(&self, username: &str, entry_id: &str) -> Result<i32> {
         use super::progress::TitleInfo;
 
-        let info = TitleInfo::load(&self.path).await?;
+        let info = TitleInfo::load(&self.info_dir()).await?;
         Ok(info.get_progress(username, entry_id).unwrap_or(0))
     }
 
@@ -205,36 +381,6 @@ impl Title {
         Ok((percentage, page))
     }
 
-    /// Mark all entries as read
-    pub async fn read_all(&self, username: &str) -> Result<()> {
-        use super::progress::TitleInfo;
-
-        let mut info = TitleInfo::load(&self.path).await?;
-
-        // Set progress to last page for all entries
-        for entry in &self.entries {
-            info.set_progress(username, &entry.id, entry.pages as i32);
-        }
-
-        info.save(&self.path).await?;
-        Ok(())
-    }
-
-    /// Mark all entries as unread
-    pub async fn unread_all(&self, username: &str) -> Result<()> {
-        use super::progress::TitleInfo;
-
-        let mut info = TitleInfo::load(&self.path).await?;
-
-        // Remove progress for all entries
-        for entry in &self.entries {
-            info.remove_progress(username, &entry.id);
-        }
-
-        info.save(&self.path).await?;
-        Ok(())
-    }
-
     /// Get overall title progress (average across all entries)
     pub async fn get_title_progress(&self, username: &str) -> Result<f32> {
         if self.entries.is_empty() {
@@ -242,7 +388,7 @@ impl Title {
         }
 
         use super::progress::TitleInfo;
-        let info = TitleInfo::load(&self.path).await?;
+        let info = TitleInfo::load(&self.info_dir()).await?;
 
         let mut total_progress = 0.0;
         let mut entry_count = 0;
@@ -263,27 +409,196 @@ impl Title {
 
     /// Populate date_added timestamps for newly discovered entries
     /// Should be called after scanning to track when entries were first discovered
-    pub async fn populate_date_added(&self) -> Result<()> {
+    /// With `from_mtime`, new entries are dated by their file mtime instead of now
+    pub async fn populate_date_added(&self, from_mtime: bool) -> Result<()> {
         use super::progress::TitleInfo;
 
-        let mut info = TitleInfo::load(&self.path).await?;
-        let now = chrono::Utc::now().timestamp();
+        let info_dir = self.info_dir();
+        if self.source != TitleSource::Directory {
+            tokio::fs::create_dir_all(&info_dir).await?;
+        }
+        let mut info = TitleInfo::load(&info_dir).await?;
+        let now = crate::util::time::now_ts();
 
         for entry in &self.entries {
             // Only set if not already set (preserve original date for existing entries)
-            info.set_date_added_if_new(&entry.id, now);
+            let date = if from_mtime { entry.mtime } else { now };
+            info.set_date_added_if_new(&entry.id, date);
         }
 
-        info.save(&self.path).await?;
+        info.save(&info_dir).await?;
         Ok(())
     }
 }
 
+/// Read the given archives and image directories into entries, in parallel, sorted by name
+/// Entries without pages and files that cannot be read are logged and returned separately,
+/// the latter sorted by path
+async fn scan_entries(entry_paths: Vec<PathBuf>) -> (Vec<Entry>, Vec<Entry>, Vec<UnreadableFile>) {
+    let entry_tasks: Vec<_> = entry_paths
+        .into_iter()
+        .map(|entry_path| {
+            let task = tokio::spawn({
+                let entry_path = entry_path.clone();
+                async move {
+                    let manga_entry = if tokio::fs::metadata(&entry_path).await?.is_dir() {
+                        Entry::from_directory(entry_path).await?
+                    } else {
+                        Entry::from_archive(entry_path).await?
+                    };
+                    with_signature(manga_entry).await
+                }
+            });
+            (entry_path, task)
+        })
+        .collect();
+
+    let mut entries = Vec::new();
+    let mut broken = Vec::new();
+    let mut unreadable = Vec::new();
+    for (path, task) in entry_tasks {
+        let error = match task.await {
+            Ok(Ok(entry)) if entry.pages == 0 => {
+                warn_no_pages(&entry);
+                broken.push(entry);
+                continue;
+            }
+            Ok(Ok(entry)) => {
+                entries.push(entry);
+                continue;
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(e) => format!("Entry processing task failed: {}", e),
+        };
+        tracing::warn!("Failed to process entry {}: {}", path.display(), error);
+        unreadable.push(UnreadableFile { path, error });
+    }
+
+    // Sort entries by title (natural ordering)
+    entries.sort_by(|a, b| natord::compare(&a.title, &b.title));
+    broken.sort_by(|a, b| natord::compare(&a.title, &b.title));
+    unreadable.sort_by(|a, b| a.path.cmp(&b.path));
+    (entries, broken, unreadable)
+}
+
+/// Log an entry left out of its title for having no pages
+fn warn_no_pages(entry: &Entry) {
+    tracing::warn!(
+        "Entry {} has no pages, leaving it out",
+        entry.path.display()
+    );
+}
+
+/// Calculate an entry's signature on the blocking pool (it stats every file with std::fs)
+async fn with_signature(mut entry: Entry) -> Result<Entry> {
+    tokio::task::spawn_blocking(move || {
+        entry.calculate_signature()?;
+        Ok(entry)
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Task join error: {}", e)))?
+}
+
+/// What a title directory holds
+struct TitleDirListing {
+    /// Archives and image directories
+    entry_paths: Vec<PathBuf>,
+    /// Other directories, scanned as nested titles
+    nested_paths: Vec<PathBuf>,
+    cover_path: Option<PathBuf>,
+}
+
+/// Sort a title directory's files out into entries, nested titles and its cover
+/// Blocking; see `Title::from_directory` for the rules
+fn list_title_dir(
+    path: &Path,
+    library_root: &Path,
+    cover_names: &[String],
+    filter: &ScanFilter,
+) -> Result<TitleDirListing> {
+    let mut entry_paths = Vec::new();
+    let mut nested_paths = Vec::new();
+    let mut cover_files = Vec::new();
+
+    for entry in std::fs::read_dir(path)? {
+        let entry_path = entry?.path();
+        if filter.excludes(&entry_path) {
+            continue;
+        }
+
+        if entry_path.is_dir() {
+            // A directory of images is an entry, any other directory a nested title
+            if super::convert::is_backup_dir(&entry_path) {
+                // An original kept by an entry conversion
+                continue;
+            } else if !crate::util::symlink_within_roots(&entry_path, &[library_root, path]) {
+                tracing::warn!(
+                    "Skipping {}: symlink points outside the library",
+                    entry_path.display()
+                );
+            } else if is_image_directory(&entry_path) {
+                entry_paths.push(entry_path);
+            } else {
+                nested_paths.push(entry_path);
+            }
+        } else if is_archive(&entry_path) {
+            if !crate::util::symlink_within_roots(&entry_path, &[library_root, path]) {
+                tracing::warn!(
+                    "Skipping {}: symlink points outside the library",
+                    entry_path.display()
+                );
+                continue;
+            }
+            entry_paths.push(entry_path);
+        } else if cover_rank(&entry_path, cover_names).is_some()
+            && crate::util::symlink_within_roots(&entry_path, &[library_root, path])
+        {
+            cover_files.push(entry_path);
+        }
+    }
+
+    let cover_path = cover_files
+        .into_iter()
+        .min_by_key(|p| cover_rank(p, cover_names));
+
+    Ok(TitleDirListing {
+        entry_paths,
+        nested_paths,
+        cover_path,
+    })
+}
+
+/// Directories whose info.json may hold an entry's data, given the entry's path
+/// relative to the library root: its title directory, or for an archive in the root
+/// the info directories of both `loose_archives` modes
+pub(super) fn entry_info_dirs(library_root: &Path, relative_path: &str) -> Vec<PathBuf> {
+    let relative_path = Path::new(relative_path);
+    match relative_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => vec![library_root.join(parent)],
+        _ => {
+            let loose = library_root.join(LOOSE_INFO_DIR);
+            let mut dirs = vec![loose.join(UNSORTED_INFO_NAME)];
+            if let Some(name) = relative_path.file_name() {
+                dirs.push(loose.join(name));
+            }
+            dirs
+        }
+    }
+}
+
+fn archive_name(entry: &Entry) -> Option<String> {
+    entry
+        .path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(String::from)
+}
+
 /// Check if a file is a supported archive format
 /// Only returns true for formats we can actually extract (currently ZIP/CBZ only)
 /// When adding new format support, update entry.rs extraction code first,
 /// then add extensions to util::EXTRACTABLE_ARCHIVE_EXTENSIONS
-fn is_archive(path: &Path) -> bool {
+pub(super) fn is_archive(path: &Path) -> bool {
     if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
         let ext_lower = ext.to_lowercase();
         crate::util::EXTRACTABLE_ARCHIVE_EXTENSIONS.contains(&ext_lower.as_str())
@@ -292,42 +607,161 @@ fn is_archive(path: &Path) -> bool {
     }
 }
 
+/// Sibling title directories whose names differ only by case or surrounding whitespace
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct NameCollision {
+    /// The name they share, trimmed
+    pub name: String,
+    /// Relative to the library root
+    pub paths: Vec<String>,
+}
+
+/// Tell apart directory titles among `titles` (siblings) and among each title's nested
+/// titles whose names collide, by adding their quoted relative path to their names:
+/// `Naruto ("naruto")`. Names are reset to the directory name first, so titles kept from
+/// an earlier scan lose the suffix once the other directory is gone
+pub(super) fn disambiguate_names<'a>(
+    titles: impl IntoIterator<Item = &'a mut Title>,
+    library_root: &Path,
+) -> Vec<NameCollision> {
+    let mut groups: std::collections::BTreeMap<String, Vec<&mut Title>> = Default::default();
+    let mut collisions = Vec::new();
+    for title in titles {
+        collisions.extend(disambiguate_names(
+            title.nested_titles.iter_mut(),
+            library_root,
+        ));
+        if title.source != TitleSource::Directory {
+            continue;
+        }
+        if let Some(name) = title.path.file_name() {
+            title.title = name.to_string_lossy().to_string();
+        }
+        groups
+            .entry(title.title.trim().to_lowercase())
+            .or_default()
+            .push(title);
+    }
+
+    for mut group in groups.into_values().filter(|g| g.len() > 1) {
+        group.sort_by(|a, b| a.path.cmp(&b.path));
+        let paths: Vec<String> = group
+            .iter()
+            .map(|t| t.relative_path(library_root))
+            .collect();
+        let name = group[0].title.trim().to_string();
+        for (title, path) in group.iter_mut().zip(&paths) {
+            title.title = format!("{} (\"{}\")", title.title.trim(), path);
+        }
+        collisions.push(NameCollision { name, paths });
+    }
+    collisions
+}
+
+/// Whether a file or directory is hidden from the scanner: its name starts with a
+/// dot or an underscore (config `scan_skip_hidden`)
+pub(super) fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy())
+        .is_some_and(|name| name.starts_with('.') || name.starts_with('_'))
+}
+
+/// Position of a file's name in the cover file name list (case-insensitive)
+fn cover_rank(path: &Path, cover_names: &[String]) -> Option<usize> {
+    let name = path.file_name()?.to_str()?;
+    cover_names
+        .iter()
+        .position(|cover| cover.eq_ignore_ascii_case(name))
+}
+
 /// Calculate directory signature (matches original Mango's Dir.signature behavior)
 /// This is now a simple wrapper around util::dir_signature for consistency
 fn calculate_dir_signature(path: &Path) -> Result<String> {
     crate::util::dir_signature(path)
 }
 
+/// Whether every entry of the title and its nested titles still has the modification
+/// time it was scanned with; archives rewritten in place keep their inode, so the
+/// signatures alone miss them
+pub(super) async fn entries_unmodified(title: &Title) -> bool {
+    let files: Vec<(PathBuf, i64)> = title
+        .deep_entries()
+        .into_iter()
+        .flat_map(|e| {
+            if e.parts.is_empty() {
+                std::slice::from_ref(e)
+            } else {
+                e.parts.as_slice()
+            }
+        })
+        .map(|e| (e.path.clone(), e.mtime))
+        .collect();
+
+    tokio::task::spawn_blocking(move || {
+        files.iter().all(|(path, mtime)| {
+            std::fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .is_some_and(|d| d.as_secs() as i64 == *mtime)
+        })
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Directory and contents signatures of a title directory
+/// Both walk the directory with std::fs, so they run on the blocking pool
+pub(super) async fn title_signatures(path: &Path) -> Result<(String, String)> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        Ok((
+            calculate_dir_signature(&path)?,
+            calculate_contents_signature(&path)?,
+        ))
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Task join error: {}", e)))?
+}
+
 /// Calculate contents signature (SHA1 of all filenames, sorted)
-/// Used for detecting when directory contents changed
+/// Used for detecting when directory contents changed; only archives and image
+/// directories count, so adding a cover image is not a content change
 fn calculate_contents_signature(path: &Path) -> Result<String> {
-    use sha1::{Digest, Sha1};
     use std::fs;
 
     let mut filenames = Vec::new();
 
-    // Collect all archive filenames
+    // Collect all archive and image directory names
     for entry in fs::read_dir(path)? {
         let entry = entry?;
         let entry_path = entry.path();
 
-        if entry_path.is_file() && is_archive(&entry_path) {
+        let is_entry = if entry_path.is_dir() {
+            is_image_directory(&entry_path)
+        } else {
+            entry_path.is_file() && is_archive(&entry_path)
+        };
+        if is_entry {
             if let Some(name) = entry_path.file_name().and_then(|n| n.to_str()) {
                 filenames.push(name.to_string());
             }
         }
     }
 
-    // Sort filenames
-    filenames.sort();
+    Ok(contents_hash(filenames))
+}
+
+/// SHA1 of entry file names, sorted and concatenated
+fn contents_hash(mut filenames: Vec<String>) -> String {
+    use sha1::{Digest, Sha1};
 
-    // SHA1 of concatenated names
+    filenames.sort();
     let mut hasher = Sha1::new();
     for name in filenames {
         hasher.update(name.as_bytes());
     }
-
-    Ok(format!("{:x}", hasher.finalize()))
+    format!("{:x}", hasher.finalize())
 }
 
 impl super::Sortable for Title {
@@ -0,0 +1,200 @@
+// Display names for entries
+//
+// Entry titles are archive file names, which release groups fill with tags:
+// "[ABC-Scans] Some Manga - c045 (v05) [x1200] [Digital]". The cleanup strips the parts
+// matching the configured patterns, turns underscores into spaces and drops the title's own
+// name from the front, giving "c045 (v05)". The result is only for display: sorting, search
+// and chapter matching keep using the raw title.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::error::{Error, Result};
+
+/// Strip patterns used unless `entry_title_strip_patterns` is configured
+pub const DEFAULT_STRIP_PATTERNS: &[&str] = &[
+    // Bracketed groups: [ABC-Scans], [x1200], [Digital]
+    r"\[[^\]]*\]",
+    r"\{[^}]*\}",
+    // Release tags, with the group names that conventionally follow them: (Digital) (1r0n)
+    r"(?i)\((?:digital|webrip|web|c2c|hq|lq|f)\b[^)]*\)(?:\s*\([^)]*\))*",
+    // Resolutions: x1200, 1920x1080, 1080p
+    r"(?i)\(?\b(?:\d{3,4}x\d{3,4}|x\d{3,4}|\d{3,4}p)\b\)?",
+];
+
+/// Separators left dangling at either end once tags and the series name are removed
+const SEPARATORS: &[char] = &['-', '.', ',', ':', '~', '|', ' '];
+
+/// Cleanup rules from config (set once at startup, see `set_title_cleanup`)
+static TITLE_CLEANUP: OnceLock<TitleCleanup> = OnceLock::new();
+
+/// Rules turning raw entry titles into display names
+#[derive(Debug, Clone)]
+pub struct TitleCleanup {
+    enabled: bool,
+    rules: Vec<Regex>,
+}
+
+impl Default for TitleCleanup {
+    fn default() -> Self {
+        let patterns: Vec<String> = DEFAULT_STRIP_PATTERNS
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        Self::new(true, &patterns).expect("default strip patterns are valid")
+    }
+}
+
+impl TitleCleanup {
+    /// Compile the strip patterns, failing on the first invalid one
+    pub fn new(enabled: bool, patterns: &[String]) -> Result<Self> {
+        let rules = patterns
+            .iter()
+            .map(|p| {
+                Regex::new(p).map_err(|e| {
+                    Error::Config(format!("Invalid entry title strip pattern {:?}: {}", p, e))
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { enabled, rules })
+    }
+
+    /// Rules configured by `entry_title_cleanup` and `entry_title_strip_patterns`
+    /// The patterns are checked when the config is loaded
+    pub fn from_config(config: &crate::Config) -> Self {
+        Self::new(
+            config.entry_title_cleanup,
+            &config.entry_title_strip_patterns,
+        )
+        .unwrap_or_else(|e| {
+            tracing::warn!("{}, using the default rules", e);
+            Self::default()
+        })
+    }
+
+    /// The rules in use (the defaults if never configured)
+    pub fn current() -> &'static Self {
+        TITLE_CLEANUP.get_or_init(Self::default)
+    }
+
+    /// Display name of an entry of the title named `title_name`
+    /// Falls back to the raw title when cleanup is disabled or would leave nothing
+    pub fn display_title(&self, raw: &str, title_name: &str) -> String {
+        if !self.enabled {
+            return raw.to_string();
+        }
+
+        let name = self.strip(raw);
+        let name = name.as_str();
+
+        // "Some Manga - c045" in "Some Manga" says the series twice
+        let title_name = self.strip(title_name);
+        let name = name
+            .get(..title_name.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(&title_name))
+            .map(|_| &name[title_name.len()..])
+            .filter(|rest| !rest.starts_with(char::is_alphanumeric))
+            .map(|rest| rest.trim_matches(SEPARATORS))
+            .filter(|rest| !rest.is_empty())
+            .unwrap_or(name);
+
+        if name.is_empty() {
+            raw.to_string()
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Remove everything matching the rules, and the separators left at either end
+    fn strip(&self, name: &str) -> String {
+        let mut name = name.replace('_', " ");
+        for rule in &self.rules {
+            name = rule.replace_all(&name, " ").into_owned();
+        }
+        let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        name.trim_matches(SEPARATORS).to_string()
+    }
+}
+
+/// Set the cleanup rules used when titles are scanned (called once at startup)
+pub fn set_title_cleanup(config: &crate::Config) {
+    if TITLE_CLEANUP
+        .set(TitleCleanup::from_config(config))
+        .is_err()
+    {
+        tracing::warn!("Entry title cleanup already set, ignoring config");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_rules() {
+        let cleanup = TitleCleanup::default();
+        let cases = [
+            (
+                "[ABC-Scans] Some Manga - c045 (v05) [x1200] [Digital] [group]",
+                "Some Manga",
+                "c045 (v05)",
+            ),
+            (
+                "Some Manga v01 (2019) (Digital) (1r0n)",
+                "Some Manga",
+                "v01 (2019)",
+            ),
+            (
+                "Some Manga v12 (2021) (Digital-Compilation) (danke-Empire)",
+                "Some Manga",
+                "v12 (2021)",
+            ),
+            ("Some_Manga_c001_[x1200]", "Some Manga", "c001"),
+            ("some manga - 012.5 {HQ}", "Some Manga", "012.5"),
+            (
+                "[Group] Another Series 012 [1920x1080]",
+                "Some Manga",
+                "Another Series 012",
+            ),
+            (
+                "Another Series - Ch.003 (1080p)",
+                "Some Manga",
+                "Another Series - Ch.003",
+            ),
+            ("Chapter 1", "Alpha", "Chapter 1"),
+            // Tags in the title's own name don't stop the series name from matching
+            (
+                "[Group] Some Manga 004",
+                "[Group] Some Manga [Digital]",
+                "004",
+            ),
+            ("Vol. 3 Extra (F)", "Alpha", "Vol. 3 Extra"),
+            // The series name alone, or as the start of a longer word, stays
+            ("Some Manga", "Some Manga", "Some Manga"),
+            ("Somewhat Unrelated 003", "Some", "Somewhat Unrelated 003"),
+            // Nothing but tags left: keep the raw name
+            ("[Digital]", "Some Manga", "[Digital]"),
+        ];
+
+        for (raw, title, expected) in cases {
+            assert_eq!(
+                cleanup.display_title(raw, title),
+                expected,
+                "cleaning up {:?}",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn test_disabled_and_custom_rules() {
+        let raw = "[Group] Some Manga - c001";
+        let disabled = TitleCleanup::new(false, &[r"\[[^\]]*\]".to_string()]).unwrap();
+        assert_eq!(disabled.display_title(raw, "Some Manga"), raw);
+
+        let custom = TitleCleanup::new(true, &[r" - c".to_string()]).unwrap();
+        assert_eq!(custom.display_title(raw, "Other"), "[Group] Some Manga 001");
+
+        assert!(TitleCleanup::new(true, &["[unclosed".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,263 @@
+// Library directory watcher (config `watch_library`)
+//
+// Files added, renamed or deleted under the library root are collected per title directory
+// and, once the title has been quiet for a moment, only that directory is rescanned. A
+// change to an archive lying in the library root rescans the whole library instead, reusing
+// every unchanged title, as loose archives are not grouped by directory.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use notify::event::{AccessKind, AccessMode, ModifyKind};
+use notify::{EventKind, RecursiveMode, Watcher};
+use tokio::time::Instant;
+
+use super::{Library, LibraryEvents, SharedLibrary};
+use crate::Storage;
+
+/// Time a title must go without changes before it is rescanned, so a series being copied
+/// in is scanned once it is complete rather than after every file
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Suffixes of files still being written by downloaders and copy tools
+const TEMP_SUFFIXES: &[&str] = &[".part", ".tmp"];
+
+/// What a change in the library needs rescanned
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Target {
+    /// A title directory directly under the library root
+    Title(PathBuf),
+    /// The archives in the library root
+    Root,
+}
+
+/// Whether a file is still being written and will be renamed once complete
+fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .is_some_and(|name| TEMP_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)))
+}
+
+/// Whether an event adds, renames or deletes files
+/// Closing a file after writing counts too, for copies that overwrite an archive in place
+fn is_relevant(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_)
+            | EventKind::Remove(_)
+            | EventKind::Modify(ModifyKind::Name(_))
+            | EventKind::Access(AccessKind::Close(AccessMode::Write))
+    )
+}
+
+/// What a change to `path` needs rescanned, None if it does not change the library
+fn target(root: &Path, path: &Path) -> Option<Target> {
+    // info.json is rewritten whenever progress is saved
+    if is_temp_file(path) || path.file_name().is_some_and(|name| name == "info.json") {
+        return None;
+    }
+
+    let mut components = path.strip_prefix(root).ok()?.components();
+    let name = components.next()?.as_os_str();
+    if name == super::title::LOOSE_INFO_DIR {
+        return None;
+    }
+
+    let title_path = root.join(name);
+    let in_root = components.next().is_none();
+    // A deleted title directory cannot be told apart from a deleted file by its type
+    if in_root && !title_path.is_dir() && super::title::is_archive(&title_path) {
+        return Some(Target::Root);
+    }
+    Some(Target::Title(title_path))
+}
+
+/// Start watching the library directory, rescanning the titles changes were made in
+/// Fails when the directory cannot be watched (e.g. the inotify watch limit is reached)
+pub fn spawn_library_watcher(
+    library: SharedLibrary,
+    storage: Storage,
+    config: Arc<crate::Config>,
+    events: LibraryEvents,
+) -> notify::Result<tokio::task::JoinHandle<()>> {
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        // Only fails once the task below has stopped
+        let _ = sender.send(event);
+    })?;
+    watcher.watch(&config.library_path, RecursiveMode::Recursive)?;
+
+    Ok(tokio::spawn(async move {
+        // Dropping the watcher stops the events
+        let _watcher = watcher;
+        let root = config.library_path.clone();
+        let mut pending: HashMap<Target, Instant> = HashMap::new();
+
+        loop {
+            let received = match pending.values().min() {
+                Some(&deadline) => tokio::time::timeout_at(deadline, receiver.recv())
+                    .await
+                    .ok(),
+                None => Some(receiver.recv().await),
+            };
+
+            match received {
+                Some(Some(Ok(event))) => {
+                    if !is_relevant(&event.kind) {
+                        continue;
+                    }
+                    for path in &event.paths {
+                        if let Some(target) = target(&root, path) {
+                            pending.insert(target, Instant::now() + DEBOUNCE);
+                        }
+                    }
+                }
+                Some(Some(Err(e))) => tracing::warn!("Library watcher error: {}", e),
+                Some(None) => break,
+                // The earliest title went quiet
+                None => {
+                    let now = Instant::now();
+                    let mut due: Vec<Target> = pending
+                        .iter()
+                        .filter(|(_, deadline)| **deadline <= now)
+                        .map(|(target, _)| target.clone())
+                        .collect();
+                    pending.retain(|_, deadline| *deadline > now);
+
+                    // Rescanning the root rescans every changed title too
+                    if due.contains(&Target::Root) {
+                        due = vec![Target::Root];
+                    }
+                    for target in due {
+                        rescan(&library, &storage, &config, &events, target).await;
+                    }
+                }
+            }
+        }
+    }))
+}
+
+/// Rescan what changed and swap the result in, keeping the current library on failure
+async fn rescan(
+    library: &SharedLibrary,
+    storage: &Storage,
+    config: &crate::Config,
+    events: &LibraryEvents,
+    target: Target,
+) {
+    let current = library.load_full();
+    let rescanned = match &target {
+        Target::Title(path) => current.rescan_title(config, path).await,
+        Target::Root => {
+            let mut new_lib = Library::new(config.library_path.clone(), storage.clone(), config);
+            new_lib.share_scan_lock(&current);
+            new_lib.scan_incremental(&current).await.map(|_| new_lib)
+        }
+    };
+
+    match rescanned {
+        Ok(new_lib) => events.store_scanned(library, new_lib),
+        Err(e) => tracing::error!("Rescan after library change failed ({:?}): {}", target, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("Series")).unwrap();
+        let series = Target::Title(root.join("Series"));
+
+        let chapter = root.join("Series").join("Vol 1").join("001.png");
+        assert_eq!(target(root, &chapter), Some(series.clone()));
+        assert_eq!(target(root, &root.join("Series")), Some(series));
+        // Deleted title directory
+        assert_eq!(
+            target(root, &root.join("Gone")),
+            Some(Target::Title(root.join("Gone")))
+        );
+        assert_eq!(target(root, &root.join("Loose.cbz")), Some(Target::Root));
+
+        // Files still being written, progress saves and paths outside the library
+        assert_eq!(
+            target(root, &root.join("Series").join("Ch 2.cbz.part")),
+            None
+        );
+        assert_eq!(
+            target(root, &root.join("Series").join("Ch 2.CBZ.TMP")),
+            None
+        );
+        assert_eq!(target(root, &root.join("Loose.zip.part")), None);
+        assert_eq!(target(root, &root.join("Series").join("info.json")), None);
+        let loose_info = root.join(super::super::title::LOOSE_INFO_DIR).join("x");
+        assert_eq!(target(root, &loose_info), None);
+        assert_eq!(target(root, Path::new("/elsewhere/Series/1.png")), None);
+    }
+
+    #[tokio::test]
+    async fn test_rescan_title() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().join("library");
+        for series in ["Alpha", "Beta"] {
+            let chapter = root.join(series).join("Chapter 1");
+            std::fs::create_dir_all(&chapter).unwrap();
+            std::fs::write(chapter.join("1.png"), "one").unwrap();
+        }
+        let database_url = format!(
+            "sqlite://{}?mode=rwc",
+            temp_dir.path().join("test.db").display()
+        );
+        let storage = Storage::new(&database_url).await.unwrap();
+        let config = crate::Config::default_config();
+        let mut library = Library::new(root.clone(), storage.clone(), &config);
+        library.scan().await.unwrap();
+
+        let find = |library: &Library, name: &str| {
+            library
+                .get_titles()
+                .into_iter()
+                .find(|t| t.title == name)
+                .cloned()
+        };
+        let alpha = find(&library, "Alpha").unwrap();
+        let beta = find(&library, "Beta").unwrap();
+
+        // A new chapter shows up and the title keeps its ID
+        let chapter = root.join("Alpha").join("Chapter 2");
+        std::fs::create_dir(&chapter).unwrap();
+        std::fs::write(chapter.join("1.png"), "two").unwrap();
+        let library = library
+            .rescan_title(&config, &root.join("Alpha"))
+            .await
+            .unwrap();
+        let rescanned = find(&library, "Alpha").unwrap();
+        assert_eq!(rescanned.id, alpha.id);
+        assert_eq!(rescanned.entries.len(), 2);
+        assert_eq!(rescanned.entries[0].id, alpha.entries[0].id);
+        assert_eq!(
+            find(&library, "Beta").unwrap().entries[0].id,
+            beta.entries[0].id
+        );
+
+        // A deleted title is dropped and marked unavailable
+        std::fs::remove_dir_all(root.join("Beta")).unwrap();
+        let library = library
+            .rescan_title(&config, &root.join("Beta"))
+            .await
+            .unwrap();
+        assert!(find(&library, "Beta").is_none());
+        assert_eq!(library.get_titles().len(), 1);
+        let unavailable: bool = sqlx::query_scalar("SELECT unavailable FROM titles WHERE id = ?")
+            .bind(&beta.id)
+            .fetch_one(storage.pool())
+            .await
+            .unwrap();
+        assert!(unavailable);
+    }
+}
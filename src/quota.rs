@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::error::{Error, Result};
+use crate::Storage;
+
+/// How often buffered download counters are flushed to the database
+const FLUSH_INTERVAL_SECS: u64 = 10;
+
+/// Longest wait between flushes while they keep failing
+const MAX_FLUSH_BACKOFF_SECS: u64 = 10 * 60;
+
+/// Per-user download accounting with an optional daily byte quota
+/// Byte counts are buffered in memory and flushed to the download_usage table
+/// periodically, so the download path never waits on SQLite
+#[derive(Clone)]
+pub struct DownloadQuota {
+    storage: Storage,
+    /// Daily quota in bytes (0 = unlimited)
+    limit_bytes: u64,
+    /// (username, day) -> bytes served but not yet flushed
+    pending: Arc<Mutex<HashMap<(String, String), u64>>>,
+}
+
+impl DownloadQuota {
+    /// Create a new quota tracker (limit_bytes = 0 disables enforcement)
+    pub fn new(storage: Storage, limit_bytes: u64) -> Self {
+        Self {
+            storage,
+            limit_bytes,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Daily quota in bytes, None if unlimited
+    pub fn limit_bytes(&self) -> Option<u64> {
+        if self.limit_bytes == 0 {
+            None
+        } else {
+            Some(self.limit_bytes)
+        }
+    }
+
+    /// Record bytes served to a user (buffered until the next flush)
+    pub fn record(&self, username: &str, bytes: u64) {
+        if bytes == 0 {
+            return;
+        }
+
+        match self.pending.lock() {
+            Ok(mut pending) => {
+                *pending.entry((username.to_string(), today())).or_insert(0) += bytes;
+            }
+            Err(e) => {
+                tracing::error!("Download quota lock poisoned during record: {}", e);
+            }
+        }
+    }
+
+    /// Bytes buffered for a user on a given day
+    fn pending_for(&self, username: &str, day: &str) -> u64 {
+        match self.pending.lock() {
+            Ok(pending) => pending
+                .get(&(username.to_string(), day.to_string()))
+                .copied()
+                .unwrap_or(0),
+            Err(e) => {
+                tracing::error!("Download quota lock poisoned during lookup: {}", e);
+                0
+            }
+        }
+    }
+
+    /// Bytes a user has downloaded today (persisted + buffered)
+    pub async fn usage_today(&self, username: &str) -> Result<u64> {
+        let day = today();
+        let stored = self.storage.get_download_usage(username, &day).await?;
+        Ok(stored + self.pending_for(username, &day))
+    }
+
+    /// Bytes every user has downloaded today (persisted + buffered)
+    pub async fn usage_today_all(&self) -> Result<HashMap<String, u64>> {
+        let day = today();
+        let mut usage: HashMap<String, u64> = self
+            .storage
+            .list_download_usage(&day)
+            .await?
+            .into_iter()
+            .collect();
+
+        if let Ok(pending) = self.pending.lock() {
+            for ((username, pending_day), bytes) in pending.iter() {
+                if *pending_day == day {
+                    *usage.entry(username.clone()).or_insert(0) += bytes;
+                }
+            }
+        }
+
+        Ok(usage)
+    }
+
+    /// Fail with TooManyRequests if the user has used up today's quota
+    pub async fn check(&self, username: &str) -> Result<()> {
+        let Some(limit) = self.limit_bytes() else {
+            return Ok(());
+        };
+
+        let used = self.usage_today(username).await?;
+        if used >= limit {
+            return Err(Error::TooManyRequests(format!(
+                "Daily download quota exceeded: {} of {} bytes used today (resets at 00:00 UTC)",
+                used, limit
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Flush buffered counters to the database
+    /// On failure the counters are put back so nothing is lost
+    pub async fn flush(&self) -> Result<()> {
+        let drained: Vec<((String, String), u64)> = match self.pending.lock() {
+            Ok(mut pending) => pending.drain().collect(),
+            Err(e) => {
+                return Err(Error::Internal(format!(
+                    "Download quota lock poisoned during flush: {}",
+                    e
+                )))
+            }
+        };
+
+        if drained.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(e) = self.storage.add_download_usage(&drained).await {
+            if let Ok(mut pending) = self.pending.lock() {
+                for (key, bytes) in drained {
+                    *pending.entry(key).or_insert(0) += bytes;
+                }
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Wrap a reader so every byte read from it is counted against the user
+    /// Bytes are counted as the response body pulls them, so an aborted
+    /// download only counts what was actually sent
+    pub fn counting_reader<R>(&self, username: String, inner: R) -> CountingReader<R> {
+        CountingReader {
+            inner,
+            quota: self.clone(),
+            username,
+        }
+    }
+}
+
+/// AsyncRead adapter that records bytes read against a user's download quota
+pub struct CountingReader<R> {
+    inner: R,
+    quota: DownloadQuota,
+    username: String,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CountingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        let result = Pin::new(&mut this.inner).poll_read(cx, buf);
+
+        if let Poll::Ready(Ok(())) = result {
+            let read = buf.filled().len() - before;
+            this.quota.record(&this.username, read as u64);
+        }
+
+        result
+    }
+}
+
+/// Spawn a background task that periodically flushes buffered download counters
+pub fn spawn_quota_flusher(quota: DownloadQuota) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(FLUSH_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut backoff = crate::util::Backoff::new(
+            Duration::from_secs(FLUSH_INTERVAL_SECS),
+            Duration::from_secs(MAX_FLUSH_BACKOFF_SECS),
+        );
+
+        loop {
+            interval.tick().await;
+
+            match quota.flush().await {
+                Ok(()) => backoff.reset(),
+                Err(e) => {
+                    let delay = backoff.next_delay();
+                    tracing::warn!(
+                        "Failed to flush download usage, retrying in {}s: {}",
+                        delay.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    })
+}
+
+/// Current UTC day as YYYY-MM-DD (the quota accounting period)
+pub fn today() -> String {
+    crate::util::time::format_date(crate::util::time::now_ts())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    async fn test_quota(limit_bytes: u64) -> (DownloadQuota, tempfile::TempDir) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        (DownloadQuota::new(storage, limit_bytes), temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_counting_reader_counts_bytes_read() {
+        let (quota, _temp_dir) = test_quota(0).await;
+        let data = vec![7u8; 10_000];
+
+        let mut reader = quota.counting_reader("alice".to_string(), &data[..]);
+        let mut first = [0u8; 1000];
+        reader.read_exact(&mut first).await.unwrap();
+        assert_eq!(quota.usage_today("alice").await.unwrap(), 1000);
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(quota.usage_today("alice").await.unwrap(), 10_000);
+        assert_eq!(quota.usage_today("bob").await.unwrap(), 0);
+
+        // Flushed bytes still count, and are not counted twice
+        quota.flush().await.unwrap();
+        assert_eq!(quota.usage_today("alice").await.unwrap(), 10_000);
+        let usage = quota.usage_today_all().await.unwrap();
+        assert_eq!(usage.get("alice"), Some(&10_000));
+    }
+
+    #[tokio::test]
+    async fn test_check_enforces_limit() {
+        let (unlimited, _temp_dir) = test_quota(0).await;
+        assert_eq!(unlimited.limit_bytes(), None);
+        unlimited.record("alice", u64::MAX / 2);
+        unlimited.check("alice").await.unwrap();
+
+        let (quota, _temp_dir) = test_quota(100).await;
+        assert_eq!(quota.limit_bytes(), Some(100));
+        quota.record("alice", 60);
+        quota.check("alice").await.unwrap();
+        quota.flush().await.unwrap();
+        quota.record("alice", 40);
+        assert!(matches!(
+            quota.check("alice").await,
+            Err(Error::TooManyRequests(_))
+        ));
+        // Other users have a quota of their own
+        quota.check("bob").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_usage_resets_each_day() {
+        let (quota, _temp_dir) = test_quota(100).await;
+        let yesterday = crate::util::time::format_date(crate::util::time::now_ts() - 24 * 3600);
+
+        // Yesterday's usage, stored and still buffered, does not count today
+        quota
+            .storage
+            .add_download_usage(&[(("alice".to_string(), yesterday.clone()), 100)])
+            .await
+            .unwrap();
+        quota
+            .pending
+            .lock()
+            .unwrap()
+            .insert(("alice".to_string(), yesterday.clone()), 100);
+        assert_eq!(quota.usage_today("alice").await.unwrap(), 0);
+        assert!(quota.usage_today_all().await.unwrap().is_empty());
+        quota.check("alice").await.unwrap();
+
+        quota.flush().await.unwrap();
+        assert_eq!(
+            quota
+                .storage
+                .get_download_usage("alice", &yesterday)
+                .await
+                .unwrap(),
+            200
+        );
+        assert_eq!(quota.usage_today("alice").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_flusher_persists_counters() {
+        let (quota, _temp_dir) = test_quota(0).await;
+        quota.record("alice", 500);
+
+        // The first flush runs as soon as the flusher starts
+        let flusher = spawn_quota_flusher(quota.clone());
+        let mut stored = 0;
+        for _ in 0..100 {
+            stored = quota
+                .storage
+                .get_download_usage("alice", &today())
+                .await
+                .unwrap();
+            if stored == 500 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        flusher.abort();
+        assert_eq!(stored, 500);
+        assert_eq!(quota.pending_for("alice", &today()), 0);
+    }
+}
@@ -6,32 +6,43 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Instant;
-
-use crate::{auth::AdminOnly, error::Result, util::render_error, AppState};
-
-/// Application version from Cargo.toml
-const VERSION: &str = env!("CARGO_PKG_VERSION");
+use tower_sessions::Session;
+
+use super::paths;
+use crate::{
+    auth::AdminOnly,
+    error::Result,
+    library::scan_status::{ScanReport, UnreadableFileInfo, UnreadableTitle},
+    util::{render_error, BaseContext, NavPage},
+    AppState,
+};
 
 /// Admin dashboard template
 #[derive(Template)]
 #[template(path = "admin.html")]
 struct AdminTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
     missing_count: usize,
-    version: &'static str,
+    report_count: usize,
+    version: crate::version::VersionInfo,
 }
 
 /// Cache debug template
 #[derive(Template)]
 #[template(path = "cache_debug.html")]
 struct CacheDebugTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
     stats: crate::library::cache::CacheStats,
     entries: Vec<crate::library::cache::CacheEntryInfo>,
+    memory_cache_enabled: bool,
+    cache_file_enabled: bool,
     cache_file_path: String,
     cache_file_exists: bool,
     cache_file_size: u64,
+    /// Title shards in the cache store
+    cache_file_shards: usize,
     cache_file_modified: String,
 }
 
@@ -43,15 +54,17 @@ struct CacheDebugTemplate {
 /// - Generate Thumbnails
 pub async fn admin_dashboard(
     State(state): State<AppState>,
-    AdminOnly(_username): AdminOnly,
+    admin: AdminOnly,
 ) -> Result<Html<String>> {
     // Get actual missing count from database
     let missing_count = state.storage.get_missing_count().await?;
+    let report_count = state.storage.get_open_report_count().await?;
 
     let template = AdminTemplate {
-        nav: crate::util::NavigationState::admin().with_admin(true), // Admin pages are always accessed by admins
+        base: BaseContext::new(&admin.into(), NavPage::Admin),
         missing_count,
-        version: VERSION,
+        report_count,
+        version: crate::version::VersionInfo::collect(&state.storage).await?,
     };
 
     Ok(Html(template.render().map_err(render_error)?))
@@ -61,13 +74,16 @@ pub async fn admin_dashboard(
 /// Shows cache statistics, entries, and control buttons
 pub async fn cache_debug_page(
     State(state): State<AppState>,
-    AdminOnly(_username): AdminOnly,
+    admin: AdminOnly,
 ) -> Result<Html<String>> {
     let lib = state.library.load();
 
     // Get cache statistics
     let cache = lib.cache().lock().await;
     let stats = cache.stats();
+    let memory_cache_enabled = cache.memory_enabled();
+    let cache_file_enabled = cache.file_enabled();
+    let store_manager = cache.store_manager();
 
     // Get top 20 cache entries sorted by access count
     let mut entries = cache.entries();
@@ -76,61 +92,138 @@ pub async fn cache_debug_page(
 
     drop(cache);
 
-    // Get cache file metadata
-    let cache_file_path = state
-        .config
-        .library_cache_path
-        .to_string_lossy()
-        .to_string();
-    let cache_file_metadata = if let Ok(metadata) =
-        tokio::fs::metadata(&state.config.library_cache_path).await
-    {
-        (
-            true,
-            metadata.len(),
-            metadata
-                .modified()
-                .ok()
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|d| {
-                    let datetime = chrono::DateTime::<chrono::Utc>::from(std::time::UNIX_EPOCH + d);
-                    datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string()
-                })
-                .unwrap_or_else(|| "Unknown".to_string()),
-        )
+    // Get cache store metadata
+    let store = store_manager.metadata().await?;
+    let cache_file_modified = if store.valid {
+        store
+            .modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| crate::util::time::format_ts(d.as_secs() as i64))
+            .unwrap_or_else(|_| "Unknown".to_string())
     } else {
-        (false, 0, "N/A".to_string())
+        "N/A".to_string()
     };
 
     drop(lib);
 
     let template = CacheDebugTemplate {
-        nav: crate::util::NavigationState::admin().with_admin(true),
+        base: BaseContext::new(&admin.into(), NavPage::Admin),
         stats,
         entries,
-        cache_file_path,
-        cache_file_exists: cache_file_metadata.0,
-        cache_file_size: cache_file_metadata.1,
-        cache_file_modified: cache_file_metadata.2,
+        memory_cache_enabled,
+        cache_file_enabled,
+        cache_file_path: store.path.to_string_lossy().to_string(),
+        cache_file_exists: store.valid,
+        cache_file_size: store.size_bytes,
+        cache_file_shards: store.shards,
+        cache_file_modified,
     };
 
     Ok(Html(template.render().map_err(render_error)?))
 }
 
-/// Response for library scan endpoint
+/// Response for the endpoints starting a library scan
 #[derive(Serialize)]
-pub struct ScanResponse {
-    pub titles: usize,
-    pub milliseconds: u128,
+pub struct ScanStartedResponse {
+    pub job_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct ScanQuery {
+    /// Read every archive, not only those in new or changed title directories
+    #[serde(default)]
+    pub full: bool,
 }
 
-/// POST /api/admin/scan - Trigger library rescan
-/// Returns number of titles found and time taken in milliseconds
+/// POST /api/admin/scan?full=true - Start a library rescan in the background
+/// Returns 202 with the job ID at once; GET /api/admin/scan/status reports its progress
+/// and, once done, the number of titles found and time taken in milliseconds
 /// Uses double-buffer approach: builds new library in background, then atomically swaps
+/// Titles whose directories are unchanged are reused unless `full` is set
+/// An interrupted scan is resumed rather than started over
+/// Returns: 409 Conflict while another scan started here is running
 pub async fn scan_library(
     State(state): State<AppState>,
     AdminOnly(_username): AdminOnly,
-) -> Result<Json<ScanResponse>> {
+    axum::extract::Query(query): axum::extract::Query<ScanQuery>,
+) -> Result<(StatusCode, Json<ScanStartedResponse>)> {
+    start_scan_job(&state, query.full)
+}
+
+/// POST /api/admin/scan/resume - Resume a scan interrupted by a restart
+/// Only titles the interrupted scan had not finished (or that changed since) are scanned
+pub async fn resume_scan(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+) -> Result<(StatusCode, Json<ScanStartedResponse>)> {
+    if state.library.load().scan_lock().scan_running() {
+        return Err(crate::error::Error::Conflict(
+            "A library scan is already running".to_string(),
+        ));
+    }
+    if crate::library::ScanJournal::progress(&state.storage)
+        .await?
+        .is_none()
+    {
+        return Err(crate::error::Error::NotFound(
+            "No interrupted scan to resume".to_string(),
+        ));
+    }
+
+    start_scan_job(&state, false)
+}
+
+#[derive(Serialize)]
+pub struct ScanStatusResponse {
+    pub running: bool,
+    /// Journal of the running or interrupted scan (scan_id, done, total, percent)
+    #[serde(flatten)]
+    pub progress: Option<crate::library::ScanProgress>,
+    /// The scan started by an admin that is running, or the last one that finished
+    /// (titles discovered, directories remaining, started_at, and its report when done)
+    pub job: Option<crate::library::ScanJob>,
+    /// How the library cache compared with the disk at startup, if it was loaded
+    pub cache_verification: Option<crate::library::CacheVerification>,
+}
+
+/// GET /api/admin/scan/status - Whether a scan is running and how far it got
+pub async fn scan_status(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+) -> Result<Json<ScanStatusResponse>> {
+    Ok(Json(ScanStatusResponse {
+        running: state.library.load().scan_lock().scan_running() || state.scan_status.is_running(),
+        progress: crate::library::ScanJournal::progress(&state.storage).await?,
+        job: state.scan_status.get(),
+        cache_verification: state.scan_status.cache_verification(),
+    }))
+}
+
+/// Run `run_scan` in the background as the admin scan job, reporting its progress
+fn start_scan_job(state: &AppState, full: bool) -> Result<(StatusCode, Json<ScanStartedResponse>)> {
+    let job_id = state.scan_status.start()?;
+
+    let task_state = state.clone();
+    tokio::spawn(async move {
+        let progress = task_state.scan_status.clone();
+        let result = run_scan(&task_state, full, Some(progress)).await;
+        if let Err(e) = &result {
+            tracing::error!("Library scan failed: {}", e);
+        }
+        task_state.scan_status.finish(result);
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(ScanStartedResponse { job_id })))
+}
+
+/// Scan the library into a new instance and swap it in
+/// Unless `full`, titles of the current library whose directories are unchanged are reused
+/// The scan reports its progress to `progress` if given
+async fn run_scan(
+    state: &AppState,
+    full: bool,
+    progress: Option<crate::library::ScanStatus>,
+) -> Result<ScanReport> {
     let start = Instant::now();
 
     // Build new library instance and scan (double-buffer approach)
@@ -139,54 +232,354 @@ pub async fn scan_library(
         state.storage.clone(),
         &state.config,
     );
-    new_lib.scan().await?;
+    new_lib.share_scan_lock(&state.library.load());
+    if let Some(progress) = progress {
+        new_lib.report_progress_to(progress);
+    }
+    let summary = if full {
+        new_lib.scan().await?
+    } else {
+        new_lib.scan_incremental(&state.library.load_full()).await?
+    };
     let stats = new_lib.stats();
 
-    // Atomically swap the new library in
-    state.library.store(std::sync::Arc::new(new_lib));
+    let mut unreadable: Vec<UnreadableTitle> = new_lib
+        .all_titles()
+        .filter(|t| !t.unreadable.is_empty())
+        .map(|t| UnreadableTitle {
+            title_id: t.id.clone(),
+            title: t.title.clone(),
+            files: t
+                .unreadable
+                .iter()
+                .map(|f| UnreadableFileInfo {
+                    path: crate::util::relative_to_root(&f.path, &state.config.library_path),
+                    error: f.error.clone(),
+                })
+                .collect(),
+        })
+        .collect();
+    unreadable.sort_by(|a, b| natord::compare(&a.title, &b.title));
+    let name_collisions = new_lib.name_collisions().to_vec();
+
+    // Atomically swap the new library in, telling open pages what changed
+    state.events.store_scanned(&state.library, new_lib);
 
     let elapsed = start.elapsed().as_millis();
 
     tracing::info!(
-        "Library scan completed: {} titles in {}ms",
+        "Library scan completed: {} titles in {}ms ({} reused, {} rescanned)",
         stats.titles,
-        elapsed
+        elapsed,
+        summary.reused,
+        summary.rescanned
     );
+    if !unreadable.is_empty() {
+        let files: usize = unreadable.iter().map(|t| t.files.len()).sum();
+        tracing::warn!(
+            "{} files in {} titles could not be read",
+            files,
+            unreadable.len()
+        );
+    }
 
-    Ok(Json(ScanResponse {
+    Ok(ScanReport {
         titles: stats.titles,
         milliseconds: elapsed,
+        reused: summary.reused,
+        rescanned: summary.rescanned,
+        unreadable,
+        name_collisions,
+    })
+}
+
+/// Response for rescanning one title
+#[derive(Serialize)]
+pub struct TitleScanResponse {
+    pub title_id: String,
+    pub title: String,
+    /// Entries of the title and its nested titles after the rescan
+    pub entries: usize,
+    pub milliseconds: u128,
+    /// Files of the title that could not be read
+    pub unreadable: Vec<UnreadableFileInfo>,
+}
+
+/// POST /api/admin/scan/:tid - Rescan the directory of one title
+/// A nested title rescans the top-level title it is in; the rest of the library is kept
+/// Returns: 400 for titles of archives in the library root (rescanned with the library),
+/// 404 if the title's directory is gone or cannot be scanned (the title is removed from
+/// the library)
+pub async fn scan_title(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    Path(title_id): Path<String>,
+) -> Result<Json<TitleScanResponse>> {
+    let start = Instant::now();
+    let lib = state.library.load_full();
+
+    let mut title = lib
+        .get_title(&title_id)
+        .ok_or_else(|| crate::error::Error::NotFound(format!("Title not found: {}", title_id)))?;
+    while let Some(parent) = title.parent_id.as_deref().and_then(|id| lib.get_title(id)) {
+        title = parent;
+    }
+    if title.source != crate::library::TitleSource::Directory {
+        return Err(crate::error::Error::BadRequest(
+            "Titles of archives in the library root are rescanned with the library".to_string(),
+        ));
+    }
+    let title_path = title.path.clone();
+
+    let new_lib = lib.rescan_title(&state.config, &title_path).await?;
+    let milliseconds = start.elapsed().as_millis();
+    let rescanned = new_lib
+        .get_titles()
+        .into_iter()
+        .find(|t| t.path == title_path)
+        .map(|t| TitleScanResponse {
+            title_id: t.id.clone(),
+            title: t.title.clone(),
+            entries: t.deep_entries().len(),
+            milliseconds,
+            unreadable: t
+                .deep_titles()
+                .into_iter()
+                .flat_map(|t| &t.unreadable)
+                .map(|f| UnreadableFileInfo {
+                    path: crate::util::relative_to_root(&f.path, &state.config.library_path),
+                    error: f.error.clone(),
+                })
+                .collect(),
+        });
+    state.events.store_scanned(&state.library, new_lib);
+
+    let response = rescanned.ok_or_else(|| {
+        crate::error::Error::NotFound(format!(
+            "Title directory could not be scanned: {}",
+            title_path.display()
+        ))
+    })?;
+    tracing::info!(
+        "Rescanned title {}: {} entries in {}ms",
+        response.title,
+        response.entries,
+        response.milliseconds
+    );
+
+    Ok(Json(response))
+}
+
+#[derive(Deserialize)]
+pub struct RebuildSignaturesQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// POST /api/admin/maintenance/rebuild-signatures?dry_run=true - Recompute stored signatures
+/// For libraries moved to a new disk: overwrites the signature of every row whose path
+/// still exists, keeping IDs. With dry_run, only lists the rows that would change
+pub async fn rebuild_signatures(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    axum::extract::Query(query): axum::extract::Query<RebuildSignaturesQuery>,
+) -> Result<Json<crate::library::maintenance::SignatureRebuild>> {
+    let result = crate::library::maintenance::rebuild_signatures(
+        &state.config.library_path,
+        &state.storage,
+        state.library.load().scan_lock(),
+        query.dry_run,
+    )
+    .await?;
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+pub struct BackfillDateAddedQuery {
+    /// Limit the backfill to one title
+    pub tid: Option<String>,
+    /// "1" to replace every date, not only the initial import's stamps
+    pub force: Option<String>,
+}
+
+/// POST /api/admin/maintenance/backfill-date-added?tid=&force=1 - Date entries by file mtime
+/// Replaces the "now" stamps of the initial import (or every date with force=1)
+pub async fn backfill_date_added(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    axum::extract::Query(query): axum::extract::Query<BackfillDateAddedQuery>,
+) -> Result<Json<crate::library::maintenance::DateAddedBackfill>> {
+    let force = matches!(query.force.as_deref(), Some("1") | Some("true"));
+    let lib = state.library.load();
+    let result = crate::library::maintenance::backfill_date_added(
+        &lib,
+        &state.storage,
+        query.tid.as_deref(),
+        force,
+    )
+    .await?;
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+pub struct PruneThumbnailsQuery {
+    /// "1" to VACUUM afterwards so the file shrinks; blocks the database meanwhile
+    pub vacuum: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PruneThumbnailsResponse {
+    #[serde(flatten)]
+    pub pruned: crate::storage::ThumbnailPrune,
+    pub vacuumed: bool,
+}
+
+/// Prune thumbnails of deleted entries and of entries missing past the grace period
+async fn prune_thumbnails(state: &AppState) -> Result<crate::storage::ThumbnailPrune> {
+    let grace_secs = state.config.thumbnail_prune_grace_days as i64 * 24 * 60 * 60;
+    state
+        .storage
+        .prune_thumbnails(crate::util::time::now_ts() - grace_secs)
+        .await
+}
+
+/// POST /api/admin/maintenance/prune-thumbnails?vacuum=1 - Delete orphaned thumbnails
+/// Returns the rows and blob bytes removed
+pub async fn prune_thumbnails_api(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    axum::extract::Query(query): axum::extract::Query<PruneThumbnailsQuery>,
+) -> Result<Json<PruneThumbnailsResponse>> {
+    let pruned = prune_thumbnails(&state).await?;
+
+    let vacuum = matches!(query.vacuum.as_deref(), Some("1") | Some("true"));
+    if vacuum {
+        let start = Instant::now();
+        state.storage.vacuum().await?;
+        tracing::info!("Database vacuumed in {}ms", start.elapsed().as_millis());
+    }
+
+    Ok(Json(PruneThumbnailsResponse {
+        pruned,
+        vacuumed: vacuum,
     }))
 }
 
+#[derive(Deserialize)]
+pub struct ConvertQuery {
+    /// Limit the job to one title (nested titles included)
+    pub tid: Option<String>,
+    /// directories, archives (RAR and 7z) or all
+    #[serde(default)]
+    pub filter: crate::library::convert::ConvertFilter,
+}
+
+/// POST /api/admin/maintenance/convert?tid=&filter=directories|archives|all - Repack as CBZ
+/// Queues the matching entries and converts them in the background, together with any
+/// left unfinished by an interrupted job; the library is rescanned when it is done
+/// Refused while a scan or another job runs, and when the disk has less free space than
+/// the largest queued entry
+pub async fn start_conversion(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    axum::extract::Query(query): axum::extract::Query<ConvertQuery>,
+) -> Result<(StatusCode, Json<crate::library::convert::ConversionReport>)> {
+    use crate::library::convert;
+
+    let lib = state.library.load_full();
+    let job = convert::ConversionJob::start(&state.conversions, lib.scan_lock())?;
+    convert::queue(&lib, &state.storage, query.tid.as_deref(), query.filter).await?;
+    convert::check_free_space(&state.storage, lib.path()).await?;
+
+    let task_state = state.clone();
+    tokio::spawn(async move {
+        let keep_originals = task_state.config.convert_keep_originals;
+        match job.run(&lib, &task_state.storage, keep_originals).await {
+            Ok(report) => tracing::info!(
+                "Conversion finished: {} converted, {} failed, {} unfinished",
+                report.converted,
+                report.failed,
+                report.pending
+            ),
+            Err(e) => tracing::warn!("Conversion job stopped: {}", e),
+        }
+        // Picks up the new archives; titles nothing was converted in are reused
+        if let Err(e) = run_scan(&task_state, false, None).await {
+            tracing::warn!("Rescan after conversion failed: {}", e);
+        }
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(convert::report(&state.storage, &state.conversions).await?),
+    ))
+}
+
+/// GET /api/admin/maintenance/convert - Per-entry results of the current or last conversion
+pub async fn conversion_status(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+) -> Result<Json<crate::library::convert::ConversionReport>> {
+    Ok(Json(
+        crate::library::convert::report(&state.storage, &state.conversions).await?,
+    ))
+}
+
 /// GET /api/admin/entries/missing - Get all missing entries
-/// Returns list of entries marked as unavailable in the database
+/// Returns list of entries marked as unavailable in the database, followed by the
+/// entries the last scan left out for having no pages (type `broken`, not in the database)
 pub async fn get_missing_entries(
     State(state): State<AppState>,
     AdminOnly(_username): AdminOnly,
 ) -> Result<Json<Vec<crate::storage::MissingEntry>>> {
-    let entries = state.storage.get_missing_entries().await?;
+    let mut entries = state.storage.get_missing_entries().await?;
+
+    let lib = state.library.load();
+    let mut broken: Vec<crate::storage::MissingEntry> = lib
+        .broken_entries()
+        .map(|entry| crate::storage::MissingEntry {
+            id: entry.id.clone(),
+            path: entry.path.to_string_lossy().to_string(),
+            entry_type: "broken".to_string(),
+        })
+        .collect();
+    broken.sort_by(|a, b| natord::compare(&a.path, &b.path));
+    entries.extend(broken);
+
     Ok(Json(entries))
 }
 
+/// Block scans while missing items are deleted
+/// A scan running meanwhile would match files against rows being deleted, so deletes
+/// are refused while one runs, and a scan starting meanwhile waits for the delete
+fn block_scans(state: &AppState) -> Result<tokio::sync::OwnedRwLockWriteGuard<()>> {
+    state.library.load().scan_lock().block_scans()
+}
+
 /// DELETE /api/admin/entries/missing/:id - Delete a specific missing entry
 /// Removes the entry from the database (cannot be undone)
+/// Conflict while a library scan is running
 pub async fn delete_missing_entry(
     State(state): State<AppState>,
     AdminOnly(_username): AdminOnly,
     Path(id): Path<String>,
 ) -> Result<StatusCode> {
+    let _no_scans = block_scans(&state)?;
     state.storage.delete_missing_entry(&id).await?;
+    prune_thumbnails(&state).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
 /// DELETE /api/admin/entries/missing - Delete all missing entries
 /// Removes all unavailable entries from the database (cannot be undone)
+/// Conflict while a library scan is running
 pub async fn delete_all_missing_entries(
     State(state): State<AppState>,
     AdminOnly(_username): AdminOnly,
 ) -> Result<Json<serde_json::Value>> {
+    let _no_scans = block_scans(&state)?;
     let count = state.storage.delete_all_missing_entries().await?;
+    prune_thumbnails(&state).await?;
     Ok(Json(serde_json::json!({
         "deleted": count
     })))
@@ -196,14 +589,50 @@ pub async fn delete_all_missing_entries(
 #[derive(Template)]
 #[template(path = "missing-items.html")]
 struct MissingItemsTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
 }
 
 /// GET /admin/missing-items - Missing items management page
 /// Shows list of items in database whose files no longer exist
-pub async fn missing_items_page(AdminOnly(_username): AdminOnly) -> Result<Html<String>> {
+pub async fn missing_items_page(admin: AdminOnly) -> Result<Html<String>> {
     let template = MissingItemsTemplate {
-        nav: crate::util::NavigationState::admin().with_admin(true),
+        base: BaseContext::new(&admin.into(), NavPage::Admin),
+    };
+
+    Ok(Html(template.render().map_err(render_error)?))
+}
+
+/// Problem reports template
+#[derive(Template)]
+#[template(path = "reports.html")]
+struct ReportsTemplate {
+    base: crate::util::BaseContext,
+}
+
+/// GET /admin/reports - Problem reports page
+/// Lists open reports filed by users; resolved ones are shown as history
+pub async fn reports_page(admin: AdminOnly) -> Result<Html<String>> {
+    let template = ReportsTemplate {
+        base: BaseContext::new(&admin.into(), NavPage::Admin),
+    };
+
+    Ok(Html(template.render().map_err(render_error)?))
+}
+
+/// Popular titles template
+#[derive(Template)]
+#[template(path = "popular.html")]
+struct PopularTemplate {
+    base: crate::util::BaseContext,
+    default_days: u32,
+}
+
+/// GET /admin/stats - Popular titles page
+/// Most read and downloaded titles over a selectable number of days
+pub async fn popular_page(admin: AdminOnly) -> Result<Html<String>> {
+    let template = PopularTemplate {
+        base: BaseContext::new(&admin.into(), NavPage::Admin),
+        default_days: crate::stats::POPULARITY_DAYS,
     };
 
     Ok(Html(template.render().map_err(render_error)?))
@@ -213,38 +642,64 @@ pub async fn missing_items_page(AdminOnly(_username): AdminOnly) -> Result<Html<
 #[derive(Template)]
 #[template(path = "users.html")]
 struct UsersTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
+    users: Vec<UserRow>,
+    new_user_url: String,
+}
+
+/// User row for the users template
+struct UserRow {
     username: String,
-    users: Vec<UserResponse>,
+    is_admin: bool,
+    /// API tokens the user created
+    api_tokens: usize,
+    /// False for read-only (guest) users
+    can_write: bool,
+    /// When the user last logged in, relative ("3 days ago") and exact
+    last_login: Option<(String, String)>,
+    edit_url: String,
 }
 
 /// User edit template
 #[derive(Template)]
 #[template(path = "user-edit.html")]
 struct UserEditTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
     new_user: bool,
     edit_username: String,
     is_admin: bool,
+    /// Whether the edited user is read-only
+    read_only: bool,
     error: String,
 }
 
 /// GET /admin/user - User management page
 /// Shows list of users and allows creating/deleting users
-pub async fn users_page(
-    State(state): State<AppState>,
-    AdminOnly(username): AdminOnly,
-) -> Result<Html<String>> {
+pub async fn users_page(State(state): State<AppState>, admin: AdminOnly) -> Result<Html<String>> {
     let users = state.storage.list_users().await?;
+    let token_counts = state.storage.count_api_tokens().await?;
+    let now = crate::util::time::now_ts();
     let users = users
         .into_iter()
-        .map(|(username, is_admin)| UserResponse { username, is_admin })
+        .map(|user| UserRow {
+            edit_url: paths::admin_user_edit(Some((&user.username, user.is_admin))),
+            api_tokens: token_counts.get(&user.username).copied().unwrap_or(0),
+            last_login: user.last_login.map(|ts| {
+                (
+                    crate::util::time::format_relative(ts, now),
+                    crate::util::time::format_ts(ts),
+                )
+            }),
+            username: user.username,
+            is_admin: user.is_admin,
+            can_write: user.can_write,
+        })
         .collect();
 
     let template = UsersTemplate {
-        nav: crate::util::NavigationState::admin().with_admin(true),
-        username,
+        base: BaseContext::new(&admin.into(), NavPage::Admin),
         users,
+        new_user_url: paths::admin_user_edit(None),
     };
 
     Ok(Html(template.render().map_err(render_error)?))
@@ -255,6 +710,10 @@ pub async fn users_page(
 pub struct UserResponse {
     pub username: String,
     pub is_admin: bool,
+    /// False for read-only (guest) users
+    pub can_write: bool,
+    /// Unix timestamp, null if the user never logged in
+    pub last_login: Option<i64>,
 }
 
 /// GET /api/admin/user - Get all users
@@ -266,43 +725,278 @@ pub async fn get_users(
     let users = state.storage.list_users().await?;
     let response = users
         .into_iter()
-        .map(|(username, is_admin)| UserResponse { username, is_admin })
+        .map(|user| UserResponse {
+            username: user.username,
+            is_admin: user.is_admin,
+            can_write: user.can_write,
+            last_login: user.last_login,
+        })
+        .collect();
+    Ok(Json(response))
+}
+
+/// Per-user download usage for today
+#[derive(Serialize)]
+pub struct QuotaUsageResponse {
+    pub username: String,
+    pub used_bytes: u64,
+    pub quota_bytes: Option<u64>,
+}
+
+/// GET /api/admin/quota - Get today's download usage for all users
+/// Usage resets at 00:00 UTC; quota_bytes is null when downloads are unlimited
+pub async fn get_all_quotas(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+) -> Result<Json<Vec<QuotaUsageResponse>>> {
+    let users = state.storage.list_users().await?;
+    let usage = state.quota.usage_today_all().await?;
+    let quota_bytes = state.quota.limit_bytes();
+
+    let response = users
+        .into_iter()
+        .map(|user| QuotaUsageResponse {
+            used_bytes: usage.get(&user.username).copied().unwrap_or(0),
+            username: user.username,
+            quota_bytes,
+        })
         .collect();
     Ok(Json(response))
 }
 
+#[derive(Deserialize)]
+pub struct ReportsQuery {
+    #[serde(default)]
+    resolved: bool,
+}
+
+/// Report with display names and links resolved from the library
+#[derive(Serialize)]
+pub struct ReportItem {
+    #[serde(flatten)]
+    report: crate::storage::Report,
+    title_name: Option<String>,
+    entry_name: Option<String>,
+    book_url: String,
+    reader_url: String,
+}
+
+/// GET /api/admin/reports?resolved=true|false - List problem reports
+/// Open reports by default (oldest first); resolved=true returns history
+pub async fn get_reports(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    axum::extract::Query(query): axum::extract::Query<ReportsQuery>,
+) -> Result<Json<Vec<ReportItem>>> {
+    let reports = state.storage.list_reports(query.resolved).await?;
+    let lib = state.library.load();
+
+    let items = reports
+        .into_iter()
+        .map(|report| {
+            let title = lib.get_title(&report.title_id);
+            let entry = lib.get_entry(&report.title_id, &report.entry_id);
+            ReportItem {
+                title_name: title.map(|t| t.title.clone()),
+                entry_name: entry.map(|e| e.title.clone()),
+                book_url: paths::book(&report.title_id),
+                reader_url: paths::reader(&report.title_id, &report.entry_id, 1),
+                report,
+            }
+        })
+        .collect();
+
+    Ok(Json(items))
+}
+
+#[derive(Deserialize)]
+pub struct PopularQuery {
+    days: Option<u32>,
+}
+
+/// Title read counters with its name and link resolved from the library
+#[derive(Serialize)]
+pub struct PopularTitle {
+    title_id: String,
+    /// None for titles no longer in the library
+    title_name: Option<String>,
+    book_url: String,
+    #[serde(flatten)]
+    counts: crate::storage::TitleCounts,
+    /// Reads plus downloads, what the popularity sort ranks by
+    score: u64,
+}
+
+/// GET /api/admin/stats/popular?days=30 - Most popular titles over the last days
+/// Days default to the popularity sort's window; today is included
+pub async fn get_popular_titles(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    axum::extract::Query(query): axum::extract::Query<PopularQuery>,
+) -> Result<Json<Vec<PopularTitle>>> {
+    let days = query.days.unwrap_or(crate::stats::POPULARITY_DAYS);
+    if days == 0 || days > crate::stats::MAX_STATS_DAYS {
+        return Err(crate::error::Error::BadRequest(format!(
+            "days must be between 1 and {}",
+            crate::stats::MAX_STATS_DAYS
+        )));
+    }
+
+    let popular = state.stats.popular(days).await?;
+    let lib = state.library.load();
+
+    let items = popular
+        .into_iter()
+        .map(|(title_id, counts)| PopularTitle {
+            title_name: lib.get_title(&title_id).map(|t| t.title.clone()),
+            book_url: paths::book(&title_id),
+            score: crate::stats::score(&counts),
+            title_id,
+            counts,
+        })
+        .collect();
+
+    Ok(Json(items))
+}
+
+/// Entry with pages that failed validation
+#[derive(Serialize)]
+pub struct BadPagesItem {
+    title_id: String,
+    title_name: String,
+    entry_id: String,
+    entry_name: String,
+    pages: usize,
+    /// One-based page numbers
+    bad_pages: Vec<usize>,
+    /// Unix timestamp (UTC seconds)
+    checked_at: i64,
+    reader_url: String,
+}
+
+/// GET /api/admin/bad_pages - List entries with pages that failed validation
+/// Populated when validate_pages is enabled (reader first access and thumbnail generation)
+/// and by pages that could not be extracted when requested
+pub async fn get_bad_pages(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+) -> Result<Json<Vec<BadPagesItem>>> {
+    let checks = state.storage.list_bad_pages().await?;
+    let lib = state.library.load();
+
+    let mut entries = HashMap::new();
+    for title in lib.all_titles() {
+        for entry in &title.entries {
+            entries.insert(entry.id.as_str(), (title, entry));
+        }
+    }
+
+    // Results for entries no longer in the library are skipped
+    let items = checks
+        .into_iter()
+        .filter_map(|check| {
+            let (title, entry) = entries.get(check.entry_id.as_str())?;
+            let first_bad = check.bad_pages.first().copied().unwrap_or(1);
+            Some(BadPagesItem {
+                title_id: title.id.clone(),
+                title_name: title.title.clone(),
+                entry_id: entry.id.clone(),
+                entry_name: entry.title.clone(),
+                pages: entry.pages,
+                reader_url: paths::reader(&title.id, &entry.id, first_bad),
+                bad_pages: check.bad_pages,
+                checked_at: check.checked_at,
+            })
+        })
+        .collect();
+
+    Ok(Json(items))
+}
+
+/// PATCH /api/admin/reports/:id - Mark a problem report as resolved
+pub async fn resolve_report(
+    State(state): State<AppState>,
+    AdminOnly(username): AdminOnly,
+    Path(id): Path<i64>,
+) -> Result<Json<serde_json::Value>> {
+    state.storage.resolve_report(id, &username).await?;
+
+    tracing::info!("Report {} resolved by {}", id, username);
+
+    Ok(Json(serde_json::json!({
+        "success": true
+    })))
+}
+
+/// Response for the admin diagnostics endpoint
+#[derive(Serialize)]
+pub struct DiagnosticsResponse {
+    pub database: crate::storage::PoolStats,
+    pub library: LibraryDiagnostics,
+}
+
+/// Library counters reported by the admin diagnostics endpoint
+#[derive(Serialize)]
+pub struct LibraryDiagnostics {
+    pub title_info_loads: u64,
+    /// Page requests that waited for an identical one instead of extracting the page
+    pub page_flights: crate::library::page_flights::PageFlightStats,
+}
+
+/// GET /api/admin/diagnostics - Get server health counters
+/// Database: pool size/idle, acquire timeouts, slow waits and slow queries since startup
+/// Library: info.json loads and coalesced page requests since startup
+pub async fn get_diagnostics(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+) -> Result<Json<DiagnosticsResponse>> {
+    Ok(Json(DiagnosticsResponse {
+        database: state.storage.pool_stats(),
+        library: LibraryDiagnostics {
+            title_info_loads: crate::library::progress::TitleInfo::load_count(),
+            page_flights: state.page_flights.stats(),
+        },
+    }))
+}
+
 /// Request body for creating a new user
 #[derive(Deserialize)]
 pub struct CreateUserRequest {
     pub username: String,
     pub password: String,
     pub is_admin: bool,
+    /// False makes a read-only (guest) user
+    #[serde(default = "default_can_write")]
+    pub can_write: bool,
+}
+
+fn default_can_write() -> bool {
+    true
 }
 
 /// POST /api/admin/user - Create a new user
-/// Creates a new user with the given credentials and admin status
+/// Creates a new user with the given credentials, admin status and write access
+/// Returns: 409 Conflict if the username is taken
 pub async fn create_user(
     State(state): State<AppState>,
     AdminOnly(_username): AdminOnly,
     Json(request): Json<CreateUserRequest>,
 ) -> Result<StatusCode> {
-    // Check if username already exists
-    if state.storage.username_exists(&request.username).await? {
-        return Err(crate::error::Error::Conflict(format!(
-            "Username '{}' already exists",
-            request.username
-        )));
-    }
-
     state
         .storage
-        .create_user(&request.username, &request.password, request.is_admin)
+        .create_user(
+            &request.username,
+            &request.password,
+            request.is_admin,
+            request.can_write,
+        )
         .await?;
 
     tracing::info!(
-        "User '{}' created (admin: {})",
+        "User '{}' created (admin: {}, can write: {})",
         request.username,
-        request.is_admin
+        request.is_admin,
+        request.can_write
     );
 
     Ok(StatusCode::CREATED)
@@ -313,13 +1007,18 @@ pub async fn create_user(
 pub struct UpdateUserRequest {
     pub is_admin: bool,
     pub password: Option<String>,
+    /// Left unchanged when missing
+    pub can_write: Option<bool>,
 }
 
 /// PATCH /api/admin/user/:username - Update user's admin status
-/// Changes whether a user is an administrator
+/// Changes whether a user is an administrator, and whether they are read-only
+/// Returns: 409 Conflict for demoting the last admin
+/// A new password logs the user out and revokes their tokens, like `reset_password`
 pub async fn update_user(
     State(state): State<AppState>,
     AdminOnly(current_username): AdminOnly,
+    session: Session,
     Path(username): Path<String>,
     Json(request): Json<UpdateUserRequest>,
 ) -> Result<StatusCode> {
@@ -330,24 +1029,28 @@ pub async fn update_user(
         ));
     }
 
-    // Check if user exists
-    if !state.storage.username_exists(&username).await? {
-        return Err(crate::error::Error::NotFound(format!(
-            "User '{}' not found",
-            username
-        )));
-    }
+    let can_write = match request.can_write {
+        Some(can_write) => can_write,
+        None => state.storage.can_write(&username).await?,
+    };
 
-    // Update user using existing update_user method
-    state
-        .storage
-        .update_user(&username, &username, request.password.as_deref(), request.is_admin)
-        .await?;
+    // 404 if the user does not exist
+    set_user(
+        &state,
+        &session,
+        &current_username,
+        &username,
+        request.password.as_deref(),
+        request.is_admin,
+        can_write,
+    )
+    .await?;
 
     tracing::info!(
-        "User '{}' updated (admin: {}, password changed: {})",
+        "User '{}' updated (admin: {}, can write: {}, password changed: {})",
         username,
         request.is_admin,
+        can_write,
         request.password.is_some()
     );
 
@@ -356,6 +1059,7 @@ pub async fn update_user(
 
 /// DELETE /api/admin/user/:username - Delete a user
 /// Removes a user from the system (cannot be undone)
+/// Returns: 409 Conflict for the last admin
 pub async fn delete_user(
     State(state): State<AppState>,
     AdminOnly(current_username): AdminOnly,
@@ -389,197 +1093,777 @@ pub async fn cache_clear_api(
 
     tracing::info!("Cache cleared by admin");
 
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "message": "Cache cleared successfully",
-        "entries_remaining": stats.entry_count
-    })))
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Cache cleared successfully",
+        "entries_remaining": stats.entry_count
+    })))
+}
+
+/// POST /api/cache/save-library - Save library to cache file
+/// Saves current library state to persistent cache file
+pub async fn cache_save_library_api(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+) -> Result<Json<serde_json::Value>> {
+    let lib = state.library.load();
+
+    // Create cached data
+    let cached_data = crate::library::cache::CachedLibraryData {
+        path: lib.path().to_path_buf(),
+        titles: lib.titles().clone(),
+    };
+
+    let cache = lib.cache().lock().await;
+    if !cache.file_enabled() {
+        return Ok(Json(serde_json::json!({
+            "success": false,
+            "message": "Library cache file is disabled (library_cache_file_enabled)"
+        })));
+    }
+    cache.save_library_data(cached_data).await?;
+    drop(cache);
+    drop(lib);
+
+    tracing::info!("Library cache saved by admin");
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Library cache saved successfully"
+    })))
+}
+
+/// POST /api/cache/load-library - Load library from cache file
+/// Reloads library from persistent cache file
+/// Uses double-buffer approach: creates new library, loads from cache, swaps
+pub async fn cache_load_library_api(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+) -> Result<Json<serde_json::Value>> {
+    if !state.config.library_cache_file_enabled {
+        return Ok(Json(serde_json::json!({
+            "success": false,
+            "message": "Library cache file is disabled (library_cache_file_enabled)"
+        })));
+    }
+
+    // Build new library instance and try to load from cache
+    let mut new_lib = crate::library::Library::new(
+        state.config.library_path.clone(),
+        state.storage.clone(),
+        &state.config,
+    );
+
+    new_lib.share_scan_lock(&state.library.load());
+    let loaded = new_lib.try_load_from_cache().await?;
+
+    if loaded {
+        let stats = new_lib.stats();
+
+        // Atomically swap the new library in
+        state.library.store(std::sync::Arc::new(new_lib));
+
+        tracing::info!("Library cache loaded by admin");
+
+        Ok(Json(serde_json::json!({
+            "success": true,
+            "message": "Library loaded from cache successfully",
+            "titles": stats.titles,
+            "entries": stats.entries
+        })))
+    } else {
+        Ok(Json(serde_json::json!({
+            "success": false,
+            "message": "No valid cache file found"
+        })))
+    }
+}
+
+/// GET /api/cache/stats - LRU cache statistics
+/// Size, entries, hits, misses and evictions, in total and by kind (sorted_titles,
+/// sorted_entries, progress_sum, info_json, other)
+pub async fn cache_stats_api(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+) -> Result<Json<crate::library::cache::CacheStats>> {
+    let lib = state.library.load();
+    let stats = lib.cache().lock().await.stats();
+    Ok(Json(stats))
+}
+
+/// Request body for cache invalidation endpoint
+#[derive(Deserialize)]
+pub struct CacheInvalidateRequest {
+    /// Filter on what entries are about (e.g., "kind=sorted_titles user=user1")
+    pub pattern: String,
+}
+
+/// POST /api/cache/invalidate - Invalidate cache entries by filter
+/// Invalidates all cache entries matching the given kind/user/title filter and lists
+/// what was removed
+pub async fn cache_invalidate_api(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    Json(request): Json<CacheInvalidateRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let filter = crate::library::cache::CacheFilter::parse(&request.pattern)?;
+
+    let lib = state.library.load();
+    let invalidated = lib.cache().lock().await.invalidate_matching(&filter);
+    drop(lib);
+
+    let count = invalidated.len();
+    tracing::info!(
+        "Cache invalidation by admin: {} entries matching '{}'",
+        count,
+        request.pattern
+    );
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Invalidated {} cache entries", count),
+        "count": count,
+        "invalidated": invalidated
+    })))
+}
+
+// ========== Title/Entry Metadata API Endpoints ==========
+
+#[derive(Deserialize)]
+pub struct DisplayNameQuery {
+    eid: Option<String>,
+}
+
+/// PUT /api/admin/display_name/:tid/:name - Update display name for title or entry
+pub async fn update_display_name(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    Path((title_id, name)): Path<(String, String)>,
+    axum::extract::Query(query): axum::extract::Query<DisplayNameQuery>,
+) -> Result<Json<serde_json::Value>> {
+    let decoded_name = percent_encoding::percent_decode_str(&name)
+        .decode_utf8()
+        .map_err(|e| crate::error::Error::BadRequest(format!("Invalid UTF-8 in name: {}", e)))?
+        .to_string();
+
+    // Update display name in database
+    if let Some(entry_id) = query.eid {
+        state
+            .storage
+            .update_entry_display_name(&entry_id, &decoded_name)
+            .await?;
+        tracing::info!("Updated entry {} display name to '{}'", entry_id, decoded_name);
+    } else {
+        state
+            .storage
+            .update_title_display_name(&title_id, &decoded_name)
+            .await?;
+        tracing::info!("Updated title {} display name to '{}'", title_id, decoded_name);
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct SortTitleQuery {
+    eid: Option<String>,
+    name: Option<String>,
+}
+
+/// PUT /api/admin/sort_title/:tid - Update sort title for title or entry
+pub async fn update_sort_title(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    Path(title_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<SortTitleQuery>,
+) -> Result<Json<serde_json::Value>> {
+    let sort_title = query.name.as_deref();
+
+    if let Some(entry_id) = &query.eid {
+        state
+            .storage
+            .update_entry_sort_title(entry_id, sort_title)
+            .await?;
+        tracing::info!("Updated entry {} sort title to {:?}", entry_id, sort_title);
+    } else {
+        state
+            .storage
+            .update_title_sort_title(&title_id, sort_title)
+            .await?;
+        tracing::info!("Updated title {} sort title to {:?}", title_id, sort_title);
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true
+    })))
+}
+
+// ========== Title Aliases API ==========
+
+#[derive(Deserialize)]
+pub struct AliasesRequest {
+    /// Primary alias first; an empty list clears the aliases
+    aliases: Vec<String>,
+}
+
+/// PUT /api/admin/title/:id/aliases - Replace the aliases of a title
+/// Aliases are normalized (trimmed, whitespace collapsed) and must be unique within the title
+pub async fn set_title_aliases(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    Path(title_id): Path<String>,
+    Json(request): Json<AliasesRequest>,
+) -> Result<Json<serde_json::Value>> {
+    if state.library.load().get_title(&title_id).is_none() {
+        return Err(crate::error::Error::NotFound(format!(
+            "Title not found: {}",
+            title_id
+        )));
+    }
+
+    let aliases = crate::util::normalize_aliases(&request.aliases)?;
+    state.storage.set_title_aliases(&title_id, &aliases).await?;
+    tracing::info!("Updated title {} aliases to {:?}", title_id, aliases);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "aliases": aliases
+    })))
+}
+
+// ========== Title Relations API ==========
+
+#[derive(Deserialize)]
+pub struct RelationsRequest {
+    /// The title this one continues
+    #[serde(default)]
+    sequel_of: Option<String>,
+    /// The title continuing this one
+    #[serde(default)]
+    prequel_of: Option<String>,
+    /// Spin-offs and side stories
+    #[serde(default)]
+    related: Vec<String>,
+}
+
+/// PUT /api/admin/title/:id/relations - Replace how a title relates to others
+/// The title a title becomes the sequel (or prequel) of gives up its previous one
+/// Returns: 400 if a linked title is not in the library or the sequels would form a cycle
+pub async fn set_title_relations(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    Path(title_id): Path<String>,
+    Json(request): Json<RelationsRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let mut related = request.related;
+    related.sort();
+    related.dedup();
+    let relations = crate::storage::TitleRelations {
+        sequel_of: request.sequel_of,
+        prequel_of: request.prequel_of,
+        related,
+    };
+
+    {
+        let lib = state.library.load();
+        if lib.get_title(&title_id).is_none() {
+            return Err(crate::error::Error::NotFound(format!(
+                "Title not found: {}",
+                title_id
+            )));
+        }
+        let linked = relations
+            .sequel_of
+            .iter()
+            .chain(&relations.prequel_of)
+            .chain(&relations.related);
+        for id in linked {
+            if lib.get_title(id).is_none() {
+                return Err(crate::error::Error::BadRequest(format!(
+                    "Linked title not found: {}",
+                    id
+                )));
+            }
+        }
+    }
+
+    state
+        .storage
+        .set_title_relations(&title_id, &relations)
+        .await?;
+    tracing::info!("Updated title {} relations to {:?}", title_id, relations);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "relations": relations
+    })))
+}
+
+/// Where a title's archives are on the server
+/// The only place absolute paths are exposed; user-facing pages show relative ones
+#[derive(Serialize)]
+pub struct TitleFilesResponse {
+    pub id: String,
+    pub title: String,
+    pub path: String,
+    pub relative_path: String,
+    pub entries: Vec<EntryFile>,
+}
+
+#[derive(Serialize)]
+pub struct EntryFile {
+    pub id: String,
+    pub title: String,
+    pub path: String,
+    pub relative_path: String,
+    /// Archives of a merged entry, in reading order
+    pub parts: Vec<String>,
+}
+
+/// GET /api/admin/title/:id/files - Absolute and library-relative paths of a title
+/// and its entries, for tracking down files on the server
+pub async fn get_title_files(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    Path(title_id): Path<String>,
+) -> Result<Json<TitleFilesResponse>> {
+    let lib = state.library.load();
+    let title = lib
+        .get_title(&title_id)
+        .ok_or_else(|| crate::error::Error::NotFound(format!("Title not found: {}", title_id)))?;
+
+    let entries = title
+        .entries
+        .iter()
+        .map(|entry| EntryFile {
+            id: entry.id.clone(),
+            title: entry.title.clone(),
+            path: entry.path.display().to_string(),
+            relative_path: entry.relative_path(lib.path()),
+            parts: entry
+                .parts
+                .iter()
+                .map(|part| part.path.display().to_string())
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(TitleFilesResponse {
+        id: title.id.clone(),
+        title: title.title.clone(),
+        path: title.path.display().to_string(),
+        relative_path: title.relative_path(lib.path()),
+        entries,
+    }))
+}
+
+/// Response for a title's reading progress by user
+#[derive(Serialize)]
+pub struct TitleReadersResponse {
+    pub id: String,
+    pub title: String,
+    /// Columns of the matrix, in the order of each reader's `entries`
+    pub entries: Vec<ReaderColumn>,
+    pub readers: Vec<TitleReader>,
+}
+
+#[derive(Serialize)]
+pub struct ReaderColumn {
+    pub id: String,
+    pub title: String,
+}
+
+/// One user's row of the matrix
+#[derive(Serialize)]
+pub struct TitleReader {
+    pub username: String,
+    pub entries: Vec<ReaderCell>,
+    /// Entries read to the end
+    pub completed: usize,
+    /// Progress through the title's entries (0.0 - 100.0)
+    pub percentage: f32,
+}
+
+#[derive(Serialize)]
+pub struct ReaderCell {
+    pub percentage: f32,
+    /// Unix timestamp of the last page turn, None if never read
+    pub last_read: Option<i64>,
+}
+
+/// GET /api/admin/title/:id/readers - Every user's progress through a title's entries
+/// Built from one copy of the title's cached progress, without reading info.json
+/// Returns: 403 Forbidden when `admin_can_view_user_progress` is off
+pub async fn get_title_readers(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    Path(title_id): Path<String>,
+) -> Result<Json<TitleReadersResponse>> {
+    if !state.config.admin_can_view_user_progress {
+        return Err(crate::error::Error::Forbidden(
+            "Viewing other users' progress is disabled (admin_can_view_user_progress)".to_string(),
+        ));
+    }
+
+    let mut usernames: Vec<String> = state
+        .storage
+        .list_users()
+        .await?
+        .into_iter()
+        .map(|user| user.username)
+        .collect();
+    usernames.sort_by(|a, b| natord::compare(a, b));
+
+    let lib = state.library.load();
+    let title = lib
+        .get_title(&title_id)
+        .ok_or_else(|| crate::error::Error::NotFound(format!("Title not found: {}", title_id)))?;
+    let info = lib
+        .progress_cache()
+        .get_title_info(&title.id)
+        .unwrap_or_default();
+
+    let readers = usernames
+        .into_iter()
+        .map(|username| {
+            let entries: Vec<ReaderCell> = title
+                .entries
+                .iter()
+                .map(|entry| {
+                    let page = info.get_progress(&username, &entry.id).unwrap_or(0);
+                    ReaderCell {
+                        percentage: lib.entry_percentage(entry, page),
+                        last_read: info.get_last_read(&username, &entry.id),
+                    }
+                })
+                .collect();
+            let completed = entries
+                .iter()
+                .filter(|cell| crate::util::is_completed(cell.percentage))
+                .count();
+            let percentage = if entries.is_empty() {
+                0.0
+            } else {
+                entries.iter().map(|cell| cell.percentage).sum::<f32>() / entries.len() as f32
+            };
+            TitleReader {
+                username,
+                entries,
+                completed,
+                percentage,
+            }
+        })
+        .collect();
+
+    Ok(Json(TitleReadersResponse {
+        id: title.id.clone(),
+        title: title.title.clone(),
+        entries: title
+            .entries
+            .iter()
+            .map(|entry| ReaderColumn {
+                id: entry.id.clone(),
+                title: entry.display_title.clone(),
+            })
+            .collect(),
+        readers,
+    }))
+}
+
+// ========== Reading Direction API ==========
+
+#[derive(Deserialize)]
+pub struct DirectionRequest {
+    /// ltr, rtl or vertical; null clears the override
+    direction: Option<String>,
+}
+
+impl DirectionRequest {
+    fn parse(&self) -> Result<Option<crate::library::ReadingDirection>> {
+        self.direction
+            .as_deref()
+            .map(|value| {
+                crate::library::ReadingDirection::parse(value).ok_or_else(|| {
+                    crate::error::Error::BadRequest(format!(
+                        "Invalid direction '{}', expected ltr, rtl or vertical",
+                        value
+                    ))
+                })
+            })
+            .transpose()
+    }
 }
 
-/// POST /api/cache/save-library - Save library to cache file
-/// Saves current library state to persistent cache file
-pub async fn cache_save_library_api(
+/// PUT /api/title/:id/direction - Set or clear the reading direction override of a title
+pub async fn set_title_direction(
     State(state): State<AppState>,
     AdminOnly(_username): AdminOnly,
+    Path(title_id): Path<String>,
+    Json(request): Json<DirectionRequest>,
 ) -> Result<Json<serde_json::Value>> {
+    let direction = request.parse()?;
     let lib = state.library.load();
-
-    // Create cached data
-    let cached_data = crate::library::cache::CachedLibraryData {
-        path: lib.path().to_path_buf(),
-        titles: lib.titles().clone(),
-    };
-
-    let cache = lib.cache().lock().await;
-    cache.save_library_data(cached_data).await?;
-    drop(cache);
-    drop(lib);
-
-    tracing::info!("Library cache saved by admin");
+    lib.set_direction(&title_id, None, direction).await?;
+    tracing::info!("Updated title {} direction to {:?}", title_id, direction);
 
     Ok(Json(serde_json::json!({
-        "success": true,
-        "message": "Library cache saved successfully"
+        "success": true
     })))
 }
 
-/// POST /api/cache/load-library - Load library from cache file
-/// Reloads library from persistent cache file
-/// Uses double-buffer approach: creates new library, loads from cache, swaps
-pub async fn cache_load_library_api(
+/// PUT /api/entry/:tid/:eid/direction - Set or clear the reading direction override of an entry
+/// Takes precedence over the title override
+pub async fn set_entry_direction(
     State(state): State<AppState>,
     AdminOnly(_username): AdminOnly,
+    Path((title_id, entry_id)): Path<(String, String)>,
+    Json(request): Json<DirectionRequest>,
 ) -> Result<Json<serde_json::Value>> {
-    // Build new library instance and try to load from cache
-    let mut new_lib = crate::library::Library::new(
-        state.config.library_path.clone(),
-        state.storage.clone(),
-        &state.config,
-    );
-
-    let loaded = new_lib.try_load_from_cache().await?;
-
-    if loaded {
-        let stats = new_lib.stats();
-
-        // Atomically swap the new library in
-        state.library.store(std::sync::Arc::new(new_lib));
-
-        tracing::info!("Library cache loaded by admin");
+    let direction = request.parse()?;
+    let lib = state.library.load();
+    lib.set_direction(&title_id, Some(&entry_id), direction)
+        .await?;
+    tracing::info!("Updated entry {} direction to {:?}", entry_id, direction);
 
-        Ok(Json(serde_json::json!({
-            "success": true,
-            "message": "Library loaded from cache successfully",
-            "titles": stats.titles,
-            "entries": stats.entries
-        })))
-    } else {
-        Ok(Json(serde_json::json!({
-            "success": false,
-            "message": "No valid cache file found"
-        })))
-    }
+    Ok(Json(serde_json::json!({
+        "success": true
+    })))
 }
 
-/// Request body for cache invalidation endpoint
+// ========== Entry Order API ==========
+
 #[derive(Deserialize)]
-pub struct CacheInvalidateRequest {
-    /// Pattern to match cache keys (e.g., "sorted_titles:user1:")
-    pub pattern: String,
+pub struct EntryOrderRequest {
+    ids: Vec<String>,
 }
 
-/// POST /api/cache/invalidate - Invalidate cache entries by pattern
-/// Invalidates all cache entries matching the given pattern prefix
-pub async fn cache_invalidate_api(
+/// PUT /api/admin/title/:id/entry_order - Pin the entry order for a title
+/// Takes precedence over every sort method; entries not listed follow in Auto order
+pub async fn set_entry_order(
     State(state): State<AppState>,
     AdminOnly(_username): AdminOnly,
-    Json(request): Json<CacheInvalidateRequest>,
+    Path(title_id): Path<String>,
+    Json(request): Json<EntryOrderRequest>,
 ) -> Result<Json<serde_json::Value>> {
     let lib = state.library.load();
-    let mut cache = lib.cache().lock().await;
 
-    // Get all entries and count matches
-    let entries = cache.entries();
-    let matching_keys: Vec<String> = entries
-        .iter()
-        .filter(|e| e.key.starts_with(&request.pattern))
-        .map(|e| e.key.clone())
-        .collect();
+    let title = lib
+        .get_title(&title_id)
+        .ok_or_else(|| crate::error::Error::NotFound(format!("Title not found: {}", title_id)))?;
 
-    let count = matching_keys.len();
+    if request.ids.is_empty() {
+        return Err(crate::error::Error::BadRequest(
+            "Entry order must not be empty (use DELETE to clear it)".to_string(),
+        ));
+    }
 
-    // Invalidate matching entries
-    for key in matching_keys {
-        cache.invalidate(&key);
+    let mut seen = std::collections::HashSet::new();
+    for entry_id in &request.ids {
+        if !title.entries.iter().any(|e| e.id == *entry_id) {
+            return Err(crate::error::Error::BadRequest(format!(
+                "Entry {} does not belong to title {}",
+                entry_id, title_id
+            )));
+        }
+        if !seen.insert(entry_id) {
+            return Err(crate::error::Error::BadRequest(format!(
+                "Duplicate entry in order: {}",
+                entry_id
+            )));
+        }
     }
 
-    drop(cache);
-    drop(lib);
+    lib.set_entry_order(&title_id, request.ids.clone()).await?;
 
     tracing::info!(
-        "Cache invalidation by admin: {} entries matching '{}'",
-        count,
-        request.pattern
+        "Pinned entry order for title {} ({} entries)",
+        title_id,
+        request.ids.len()
     );
 
     Ok(Json(serde_json::json!({
-        "success": true,
-        "message": format!("Invalidated {} cache entries", count),
-        "count": count
+        "success": true
     })))
 }
 
-// ========== Title/Entry Metadata API Endpoints ==========
-
-#[derive(Deserialize)]
-pub struct DisplayNameQuery {
-    eid: Option<String>,
-}
-
-/// PUT /api/admin/display_name/:tid/:name - Update display name for title or entry
-pub async fn update_display_name(
+/// DELETE /api/admin/title/:id/entry_order - Clear the pinned entry order for a title
+pub async fn clear_entry_order(
     State(state): State<AppState>,
     AdminOnly(_username): AdminOnly,
-    Path((title_id, name)): Path<(String, String)>,
-    axum::extract::Query(query): axum::extract::Query<DisplayNameQuery>,
+    Path(title_id): Path<String>,
 ) -> Result<Json<serde_json::Value>> {
-    let decoded_name = percent_encoding::percent_decode_str(&name)
-        .decode_utf8()
-        .map_err(|e| crate::error::Error::BadRequest(format!("Invalid UTF-8 in name: {}", e)))?
-        .to_string();
+    let lib = state.library.load();
 
-    // Update display name in database
-    if let Some(entry_id) = query.eid {
-        state
-            .storage
-            .update_entry_display_name(&entry_id, &decoded_name)
-            .await?;
-        tracing::info!("Updated entry {} display name to '{}'", entry_id, decoded_name);
-    } else {
-        state
-            .storage
-            .update_title_display_name(&title_id, &decoded_name)
-            .await?;
-        tracing::info!("Updated title {} display name to '{}'", title_id, decoded_name);
-    }
+    lib.set_entry_order(&title_id, Vec::new()).await?;
+
+    tracing::info!("Cleared pinned entry order for title {}", title_id);
 
     Ok(Json(serde_json::json!({
         "success": true
     })))
 }
 
+// ========== Entry Merge API ==========
+
 #[derive(Deserialize)]
-pub struct SortTitleQuery {
-    eid: Option<String>,
-    name: Option<String>,
+pub struct MergeEntriesRequest {
+    /// Entries to merge, in reading order
+    entry_ids: Vec<String>,
 }
 
-/// PUT /api/admin/sort_title/:tid - Update sort title for title or entry
-pub async fn update_sort_title(
+#[derive(Serialize)]
+pub struct MergeEntriesResponse {
+    /// ID of the merged entry (its first part's)
+    entry_id: String,
+    title: String,
+    pages: usize,
+}
+
+/// Swap in a copy of the library with a title's merges reapplied
+async fn reapply_merges(state: &AppState, title: &crate::library::Title) -> Result<()> {
+    let merges = state.storage.list_entry_merges().await?;
+    let mut title = title.clone();
+    crate::library::merge::apply_merges(&mut title, &merges);
+
+    let new_lib = state.library.load().with_title(&state.config, title).await;
+    state.library.store(std::sync::Arc::new(new_lib));
+    Ok(())
+}
+
+/// POST /api/admin/title/:id/merge_entries - Present entries as one merged entry
+/// Pages are served from the entries in the given order and users' progress carries
+/// over. The merged entry takes the first entry's ID; the others are hidden until
+/// the merge is undone. Conflict while a library scan is running
+pub async fn merge_entries(
     State(state): State<AppState>,
     AdminOnly(_username): AdminOnly,
     Path(title_id): Path<String>,
-    axum::extract::Query(query): axum::extract::Query<SortTitleQuery>,
-) -> Result<Json<serde_json::Value>> {
-    let sort_title = query.name.as_deref();
+    Json(request): Json<MergeEntriesRequest>,
+) -> Result<Json<MergeEntriesResponse>> {
+    let _no_scans = block_scans(&state)?;
+    let lib = state.library.load();
+    let title = lib
+        .get_title(&title_id)
+        .ok_or_else(|| crate::error::Error::NotFound(format!("Title not found: {}", title_id)))?;
 
-    if let Some(entry_id) = &query.eid {
-        state
-            .storage
-            .update_entry_sort_title(entry_id, sort_title)
-            .await?;
-        tracing::info!("Updated entry {} sort title to {:?}", entry_id, sort_title);
-    } else {
-        state
-            .storage
-            .update_title_sort_title(&title_id, sort_title)
-            .await?;
-        tracing::info!("Updated title {} sort title to {:?}", title_id, sort_title);
+    if request.entry_ids.len() < 2 {
+        return Err(crate::error::Error::BadRequest(
+            "At least two entries are needed for a merge".to_string(),
+        ));
     }
 
-    Ok(Json(serde_json::json!({
-        "success": true
-    })))
+    let mut seen = std::collections::HashSet::new();
+    let mut parts = Vec::with_capacity(request.entry_ids.len());
+    for entry_id in &request.entry_ids {
+        let entry = title
+            .entries
+            .iter()
+            .find(|e| e.id == *entry_id)
+            .ok_or_else(|| {
+                crate::error::Error::BadRequest(format!(
+                    "Entry {} does not belong to title {}",
+                    entry_id, title_id
+                ))
+            })?;
+        if !entry.parts.is_empty() {
+            return Err(crate::error::Error::BadRequest(format!(
+                "Entry {} is already merged",
+                entry_id
+            )));
+        }
+        if !seen.insert(entry_id) {
+            return Err(crate::error::Error::BadRequest(format!(
+                "Duplicate entry in merge: {}",
+                entry_id
+            )));
+        }
+        parts.push(entry.clone());
+    }
+
+    state
+        .storage
+        .save_entry_merge(&crate::storage::EntryMerge {
+            title_id: title_id.clone(),
+            parts: parts
+                .iter()
+                .map(|p| (p.id.clone(), p.signature.clone()))
+                .collect(),
+        })
+        .await?;
+
+    let layout = crate::library::merge::MergedEntry::from_parts(&parts);
+    lib.progress_cache()
+        .update_title_info(&title_id, &title.info_dir(), |info| {
+            crate::library::merge::merge_title_progress(info, &request.entry_ids, &layout)
+        })
+        .await?;
+
+    reapply_merges(&state, title).await?;
+
+    let merged = crate::library::merge::merge_entries(parts);
+    tracing::info!(
+        "Merged {} entries of title {} into {} ({} pages)",
+        request.entry_ids.len(),
+        title_id,
+        merged.title,
+        merged.pages
+    );
+
+    Ok(Json(MergeEntriesResponse {
+        entry_id: merged.id,
+        title: merged.title,
+        pages: merged.pages,
+    }))
+}
+
+/// DELETE /api/admin/title/:id/merge_entries/:eid - Undo a merge
+/// The parts are listed again and progress on the merged entry is spread over them.
+/// Conflict while a library scan is running
+pub async fn unmerge_entries(
+    State(state): State<AppState>,
+    AdminOnly(_username): AdminOnly,
+    Path((title_id, entry_id)): Path<(String, String)>,
+) -> Result<StatusCode> {
+    let _no_scans = block_scans(&state)?;
+    let lib = state.library.load();
+    let title = lib
+        .get_title(&title_id)
+        .ok_or_else(|| crate::error::Error::NotFound(format!("Title not found: {}", title_id)))?;
+    let merged = title
+        .entries
+        .iter()
+        .find(|e| e.id == entry_id && !e.parts.is_empty())
+        .ok_or_else(|| {
+            crate::error::Error::NotFound(format!("Merged entry not found: {}", entry_id))
+        })?;
+
+    if !state.storage.delete_entry_merge(&entry_id).await? {
+        return Err(crate::error::Error::NotFound(format!(
+            "Merged entry not found: {}",
+            entry_id
+        )));
+    }
+
+    let part_ids: Vec<String> = merged.parts.iter().map(|p| p.id.clone()).collect();
+    let layout = crate::library::merge::MergedEntry::from_parts(&merged.parts);
+    lib.progress_cache()
+        .update_title_info(&title_id, &title.info_dir(), |info| {
+            crate::library::merge::split_title_progress(info, &part_ids, &layout)
+        })
+        .await?;
+
+    reapply_merges(&state, title).await?;
+
+    tracing::info!(
+        "Split merged entry {} of title {} into {} entries",
+        entry_id,
+        title_id,
+        part_ids.len()
+    );
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
 // ========== Bulk Progress API ==========
@@ -593,17 +1877,16 @@ pub struct BulkProgressRequest {
 /// action: "read" (100%) or "unread" (0%)
 pub async fn bulk_progress(
     State(state): State<AppState>,
-    crate::auth::Username(username): crate::auth::Username,
+    crate::auth::Writer(username): crate::auth::Writer,
     Path((action, title_id)): Path<(String, String)>,
     Json(request): Json<BulkProgressRequest>,
 ) -> Result<Json<serde_json::Value>> {
     let lib = state.library.load();
 
-    let title = lib
+    let _title = lib
         .get_title(&title_id)
         .ok_or_else(|| crate::error::Error::NotFound(format!("Title not found: {}", title_id)))?;
 
-    let cache = lib.progress_cache();
     for entry_id in &request.ids {
         // Get entry to find page count
         if let Some(entry) = lib.get_entry(&title_id, entry_id) {
@@ -618,8 +1901,7 @@ pub async fn bulk_progress(
                 }
             };
 
-            cache
-                .save_progress(&title_id, &title.path, &username, entry_id, page)
+            lib.save_progress(&title_id, &username, entry_id, page)
                 .await?;
         }
     }
@@ -685,7 +1967,7 @@ pub async fn generate_thumbnails(
     let lib = state.library.load();
     let mut entries_to_process: Vec<(String, String)> = Vec::new();
 
-    for title in lib.get_titles() {
+    for title in lib.all_titles() {
         for entry in &title.entries {
             entries_to_process.push((title.id.clone(), entry.id.clone()));
         }
@@ -704,15 +1986,31 @@ pub async fn generate_thumbnails(
             THUMBNAIL_CURRENT.store(i + 1, Ordering::SeqCst);
 
             if let Some(entry) = lib.get_entry(title_id, entry_id) {
-                // Check if thumbnail already exists
-                match crate::library::Entry::get_thumbnail(entry_id, db).await {
-                    Ok(Some(_)) => continue, // Already has thumbnail
-                    _ => {}
+                // Generate thumbnail unless one already exists
+                let has_thumbnail = matches!(
+                    crate::library::Entry::get_thumbnail(entry_id, db).await,
+                    Ok(Some(_))
+                );
+                if !has_thumbnail {
+                    match entry.generate_thumbnail(db, &state_clone.archives).await {
+                        Err(crate::error::Error::StorageUnavailable(reason)) => {
+                            // Every other entry would fail the same way
+                            state_clone.storage.health().mark_unavailable(&reason);
+                            tracing::warn!("Stopping thumbnail generation: {}", reason);
+                            break;
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to generate thumbnail for {}: {}", entry_id, e)
+                        }
+                        Ok(_) => {}
+                    }
                 }
 
-                // Generate thumbnail
-                if let Err(e) = entry.generate_thumbnail(db).await {
-                    tracing::warn!("Failed to generate thumbnail for {}: {}", entry_id, e);
+                // Page validation rides along with the library-wide pass
+                if state_clone.config.validate_pages {
+                    if let Err(e) = lib.check_entry_pages(entry, &state_clone.archives).await {
+                        tracing::warn!("Failed to validate pages of {}: {}", entry_id, e);
+                    }
                 }
             }
         }
@@ -737,6 +2035,9 @@ pub struct CoverUploadQuery {
     eid: Option<String>,
 }
 
+/// Largest accepted cover upload, in bytes
+pub(crate) const MAX_COVER_SIZE: usize = 10 * 1024 * 1024;
+
 /// POST /api/admin/upload/cover - Upload custom cover image
 pub async fn upload_cover(
     State(state): State<AppState>,
@@ -765,7 +2066,6 @@ pub async fn upload_cover(
     })?;
 
     // Validate file size (max 10MB)
-    const MAX_COVER_SIZE: usize = 10 * 1024 * 1024;
     if data.len() > MAX_COVER_SIZE {
         return Err(crate::error::Error::BadRequest(format!(
             "File too large. Maximum size is {} bytes",
@@ -774,19 +2074,27 @@ pub async fn upload_cover(
     }
 
     // Determine entry ID (either specific entry or first entry of title)
-    let entry_id = if let Some(eid) = query.eid {
-        eid
-    } else {
-        // Get first entry of title
-        let lib = state.library.load();
-        let title = lib.get_title(&query.tid).ok_or_else(|| {
-            crate::error::Error::NotFound(format!("Title not found: {}", query.tid))
-        })?;
-        title.entries.first().map(|e| e.id.clone()).ok_or_else(|| {
-            crate::error::Error::NotFound("Title has no entries".to_string())
-        })?
+    let lib = state.library.load();
+    let title = lib
+        .get_title(&query.tid)
+        .ok_or_else(|| crate::error::Error::NotFound(format!("Title not found: {}", query.tid)))?;
+    let first_entry_id = title.entries.first().map(|e| e.id.clone());
+    let entry_id = match query.eid {
+        Some(eid) => eid,
+        None => first_entry_id
+            .clone()
+            .ok_or_else(|| crate::error::Error::NotFound("Title has no entries".to_string()))?,
     };
 
+    // The title card shows a cover file from the title directory instead of this one
+    let folder_cover = title
+        .cover_path
+        .as_ref()
+        .filter(|_| first_entry_id.as_deref() == Some(entry_id.as_str()))
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().to_string());
+    drop(lib);
+
     // Determine MIME type
     let mime = content_type.unwrap_or_else(|| {
         // Guess from data
@@ -806,7 +2114,8 @@ pub async fn upload_cover(
     tracing::info!("Uploaded custom cover for entry {}", entry_id);
 
     Ok(Json(serde_json::json!({
-        "success": true
+        "success": true,
+        "folder_cover": folder_cover
     })))
 }
 
@@ -819,14 +2128,20 @@ pub struct UserEditQuery {
 
 /// GET /admin/user/edit - User edit page
 pub async fn user_edit_page(
-    AdminOnly(_username): AdminOnly,
+    State(state): State<AppState>,
+    admin: AdminOnly,
     axum::extract::Query(query): axum::extract::Query<UserEditQuery>,
 ) -> Result<Html<String>> {
+    let read_only = match &query.username {
+        Some(username) => !state.storage.can_write(username).await?,
+        None => false,
+    };
     let template = UserEditTemplate {
-        nav: crate::util::NavigationState::admin().with_admin(true),
+        base: BaseContext::new(&admin.into(), NavPage::Admin),
         new_user: query.username.is_none(),
         edit_username: query.username.unwrap_or_default(),
         is_admin: query.admin.unwrap_or(false),
+        read_only,
         error: String::new(),
     };
 
@@ -840,6 +2155,8 @@ pub struct UserEditForm {
     pub password: Option<String>,
     #[serde(default)]
     pub admin: Option<String>,
+    #[serde(default)]
+    pub read_only: Option<String>,
 }
 
 /// POST /admin/user/edit - Create new user
@@ -849,6 +2166,7 @@ pub async fn user_edit_post(
     axum::extract::Form(form): axum::extract::Form<UserEditForm>,
 ) -> Result<axum::response::Redirect> {
     let is_admin = form.admin.is_some();
+    let can_write = form.read_only.is_none();
     let password = form.password.unwrap_or_default();
 
     if password.is_empty() {
@@ -859,22 +2177,30 @@ pub async fn user_edit_post(
 
     state
         .storage
-        .create_user(&form.username, &password, is_admin)
+        .create_user(&form.username, &password, is_admin, can_write)
         .await?;
 
-    tracing::info!("Created user '{}' (admin: {})", form.username, is_admin);
+    tracing::info!(
+        "Created user '{}' (admin: {}, can write: {})",
+        form.username,
+        is_admin,
+        can_write
+    );
 
-    Ok(axum::response::Redirect::to("/admin/user"))
+    Ok(axum::response::Redirect::to(&paths::admin_users()))
 }
 
 /// POST /admin/user/edit/:username - Update existing user
+/// A new password logs the user out and revokes their tokens, like `reset_password`
 pub async fn user_edit_post_existing(
     State(state): State<AppState>,
     AdminOnly(current_username): AdminOnly,
+    session: Session,
     Path(username): Path<String>,
     axum::extract::Form(form): axum::extract::Form<UserEditForm>,
 ) -> Result<axum::response::Redirect> {
     let is_admin = form.admin.is_some();
+    let can_write = form.read_only.is_none();
 
     // Prevent users from demoting themselves
     if username == current_username && !is_admin {
@@ -885,19 +2211,124 @@ pub async fn user_edit_post_existing(
 
     let password = form.password.filter(|p| !p.is_empty());
 
-    state
-        .storage
-        .update_user(&username, &username, password.as_deref(), is_admin)
-        .await?;
+    set_user(
+        &state,
+        &session,
+        &current_username,
+        &username,
+        password.as_deref(),
+        is_admin,
+        can_write,
+    )
+    .await?;
 
     tracing::info!(
-        "Updated user '{}' (admin: {}, password changed: {})",
+        "Updated user '{}' (admin: {}, can write: {}, password changed: {})",
         username,
         is_admin,
+        can_write,
         password.is_some()
     );
 
-    Ok(axum::response::Redirect::to("/admin/user"))
+    Ok(axum::response::Redirect::to(&paths::admin_users()))
+}
+
+/// Update a user's roles, and their password when one is given
+/// A new password goes through `auth::change_password`
+async fn set_user(
+    state: &AppState,
+    session: &Session,
+    admin: &str,
+    username: &str,
+    password: Option<&str>,
+    is_admin: bool,
+    can_write: bool,
+) -> Result<()> {
+    let update = state
+        .storage
+        .update_user(username, username, password, is_admin, can_write);
+    match password {
+        Some(password) => {
+            let keep = own_session(session, admin, username).await;
+            crate::auth::change_password(
+                &state.storage,
+                username,
+                password,
+                keep.as_deref(),
+                update,
+            )
+            .await?;
+            Ok(())
+        }
+        None => update.await,
+    }
+}
+
+/// Token of the admin's own session when `username` is the admin, which a password change
+/// leaves logged in
+async fn own_session(session: &Session, admin: &str, username: &str) -> Option<String> {
+    if username != admin {
+        return None;
+    }
+    session
+        .get::<String>(crate::auth::SESSION_TOKEN_KEY)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Request body for resetting a user's password
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub password: String,
+}
+
+#[derive(Serialize)]
+pub struct ResetPasswordResponse {
+    /// Login sessions of the user that were logged out
+    pub sessions_revoked: u64,
+    /// App tokens and API tokens of the user that stopped working
+    pub tokens_revoked: u64,
+}
+
+/// POST /api/admin/users/:username/reset-password - Set a new password for a user who
+/// forgot theirs `{"password": "..."}`
+/// Logs out the user's sessions, except the admin's own when resetting their own password,
+/// and revokes their app and API tokens
+pub async fn reset_password(
+    State(state): State<AppState>,
+    AdminOnly(admin): AdminOnly,
+    session: Session,
+    Path(username): Path<String>,
+    Json(request): Json<ResetPasswordRequest>,
+) -> Result<Json<ResetPasswordResponse>> {
+    let keep = own_session(&session, &admin, &username).await;
+    let changed = crate::auth::change_password(
+        &state.storage,
+        &username,
+        &request.password,
+        keep.as_deref(),
+        state.storage.set_password(&username, &request.password),
+    )
+    .await?;
+
+    tracing::info!("Admin '{}' reset the password of '{}'", admin, username);
+    Ok(Json(ResetPasswordResponse {
+        sessions_revoked: changed.sessions_revoked,
+        tokens_revoked: changed.tokens_revoked,
+    }))
+}
+
+/// DELETE /api/admin/users/:username/two-factor - Turn off a user's two-factor login
+/// For users who lost their authenticator and recovery codes; also revokes app tokens
+pub async fn reset_two_factor(
+    State(state): State<AppState>,
+    AdminOnly(admin): AdminOnly,
+    Path(username): Path<String>,
+) -> Result<StatusCode> {
+    state.storage.reset_totp(&username).await?;
+    tracing::info!("Admin '{}' reset two-factor login of '{}'", admin, username);
+    Ok(StatusCode::NO_CONTENT)
 }
 
 /// DELETE /api/admin/user/delete/:username - Delete user
@@ -914,16 +2345,17 @@ pub async fn delete_user_api(
         })));
     }
 
-    // Check if user exists
-    if !state.storage.username_exists(&username).await? {
-        return Ok(Json(serde_json::json!({
-            "success": false,
-            "error": format!("User '{}' not found", username)
-        })));
+    match state.storage.delete_user(&username).await {
+        Ok(()) => {}
+        Err(crate::error::Error::NotFound(message)) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": message
+            })));
+        }
+        Err(e) => return Err(e),
     }
 
-    state.storage.delete_user(&username).await?;
-
     tracing::info!("Deleted user '{}'", username);
 
     Ok(Json(serde_json::json!({
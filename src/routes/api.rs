@@ -1,50 +1,166 @@
 use axum::{
     extract::{Path, Query, State},
-    http::{header, StatusCode},
-    response::IntoResponse,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tower_sessions::Session;
 
+use super::{paths, TagLocks};
 use crate::{
+    auth::Username,
+    config::CardDensity,
     error::{Error, Result},
-    library::{Entry, SortMethod},
-    routes::calculate_progress_percentage,
-    util::SortParams,
+    library::{
+        entry::ThumbnailMeta, letter_buckets, Entry, EntrySource, LetterBucket, SortMethod, Title,
+        TitleInfo as LibraryTitleInfo,
+    },
+    storage::{PageSprite, ReportCategory, SpriteFrame, UserPreferences},
+    util::{get_image_dimensions, guess_mime_type, ArchiveKind, ProgressFilterParams, SortParams},
     AppState,
 };
 
-/// API route: GET /api/library?sort=title|modified|auto&ascend=0|1
-/// Returns list of all manga titles with optional sorting
+/// API route: GET /api/library?sort=title|modified|auto&ascend=0|1&filter=unread|reading|completed
+/// Returns list of all manga titles with optional sorting, the user's pinned titles first.
+/// Completed titles are left out when the user hides them (unless `include_completed=1`);
+/// how many were left out is sent in the X-Hidden-Completed header
 pub async fn get_library(
     State(state): State<AppState>,
     Query(params): Query<SortParams>,
+    Query(filter_params): Query<ProgressFilterParams>,
+    Username(username): Username,
+    session: Session,
 ) -> Result<impl IntoResponse> {
+    let hide = state
+        .storage
+        .get_user_preferences(&username)
+        .await?
+        .hide_completed;
+    let pins = state.storage.list_pins(&username).await?;
     let lib = state.library.load();
     let (sort_method, ascending) =
         SortMethod::from_params(params.sort.as_deref(), params.ascend.as_deref());
-    let titles = lib.get_titles_sorted(sort_method, ascending);
+    let mut titles = lib.get_titles_sorted(sort_method, ascending);
+    let hidden_completed = if filter_params.needs_progress(hide) {
+        filter_params.apply(&mut titles, hide, |t| lib.get_title_progress(t, &username))
+    } else {
+        0
+    };
+    let pinned = crate::util::pinned_first(&mut titles, &pins, |t| t.id.as_str());
+
+    // A title's cover is its first entry's thumbnail
+    let cover_ids: Vec<&str> = titles
+        .iter()
+        .filter_map(|t| t.entries.first().map(|e| e.id.as_str()))
+        .collect();
+    let covers = Entry::get_thumbnail_meta(&cover_ids, state.storage.pool()).await?;
+    let locks = TagLocks::load(&state, &session, &username).await?;
 
     let response: Vec<TitleInfo> = titles
         .iter()
-        .map(|t| TitleInfo {
+        .enumerate()
+        .map(|(i, t)| TitleInfo {
             id: t.id.clone(),
             title: t.title.clone(),
             entries: t.entries.len(),
             pages: t.total_pages(),
+            is_pinned: i < pinned,
+            cover: CoverInfo::for_title(t, &covers).hide_locked(&locks, &t.id),
         })
         .collect();
 
-    Ok(Json(response))
+    Ok((
+        [("X-Hidden-Completed", hidden_completed.to_string())],
+        Json(response),
+    ))
 }
 
-/// API route: GET /api/title/:id?sort=title|modified|auto&ascend=0|1
-/// Returns details of a specific manga title including all its entries with optional sorting
+/// API route: GET /api/library/index_letters?sort=title|auto&ascend=0|1
+/// A-Z jump bar `[{letter, count, offset}]` over the same cached sorted list the
+/// library page renders; without parameters the user's saved sort applies. Takes the
+/// library page's progress filters so offsets match the titles it shows; the user's
+/// pinned titles come first there, so they are left out and the offsets start after them
+pub async fn get_library_letters(
+    State(state): State<AppState>,
+    Query(params): Query<SortParams>,
+    Query(filter_params): Query<ProgressFilterParams>,
+    Username(username): Username,
+) -> Result<Json<Vec<LetterBucket>>> {
+    let hide = state
+        .storage
+        .get_user_preferences(&username)
+        .await?
+        .hide_completed;
+    let pins = state.storage.list_pins(&username).await?;
+    let lib = state.library.load();
+    let (sort_method, ascending) = if params.sort.is_some() {
+        SortMethod::from_params(params.sort.as_deref(), params.ascend.as_deref())
+    } else {
+        LibraryTitleInfo::load(lib.path())
+            .await?
+            .get_sort_by(&username)
+            .map(|(method, ascending)| (SortMethod::parse(&method), ascending))
+            .unwrap_or((SortMethod::Name, true))
+    };
+
+    // Other orders do not keep a letter's titles together
+    if !matches!(sort_method, SortMethod::Name | SortMethod::Auto) {
+        return Err(Error::BadRequest(
+            "The letter index needs the library sorted by name".to_string(),
+        ));
+    }
+
+    let mut titles = lib
+        .get_titles_sorted_cached(&username, sort_method, ascending)
+        .await;
+    if filter_params.needs_progress(hide) {
+        filter_params.apply(&mut titles, hide, |t| lib.get_title_progress(t, &username));
+    }
+    let pinned = crate::util::pinned_first(&mut titles, &pins, |t| t.id.as_str());
+
+    let names = titles[pinned..].iter().map(|t| t.title.as_str());
+    let mut buckets = letter_buckets(names);
+    for bucket in &mut buckets {
+        bucket.offset += pinned;
+    }
+    Ok(Json(buckets))
+}
+
+/// API route: GET /api/library/index
+/// Lightweight `[{id, name, entry_count}]` list for the quick-switcher, served from
+/// a per-snapshot cache; honors If-None-Match so clients can poll cheaply
+pub async fn get_library_index(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let index = state.library.load().index();
+
+    let cache_headers = [
+        (header::ETAG, index.etag.clone()),
+        (header::CACHE_CONTROL, "no-cache".to_string()),
+    ];
+
+    if etag_matches(&headers, &index.etag) {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+
+    (
+        cache_headers,
+        [(header::CONTENT_TYPE, "application/json")],
+        index.body.clone(),
+    )
+        .into_response()
+}
+
+/// API route: GET /api/title/:id?sort=title|modified|auto|opened&ascend=0|1
+/// Returns details of a specific manga title including all its entries with optional sorting,
+/// and the titles nested in it. `opened` orders entries by when the user last opened them
+/// Titles locked for the user (see `TagLocks`) are listed with the placeholder cover
 pub async fn get_title(
     State(state): State<AppState>,
     Path(title_id): Path<String>,
     Query(params): Query<SortParams>,
+    Username(username): Username,
+    session: Session,
 ) -> Result<impl IntoResponse> {
     let lib = state.library.load();
 
@@ -54,54 +170,155 @@ pub async fn get_title(
 
     let (sort_method, ascending) =
         SortMethod::from_params(params.sort.as_deref(), params.ascend.as_deref());
-    let entries: Vec<EntryInfo> = title
-        .get_entries_sorted(sort_method, ascending)
+
+    // Nested titles' covers are their first entries' thumbnails
+    let entry_ids: Vec<&str> = title
+        .entries
+        .iter()
+        .chain(title.nested_titles.iter().filter_map(|t| t.entries.first()))
+        .map(|e| e.id.as_str())
+        .collect();
+    let covers = Entry::get_thumbnail_meta(&entry_ids, state.storage.pool()).await?;
+    let aliases = state.storage.get_title_aliases(&title.id).await?;
+    let relations = state.storage.get_title_relations(&title.id).await?;
+    let locks = TagLocks::load(&state, &session, &username).await?;
+
+    let titles: Vec<TitleInfo> = title
+        .nested_titles
+        .iter()
+        .map(|t| TitleInfo {
+            id: t.id.clone(),
+            title: t.title.clone(),
+            entries: t.entries.len(),
+            pages: t.total_pages(),
+            // Only titles listed in the library are pinned
+            is_pinned: false,
+            cover: CoverInfo::for_title(t, &covers).hide_locked(&locks, &t.id),
+        })
+        .collect();
+
+    let entries: Vec<EntryInfo> = lib
+        .get_entries_sorted_for(title, &username, sort_method, ascending)
         .iter()
         .map(|e| EntryInfo {
             id: e.id.clone(),
             title: e.title.clone(),
+            display_title: e.display_title.clone(),
             pages: e.pages,
+            cover: CoverInfo::for_entry(&title.id, e, &covers).hide_locked(&locks, &title.id),
         })
         .collect();
 
     let response = TitleDetail {
         id: title.id.clone(),
         title: title.title.clone(),
+        cover: CoverInfo::for_title(title, &covers).hide_locked(&locks, &title.id),
+        custom_order: lib.get_entry_order(&title.id).is_some(),
+        aliases,
+        relations,
+        parent_id: title.parent_id.clone(),
+        titles,
         entries,
     };
 
     Ok(Json(response))
 }
 
-/// API route: GET /api/page/:tid/:eid/:page
+/// Query parameters for the search endpoint
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    #[serde(default)]
+    q: String,
+}
+
+/// API route: GET /api/search?q=
+/// Finds titles whose name or one of its aliases contains the query (case-insensitive)
+pub async fn search(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchResult>>> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+
+    let aliases = state.storage.list_title_aliases().await?;
+    let lib = state.library.load();
+
+    let results = lib
+        .get_titles()
+        .into_iter()
+        .filter_map(|t| {
+            let title_aliases = aliases.get(&t.id).map(Vec::as_slice).unwrap_or_default();
+            crate::util::match_title(q, &t.title, title_aliases).map(|matched_alias| SearchResult {
+                id: t.id.clone(),
+                title: t.title.clone(),
+                matched_alias: matched_alias.map(str::to_string),
+            })
+        })
+        .collect();
+
+    Ok(Json(results))
+}
+
+/// Query parameters for page and cover images
+#[derive(Deserialize)]
+pub struct ImageQuery {
+    /// Version token from a server-built URL (`paths::version_token`)
+    pub v: Option<String>,
+}
+
+/// API route: GET /api/page/:tid/:eid/:page?v=token
 /// Serves a specific page image from an entry
 pub async fn get_page(
     State(state): State<AppState>,
     Path((title_id, entry_id, page)): Path<(String, String, usize)>,
+    Query(query): Query<ImageQuery>,
+    crate::auth::Username(username): crate::auth::Username,
+    session: Session,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse> {
+    TagLocks::load(&state, &session, &username)
+        .await?
+        .check(&title_id)?;
     let lib = state.library.load();
 
     let entry = lib.get_entry(&title_id, &entry_id).ok_or_else(|| {
         crate::error::Error::NotFound(format!("Entry not found: {}/{}", title_id, entry_id))
     })?;
+    let immutable = query.v == Some(paths::page_version(entry));
 
     // Pages are 1-indexed in the API, but 0-indexed internally
     let page_idx = page.saturating_sub(1);
-    let image_data = entry.get_page(page_idx).await?;
+    // Identical requests arriving meanwhile wait for this extraction instead of repeating it
+    let image_data = match state
+        .page_flights
+        .get(&entry.id, page_idx, || {
+            state.archives.get_page(entry, page_idx)
+        })
+        .await
+    {
+        Ok(data) => data,
+        Err(e) => return Err(lib.page_unreadable(entry, page_idx, e).await),
+    };
+    state
+        .stats
+        .record_page_view(&username, &title_id, &entry_id);
 
     // Determine MIME type from image data
     let mime_type = guess_mime_type(&image_data);
 
-    Ok((
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, mime_type)],
-        image_data,
+    Ok(image_response(
+        &headers, mime_type, image_data, immutable, true,
     ))
 }
 
 /// API route: GET /api/stats
-/// Returns library statistics
-pub async fn get_stats(State(state): State<AppState>) -> Result<impl IntoResponse> {
+/// Returns library statistics and the current user's re-read total
+pub async fn get_stats(
+    State(state): State<AppState>,
+    crate::auth::Username(username): crate::auth::Username,
+) -> Result<impl IntoResponse> {
     let lib = state.library.load();
     let stats = lib.stats();
 
@@ -109,35 +326,381 @@ pub async fn get_stats(State(state): State<AppState>) -> Result<impl IntoRespons
         titles: stats.titles,
         entries: stats.entries,
         pages: stats.pages,
+        rereads: lib.progress_cache().get_total_rereads(&username),
     };
 
     Ok(Json(response))
 }
 
-/// GET /api/cover/:tid/:eid - Get manga entry cover/thumbnail
+/// Query parameters for the cover endpoint
+#[derive(Deserialize)]
+pub struct CoverQuery {
+    /// Maximum width in pixels (sent for Save-Data clients); larger covers are downscaled
+    pub w: Option<u32>,
+    /// Version token from a server-built URL (`paths::cover_version`)
+    pub v: Option<String>,
+}
+
+/// Smallest width the cover endpoint downscales to
+const MIN_COVER_WIDTH: u32 = 32;
+
+/// GET /api/cover/:tid/:eid?v=token&w=100 - Get manga entry cover/thumbnail
+/// Blurred for titles locked for the user (see `TagLocks`)
 pub async fn get_cover(
     State(state): State<AppState>,
     Path((title_id, entry_id)): Path<(String, String)>,
-) -> Result<impl IntoResponse> {
+    Query(query): Query<CoverQuery>,
+    Username(username): Username,
+    session: Session,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let (mime, data) = load_sized_cover(&state, &title_id, &entry_id, query.w).await?;
+    if TagLocks::load(&state, &session, &username)
+        .await?
+        .is_locked(&title_id)
+    {
+        return locked_cover_response(data, true).await;
+    }
+    let immutable = query.v == Some(current_cover_version(&state, &title_id, &entry_id).await?);
+    Ok(image_response(&headers, &mime, data, immutable, true))
+}
+
+/// API route: HEAD /api/cover/:tid/:eid?v=token&w=100
+/// Same headers as GET (Content-Type, Content-Length, ETag, Cache-Control) without the body
+pub async fn head_cover(
+    State(state): State<AppState>,
+    Path((title_id, entry_id)): Path<(String, String)>,
+    Query(query): Query<CoverQuery>,
+    Username(username): Username,
+    session: Session,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let (mime, data) = load_sized_cover(&state, &title_id, &entry_id, query.w).await?;
+    if TagLocks::load(&state, &session, &username)
+        .await?
+        .is_locked(&title_id)
+    {
+        return locked_cover_response(data, false).await;
+    }
+    let immutable = query.v == Some(current_cover_version(&state, &title_id, &entry_id).await?);
+    Ok(image_response(&headers, &mime, data, immutable, false))
+}
+
+/// Cover version tokens of `entries` by entry ID (see `paths::cover_version`)
+pub(crate) async fn cover_versions(
+    state: &AppState,
+    entries: &[&Entry],
+) -> Result<HashMap<String, String>> {
+    let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+    let covers = Entry::get_thumbnail_meta(&ids, state.storage.pool()).await?;
+    Ok(entries
+        .iter()
+        .map(|e| (e.id.clone(), paths::cover_version(e, covers.get(&e.id))))
+        .collect())
+}
+
+/// Version of a title's cover: its first entry's cover version from `versions`, or
+/// None when it shows a folder cover, which can change without a rescan
+pub(crate) fn title_cover_version<'a>(
+    title: &Title,
+    versions: &'a HashMap<String, String>,
+) -> Option<&'a str> {
+    if title.cover_path.is_some() {
+        return None;
+    }
+    let first = title.entries.first()?;
+    versions.get(&first.id).map(String::as_str)
+}
+
+/// URL of a title's cover, versioned by `versions` unless it shows a folder cover
+pub(crate) fn title_cover_url(
+    title: &Title,
+    versions: &HashMap<String, String>,
+    width: Option<u32>,
+) -> String {
+    paths::title_cover(
+        &title.id,
+        !title.entries.is_empty(),
+        title_cover_version(title, versions),
+        width,
+    )
+}
+
+/// Current cover version of an entry
+async fn current_cover_version(state: &AppState, title_id: &str, entry_id: &str) -> Result<String> {
+    let lib = state.library.load();
+    let entry = lib
+        .get_entry(title_id, entry_id)
+        .ok_or_else(|| Error::NotFound(format!("Entry not found: {}/{}", title_id, entry_id)))?;
+    let versions = cover_versions(state, &[entry]).await?;
+    Ok(versions.get(entry_id).cloned().unwrap_or_default())
+}
+
+/// Load an entry's cover, downscaled to at most `width` pixels wide if given
+async fn load_sized_cover(
+    state: &AppState,
+    title_id: &str,
+    entry_id: &str,
+    width: Option<u32>,
+) -> Result<(String, Vec<u8>)> {
+    let (mime, data) = load_cover(state, title_id, entry_id).await?;
+    fit_width(mime, data, width).await
+}
+
+/// Downscale a cover to at most `width` pixels wide if given
+async fn fit_width(mime: String, data: Vec<u8>, width: Option<u32>) -> Result<(String, Vec<u8>)> {
+    let Some(width) = width else {
+        return Ok((mime, data));
+    };
+
+    let width = width.max(MIN_COVER_WIDTH);
+    tokio::task::spawn_blocking(move || match downscale_to_width(&data, width) {
+        Some(smaller) => ("image/jpeg".to_string(), smaller),
+        None => (mime, data),
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Task join error: {}", e)))
+}
+
+/// GET /api/title/:id/cover?v=token&w=100 - Get a title's cover
+/// A cover image in the title directory (config `cover_file_names`) wins over the
+/// first entry's cover. Blurred for titles locked for the user (see `TagLocks`)
+pub async fn get_title_cover(
+    State(state): State<AppState>,
+    Path(title_id): Path<String>,
+    Query(query): Query<CoverQuery>,
+    Username(username): Username,
+    session: Session,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let (mime, data) = load_title_cover(&state, &title_id, query.w).await?;
+    if TagLocks::load(&state, &session, &username)
+        .await?
+        .is_locked(&title_id)
+    {
+        return locked_cover_response(data, true).await;
+    }
+    let immutable =
+        query.v.is_some() && query.v == current_title_cover_version(&state, &title_id).await?;
+    Ok(image_response(&headers, &mime, data, immutable, true))
+}
+
+/// API route: HEAD /api/title/:id/cover?v=token&w=100
+pub async fn head_title_cover(
+    State(state): State<AppState>,
+    Path(title_id): Path<String>,
+    Query(query): Query<CoverQuery>,
+    Username(username): Username,
+    session: Session,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let (mime, data) = load_title_cover(&state, &title_id, query.w).await?;
+    if TagLocks::load(&state, &session, &username)
+        .await?
+        .is_locked(&title_id)
+    {
+        return locked_cover_response(data, false).await;
+    }
+    let immutable =
+        query.v.is_some() && query.v == current_title_cover_version(&state, &title_id).await?;
+    Ok(image_response(&headers, &mime, data, immutable, false))
+}
+
+/// Current cover version of a title, None if it has none (see `title_cover_version`)
+async fn current_title_cover_version(state: &AppState, title_id: &str) -> Result<Option<String>> {
+    let lib = state.library.load();
+    let title = lib
+        .get_title(title_id)
+        .ok_or_else(|| Error::NotFound(format!("Title not found: {}", title_id)))?;
+    let versions = cover_versions(state, &title.entries.iter().take(1).collect::<Vec<_>>()).await?;
+    Ok(title_cover_version(title, &versions).map(str::to_string))
+}
+
+/// Load a title's cover: its folder cover if readable, else its first entry's cover
+async fn load_title_cover(
+    state: &AppState,
+    title_id: &str,
+    width: Option<u32>,
+) -> Result<(String, Vec<u8>)> {
+    let (cover_path, first_entry_id) = {
+        let lib = state.library.load();
+        let title = lib
+            .get_title(title_id)
+            .ok_or_else(|| Error::NotFound(format!("Title not found: {}", title_id)))?;
+        (
+            title.cover_path.clone(),
+            title.entries.first().map(|e| e.id.clone()),
+        )
+    };
+
+    if let Some(path) = cover_path {
+        if let Some(thumbnail) = state.folder_covers.thumbnail(&path).await? {
+            return fit_width("image/jpeg".to_string(), thumbnail.to_vec(), width).await;
+        }
+    }
+
+    let entry_id = first_entry_id
+        .ok_or_else(|| Error::NotFound(format!("Title has no cover: {}", title_id)))?;
+    load_sized_cover(state, title_id, &entry_id, width).await
+}
+
+/// Re-encode an image as JPEG at most `width` pixels wide
+/// None if it is already narrow enough or cannot be decoded
+fn downscale_to_width(data: &[u8], width: u32) -> Option<Vec<u8>> {
+    let img = image::load_from_memory(data).ok()?;
+    if img.width() <= width {
+        return None;
+    }
+
+    // JPEG has no alpha channel
+    let resized = img
+        .resize(width, u32::MAX, image::imageops::FilterType::Triangle)
+        .to_rgb8();
+    let mut buffer = Vec::new();
+    resized
+        .write_to(
+            &mut std::io::Cursor::new(&mut buffer),
+            image::ImageFormat::Jpeg,
+        )
+        .ok()?;
+    Some(buffer)
+}
+
+/// Size of the blurred stand-in for the cover of a locked title, in pixels
+const LOCKED_COVER_WIDTH: u32 = 200;
+const LOCKED_COVER_HEIGHT: u32 = 300;
+
+/// Blurred stand-in for a cover, which only keeps its colors
+/// Gray when the cover cannot be decoded
+fn blur_cover(data: &[u8]) -> Vec<u8> {
+    use image::imageops::FilterType;
+
+    // Shrunk to a few pixels and stretched back, nothing of the picture is left
+    let blurred = match image::load_from_memory(data) {
+        Ok(img) => img
+            .resize_exact(4, 6, FilterType::Triangle)
+            .resize_exact(
+                LOCKED_COVER_WIDTH,
+                LOCKED_COVER_HEIGHT,
+                FilterType::Triangle,
+            )
+            .to_rgb8(),
+        Err(_) => image::RgbImage::from_pixel(
+            LOCKED_COVER_WIDTH,
+            LOCKED_COVER_HEIGHT,
+            image::Rgb([128, 128, 128]),
+        ),
+    };
+    let mut buffer = Vec::new();
+    let _ = blurred.write_to(
+        &mut std::io::Cursor::new(&mut buffer),
+        image::ImageFormat::Jpeg,
+    );
+    buffer
+}
+
+/// Respond with the blurred cover of a locked title; never cached, so the real cover
+/// shows at the same URL once the title is unlocked
+async fn locked_cover_response(data: Vec<u8>, with_body: bool) -> Result<Response> {
+    let blurred = tokio::task::spawn_blocking(move || blur_cover(&data))
+        .await
+        .map_err(|e| Error::Internal(format!("Task join error: {}", e)))?;
+    let headers = [
+        (header::CONTENT_TYPE, "image/jpeg".to_string()),
+        (header::CONTENT_LENGTH, blurred.len().to_string()),
+        (header::CACHE_CONTROL, "no-store".to_string()),
+    ];
+    if with_body {
+        Ok((headers, blurred).into_response())
+    } else {
+        Ok(headers.into_response())
+    }
+}
+
+/// Response headers describing a cover image
+fn cover_headers(mime: &str, data: &[u8]) -> [(header::HeaderName, String); 3] {
+    [
+        (header::CONTENT_TYPE, mime.to_string()),
+        (header::CONTENT_LENGTH, data.len().to_string()),
+        (header::ETAG, format!("\"{:08x}\"", crc32fast::hash(data))),
+    ]
+}
+
+/// Cache-Control of image URLs naming the image's current version, which never change
+const IMMUTABLE_CACHE: &str = "public, max-age=31536000, immutable";
+
+/// Cache-Control of image URLs without the current version; clients revalidate with
+/// the ETag once it runs out
+const REVALIDATE_CACHE: &str = "public, max-age=300";
+
+/// Whether the request's If-None-Match lists `etag`
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            v.split(',')
+                .any(|tag| tag.trim() == etag || tag.trim() == "*")
+        })
+}
+
+/// Respond with a cover or page image: cached for good when the URL named its current
+/// version, briefly otherwise, and 304 when the client already has it
+fn image_response(
+    headers: &HeaderMap,
+    mime: &str,
+    data: Vec<u8>,
+    immutable: bool,
+    with_body: bool,
+) -> Response {
+    let cache_control = [(
+        header::CACHE_CONTROL,
+        if immutable {
+            IMMUTABLE_CACHE
+        } else {
+            REVALIDATE_CACHE
+        },
+    )];
+    let image_headers = cover_headers(mime, &data);
+    let etag = image_headers[2].1.clone();
+
+    if etag_matches(headers, &etag) {
+        return (
+            StatusCode::NOT_MODIFIED,
+            cache_control,
+            [(header::ETAG, etag)],
+        )
+            .into_response();
+    }
+    if with_body {
+        (image_headers, cache_control, data).into_response()
+    } else {
+        (image_headers, cache_control).into_response()
+    }
+}
+
+/// Load an entry's cover: stored thumbnail, freshly generated thumbnail, or first page
+/// Returns (mime_type, data)
+async fn load_cover(state: &AppState, title_id: &str, entry_id: &str) -> Result<(String, Vec<u8>)> {
     let lib = state.library.load();
 
     // Get entry
     let entry = lib
-        .get_entry(&title_id, &entry_id)
+        .get_entry(title_id, entry_id)
         .ok_or_else(|| Error::NotFound(format!("Entry not found: {}/{}", title_id, entry_id)))?;
 
     let db = state.storage.pool();
 
     // Try to get thumbnail first
-    match Entry::get_thumbnail(&entry_id, db).await {
-        Ok(Some((data, mime))) => {
-            return Ok(([(header::CONTENT_TYPE, mime.as_str())], data).into_response());
+    match Entry::get_thumbnail(entry_id, db).await {
+        Ok(Some(thumbnail)) => {
+            return Ok((thumbnail.mime, thumbnail.data));
         }
         Ok(None) => {
             // No thumbnail exists, try to generate one
-            match entry.generate_thumbnail(db).await {
+            match entry.generate_thumbnail(db, &state.archives).await {
                 Ok(Some((data, mime, _size))) => {
-                    return Ok(([(header::CONTENT_TYPE, mime.as_str())], data).into_response());
+                    return Ok((mime, data));
                 }
                 Ok(None) => {
                     tracing::warn!(
@@ -162,33 +725,113 @@ pub async fn get_cover(
     }
 
     // Fallback: return first page directly
-    let data = entry.get_page(0).await?;
+    let data = state.archives.get_page(entry, 0).await?;
     let mime = guess_mime_type(&data);
-    Ok(([(header::CONTENT_TYPE, mime)], data).into_response())
+    Ok((mime.to_string(), data))
 }
 
 // Response types
 
+/// Cover URL and metadata (null until a thumbnail with known dimensions exists)
+#[derive(Serialize)]
+struct CoverInfo {
+    cover_url: String,
+    cover_width: Option<u32>,
+    cover_height: Option<u32>,
+    cover_mime: Option<String>,
+}
+
+impl CoverInfo {
+    /// Cover of an entry, given the thumbnail metadata of its title's entries
+    fn for_entry(title_id: &str, entry: &Entry, covers: &HashMap<String, ThumbnailMeta>) -> Self {
+        let meta = covers.get(&entry.id);
+        Self {
+            cover_url: paths::cover(title_id, &entry.id, &paths::cover_version(entry, meta)),
+            cover_width: meta.and_then(|m| m.width),
+            cover_height: meta.and_then(|m| m.height),
+            cover_mime: meta.map(|m| m.mime.clone()),
+        }
+    }
+
+    /// Cover of a title (its first entry's thumbnail), given its thumbnail metadata
+    fn for_title(title: &Title, covers: &HashMap<String, ThumbnailMeta>) -> Self {
+        let first = title.entries.first();
+        let meta = first.and_then(|e| covers.get(&e.id));
+        let versions: HashMap<String, String> = first
+            .map(|e| (e.id.clone(), paths::cover_version(e, meta)))
+            .into_iter()
+            .collect();
+        Self {
+            cover_url: title_cover_url(title, &versions, None),
+            cover_width: meta.and_then(|m| m.width),
+            cover_height: meta.and_then(|m| m.height),
+            cover_mime: meta.map(|m| m.mime.clone()),
+        }
+    }
+
+    /// The placeholder in place of the cover of a title locked for the user
+    fn hide_locked(self, locks: &TagLocks, title_id: &str) -> Self {
+        if !locks.is_locked(title_id) {
+            return self;
+        }
+        Self {
+            cover_url: paths::placeholder_cover(),
+            cover_width: None,
+            cover_height: None,
+            cover_mime: None,
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct TitleInfo {
     id: String,
     title: String,
     entries: usize,
     pages: usize,
+    /// Pinned by the user to the top of the library
+    is_pinned: bool,
+    #[serde(flatten)]
+    cover: CoverInfo,
 }
 
 #[derive(Serialize)]
 struct TitleDetail {
     id: String,
     title: String,
+    #[serde(flatten)]
+    cover: CoverInfo,
+    /// True when an admin has pinned the entry order (sort params are ignored)
+    custom_order: bool,
+    /// Alternative names, primary alias first
+    aliases: Vec<String>,
+    /// Sequel chain and related titles, linked by admins
+    relations: crate::storage::TitleRelations,
+    /// Title this one is nested in (null for a top-level title)
+    parent_id: Option<String>,
+    /// Titles nested in this one, by name
+    titles: Vec<TitleInfo>,
     entries: Vec<EntryInfo>,
 }
 
+#[derive(Serialize)]
+pub struct SearchResult {
+    id: String,
+    title: String,
+    /// Alias that matched the query; null when the title name itself matched
+    matched_alias: Option<String>,
+}
+
 #[derive(Serialize)]
 struct EntryInfo {
     id: String,
+    /// File name, for sorting and matching
     title: String,
+    /// Name to show, see config `entry_title_cleanup`
+    display_title: String,
     pages: usize,
+    #[serde(flatten)]
+    cover: CoverInfo,
 }
 
 #[derive(Serialize)]
@@ -196,43 +839,66 @@ struct LibraryStats {
     titles: usize,
     entries: usize,
     pages: usize,
+    /// Entries the user read to the end again, counted once per extra read
+    rereads: u32,
 }
 
-/// API route: GET /api/library/continue_reading
+/// Query parameters for the continue reading endpoint
+#[derive(Deserialize)]
+pub struct ContinueReadingParams {
+    /// Show where the sequel starts in place of a title the user has finished
+    #[serde(default)]
+    follow_sequels: bool,
+}
+
+/// API route: GET /api/library/continue_reading?follow_sequels=true
 /// Returns the last 8 entries the user has read, sorted by last_read timestamp
+/// With follow_sequels, an entry of a finished title with a sequel is replaced by the
+/// first entry of the sequel the user has not finished
+/// Served from the recently read index, so no info.json is loaded
 pub async fn continue_reading(
     State(state): State<AppState>,
+    Query(params): Query<ContinueReadingParams>,
     crate::auth::Username(username): crate::auth::Username,
 ) -> Result<impl IntoResponse> {
     let lib = state.library.load();
-    let cache = lib.progress_cache();
-    let mut entries_with_progress = Vec::new();
+    let item = |title: &crate::library::Title, entry: &Entry, page: i32, last_read: i64| {
+        ContinueReadingEntry {
+            title_id: title.id.clone(),
+            title_name: title.title.clone(),
+            entry_id: entry.id.clone(),
+            entry_name: entry.display_title.clone(),
+            pages: entry.pages,
+            progress: page,
+            percentage: lib.entry_percentage(entry, page),
+            last_read,
+            sequel_of: None,
+        }
+    };
 
-    // Collect all entries with last_read timestamps (O(1) cache lookups instead of O(N) file reads)
-    for title in lib.get_titles_sorted(crate::library::SortMethod::Name, true) {
-        for entry in &title.entries {
-            if let Some(last_read) = cache.get_last_read(&title.id, &username, &entry.id) {
-                let progress = cache.get_progress(&title.id, &username, &entry.id).unwrap_or(0);
-                let percentage = calculate_progress_percentage(progress, entry.pages);
-
-                entries_with_progress.push(ContinueReadingEntry {
-                    title_id: title.id.clone(),
-                    title_name: title.title.clone(),
-                    entry_id: entry.id.clone(),
-                    entry_name: entry.title.clone(),
-                    pages: entry.pages,
-                    progress,
-                    percentage,
-                    last_read,
-                });
+    let mut entries_with_progress: Vec<ContinueReadingEntry> = Vec::new();
+    for read in lib.get_recently_read(&username, 8, |_, _| true).await? {
+        let mut next = item(read.title, read.entry, read.page, read.last_read);
+        if params.follow_sequels && lib.get_title_progress(read.title, &username) >= 100.0 {
+            if let Some((sequel, entry)) = lib.sequel_start(&read.title.id, Some(&username)).await?
+            {
+                let page = lib
+                    .progress_cache()
+                    .get_progress(&sequel.id, &username, &entry.id)
+                    .unwrap_or(0);
+                next = item(sequel, entry, page, read.last_read);
+                next.sequel_of = Some(read.title.id.clone());
             }
         }
+        // Finished titles of the same series can lead to the same sequel entry
+        if !entries_with_progress
+            .iter()
+            .any(|e| e.entry_id == next.entry_id)
+        {
+            entries_with_progress.push(next);
+        }
     }
 
-    // Sort by last_read (most recent first) and take top 8
-    entries_with_progress.sort_by(|a, b| b.last_read.cmp(&a.last_read));
-    entries_with_progress.truncate(8);
-
     Ok(Json(entries_with_progress))
 }
 
@@ -243,28 +909,11 @@ pub async fn start_reading(
     crate::auth::Username(username): crate::auth::Username,
 ) -> Result<impl IntoResponse> {
     let lib = state.library.load();
-    let cache = lib.progress_cache();
     let mut unread_titles = Vec::new();
 
     for title in lib.get_titles_sorted(crate::library::SortMethod::Name, true) {
         // Calculate title progress using cache (avoids filesystem reads)
-        let progress_pct = if title.entries.is_empty() {
-            0.0
-        } else {
-            let mut total_progress = 0.0;
-            for entry in &title.entries {
-                let page = cache
-                    .get_progress(&title.id, &username, &entry.id)
-                    .unwrap_or(0);
-                let pct = if entry.pages > 0 {
-                    (page as f32 / entry.pages as f32) * 100.0
-                } else {
-                    0.0
-                };
-                total_progress += pct;
-            }
-            total_progress / title.entries.len() as f32
-        };
+        let progress_pct = lib.get_title_progress(title, &username);
 
         if progress_pct == 0.0 {
             unread_titles.push(StartReadingTitle {
@@ -293,6 +942,7 @@ struct RecentEntryData {
     entry_name: String,
     pages: usize,
     percentage: f32,
+    /// Unix timestamp (UTC seconds)
     date_added: i64,
 }
 
@@ -305,7 +955,7 @@ pub async fn recently_added(
     let lib = state.library.load();
     let cache = lib.progress_cache();
     let mut entries_with_dates = Vec::new();
-    let one_month_ago = chrono::Utc::now().timestamp() - (30 * 24 * 60 * 60);
+    let one_month_ago = crate::util::time::now_ts() - (30 * 24 * 60 * 60);
 
     // Collect all entries with date_added within last month (O(1) cache lookups)
     for title in lib.get_titles_sorted(crate::library::SortMethod::Name, true) {
@@ -313,13 +963,13 @@ pub async fn recently_added(
             if let Some(date_added) = cache.get_date_added(&title.id, &entry.id) {
                 if date_added > one_month_ago {
                     let progress = cache.get_progress(&title.id, &username, &entry.id).unwrap_or(0);
-                    let percentage = calculate_progress_percentage(progress, entry.pages);
+                    let percentage = lib.entry_percentage(entry, progress);
 
                     entries_with_dates.push(RecentEntryData {
                         title_id: title.id.clone(),
                         title_name: title.title.clone(),
                         entry_id: entry.id.clone(),
-                        entry_name: entry.title.clone(),
+                        entry_name: entry.display_title.clone(),
                         pages: entry.pages,
                         percentage,
                         date_added,
@@ -380,7 +1030,11 @@ struct ContinueReadingEntry {
     pages: usize,
     progress: i32,
     percentage: f32, // Progress percentage (0.0 - 100.0)
+    /// Unix timestamp (UTC seconds)
     last_read: i64,
+    /// The finished title whose sequel starts at this entry (with follow_sequels)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sequel_of: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -400,6 +1054,7 @@ struct RecentlyAddedEntry {
     pages: usize,
     percentage: f32, // Progress percentage (0.0 - 100.0)
     grouped_count: usize,
+    /// Unix timestamp (UTC seconds)
     date_added: i64,
 }
 
@@ -501,8 +1156,15 @@ pub async fn delete_tag(
 pub async fn download_entry(
     State(state): State<AppState>,
     Path((title_id, entry_id)): Path<(String, String)>,
-    _username: crate::auth::Username,
+    crate::auth::Username(username): crate::auth::Username,
+    session: Session,
 ) -> Result<impl IntoResponse> {
+    TagLocks::load(&state, &session, &username)
+        .await?
+        .check(&title_id)?;
+    // Refuse before touching the file if today's quota is used up
+    state.quota.check(&username).await?;
+
     let lib = state.library.load();
 
     // Get entry
@@ -510,21 +1172,39 @@ pub async fn download_entry(
         .get_entry(&title_id, &entry_id)
         .ok_or_else(|| Error::NotFound(format!("Entry not found: {}/{}", title_id, entry_id)))?;
 
-    // Read the archive file
-    let file_data = tokio::fs::read(&entry.path).await.map_err(|e| {
+    // A merged entry is several archives; they are downloaded one by one after unmerging
+    if !entry.parts.is_empty() {
+        return Err(Error::BadRequest(format!(
+            "Entry {} is merged from {} archives and cannot be downloaded as one file",
+            entry_id,
+            entry.parts.len()
+        )));
+    }
+
+    // A directory entry has no archive to hand out
+    if entry.source == EntrySource::Directory {
+        return Err(Error::BadRequest(format!(
+            "Entry {} is a directory of images and cannot be downloaded as one file",
+            entry_id
+        )));
+    }
+
+    // Open the archive file (streamed, not buffered into memory)
+    let file = tokio::fs::File::open(&entry.path).await.map_err(|e| {
         Error::Internal(format!(
-            "Failed to read file {}: {}",
-            entry.path.display(),
+            "Failed to open file {}: {}",
+            entry.relative_path(lib.path()),
             e
         ))
     })?;
+    let file_len = file.metadata().await?.len();
+    state.stats.record_download(&title_id);
 
-    // Determine MIME type from file extension
-    let mime_type = match entry.path.extension().and_then(|e| e.to_str()) {
-        Some("cbz") | Some("zip") => "application/zip",
-        Some("cbr") | Some("rar") => "application/x-rar-compressed",
-        _ => "application/octet-stream",
-    };
+    // Determine MIME type from the archive header, not just the extension
+    let path = entry.path.clone();
+    let mime_type = tokio::task::spawn_blocking(move || ArchiveKind::detect(&path).mime_type())
+        .await
+        .map_err(|e| Error::Internal(format!("Task join error: {}", e)))?;
 
     // Get filename
     let filename = entry
@@ -536,31 +1216,435 @@ pub async fn download_entry(
     // Set headers for file download
     let content_disposition = format!("attachment; filename=\"{}\"", filename);
 
+    // Bytes are counted against the quota as the body is streamed out
+    let reader = state.quota.counting_reader(username, file);
+    let body = axum::body::Body::from_stream(tokio_util::io::ReaderStream::new(reader));
+
     Ok((
         [
-            (header::CONTENT_TYPE, mime_type),
-            (header::CONTENT_DISPOSITION, content_disposition.as_str()),
+            (header::CONTENT_TYPE, mime_type.to_string()),
+            (header::CONTENT_DISPOSITION, content_disposition),
+            (header::CONTENT_LENGTH, file_len.to_string()),
         ],
-        file_data,
+        body,
     )
         .into_response())
 }
 
-/// Guess MIME type from image data magic bytes
-fn guess_mime_type(data: &[u8]) -> &'static str {
-    if data.len() < 4 {
-        return "application/octet-stream";
+#[derive(Serialize)]
+struct QuotaResponse {
+    day: String,
+    used_bytes: u64,
+    quota_bytes: Option<u64>,
+    remaining_bytes: Option<u64>,
+}
+
+/// API route: GET /api/user/quota
+/// Returns the current user's download usage for today (UTC) and remaining quota
+pub async fn get_quota(
+    State(state): State<AppState>,
+    crate::auth::Username(username): crate::auth::Username,
+) -> Result<impl IntoResponse> {
+    let used_bytes = state.quota.usage_today(&username).await?;
+    let quota_bytes = state.quota.limit_bytes();
+
+    Ok(success_response(QuotaResponse {
+        day: crate::quota::today(),
+        used_bytes,
+        quota_bytes,
+        remaining_bytes: quota_bytes.map(|limit| limit.saturating_sub(used_bytes)),
+    }))
+}
+
+// ========== Display Preferences ==========
+
+/// Body of PUT /api/user/preferences; a missing or null field resets it to the instance default
+#[derive(Deserialize)]
+pub struct PreferencesRequest {
+    #[serde(default)]
+    page_size: Option<usize>,
+    #[serde(default)]
+    card_density: Option<CardDensity>,
+}
+
+/// Body of PUT /api/user/preferences/hide-completed
+#[derive(Deserialize)]
+pub struct HideCompletedRequest {
+    hide_completed: bool,
+}
+
+#[derive(Serialize)]
+struct PreferencesResponse {
+    /// The user's own choices (null = instance default)
+    page_size: Option<usize>,
+    card_density: Option<CardDensity>,
+    hide_completed: bool,
+    /// The values in effect after falling back and clamping
+    effective_page_size: usize,
+    effective_card_density: CardDensity,
+    default_page_size: usize,
+    default_card_density: CardDensity,
+    max_page_size: usize,
+}
+
+impl PreferencesResponse {
+    fn new(preferences: UserPreferences, config: &crate::Config) -> Self {
+        Self {
+            page_size: preferences.page_size,
+            card_density: preferences.card_density,
+            hide_completed: preferences.hide_completed,
+            effective_page_size: preferences.page_size(config),
+            effective_card_density: preferences.card_density(config),
+            default_page_size: config.library_page_size,
+            default_card_density: config.library_card_density,
+            max_page_size: config.library_max_page_size,
+        }
+    }
+}
+
+/// API route: GET /api/user/preferences
+/// Returns the current user's library display preferences and the instance defaults
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<impl IntoResponse> {
+    let preferences = state.storage.get_user_preferences(&username).await?;
+    Ok(success_response(PreferencesResponse::new(
+        preferences,
+        &state.config,
+    )))
+}
+
+/// API route: PUT /api/user/preferences
+/// Saves the current user's library display preferences; page sizes above the maximum are clamped
+pub async fn set_preferences(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Json(request): Json<PreferencesRequest>,
+) -> Result<impl IntoResponse> {
+    if request.page_size == Some(0) {
+        return Err(Error::BadRequest(
+            "page_size must be at least 1".to_string(),
+        ));
+    }
+
+    let preferences = UserPreferences {
+        page_size: request
+            .page_size
+            .map(|size| size.min(state.config.library_max_page_size)),
+        card_density: request.card_density,
+        ..Default::default()
+    };
+    state
+        .storage
+        .set_user_preferences(&username, &preferences)
+        .await?;
+
+    // Reloaded so the response carries the hide_completed setting left untouched
+    let preferences = state.storage.get_user_preferences(&username).await?;
+    Ok(success_response(PreferencesResponse::new(
+        preferences,
+        &state.config,
+    )))
+}
+
+/// API route: PUT /api/user/preferences/hide-completed
+/// Sets whether the library leaves out the current user's fully read titles
+pub async fn set_hide_completed(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Json(request): Json<HideCompletedRequest>,
+) -> Result<impl IntoResponse> {
+    state
+        .storage
+        .set_hide_completed(&username, request.hide_completed)
+        .await?;
+
+    let preferences = state.storage.get_user_preferences(&username).await?;
+    Ok(success_response(PreferencesResponse::new(
+        preferences,
+        &state.config,
+    )))
+}
+
+// ========== Title Pins ==========
+
+#[derive(Serialize)]
+struct PinsResponse {
+    /// Title IDs in the order they were pinned
+    pins: Vec<String>,
+}
+
+/// API route: GET /api/pins
+/// The current user's pinned titles, listed first in the library whatever the sort
+pub async fn list_pins(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<impl IntoResponse> {
+    let pins = state.storage.list_pins(&username).await?;
+    Ok(success_response(PinsResponse { pins }))
+}
+
+/// API route: PUT /api/pins/:tid
+/// Pins a title to the top of the current user's library, after the titles pinned before
+/// Returns: 400 for nested titles, which are not listed in the library
+pub async fn pin_title(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Path(title_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    {
+        let lib = state.library.load();
+        if !lib.titles().contains_key(&title_id) {
+            return Err(match lib.get_title(&title_id) {
+                Some(_) => {
+                    Error::BadRequest("Only titles listed in the library can be pinned".to_string())
+                }
+                None => Error::NotFound(format!("Title not found: {}", title_id)),
+            });
+        }
+    }
+
+    state.storage.pin_title(&username, &title_id).await?;
+    let pins = state.storage.list_pins(&username).await?;
+    Ok(success_response(PinsResponse { pins }))
+}
+
+/// API route: DELETE /api/pins/:tid
+/// Unpins a title; unpinning a title that is not pinned changes nothing
+pub async fn unpin_title(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Path(title_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    state.storage.unpin_title(&username, &title_id).await?;
+    let pins = state.storage.list_pins(&username).await?;
+    Ok(success_response(PinsResponse { pins }))
+}
+
+// ========== Collections ==========
+
+/// Maximum length of a collection name, in characters
+const MAX_COLLECTION_NAME_CHARS: usize = 100;
+
+#[derive(Deserialize)]
+pub struct CollectionRequest {
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct CollectionOrderRequest {
+    title_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct CollectionsResponse {
+    collections: Vec<crate::storage::Collection>,
+}
+
+/// A collection name with surrounding whitespace trimmed
+/// Returns: 400 when it is empty or too long
+fn collection_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_COLLECTION_NAME_CHARS {
+        return Err(Error::BadRequest(format!(
+            "Collection name must be 1 to {} characters",
+            MAX_COLLECTION_NAME_CHARS
+        )));
+    }
+    Ok(name)
+}
+
+/// One of the user's collections
+/// Returns: 404 when it does not exist or belongs to another user
+async fn owned_collection(
+    state: &AppState,
+    username: &str,
+    id: &str,
+) -> Result<crate::storage::Collection> {
+    state
+        .storage
+        .get_collection(username, id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Collection not found: {}", id)))
+}
+
+/// API route: GET /api/collections
+/// The current user's collections, oldest first, each with its title IDs in order
+pub async fn list_collections(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<impl IntoResponse> {
+    let collections = state.storage.list_collections(&username).await?;
+    Ok(success_response(CollectionsResponse { collections }))
+}
+
+/// API route: POST /api/collections
+/// Creates an empty collection
+/// Returns: 409 if the user already has a collection of that name
+pub async fn create_collection(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Json(request): Json<CollectionRequest>,
+) -> Result<impl IntoResponse> {
+    let name = collection_name(&request.name)?;
+    let collection = state.storage.create_collection(&username, name).await?;
+    Ok((StatusCode::CREATED, success_response(collection)))
+}
+
+/// API route: GET /api/collections/:id
+pub async fn get_collection(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let collection = owned_collection(&state, &username, &id).await?;
+    Ok(success_response(collection))
+}
+
+/// API route: PUT /api/collections/:id
+/// Renames a collection
+/// Returns: 409 if the user already has a collection of that name
+pub async fn rename_collection(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Path(id): Path<String>,
+    Json(request): Json<CollectionRequest>,
+) -> Result<impl IntoResponse> {
+    let name = collection_name(&request.name)?;
+    if !state
+        .storage
+        .rename_collection(&username, &id, name)
+        .await?
+    {
+        return Err(Error::NotFound(format!("Collection not found: {}", id)));
+    }
+    let collection = owned_collection(&state, &username, &id).await?;
+    Ok(success_response(collection))
+}
+
+/// API route: DELETE /api/collections/:id
+/// Deletes a collection; its titles stay in the library
+pub async fn delete_collection(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Path(id): Path<String>,
+) -> Result<StatusCode> {
+    if !state.storage.delete_collection(&username, &id).await? {
+        return Err(Error::NotFound(format!("Collection not found: {}", id)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// API route: PUT /api/collections/:id/titles/:tid
+/// Adds a title to the end of a collection; a title already in it keeps its place
+pub async fn add_collection_title(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Path((id, title_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse> {
+    let collection = owned_collection(&state, &username, &id).await?;
+    if state.library.load().get_title(&title_id).is_none() {
+        return Err(Error::NotFound(format!("Title not found: {}", title_id)));
+    }
+
+    state
+        .storage
+        .add_to_collection(&collection.id, &title_id)
+        .await?;
+    let collection = owned_collection(&state, &username, &id).await?;
+    Ok(success_response(collection))
+}
+
+/// API route: DELETE /api/collections/:id/titles/:tid
+/// Removes a title from a collection; removing a title that is not in it changes nothing
+pub async fn remove_collection_title(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Path((id, title_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse> {
+    let collection = owned_collection(&state, &username, &id).await?;
+    state
+        .storage
+        .remove_from_collection(&collection.id, &title_id)
+        .await?;
+    let collection = owned_collection(&state, &username, &id).await?;
+    Ok(success_response(collection))
+}
+
+/// API route: PUT /api/collections/:id/order
+/// Puts the titles of a collection in the order given
+/// Returns: 400 unless `title_ids` lists every title of the collection exactly once
+pub async fn set_collection_order(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Path(id): Path<String>,
+    Json(request): Json<CollectionOrderRequest>,
+) -> Result<impl IntoResponse> {
+    let collection = owned_collection(&state, &username, &id).await?;
+    state
+        .storage
+        .reorder_collection(&collection.id, &request.title_ids)
+        .await?;
+    let collection = owned_collection(&state, &username, &id).await?;
+    Ok(success_response(collection))
+}
+
+// ========== Problem Reports ==========
+
+/// Maximum length of a report note, in characters
+pub(crate) const MAX_REPORT_NOTE_CHARS: usize = 1000;
+
+#[derive(Deserialize)]
+pub struct ReportRequest {
+    category: ReportCategory,
+    #[serde(default)]
+    note: String,
+}
+
+#[derive(Serialize)]
+struct ReportResponse {
+    id: i64,
+}
+
+/// API route: POST /api/report/:tid/:eid
+/// Report a problem with an entry (corrupt pages, wrong order, ...) to the admins
+pub async fn report_entry(
+    State(state): State<AppState>,
+    Path((title_id, entry_id)): Path<(String, String)>,
+    crate::auth::Username(username): crate::auth::Username,
+    Json(request): Json<ReportRequest>,
+) -> Result<impl IntoResponse> {
+    let lib = state.library.load();
+    if lib.get_entry(&title_id, &entry_id).is_none() {
+        return Err(Error::NotFound(format!(
+            "Entry not found: {}/{}",
+            title_id, entry_id
+        )));
     }
+    drop(lib);
 
-    // Check magic bytes
-    match &data[0..4] {
-        [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
-        [0x89, 0x50, 0x4E, 0x47] => "image/png",
-        [0x47, 0x49, 0x46, 0x38] => "image/gif",
-        [0x52, 0x49, 0x46, 0x46] => "image/webp", // RIFF header (WebP)
-        [0x42, 0x4D, ..] => "image/bmp",
-        _ => "application/octet-stream",
+    let note = request.note.trim();
+    if note.chars().count() > MAX_REPORT_NOTE_CHARS {
+        return Err(Error::BadRequest(format!(
+            "Note is too long (max {} characters)",
+            MAX_REPORT_NOTE_CHARS
+        )));
     }
+
+    let id = state
+        .storage
+        .create_report(&title_id, &entry_id, &username, request.category, note)
+        .await?;
+
+    tracing::info!(
+        "User {} reported entry {} as {}",
+        username,
+        entry_id,
+        request.category.as_str()
+    );
+
+    Ok(success_response(ReportResponse { id }))
 }
 
 // ========== Dimensions API (for reader) ==========
@@ -571,6 +1655,9 @@ struct PageDimension {
     height: u32,
     #[serde(skip_serializing_if = "std::ops::Not::not")]
     estimated: bool,
+    /// Page failed validation (zero-byte, truncated or not an image)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    bad: bool,
 }
 
 #[derive(Serialize)]
@@ -580,11 +1667,16 @@ struct DimensionsResponse {
 
 /// API route: GET /api/dimensions/:tid/:eid
 /// Returns the image dimensions of all pages in an entry (used by reader for layout)
+/// Pages that failed validation are flagged with `bad`
 pub async fn get_dimensions(
     State(state): State<AppState>,
     Path((title_id, entry_id)): Path<(String, String)>,
-    _username: crate::auth::Username,
+    Username(username): Username,
+    session: Session,
 ) -> Result<impl IntoResponse> {
+    TagLocks::load(&state, &session, &username)
+        .await?
+        .check(&title_id)?;
     let lib = state.library.load();
 
     let entry = lib.get_entry(&title_id, &entry_id).ok_or_else(|| {
@@ -592,6 +1684,18 @@ pub async fn get_dimensions(
     })?;
     let entry_pages = entry.pages;
     let entry_clone = entry.clone();
+
+    // Validate pages on first access (a no-op once the entry has been checked)
+    let bad_pages = if state.config.validate_pages {
+        lib.check_entry_pages(&entry_clone, &state.archives)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to validate pages of entry {}: {}", entry_id, e);
+                lib.get_bad_pages(&entry_id)
+            })
+    } else {
+        lib.get_bad_pages(&entry_id)
+    };
     drop(lib); // Release library lock early
 
     // Check database cache first
@@ -604,6 +1708,7 @@ pub async fn get_dimensions(
                     width: d.width,
                     height: d.height,
                     estimated: false,
+                    bad: bad_pages.contains(&(d.page_num + 1)),
                 })
                 .collect();
             return Ok(success_response(DimensionsResponse { dimensions }));
@@ -636,7 +1741,7 @@ pub async fn get_dimensions(
     let mut dims_to_cache = Vec::with_capacity(entry_pages);
 
     for page_idx in 0..entry_pages {
-        match entry_clone.get_page(page_idx).await {
+        match state.archives.get_page(&entry_clone, page_idx).await {
             Ok(data) => {
                 let (width, height, estimated) = match get_image_dimensions(&data) {
                     Some((w, h)) => (w, h, false),
@@ -649,7 +1754,12 @@ pub async fn get_dimensions(
                         (1000, 1000, true)
                     }
                 };
-                dimensions.push(PageDimension { width, height, estimated });
+                dimensions.push(PageDimension {
+                    width,
+                    height,
+                    estimated,
+                    bad: bad_pages.contains(&(page_idx + 1)),
+                });
                 // Only cache actual dimensions, not estimated ones
                 if !estimated {
                     dims_to_cache.push((page_idx, width, height));
@@ -666,6 +1776,7 @@ pub async fn get_dimensions(
                     width: 1000,
                     height: 1000,
                     estimated: true,
+                    bad: bad_pages.contains(&(page_idx + 1)),
                 });
             }
         }
@@ -681,17 +1792,82 @@ pub async fn get_dimensions(
     Ok(success_response(DimensionsResponse { dimensions }))
 }
 
-/// Get image dimensions from raw image data
-fn get_image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
-    // Try to use image crate to get dimensions without full decode
-    use std::io::Cursor;
+// ========== Page Thumbnails API (reader scrub bar) ==========
 
-    let reader = image::ImageReader::new(Cursor::new(data))
-        .with_guessed_format()
-        .ok()?;
+#[derive(Serialize)]
+struct PageThumbnailsResponse {
+    sprite_url: String,
+    /// Frame height in pixels
+    height: u32,
+    /// Every step-th page has a frame (pages in between share the previous frame)
+    step: usize,
+    frames: Vec<SpriteFrame>,
+}
+
+/// API route: GET /api/entry/:tid/:eid/page_thumbnails
+/// Returns the frame offsets of the entry's page sprite (generated on first request)
+pub async fn get_page_thumbnails(
+    State(state): State<AppState>,
+    Path((title_id, entry_id)): Path<(String, String)>,
+    Username(username): Username,
+    session: Session,
+) -> Result<impl IntoResponse> {
+    TagLocks::load(&state, &session, &username)
+        .await?
+        .check(&title_id)?;
+    let sprite = load_page_sprite(&state, &title_id, &entry_id).await?;
+
+    Ok(success_response(PageThumbnailsResponse {
+        sprite_url: crate::routes::paths::api_page_thumbnails_sprite(&title_id, &entry_id),
+        height: sprite.height,
+        step: sprite.step,
+        frames: sprite.frames,
+    }))
+}
+
+/// API route: GET /api/entry/:tid/:eid/page_thumbnails/sprite
+/// Returns the entry's page sprite image (generated on first request)
+pub async fn get_page_thumbnails_sprite(
+    State(state): State<AppState>,
+    Path((title_id, entry_id)): Path<(String, String)>,
+    Username(username): Username,
+    session: Session,
+) -> Result<impl IntoResponse> {
+    TagLocks::load(&state, &session, &username)
+        .await?
+        .check(&title_id)?;
+    let sprite = load_page_sprite(&state, &title_id, &entry_id).await?;
+    Ok((cover_headers(&sprite.mime, &sprite.data), sprite.data).into_response())
+}
 
-    let dims = reader.into_dimensions().ok()?;
-    Some(dims)
+/// Load an entry's page sprite, generating and storing it if missing or stale
+/// Generation runs inside the request, so a client disconnect cancels it
+async fn load_page_sprite(state: &AppState, title_id: &str, entry_id: &str) -> Result<PageSprite> {
+    let entry = {
+        let lib = state.library.load();
+        lib.get_entry(title_id, entry_id)
+            .ok_or_else(|| Error::NotFound(format!("Entry not found: {}/{}", title_id, entry_id)))?
+            .clone()
+    };
+
+    match state.storage.get_page_sprite(entry_id).await {
+        Ok(Some(sprite)) if sprite.signature == entry.signature => return Ok(sprite),
+        Ok(_) => {}
+        Err(e) => {
+            tracing::warn!("Failed to read page sprite of entry {}: {}", entry_id, e);
+        }
+    }
+
+    let sprite = entry
+        .generate_page_sprite(&state.archives)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("No readable pages in entry: {}", entry_id)))?;
+
+    if let Err(e) = state.storage.save_page_sprite(&sprite).await {
+        tracing::warn!("Failed to store page sprite of entry {}: {}", entry_id, e);
+    }
+
+    Ok(sprite)
 }
 
 // ========== Progress API ==========
@@ -708,14 +1884,14 @@ pub async fn update_progress(
     State(state): State<AppState>,
     Path((title_id, page)): Path<(String, usize)>,
     Query(query): Query<ProgressQuery>,
-    crate::auth::Username(username): crate::auth::Username,
+    crate::auth::Writer(username): crate::auth::Writer,
 ) -> Result<impl IntoResponse> {
     let entry_id = query.eid.ok_or_else(|| {
         Error::BadRequest("Missing 'eid' query parameter".to_string())
     })?;
 
     let lib = state.library.load();
-    let title = lib
+    let _title = lib
         .get_title(&title_id)
         .ok_or_else(|| Error::NotFound(format!("Title not found: {}", title_id)))?;
 
@@ -724,9 +1900,8 @@ pub async fn update_progress(
         .get_entry(&title_id, &entry_id)
         .ok_or_else(|| Error::NotFound(format!("Entry not found: {}", entry_id)))?;
 
-    // Save progress via cache (updates cache and persists to disk)
-    lib.progress_cache()
-        .save_progress(&title_id, &title.path, &username, &entry_id, page as i32)
+    // Save progress (updates cache, persists to disk and updates the recently read index)
+    lib.save_progress(&title_id, &username, &entry_id, page as i32)
         .await?;
 
     // Invalidate response cache
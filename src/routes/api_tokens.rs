@@ -0,0 +1,81 @@
+// API tokens - bearer tokens for scripts and third-party clients
+//
+// Any user can create named tokens (POST /api/user/tokens) and send one as
+// `Authorization: Bearer <token>` to call /api/ endpoints as themselves, without a
+// session. Only a hash is stored, so the token is shown once, when it is created.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::Username,
+    error::{Error, Result},
+    storage::ApiToken,
+    totp, AppState,
+};
+
+/// Longest accepted token name, in characters
+const MAX_TOKEN_NAME_CHARS: usize = 100;
+
+#[derive(Deserialize)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct CreateApiTokenResponse {
+    pub id: String,
+    /// Shown only this once
+    pub token: String,
+}
+
+/// GET /api/user/tokens - The current user's API tokens, without the tokens themselves
+pub async fn list_api_tokens(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<Json<Vec<ApiToken>>> {
+    Ok(Json(state.storage.list_api_tokens(&username).await?))
+}
+
+/// POST /api/user/tokens - Create an API token `{"name": "Backup script"}`
+pub async fn create_api_token(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Json(request): Json<CreateApiTokenRequest>,
+) -> Result<(StatusCode, Json<CreateApiTokenResponse>)> {
+    let name = request.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_TOKEN_NAME_CHARS {
+        return Err(Error::BadRequest(format!(
+            "Name must be 1 to {} characters",
+            MAX_TOKEN_NAME_CHARS
+        )));
+    }
+
+    let token = totp::generate_app_token();
+    let id = state
+        .storage
+        .create_api_token(&username, name, &totp::hash_secret(&token))
+        .await?;
+    tracing::info!("User {} created API token {}", username, name);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateApiTokenResponse { id, token }),
+    ))
+}
+
+/// DELETE /api/user/tokens/:id - Revoke an API token
+pub async fn delete_api_token(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Path(id): Path<String>,
+) -> Result<StatusCode> {
+    if !state.storage.delete_api_token(&username, &id).await? {
+        return Err(Error::NotFound(format!("API token not found: {}", id)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,16 +1,17 @@
 use askama::Template;
 use axum::{
     extract::{Path, Query, State},
-    response::Html,
+    response::{Html, IntoResponse, Response},
 };
 use serde::Deserialize;
+use tower_sessions::Session;
 
-use super::{sort_by_progress, HasProgress};
+use super::{paths, sort_by_progress, tag_locks, HasProgress, TagLocks};
 use crate::{
     auth::User,
     error::{Error, Result},
-    library::SortMethod,
-    util::render_error,
+    library::{Entry, SortMethod},
+    util::{render_error, BaseContext, ClientHints, NavPage},
     AppState,
 };
 
@@ -43,6 +44,16 @@ impl SortOption {
 struct ParentItem {
     id: String,
     display_name: String,
+    url: String,
+}
+
+/// Link to a sequel, prequel or related title
+#[derive(serde::Serialize)]
+struct RelatedLink {
+    /// "Sequel of", "Continued in" or "Related"
+    label: &'static str,
+    display_name: String,
+    url: String,
 }
 
 /// Title info for the page header and edit modal
@@ -55,6 +66,36 @@ struct TitleInfo {
     cover_url: String,
     content_label: String,
     parents: Vec<ParentItem>,
+    /// True when an admin has pinned the entry order
+    custom_order: bool,
+    /// Reading direction override label (e.g. "RTL"), if the title has one
+    direction: Option<&'static str>,
+    /// First of the title's aliases, shown as a subtitle
+    primary_alias: Option<String>,
+    /// Linked titles that are in the library
+    relations: Vec<RelatedLink>,
+}
+
+/// Links to the linked titles that are in the library, the prequel first
+fn related_links(
+    lib: &crate::library::Library,
+    relations: &crate::storage::TitleRelations,
+) -> Vec<RelatedLink> {
+    let linked = relations
+        .sequel_of
+        .iter()
+        .map(|id| ("Sequel of", id))
+        .chain(relations.prequel_of.iter().map(|id| ("Continued in", id)))
+        .chain(relations.related.iter().map(|id| ("Related", id)));
+    linked
+        .filter_map(|(label, id)| {
+            lib.get_title(id).map(|title| RelatedLink {
+                label,
+                display_name: title.title.clone(),
+                url: paths::book(&title.id),
+            })
+        })
+        .collect()
 }
 
 /// Card item for the book page - unified structure for entries and nested titles
@@ -66,10 +107,12 @@ struct BookCardItem {
     is_entry: bool,
     display_name: String,
     cover_url: String,
+    url: String, // Reader for entries, book page for titles
 
     // Entry-specific fields (used when is_entry = true)
     book_id: String,
     book_display_name: String,
+    book_url: String,
     pages: usize,
     encoded_path: String,
     encoded_title: String,
@@ -82,26 +125,38 @@ struct BookCardItem {
     // Optional metadata
     title: Option<String>,
     sort_title: Option<String>,
+    /// Title aliases joined by newlines, matched by the search box (empty for entries)
+    aliases: String,
+    /// Times the user finished the entry (0 for titles)
+    read_count: u32,
+    /// Always false; only library titles collect the root's archives
+    unsorted: bool,
+    /// Always false; only library titles are pinned
+    pinned: bool,
 }
 
 impl BookCardItem {
     /// Create a card item for an entry
+    /// It shows the cleaned-up name; the file name stays available for sorting and renaming
     fn from_entry(
-        entry_id: &str,
-        entry_title: &str,
+        entry: &Entry,
         book_id: &str,
         book_title: &str,
-        pages: usize,
         entry_path: &str,
+        cover_version: &str,
+        cover_width: Option<u32>,
     ) -> Self {
+        let entry_title = entry.title.as_str();
         Self {
-            id: entry_id.to_string(),
+            id: entry.id.clone(),
             is_entry: true,
-            display_name: entry_title.to_string(),
-            cover_url: format!("/api/cover/{}/{}", book_id, entry_id),
+            display_name: entry.display_title.clone(),
+            cover_url: paths::cover_sized(book_id, &entry.id, cover_version, cover_width),
+            url: paths::reader_continue(book_id, &entry.id),
             book_id: book_id.to_string(),
             book_display_name: book_title.to_string(),
-            pages,
+            book_url: paths::book(book_id),
+            pages: entry.pages,
             encoded_path: percent_encoding::percent_encode(
                 entry_path.as_bytes(),
                 percent_encoding::NON_ALPHANUMERIC,
@@ -121,34 +176,30 @@ impl BookCardItem {
             content_label: String::new(),
             title: Some(entry_title.to_string()),
             sort_title: None,
+            aliases: String::new(),
+            read_count: 0,
+            unsorted: false,
+            pinned: false,
         }
     }
 
     /// Create a card item for a nested title
-    fn from_title(
-        title_id: &str,
-        title_name: &str,
-        entry_count: usize,
-        first_entry_id: Option<&str>,
-    ) -> Self {
+    fn from_title(title_id: &str, title_name: &str, entry_count: usize, cover_url: String) -> Self {
         let content_label = if entry_count == 1 {
             "1 entry".to_string()
         } else {
             format!("{} entries", entry_count)
         };
 
-        // Cover URL uses first entry's cover if available
-        let cover_url = first_entry_id
-            .map(|eid| format!("/api/cover/{}/{}", title_id, eid))
-            .unwrap_or_else(|| "/static/img/placeholder.png".to_string());
-
         Self {
             id: title_id.to_string(),
             is_entry: false,
             display_name: title_name.to_string(),
             cover_url,
+            url: paths::book(title_id),
             book_id: String::new(),
             book_display_name: String::new(),
+            book_url: String::new(),
             pages: 0,
             encoded_path: String::new(),
             encoded_title: String::new(),
@@ -157,6 +208,10 @@ impl BookCardItem {
             content_label,
             title: Some(title_name.to_string()),
             sort_title: None,
+            aliases: String::new(),
+            read_count: 0,
+            unsorted: false,
+            pinned: false,
         }
     }
 }
@@ -177,28 +232,53 @@ impl HasProgress for BookItem {
 #[derive(Template)]
 #[template(path = "book.html")]
 struct BookTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
     title: TitleInfo,
     sort_options: Vec<(&'static str, &'static str)>,
     sort_opt: Option<SortOption>,
     nested_title_items: Vec<BookItem>,
     items: Vec<BookItem>,
     supported_img_types: String,
+    /// Atom feed of the title's new entries, once the user has a feed token
+    feed_url: Option<String>,
+    /// Files of the title that could not be read (admins only)
+    unreadable: Vec<UnreadableItem>,
+    /// Whether the user pinned the title to the top of the library, None for nested titles
+    pinned: Option<bool>,
+    /// Whether to show the tab of every user's progress (admins only)
+    show_readers: bool,
+}
+
+/// A file left out of the title because it could not be read
+struct UnreadableItem {
+    /// Relative to the title directory
+    name: String,
+    error: String,
 }
 
+/// GET /book/:id - Book page listing a title's entries and nested titles
+/// With Save-Data, cards link downscaled covers
+/// Titles locked for the user (see `TagLocks`) get the PIN prompt with 423 Locked
 pub async fn get_book(
     State(state): State<AppState>,
     Path(title_id): Path<String>,
     Query(params): Query<BookParams>,
     user: User,
-) -> Result<Html<String>> {
-    // Get title path for loading/saving sort preferences
-    let title_path = {
+    session: Session,
+    hints: ClientHints,
+) -> Result<Response> {
+    let locks = TagLocks::load(&state, &session, &user.username).await?;
+
+    // Get the title's info.json directory for loading/saving sort preferences
+    let info_dir = {
         let lib = state.library.load();
         let title = lib
             .get_title(&title_id)
             .ok_or_else(|| Error::NotFound(format!("Title not found: {}", title_id)))?;
-        title.path.clone()
+        if locks.is_locked(&title_id) {
+            return tag_locks::locked_page(&title.title);
+        }
+        title.info_dir()
     };
 
     // Load/save sort preferences from title's info.json
@@ -207,13 +287,33 @@ pub async fn get_book(
         ascend: params.ascend.clone(),
     };
     let (sort_method_str, ascending) =
-        crate::util::get_and_save_sort(&title_path, &user.username, &sort_params).await?;
+        crate::util::get_and_save_sort(&info_dir, &user.username, &sort_params).await?;
+
+    // The primary alias is shown under the title name
+    let primary_alias = state
+        .storage
+        .get_title_aliases(&title_id)
+        .await?
+        .into_iter()
+        .next();
+    let relations = state.storage.get_title_relations(&title_id).await?;
+    let feed_url = state
+        .storage
+        .get_feed_token(&user.username)
+        .await?
+        .map(|token| paths::feed_title(&title_id, &token));
+    let pinned = if state.library.load().titles().contains_key(&title_id) {
+        let pins = state.storage.list_pins(&user.username).await?;
+        Some(pins.contains(&title_id))
+    } else {
+        None
+    };
 
     // Parse sort method from string
     let sort_method = SortMethod::parse(&sort_method_str);
 
     // Build the title info and gather all data
-    let (title_info, nested_title_items, mut items) = {
+    let (title_info, nested_title_items, mut items, unreadable) = {
         let lib = state.library.load();
 
         // Get the title
@@ -229,6 +329,7 @@ pub async fn get_book(
                 parents.push(ParentItem {
                     id: parent_title.id.clone(),
                     display_name: parent_title.title.clone(),
+                    url: paths::book(&parent_title.id),
                 });
                 current_parent_id = parent_title.parent_id.clone();
             } else {
@@ -263,12 +364,19 @@ pub async fn get_book(
             )
         };
 
-        // Build title info
-        let cover_url = title
+        // A pinned entry order overrides every sort method, including progress
+        let custom_order = lib.get_entry_order(&title.id).is_some();
+
+        // Cover URLs carry the version of the title's, its entries' and nested titles' covers
+        let cover_entries: Vec<&crate::library::Entry> = title
             .entries
-            .first()
-            .map(|e| format!("/api/cover/{}/{}", title.id, e.id))
-            .unwrap_or_else(|| "/static/img/placeholder.png".to_string());
+            .iter()
+            .chain(title.nested_titles.iter().filter_map(|t| t.entries.first()))
+            .collect();
+        let versions = super::api::cover_versions(&state, &cover_entries).await?;
+
+        // Build title info
+        let cover_url = super::api::title_cover_url(title, &versions, hints.cover_width());
 
         let title_info = TitleInfo {
             id: title.id.clone(),
@@ -278,75 +386,78 @@ pub async fn get_book(
             cover_url,
             content_label,
             parents,
+            custom_order,
+            direction: lib
+                .progress_cache()
+                .get_title_direction(&title.id)
+                .map(|d| d.label()),
+            primary_alias,
+            relations: related_links(&lib, &relations),
         };
 
         // Build nested titles cards and calculate their progress
         let mut nested_title_items = Vec::new();
 
         for nested in &title.nested_titles {
-            let nested_entry_count = nested.entries.len();
-            let first_entry_id = nested.entries.first().map(|e| e.id.as_str());
-
             let card = BookCardItem::from_title(
                 &nested.id,
                 &nested.title,
-                nested_entry_count,
-                first_entry_id,
+                nested.entries.len(),
+                locks.cover_url(
+                    &nested.id,
+                    super::api::title_cover_url(nested, &versions, hints.cover_width()),
+                ),
             );
 
-            // Calculate average progress for nested title
-            let mut total_progress = 0.0f64;
-            let mut count = 0;
-            for entry in &nested.entries {
-                let (progress, _) = nested
-                    .get_entry_progress(&user.username, &entry.id)
-                    .await
-                    .unwrap_or((0.0, 0));
-                total_progress += progress as f64;
-                count += 1;
-            }
-            let avg_progress = if count > 0 {
-                total_progress / count as f64
-            } else {
-                0.0
-            };
-
+            // Average progress over the nested title's entries, its own nested titles' included
             nested_title_items.push(BookItem {
                 item: card,
-                progress: avg_progress,
+                progress: lib.get_title_progress(nested, &user.username) as f64,
             });
         }
 
         // Build entry items - use sort method if not progress-based
         let all_entries = if matches!(sort_method, SortMethod::Progress) {
-            title.get_entries_sorted(SortMethod::Name, true) // Get name-sorted as base
+            lib.get_entries_sorted(title, SortMethod::Name, true) // Get name-sorted as base
         } else {
-            title.get_entries_sorted(sort_method, ascending)
+            lib.get_entries_sorted_for(title, &user.username, sort_method, ascending)
         };
 
         let mut items = Vec::new();
         for entry in all_entries {
             // Load progress for this entry using Title's method
-            let (progress_percentage, _saved_page) = title
+            // Percentage comes from the library so bad pages are left out
+            let (_, saved_page) = title
                 .get_entry_progress(&user.username, &entry.id)
                 .await
                 .unwrap_or((0.0, 0));
+            let progress_percentage = lib.entry_percentage(entry, saved_page);
 
             // Apply search filter if provided
             if let Some(ref search) = params.search {
-                if !entry.title.to_lowercase().contains(&search.to_lowercase()) {
+                let search = search.to_lowercase();
+                if !entry.title.to_lowercase().contains(&search)
+                    && !entry.display_title.to_lowercase().contains(&search)
+                {
                     continue;
                 }
             }
 
-            let card = BookCardItem::from_entry(
-                &entry.id,
-                &entry.title,
+            let mut card = BookCardItem::from_entry(
+                entry,
                 &title.id,
                 &title.title,
-                entry.pages,
-                &entry.path.to_string_lossy(),
+                &entry.relative_path(lib.path()),
+                versions
+                    .get(&entry.id)
+                    .map(String::as_str)
+                    .unwrap_or_default(),
+                hints.cover_width(),
             );
+            card.read_count = lib
+                .progress_cache()
+                .get_read_state(&title.id, &user.username, &entry.id)
+                .count;
 
             items.push(BookItem {
                 item: card,
@@ -354,11 +465,24 @@ pub async fn get_book(
             });
         }
 
-        (title_info, nested_title_items, items)
+        let unreadable = if user.is_admin {
+            title
+                .unreadable
+                .iter()
+                .map(|f| UnreadableItem {
+                    name: crate::util::relative_to_root(&f.path, &title.path),
+                    error: f.error.clone(),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        (title_info, nested_title_items, items, unreadable)
     }; // Lock is released here
 
     // Sort by progress if requested (after calculating progress)
-    if matches!(sort_method, SortMethod::Progress) {
+    if matches!(sort_method, SortMethod::Progress) && !title_info.custom_order {
         sort_by_progress(&mut items, ascending);
     }
 
@@ -372,20 +496,29 @@ pub async fn get_book(
         ("time_modified", "Date Modified"),
         ("time_added", "Date Added"),
         ("progress", "Progress"),
+        ("opened", "Last Opened"),
     ];
 
     // Supported image types for upload
     let supported_img_types = "image/jpeg,image/png,image/gif,image/webp".to_string();
 
     let template = BookTemplate {
-        nav: crate::util::NavigationState::library().with_admin(user.is_admin),
+        base: BaseContext::new(&user, NavPage::Library),
         title: title_info,
         sort_options,
         sort_opt,
         nested_title_items,
         items,
         supported_img_types,
+        feed_url,
+        unreadable,
+        pinned,
+        show_readers: user.is_admin && state.config.admin_can_view_user_progress,
     };
 
-    Ok(Html(template.render().map_err(render_error)?))
+    Ok((
+        ClientHints::vary(),
+        Html(template.render().map_err(render_error)?),
+    )
+        .into_response())
 }
@@ -0,0 +1,158 @@
+// Capability discovery - lets clients see which optional features are on and the
+// limits they must respect, instead of probing endpoints and interpreting 404s
+
+use axum::{extract::State, Json};
+use serde::Serialize;
+
+use super::paths;
+use crate::{error::Result, version::VersionInfo, AppState, Config};
+
+/// Version of the JSON API; bumped on breaking changes to existing endpoints
+pub const API_VERSION: u32 = 1;
+
+/// Routes that must be registered for each feature that can be advertised as enabled
+/// Keep in sync with `Features`; the tests check both directions
+pub const FEATURE_ROUTES: &[(&str, &[&str])] = &[
+    ("tags", &[paths::API_TAGS, paths::API_TITLE_TAGS]),
+    (
+        "search",
+        &[paths::API_SEARCH, paths::API_ADMIN_TITLE_ALIASES],
+    ),
+    ("opds", &[paths::OPDS, paths::OPDS_BOOK]),
+    ("download", &[paths::API_DOWNLOAD]),
+    ("download_quota", &[paths::API_USER_QUOTA]),
+    ("cover_upload", &[paths::API_ADMIN_UPLOAD_COVER]),
+    ("reports", &[paths::API_REPORT]),
+    (
+        "page_thumbnails",
+        &[
+            paths::API_PAGE_THUMBNAILS,
+            paths::API_PAGE_THUMBNAILS_SPRITE,
+        ],
+    ),
+    (
+        "komga_compat",
+        &[paths::KOMGA_BOOK, paths::KOMGA_READ_PROGRESS],
+    ),
+];
+
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub api_version: u32,
+    /// Server version (crate version)
+    pub server_version: &'static str,
+    pub features: Features,
+    pub limits: Limits,
+}
+
+/// Optional features; false means the server does not implement or has disabled it
+#[derive(Debug, Serialize)]
+pub struct Features {
+    pub tags: bool,
+    /// Title search by name and admin-defined aliases
+    pub search: bool,
+    pub opds: bool,
+    /// OPDS Page Streaming Extension (not implemented)
+    pub opds_pse: bool,
+    /// Not implemented
+    pub favorites: bool,
+    /// Not implemented; pages are served as stored in the archive
+    pub transcoding: bool,
+    pub download: bool,
+    /// Per-user daily download quota is enforced
+    pub download_quota: bool,
+    /// Admins can upload custom covers
+    pub cover_upload: bool,
+    /// Users can report problems with entries
+    pub reports: bool,
+    /// Reader page thumbnail sprites
+    pub page_thumbnails: bool,
+    /// Komga-style read progress endpoints under /api/v1
+    pub komga_compat: bool,
+    /// Browsing without logging in, as `default_username` (config `disable_login`)
+    pub anonymous: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Limits {
+    /// Daily download quota per user, None when unlimited
+    pub download_quota_bytes: Option<u64>,
+    pub max_cover_upload_bytes: usize,
+    pub max_report_note_chars: usize,
+    /// Most frames in a page thumbnail sprite; longer entries sample every Nth page
+    pub max_page_thumbnails: usize,
+}
+
+impl Capabilities {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            api_version: API_VERSION,
+            server_version: crate::version::VERSION,
+            features: Features {
+                tags: true,
+                search: true,
+                opds: true,
+                opds_pse: false,
+                favorites: false,
+                transcoding: false,
+                download: true,
+                download_quota: config.download_quota_mbs > 0,
+                cover_upload: true,
+                reports: true,
+                page_thumbnails: true,
+                komga_compat: config.komga_compat_enabled,
+                anonymous: config.disable_login,
+            },
+            limits: Limits {
+                download_quota_bytes: (config.download_quota_mbs > 0)
+                    .then(|| config.download_quota_mbs * 1024 * 1024),
+                max_cover_upload_bytes: super::admin::MAX_COVER_SIZE,
+                max_report_note_chars: super::api::MAX_REPORT_NOTE_CHARS,
+                max_page_thumbnails: crate::library::entry::SPRITE_MAX_FRAMES,
+            },
+        }
+    }
+}
+
+/// API route: GET /api/capabilities
+/// Returns enabled features, limits and server/API versions for client discovery
+pub async fn get_capabilities(State(state): State<AppState>) -> Result<Json<Capabilities>> {
+    Ok(Json(Capabilities::from_config(&state.config)))
+}
+
+/// API route: GET /api/version
+/// Build, commit, schema and cache format versions to include in bug reports
+pub async fn get_version(State(state): State<AppState>) -> Result<Json<VersionInfo>> {
+    Ok(Json(VersionInfo::collect(&state.storage).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_routes_name_real_features() {
+        let capabilities = Capabilities::from_config(&Config::default_config());
+        let features = serde_json::to_value(&capabilities.features).unwrap();
+        for (name, _) in FEATURE_ROUTES {
+            assert!(features.get(name).is_some(), "unknown feature {}", name);
+        }
+    }
+
+    #[test]
+    fn test_quota_follows_config() {
+        let mut config = Config::default_config();
+        config.download_quota_mbs = 0;
+        let capabilities = Capabilities::from_config(&config);
+        assert!(!capabilities.features.download_quota);
+        assert_eq!(capabilities.limits.download_quota_bytes, None);
+
+        config.download_quota_mbs = 2;
+        let capabilities = Capabilities::from_config(&config);
+        assert!(capabilities.features.download_quota);
+        assert_eq!(
+            capabilities.limits.download_quota_bytes,
+            Some(2 * 1024 * 1024)
+        );
+    }
+}
@@ -0,0 +1,69 @@
+// Server-sent library change events for open library and book pages
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::Stream;
+
+use crate::{
+    auth::Username,
+    error::{Error, Result},
+    library::{events::StreamSlot, events::MAX_STREAMS_PER_USER, LibraryEvent},
+    AppState,
+};
+
+/// Comment lines sent on idle streams so proxies do not close them
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(20);
+
+/// One user's event stream; gives back its slot in the per-user cap when dropped
+pub struct EventStream {
+    events: BroadcastStream<LibraryEvent>,
+    _slot: StreamSlot,
+}
+
+impl Stream for EventStream {
+    type Item = std::result::Result<Event, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match ready!(Pin::new(&mut self.events).poll_next(cx)) {
+                Some(Ok(event)) => {
+                    let sse = Event::default().event(event.name()).json_data(&event);
+                    return Poll::Ready(Some(sse));
+                }
+                // A slow client missed some events; the next scan_completed still arrives
+                Some(Err(BroadcastStreamRecvError::Lagged(skipped))) => {
+                    tracing::debug!("Event stream lagged, skipped {} events", skipped);
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// GET /api/events - Library changes pushed as server-sent events
+/// `title_updated {title_id, new_entries}` per title that gained entries, then
+/// `scan_completed {added, removed}` once per scan
+pub async fn library_events(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<Sse<EventStream>> {
+    let (receiver, slot) = state.events.subscribe(&username).ok_or_else(|| {
+        Error::TooManyRequests(format!(
+            "At most {} event streams per user",
+            MAX_STREAMS_PER_USER
+        ))
+    })?;
+
+    let stream = EventStream {
+        events: BroadcastStream::new(receiver),
+        _slot: slot,
+    };
+    Ok(Sse::new(stream).keep_alive(KeepAlive::new().interval(HEARTBEAT_INTERVAL)))
+}
@@ -0,0 +1,229 @@
+// Atom feeds of newly added entries, for feed readers
+//
+// Feed readers can neither keep a session nor reliably send Basic auth, so the feeds are
+// authenticated by a per-user feed token in the URL (`?token=...`), checked by
+// require_auth for every path under /feed. The token is generated, regenerated (which
+// revokes the old one) and revoked from the account page.
+
+use askama::Template;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+
+use super::paths;
+use crate::{
+    auth::Username,
+    error::{Error, Result},
+    library::{Entry, Library, Title},
+    totp,
+    util::time,
+    AppState,
+};
+
+/// Entries listed by a feed, newest first
+const FEED_ENTRY_LIMIT: usize = 50;
+
+/// Template for the Atom feeds of recently added entries
+#[derive(Template)]
+#[template(path = "feed.xml", escape = "xml")]
+struct FeedTemplate {
+    instance_name: String,
+    feed_id: String,
+    feed_title: String,
+    self_url: String,
+    /// Page the feed is about, for readers that link back to the site
+    alternate_url: String,
+    /// RFC 3339 time of the newest entry
+    updated: String,
+    entries: Vec<FeedEntry>,
+}
+
+struct FeedEntry {
+    id: String,
+    title: String,
+    /// RFC 3339 time the entry was added
+    updated: String,
+    summary: String,
+    reader_url: String,
+}
+
+/// When an entry was added, falling back to its file time for entries from before
+/// date_added was tracked
+fn date_added(lib: &Library, title: &Title, entry: &Entry) -> i64 {
+    lib.progress_cache()
+        .get_date_added(&title.id, &entry.id)
+        .unwrap_or(entry.mtime)
+}
+
+/// Render a feed of `entries` (title, entry, date added), keeping the newest ones
+fn render_feed(
+    instance_name: &str,
+    feed_id: String,
+    feed_title: String,
+    self_url: String,
+    alternate_url: String,
+    mut entries: Vec<(&Title, &Entry, i64)>,
+    library_wide: bool,
+) -> Result<impl IntoResponse> {
+    entries.sort_by(|(_, a, a_added), (_, b, b_added)| {
+        b_added.cmp(a_added).then_with(|| a.title.cmp(&b.title))
+    });
+    entries.truncate(FEED_ENTRY_LIMIT);
+
+    let updated = entries.first().map_or(0, |(_, _, added)| *added);
+    let entries = entries
+        .into_iter()
+        .map(|(title, entry, added)| FeedEntry {
+            id: entry.id.clone(),
+            title: if library_wide {
+                format!("{} - {}", title.title, entry.display_title)
+            } else {
+                entry.display_title.clone()
+            },
+            updated: time::format_rfc3339(added),
+            summary: format!("{} pages in {}", entry.pages, title.title),
+            reader_url: paths::reader(&title.id, &entry.id, 1),
+        })
+        .collect();
+
+    let template = FeedTemplate {
+        instance_name: instance_name.to_string(),
+        feed_id,
+        feed_title,
+        self_url,
+        alternate_url,
+        updated: time::format_rfc3339(updated),
+        entries,
+    };
+    let xml = template
+        .render()
+        .map_err(|e| Error::Internal(format!("Failed to render feed: {}", e)))?;
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        xml,
+    ))
+}
+
+/// Feed token of the user, the one require_auth accepted for this request
+async fn feed_token(state: &AppState, username: &str) -> Result<String> {
+    state
+        .storage
+        .get_feed_token(username)
+        .await?
+        .ok_or(Error::AuthFailed)
+}
+
+/// GET /feed/recent.atom?token=... - Recently added entries across the library
+pub async fn feed_recent(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<impl IntoResponse> {
+    let token = feed_token(&state, &username).await?;
+    let lib = state.library.load();
+    let mut entries = Vec::new();
+    for title in lib.all_titles() {
+        for entry in &title.entries {
+            entries.push((title, entry, date_added(&lib, title, entry)));
+        }
+    }
+
+    render_feed(
+        &state.config.instance_name,
+        "recent".to_string(),
+        format!("{} - Recently Added", state.config.instance_name),
+        paths::feed_recent(&token),
+        paths::library(),
+        entries,
+        true,
+    )
+}
+
+/// GET /feed/title/:id.atom?token=... - Recently added entries of one title
+pub async fn feed_title(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+    Username(username): Username,
+) -> Result<impl IntoResponse> {
+    let title_id = file
+        .strip_suffix(".atom")
+        .ok_or_else(|| Error::NotFound(format!("Feed not found: {}", file)))?;
+    let token = feed_token(&state, &username).await?;
+    let lib = state.library.load();
+    let title = lib
+        .get_title(title_id)
+        .ok_or_else(|| Error::NotFound(format!("Title not found: {}", title_id)))?;
+    let entries = title
+        .entries
+        .iter()
+        .map(|entry| (title, entry, date_added(&lib, title, entry)))
+        .collect();
+
+    render_feed(
+        &state.config.instance_name,
+        format!("title:{}", title.id),
+        title.title.clone(),
+        paths::feed_title(&title.id, &token),
+        paths::book(&title.id),
+        entries,
+        false,
+    )
+}
+
+#[derive(Serialize)]
+pub struct FeedTokenResponse {
+    /// None until a token is generated, and after it is revoked
+    pub token: Option<String>,
+    /// Library-wide feed; title feeds are linked from each book page
+    pub recent_url: Option<String>,
+}
+
+impl FeedTokenResponse {
+    fn new(token: Option<String>) -> Self {
+        Self {
+            recent_url: token.as_deref().map(paths::feed_recent),
+            token,
+        }
+    }
+}
+
+/// GET /api/user/feed-token - The current user's feed token and feed URL
+pub async fn get_feed_token(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<Json<FeedTokenResponse>> {
+    let token = state.storage.get_feed_token(&username).await?;
+    Ok(Json(FeedTokenResponse::new(token)))
+}
+
+/// POST /api/user/feed-token - Generate a feed token, replacing (revoking) the old one
+pub async fn create_feed_token(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<(StatusCode, Json<FeedTokenResponse>)> {
+    let token = totp::generate_app_token();
+    state.storage.set_feed_token(&username, &token).await?;
+    tracing::info!("Feed token generated for user: {}", username);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(FeedTokenResponse::new(Some(token))),
+    ))
+}
+
+/// DELETE /api/user/feed-token - Revoke the feed token, breaking every feed URL
+pub async fn delete_feed_token(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<StatusCode> {
+    if !state.storage.delete_feed_token(&username).await? {
+        return Err(Error::NotFound("No feed token to revoke".to_string()));
+    }
+    tracing::info!("Feed token revoked for user: {}", username);
+    Ok(StatusCode::NO_CONTENT)
+}
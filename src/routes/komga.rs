@@ -0,0 +1,239 @@
+// Komga compatibility - the few Komga REST endpoints mobile clients use to sync
+// read progress (see docs/KOMGA.md)
+//
+// Only registered when `komga_compat_enabled` is set. A Komga book is one of our
+// entries and a Komga series one of our titles, under the same IDs: entry and title
+// IDs are persisted, so they stay stable across restarts and rescans and need no
+// mapping table. Anything else under /api/v1 answers 501 so clients can tell an
+// unsupported feature from a missing book.
+
+use axum::{
+    extract::{Path, State},
+    http::{StatusCode, Uri},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{Username, Writer},
+    error::{Error, Result},
+    library::{Entry, Library, SortMethod, Title},
+    AppState,
+};
+
+/// Komga library ID reported for every book; Mango has a single library
+const LIBRARY_ID: &str = "mango";
+
+/// Komga's BookDto, limited to the fields read progress clients look at
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookDto {
+    id: String,
+    series_id: String,
+    series_title: String,
+    library_id: &'static str,
+    name: String,
+    url: String,
+    /// Position in the title's entry list, starting at 1
+    number: usize,
+    created: String,
+    last_modified: String,
+    file_last_modified: String,
+    media: MediaDto,
+    metadata: BookMetadataDto,
+    /// None while the user has not started the book
+    read_progress: Option<ReadProgressDto>,
+    deleted: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaDto {
+    status: &'static str,
+    pages_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookMetadataDto {
+    title: String,
+    number: String,
+    number_sort: f32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadProgressDto {
+    /// Last page read, starting at 1
+    page: i32,
+    completed: bool,
+    read_date: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Komga's ReadProgressUpdateDto; at least one field must be set
+#[derive(Debug, Deserialize)]
+pub struct ReadProgressUpdate {
+    page: Option<i32>,
+    completed: Option<bool>,
+}
+
+impl ReadProgressUpdate {
+    /// Page to save for an entry of `pages` pages
+    /// As in Komga, `completed: true` wins and means the last page
+    fn target_page(&self, pages: usize) -> Result<i32> {
+        if self.completed == Some(true) {
+            return Ok(pages as i32);
+        }
+        match self.page {
+            Some(page) if page >= 1 && page as usize <= pages => Ok(page),
+            Some(page) => Err(Error::BadRequest(format!(
+                "Page {} is out of range 1-{}",
+                page, pages
+            ))),
+            None => Err(Error::BadRequest(
+                "Either page or completed=true must be set".to_string(),
+            )),
+        }
+    }
+}
+
+/// Unix timestamp as an RFC 3339 date, the format Komga uses
+fn iso_date(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+/// Look up a book by its Komga ID (our entry ID)
+fn find_book<'a>(lib: &'a Library, book_id: &str) -> Result<(&'a Title, &'a Entry)> {
+    lib.find_entry(book_id)
+        .ok_or_else(|| Error::NotFound(format!("Book not found: {}", book_id)))
+}
+
+/// GET /api/v1/books/{bookId} - A book with the user's read progress
+pub async fn get_komga_book(
+    State(state): State<AppState>,
+    Path(book_id): Path<String>,
+    Username(username): Username,
+) -> Result<Json<BookDto>> {
+    let lib = state.library.load();
+    let (title, entry) = find_book(&lib, &book_id)?;
+
+    let number = lib
+        .get_entries_sorted(title, SortMethod::Name, true)
+        .iter()
+        .position(|e| e.id == entry.id)
+        .map_or(1, |i| i + 1);
+    let series_title = lib
+        .progress_cache()
+        .get_display_name(&title.id)
+        .unwrap_or_else(|| title.title.clone());
+
+    let progress = lib.progress_cache();
+    let read_progress = progress
+        .get_progress(&title.id, &username, &entry.id)
+        .filter(|page| *page > 0)
+        .map(|page| {
+            let read_date = progress
+                .get_last_read(&title.id, &username, &entry.id)
+                .map(iso_date);
+            ReadProgressDto {
+                page,
+                completed: page as usize >= entry.pages,
+                last_modified: read_date.clone(),
+                read_date,
+            }
+        });
+
+    let modified = iso_date(entry.mtime);
+    Ok(Json(BookDto {
+        id: entry.id.clone(),
+        series_id: title.id.clone(),
+        series_title,
+        library_id: LIBRARY_ID,
+        name: entry.display_title.clone(),
+        url: entry.relative_path(lib.path()),
+        number,
+        created: modified.clone(),
+        last_modified: modified.clone(),
+        file_last_modified: modified,
+        media: MediaDto {
+            status: "READY",
+            pages_count: entry.pages,
+        },
+        metadata: BookMetadataDto {
+            title: entry.display_title.clone(),
+            number: number.to_string(),
+            number_sort: number as f32,
+        },
+        read_progress,
+        deleted: false,
+    }))
+}
+
+/// PATCH /api/v1/books/{bookId}/read-progress - Save the user's read progress
+/// Returns: 204 No Content, like Komga
+pub async fn patch_komga_read_progress(
+    State(state): State<AppState>,
+    Path(book_id): Path<String>,
+    Writer(username): Writer,
+    Json(update): Json<ReadProgressUpdate>,
+) -> Result<StatusCode> {
+    let lib = state.library.load();
+    let (title, entry) = find_book(&lib, &book_id)?;
+    let page = update.target_page(entry.pages)?;
+    let title_id = title.id.clone();
+
+    lib.save_progress(&title_id, &username, &book_id, page)
+        .await?;
+    lib.invalidate_cache_for_progress(&title_id, &username)
+        .await;
+    drop(lib);
+
+    tracing::debug!(
+        "Saved progress (Komga): {} / {} = page {}",
+        title_id,
+        book_id,
+        page
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Any other method or endpoint under /api/v1 - 501 Not Implemented
+/// The body follows Komga's error shape
+pub async fn komga_not_implemented(uri: Uri) -> Response {
+    (
+        StatusCode::NOT_IMPLEMENTED,
+        Json(serde_json::json!({
+            "status": StatusCode::NOT_IMPLEMENTED.as_u16(),
+            "error": "Not Implemented",
+            "message": "Mango only implements the Komga read progress endpoints",
+            "path": uri.path(),
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(page: Option<i32>, completed: Option<bool>) -> ReadProgressUpdate {
+        ReadProgressUpdate { page, completed }
+    }
+
+    #[test]
+    fn test_target_page() {
+        assert_eq!(update(Some(2), None).target_page(3).unwrap(), 2);
+        assert_eq!(update(None, Some(true)).target_page(3).unwrap(), 3);
+        assert_eq!(update(Some(1), Some(true)).target_page(3).unwrap(), 3);
+        assert_eq!(update(Some(2), Some(false)).target_page(3).unwrap(), 2);
+
+        assert!(update(None, None).target_page(3).is_err());
+        assert!(update(None, Some(false)).target_page(3).is_err());
+        assert!(update(Some(0), None).target_page(3).is_err());
+        assert!(update(Some(4), None).target_page(3).is_err());
+    }
+}
@@ -1,24 +1,70 @@
 use askama::Template;
 use axum::{
-    extract::State,
-    response::{Html, IntoResponse, Redirect},
-    Form,
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    Form, Json,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tower_sessions::Session;
 
+use super::paths;
 use crate::{
-    auth::{SESSION_TOKEN_KEY, SESSION_USERNAME_KEY},
+    auth::{
+        ClientIp, PendingTwoFactor, Username, SESSION_PENDING_2FA_KEY, SESSION_TOKEN_KEY,
+        SESSION_USERNAME_KEY,
+    },
     error::{Error, Result},
+    totp,
     util::render_error,
     AppState,
 };
 
+/// Seconds a password step stays valid while waiting for the two-factor code
+const PENDING_2FA_TTL_SECS: i64 = 5 * 60;
+
+/// Invalid codes allowed before the password has to be entered again
+const MAX_2FA_ATTEMPTS: u32 = 5;
+
+/// Longest user agent kept with a login session, in characters
+const MAX_USER_AGENT_CHARS: usize = 200;
+
 /// Login page template
 #[derive(Template)]
 #[template(path = "login.html")]
 struct LoginTemplate {
+    branding: crate::util::Branding,
+    /// Prefix of the stylesheet, script and form URLs
+    base_url: String,
     error: Option<String>,
+    /// Validated page to return to after logging in
+    next: Option<String>,
+    /// Ask for the two-factor code instead of the password
+    two_factor: bool,
+}
+
+impl LoginTemplate {
+    fn render_page(
+        error: Option<&str>,
+        next: Option<String>,
+        two_factor: bool,
+    ) -> Result<Response> {
+        let template = LoginTemplate {
+            branding: crate::util::Branding::current(),
+            base_url: paths::base_url(),
+            error: error.map(str::to_string),
+            next,
+            two_factor,
+        };
+        Ok(Html(template.render().map_err(render_error)?).into_response())
+    }
+}
+
+/// Query parameters for the login page
+#[derive(Deserialize)]
+pub struct LoginQuery {
+    /// Page to return to after logging in (set by the auth middleware)
+    next: Option<String>,
 }
 
 /// Login form data
@@ -26,55 +72,347 @@ struct LoginTemplate {
 pub struct LoginForm {
     username: String,
     password: String,
+    #[serde(default)]
+    next: Option<String>,
 }
 
-/// GET /login - Show login page
-pub async fn get_login() -> Result<Html<String>> {
-    let template = LoginTemplate { error: None };
-    Ok(Html(template.render().map_err(render_error)?))
+/// Two-factor code form data
+#[derive(Deserialize)]
+pub struct TwoFactorForm {
+    /// Authenticator code or recovery code
+    code: String,
+}
+
+/// GET /login?next= - Show login page
+pub async fn get_login(Query(query): Query<LoginQuery>) -> Result<Response> {
+    let next = query
+        .next
+        .as_deref()
+        .and_then(paths::safe_next)
+        .map(str::to_string);
+    LoginTemplate::render_page(None, next, false)
 }
 
 /// POST /login - Process login
 pub async fn post_login(
     State(state): State<AppState>,
     session: Session,
+    headers: HeaderMap,
+    ClientIp(client): ClientIp,
     Form(form): Form<LoginForm>,
 ) -> Result<impl IntoResponse> {
+    // Anything but a same-origin path under base_url falls back to the home page
+    let next = form.next.as_deref().and_then(paths::safe_next);
+
+    let now = crate::util::time::now_ts();
+    if let Some(wait) = state
+        .login_throttle
+        .retry_after(client, &form.username, now)
+    {
+        tracing::warn!(
+            "Refused login for username {}: too many failures",
+            form.username
+        );
+        return too_many_failures(wait, next.map(str::to_string));
+    }
+
     // Verify credentials
     match state
         .storage
         .verify_user(&form.username, &form.password)
         .await?
     {
-        Some(token) => {
-            // Store token and username in session
-            session
-                .insert(SESSION_TOKEN_KEY, token)
-                .await
-                .map_err(|e| Error::Internal(format!("Failed to save session: {}", e)))?;
+        true if state.storage.totp_enabled(&form.username).await? => {
+            // Password checks out; the session stays anonymous until the code does too.
+            // Failures are only forgotten once the code checks out, so entering the
+            // password again does not buy more guesses at the code
+            let pending = PendingTwoFactor {
+                username: form.username.clone(),
+                next: next.map(str::to_string),
+                started_at: crate::util::time::now_ts(),
+                attempts: 0,
+            };
             session
-                .insert(SESSION_USERNAME_KEY, form.username.clone())
+                .insert(SESSION_PENDING_2FA_KEY, pending)
                 .await
                 .map_err(|e| Error::Internal(format!("Failed to save session: {}", e)))?;
 
+            tracing::debug!("User {} passed the password step", form.username);
+            LoginTemplate::render_page(None, None, true)
+        }
+        true => {
+            state.login_throttle.clear(client, &form.username);
             tracing::info!("User {} logged in successfully", form.username);
-            Ok(Redirect::to("/").into_response())
+            log_in(&state, &session, &headers, &form.username, next).await
         }
-        None => {
+        false => {
             // Invalid credentials, show error
             tracing::warn!("Failed login attempt for username: {}", form.username);
-            let template = LoginTemplate {
-                error: Some("Invalid username or password".to_string()),
-            };
-            Ok(Html(template.render().map_err(render_error)?).into_response())
+            state
+                .login_throttle
+                .record_failure(client, &form.username, now);
+            LoginTemplate::render_page(
+                Some("Invalid username or password"),
+                next.map(str::to_string),
+                false,
+            )
         }
     }
 }
 
-/// GET /logout - Clear session and redirect to login
-pub async fn logout(session: Session) -> Redirect {
-    // Clear session
+/// POST /login/2fa - Check the two-factor code of a login that passed the password step
+/// Accepts a current authenticator code (each only once) or an unused recovery code
+/// Invalid codes count as failed logins of the user (see `LoginThrottle`)
+pub async fn post_login_2fa(
+    State(state): State<AppState>,
+    session: Session,
+    headers: HeaderMap,
+    ClientIp(client): ClientIp,
+    Form(form): Form<TwoFactorForm>,
+) -> Result<Response> {
+    let pending = session
+        .get::<PendingTwoFactor>(SESSION_PENDING_2FA_KEY)
+        .await
+        .ok()
+        .flatten();
+    let Some(mut pending) = pending else {
+        return Ok(Redirect::to(&paths::login()).into_response());
+    };
+
+    if crate::util::time::now_ts() - pending.started_at > PENDING_2FA_TTL_SECS {
+        let _ = session
+            .remove::<PendingTwoFactor>(SESSION_PENDING_2FA_KEY)
+            .await;
+        return LoginTemplate::render_page(
+            Some("The login timed out, please log in again"),
+            pending.next,
+            false,
+        );
+    }
+
+    let now = crate::util::time::now_ts();
+    if let Some(wait) = state
+        .login_throttle
+        .retry_after(client, &pending.username, now)
+    {
+        tracing::warn!(
+            "Refused two-factor code for username {}: too many failures",
+            pending.username
+        );
+        let _ = session
+            .remove::<PendingTwoFactor>(SESSION_PENDING_2FA_KEY)
+            .await;
+        return too_many_failures(wait, pending.next);
+    }
+
+    if verify_second_factor(&state, &pending.username, &form.code).await? {
+        let _ = session
+            .remove::<PendingTwoFactor>(SESSION_PENDING_2FA_KEY)
+            .await;
+        state.login_throttle.clear(client, &pending.username);
+        tracing::info!("User {} logged in with two-factor", pending.username);
+        let next = pending.next.as_deref().and_then(paths::safe_next);
+        return log_in(&state, &session, &headers, &pending.username, next).await;
+    }
+
+    tracing::warn!("Invalid two-factor code for username: {}", pending.username);
+    state
+        .login_throttle
+        .record_failure(client, &pending.username, now);
+    pending.attempts += 1;
+    if pending.attempts >= MAX_2FA_ATTEMPTS {
+        let _ = session
+            .remove::<PendingTwoFactor>(SESSION_PENDING_2FA_KEY)
+            .await;
+        return LoginTemplate::render_page(
+            Some("Too many invalid codes, please log in again"),
+            pending.next,
+            false,
+        );
+    }
+    session
+        .insert(SESSION_PENDING_2FA_KEY, pending)
+        .await
+        .map_err(|e| Error::Internal(format!("Failed to save session: {}", e)))?;
+    LoginTemplate::render_page(Some("Invalid code"), None, true)
+}
+
+/// Login page refusing a client that failed too often, with the seconds to wait
+fn too_many_failures(wait: i64, next: Option<String>) -> Result<Response> {
+    let mut response = LoginTemplate::render_page(
+        Some(&format!(
+            "Too many failed logins, try again in {} minutes",
+            (wait + 59) / 60
+        )),
+        next,
+        false,
+    )?;
+    *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(wait));
+    Ok(response)
+}
+
+/// Check an authenticator code or recovery code, consuming it on success
+async fn verify_second_factor(state: &AppState, username: &str, code: &str) -> Result<bool> {
+    let code = code.trim();
+
+    // Six digits is an authenticator code, anything else a recovery code
+    if code.len() == 6 && code.bytes().all(|b| b.is_ascii_digit()) {
+        let Some((secret, last_step)) = state.storage.get_totp(username).await? else {
+            return Ok(false);
+        };
+        let secret = totp::decode_secret(&secret)
+            .ok_or_else(|| Error::Internal("Stored TOTP secret is not valid base32".to_string()))?;
+        return match totp::verify_code(&secret, code, crate::util::time::now_ts(), last_step) {
+            Some(step) => state.storage.accept_totp_step(username, step).await,
+            None => Ok(false),
+        };
+    }
+
+    let hash = totp::hash_secret(&totp::normalize_recovery_code(code));
+    let used = state.storage.use_recovery_code(username, &hash).await?;
+    if used {
+        tracing::info!("User {} used a recovery code", username);
+    }
+    Ok(used)
+}
+
+/// Authenticate the session and send the user on to `next` (or home)
+/// Every login gets a session of its own, so logging out elsewhere leaves it alone
+async fn log_in(
+    state: &AppState,
+    session: &Session,
+    headers: &HeaderMap,
+    username: &str,
+    next: Option<&str>,
+) -> Result<Response> {
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|ua| ua.chars().take(MAX_USER_AGENT_CHARS).collect::<String>());
+    let token = state
+        .storage
+        .create_session(username, user_agent.as_deref())
+        .await?;
+
+    // Store token and username in session
+    session
+        .insert(SESSION_TOKEN_KEY, token)
+        .await
+        .map_err(|e| Error::Internal(format!("Failed to save session: {}", e)))?;
+    session
+        .insert(SESSION_USERNAME_KEY, username.to_string())
+        .await
+        .map_err(|e| Error::Internal(format!("Failed to save session: {}", e)))?;
+    state.storage.record_login(username).await;
+
+    let target = next.map_or_else(paths::home, str::to_string);
+    Ok(Redirect::to(&target).into_response())
+}
+
+/// GET /logout - End the login session and redirect to login
+/// Other browsers logged in as the same user stay logged in
+pub async fn logout(State(state): State<AppState>, session: Session) -> Redirect {
+    if let Ok(Some(token)) = session.get::<String>(SESSION_TOKEN_KEY).await {
+        if let Err(e) = state.storage.logout(&token).await {
+            tracing::error!("Failed to end login session: {}", e);
+        }
+    }
     let _ = session.delete().await;
     tracing::info!("User logged out");
-    Redirect::to("/login")
+    Redirect::to(&paths::login())
+}
+
+/// A login session of the current user, for the account page
+#[derive(Serialize)]
+pub struct SessionInfo {
+    /// Unix timestamps
+    pub created_at: i64,
+    pub last_seen: i64,
+    pub user_agent: Option<String>,
+    /// The session making this request
+    pub current: bool,
+}
+
+#[derive(Serialize)]
+pub struct RevokeSessionsResponse {
+    pub revoked: u64,
+}
+
+/// GET /api/user/sessions - Browsers logged in as the current user
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Username(username): Username,
+    session: Session,
+) -> Result<Json<Vec<SessionInfo>>> {
+    let current = session
+        .get::<String>(SESSION_TOKEN_KEY)
+        .await
+        .ok()
+        .flatten();
+    let sessions = state.storage.list_sessions(&username).await?;
+    Ok(Json(
+        sessions
+            .into_iter()
+            .map(|s| SessionInfo {
+                current: current.as_deref() == Some(s.token.as_str()),
+                created_at: s.created_at,
+                last_seen: s.last_seen,
+                user_agent: s.user_agent,
+            })
+            .collect(),
+    ))
+}
+
+/// POST /api/user/sessions/revoke-all - Log out every browser of the current user but
+/// the one making the request
+pub async fn revoke_sessions(
+    State(state): State<AppState>,
+    Username(username): Username,
+    session: Session,
+) -> Result<Json<RevokeSessionsResponse>> {
+    let current = session
+        .get::<String>(SESSION_TOKEN_KEY)
+        .await
+        .ok()
+        .flatten();
+    let revoked = state
+        .storage
+        .revoke_sessions(&username, current.as_deref())
+        .await?;
+    tracing::info!("User {} logged out {} other sessions", username, revoked);
+    Ok(Json(RevokeSessionsResponse { revoked }))
+}
+
+/// GET /branding/logo - Serve the custom logo configured with logo_path
+/// Public so the login page can show it
+pub async fn branding_logo(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let path = state
+        .config
+        .logo_path
+        .as_ref()
+        .ok_or_else(|| Error::NotFound("No custom logo configured".to_string()))?;
+
+    let data = tokio::fs::read(path).await.map_err(|e| {
+        tracing::warn!("Failed to read logo {}: {}", path.display(), e);
+        Error::NotFound("Custom logo not found".to_string())
+    })?;
+
+    // SVG has no magic bytes, so fall back to the file extension
+    let mime = match crate::util::guess_mime_type(&data) {
+        "application/octet-stream" if path.extension().and_then(|e| e.to_str()) == Some("svg") => {
+            "image/svg+xml"
+        }
+        mime => mime,
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, mime),
+            (header::CACHE_CONTROL, "public, max-age=3600"),
+        ],
+        data,
+    ))
 }
@@ -1,15 +1,16 @@
 use askama::Template;
 use axum::{
     extract::{Path, Query, State},
-    response::Html,
+    response::{Html, IntoResponse},
 };
+use tower_sessions::Session;
 
-use super::{sort_by_progress, HasProgress};
+use super::{paths, sort_by_progress, HasProgress, TagLocks};
 use crate::{
     auth::User,
     error::Result,
-    library::SortMethod,
-    util::{render_error, SortParams},
+    library::{Entry, SortMethod, TitleSource},
+    util::{render_error, BaseContext, ClientHints, NavPage, ProgressFilterParams, SortParams},
     AppState,
 };
 
@@ -38,6 +39,9 @@ struct TitleData {
     progress: f32,                  // Progress percentage (0.0 - 100.0) for sorting
     progress_display: String,       // Formatted progress for display (e.g., "0.0")
     first_entry_id: Option<String>, // For cover thumbnail URL
+    cover_url: String,              // First entry's cover, or placeholder
+    url: String,                    // Book page
+    unsorted: bool,                 // Holds the archives lying in the library root
 }
 
 impl HasProgress for TitleData {
@@ -56,11 +60,28 @@ struct LibraryItem {
 #[derive(Template)]
 #[template(path = "library.html")]
 struct LibraryTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
     titles: Vec<HomeCardItem>,  // For titles.len() in template
     items: Vec<LibraryItem>,    // Items with progress for iteration
     sort_options: Vec<(String, String)>,
     sort_opt: Option<SortOption>,
+    /// Card density: "comfortable", "compact" or "list"
+    density: &'static str,
+    /// Titles per page (0 = all on one page)
+    page_size: usize,
+    /// Current page, counted from 1
+    page: usize,
+    total_pages: usize,
+    /// Completed titles left out because the user hides them
+    hidden_completed: usize,
+    /// Progress filter parameters to carry over to other pages ("" when none)
+    filter_query: String,
+}
+
+/// Query parameters of the library page
+#[derive(serde::Deserialize)]
+pub struct LibraryPageParams {
+    page: Option<usize>,
 }
 
 /// Card item for home page - unified structure for entries and titles
@@ -72,10 +93,12 @@ struct HomeCardItem {
     is_entry: bool,
     display_name: String,
     cover_url: String,
+    url: String, // Reader for entries, book page for titles
 
     // Entry-specific fields (used when is_entry = true)
     book_id: String,
     book_display_name: String,
+    book_url: String,
     pages: usize,
     encoded_path: String,
     encoded_title: String,
@@ -89,27 +112,37 @@ struct HomeCardItem {
     // Optional metadata
     title: Option<String>,
     sort_title: Option<String>,
+    /// Title aliases joined by newlines, matched by the search box
+    aliases: String,
+    /// Times the user finished the entry (only shown on the book page)
+    read_count: u32,
+    /// The title collecting archives lying in the library root
+    unsorted: bool,
+    /// Pinned by the user to the top of the library
+    pinned: bool,
 }
 
 impl HomeCardItem {
     /// Create a card item for an entry
     #[allow(dead_code)]
     fn from_entry(
-        entry_id: &str,
-        entry_title: &str,
+        entry: &Entry,
         book_id: &str,
         book_title: &str,
-        pages: usize,
         entry_path: &str,
+        cover_version: &str,
     ) -> Self {
+        let entry_title = entry.title.as_str();
         Self {
-            id: entry_id.to_string(),
+            id: entry.id.clone(),
             is_entry: true,
-            display_name: entry_title.to_string(),
-            cover_url: format!("/api/cover/{}/{}", book_id, entry_id),
+            display_name: entry.display_title.clone(),
+            cover_url: paths::cover(book_id, &entry.id, cover_version),
+            url: paths::reader_continue(book_id, &entry.id),
             book_id: book_id.to_string(),
             book_display_name: book_title.to_string(),
-            pages,
+            book_url: paths::book(book_id),
+            pages: entry.pages,
             encoded_path: percent_encoding::percent_encode(
                 entry_path.as_bytes(),
                 percent_encoding::NON_ALPHANUMERIC,
@@ -130,30 +163,37 @@ impl HomeCardItem {
             grouped_count: None,
             title: Some(entry_title.to_string()),
             sort_title: Some(entry_title.to_string()),
+            aliases: String::new(),
+            read_count: 0,
+            unsorted: false,
+            pinned: false,
         }
     }
 
+    /// The placeholder cover for an entry of a title locked for the user
+    fn hide_locked(mut self, locks: &TagLocks) -> Self {
+        self.cover_url = locks.cover_url(&self.book_id, self.cover_url);
+        self
+    }
+
     /// Create a card item for a title
     #[allow(dead_code)]
-    fn from_title(title_id: &str, title_name: &str, entry_count: usize, first_entry_id: Option<&str>) -> Self {
+    fn from_title(title_id: &str, title_name: &str, entry_count: usize, cover_url: String) -> Self {
         let content_label = if entry_count == 1 {
             "1 entry".to_string()
         } else {
             format!("{} entries", entry_count)
         };
 
-        // Cover URL uses first entry's cover if available (requires both tid and eid)
-        let cover_url = first_entry_id
-            .map(|eid| format!("/api/cover/{}/{}", title_id, eid))
-            .unwrap_or_else(|| "/static/img/placeholder.png".to_string());
-
         Self {
             id: title_id.to_string(),
             is_entry: false,
             display_name: title_name.to_string(),
             cover_url,
+            url: paths::book(title_id),
             book_id: String::new(),
             book_display_name: String::new(),
+            book_url: String::new(),
             pages: 0,
             encoded_path: String::new(),
             encoded_title: String::new(),
@@ -163,6 +203,10 @@ impl HomeCardItem {
             grouped_count: None,
             title: Some(title_name.to_string()),
             sort_title: Some(title_name.to_string()),
+            aliases: String::new(),
+            read_count: 0,
+            unsorted: false,
+            pinned: false,
         }
     }
 }
@@ -187,7 +231,7 @@ struct RecentlyAddedItem {
 #[derive(Template)]
 #[template(path = "home.html")]
 struct HomeTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
     // User state
     new_user: bool,
     empty_library: bool,
@@ -202,7 +246,12 @@ struct HomeTemplate {
 }
 
 /// GET / - Home page with Continue Reading, Start Reading, Recently Added (requires authentication)
-pub async fn home(State(state): State<AppState>, user: User) -> Result<Html<String>> {
+/// Covers of titles locked for the user (see `TagLocks`) show the placeholder
+pub async fn home(
+    State(state): State<AppState>,
+    user: User,
+    session: Session,
+) -> Result<Html<String>> {
     // Get library stats to determine empty_library
     let (title_count, has_any_progress) = {
         let lib = state.library.load();
@@ -212,11 +261,9 @@ pub async fn home(State(state): State<AppState>, user: User) -> Result<Html<Stri
         // For now, we'll do a simple check - iterate through titles and check progress
         let mut has_progress = false;
         for title in lib.get_titles() {
-            if let Ok(progress) = title.get_title_progress(&user.username).await {
-                if progress > 0.0 {
-                    has_progress = true;
-                    break;
-                }
+            if lib.get_title_progress(title, &user.username) > 0.0 {
+                has_progress = true;
+                break;
             }
         }
 
@@ -226,130 +273,132 @@ pub async fn home(State(state): State<AppState>, user: User) -> Result<Html<Stri
     let empty_library = title_count == 0;
     let new_user = !has_any_progress;
 
-    // Get library path and config path from state
-    let library_path = state.config.library_path.display().to_string();
-    let config_path = dirs::config_dir()
-        .map(|p| p.join("mango/config.yml").display().to_string())
-        .unwrap_or_else(|| "~/.config/mango/config.yml".to_string());
+    // Server paths are only shown to admins, who can act on them
+    let (library_path, config_path) = if user.is_admin {
+        let config_path = dirs::config_dir()
+            .map(|p| p.join("mango/config.yml").display().to_string())
+            .unwrap_or_else(|| "~/.config/mango/config.yml".to_string());
+        (state.config.library_path.display().to_string(), config_path)
+    } else {
+        (String::new(), String::new())
+    };
     let scan_interval = state.config.scan_interval_minutes;
+    let locks = TagLocks::load(&state, &session, &user.username).await?;
 
     // Get home page content sections
     let (continue_reading, start_reading, recently_added) = {
-        use crate::library::progress::TitleInfo;
-
         let lib = state.library.load();
-        let mut cr_items = Vec::new();
-        let mut sr_items = Vec::new();
-        let mut ra_items = Vec::new();
+        let cache = lib.progress_cache();
+        let mut sr_titles = Vec::new();
+        let mut ra_entries = Vec::new();
 
         const MAX_ITEMS: usize = 8;
-        let one_month_ago = chrono::Utc::now().timestamp() - (30 * 24 * 60 * 60);
+        let one_month_ago = crate::util::time::now_ts() - (30 * 24 * 60 * 60);
 
-        // Collect data for all titles
-        for title in lib.get_titles() {
-            let info = match TitleInfo::load(&title.path).await {
-                Ok(info) => info,
-                Err(_) => continue,
-            };
+        // Continue reading: only entries that are partially read (0 < progress < 100%)
+        let recently_read = lib
+            .get_recently_read(&user.username, MAX_ITEMS, |entry, page| {
+                let percentage = lib.entry_percentage(entry, page);
+                percentage > 0.0 && percentage < 100.0
+            })
+            .await?;
 
+        // Collect data for all titles (progress cache lookups, no info.json reads)
+        for title in lib.get_titles() {
             // Check title progress for start_reading
-            let title_progress = title.get_title_progress(&user.username).await.unwrap_or(0.0);
-            if title_progress == 0.0 && sr_items.len() < MAX_ITEMS {
-                sr_items.push(HomeCardItem::from_title(
-                    &title.id,
-                    &title.title,
-                    title.entries.len(),
-                    title.entries.first().map(|e| e.id.as_str()),
-                ));
+            let title_progress = lib.get_title_progress(title, &user.username);
+            if title_progress == 0.0 && sr_titles.len() < MAX_ITEMS {
+                sr_titles.push(title);
             }
 
-            // Process entries for continue_reading and recently_added
-            for entry in &title.entries {
-                // Continue reading: entries with last_read timestamp
-                if let Some(last_read) = info.get_last_read(&user.username, &entry.id) {
-                    let progress = info.get_progress(&user.username, &entry.id).unwrap_or(0);
-                    let percentage = if entry.pages > 0 {
-                        (progress as f32 / entry.pages as f32) * 100.0
-                    } else {
-                        0.0
-                    };
-
-                    // Only include entries that are partially read (0 < progress < 100%)
-                    if percentage > 0.0 && percentage < 100.0 {
-                        cr_items.push((
-                            last_read,
-                            ContinueReadingItem {
-                                entry: HomeCardItem::from_entry(
-                                    &entry.id,
-                                    &entry.title,
-                                    &title.id,
-                                    &title.title,
-                                    entry.pages,
-                                    &entry.path.to_string_lossy(),
-                                ),
-                                percentage,
-                            },
-                        ));
-                    }
-                }
-
-                // Recently added: entries added within last month
-                if let Some(date_added) = info.get_date_added(&entry.id) {
+            // Recently added: entries added within last month, nested titles' included
+            let deep_entries = title
+                .deep_titles()
+                .into_iter()
+                .flat_map(|t| t.entries.iter().map(move |e| (t, e)));
+            for (title, entry) in deep_entries {
+                if let Some(date_added) = cache.get_date_added(&title.id, &entry.id) {
                     if date_added > one_month_ago {
-                        let progress = info.get_progress(&user.username, &entry.id).unwrap_or(0);
-                        let percentage = if entry.pages > 0 {
-                            (progress as f32 / entry.pages as f32) * 100.0
-                        } else {
-                            0.0
-                        };
-
-                        ra_items.push((
-                            date_added,
-                            RecentlyAddedItem {
-                                item: HomeCardItem::from_entry(
-                                    &entry.id,
-                                    &entry.title,
-                                    &title.id,
-                                    &title.title,
-                                    entry.pages,
-                                    &entry.path.to_string_lossy(),
-                                ),
-                                percentage,
-                                grouped_count: None,
-                            },
-                        ));
+                        let progress = cache
+                            .get_progress(&title.id, &user.username, &entry.id)
+                            .unwrap_or(0);
+                        let percentage = lib.entry_percentage(entry, progress);
+                        ra_entries.push((date_added, title, entry, percentage));
                     }
                 }
             }
         }
 
-        // Sort continue_reading by last_read (most recent first) and take top items
-        cr_items.sort_by(|a, b| b.0.cmp(&a.0));
-        let continue_reading: Vec<ContinueReadingItem> = cr_items
-            .into_iter()
-            .take(MAX_ITEMS)
-            .map(|(_, item)| item)
-            .collect();
-
         // Shuffle start_reading titles (random selection like original Mango)
         use rand::seq::SliceRandom;
-        let mut rng = rand::thread_rng();
-        sr_items.shuffle(&mut rng);
-        sr_items.truncate(MAX_ITEMS);
+        sr_titles.shuffle(&mut rand::thread_rng());
+        sr_titles.truncate(MAX_ITEMS);
 
         // Sort recently_added by date_added (most recent first)
-        ra_items.sort_by(|a, b| b.0.cmp(&a.0));
-        let recently_added: Vec<RecentlyAddedItem> = ra_items
+        ra_entries.sort_by(|a, b| b.0.cmp(&a.0));
+        ra_entries.truncate(MAX_ITEMS);
+
+        // Cover URLs carry the version of every cover shown
+        let cover_entries: Vec<&crate::library::Entry> = recently_read
+            .iter()
+            .map(|item| item.entry)
+            .chain(ra_entries.iter().map(|(_, _, entry, _)| *entry))
+            .chain(sr_titles.iter().filter_map(|t| t.entries.first()))
+            .collect();
+        let versions = super::api::cover_versions(&state, &cover_entries).await?;
+        let version = |id: &str| versions.get(id).map(String::as_str).unwrap_or_default();
+
+        let continue_reading: Vec<ContinueReadingItem> = recently_read
+            .iter()
+            .map(|item| ContinueReadingItem {
+                entry: HomeCardItem::from_entry(
+                    item.entry,
+                    &item.title.id,
+                    &item.title.title,
+                    &item.entry.relative_path(lib.path()),
+                    version(&item.entry.id),
+                )
+                .hide_locked(&locks),
+                percentage: lib.entry_percentage(item.entry, item.page),
+            })
+            .collect();
+
+        let sr_items: Vec<HomeCardItem> = sr_titles
+            .iter()
+            .map(|title| {
+                HomeCardItem::from_title(
+                    &title.id,
+                    &title.title,
+                    title.entries.len(),
+                    locks.cover_url(
+                        &title.id,
+                        super::api::title_cover_url(title, &versions, None),
+                    ),
+                )
+            })
+            .collect();
+
+        let recently_added: Vec<RecentlyAddedItem> = ra_entries
             .into_iter()
-            .take(MAX_ITEMS)
-            .map(|(_, item)| item)
+            .map(|(_, title, entry, percentage)| RecentlyAddedItem {
+                item: HomeCardItem::from_entry(
+                    entry,
+                    &title.id,
+                    &title.title,
+                    &entry.relative_path(lib.path()),
+                    version(&entry.id),
+                )
+                .hide_locked(&locks),
+                percentage,
+                grouped_count: None,
+            })
             .collect();
 
         (continue_reading, sr_items, recently_added)
     };
 
     let template = HomeTemplate {
-        nav: crate::util::NavigationState::home().with_admin(user.is_admin),
+        base: BaseContext::new(&user, NavPage::Home),
         new_user,
         empty_library,
         library_path,
@@ -363,28 +412,47 @@ pub async fn home(State(state): State<AppState>, user: User) -> Result<Html<Stri
     Ok(Html(template.render().map_err(render_error)?))
 }
 
+/// GET /library - Library page
+/// With Save-Data, cards link downscaled covers
 pub async fn library(
     State(state): State<AppState>,
     Query(params): Query<SortParams>,
+    Query(page_params): Query<LibraryPageParams>,
+    Query(filter_params): Query<ProgressFilterParams>,
     user: User,
-) -> Result<Html<String>> {
+    session: Session,
+    hints: ClientHints,
+) -> Result<impl IntoResponse> {
+    let preferences = state.storage.get_user_preferences(&user.username).await?;
+    let locks = TagLocks::load(&state, &session, &user.username).await?;
+
     // Get library path for loading/saving sort preferences
     let library_path = state.library.load().path().to_path_buf();
 
     // Load/save sort preferences from info.json
-    let (sort_method_str, ascending) =
+    let (mut sort_method_str, mut ascending) =
         crate::util::get_and_save_sort(&library_path, &user.username, &params).await?;
 
     // Parse sort method from string
-    let sort_method = SortMethod::parse(&sort_method_str);
+    // Popularity comes from read statistics, which only admins get to see; other users
+    // get plain name order rather than the reverse of it
+    let sort_method = match SortMethod::parse(&sort_method_str) {
+        SortMethod::Popularity if !user.is_admin => {
+            sort_method_str = "title".to_string();
+            ascending = true;
+            SortMethod::Name
+        }
+        method => method,
+    };
 
     // Get library statistics and title data
     let mut title_data_list = {
         let lib = state.library.load();
 
-        // For progress sorting, we need to calculate progress first, then sort
+        // For progress and popularity sorting, we need to calculate them first, then sort
         // For other methods, use the library's cached sorting
-        let sorted_titles = if matches!(sort_method, SortMethod::Progress) {
+        let sorted_titles = if matches!(sort_method, SortMethod::Progress | SortMethod::Popularity)
+        {
             lib.get_titles_sorted_cached(&user.username, SortMethod::Name, true)
                 .await // Get name-sorted as base
         } else {
@@ -392,10 +460,16 @@ pub async fn library(
                 .await
         };
 
-        // Calculate progress for each title
+        let first_entries: Vec<&crate::library::Entry> = sorted_titles
+            .iter()
+            .filter_map(|t| t.entries.first())
+            .collect();
+        let versions = super::api::cover_versions(&state, &first_entries).await?;
+
+        // Progress for each title, from the progress cache
         let mut title_data_list = Vec::new();
         for t in sorted_titles {
-            let progress_pct = t.get_title_progress(&user.username).await.unwrap_or(0.0);
+            let progress_pct = lib.get_title_progress(t, &user.username);
             title_data_list.push(TitleData {
                 id: t.id.clone(),
                 name: t.title.clone(),
@@ -403,6 +477,12 @@ pub async fn library(
                 progress: progress_pct,
                 progress_display: format!("{:.1}", progress_pct),
                 first_entry_id: t.entries.first().map(|e| e.id.clone()),
+                cover_url: locks.cover_url(
+                    &t.id,
+                    super::api::title_cover_url(t, &versions, hints.cover_width()),
+                ),
+                url: paths::book(&t.id),
+                unsorted: t.source == TitleSource::Unsorted,
             });
         }
 
@@ -414,17 +494,43 @@ pub async fn library(
         sort_by_progress(&mut title_data_list, ascending);
     }
 
+    // Popularity ranks by recent reads and downloads; ties keep name order
+    if matches!(sort_method, SortMethod::Popularity) {
+        let scores = state.stats.popularity_scores().await?;
+        let score = |td: &TitleData| scores.get(&td.id).copied().unwrap_or(0);
+        title_data_list.sort_by(|a, b| {
+            let ord = score(a).cmp(&score(b));
+            if ascending {
+                ord
+            } else {
+                ord.reverse()
+            }
+        });
+    }
+
+    // Filtered after sorting so the remaining titles keep their order
+    let hide = preferences.hide_completed;
+    let hidden_completed = filter_params.apply(&mut title_data_list, hide, |td| td.progress);
+
+    // Pinned titles lead whatever the sort, and so fill the first page
+    let pins = state.storage.list_pins(&user.username).await?;
+    let pinned = crate::util::pinned_first(&mut title_data_list, &pins, |td| td.id.as_str());
+
+    // Aliases are matched by the search box along with the title name
+    let aliases = state.storage.list_title_aliases().await?;
+
     // Convert TitleData to HomeCardItem and create LibraryItem list
     let mut titles = Vec::with_capacity(title_data_list.len());
     let mut items = Vec::with_capacity(title_data_list.len());
 
-    for td in title_data_list {
-        let card_item = HomeCardItem::from_title(
-            &td.id,
-            &td.name,
-            td.entry_count,
-            td.first_entry_id.as_deref(),
-        );
+    for (i, td) in title_data_list.into_iter().enumerate() {
+        let mut card_item =
+            HomeCardItem::from_title(&td.id, &td.name, td.entry_count, td.cover_url.clone());
+        if let Some(title_aliases) = aliases.get(&td.id) {
+            card_item.aliases = title_aliases.join("\n");
+        }
+        card_item.unsorted = td.unsorted;
+        card_item.pinned = i < pinned;
         items.push(LibraryItem {
             item: card_item.clone(),
             progress: td.progress as f64,
@@ -432,39 +538,68 @@ pub async fn library(
         titles.push(card_item);
     }
 
+    // Page through the sorted titles; `titles` keeps all of them for the count
+    let page_size = preferences.page_size(&state.config);
+    let total_pages = if page_size == 0 {
+        1
+    } else {
+        items.len().div_ceil(page_size).max(1)
+    };
+    let page = page_params.page.unwrap_or(1).clamp(1, total_pages);
+    if page_size > 0 {
+        items = items
+            .into_iter()
+            .skip((page - 1) * page_size)
+            .take(page_size)
+            .collect();
+    }
+
     // Build sort options matching original Mango
-    let sort_options = vec![
+    let mut sort_options = vec![
         ("auto".to_string(), "Auto".to_string()),
         ("title".to_string(), "Name".to_string()),
         ("time_modified".to_string(), "Date Modified".to_string()),
         ("progress".to_string(), "Progress".to_string()),
     ];
+    if user.is_admin {
+        sort_options.push(("popularity".to_string(), "Popularity".to_string()));
+    }
 
     // Build current sort option
     let sort_opt = Some(SortOption::new(&sort_method_str, ascending));
 
     let template = LibraryTemplate {
-        nav: crate::util::NavigationState::library().with_admin(user.is_admin),
+        base: BaseContext::new(&user, NavPage::Library),
         titles,
         items,
         sort_options,
         sort_opt,
+        density: preferences.card_density(&state.config).as_str(),
+        page_size,
+        page,
+        total_pages,
+        hidden_completed,
+        filter_query: filter_params.query_string(),
     };
 
-    Ok(Html(template.render().map_err(render_error)?))
+    Ok((
+        ClientHints::vary(),
+        Html(template.render().map_err(render_error)?),
+    ))
 }
 
 /// Change Password page template
 #[derive(Template)]
 #[template(path = "change-password.html")]
 struct ChangePasswordTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
 }
 
 /// GET /change-password - Change password page (requires authentication)
 pub async fn change_password_page(user: User) -> Result<Html<String>> {
     let template = ChangePasswordTemplate {
-        nav: crate::util::NavigationState::home().with_admin(user.is_admin), // No specific page active for change password
+        // No specific page active for change password
+        base: BaseContext::new(&user, NavPage::Home),
     };
 
     Ok(Html(template.render().map_err(render_error)?))
@@ -477,30 +612,53 @@ pub struct ChangePasswordRequest {
     pub new_password: String,
 }
 
+/// Response of the change password API endpoint
+#[derive(serde::Serialize)]
+pub struct ChangePasswordResponse {
+    /// Other login sessions that were logged out and have to log in again
+    pub sessions_revoked: u64,
+    /// App tokens and API tokens that stopped working and have to be recreated
+    pub tokens_revoked: u64,
+}
+
 /// POST /api/user/change-password - Change user's password (requires authentication)
+/// Logs out every other session of the user and revokes their app and API tokens, in case
+/// the old password was stolen
+/// Returns: 403 for read-only users, who often share one account
 pub async fn change_password_api(
     State(state): State<AppState>,
     user: User,
+    session: Session,
     axum::Json(request): axum::Json<ChangePasswordRequest>,
-) -> Result<axum::http::StatusCode> {
-    // Validate new password length
-    if request.new_password.len() < 6 {
-        return Err(crate::error::Error::BadRequest(
-            "New password must be at least 6 characters".to_string(),
+) -> Result<axum::Json<ChangePasswordResponse>> {
+    if !user.can_write {
+        return Err(crate::error::Error::Forbidden(
+            "Read-only users cannot change the password".to_string(),
         ));
     }
 
-    // Change the password
-    state
-        .storage
-        .change_password(
+    let current = session
+        .get::<String>(crate::auth::SESSION_TOKEN_KEY)
+        .await
+        .ok()
+        .flatten();
+    let changed = crate::auth::change_password(
+        &state.storage,
+        &user.username,
+        &request.new_password,
+        current.as_deref(),
+        state.storage.change_password(
             &user.username,
             &request.current_password,
             &request.new_password,
-        )
-        .await?;
-
-    Ok(axum::http::StatusCode::OK)
+        ),
+    )
+    .await?;
+
+    Ok(axum::Json(ChangePasswordResponse {
+        sessions_revoked: changed.sessions_revoked,
+        tokens_revoked: changed.tokens_revoked,
+    }))
 }
 
 // ========== Tags Page Handlers ==========
@@ -508,14 +666,14 @@ pub async fn change_password_api(
 #[derive(Template)]
 #[template(path = "tags.html")]
 struct TagsTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
     tags: Vec<TagWithCount>,
 }
 
 #[derive(serde::Serialize)]
 struct TagWithCount {
     tag: String,
-    encoded_tag: String,
+    url: String,
     count: usize,
 }
 
@@ -530,14 +688,9 @@ pub async fn list_tags_page(State(state): State<AppState>, user: User) -> Result
         let title_ids = storage.get_tag_titles(&tag).await?;
         let count = title_ids.len();
 
-        // URL-encode the tag for links
-        let encoded_tag =
-            percent_encoding::percent_encode(tag.as_bytes(), percent_encoding::NON_ALPHANUMERIC)
-                .to_string();
-
         tags_with_counts.push(TagWithCount {
+            url: paths::tag(&tag),
             tag,
-            encoded_tag,
             count,
         });
     }
@@ -550,7 +703,7 @@ pub async fn list_tags_page(State(state): State<AppState>, user: User) -> Result
     });
 
     let template = TagsTemplate {
-        nav: crate::util::NavigationState::tags().with_admin(user.is_admin),
+        base: BaseContext::new(&user, NavPage::Tags),
         tags: tags_with_counts,
     };
     Ok(Html(template.render().map_err(render_error)?))
@@ -559,7 +712,7 @@ pub async fn list_tags_page(State(state): State<AppState>, user: User) -> Result
 #[derive(Template)]
 #[template(path = "tag.html")]
 struct TagTemplate {
-    nav: crate::util::NavigationState,
+    base: crate::util::BaseContext,
     tag: String,
     title_count: usize,
     titles: Vec<TitleData>,
@@ -577,8 +730,10 @@ pub async fn view_tag_page(
     Path(tag): Path<String>,
     Query(params): Query<crate::util::SortParams>,
     user: User,
+    session: Session,
 ) -> Result<Html<String>> {
     let storage = &state.storage;
+    let locks = TagLocks::load(&state, &session, &user.username).await?;
     let lib = state.library.load();
 
     // Get all title IDs with this tag
@@ -591,6 +746,12 @@ pub async fn view_tag_page(
         )));
     }
 
+    let first_entries: Vec<&crate::library::Entry> = title_ids
+        .iter()
+        .filter_map(|id| lib.get_title(id)?.entries.first())
+        .collect();
+    let versions = super::api::cover_versions(&state, &first_entries).await?;
+
     // Get title objects for these IDs
     let mut titles: Vec<TitleData> = title_ids
         .iter()
@@ -601,8 +762,14 @@ pub async fn view_tag_page(
                     name: title.title.clone(),
                     entry_count: title.entries.len(),
                     first_entry_id: title.entries.first().map(|e| e.id.clone()),
+                    cover_url: locks.cover_url(
+                        &title.id,
+                        super::api::title_cover_url(title, &versions, None),
+                    ),
+                    url: paths::book(&title.id),
                     progress: 0.0, // Will be filled later
                     progress_display: String::from("0.0"),
+                    unsorted: title.source == TitleSource::Unsorted,
                 }
             })
         })
@@ -652,8 +819,11 @@ pub async fn view_tag_page(
                 crate::routes::sort_by_progress(&mut titles, false);
             }
         }
-        crate::library::SortMethod::Auto => {
-            // Auto sort defaults to Name ascending
+        crate::library::SortMethod::Auto
+        | crate::library::SortMethod::Popularity
+        | crate::library::SortMethod::Opened => {
+            // Auto sort defaults to Name ascending; the tag page has no popularity sort and
+            // last opened only applies to entries
             titles.sort_by(|a, b| natord::compare(&a.name, &b.name));
         }
     }
@@ -677,11 +847,16 @@ pub async fn view_tag_page(
         }
         (crate::library::SortMethod::Progress, true) => (false, false, false, false, true, false),
         (crate::library::SortMethod::Progress, false) => (false, false, false, false, false, true),
-        (crate::library::SortMethod::Auto, _) => (true, false, false, false, false, false),
+        (
+            crate::library::SortMethod::Auto
+            | crate::library::SortMethod::Popularity
+            | crate::library::SortMethod::Opened,
+            _,
+        ) => (true, false, false, false, false, false),
     };
 
     let template = TagTemplate {
-        nav: crate::util::NavigationState::tags().with_admin(user.is_admin),
+        base: BaseContext::new(&user, NavPage::Tags),
         tag,
         title_count: titles.len(),
         titles,
@@ -695,3 +870,62 @@ pub async fn view_tag_page(
 
     Ok(Html(template.render().map_err(render_error)?))
 }
+
+// ========== Collection Page Handler ==========
+
+#[derive(Template)]
+#[template(path = "collection.html")]
+struct CollectionTemplate {
+    base: crate::util::BaseContext,
+    name: String,
+    items: Vec<LibraryItem>,
+}
+
+/// GET /collections/:id - One of the user's collections, its titles in the user's order
+/// Titles no longer in the library are left out; other users' collections are not found
+pub async fn view_collection_page(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    user: User,
+    session: Session,
+    hints: ClientHints,
+) -> Result<Html<String>> {
+    let locks = TagLocks::load(&state, &session, &user.username).await?;
+    let collection = state
+        .storage
+        .get_collection(&user.username, &id)
+        .await?
+        .ok_or_else(|| crate::error::Error::NotFound(format!("Collection not found: {}", id)))?;
+
+    let lib = state.library.load();
+    let titles: Vec<&crate::library::Title> = collection
+        .title_ids
+        .iter()
+        .filter_map(|id| lib.get_title(id))
+        .collect();
+    let first_entries: Vec<&Entry> = titles.iter().filter_map(|t| t.entries.first()).collect();
+    let versions = super::api::cover_versions(&state, &first_entries).await?;
+
+    let items = titles
+        .iter()
+        .map(|t| {
+            let cover_url = locks.cover_url(
+                &t.id,
+                super::api::title_cover_url(t, &versions, hints.cover_width()),
+            );
+            let mut item = HomeCardItem::from_title(&t.id, &t.title, t.entries.len(), cover_url);
+            item.unsorted = t.source == TitleSource::Unsorted;
+            LibraryItem {
+                item,
+                progress: lib.get_title_progress(t, &user.username) as f64,
+            }
+        })
+        .collect();
+
+    let template = CollectionTemplate {
+        base: BaseContext::new(&user, NavPage::Library),
+        name: collection.name,
+        items,
+    };
+    Ok(Html(template.render().map_err(render_error)?))
+}
@@ -1,33 +1,63 @@
 pub mod admin;
 pub mod api;
+pub mod api_tokens;
 pub mod book;
+pub mod capabilities;
+pub mod events;
+pub mod feed;
+pub mod komga;
 pub mod login;
 pub mod main;
 pub mod opds;
+pub mod paths;
 pub mod progress;
 pub mod reader;
+pub mod tag_locks;
+pub mod two_factor;
 
 pub use admin::{
-    admin_dashboard, bulk_progress, cache_clear_api, cache_debug_page, cache_invalidate_api,
-    cache_load_library_api, cache_save_library_api, create_user, delete_all_missing_entries,
-    delete_missing_entry, delete_user, delete_user_api, generate_thumbnails, get_missing_entries,
-    get_users, missing_items_page, scan_library, thumbnail_progress, update_display_name,
-    update_sort_title, update_user, upload_cover, user_edit_page, user_edit_post,
-    user_edit_post_existing, users_page,
+    admin_dashboard, backfill_date_added, bulk_progress, cache_clear_api, cache_debug_page,
+    cache_invalidate_api, cache_load_library_api, cache_save_library_api, cache_stats_api,
+    clear_entry_order, conversion_status, create_user, delete_all_missing_entries,
+    delete_missing_entry, delete_user, delete_user_api, generate_thumbnails, get_all_quotas,
+    get_bad_pages, get_diagnostics, get_missing_entries, get_popular_titles, get_reports,
+    get_title_files, get_title_readers, get_users, merge_entries, missing_items_page, popular_page,
+    prune_thumbnails_api, rebuild_signatures, reports_page, reset_password, reset_two_factor,
+    resolve_report, resume_scan, scan_library, scan_status, scan_title, set_entry_direction,
+    set_entry_order, set_title_aliases, set_title_direction, set_title_relations, start_conversion,
+    thumbnail_progress, unmerge_entries, update_display_name, update_sort_title, update_user,
+    upload_cover, user_edit_page, user_edit_post, user_edit_post_existing, users_page,
 };
 pub use api::{
-    add_tag, continue_reading, delete_tag, download_entry, get_cover, get_dimensions, get_library,
-    get_page, get_stats, get_title, get_title_tags, list_tags, recently_added, start_reading,
+    add_collection_title, add_tag, continue_reading, create_collection, delete_collection,
+    delete_tag, download_entry, get_collection, get_cover, get_dimensions, get_library,
+    get_library_index, get_library_letters, get_page, get_page_thumbnails,
+    get_page_thumbnails_sprite, get_preferences, get_quota, get_stats, get_title, get_title_cover,
+    get_title_tags, head_cover, head_title_cover, list_collections, list_pins, list_tags,
+    pin_title, recently_added, remove_collection_title, rename_collection, report_entry, search,
+    set_collection_order, set_hide_completed, set_preferences, start_reading, unpin_title,
     update_progress,
 };
+pub use api_tokens::{create_api_token, delete_api_token, list_api_tokens};
 pub use book::get_book;
-pub use login::{get_login, logout, post_login};
+pub use capabilities::{get_capabilities, get_version};
+pub use events::library_events;
+pub use feed::{create_feed_token, delete_feed_token, feed_recent, feed_title, get_feed_token};
+pub use komga::{get_komga_book, komga_not_implemented, patch_komga_read_progress};
+pub use login::{
+    branding_logo, get_login, list_sessions, logout, post_login, post_login_2fa, revoke_sessions,
+};
 pub use main::{
-    change_password_api, change_password_page, home, library, list_tags_page, view_tag_page,
+    change_password_api, change_password_page, home, library, list_tags_page, view_collection_page,
+    view_tag_page,
 };
-pub use opds::{opds_index, opds_title};
-pub use progress::{get_all_progress, get_progress, save_progress};
+pub use opds::{opds_collection, opds_continue, opds_index, opds_recent, opds_title};
+pub use progress::{bulk_read, get_all_progress, get_progress, get_progress_csv, save_progress};
 pub use reader::{reader, reader_continue};
+pub use tag_locks::{list_tag_locks, remove_tag_lock, set_tag_lock, unlock_tags, TagLocks};
+pub use two_factor::{
+    create_app_token, delete_app_token, two_factor_enable, two_factor_enroll, two_factor_status,
+};
 
 /// Trait for types that have a progress field (as f32 percentage)
 pub trait HasProgress {
@@ -5,27 +5,51 @@ use axum::{
     response::IntoResponse,
 };
 
-use crate::{error::Result, AppState};
+use super::{paths, TagLocks};
+use crate::{
+    error::Result,
+    library::{EntrySource, SortMethod},
+    util::ArchiveKind,
+    AppState,
+};
+
+/// Titles listed by the continue reading and recently added feeds
+const OPDS_FEED_LIMIT: usize = 20;
 
-/// Template for OPDS main catalog feed
+/// Template for OPDS navigation feeds: the main catalog and its title lists
 #[derive(Template)]
 #[template(path = "opds_index.xml", escape = "xml")]
 struct OPDSIndexTemplate {
-    base_url: String,
+    instance_name: String,
+    feed_id: String,
+    feed_title: String,
+    opds_url: String,
+    self_url: String,
+    links: Vec<OPDSNavLink>,
     titles: Vec<OPDSTitleEntry>,
 }
 
+/// Link from the main catalog to one of its sub-feeds
+struct OPDSNavLink {
+    id: String,
+    name: String,
+    rel: String,
+    feed_url: String,
+}
+
 /// Simplified title entry for OPDS index
 struct OPDSTitleEntry {
     id: String,
     name: String,
+    feed_url: String,
 }
 
 /// Template for OPDS title detail feed
 #[derive(Template)]
 #[template(path = "opds_title.xml", escape = "xml")]
 struct OPDSTitleTemplate {
-    base_url: String,
+    instance_name: String,
+    opds_url: String,
     title: OPDSTitleInfo,
     entries: Vec<OPDSEntryInfo>,
 }
@@ -34,6 +58,7 @@ struct OPDSTitleTemplate {
 struct OPDSTitleInfo {
     id: String,
     name: String,
+    feed_url: String,
 }
 
 /// Entry information for OPDS
@@ -41,32 +66,30 @@ struct OPDSEntryInfo {
     id: String,
     title: String,
     mime_type: String,
+    /// Archive size in bytes, advertised on the acquisition link
+    length: Option<u64>,
+    cover_url: String,
+    download_url: String,
+    reader_url: String,
+    book_url: String,
 }
 
-/// OPDS route: GET /opds
-/// Returns the main catalog feed listing all titles
-pub async fn opds_index(
-    State(state): State<AppState>,
-    _username: crate::auth::Username,
-) -> Result<impl IntoResponse> {
-    let lib = state.library.load();
-    let titles = lib.get_titles();
-
-    let opds_titles: Vec<OPDSTitleEntry> = titles
+/// OPDS entries linking to the acquisition feeds of `titles`
+fn title_entries(titles: &[&crate::library::Title]) -> Vec<OPDSTitleEntry> {
+    titles
         .iter()
         .map(|t| OPDSTitleEntry {
             id: t.id.clone(),
             name: t.title.clone(),
+            feed_url: paths::opds_book(&t.id),
         })
-        .collect();
-
-    let template = OPDSIndexTemplate {
-        base_url: get_base_url(&state),
-        titles: opds_titles,
-    };
+        .collect()
+}
 
+/// Render a navigation feed listing titles
+fn navigation_feed(template: OPDSIndexTemplate) -> Result<impl IntoResponse> {
     let xml = template.render().map_err(|e| {
-        crate::error::Error::Internal(format!("Failed to render OPDS index: {}", e))
+        crate::error::Error::Internal(format!("Failed to render OPDS navigation feed: {}", e))
     })?;
 
     Ok((
@@ -79,13 +102,152 @@ pub async fn opds_index(
     ))
 }
 
+/// OPDS route: GET /opds
+/// Returns the main catalog feed listing all titles, linking the user's collections
+/// Titles behind one of the user's tag locks are left out of every OPDS feed, as an
+/// e-reader has no way to enter the PIN (see `TagLocks::load_all`)
+pub async fn opds_index(
+    State(state): State<AppState>,
+    crate::auth::Username(username): crate::auth::Username,
+) -> Result<impl IntoResponse> {
+    let collections = state.storage.list_collections(&username).await?;
+    let locks = TagLocks::load_all(&state, &username).await?;
+
+    let lib = state.library.load();
+    let mut titles = lib.get_titles();
+    titles.retain(|t| !locks.is_locked(&t.id));
+
+    // Keep the historical "Library" title unless the instance has its own name
+    let instance_name = state.config.instance_name.clone();
+    let feed_title = if instance_name == crate::config::DEFAULT_INSTANCE_NAME {
+        "Library".to_string()
+    } else {
+        instance_name.clone()
+    };
+
+    // E-readers without search reach the titles in progress and new ones from here
+    let mut links = vec![
+        OPDSNavLink {
+            id: "continue".to_string(),
+            name: "Continue Reading".to_string(),
+            rel: "subsection".to_string(),
+            feed_url: paths::opds_continue(),
+        },
+        OPDSNavLink {
+            id: "recent".to_string(),
+            name: "Recently Added".to_string(),
+            rel: "http://opds-spec.org/sort/new".to_string(),
+            feed_url: paths::opds_recent(),
+        },
+    ];
+    links.extend(collections.into_iter().map(|c| OPDSNavLink {
+        feed_url: paths::opds_collection(&c.id),
+        id: format!("collection:{}", c.id),
+        name: c.name,
+        rel: "subsection".to_string(),
+    }));
+
+    navigation_feed(OPDSIndexTemplate {
+        instance_name,
+        feed_id: "index".to_string(),
+        feed_title,
+        opds_url: paths::opds(),
+        self_url: paths::opds(),
+        links,
+        titles: title_entries(&titles),
+    })
+}
+
+/// OPDS route: GET /opds/continue
+/// Returns the titles the user read most recently
+pub async fn opds_continue(
+    State(state): State<AppState>,
+    crate::auth::Username(username): crate::auth::Username,
+) -> Result<impl IntoResponse> {
+    let locks = TagLocks::load_all(&state, &username).await?;
+    let lib = state.library.load();
+    let mut titles = lib
+        .get_recently_read_titles(&username, OPDS_FEED_LIMIT)
+        .await?;
+    titles.retain(|t| !locks.is_locked(&t.id));
+
+    navigation_feed(OPDSIndexTemplate {
+        instance_name: state.config.instance_name.clone(),
+        feed_id: "continue".to_string(),
+        feed_title: "Continue Reading".to_string(),
+        opds_url: paths::opds(),
+        self_url: paths::opds_continue(),
+        links: Vec::new(),
+        titles: title_entries(&titles),
+    })
+}
+
+/// OPDS route: GET /opds/recent
+/// Returns the titles with the most recently added entries
+pub async fn opds_recent(
+    State(state): State<AppState>,
+    crate::auth::Username(username): crate::auth::Username,
+) -> Result<impl IntoResponse> {
+    let locks = TagLocks::load_all(&state, &username).await?;
+    let lib = state.library.load();
+    let mut titles = lib.get_recently_added_titles(OPDS_FEED_LIMIT);
+    titles.retain(|t| !locks.is_locked(&t.id));
+
+    navigation_feed(OPDSIndexTemplate {
+        instance_name: state.config.instance_name.clone(),
+        feed_id: "recent".to_string(),
+        feed_title: "Recently Added".to_string(),
+        opds_url: paths::opds(),
+        self_url: paths::opds_recent(),
+        links: Vec::new(),
+        titles: title_entries(&titles),
+    })
+}
+
+/// OPDS route: GET /opds/collection/:id
+/// Returns the titles of one of the user's collections, in the user's order
+pub async fn opds_collection(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    crate::auth::Username(username): crate::auth::Username,
+) -> Result<impl IntoResponse> {
+    let collection = state
+        .storage
+        .get_collection(&username, &id)
+        .await?
+        .ok_or_else(|| crate::error::Error::NotFound(format!("Collection not found: {}", id)))?;
+    let locks = TagLocks::load_all(&state, &username).await?;
+
+    let lib = state.library.load();
+    let titles: Vec<&crate::library::Title> = collection
+        .title_ids
+        .iter()
+        .filter(|id| !locks.is_locked(id))
+        .filter_map(|id| lib.get_title(id))
+        .collect();
+
+    navigation_feed(OPDSIndexTemplate {
+        instance_name: state.config.instance_name.clone(),
+        feed_id: format!("collection:{}", collection.id),
+        feed_title: collection.name,
+        opds_url: paths::opds(),
+        self_url: paths::opds_collection(&collection.id),
+        links: Vec::new(),
+        titles: title_entries(&titles),
+    })
+}
+
 /// OPDS route: GET /opds/book/:title_id
 /// Returns a feed for a specific title showing all its entries
+/// Returns: 423 Locked for a title behind one of the user's tag locks
 pub async fn opds_title(
     State(state): State<AppState>,
     Path(title_id): Path<String>,
-    _username: crate::auth::Username,
+    crate::auth::Username(username): crate::auth::Username,
 ) -> Result<impl IntoResponse> {
+    TagLocks::load_all(&state, &username)
+        .await?
+        .check(&title_id)?;
     let lib = state.library.load();
 
     // Get the title
@@ -96,20 +258,54 @@ pub async fn opds_title(
     let opds_title = OPDSTitleInfo {
         id: title.id.clone(),
         name: title.title.clone(),
+        feed_url: paths::opds_book(&title.id),
     };
 
-    let opds_entries: Vec<OPDSEntryInfo> = title
-        .entries
+    // Merged entries and image directories have no single file to acquire (the download
+    // route refuses them), so they are left out rather than given broken links
+    let entries: Vec<_> = lib
+        .get_entries_sorted(title, SortMethod::Name, true)
+        .into_iter()
+        .filter(|e| e.parts.is_empty() && e.source != EntrySource::Directory)
+        .collect();
+    let versions = super::api::cover_versions(&state, &entries).await?;
+
+    // Sniff each archive's real format and size off the async runtime
+    let entry_paths: Vec<std::path::PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+    let file_info = tokio::task::spawn_blocking(move || {
+        entry_paths
+            .iter()
+            .map(|path| {
+                let length = std::fs::metadata(path).ok().map(|m| m.len());
+                (ArchiveKind::detect(path), length)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| crate::error::Error::Internal(format!("Task join error: {}", e)))?;
+
+    let opds_entries: Vec<OPDSEntryInfo> = entries
         .iter()
-        .map(|e| OPDSEntryInfo {
+        .zip(file_info)
+        .map(|(e, (kind, length))| OPDSEntryInfo {
             id: e.id.clone(),
-            title: e.title.clone(),
-            mime_type: get_mime_type(&e.path),
+            title: e.display_title.clone(),
+            mime_type: kind.mime_type().to_string(),
+            length,
+            cover_url: paths::cover(
+                &title.id,
+                &e.id,
+                versions.get(&e.id).map(String::as_str).unwrap_or_default(),
+            ),
+            download_url: paths::download(&title.id, &e.id),
+            reader_url: paths::reader(&title.id, &e.id, 1),
+            book_url: paths::book(&title.id),
         })
         .collect();
 
     let template = OPDSTitleTemplate {
-        base_url: get_base_url(&state),
+        instance_name: state.config.instance_name.clone(),
+        opds_url: paths::opds(),
         title: opds_title,
         entries: opds_entries,
     };
@@ -127,18 +323,3 @@ pub async fn opds_title(
         xml,
     ))
 }
-
-/// Get base URL from config or default to "/"
-fn get_base_url(_state: &AppState) -> String {
-    // For now, return root path - can be made configurable later
-    "/".to_string()
-}
-
-/// Determine MIME type from file path
-fn get_mime_type(path: &std::path::Path) -> String {
-    match path.extension().and_then(|e| e.to_str()) {
-        Some("cbz") | Some("zip") => "application/zip".to_string(),
-        Some("cbr") | Some("rar") => "application/x-rar-compressed".to_string(),
-        _ => "application/octet-stream".to_string(),
-    }
-}
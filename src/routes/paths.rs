@@ -0,0 +1,559 @@
+// Route paths - single source of truth for every URL the server knows about
+//
+// Provides:
+// 1. Route patterns - the constants registered with the router in server.rs
+// 2. URL builders - typed functions for links and redirects, prefixed with base_url
+//
+// Use these instead of hand-written path strings so renaming a route is a one-line change.
+
+use percent_encoding::{percent_encode, NON_ALPHANUMERIC};
+
+// ========== Route Patterns ==========
+
+pub const HOME: &str = "/";
+pub const LOGIN: &str = "/login";
+pub const LOGIN_2FA: &str = "/login/2fa";
+pub const LOGOUT: &str = "/logout";
+pub const STATIC: &str = "/static";
+pub const BRANDING_LOGO: &str = "/branding/logo";
+pub const LIBRARY: &str = "/library";
+pub const BOOK: &str = "/book/:id";
+pub const CHANGE_PASSWORD: &str = "/change-password";
+pub const TAGS: &str = "/tags";
+pub const TAG: &str = "/tags/:tag";
+pub const COLLECTION: &str = "/collections/:id";
+pub const ADMIN: &str = "/admin";
+pub const ADMIN_MISSING_ITEMS: &str = "/admin/missing-items";
+pub const ADMIN_REPORTS: &str = "/admin/reports";
+pub const ADMIN_STATS: &str = "/admin/stats";
+pub const ADMIN_USERS: &str = "/admin/user";
+pub const ADMIN_USER_EDIT: &str = "/admin/user/edit";
+pub const ADMIN_USER_EDIT_EXISTING: &str = "/admin/user/edit/:username";
+pub const DEBUG_CACHE: &str = "/debug/cache";
+pub const READER_CONTINUE: &str = "/reader/:tid/:eid";
+pub const READER: &str = "/reader/:tid/:eid/:page";
+pub const OPDS: &str = "/opds";
+pub const OPDS_BOOK: &str = "/opds/book/:title_id";
+pub const OPDS_CONTINUE: &str = "/opds/continue";
+pub const OPDS_RECENT: &str = "/opds/recent";
+pub const OPDS_COLLECTION: &str = "/opds/collection/:id";
+pub const FEED: &str = "/feed";
+pub const FEED_RECENT: &str = "/feed/recent.atom";
+/// `:file` is `<title id>.atom` (a parameter always spans the whole segment)
+pub const FEED_TITLE: &str = "/feed/title/:file";
+
+pub const API_ADMIN_SCAN: &str = "/api/admin/scan";
+pub const API_ADMIN_SCAN_RESUME: &str = "/api/admin/scan/resume";
+pub const API_ADMIN_SCAN_STATUS: &str = "/api/admin/scan/status";
+pub const API_ADMIN_SCAN_TITLE: &str = "/api/admin/scan/:tid";
+pub const API_ADMIN_REBUILD_SIGNATURES: &str = "/api/admin/maintenance/rebuild-signatures";
+pub const API_ADMIN_BACKFILL_DATE_ADDED: &str = "/api/admin/maintenance/backfill-date-added";
+pub const API_ADMIN_PRUNE_THUMBNAILS: &str = "/api/admin/maintenance/prune-thumbnails";
+pub const API_ADMIN_CONVERT: &str = "/api/admin/maintenance/convert";
+pub const API_CACHE_CLEAR: &str = "/api/cache/clear";
+pub const API_CACHE_SAVE_LIBRARY: &str = "/api/cache/save-library";
+pub const API_CACHE_LOAD_LIBRARY: &str = "/api/cache/load-library";
+pub const API_CACHE_INVALIDATE: &str = "/api/cache/invalidate";
+pub const API_CACHE_STATS: &str = "/api/cache/stats";
+pub const API_ADMIN_MISSING_ENTRIES: &str = "/api/admin/entries/missing";
+pub const API_ADMIN_MISSING_ENTRY: &str = "/api/admin/entries/missing/:id";
+pub const API_ADMIN_USERS: &str = "/api/admin/users";
+pub const API_ADMIN_USER: &str = "/api/admin/users/:username";
+pub const API_ADMIN_USER_DELETE: &str = "/api/admin/user/delete/:username";
+pub const API_ADMIN_USER_TWO_FACTOR: &str = "/api/admin/users/:username/two-factor";
+pub const API_ADMIN_USER_RESET_PASSWORD: &str = "/api/admin/users/:username/reset-password";
+pub const API_LIBRARY: &str = "/api/library";
+pub const API_LIBRARY_INDEX: &str = "/api/library/index";
+pub const API_LIBRARY_LETTERS: &str = "/api/library/index_letters";
+pub const API_EVENTS: &str = "/api/events";
+pub const API_TITLE: &str = "/api/title/:id";
+pub const API_PAGE: &str = "/api/page/:tid/:eid/:page";
+pub const API_COVER: &str = "/api/cover/:tid/:eid";
+pub const API_TITLE_COVER: &str = "/api/title/:id/cover";
+pub const API_STATS: &str = "/api/stats";
+pub const API_CAPABILITIES: &str = "/api/capabilities";
+pub const API_VERSION: &str = "/api/version";
+pub const API_SEARCH: &str = "/api/search";
+pub const API_DOWNLOAD: &str = "/api/download/:tid/:eid";
+pub const API_TAGS: &str = "/api/tags";
+pub const API_TITLE_TAGS: &str = "/api/tags/:tid";
+pub const API_ADMIN_TAG: &str = "/api/admin/tags/:tid/:tag";
+pub const API_CONTINUE_READING: &str = "/api/library/continue_reading";
+pub const API_START_READING: &str = "/api/library/start_reading";
+pub const API_RECENTLY_ADDED: &str = "/api/library/recently_added";
+pub const API_PROGRESS_ENTRY: &str = "/api/progress/:tid/:page";
+pub const API_PROGRESS: &str = "/api/progress";
+pub const API_PROGRESS_BULK_READ: &str = "/api/progress/bulk_read";
+pub const API_DIMENSIONS: &str = "/api/dimensions/:tid/:eid";
+pub const API_PAGE_THUMBNAILS: &str = "/api/entry/:tid/:eid/page_thumbnails";
+pub const API_PAGE_THUMBNAILS_SPRITE: &str = "/api/entry/:tid/:eid/page_thumbnails/sprite";
+pub const API_CHANGE_PASSWORD: &str = "/api/user/change-password";
+pub const API_USER_QUOTA: &str = "/api/user/quota";
+pub const API_USER_PREFERENCES: &str = "/api/user/preferences";
+pub const API_USER_HIDE_COMPLETED: &str = "/api/user/preferences/hide-completed";
+pub const API_USER_PROGRESS_CSV: &str = "/api/user/progress.csv";
+pub const API_PINS: &str = "/api/pins";
+pub const API_PIN: &str = "/api/pins/:tid";
+pub const API_COLLECTIONS: &str = "/api/collections";
+pub const API_COLLECTION: &str = "/api/collections/:id";
+pub const API_COLLECTION_TITLE: &str = "/api/collections/:id/titles/:tid";
+pub const API_COLLECTION_ORDER: &str = "/api/collections/:id/order";
+pub const API_TWO_FACTOR: &str = "/api/user/two-factor";
+pub const API_TWO_FACTOR_ENROLL: &str = "/api/user/two-factor/enroll";
+pub const API_TWO_FACTOR_ENABLE: &str = "/api/user/two-factor/enable";
+pub const API_APP_TOKENS: &str = "/api/user/app-tokens";
+pub const API_APP_TOKEN: &str = "/api/user/app-tokens/:id";
+pub const API_API_TOKENS: &str = "/api/user/tokens";
+pub const API_API_TOKEN: &str = "/api/user/tokens/:id";
+pub const API_FEED_TOKEN: &str = "/api/user/feed-token";
+pub const API_SESSIONS: &str = "/api/user/sessions";
+pub const API_SESSIONS_REVOKE_ALL: &str = "/api/user/sessions/revoke-all";
+pub const API_TAG_LOCKS: &str = "/api/user/tag-locks";
+pub const API_TAG_LOCK: &str = "/api/user/tag-locks/:tag";
+pub const API_UNLOCK: &str = "/api/unlock";
+pub const API_ADMIN_QUOTA: &str = "/api/admin/quota";
+pub const API_ADMIN_DIAGNOSTICS: &str = "/api/admin/diagnostics";
+pub const API_ADMIN_BAD_PAGES: &str = "/api/admin/bad_pages";
+pub const API_ADMIN_POPULAR: &str = "/api/admin/stats/popular";
+pub const API_REPORT: &str = "/api/report/:tid/:eid";
+pub const API_ADMIN_REPORTS: &str = "/api/admin/reports";
+pub const API_ADMIN_REPORT: &str = "/api/admin/reports/:id";
+pub const API_ADMIN_DISPLAY_NAME: &str = "/api/admin/display_name/:tid/:name";
+pub const API_ADMIN_SORT_TITLE: &str = "/api/admin/sort_title/:tid";
+pub const API_TITLE_DIRECTION: &str = "/api/title/:id/direction";
+pub const API_ENTRY_DIRECTION: &str = "/api/entry/:tid/:eid/direction";
+pub const API_ADMIN_ENTRY_ORDER: &str = "/api/admin/title/:id/entry_order";
+pub const API_ADMIN_MERGE_ENTRIES: &str = "/api/admin/title/:id/merge_entries";
+pub const API_ADMIN_MERGED_ENTRY: &str = "/api/admin/title/:id/merge_entries/:eid";
+pub const API_ADMIN_TITLE_ALIASES: &str = "/api/admin/title/:id/aliases";
+pub const API_ADMIN_TITLE_RELATIONS: &str = "/api/admin/title/:id/relations";
+pub const API_ADMIN_TITLE_FILES: &str = "/api/admin/title/:id/files";
+pub const API_ADMIN_TITLE_READERS: &str = "/api/admin/title/:id/readers";
+pub const API_ADMIN_UPLOAD_COVER: &str = "/api/admin/upload/cover";
+pub const API_BULK_PROGRESS: &str = "/api/bulk_progress/:action/:tid";
+pub const API_THUMBNAIL_PROGRESS: &str = "/api/admin/thumbnail_progress";
+pub const API_GENERATE_THUMBNAILS: &str = "/api/admin/generate_thumbnails";
+
+// Komga-compatible API, registered only when komga_compat_enabled is set
+pub const KOMGA_API: &str = "/api/v1";
+pub const KOMGA_BOOK: &str = "/api/v1/books/:id";
+pub const KOMGA_READ_PROGRESS: &str = "/api/v1/books/:id/read-progress";
+pub const KOMGA_UNIMPLEMENTED: &str = "/api/v1/*rest";
+
+/// Legacy path prefixes that never require authentication
+/// Matches original AuthHandler's exclude logic
+const PUBLIC_PREFIXES: &[&str] = &["/api/login", "/static/", "/img/", "/css/", "/js/"];
+
+// ========== Base URL ==========
+
+tokio::task_local! {
+    /// base_url (without the trailing slash) of the app serving the current request, set
+    /// by the app's `strip_base_url` middleware so each app links under its own mount
+    pub static BASE_URL: String;
+}
+
+/// Base URL prefix for builders ("" outside a request of an app mounted under a base_url)
+fn base() -> String {
+    BASE_URL.try_with(String::clone).unwrap_or_default()
+}
+
+/// Configured base_url with the trailing slash ("/" when mounted at "/"), which templates
+/// and scripts put in front of relative paths like `static/js/common.js`
+pub fn base_url() -> String {
+    format!("{}/", base())
+}
+
+/// Prefix a router path with the configured base_url
+fn url(path: &str) -> String {
+    format!("{}{}", base(), path)
+}
+
+/// Router path (with query) of a request path under `base` (base_url without the trailing
+/// slash), None for paths outside it
+/// Example: ("/mango/book/abc", "/mango") -> "/book/abc", ("/mango", "/mango") -> "/"
+pub fn strip_base(path_and_query: &str, base: &str) -> Option<String> {
+    let rest = path_and_query.strip_prefix(base)?;
+    if rest.is_empty() || rest.starts_with('?') {
+        Some(format!("/{}", rest))
+    } else if rest.starts_with('/') {
+        Some(rest.to_string())
+    } else {
+        None
+    }
+}
+
+/// Percent-encode a value for use as a single path segment
+fn segment(value: &str) -> String {
+    percent_encode(value.as_bytes(), NON_ALPHANUMERIC).to_string()
+}
+
+/// Static part of a route pattern, up to the first parameter
+/// Example: "/api/download/:tid/:eid" -> "/api/download"
+pub fn prefix(pattern: &str) -> &str {
+    pattern.split("/:").next().unwrap_or(pattern)
+}
+
+/// Check if a request path should skip authentication
+pub fn is_public(path: &str) -> bool {
+    path == LOGIN
+        || path == LOGIN_2FA
+        || path == BRANDING_LOGO
+        || PUBLIC_PREFIXES.iter().any(|p| path.starts_with(p))
+}
+
+/// Check if a request path is under /api (the routes external web clients may call)
+pub fn is_api(path: &str) -> bool {
+    path.starts_with("/api/")
+}
+
+/// Check if a request path is an Atom feed (authenticated by feed token, 401 instead of redirect)
+pub fn is_feed(path: &str) -> bool {
+    path.strip_prefix(FEED)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Check if a request path is served to OPDS clients (Basic auth, 401 instead of redirect)
+/// Komga-compatible clients authenticate the same way
+pub fn is_opds(path: &str) -> bool {
+    path.starts_with(OPDS)
+        || path.starts_with(prefix(API_DOWNLOAD))
+        || path
+            .strip_prefix(KOMGA_API)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+// ========== Page URLs ==========
+
+pub fn home() -> String {
+    url(HOME)
+}
+
+pub fn login() -> String {
+    url(LOGIN)
+}
+
+/// Login page that returns to `path` (router path with query) after logging in
+pub fn login_next(path: &str) -> String {
+    format!("{}?next={}", login(), segment(&url(path)))
+}
+
+/// Validate a post-login redirect target
+/// Only same-origin paths under base_url are accepted, so `next` cannot send the user
+/// to another site (`//host`, `https://host`, `/\host`)
+pub fn safe_next(next: &str) -> Option<&str> {
+    is_safe_next(next, &base()).then_some(next)
+}
+
+fn is_safe_next(next: &str, base: &str) -> bool {
+    let Some(rest) = next.strip_prefix(base) else {
+        return false;
+    };
+    rest.starts_with('/')
+        && !next.starts_with("//")
+        && !next.contains('\\')
+        && !next.chars().any(char::is_control)
+}
+
+pub fn branding_logo() -> String {
+    url(BRANDING_LOGO)
+}
+
+pub fn logout() -> String {
+    url(LOGOUT)
+}
+
+pub fn library() -> String {
+    url(LIBRARY)
+}
+
+pub fn book(tid: &str) -> String {
+    url(&format!("/book/{}", tid))
+}
+
+pub fn tags() -> String {
+    url(TAGS)
+}
+
+pub fn tag(tag: &str) -> String {
+    url(&format!("/tags/{}", segment(tag)))
+}
+
+pub fn collection(id: &str) -> String {
+    url(&format!("/collections/{}", id))
+}
+
+pub fn admin() -> String {
+    url(ADMIN)
+}
+
+pub fn admin_users() -> String {
+    url(ADMIN_USERS)
+}
+
+/// User edit page; pass a username (and its admin flag) to edit an existing user
+pub fn admin_user_edit(user: Option<(&str, bool)>) -> String {
+    match user {
+        Some((username, is_admin)) => url(&format!(
+            "{}?username={}&admin={}",
+            ADMIN_USER_EDIT,
+            segment(username),
+            is_admin
+        )),
+        None => url(ADMIN_USER_EDIT),
+    }
+}
+
+/// Reader at a specific page (1-indexed)
+pub fn reader(tid: &str, eid: &str, page: usize) -> String {
+    url(&format!("/reader/{}/{}/{}", tid, eid, page))
+}
+
+/// Reader at the user's saved progress
+pub fn reader_continue(tid: &str, eid: &str) -> String {
+    url(&format!("/reader/{}/{}", tid, eid))
+}
+
+pub fn opds() -> String {
+    url(OPDS)
+}
+
+pub fn opds_book(tid: &str) -> String {
+    url(&format!("/opds/book/{}", tid))
+}
+
+pub fn opds_continue() -> String {
+    url(OPDS_CONTINUE)
+}
+
+pub fn opds_recent() -> String {
+    url(OPDS_RECENT)
+}
+
+pub fn opds_collection(id: &str) -> String {
+    url(&format!("/opds/collection/{}", id))
+}
+
+/// Atom feed of recently added entries, authenticated by a feed token
+pub fn feed_recent(token: &str) -> String {
+    format!("{}?token={}", url(FEED_RECENT), segment(token))
+}
+
+/// Atom feed of a title's recently added entries, authenticated by a feed token
+pub fn feed_title(tid: &str, token: &str) -> String {
+    url(&format!(
+        "/feed/title/{}.atom?token={}",
+        tid,
+        segment(token)
+    ))
+}
+
+// ========== API URLs ==========
+
+pub fn api_page(tid: &str, eid: &str, page: usize) -> String {
+    url(&format!("/api/page/{}/{}/{}", tid, eid, page))
+}
+
+/// Page thumbnail metadata (frame offsets) for the reader scrub bar
+pub fn api_page_thumbnails(tid: &str, eid: &str) -> String {
+    url(&format!("/api/entry/{}/{}/page_thumbnails", tid, eid))
+}
+
+/// Page thumbnail sprite image for the reader scrub bar
+pub fn api_page_thumbnails_sprite(tid: &str, eid: &str) -> String {
+    url(&format!(
+        "/api/entry/{}/{}/page_thumbnails/sprite",
+        tid, eid
+    ))
+}
+
+/// An entry's cover at one version (see `cover_version`)
+pub fn cover(tid: &str, eid: &str, version: &str) -> String {
+    url(&format!("/api/cover/{}/{}?v={}", tid, eid, version))
+}
+
+/// Cover downscaled to at most `width` pixels wide (full size when None)
+pub fn cover_sized(tid: &str, eid: &str, version: &str, width: Option<u32>) -> String {
+    match width {
+        Some(width) => format!("{}&w={}", cover(tid, eid, version), width),
+        None => cover(tid, eid, version),
+    }
+}
+
+pub fn download(tid: &str, eid: &str) -> String {
+    url(&format!("/api/download/{}/{}", tid, eid))
+}
+
+// ========== Static Assets ==========
+
+/// URL of a file under static/
+pub fn static_file(path: &str) -> String {
+    url(&format!("{}/{}", STATIC, path.trim_start_matches('/')))
+}
+
+/// Cover shown for titles without entries: the app icon, as in original Mango
+pub fn placeholder_cover() -> String {
+    static_file("img/icons/icon_x192.png")
+}
+
+/// Cover for a title (its folder cover or first entry's cover), or the placeholder
+/// for titles without entries; unversioned when None (folder covers change without
+/// a rescan, so they have no version)
+pub fn title_cover(
+    tid: &str,
+    has_entries: bool,
+    version: Option<&str>,
+    width: Option<u32>,
+) -> String {
+    if !has_entries {
+        return placeholder_cover();
+    }
+    let mut query = Vec::new();
+    if let Some(version) = version {
+        query.push(format!("v={}", version));
+    }
+    if let Some(width) = width {
+        query.push(format!("w={}", width));
+    }
+    let cover = url(&format!("/api/title/{}/cover", tid));
+    if query.is_empty() {
+        cover
+    } else {
+        format!("{}?{}", cover, query.join("&"))
+    }
+}
+
+// ========== Image Versions ==========
+
+/// Short token naming one version of an image, sent as `?v=` in its URL
+/// Built from everything the image depends on: URLs carrying the current token are
+/// served as immutable, so the token has to change whenever the image does
+pub fn version_token(parts: &[&str]) -> String {
+    let mut hasher = crc32fast::Hasher::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update(&[0]);
+    }
+    format!("{:08x}", hasher.finalize())
+}
+
+/// Version of an entry's pages, which only change along with its archive file
+pub fn page_version(entry: &crate::library::Entry) -> String {
+    version_token(&[&entry.signature, &entry.mtime.to_string()])
+}
+
+/// Version of an entry's cover: its pages, unless an admin uploaded a cover, which
+/// is told apart from earlier uploads by its stored thumbnail
+pub fn cover_version(
+    entry: &crate::library::Entry,
+    thumbnail: Option<&crate::library::entry::ThumbnailMeta>,
+) -> String {
+    let thumbnail = thumbnail
+        .filter(|t| t.uploaded)
+        .map(|t| format!("{}:{}:{:?}x{:?}", t.mime, t.size, t.width, t.height))
+        .unwrap_or_default();
+    version_token(&[&entry.signature, &entry.mtime.to_string(), &thumbnail])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_segment_is_encoded() {
+        assert!(tag("a/b c").ends_with("/tags/a%2Fb%20c"));
+    }
+
+    #[test]
+    fn test_cover_width_hint() {
+        assert!(cover_sized("t", "e", "v1", Some(100)).ends_with("/api/cover/t/e?v=v1&w=100"));
+        assert!(cover_sized("t", "e", "v1", None).ends_with("/api/cover/t/e?v=v1"));
+        assert!(title_cover("t", true, None, Some(100)).ends_with("/api/title/t/cover?w=100"));
+        assert!(title_cover("t", true, Some("v1"), None).ends_with("/api/title/t/cover?v=v1"));
+        assert!(title_cover("t", true, Some("v1"), Some(100))
+            .ends_with("/api/title/t/cover?v=v1&w=100"));
+        assert_eq!(
+            title_cover("t", false, None, Some(100)),
+            placeholder_cover()
+        );
+    }
+
+    #[test]
+    fn test_version_token() {
+        let token = version_token(&["123", "456"]);
+        assert_eq!(token.len(), 8);
+        assert_eq!(token, version_token(&["123", "456"]));
+        // Parts are separated, so moving a boundary changes the token
+        assert_ne!(token, version_token(&["1234", "56"]));
+    }
+
+    #[test]
+    fn test_login_next() {
+        assert_eq!(
+            login_next("/book/abc?sort=title"),
+            "/login?next=%2Fbook%2Fabc%3Fsort%3Dtitle"
+        );
+    }
+
+    #[test]
+    fn test_safe_next() {
+        for next in ["/", "/book/abc", "/reader/t/e/3?set_progress=1"] {
+            assert!(is_safe_next(next, ""), "{} should be accepted", next);
+        }
+        assert!(is_safe_next("/mango/book/abc", "/mango"));
+
+        for next in [
+            "",
+            "book/abc",
+            "//evil.example",
+            "///evil.example",
+            "https://evil.example/",
+            "javascript:alert(1)",
+            "/\\evil.example",
+            "/book\nSet-Cookie: x",
+        ] {
+            assert!(!is_safe_next(next, ""), "{:?} should be rejected", next);
+        }
+
+        // Must stay under base_url
+        assert!(!is_safe_next("/book/abc", "/mango"));
+        assert!(!is_safe_next("/mangoevil/book", "/mango"));
+        assert!(!is_safe_next("//evil.example", "/"));
+    }
+
+    #[test]
+    fn test_strip_base() {
+        assert_eq!(
+            strip_base("/mango/book/abc", "/mango").unwrap(),
+            "/book/abc"
+        );
+        assert_eq!(strip_base("/mango/", "/mango").unwrap(), "/");
+        assert_eq!(strip_base("/mango", "/mango").unwrap(), "/");
+        assert_eq!(strip_base("/mango?next=x", "/mango").unwrap(), "/?next=x");
+        assert_eq!(strip_base("/login", "").unwrap(), "/login");
+        assert_eq!(strip_base("/mangoevil/book", "/mango"), None);
+        assert_eq!(strip_base("/book/abc", "/mango"), None);
+    }
+
+    #[test]
+    fn test_prefix() {
+        assert_eq!(prefix(API_DOWNLOAD), "/api/download");
+        assert_eq!(prefix(LIBRARY), "/library");
+    }
+
+    #[test]
+    fn test_public_and_opds_paths() {
+        assert!(is_public(LOGIN));
+        assert!(is_public(LOGIN_2FA));
+        assert!(is_public("/static/js/common.js"));
+        assert!(is_public(BRANDING_LOGO));
+        assert!(!is_public(LIBRARY));
+        assert!(is_opds("/opds/book/abc"));
+        assert!(is_opds("/api/download/abc/def"));
+        assert!(is_opds("/api/v1/books/abc/read-progress"));
+        assert!(!is_opds(API_LIBRARY));
+        assert!(!is_opds("/api/v10"));
+        assert!(is_feed(FEED_RECENT));
+        assert!(is_feed("/feed/title/abc.atom"));
+        assert!(!is_feed("/feeds"));
+    }
+}
@@ -1,15 +1,16 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
     response::{IntoResponse, Json},
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::{
-    auth::Username,
+    auth::{Username, Writer},
     error::{Error, Result},
-    AppState,
+    library, AppState,
 };
 
 #[derive(Debug, Deserialize)]
@@ -20,19 +21,23 @@ pub struct SaveProgressRequest {
 #[derive(Debug, Serialize)]
 pub struct ProgressResponse {
     page: i32,
+    /// Times the user read the entry to the end, the first read included
+    read_count: u32,
+    /// Unix timestamp of the last time the user opened the entry in the reader
+    opened_at: Option<i64>,
 }
 
 /// POST /api/progress/{title_id}/{entry_id} - Save reading progress for an entry
-/// Returns: 200 OK on success
+/// Returns: 200 OK on success, 403 for read-only users
 pub async fn save_progress(
     State(state): State<AppState>,
     Path((title_id, entry_id)): Path<(String, String)>,
-    Username(username): Username,
+    Writer(username): Writer,
     Json(request): Json<SaveProgressRequest>,
 ) -> Result<impl IntoResponse> {
     // Get library read lock to find the title
     let lib = state.library.load();
-    let title = lib
+    let _title = lib
         .get_title(&title_id)
         .ok_or_else(|| Error::NotFound(format!("Title not found: {}", title_id)))?;
 
@@ -41,9 +46,8 @@ pub async fn save_progress(
         .get_entry(&title_id, &entry_id)
         .ok_or_else(|| Error::NotFound(format!("Entry not found: {}", entry_id)))?;
 
-    // Save progress via cache (updates cache and persists to disk)
-    lib.progress_cache()
-        .save_progress(&title_id, &title.path, &username, &entry_id, request.page)
+    // Save progress (updates cache, persists to disk and updates the recently read index)
+    lib.save_progress(&title_id, &username, &entry_id, request.page)
         .await?;
 
     // Invalidate response cache after progress update
@@ -81,9 +85,21 @@ pub async fn get_progress(
         .progress_cache()
         .get_progress(&title_id, &username, &entry_id)
         .unwrap_or(0);
+    let read_count = lib
+        .progress_cache()
+        .get_read_state(&title_id, &username, &entry_id)
+        .count;
+    let opened_at = lib
+        .progress_cache()
+        .get_opened_at(&title_id, &username, &entry_id)
+        .map(|dt| dt.timestamp());
     drop(lib);
 
-    Ok(Json(ProgressResponse { page: page.max(1) })) // Default to page 1
+    Ok(Json(ProgressResponse {
+        page: page.max(1), // Default to page 1
+        read_count,
+        opened_at,
+    }))
 }
 
 /// GET /api/progress - Get all progress for a user across all titles
@@ -96,8 +112,8 @@ pub async fn get_all_progress(
     let cache = lib.progress_cache();
     let mut all_progress = HashMap::new();
 
-    // Iterate through all titles using cache
-    for title in lib.get_titles() {
+    // Iterate through all titles, nested ones included, using cache
+    for title in lib.all_titles() {
         for entry in &title.entries {
             if let Some(page) = cache.get_progress(&title.id, &username, &entry.id) {
                 if page > 0 {
@@ -109,3 +125,235 @@ pub async fn get_all_progress(
 
     Ok(Json(all_progress))
 }
+
+/// Columns of the progress export, in order
+const PROGRESS_CSV_HEADER: [&str; 7] = [
+    "title",
+    "entry",
+    "pages",
+    "pages_read",
+    "percent",
+    "last_read",
+    "completed",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct ProgressCsvQuery {
+    /// Non-zero to start the file with a UTF-8 byte order mark, which Excel needs to
+    /// detect the encoding
+    #[serde(default)]
+    excel: u8,
+}
+
+/// GET /api/user/progress.csv - Export the user's reading progress as CSV
+/// One row per entry with saved progress, ordered by title then entry
+pub async fn get_progress_csv(
+    State(state): State<AppState>,
+    Query(query): Query<ProgressCsvQuery>,
+    Username(username): Username,
+) -> Result<impl IntoResponse> {
+    let rows = {
+        let lib = state.library.load();
+        let cache = lib.progress_cache();
+
+        // Nested titles are named with their parents: "Series / Volume 1"
+        let names: HashMap<&str, &library::Title> =
+            lib.all_titles().map(|t| (t.id.as_str(), t)).collect();
+        let full_name = |title: &library::Title| {
+            let mut parts = vec![title.title.as_str()];
+            let mut parent = title.parent_id.as_deref();
+            while let Some(t) = parent.and_then(|id| names.get(id)) {
+                parts.push(t.title.as_str());
+                parent = t.parent_id.as_deref();
+            }
+            parts.reverse();
+            parts.join(" / ")
+        };
+
+        let mut titles: Vec<(String, &library::Title)> =
+            lib.all_titles().map(|t| (full_name(t), t)).collect();
+        titles.sort_by(|a, b| natord::compare(&a.0, &b.0));
+
+        let mut rows = Vec::new();
+        for (name, title) in &titles {
+            for entry in &title.entries {
+                let Some(page) = cache.get_progress(&title.id, &username, &entry.id) else {
+                    continue;
+                };
+                let last_read = cache
+                    .get_last_read(&title.id, &username, &entry.id)
+                    .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                    .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+                    .unwrap_or_default();
+                let pages_read = (page.max(0) as usize).min(entry.pages);
+                rows.push([
+                    name.clone(),
+                    entry.display_title.clone(),
+                    entry.pages.to_string(),
+                    pages_read.to_string(),
+                    format!("{:.1}", lib.entry_percentage(entry, page)),
+                    last_read,
+                    (entry.pages > 0 && pages_read >= entry.pages).to_string(),
+                ]);
+            }
+        }
+        rows
+    };
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    let mut header_line = csv_line(PROGRESS_CSV_HEADER);
+    if query.excel != 0 {
+        header_line.insert(0, '\u{FEFF}');
+    }
+    lines.push(header_line);
+    lines.extend(rows.into_iter().map(csv_line));
+
+    let filename = format!(
+        "mango-progress-{}.csv",
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+    let body = Body::from_stream(tokio_stream::iter(
+        lines.into_iter().map(Ok::<_, std::convert::Infallible>),
+    ));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        body,
+    ))
+}
+
+/// One CSV record (RFC 4180), CRLF terminated
+fn csv_line<S: AsRef<str>>(fields: impl IntoIterator<Item = S>) -> String {
+    let mut line = fields
+        .into_iter()
+        .map(|f| csv_field(f.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// Quote a field if it contains a separator, quote or line break, doubling inner quotes
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkReadRequest {
+    /// Titles to mark read in full
+    #[serde(default)]
+    title_ids: Vec<String>,
+    /// Titles to mark read up to and including a threshold entry
+    #[serde(default)]
+    up_to: Vec<ReadUpTo>,
+    /// Only count the entries that would change
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReadUpTo {
+    title_id: String,
+    /// Chapter number ("12") or entry name ("Vol.2 Ch.12"), see `library::at_or_before`
+    key: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkReadTitle {
+    title_id: String,
+    /// Entries marked read, or that would be in a dry run
+    entries: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkReadResponse {
+    dry_run: bool,
+    titles: Vec<BulkReadTitle>,
+    total: usize,
+}
+
+/// POST /api/progress/bulk_read - Mark whole titles, or everything up to a chapter, as read
+/// Entries already read to the end are left untouched and not counted
+/// Returns: JSON with per-title counts
+pub async fn bulk_read(
+    State(state): State<AppState>,
+    Writer(username): Writer,
+    Json(request): Json<BulkReadRequest>,
+) -> Result<impl IntoResponse> {
+    if request.title_ids.is_empty() && request.up_to.is_empty() {
+        return Err(Error::BadRequest("Provide title_ids or up_to".to_string()));
+    }
+
+    let targets = request.title_ids.iter().map(|id| (id, None)).chain(
+        request
+            .up_to
+            .iter()
+            .map(|t| (&t.title_id, Some(t.key.as_str()))),
+    );
+
+    let lib = state.library.load();
+    let cache = lib.progress_cache();
+
+    // Resolve every title before writing anything, so a bad id fails the whole request
+    let mut batches = Vec::new();
+    for (title_id, key) in targets {
+        let title = lib
+            .get_title(title_id)
+            .ok_or_else(|| Error::NotFound(format!("Title not found: {}", title_id)))?;
+        let entry_ids: Vec<&str> = title
+            .entries
+            .iter()
+            .filter(|e| key.is_none_or(|key| library::at_or_before(&e.title, key)))
+            .filter(|e| {
+                let page = cache.get_progress(&title.id, &username, &e.id).unwrap_or(0);
+                e.pages > 0 && page < e.pages as i32
+            })
+            .map(|e| e.id.as_str())
+            .collect();
+        batches.push((title_id.as_str(), entry_ids));
+    }
+
+    if !request.dry_run {
+        for (title_id, entry_ids) in &batches {
+            if entry_ids.is_empty() {
+                continue;
+            }
+            lib.mark_entries_read(title_id, &username, entry_ids)
+                .await?;
+            lib.invalidate_cache_for_progress(title_id, &username).await;
+        }
+    }
+
+    let titles: Vec<BulkReadTitle> = batches
+        .into_iter()
+        .map(|(title_id, entry_ids)| BulkReadTitle {
+            title_id: title_id.to_string(),
+            entries: entry_ids.len(),
+        })
+        .collect();
+    let total = titles.iter().map(|t| t.entries).sum();
+
+    tracing::info!(
+        "Bulk read for {}: {} entries across {} titles{}",
+        username,
+        total,
+        titles.len(),
+        if request.dry_run { " (dry run)" } else { "" }
+    );
+
+    Ok(Json(BulkReadResponse {
+        dry_run: request.dry_run,
+        titles,
+        total,
+    }))
+}
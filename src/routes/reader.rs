@@ -1,12 +1,17 @@
 use askama::Template;
 use axum::{
-    extract::{Path, State},
-    response::{Html, Redirect},
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{Html, IntoResponse, Redirect, Response},
 };
+use serde::Deserialize;
+use tower_sessions::Session;
 
+use super::{paths, tag_locks, TagLocks};
 use crate::{
-    auth::Username,
+    auth::{User, Username},
     error::{Error, Result},
+    library::{ReadingDirection, SortMethod},
     util::render_error,
     AppState,
 };
@@ -18,10 +23,69 @@ struct EntryOption {
     name: String,
 }
 
+/// Query parameters for the reader page
+#[derive(Deserialize)]
+pub struct ReaderQuery {
+    /// 1 to save the viewed page as progress even if it is behind the saved page
+    pub set_progress: Option<u8>,
+}
+
+/// What opening a reader page does to the user's saved progress
+#[derive(Debug, PartialEq, Eq)]
+enum ProgressAction {
+    /// Save the viewed page (ahead of saved progress, or explicitly requested)
+    Save,
+    /// Leave saved progress alone (same page, or rereading a finished entry)
+    Keep,
+    /// The viewed page is behind saved progress: ask before overwriting it
+    Confirm { saved_page: usize },
+}
+
+/// Decide whether viewing a page (1-indexed) advances saved progress
+/// Shared deep links to an earlier page must not roll back someone's progress
+/// unless they ask for it with ?set_progress=1
+fn progress_action(
+    page: usize,
+    saved_page: usize,
+    total_pages: usize,
+    set_progress: bool,
+) -> ProgressAction {
+    if set_progress || page > saved_page {
+        ProgressAction::Save
+    } else if page == saved_page || saved_page >= total_pages {
+        ProgressAction::Keep
+    } else {
+        ProgressAction::Confirm { saved_page }
+    }
+}
+
+/// Cookie holding the reader's own direction preference (written by reader.js)
+const DIRECTION_COOKIE: &str = "reader_direction";
+
+/// Effective reading direction for an entry:
+/// entry override, then title override, then user preference, then instance default
+fn resolve_direction(
+    entry: Option<ReadingDirection>,
+    title: Option<ReadingDirection>,
+    user: Option<ReadingDirection>,
+    default: ReadingDirection,
+) -> ReadingDirection {
+    entry.or(title).or(user).unwrap_or(default)
+}
+
+/// First entry of the sequel of the title being read
+struct SequelLink {
+    title: String,
+    url: String,
+}
+
 /// Reader page template
 #[derive(Template)]
 #[template(path = "reader.html")]
 struct ReaderTemplate {
+    branding: crate::util::Branding,
+    /// Prefix of the stylesheet, script and API URLs
+    base_url: String,
     title_id: String,
     entry_id: String,
     entry_name: String,
@@ -31,16 +95,47 @@ struct ReaderTemplate {
     entries: Vec<EntryOption>,
     prev_entry_url: Option<String>,
     next_entry_url: Option<String>,
+    /// Where the title's sequel starts, offered after its last entry
+    sequel: Option<SequelLink>,
     exit_url: String,
+    /// Page thumbnail metadata for slider hover previews
+    page_thumbnails_url: String,
+    /// Page thumbnail sprite for slider hover previews
+    page_sprite_url: String,
+    /// Version token of the entry's pages, so page URLs can be cached for good
+    page_version: String,
+    /// Saved page when it is ahead of the viewed page (asks before overwriting)
+    saved_page: Option<usize>,
+    /// Reader URL of the viewed page that also saves it as progress
+    update_progress_url: String,
+    /// Effective reading direction (ltr, rtl or vertical)
+    direction: &'static str,
+    /// The direction comes from an entry/title override rather than the user's preference
+    direction_overridden: bool,
+    /// False for read-only users, whose progress is not saved
+    can_write: bool,
 }
 
-/// GET /reader/{title_id}/{entry_id}/{page} - Display reader for an entry page
-/// Returns: HTML page with reader interface, entry content, and navigation
+/// GET /reader/{title_id}/{entry_id}/{page}?set_progress=1 - Display reader for an entry page
+/// Viewing a page past the saved progress saves it; an earlier page only does with
+/// set_progress=1, otherwise the template asks whether to keep or update the saved page
+/// Read-only users read without saving anything
+/// Returns: HTML page with reader interface, entry content, and navigation, or the PIN
+/// prompt with 423 Locked for titles locked for the user (see `TagLocks`)
 pub async fn reader(
     State(state): State<AppState>,
     Path((title_id, entry_id, page)): Path<(String, String, usize)>,
-    Username(_username): Username,
-) -> Result<Html<String>> {
+    Query(query): Query<ReaderQuery>,
+    User {
+        username,
+        can_write,
+        ..
+    }: User,
+    session: Session,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let locks = TagLocks::load(&state, &session, &username).await?;
+
     // Get library read lock
     let lib = state.library.load();
 
@@ -48,6 +143,9 @@ pub async fn reader(
     let title = lib
         .get_title(&title_id)
         .ok_or_else(|| Error::NotFound(format!("Title not found: {}", title_id)))?;
+    if locks.is_locked(&title_id) {
+        return tag_locks::locked_page(&title.title);
+    }
 
     // Find the entry within the title
     let entry = lib
@@ -64,30 +162,76 @@ pub async fn reader(
         )));
     }
 
+    state.stats.record_open(&username, &title_id, &entry_id);
+    if can_write {
+        if let Err(e) = lib.record_opened(&title_id, &username, &entry_id).await {
+            tracing::warn!(
+                "Failed to record open for user '{}' entry '{}': {}",
+                username,
+                entry_id,
+                e
+            );
+        }
+    }
+
+    let saved = lib
+        .progress_cache()
+        .get_progress(&title_id, &username, &entry_id)
+        .unwrap_or(0)
+        .max(0) as usize;
+    let set_progress = query.set_progress == Some(1);
+    let action = if can_write {
+        progress_action(page, saved, total_pages, set_progress)
+    } else {
+        ProgressAction::Keep
+    };
+    let saved_page = match action {
+        ProgressAction::Save => {
+            if let Err(e) = lib
+                .save_progress(&title_id, &username, &entry_id, page as i32)
+                .await
+            {
+                tracing::warn!(
+                    "Failed to save progress for user '{}' entry '{}': {}",
+                    username,
+                    entry_id,
+                    e
+                );
+            }
+            lib.invalidate_cache_for_progress(&title_id, &username)
+                .await;
+            None
+        }
+        ProgressAction::Keep => None,
+        ProgressAction::Confirm { saved_page } => Some(saved_page),
+    };
+
+    // Entries in display order (honors a pinned entry order)
+    let ordered_entries = lib.get_entries_sorted(title, SortMethod::Name, true);
+
     // Get all entries in this title for jump functionality
-    let entries: Vec<EntryOption> = title
-        .entries
+    let entries: Vec<EntryOption> = ordered_entries
         .iter()
         .map(|e| EntryOption {
             id: e.id.clone(),
-            name: e.title.clone(),
+            name: e.display_title.clone(),
         })
         .collect();
 
     // Find current entry index to determine prev/next entry
-    let current_entry_idx = title.entries.iter().position(|e| e.id == entry_id);
+    let current_entry_idx = ordered_entries.iter().position(|e| e.id == entry_id);
 
     let (prev_entry_url, next_entry_url) = if let Some(idx) = current_entry_idx {
         let prev_url = if idx > 0 {
-            let prev_entry = &title.entries[idx - 1];
-            Some(format!("/reader/{}/{}/1", title_id, prev_entry.id))
+            let prev_entry = ordered_entries[idx - 1];
+            Some(paths::reader(&title_id, &prev_entry.id, 1))
         } else {
             None
         };
 
-        let next_url = if idx < title.entries.len() - 1 {
-            let next_entry = &title.entries[idx + 1];
-            Some(format!("/reader/{}/{}/1", title_id, next_entry.id))
+        let next_url = if idx < ordered_entries.len() - 1 {
+            let next_entry = ordered_entries[idx + 1];
+            Some(paths::reader(&title_id, &next_entry.id, 1))
         } else {
             None
         };
@@ -97,20 +241,60 @@ pub async fn reader(
         (None, None)
     };
 
+    // The last entry offers to continue with the sequel
+    let sequel = match (&next_entry_url, current_entry_idx) {
+        (None, Some(_)) => lib
+            .sequel_start(&title_id, None)
+            .await?
+            .map(|(sequel, first)| SequelLink {
+                title: sequel.title.clone(),
+                url: paths::reader(&sequel.id, &first.id, 1),
+            }),
+        _ => None,
+    };
+
+    let (title_direction, entry_direction) = lib
+        .progress_cache()
+        .get_direction_overrides(&title_id, &entry_id);
+    let direction = resolve_direction(
+        entry_direction,
+        title_direction,
+        crate::util::cookie(&headers, DIRECTION_COOKIE).and_then(ReadingDirection::parse),
+        state.config.default_reading_direction,
+    );
+
+    let page_thumbnails_url = paths::api_page_thumbnails(&title_id, &entry_id);
+    let page_sprite_url = paths::api_page_thumbnails_sprite(&title_id, &entry_id);
+    let update_progress_url = format!(
+        "{}?set_progress=1",
+        paths::reader(&title_id, &entry_id, page)
+    );
+
     let template = ReaderTemplate {
+        branding: crate::util::Branding::current(),
+        base_url: paths::base_url(),
         title_id,
         entry_id,
-        entry_name: entry.title.clone(),
-        entry_path: entry.path.display().to_string(),
+        entry_name: entry.display_title.clone(),
+        entry_path: entry.relative_path(lib.path()),
         current_page: page,
         total_pages,
         entries,
         prev_entry_url,
         next_entry_url,
-        exit_url: format!("/book/{}", title.id),
+        sequel,
+        exit_url: paths::book(&title.id),
+        page_thumbnails_url,
+        page_sprite_url,
+        page_version: paths::page_version(entry),
+        saved_page,
+        update_progress_url,
+        direction: direction.as_str(),
+        direction_overridden: entry_direction.or(title_direction).is_some(),
+        can_write,
     };
 
-    Ok(Html(template.render().map_err(render_error)?))
+    Ok(Html(template.render().map_err(render_error)?).into_response())
 }
 
 /// GET /reader/{title_id}/{entry_id} - Continue reading from saved progress
@@ -157,5 +341,59 @@ pub async fn reader_continue(
         progress_page.max(1)
     };
 
-    Ok(Redirect::to(&format!("/reader/{}/{}/{}", title_id, entry_id, page)))
+    Ok(Redirect::to(&paths::reader(
+        &title_id,
+        &entry_id,
+        page as usize,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_action_ahead_saves() {
+        assert_eq!(progress_action(57, 40, 100, false), ProgressAction::Save);
+        // Not started yet
+        assert_eq!(progress_action(1, 0, 100, false), ProgressAction::Save);
+    }
+
+    #[test]
+    fn test_progress_action_behind_confirms() {
+        assert_eq!(
+            progress_action(57, 80, 100, false),
+            ProgressAction::Confirm { saved_page: 80 }
+        );
+    }
+
+    #[test]
+    fn test_progress_action_equal_keeps() {
+        assert_eq!(progress_action(80, 80, 100, false), ProgressAction::Keep);
+    }
+
+    #[test]
+    fn test_progress_action_finished_keeps() {
+        // Rereading a finished entry does not prompt
+        assert_eq!(progress_action(3, 100, 100, false), ProgressAction::Keep);
+    }
+
+    #[test]
+    fn test_progress_action_explicit_override() {
+        assert_eq!(progress_action(57, 80, 100, true), ProgressAction::Save);
+        assert_eq!(progress_action(80, 80, 100, true), ProgressAction::Save);
+    }
+
+    #[test]
+    fn test_resolve_direction_precedence() {
+        use ReadingDirection::*;
+
+        assert_eq!(
+            resolve_direction(Some(Ltr), Some(Rtl), Some(Vertical), Vertical),
+            Ltr
+        );
+        assert_eq!(resolve_direction(None, Some(Rtl), Some(Ltr), Vertical), Rtl);
+        assert_eq!(resolve_direction(None, None, Some(Ltr), Vertical), Ltr);
+        assert_eq!(resolve_direction(None, None, None, Rtl), Rtl);
+    }
 }
@@ -0,0 +1,312 @@
+// Tag locks - titles a user keeps behind a PIN
+//
+// A user can lock tags with a PIN (PUT /api/user/tag-locks/:tag). Titles carrying a locked
+// tag, and the titles nested in them, are still listed, but their covers show the
+// placeholder and their book page, reader, pages and downloads answer 423 Locked until the
+// PIN is entered (POST /api/unlock). Entering it unlocks every tag locked with that PIN for
+// `tag_unlock_minutes`, in that session only; OPDS feeds leave locked titles out. Wrong PINs
+// are counted per client address and user like failed logins (`AppState::pin_throttle`).
+// Handlers check `TagLocks` themselves rather than leaving it to a middleware, as telling
+// whether a title is locked needs its tags.
+
+use std::collections::{HashMap, HashSet};
+
+use askama::Template;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tower_sessions::Session;
+
+use super::paths;
+use crate::{
+    auth::{ClientIp, Username, Writer, SESSION_UNLOCKED_TAGS_KEY},
+    error::{Error, Result},
+    library::Title,
+    util::render_error,
+    AppState,
+};
+
+/// Shortest and longest accepted PIN, in characters
+const MIN_PIN_CHARS: usize = 4;
+const MAX_PIN_CHARS: usize = 64;
+
+/// Locked tags whose PIN was entered in a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionUnlocks {
+    /// Unix timestamp each tag locks again at
+    pub tags: HashMap<String, i64>,
+}
+
+impl SessionUnlocks {
+    async fn load(session: &Session) -> Self {
+        session
+            .get::<SessionUnlocks>(SESSION_UNLOCKED_TAGS_KEY)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    async fn save(&self, session: &Session) -> Result<()> {
+        session
+            .insert(SESSION_UNLOCKED_TAGS_KEY, self)
+            .await
+            .map_err(|e| Error::Internal(format!("Failed to save session: {}", e)))
+    }
+
+    fn is_unlocked(&self, tag: &str, now: i64) -> bool {
+        self.tags.get(tag).is_some_and(|&until| until > now)
+    }
+}
+
+/// Titles locked for the user of a request
+#[derive(Debug, Default)]
+pub struct TagLocks {
+    locked: HashSet<String>,
+}
+
+impl TagLocks {
+    /// Titles locked for `username` in this session: those carrying one of the user's
+    /// locked tags that was not unlocked, and the titles nested in them
+    pub async fn load(state: &AppState, session: &Session, username: &str) -> Result<Self> {
+        Self::load_with(state, Some(session), username).await
+    }
+
+    /// Titles locked for `username` where no PIN can be entered, as in OPDS clients: all
+    /// those carrying one of the user's locked tags
+    pub async fn load_all(state: &AppState, username: &str) -> Result<Self> {
+        Self::load_with(state, None, username).await
+    }
+
+    async fn load_with(
+        state: &AppState,
+        session: Option<&Session>,
+        username: &str,
+    ) -> Result<Self> {
+        let tags = state.storage.list_tag_locks(username).await?;
+        if tags.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let unlocks = match session {
+            Some(session) => SessionUnlocks::load(session).await,
+            None => SessionUnlocks::default(),
+        };
+        let now = crate::util::time::now_ts();
+        let locked_tags: Vec<String> = tags
+            .into_iter()
+            .filter(|tag| !unlocks.is_unlocked(tag, now))
+            .collect();
+        let tagged = state.storage.get_titles_with_tags(&locked_tags).await?;
+
+        let lib = state.library.load();
+        let mut locked = HashSet::new();
+        for title_id in tagged {
+            match lib.get_title(&title_id) {
+                Some(title) => lock_with_nested(title, &mut locked),
+                None => {
+                    locked.insert(title_id);
+                }
+            }
+        }
+        Ok(Self { locked })
+    }
+
+    pub fn is_locked(&self, title_id: &str) -> bool {
+        self.locked.contains(title_id)
+    }
+
+    /// Returns: Locked if the title is locked
+    pub fn check(&self, title_id: &str) -> Result<()> {
+        if self.is_locked(title_id) {
+            return Err(Error::Locked(format!(
+                "Title {} is locked, enter its PIN to open it",
+                title_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// `url` for an unlocked title, the placeholder cover for a locked one
+    pub fn cover_url(&self, title_id: &str, url: String) -> String {
+        if self.is_locked(title_id) {
+            return paths::placeholder_cover();
+        }
+        url
+    }
+}
+
+fn lock_with_nested(title: &Title, locked: &mut HashSet<String>) {
+    locked.insert(title.id.clone());
+    for nested in &title.nested_titles {
+        lock_with_nested(nested, locked);
+    }
+}
+
+/// Page asking for the PIN in place of a locked title's book page or reader
+#[derive(Template)]
+#[template(path = "locked.html")]
+struct LockedTemplate {
+    branding: crate::util::Branding,
+    /// Prefix of the stylesheet, script and API URLs
+    base_url: String,
+    title: String,
+}
+
+/// 423 response with the PIN prompt for a locked title
+pub fn locked_page(title: &str) -> Result<Response> {
+    let template = LockedTemplate {
+        branding: crate::util::Branding::current(),
+        base_url: paths::base_url(),
+        title: title.to_string(),
+    };
+    Ok((
+        StatusCode::LOCKED,
+        Html(template.render().map_err(render_error)?),
+    )
+        .into_response())
+}
+
+#[derive(Deserialize)]
+pub struct PinRequest {
+    pub pin: String,
+}
+
+#[derive(Serialize)]
+pub struct TagLocksResponse {
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct UnlockResponse {
+    pub success: bool,
+    /// Tags unlocked by the PIN
+    pub unlocked: Vec<String>,
+    /// Unix timestamp they lock again at
+    pub expires_at: i64,
+}
+
+/// GET /api/user/tag-locks - Tags the current user locked
+pub async fn list_tag_locks(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<Json<TagLocksResponse>> {
+    Ok(Json(TagLocksResponse {
+        tags: state.storage.list_tag_locks(&username).await?,
+    }))
+}
+
+/// PUT /api/user/tag-locks/:tag - Lock a tag behind a PIN `{"pin": "1234"}`
+/// Changing the PIN of a locked tag needs the tag unlocked in this session
+pub async fn set_tag_lock(
+    State(state): State<AppState>,
+    Writer(username): Writer,
+    session: Session,
+    Path(tag): Path<String>,
+    Json(request): Json<PinRequest>,
+) -> Result<Json<TagLocksResponse>> {
+    let chars = request.pin.chars().count();
+    if !(MIN_PIN_CHARS..=MAX_PIN_CHARS).contains(&chars) {
+        return Err(Error::BadRequest(format!(
+            "The PIN must be {} to {} characters long",
+            MIN_PIN_CHARS, MAX_PIN_CHARS
+        )));
+    }
+    check_tag_unlocked(&state, &session, &username, &tag).await?;
+
+    state
+        .storage
+        .set_tag_lock(&username, &tag, &request.pin)
+        .await?;
+    Ok(Json(TagLocksResponse {
+        tags: state.storage.list_tag_locks(&username).await?,
+    }))
+}
+
+/// DELETE /api/user/tag-locks/:tag - Stop locking a tag
+/// Needs the tag unlocked in this session
+pub async fn remove_tag_lock(
+    State(state): State<AppState>,
+    Writer(username): Writer,
+    session: Session,
+    Path(tag): Path<String>,
+) -> Result<Json<TagLocksResponse>> {
+    check_tag_unlocked(&state, &session, &username, &tag).await?;
+
+    if !state.storage.remove_tag_lock(&username, &tag).await? {
+        return Err(Error::NotFound(format!("Tag is not locked: {}", tag)));
+    }
+    Ok(Json(TagLocksResponse {
+        tags: state.storage.list_tag_locks(&username).await?,
+    }))
+}
+
+/// Returns: Locked if the user locked `tag` and it is not unlocked in this session
+async fn check_tag_unlocked(
+    state: &AppState,
+    session: &Session,
+    username: &str,
+    tag: &str,
+) -> Result<()> {
+    let locked = state.storage.list_tag_locks(username).await?;
+    if !locked.iter().any(|t| t == tag) {
+        return Ok(());
+    }
+    let unlocks = SessionUnlocks::load(session).await;
+    if !unlocks.is_unlocked(tag, crate::util::time::now_ts()) {
+        return Err(Error::Locked(format!(
+            "Tag {} is locked, enter its PIN first",
+            tag
+        )));
+    }
+    Ok(())
+}
+
+/// POST /api/unlock - Enter a PIN `{"pin": "1234"}`, unlocking the user's tags locked
+/// with it in this session for `tag_unlock_minutes`
+/// Returns: 403 for a wrong PIN, 429 once the client entered too many of them for the user
+/// (counted like failed logins, so neither a new session nor logging in again resets it)
+pub async fn unlock_tags(
+    State(state): State<AppState>,
+    Username(username): Username,
+    ClientIp(client): ClientIp,
+    session: Session,
+    Json(request): Json<PinRequest>,
+) -> Result<Json<UnlockResponse>> {
+    let now = crate::util::time::now_ts();
+    if let Some(wait) = state.pin_throttle.retry_after(client, &username, now) {
+        return Err(Error::TooManyRequests(format!(
+            "Too many wrong PINs, try again in {} minutes",
+            (wait + 59) / 60
+        )));
+    }
+
+    let tags = state
+        .storage
+        .verify_tag_pin(&username, &request.pin)
+        .await?;
+    if tags.is_empty() {
+        tracing::warn!("Wrong tag PIN entered by user: {}", username);
+        state.pin_throttle.record_failure(client, &username, now);
+        return Err(Error::Forbidden("Wrong PIN".to_string()));
+    }
+    state.pin_throttle.clear(client, &username);
+
+    let expires_at = now + state.config.tag_unlock_minutes as i64 * 60;
+    let mut unlocks = SessionUnlocks::load(&session).await;
+    unlocks.tags.retain(|_, &mut until| until > now);
+    for tag in &tags {
+        unlocks.tags.insert(tag.clone(), expires_at);
+    }
+    unlocks.save(&session).await?;
+
+    Ok(Json(UnlockResponse {
+        success: true,
+        unlocked: tags,
+        expires_at,
+    }))
+}
@@ -0,0 +1,162 @@
+// Two-factor authentication settings for the logged-in user
+//
+// Enrollment is two steps: `enroll` generates a secret and returns it with its otpauth://
+// URI, and `enable` turns two-factor on once a first code from the authenticator app
+// checks out, returning the one-time recovery codes. App-specific tokens let HTTP Basic
+// clients (OPDS readers) log in without a code.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::Username,
+    error::{Error, Result},
+    storage::AppToken,
+    totp, AppState,
+};
+
+/// Longest accepted app token label, in characters
+const MAX_TOKEN_LABEL_CHARS: usize = 100;
+
+#[derive(Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    /// Unused recovery codes
+    pub recovery_codes_left: i64,
+    pub app_tokens: Vec<AppToken>,
+}
+
+/// GET /api/user/two-factor - Two-factor state and app tokens of the current user
+pub async fn two_factor_status(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<Json<TwoFactorStatus>> {
+    Ok(Json(TwoFactorStatus {
+        enabled: state.storage.totp_enabled(&username).await?,
+        recovery_codes_left: state.storage.recovery_codes_left(&username).await?,
+        app_tokens: state.storage.list_app_tokens(&username).await?,
+    }))
+}
+
+#[derive(Serialize)]
+pub struct EnrollResponse {
+    /// Base32 secret, for typing into an authenticator app
+    pub secret: String,
+    /// Provisioning URI, for rendering as a QR code
+    pub otpauth_uri: String,
+}
+
+/// POST /api/user/two-factor/enroll - Generate a secret to confirm with `enable`
+/// Calling it again replaces the pending secret
+pub async fn two_factor_enroll(
+    State(state): State<AppState>,
+    Username(username): Username,
+) -> Result<Json<EnrollResponse>> {
+    if state.storage.totp_enabled(&username).await? {
+        return Err(Error::Conflict(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let secret = totp::generate_secret();
+    let encoded = totp::encode_secret(&secret);
+    state.storage.set_totp_pending(&username, &encoded).await?;
+
+    Ok(Json(EnrollResponse {
+        otpauth_uri: totp::otpauth_uri(&state.config.instance_name, &username, &secret),
+        secret: encoded,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct EnableRequest {
+    pub code: String,
+}
+
+#[derive(Serialize)]
+pub struct EnableResponse {
+    /// Shown only this once; each can replace a code a single time
+    pub recovery_codes: Vec<String>,
+}
+
+/// POST /api/user/two-factor/enable - Confirm enrollment with a first code
+pub async fn two_factor_enable(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Json(request): Json<EnableRequest>,
+) -> Result<Json<EnableResponse>> {
+    let pending = state
+        .storage
+        .get_totp_pending(&username)
+        .await?
+        .ok_or_else(|| Error::BadRequest("No two-factor enrollment in progress".to_string()))?;
+    let secret = totp::decode_secret(&pending)
+        .ok_or_else(|| Error::Internal("Stored TOTP secret is not valid base32".to_string()))?;
+
+    let step = totp::verify_code(&secret, &request.code, crate::util::time::now_ts(), None)
+        .ok_or_else(|| Error::BadRequest("Invalid code".to_string()))?;
+
+    let recovery_codes = totp::generate_recovery_codes();
+    let hashes: Vec<String> = recovery_codes
+        .iter()
+        .map(|code| totp::hash_secret(&totp::normalize_recovery_code(code)))
+        .collect();
+    state.storage.enable_totp(&username, step, &hashes).await?;
+
+    Ok(Json(EnableResponse { recovery_codes }))
+}
+
+#[derive(Deserialize)]
+pub struct CreateAppTokenRequest {
+    pub label: String,
+}
+
+#[derive(Serialize)]
+pub struct CreateAppTokenResponse {
+    pub id: String,
+    /// Shown only this once
+    pub token: String,
+}
+
+/// POST /api/user/app-tokens - Create an app-specific token for an HTTP Basic client
+/// The token is used in place of the password
+pub async fn create_app_token(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Json(request): Json<CreateAppTokenRequest>,
+) -> Result<(StatusCode, Json<CreateAppTokenResponse>)> {
+    let label = request.label.trim();
+    if label.is_empty() || label.chars().count() > MAX_TOKEN_LABEL_CHARS {
+        return Err(Error::BadRequest(format!(
+            "Label must be 1 to {} characters",
+            MAX_TOKEN_LABEL_CHARS
+        )));
+    }
+
+    let token = totp::generate_app_token();
+    let id = state
+        .storage
+        .create_app_token(&username, label, &totp::hash_secret(&token))
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateAppTokenResponse { id, token }),
+    ))
+}
+
+/// DELETE /api/user/app-tokens/:id - Revoke an app-specific token
+pub async fn delete_app_token(
+    State(state): State<AppState>,
+    Username(username): Username,
+    Path(id): Path<String>,
+) -> Result<StatusCode> {
+    if !state.storage.delete_app_token(&username, &id).await? {
+        return Err(Error::NotFound(format!("App token not found: {}", id)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,32 +1,61 @@
 use axum::{
-    middleware,
-    routing::{delete, get, patch, post, put},
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode, Uri},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{any, delete, get, patch, post, put},
     Router,
 };
 use std::sync::Arc;
 use arc_swap::ArcSwap;
-use tower_http::{services::ServeDir, trace::TraceLayer};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::trace::TraceLayer;
 use tower_sessions::{Expiry, SessionManagerLayer};
 use tower_sessions_sqlx_store::SqliteStore;
 
 use crate::{
-    auth::require_auth,
+    assets,
+    auth::{require_auth, LoginThrottle},
     config::Config,
-    error::Result,
-    library::{spawn_periodic_scanner, Library},
+    error::{ErrorDetails, Result, StorageUnavailableResponse},
+    library::{
+        convert::Conversions, spawn_library_watcher, spawn_periodic_scanner, ArchiveCache,
+        FolderCovers, Library, LibraryEvents, PageFlights, ScanStatus,
+    },
+    quota::{spawn_quota_flusher, DownloadQuota},
     routes::{
-        add_tag, admin_dashboard, bulk_progress, cache_clear_api, cache_debug_page,
-        cache_invalidate_api, cache_load_library_api, cache_save_library_api, change_password_api,
-        change_password_page, continue_reading, create_user, delete_all_missing_entries,
-        delete_missing_entry, delete_tag, delete_user, delete_user_api, download_entry,
-        generate_thumbnails, get_all_progress, get_book, get_cover, get_dimensions, get_library,
-        get_login, get_missing_entries, get_page, get_progress, get_stats, get_title,
-        get_title_tags, get_users, home, library as library_page, list_tags, list_tags_page, logout,
-        missing_items_page, opds_index, opds_title, post_login, reader, reader_continue,
-        recently_added, save_progress, scan_library, start_reading, thumbnail_progress,
-        update_display_name, update_progress, update_sort_title, update_user, upload_cover,
-        user_edit_page, user_edit_post, user_edit_post_existing, users_page, view_tag_page,
+        add_collection_title, add_tag, admin_dashboard, backfill_date_added, branding_logo,
+        bulk_progress, bulk_read, cache_clear_api, cache_debug_page, cache_invalidate_api,
+        cache_load_library_api, cache_save_library_api, cache_stats_api, change_password_api,
+        change_password_page, clear_entry_order, continue_reading, conversion_status,
+        create_api_token, create_app_token, create_collection, create_feed_token, create_user,
+        delete_all_missing_entries, delete_api_token, delete_app_token, delete_collection,
+        delete_feed_token, delete_missing_entry, delete_tag, delete_user, delete_user_api,
+        download_entry, feed_recent, feed_title, generate_thumbnails, get_all_progress,
+        get_all_quotas, get_bad_pages, get_book, get_capabilities, get_collection, get_cover,
+        get_diagnostics, get_dimensions, get_feed_token, get_komga_book, get_library,
+        get_library_index, get_library_letters, get_login, get_missing_entries, get_page,
+        get_page_thumbnails, get_page_thumbnails_sprite, get_popular_titles, get_preferences,
+        get_progress, get_progress_csv, get_quota, get_reports, get_stats, get_title,
+        get_title_cover, get_title_files, get_title_readers, get_title_tags, get_users,
+        get_version, head_cover, head_title_cover, home, komga_not_implemented,
+        library as library_page, library_events, list_api_tokens, list_collections, list_pins,
+        list_sessions, list_tag_locks, list_tags, list_tags_page, logout, merge_entries,
+        missing_items_page, opds_collection, opds_continue, opds_index, opds_recent, opds_title,
+        patch_komga_read_progress, paths, pin_title, popular_page, post_login, post_login_2fa,
+        prune_thumbnails_api, reader, reader_continue, rebuild_signatures, recently_added,
+        remove_collection_title, remove_tag_lock, rename_collection, report_entry, reports_page,
+        reset_password, reset_two_factor, resolve_report, resume_scan, revoke_sessions,
+        save_progress, scan_library, scan_status, scan_title, search, set_collection_order,
+        set_entry_direction, set_entry_order, set_hide_completed, set_preferences, set_tag_lock,
+        set_title_aliases, set_title_direction, set_title_relations, start_conversion,
+        start_reading, thumbnail_progress, two_factor_enable, two_factor_enroll, two_factor_status,
+        unlock_tags, unmerge_entries, unpin_title, update_display_name, update_progress,
+        update_sort_title, update_user, upload_cover, user_edit_page, user_edit_post,
+        user_edit_post_existing, users_page, view_collection_page, view_tag_page,
     },
+    stats::{spawn_stats_flusher, TitleStats},
+    storage::PoolSettings,
     Storage,
 };
 
@@ -34,12 +63,31 @@ use crate::{
 #[derive(Clone)]
 pub struct AppState {
     pub storage: Storage,
+    /// Catalog snapshot: readers `load()` it without locking, scans `store()` a new one
     pub library: Arc<ArcSwap<Library>>,
     pub config: Arc<Config>,
+    pub quota: DownloadQuota,
+    pub archives: ArchiveCache,
+    /// Page extractions in progress, joined by identical requests
+    pub page_flights: PageFlights,
+    /// Progress of the library scan started by an admin
+    pub scan_status: ScanStatus,
+    /// Whether the CBZ conversion job is running
+    pub conversions: Conversions,
+    /// Thumbnails of cover images found in title directories
+    pub folder_covers: FolderCovers,
+    /// Library change notifications for `/api/events`
+    pub events: LibraryEvents,
+    /// Per-title read counters, for the popular titles report and sort
+    pub stats: TitleStats,
+    /// Recent failed logins, to stop password guessing
+    pub login_throttle: LoginThrottle,
+    /// Recent wrong tag-lock PINs, kept apart from failed logins
+    pub pin_throttle: LoginThrottle,
 }
 
 /// Build and run the Axum server
-pub async fn run(config: Config) -> Result<()> {
+pub async fn run(mut config: Config) -> Result<()> {
     // Initialize tracing
     tracing::info!("Starting Mango-Rust server");
     tracing::info!("Host: {}:{}", config.host, config.port);
@@ -49,8 +97,37 @@ pub async fn run(config: Config) -> Result<()> {
     // Initialize storage (connects to database, runs migrations)
     let database_url = format!("sqlite://{}?mode=rwc", config.db_path.to_string_lossy());
     tracing::info!("Connecting to database: {}", database_url);
-    let storage = Storage::new(&database_url).await?;
+    let storage = Storage::with_settings(&database_url, PoolSettings::from_config(&config)).await?;
     tracing::info!("Database initialized at {}", config.db_path.display());
+    let version = crate::version::VersionInfo::collect(&storage).await?;
+    tracing::info!("{}", version.summary());
+
+    if let (true, Some(username)) = (config.disable_login, &config.default_username) {
+        tracing::warn!(
+            "Login is disabled, every request acts as user: {}",
+            username
+        );
+        if !storage.username_exists(username).await? {
+            tracing::warn!("default_username {} does not exist", username);
+        }
+    }
+
+    // Instance name, accent color and logo shown by every template
+    crate::util::set_branding(&config);
+
+    // Entry display names are cleaned up when titles are scanned
+    crate::library::title_cleanup::set_title_cleanup(&config);
+
+    // Stored paths are matched the way the library's filesystem compares names
+    if config.library_case_sensitive.is_none() {
+        let case_sensitive = match crate::util::probe_case_sensitive(&config.library_path) {
+            Some(case_sensitive) => case_sensitive,
+            // Nothing in the library yet: what the platform's filesystems usually do
+            None => !cfg!(any(windows, target_os = "macos")),
+        };
+        tracing::info!("Library filesystem is case-sensitive: {}", case_sensitive);
+        config.library_case_sensitive = Some(case_sensitive);
+    }
 
     // Wrap config in Arc early (needed for periodic scanner)
     let config = Arc::new(config);
@@ -62,15 +139,33 @@ pub async fn run(config: Config) -> Result<()> {
     // Try to load from cache first (fast)
     let cache_loaded = library.try_load_from_cache().await?;
 
+    // A scan interrupted by a restart is resumed even when the cache loaded
+    let interrupted_scan = crate::library::ScanJournal::progress(&storage).await?;
+    if let Some(progress) = &interrupted_scan {
+        tracing::info!(
+            "Found interrupted library scan {} ({}/{} titles done)",
+            progress.scan_id,
+            progress.done,
+            progress.total
+        );
+    }
+
     // Use ArcSwap for lock-free reads
     let library = Arc::new(ArcSwap::from_pointee(library));
 
+    // Scans publish what they changed to open browser pages
+    let events = LibraryEvents::new();
+
+    // Progress of admin scans, and how the cache compared with the disk
+    let scan_status = ScanStatus::new();
+
     // If cache didn't load, spawn background scan task (non-blocking, double-buffer)
-    if !cache_loaded {
-        tracing::info!("Cache not available, starting background library scan...");
+    if !cache_loaded || interrupted_scan.is_some() {
+        tracing::info!("Starting background library scan...");
         let library_clone = library.clone();
         let storage_clone = storage.clone();
         let config_clone = config.clone();
+        let events_clone = events.clone();
         tokio::spawn(async move {
             let start = std::time::Instant::now();
             // Build new library instance in background
@@ -79,11 +174,12 @@ pub async fn run(config: Config) -> Result<()> {
                 storage_clone,
                 &config_clone,
             );
+            new_lib.share_scan_lock(&library_clone.load());
             match new_lib.scan().await {
                 Ok(_) => {
                     let stats = new_lib.stats();
                     // Atomically swap the new library in
-                    library_clone.store(Arc::new(new_lib));
+                    events_clone.store_scanned(&library_clone, new_lib);
                     tracing::info!(
                         "Background library scan completed in {:.2}s - {} titles, {} entries",
                         start.elapsed().as_secs_f64(),
@@ -96,6 +192,31 @@ pub async fn run(config: Config) -> Result<()> {
                 }
             }
         });
+    } else {
+        // The library may have changed while the server was down: check the cache
+        // against the disk in the background and apply what it got wrong
+        tracing::info!("Verifying library cache in background...");
+        let library_clone = library.clone();
+        let storage_clone = storage.clone();
+        let config_clone = config.clone();
+        let events_clone = events.clone();
+        let scan_status_clone = scan_status.clone();
+        tokio::spawn(async move {
+            let cached = library_clone.load_full();
+            let mut new_lib = Library::new(
+                config_clone.library_path.clone(),
+                storage_clone,
+                &config_clone,
+            );
+            new_lib.share_scan_lock(&cached);
+            match new_lib.verify_cache(&cached).await {
+                Ok(verification) => {
+                    scan_status_clone.set_cache_verification(verification);
+                    events_clone.store_scanned(&library_clone, new_lib);
+                }
+                Err(e) => tracing::error!("Library cache verification failed: {}", e),
+            }
+        });
     }
 
     // Start periodic scanner if configured (similar to original Mango)
@@ -108,23 +229,316 @@ pub async fn run(config: Config) -> Result<()> {
             library.clone(),
             storage.clone(),
             config.clone(),
+            events.clone(),
             config.scan_interval_minutes as u64,
         );
     } else {
         tracing::info!("Periodic library scanning disabled (scan_interval_minutes = 0)");
     }
 
+    // Rescan titles as soon as their files change, if configured
+    if config.watch_library {
+        match spawn_library_watcher(
+            library.clone(),
+            storage.clone(),
+            config.clone(),
+            events.clone(),
+        ) {
+            Ok(_) => tracing::info!("Watching {} for changes", config.library_path.display()),
+            Err(e) => tracing::warn!("Could not watch the library for changes: {}", e),
+        }
+    }
+
     tracing::info!("Library initialization complete (server ready)");
 
+    // Download accounting (counters are buffered and flushed periodically)
+    let quota = DownloadQuota::new(storage.clone(), config.download_quota_mbs * 1024 * 1024);
+    spawn_quota_flusher(quota.clone());
+
+    // Title read statistics (buffered and flushed the same way)
+    let stats = TitleStats::new(storage.clone());
+    spawn_stats_flusher(stats.clone());
+
+    // Recently read archives are kept in memory so page requests skip re-opening them
+    let archives = ArchiveCache::new(
+        config.archive_cache_entries,
+        config.archive_cache_size_mbs * 1024 * 1024,
+    );
+
     // Create application state
     let app_state = AppState {
         storage: storage.clone(),
         library,
         config: config.clone(),
+        quota,
+        archives,
+        page_flights: PageFlights::new(),
+        scan_status,
+        conversions: Conversions::new(),
+        folder_covers: FolderCovers::new(),
+        events,
+        stats,
+        login_throttle: LoginThrottle::new(),
+        pin_throttle: LoginThrottle::new(),
+    };
+
+    let app = build_app(app_state).await?;
+
+    // Bind and serve
+    let addr = format!("{}:{}", config.host, config.port);
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    tracing::info!("Server listening on {}", addr);
+    tracing::info!("Visit http://{}{} to access Mango", addr, config.base_url);
+
+    // Connect info gives `LoginThrottle` the client address
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await
+    .map_err(|e| crate::error::Error::Internal(format!("Server error: {}", e)))?;
+
+    Ok(())
+}
+
+/// Keep `StorageHealth` up to date and let pages show the read-only banner
+/// Responses that failed with StorageUnavailable mark storage unavailable. While it is,
+/// a write request that succeeds anyway checks whether storage accepts writes again
+async fn track_storage_health(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let health = state.storage.health();
+    let write = !matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+
+    let response = crate::util::STORAGE_UNAVAILABLE
+        .scope(health.is_unavailable(), next.run(request))
+        .await;
+
+    if let Some(StorageUnavailableResponse(reason)) =
+        response.extensions().get::<StorageUnavailableResponse>()
+    {
+        health.mark_unavailable(reason);
+    } else if write && response.status().is_success() && health.is_unavailable() {
+        // check_writable records the outcome in StorageHealth
+        let _ = state.storage.check_writable().await;
+    }
+    response
+}
+
+/// Correlation ID of a request, set by the `request_id` middleware
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest inbound X-Request-Id taken over; longer ones get a new ID
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Give each request a correlation ID: the client's X-Request-Id if it sent a usable one,
+/// a new UUID otherwise. It is sent back in X-Request-Id, recorded in the request's
+/// tracing span and carried in problem documents
+async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map_or_else(|| uuid::Uuid::new_v4().to_string(), str::to_string);
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(X_REQUEST_ID.clone(), value);
+    }
+    response
+}
+
+/// Whether an inbound request ID is safe to log and echo back
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Tracing span of a request, carrying its correlation ID into every event logged for it
+fn request_span(request: &Request) -> tracing::Span {
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map_or("", |id| id.0.as_str());
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id,
+    )
+}
+
+/// Route a request under base_url by its path below it; anything outside is not found
+/// The URL builders in `paths` prefix links with this base while the request is handled
+async fn strip_base_url(State(base): State<String>, mut request: Request, next: Next) -> Response {
+    let path_and_query = request.uri().path_and_query().map_or("/", |pq| pq.as_str());
+    let Some(uri) =
+        paths::strip_base(path_and_query, &base).and_then(|path| path.parse::<Uri>().ok())
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    *request.uri_mut() = uri;
+    paths::BASE_URL.scope(base, next.run(request)).await
+}
+
+/// Largest error body carried over into the structured one; longer ones give way to the
+/// status reason
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+
+/// Media type of RFC 7807 problem documents
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Problem types are this prefix followed by the error variant's kind, e.g.
+/// `urn:mango:problem:not-found` (see `Error::kind`)
+const PROBLEM_TYPE_PREFIX: &str = "urn:mango:problem:";
+
+/// Answer failed /api requests with `{"success": false, "error": "...", "code": 404}` and
+/// failed OPDS requests with an XML error document, so clients can parse failures. Covers
+/// every plain text error response: handler errors, rejected extractors (e.g. a malformed
+/// JSON body) and the auth layer alike. Pages keep their plain text errors
+/// A client sending `Accept: application/problem+json` gets an RFC 7807 problem document
+/// instead, on any path and for every error
+async fn structured_errors(request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    let (api, opds) = (paths::is_api(path), paths::is_opds(path));
+    let problem = request
+        .headers()
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.contains(PROBLEM_JSON));
+    let request_id = request.extensions().get::<RequestId>().cloned();
+    if !api && !opds && !problem {
+        return next.run(request).await;
+    }
+
+    let response = next.run(request).await;
+    let failed = response.status().is_client_error() || response.status().is_server_error();
+    let plain_text = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/plain"));
+    let details = response.extensions().get::<ErrorDetails>().cloned();
+    if !failed || !(plain_text || (problem && details.is_some())) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let message = match &details {
+        Some(details) => details.detail.clone(),
+        None => match axum::body::to_bytes(body, MAX_ERROR_BODY_BYTES).await {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(_) => parts
+                .status
+                .canonical_reason()
+                .unwrap_or("Request failed")
+                .to_string(),
+        },
     };
+    let code = parts.status.as_u16();
+    let (content_type, body) = if problem {
+        // Failures that are not an `Error` (e.g. a rejected extractor) mean what their
+        // status means, which RFC 7807 calls `about:blank`
+        let (problem_type, title) = match &details {
+            Some(details) => (
+                format!("{}{}", PROBLEM_TYPE_PREFIX, details.kind),
+                details.title,
+            ),
+            None => (
+                "about:blank".to_string(),
+                parts.status.canonical_reason().unwrap_or("Request failed"),
+            ),
+        };
+        let request_id = request_id.map_or_else(String::new, |id| id.0);
+        tracing::info!(
+            request_id = %request_id,
+            status = code,
+            "Request failed ({}): {}",
+            problem_type,
+            message
+        );
+        let body = serde_json::json!({
+            "type": problem_type,
+            "title": title,
+            "status": code,
+            "detail": message,
+            "instance": format!("urn:mango:request:{}", request_id),
+        });
+        (PROBLEM_JSON, body.to_string())
+    } else if api {
+        let body = serde_json::json!({ "success": false, "error": message, "code": code });
+        ("application/json", body.to_string())
+    } else {
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <error><code>{}</code><message>{}</message></error>\n",
+            code,
+            xml_escape(&message)
+        );
+        ("application/xml", body)
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    Response::from_parts(parts, axum::body::Body::from(body))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// CORS for web clients hosted on other origins (config `cors_allowed_origins`)
+/// Only /api paths are allowed. The layer sits outside the auth layer, so it answers
+/// preflight requests itself, which carry no credentials.
+fn cors_layer(origins: &[String]) -> CorsLayer {
+    let any_origin = origins.iter().any(|origin| origin == "*");
+    let origins: Vec<HeaderValue> = origins
+        .iter()
+        .filter_map(|origin| HeaderValue::from_str(origin).ok())
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin, request| {
+            paths::is_api(request.uri.path()) && (any_origin || origins.contains(origin))
+        }))
+        .allow_credentials(true)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
+        .expose_headers([
+            HeaderName::from_static("x-hidden-completed"),
+            X_REQUEST_ID.clone(),
+        ])
+}
 
+/// Build the application router with its session and auth layers
+/// Kept separate from `run` so tests can drive the full app without binding a socket
+pub async fn build_app(state: AppState) -> Result<Router> {
     // Create session store (uses same database)
-    let session_store = SqliteStore::new(storage.pool().clone());
+    let session_store = SqliteStore::new(state.storage.pool().clone());
     session_store
         .migrate()
         .await
@@ -135,110 +549,254 @@ pub async fn run(config: Config) -> Result<()> {
         .with_expiry(Expiry::OnInactivity(time::Duration::days(7)));
 
     // Build router
-    let app = Router::new()
+    let mut app = Router::new()
         // Public routes (no auth required)
-        .route("/login", get(get_login).post(post_login))
+        .route(paths::LOGIN, get(get_login).post(post_login))
+        .route(paths::LOGIN_2FA, post(post_login_2fa))
+        .route(paths::BRANDING_LOGO, get(branding_logo))
         // Static files (no auth required)
-        .nest_service("/static", ServeDir::new("static"))
+        .nest(
+            paths::STATIC,
+            assets::router(state.config.static_path.as_deref()),
+        )
         // Protected routes (auth required)
-        .route("/", get(home))
-        .route("/library", get(library_page))
-        .route("/book/:id", get(get_book))
-        .route("/change-password", get(change_password_page))
-        .route("/logout", get(logout))
+        .route(paths::HOME, get(home))
+        .route(paths::LIBRARY, get(library_page))
+        .route(paths::BOOK, get(get_book))
+        .route(paths::CHANGE_PASSWORD, get(change_password_page))
+        .route(paths::LOGOUT, get(logout))
         // Tags routes
-        .route("/tags", get(list_tags_page))
-        .route("/tags/:tag", get(view_tag_page))
+        .route(paths::TAGS, get(list_tags_page))
+        .route(paths::TAG, get(view_tag_page))
+        .route(paths::COLLECTION, get(view_collection_page))
         // Admin routes (requires admin access)
-        .route("/admin", get(admin_dashboard))
-        .route("/admin/missing-items", get(missing_items_page))
-        .route("/admin/user", get(users_page))
-        .route("/admin/user/edit", get(user_edit_page).post(user_edit_post))
-        .route("/admin/user/edit/:username", post(user_edit_post_existing))
+        .route(paths::ADMIN, get(admin_dashboard))
+        .route(paths::ADMIN_MISSING_ITEMS, get(missing_items_page))
+        .route(paths::ADMIN_REPORTS, get(reports_page))
+        .route(paths::ADMIN_STATS, get(popular_page))
+        .route(paths::ADMIN_USERS, get(users_page))
+        .route(
+            paths::ADMIN_USER_EDIT,
+            get(user_edit_page).post(user_edit_post),
+        )
+        .route(
+            paths::ADMIN_USER_EDIT_EXISTING,
+            post(user_edit_post_existing),
+        )
         // Cache debug route
-        .route("/debug/cache", get(cache_debug_page))
+        .route(paths::DEBUG_CACHE, get(cache_debug_page))
         // Admin API routes
-        .route("/api/admin/scan", post(scan_library))
-        // Cache API routes
-        .route("/api/cache/clear", post(cache_clear_api))
-        .route("/api/cache/save-library", post(cache_save_library_api))
-        .route("/api/cache/load-library", post(cache_load_library_api))
-        .route("/api/cache/invalidate", post(cache_invalidate_api))
+        .route(paths::API_ADMIN_SCAN, post(scan_library))
+        .route(paths::API_ADMIN_SCAN_RESUME, post(resume_scan))
+        .route(paths::API_ADMIN_SCAN_STATUS, get(scan_status))
+        .route(paths::API_ADMIN_SCAN_TITLE, post(scan_title))
         .route(
-            "/api/admin/entries/missing",
-            get(get_missing_entries).delete(delete_all_missing_entries),
+            paths::API_ADMIN_REBUILD_SIGNATURES,
+            post(rebuild_signatures),
         )
         .route(
-            "/api/admin/entries/missing/:id",
-            delete(delete_missing_entry),
+            paths::API_ADMIN_BACKFILL_DATE_ADDED,
+            post(backfill_date_added),
         )
-        .route("/api/admin/users", get(get_users).post(create_user))
         .route(
-            "/api/admin/users/:username",
-            patch(update_user).delete(delete_user),
+            paths::API_ADMIN_PRUNE_THUMBNAILS,
+            post(prune_thumbnails_api),
         )
         .route(
-            "/api/admin/user/delete/:username",
-            delete(delete_user_api),
+            paths::API_ADMIN_CONVERT,
+            get(conversion_status).post(start_conversion),
         )
+        // Cache API routes
+        .route(paths::API_CACHE_CLEAR, post(cache_clear_api))
+        .route(paths::API_CACHE_SAVE_LIBRARY, post(cache_save_library_api))
+        .route(paths::API_CACHE_LOAD_LIBRARY, post(cache_load_library_api))
+        .route(paths::API_CACHE_INVALIDATE, post(cache_invalidate_api))
+        .route(paths::API_CACHE_STATS, get(cache_stats_api))
+        .route(
+            paths::API_ADMIN_MISSING_ENTRIES,
+            get(get_missing_entries).delete(delete_all_missing_entries),
+        )
+        .route(paths::API_ADMIN_MISSING_ENTRY, delete(delete_missing_entry))
+        .route(paths::API_ADMIN_USERS, get(get_users).post(create_user))
+        .route(
+            paths::API_ADMIN_USER,
+            patch(update_user).delete(delete_user),
+        )
+        .route(paths::API_ADMIN_USER_DELETE, delete(delete_user_api))
+        .route(paths::API_ADMIN_USER_TWO_FACTOR, delete(reset_two_factor))
+        .route(paths::API_ADMIN_USER_RESET_PASSWORD, post(reset_password))
         // Reader routes
-        .route("/reader/:tid/:eid", get(reader_continue))
-        .route("/reader/:tid/:eid/:page", get(reader))
+        .route(paths::READER_CONTINUE, get(reader_continue))
+        .route(paths::READER, get(reader))
         // API routes
-        .route("/api/library", get(get_library))
-        .route("/api/title/:id", get(get_title))
-        .route("/api/page/:tid/:eid/:page", get(get_page))
-        .route("/api/cover/:tid/:eid", get(get_cover))
-        .route("/api/stats", get(get_stats))
-        .route("/api/download/:tid/:eid", get(download_entry))
+        .route(paths::API_LIBRARY, get(get_library))
+        .route(paths::API_LIBRARY_INDEX, get(get_library_index))
+        .route(paths::API_LIBRARY_LETTERS, get(get_library_letters))
+        .route(paths::API_EVENTS, get(library_events))
+        .route(paths::API_TITLE, get(get_title))
+        .route(paths::API_PAGE, get(get_page))
+        .route(paths::API_COVER, get(get_cover).head(head_cover))
+        .route(
+            paths::API_TITLE_COVER,
+            get(get_title_cover).head(head_title_cover),
+        )
+        .route(paths::API_STATS, get(get_stats))
+        .route(paths::API_CAPABILITIES, get(get_capabilities))
+        .route(paths::API_VERSION, get(get_version))
+        .route(paths::API_SEARCH, get(search))
+        .route(paths::API_DOWNLOAD, get(download_entry))
         // OPDS catalog routes
-        .route("/opds", get(opds_index))
-        .route("/opds/book/:title_id", get(opds_title))
+        .route(paths::OPDS, get(opds_index))
+        .route(paths::OPDS_BOOK, get(opds_title))
+        .route(paths::OPDS_CONTINUE, get(opds_continue))
+        .route(paths::OPDS_RECENT, get(opds_recent))
+        .route(paths::OPDS_COLLECTION, get(opds_collection))
+        // Atom feeds (feed token auth)
+        .route(paths::FEED_RECENT, get(feed_recent))
+        .route(paths::FEED_TITLE, get(feed_title))
         // Tags API routes
-        .route("/api/tags", get(list_tags))
-        .route("/api/tags/:tid", get(get_title_tags))
-        .route("/api/admin/tags/:tid/:tag", put(add_tag).delete(delete_tag))
+        .route(paths::API_TAGS, get(list_tags))
+        .route(paths::API_TITLE_TAGS, get(get_title_tags))
+        .route(paths::API_ADMIN_TAG, put(add_tag).delete(delete_tag))
         // Home page API routes
-        .route("/api/library/continue_reading", get(continue_reading))
-        .route("/api/library/start_reading", get(start_reading))
-        .route("/api/library/recently_added", get(recently_added))
+        .route(paths::API_CONTINUE_READING, get(continue_reading))
+        .route(paths::API_START_READING, get(start_reading))
+        .route(paths::API_RECENTLY_ADDED, get(recently_added))
         // Progress API
         .route(
-            "/api/progress/:tid/:page",
+            paths::API_PROGRESS_ENTRY,
             get(get_progress).post(save_progress).put(update_progress),
         )
-        .route("/api/progress", get(get_all_progress))
+        .route(paths::API_PROGRESS, get(get_all_progress))
+        .route(paths::API_PROGRESS_BULK_READ, post(bulk_read))
         // Dimensions API (for reader)
-        .route("/api/dimensions/:tid/:eid", get(get_dimensions))
+        .route(paths::API_DIMENSIONS, get(get_dimensions))
+        .route(paths::API_PAGE_THUMBNAILS, get(get_page_thumbnails))
+        .route(
+            paths::API_PAGE_THUMBNAILS_SPRITE,
+            get(get_page_thumbnails_sprite),
+        )
         // User API
-        .route("/api/user/change-password", post(change_password_api))
+        .route(paths::API_CHANGE_PASSWORD, post(change_password_api))
+        .route(paths::API_USER_QUOTA, get(get_quota))
+        .route(
+            paths::API_USER_PREFERENCES,
+            get(get_preferences).put(set_preferences),
+        )
+        .route(paths::API_USER_HIDE_COMPLETED, put(set_hide_completed))
+        .route(paths::API_USER_PROGRESS_CSV, get(get_progress_csv))
+        .route(paths::API_PINS, get(list_pins))
+        .route(paths::API_PIN, put(pin_title).delete(unpin_title))
+        .route(
+            paths::API_COLLECTIONS,
+            get(list_collections).post(create_collection),
+        )
+        .route(
+            paths::API_COLLECTION,
+            get(get_collection)
+                .put(rename_collection)
+                .delete(delete_collection),
+        )
+        .route(
+            paths::API_COLLECTION_TITLE,
+            put(add_collection_title).delete(remove_collection_title),
+        )
+        .route(paths::API_COLLECTION_ORDER, put(set_collection_order))
+        .route(paths::API_TWO_FACTOR, get(two_factor_status))
+        .route(paths::API_TWO_FACTOR_ENROLL, post(two_factor_enroll))
+        .route(paths::API_TWO_FACTOR_ENABLE, post(two_factor_enable))
+        .route(paths::API_APP_TOKENS, post(create_app_token))
+        .route(paths::API_APP_TOKEN, delete(delete_app_token))
+        .route(
+            paths::API_API_TOKENS,
+            get(list_api_tokens).post(create_api_token),
+        )
+        .route(paths::API_API_TOKEN, delete(delete_api_token))
+        .route(paths::API_SESSIONS, get(list_sessions))
+        .route(paths::API_SESSIONS_REVOKE_ALL, post(revoke_sessions))
+        .route(
+            paths::API_FEED_TOKEN,
+            get(get_feed_token)
+                .post(create_feed_token)
+                .delete(delete_feed_token),
+        )
+        .route(paths::API_TAG_LOCKS, get(list_tag_locks))
+        .route(
+            paths::API_TAG_LOCK,
+            put(set_tag_lock).delete(remove_tag_lock),
+        )
+        .route(paths::API_UNLOCK, post(unlock_tags))
+        .route(paths::API_ADMIN_QUOTA, get(get_all_quotas))
+        .route(paths::API_ADMIN_DIAGNOSTICS, get(get_diagnostics))
+        .route(paths::API_ADMIN_BAD_PAGES, get(get_bad_pages))
+        .route(paths::API_ADMIN_POPULAR, get(get_popular_titles))
+        // Problem reports
+        .route(paths::API_REPORT, post(report_entry))
+        .route(paths::API_ADMIN_REPORTS, get(get_reports))
+        .route(paths::API_ADMIN_REPORT, patch(resolve_report))
         // Admin metadata API
-        .route("/api/admin/display_name/:tid/:name", put(update_display_name))
-        .route("/api/admin/sort_title/:tid", put(update_sort_title))
-        .route("/api/admin/upload/cover", post(upload_cover))
+        .route(paths::API_ADMIN_DISPLAY_NAME, put(update_display_name))
+        .route(paths::API_ADMIN_SORT_TITLE, put(update_sort_title))
+        .route(paths::API_TITLE_DIRECTION, put(set_title_direction))
+        .route(paths::API_ENTRY_DIRECTION, put(set_entry_direction))
+        .route(
+            paths::API_ADMIN_ENTRY_ORDER,
+            put(set_entry_order).delete(clear_entry_order),
+        )
+        .route(paths::API_ADMIN_MERGE_ENTRIES, post(merge_entries))
+        .route(paths::API_ADMIN_MERGED_ENTRY, delete(unmerge_entries))
+        .route(paths::API_ADMIN_TITLE_ALIASES, put(set_title_aliases))
+        .route(paths::API_ADMIN_TITLE_RELATIONS, put(set_title_relations))
+        .route(paths::API_ADMIN_TITLE_FILES, get(get_title_files))
+        .route(paths::API_ADMIN_TITLE_READERS, get(get_title_readers))
+        .route(paths::API_ADMIN_UPLOAD_COVER, post(upload_cover))
         // Bulk progress API
-        .route("/api/bulk_progress/:action/:tid", put(bulk_progress))
+        .route(paths::API_BULK_PROGRESS, put(bulk_progress))
         // Thumbnail generation API
-        .route("/api/admin/thumbnail_progress", get(thumbnail_progress))
-        .route("/api/admin/generate_thumbnails", post(generate_thumbnails))
+        .route(paths::API_THUMBNAIL_PROGRESS, get(thumbnail_progress))
+        .route(paths::API_GENERATE_THUMBNAILS, post(generate_thumbnails));
+
+    // Komga-compatible read progress API (opt-in); other methods and endpoints get 501
+    if state.config.komga_compat_enabled {
+        app = app
+            .route(
+                paths::KOMGA_BOOK,
+                get(get_komga_book).fallback(komga_not_implemented),
+            )
+            .route(
+                paths::KOMGA_READ_PROGRESS,
+                patch(patch_komga_read_progress).fallback(komga_not_implemented),
+            )
+            .route(paths::KOMGA_UNIMPLEMENTED, any(komga_not_implemented));
+    }
+
+    let app = app
         // Add state and middleware
+        .layer(middleware::from_fn_with_state(state.clone(), require_auth))
+        .layer(middleware::from_fn(structured_errors))
         .layer(middleware::from_fn_with_state(
-            app_state.clone(),
-            require_auth,
+            state.clone(),
+            track_storage_health,
         ))
-        .layer(session_layer)
-        .layer(TraceLayer::new_for_http())
-        .with_state(app_state);
-
-    // Bind and serve
-    let addr = format!("{}:{}", config.host, config.port);
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    tracing::info!("Server listening on {}", addr);
-    tracing::info!("Visit http://{}{} to access Mango", addr, config.base_url);
+        .layer(session_layer);
+    let app = if state.config.cors_allowed_origins.is_empty() {
+        app
+    } else {
+        app.layer(cors_layer(&state.config.cors_allowed_origins))
+    };
 
-    axum::serve(listener, app)
-        .await
-        .map_err(|e| crate::error::Error::Internal(format!("Server error: {}", e)))?;
+    // Mounted under base_url, the routes and middleware only see paths below it
+    let base = state.config.base_url.trim_end_matches('/').to_string();
+    let app = app.with_state(state);
+    let app = if base.is_empty() {
+        app
+    } else {
+        Router::new()
+            .fallback_service(app)
+            .layer(middleware::from_fn_with_state(base, strip_base_url))
+    };
 
-    Ok(())
+    Ok(app
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(middleware::from_fn(request_id)))
 }
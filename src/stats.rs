@@ -0,0 +1,325 @@
+// Per-title read statistics and the popularity ranking built on them
+//
+// Page views, reads and downloads are counted per title per UTC day. Like download
+// quota accounting, counters are buffered in memory and flushed to the title_stats
+// table periodically, so serving a page never waits on SQLite. A "read" is an entry
+// open; a user's repeated views of the same entry count once until they have left it
+// alone for a while.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::storage::TitleCounts;
+use crate::Storage;
+
+/// How often buffered counters are flushed to the database
+const FLUSH_INTERVAL_SECS: u64 = 10;
+
+/// Longest wait between flushes while they keep failing
+const MAX_FLUSH_BACKOFF_SECS: u64 = 10 * 60;
+
+/// Views of an entry by the same user closer together than this are one read
+const READ_DEDUP_SECS: i64 = 30 * 60;
+
+/// How long computed popularity scores are reused
+const SCORE_CACHE_SECS: u64 = 5 * 60;
+
+/// Popularity score per title ID
+type Scores = Arc<HashMap<String, u64>>;
+
+/// Days of statistics the popularity sort looks at
+pub const POPULARITY_DAYS: u32 = 30;
+
+/// Longest window the popular titles report accepts
+pub const MAX_STATS_DAYS: u32 = 365;
+
+/// Popularity score of a title's counters: reads plus downloads
+pub fn score(counts: &TitleCounts) -> u64 {
+    counts.reads + counts.downloads
+}
+
+/// When each user last viewed each entry, for deduplicating reads
+#[derive(Default)]
+struct RecentReads {
+    /// (username, entry_id) -> Unix timestamp of the last view
+    seen: HashMap<(String, String), i64>,
+}
+
+impl RecentReads {
+    /// Note a view, returning true if it starts a new read
+    /// Every view extends the window, so a long reading session counts once
+    fn view(&mut self, username: &str, entry_id: &str, now: i64) -> bool {
+        let last = self
+            .seen
+            .insert((username.to_string(), entry_id.to_string()), now);
+        !matches!(last, Some(last) if now - last < READ_DEDUP_SECS)
+    }
+
+    /// Forget views old enough that the next one is a new read anyway
+    fn prune(&mut self, now: i64) {
+        self.seen.retain(|_, last| now - *last < READ_DEDUP_SECS);
+    }
+}
+
+/// Buffered per-title read counters with a cached popularity ranking
+#[derive(Clone)]
+pub struct TitleStats {
+    storage: Storage,
+    /// (title_id, day) -> counters not yet flushed
+    pending: Arc<Mutex<HashMap<(String, String), TitleCounts>>>,
+    recent_reads: Arc<Mutex<RecentReads>>,
+    /// Scores per title ID over the last POPULARITY_DAYS, with when they were computed
+    scores: Arc<Mutex<Option<(Instant, Scores)>>>,
+}
+
+impl TitleStats {
+    pub fn new(storage: Storage) -> Self {
+        Self {
+            storage,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            recent_reads: Arc::new(Mutex::new(RecentReads::default())),
+            scores: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Apply a change to today's buffered counters of a title
+    fn bump(&self, title_id: &str, update: impl FnOnce(&mut TitleCounts)) {
+        match self.pending.lock() {
+            Ok(mut pending) => {
+                update(
+                    pending
+                        .entry((title_id.to_string(), crate::quota::today()))
+                        .or_default(),
+                );
+            }
+            Err(e) => {
+                tracing::error!("Title stats lock poisoned during record: {}", e);
+            }
+        }
+    }
+
+    /// Whether a user's view of an entry starts a new read
+    fn is_new_read(&self, username: &str, entry_id: &str) -> bool {
+        let mut recent = self.recent_reads.lock().unwrap_or_else(|e| e.into_inner());
+        recent.view(username, entry_id, crate::util::time::now_ts())
+    }
+
+    /// Record a page image served to a user (also a read if they just opened the entry)
+    pub fn record_page_view(&self, username: &str, title_id: &str, entry_id: &str) {
+        let new_read = self.is_new_read(username, entry_id);
+        self.bump(title_id, |counts| {
+            counts.page_views += 1;
+            if new_read {
+                counts.reads += 1;
+            }
+        });
+    }
+
+    /// Record a user opening an entry in the reader
+    pub fn record_open(&self, username: &str, title_id: &str, entry_id: &str) {
+        if self.is_new_read(username, entry_id) {
+            self.bump(title_id, |counts| counts.reads += 1);
+        }
+    }
+
+    /// Record an entry download
+    pub fn record_download(&self, title_id: &str) {
+        self.bump(title_id, |counts| counts.downloads += 1);
+    }
+
+    /// Counters per title over the last `days` days including today (persisted +
+    /// buffered), most popular first
+    pub async fn popular(&self, days: u32) -> Result<Vec<(String, TitleCounts)>> {
+        let since = (chrono::Utc::now() - chrono::Duration::days(days.max(1) as i64 - 1))
+            .format("%Y-%m-%d")
+            .to_string();
+
+        let mut totals: HashMap<String, TitleCounts> = self
+            .storage
+            .title_stats_since(&since)
+            .await?
+            .into_iter()
+            .collect();
+
+        if let Ok(pending) = self.pending.lock() {
+            for ((title_id, day), counts) in pending.iter() {
+                if *day >= since {
+                    totals.entry(title_id.clone()).or_default().add(counts);
+                }
+            }
+        }
+
+        let mut popular: Vec<(String, TitleCounts)> = totals.into_iter().collect();
+        popular.sort_by(|(a_id, a), (b_id, b)| {
+            score(b)
+                .cmp(&score(a))
+                .then_with(|| b.page_views.cmp(&a.page_views))
+                .then_with(|| a_id.cmp(b_id))
+        });
+        Ok(popular)
+    }
+
+    /// Popularity scores per title ID over the last POPULARITY_DAYS days
+    /// Computed at most every few minutes; titles without reads are absent
+    pub async fn popularity_scores(&self) -> Result<Scores> {
+        if let Ok(cached) = self.scores.lock() {
+            if let Some((computed, scores)) = cached.as_ref() {
+                if computed.elapsed() < Duration::from_secs(SCORE_CACHE_SECS) {
+                    return Ok(scores.clone());
+                }
+            }
+        }
+
+        let scores: Scores = Arc::new(
+            self.popular(POPULARITY_DAYS)
+                .await?
+                .into_iter()
+                .map(|(title_id, counts)| (title_id, score(&counts)))
+                .collect(),
+        );
+        if let Ok(mut cached) = self.scores.lock() {
+            *cached = Some((Instant::now(), scores.clone()));
+        }
+        Ok(scores)
+    }
+
+    /// Flush buffered counters to the database
+    /// On failure the counters are put back so nothing is lost
+    pub async fn flush(&self) -> Result<()> {
+        if let Ok(mut recent) = self.recent_reads.lock() {
+            recent.prune(crate::util::time::now_ts());
+        }
+
+        let drained: Vec<((String, String), TitleCounts)> = match self.pending.lock() {
+            Ok(mut pending) => pending.drain().collect(),
+            Err(e) => {
+                return Err(Error::Internal(format!(
+                    "Title stats lock poisoned during flush: {}",
+                    e
+                )))
+            }
+        };
+
+        if drained.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(e) = self.storage.add_title_stats(&drained).await {
+            if let Ok(mut pending) = self.pending.lock() {
+                for (key, counts) in drained {
+                    pending.entry(key).or_default().add(&counts);
+                }
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+}
+
+/// Spawn a background task that periodically flushes buffered title statistics
+pub fn spawn_stats_flusher(stats: TitleStats) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(FLUSH_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut backoff = crate::util::Backoff::new(
+            Duration::from_secs(FLUSH_INTERVAL_SECS),
+            Duration::from_secs(MAX_FLUSH_BACKOFF_SECS),
+        );
+
+        loop {
+            interval.tick().await;
+
+            match stats.flush().await {
+                Ok(()) => backoff.reset(),
+                Err(e) => {
+                    // Counters stay buffered; wait longer each time so a read-only or
+                    // full disk is not hammered
+                    let delay = backoff.next_delay();
+                    tracing::warn!(
+                        "Failed to flush title statistics, retrying in {}s: {}",
+                        delay.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_are_deduplicated_per_user_and_entry() {
+        let mut recent = RecentReads::default();
+        assert!(recent.view("alice", "e1", 1000));
+        // Turning pages keeps it one read
+        assert!(!recent.view("alice", "e1", 1010));
+        assert!(!recent.view("alice", "e1", 1000 + READ_DEDUP_SECS));
+        // Other entries and other users are separate reads
+        assert!(recent.view("alice", "e2", 1020));
+        assert!(recent.view("bob", "e1", 1020));
+
+        // Coming back after leaving it alone is a new read
+        let later = 1000 + 3 * READ_DEDUP_SECS;
+        assert!(recent.view("alice", "e1", later));
+
+        recent.prune(later + READ_DEDUP_SECS);
+        assert!(recent.seen.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_popular_merges_stored_and_buffered_counts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        let stats = TitleStats::new(storage.clone());
+
+        // An old day outside the window is ignored
+        let old = TitleCounts {
+            page_views: 100,
+            reads: 50,
+            downloads: 0,
+        };
+        storage
+            .add_title_stats(&[(("t1".to_string(), "2000-01-01".to_string()), old)])
+            .await
+            .unwrap();
+
+        stats.record_page_view("alice", "t1", "e1");
+        stats.record_page_view("alice", "t1", "e1");
+        stats.record_download("t2");
+        stats.record_download("t2");
+        stats.flush().await.unwrap();
+        stats.record_open("bob", "t1", "e1");
+
+        let popular = stats.popular(30).await.unwrap();
+        assert_eq!(
+            popular,
+            vec![
+                (
+                    "t1".to_string(),
+                    TitleCounts {
+                        page_views: 2,
+                        reads: 2,
+                        downloads: 0
+                    }
+                ),
+                (
+                    "t2".to_string(),
+                    TitleCounts {
+                        page_views: 0,
+                        reads: 0,
+                        downloads: 2
+                    }
+                ),
+            ]
+        );
+    }
+}
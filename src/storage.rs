@@ -1,9 +1,75 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use bcrypt::{hash, verify, DEFAULT_COST};
-use sqlx::{sqlite::SqlitePool, Row};
+use sqlx::pool::PoolConnection;
+use sqlx::sqlite::{Sqlite, SqliteConnection, SqlitePool};
+use sqlx::{Connection, Row};
 use uuid::Uuid;
 
+use crate::config::CardDensity;
 use crate::error::{Error, Result};
 
+/// Rows and blob bytes removed by `Storage::prune_thumbnails`
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize)]
+pub struct ThumbnailPrune {
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+/// Read counters of a title, per day in the title_stats table or summed over days
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct TitleCounts {
+    pub page_views: u64,
+    pub reads: u64,
+    pub downloads: u64,
+}
+
+impl TitleCounts {
+    /// Add another set of counters to this one
+    pub fn add(&mut self, other: &TitleCounts) {
+        self.page_views += other.page_views;
+        self.reads += other.reads;
+        self.downloads += other.downloads;
+    }
+}
+
+/// Entries merged into one logical entry (see `library::merge`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryMerge {
+    pub title_id: String,
+    /// (entry_id, signature) of each part in reading order; the first part's ID is
+    /// the merged entry's
+    pub parts: Vec<(String, String)>,
+}
+
+/// A user's named group of titles, in an order of their choosing
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    /// Unix timestamp
+    pub created_at: i64,
+    /// Title IDs in the user's order
+    pub title_ids: Vec<String>,
+}
+
+/// How a title relates to other titles, by their IDs
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct TitleRelations {
+    /// The title this one continues
+    pub sequel_of: Option<String>,
+    /// The title continuing this one
+    pub prequel_of: Option<String>,
+    /// Spin-offs and side stories, in no particular order
+    pub related: Vec<String>,
+}
+
 /// Represents a missing (unavailable) database entry
 /// Used for displaying and managing items whose files are no longer on disk
 #[derive(Debug, Clone, serde::Serialize)]
@@ -14,6 +80,81 @@ pub struct MissingEntry {
     pub entry_type: String,
 }
 
+/// An app-specific token (the token itself is only shown when created)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AppToken {
+    pub id: String,
+    pub label: String,
+    pub created_at: i64,
+}
+
+/// A user as listed for admins
+#[derive(Debug, Clone)]
+pub struct UserAccount {
+    pub username: String,
+    pub is_admin: bool,
+    /// False for read-only (guest) users; see `can_write`
+    pub can_write: bool,
+    /// Unix timestamp, None if the user never logged in
+    pub last_login: Option<i64>,
+}
+
+/// A browser logged in as a user
+#[derive(Debug, Clone)]
+pub struct LoginSession {
+    /// What the browser's session holds
+    pub token: String,
+    /// Unix timestamps
+    pub created_at: i64,
+    pub last_seen: i64,
+    pub user_agent: Option<String>,
+}
+
+/// An API token (the token itself is only shown when created)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+    /// Unix timestamp of the last request it authenticated
+    pub last_used: Option<i64>,
+}
+
+/// A user's display preferences; None falls back to the instance default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UserPreferences {
+    /// Titles per library page
+    pub page_size: Option<usize>,
+    pub card_density: Option<CardDensity>,
+    /// Leave fully read titles out of the library unless asked for
+    #[serde(default)]
+    pub hide_completed: bool,
+}
+
+impl UserPreferences {
+    /// Titles per library page in effect (0 = all on one page), within the configured maximum
+    pub fn page_size(&self, config: &crate::Config) -> usize {
+        match self.page_size {
+            Some(size) => size.clamp(1, config.library_max_page_size),
+            None => config.library_page_size,
+        }
+    }
+
+    /// Library layout in effect
+    pub fn card_density(&self, config: &crate::Config) -> CardDensity {
+        self.card_density.unwrap_or(config.library_card_density)
+    }
+}
+
+/// A title or entry ID row with its stored signature (for signature maintenance)
+#[derive(Debug, Clone)]
+pub struct SignatureRow {
+    pub id: String,
+    /// Path relative to the library root
+    pub path: String,
+    pub signature: Option<String>,
+}
+
 /// Stored page dimension data (from database cache)
 #[derive(Debug, Clone)]
 pub struct StoredDimension {
@@ -22,16 +163,280 @@ pub struct StoredDimension {
     pub height: u32,
 }
 
+/// Page validation result for an entry
+#[derive(Debug, Clone)]
+pub struct PageCheck {
+    pub entry_id: String,
+    /// Page count when the entry was checked
+    pub pages: usize,
+    /// One-based page numbers that failed to decode
+    pub bad_pages: Vec<usize>,
+    pub checked_at: i64,
+}
+
+/// Page thumbnail sprite of an entry (one small frame per sampled page)
+#[derive(Debug, Clone)]
+pub struct PageSprite {
+    pub entry_id: String,
+    /// Entry signature when generated (a changed signature means the archive was replaced)
+    pub signature: String,
+    pub data: Vec<u8>,
+    pub mime: String,
+    /// Frame height in pixels
+    pub height: u32,
+    /// Every step-th page has a frame (1 unless the entry is very long)
+    pub step: usize,
+    pub frames: Vec<SpriteFrame>,
+}
+
+/// Columns of a `page_sprites` row, frames as JSON
+type PageSpriteRow = (String, String, Vec<u8>, String, i64, i64, String);
+
+/// Position of a page's frame inside a sprite
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SpriteFrame {
+    /// One-based page number
+    pub page: usize,
+    /// Horizontal offset in pixels
+    pub x: u32,
+    pub width: u32,
+}
+
+/// Resolved reports kept for history (oldest are pruned beyond this)
+const RESOLVED_REPORT_RETENTION: i64 = 500;
+
+/// Seconds between updates of a login session's last_seen, to spare a write per request
+const SESSION_SEEN_INTERVAL_SECS: i64 = 60;
+
+/// Seconds between updates of a user's last_login, as Basic auth logs in on every request
+const LOGIN_SEEN_INTERVAL_SECS: i64 = 60;
+
+/// Kind of problem a user can report against an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportCategory {
+    Corrupt,
+    Misordered,
+    WrongTitle,
+    Other,
+}
+
+impl ReportCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportCategory::Corrupt => "corrupt",
+            ReportCategory::Misordered => "misordered",
+            ReportCategory::WrongTitle => "wrong_title",
+            ReportCategory::Other => "other",
+        }
+    }
+}
+
+/// A problem report filed by a user against an entry
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Report {
+    pub id: i64,
+    pub title_id: String,
+    pub entry_id: String,
+    pub username: String,
+    pub category: String,
+    pub note: String,
+    /// Unix timestamp (UTC seconds)
+    pub created_at: i64,
+    /// Unix timestamp (UTC seconds), null while open
+    #[serde(with = "crate::util::time::opt_ts")]
+    pub resolved_at: Option<i64>,
+    pub resolved_by: Option<String>,
+}
+
+/// A row of the per-user recently read index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecentlyRead {
+    pub username: String,
+    pub title_id: String,
+    pub entry_id: String,
+    pub page: i32,
+    pub last_read: i64,
+}
+
+/// Connection pool sizing and health thresholds
+#[derive(Debug, Clone)]
+pub struct PoolSettings {
+    /// Maximum number of pooled connections
+    pub max_connections: u32,
+    /// Connections kept warm even when idle
+    pub min_connections: u32,
+    /// How long a caller waits for a free connection before giving up
+    pub acquire_timeout: Duration,
+    /// Waits for a connection longer than this are logged and counted
+    pub slow_acquire: Duration,
+    /// Statements (and connection holds) longer than this are logged and counted
+    pub slow_query: Duration,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: 20, // Support up to 20 concurrent connections for parallel scanning
+            min_connections: 3,  // Keep 3 connections warm
+            acquire_timeout: Duration::from_secs(30),
+            slow_acquire: Duration::from_millis(100),
+            slow_query: Duration::from_millis(500),
+        }
+    }
+}
+
+impl PoolSettings {
+    /// Default pool settings with thresholds taken from the config file
+    pub fn from_config(config: &crate::Config) -> Self {
+        Self {
+            slow_acquire: Duration::from_millis(config.db_slow_acquire_ms),
+            slow_query: Duration::from_millis(config.db_slow_query_ms),
+            ..Self::default()
+        }
+    }
+}
+
+/// Pool health counters (shared by every clone of Storage)
+#[derive(Default)]
+struct PoolMetrics {
+    acquire_timeouts: AtomicU64,
+    slow_acquires: AtomicU64,
+    slow_queries: AtomicU64,
+}
+
+/// Snapshot of pool health for the admin diagnostics endpoint
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PoolStats {
+    /// Open connections (idle + in use)
+    pub size: u32,
+    /// Open connections not currently in use
+    pub idle: usize,
+    pub max_connections: u32,
+    /// Callers that gave up waiting for a connection (served as 503)
+    pub acquire_timeouts: u64,
+    /// Connection waits above the slow_acquire threshold
+    pub slow_acquires: u64,
+    /// Statements above the slow_query threshold
+    pub slow_queries: u64,
+    pub slow_acquire_ms: u64,
+    pub slow_query_ms: u64,
+    /// When writes started failing on read-only or full storage, None while they succeed
+    pub unavailable_since: Option<i64>,
+}
+
+/// Whether the database currently accepts writes (shared by every clone of Storage)
+/// Set by the first write failing with `Error::StorageUnavailable` (read-only database,
+/// full disk), cleared by the next write that succeeds. Reads are unaffected either way
+#[derive(Debug, Clone, Default)]
+pub struct StorageHealth {
+    /// When writes started failing (Unix seconds), 0 while healthy
+    unavailable_since: Arc<AtomicI64>,
+}
+
+impl StorageHealth {
+    /// When writes started failing, None while they succeed
+    pub fn unavailable_since(&self) -> Option<i64> {
+        match self.unavailable_since.load(Ordering::Relaxed) {
+            0 => None,
+            since => Some(since),
+        }
+    }
+
+    pub fn is_unavailable(&self) -> bool {
+        self.unavailable_since().is_some()
+    }
+
+    /// Note that a write failed because storage is unavailable (logged once per outage)
+    pub fn mark_unavailable(&self, reason: &str) {
+        let now = crate::util::time::now_ts();
+        if self
+            .unavailable_since
+            .compare_exchange(0, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            tracing::error!(
+                "Storage stopped accepting writes: {}. Serving read-only until a write succeeds",
+                reason
+            );
+        }
+    }
+
+    /// Note that a write succeeded
+    pub fn mark_available(&self) {
+        if self.unavailable_since.swap(0, Ordering::Relaxed) != 0 {
+            tracing::info!("Storage accepts writes again");
+        }
+    }
+
+    /// Note the outcome of a write; errors other than StorageUnavailable say nothing
+    pub fn record<T>(&self, result: &Result<T>) {
+        match result {
+            Ok(_) => self.mark_available(),
+            Err(Error::StorageUnavailable(reason)) => self.mark_unavailable(reason),
+            Err(_) => {}
+        }
+    }
+}
+
+/// Pooled connection that records how long it was held
+/// Storage methods run one statement per connection, so a long hold is a slow query
+pub struct TimedConnection {
+    conn: PoolConnection<Sqlite>,
+    acquired_at: Instant,
+    slow_query: Duration,
+    metrics: Arc<PoolMetrics>,
+}
+
+impl Deref for TimedConnection {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.conn
+    }
+}
+
+impl DerefMut for TimedConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.conn
+    }
+}
+
+impl Drop for TimedConnection {
+    fn drop(&mut self) {
+        let held = self.acquired_at.elapsed();
+        if held >= self.slow_query {
+            self.metrics.slow_queries.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(
+                "Slow database query: connection held for {}ms (threshold {}ms)",
+                held.as_millis(),
+                self.slow_query.as_millis()
+            );
+        }
+    }
+}
+
+/// Future of a `Storage::transaction` body, borrowing the transaction's connection
+pub type TxFuture<'c, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'c>>;
+
 /// Database storage layer - handles user authentication and data persistence
 /// Matches original Mango's Storage class functionality
 #[derive(Clone)]
 pub struct Storage {
     pool: SqlitePool,
+    settings: PoolSettings,
+    metrics: Arc<PoolMetrics>,
+    health: StorageHealth,
 }
 
 impl Storage {
     /// Initialize storage and run migrations
     pub async fn new(database_url: &str) -> Result<Self> {
+        Self::with_settings(database_url, PoolSettings::default()).await
+    }
+
+    /// Initialize storage with explicit pool settings and run migrations
+    pub async fn with_settings(database_url: &str, settings: PoolSettings) -> Result<Self> {
         // Create parent directory if it doesn't exist
         if let Some(path) = database_url.strip_prefix("sqlite://") {
             // Handle both sqlite://path and sqlite:///path (triple slash for absolute paths)
@@ -48,18 +453,20 @@ impl Storage {
 
         // Configure connection pool for better concurrency
         use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+        use sqlx::ConnectOptions;
         use std::str::FromStr;
 
         let options = SqliteConnectOptions::from_str(database_url)?
             .busy_timeout(std::time::Duration::from_secs(30)) // Wait up to 30s for locks
             .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal) // Use WAL mode for better concurrency
-            .synchronous(sqlx::sqlite::SqliteSynchronous::Normal); // Balance between safety and performance
+            .synchronous(sqlx::sqlite::SqliteSynchronous::Normal) // Balance between safety and performance
+            .log_slow_statements(log::LevelFilter::Warn, settings.slow_query); // Logs SQL text and duration
 
         // Connect to database with optimized pool settings
         let pool = SqlitePoolOptions::new()
-            .max_connections(20) // Support up to 20 concurrent connections for parallel scanning
-            .min_connections(3) // Keep 3 connections warm
-            .acquire_timeout(std::time::Duration::from_secs(30))
+            .max_connections(settings.max_connections)
+            .min_connections(settings.min_connections.min(settings.max_connections))
+            .acquire_timeout(settings.acquire_timeout)
             .connect_with(options)
             .await?;
 
@@ -74,7 +481,12 @@ impl Storage {
             .execute(&pool)
             .await?;
 
-        let storage = Self { pool };
+        let storage = Self {
+            pool,
+            settings,
+            metrics: Arc::new(PoolMetrics::default()),
+            health: StorageHealth::default(),
+        };
 
         // Initialize admin user if no users exist (matches original behavior)
         storage.init_admin_if_needed().await?;
@@ -86,7 +498,7 @@ impl Storage {
     /// Matches original Mango's init_admin macro
     async fn init_admin_if_needed(&self) -> Result<()> {
         let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *self.acquire().await?)
             .await?;
 
         if count == 0 {
@@ -98,7 +510,7 @@ impl Storage {
             )
             .bind("admin")
             .bind(&password_hash)
-            .execute(&self.pool)
+            .execute(&mut *self.acquire().await?)
             .await?;
 
             tracing::warn!("═══════════════════════════════════════════════════════════");
@@ -112,75 +524,168 @@ impl Storage {
         Ok(())
     }
 
-    /// Verify username and password, return session token on success
-    /// Matches original Storage#verify_user
-    pub async fn verify_user(&self, username: &str, password: &str) -> Result<Option<String>> {
-        let row = sqlx::query("SELECT password, token FROM users WHERE username = ?")
-            .bind(username)
-            .fetch_optional(&self.pool)
-            .await?;
-
-        if let Some(row) = row {
-            let password_hash: String = row.get("password");
-
-            // Verify password
-            if !verify_password(password, &password_hash)? {
-                tracing::debug!("Password verification failed for user: {}", username);
-                return Ok(None);
-            }
-
-            tracing::debug!("User {} verified successfully", username);
-
-            // Return existing token or generate new one
-            let token: Option<String> = row.get("token");
-            if let Some(existing_token) = token {
-                return Ok(Some(existing_token));
-            }
-
-            // Generate new token
-            let new_token = Uuid::new_v4().to_string();
-            sqlx::query("UPDATE users SET token = ? WHERE username = ?")
-                .bind(&new_token)
+    /// Verify username and password
+    /// Matches original Storage#verify_user, except that logging in makes a new session
+    /// with `create_session` instead of reusing the user's one token
+    pub async fn verify_user(&self, username: &str, password: &str) -> Result<bool> {
+        let password_hash: Option<String> =
+            sqlx::query_scalar("SELECT password FROM users WHERE username = ?")
                 .bind(username)
-                .execute(&self.pool)
+                .fetch_optional(&mut *self.acquire().await?)
                 .await?;
 
-            Ok(Some(new_token))
-        } else {
+        let Some(password_hash) = password_hash else {
             tracing::debug!("User not found: {}", username);
-            Ok(None)
+            return Ok(false);
+        };
+        if !verify_password(password, &password_hash)? {
+            tracing::debug!("Password verification failed for user: {}", username);
+            return Ok(false);
+        }
+
+        tracing::debug!("User {} verified successfully", username);
+        Ok(true)
+    }
+
+    /// Set a user's last login to now, unless it was set within the last minute
+    /// Logging in still works when it cannot be recorded (read-only storage)
+    pub async fn record_login(&self, username: &str) {
+        let now = crate::util::time::now_ts();
+        let result = async {
+            sqlx::query(
+                "UPDATE users SET last_login = ?
+                 WHERE username = ? AND (last_login IS NULL OR last_login < ?)",
+            )
+            .bind(now)
+            .bind(username)
+            .bind(now - LOGIN_SEEN_INTERVAL_SECS)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+            Ok::<_, Error>(())
         }
+        .await;
+        if let Err(e) = result {
+            tracing::debug!("Failed to record login of {}: {}", username, e);
+        }
+    }
+
+    /// Start a login session for a user, returns its token
+    pub async fn create_session(&self, username: &str, user_agent: Option<&str>) -> Result<String> {
+        let token = Uuid::new_v4().to_string();
+        let now = crate::util::time::now_ts();
+        sqlx::query(
+            "INSERT INTO login_sessions (token, username, created_at, last_seen, user_agent)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&token)
+        .bind(username)
+        .bind(now)
+        .bind(now)
+        .bind(user_agent)
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(token)
     }
 
     /// Verify session token, return username on success
     /// Matches original Storage#verify_token
+    /// Records that the session was seen; it still works when that cannot be recorded
     pub async fn verify_token(&self, token: &str) -> Result<Option<String>> {
+        let mut conn = self.acquire().await?;
         let username: Option<String> =
-            sqlx::query_scalar("SELECT username FROM users WHERE token = ?")
+            sqlx::query_scalar("SELECT username FROM login_sessions WHERE token = ?")
                 .bind(token)
-                .fetch_optional(&self.pool)
+                .fetch_optional(&mut *conn)
                 .await?;
 
+        if username.is_some() {
+            let now = crate::util::time::now_ts();
+            if let Err(e) = sqlx::query(
+                "UPDATE login_sessions SET last_seen = ? WHERE token = ? AND last_seen < ?",
+            )
+            .bind(now)
+            .bind(token)
+            .bind(now - SESSION_SEEN_INTERVAL_SECS)
+            .execute(&mut *conn)
+            .await
+            {
+                tracing::debug!("Failed to record session use: {}", e);
+            }
+        }
         Ok(username)
     }
 
     /// Check if user is admin
     /// Matches original Storage#verify_admin
     pub async fn verify_admin(&self, token: &str) -> Result<bool> {
-        let admin: Option<i32> = sqlx::query_scalar("SELECT admin FROM users WHERE token = ?")
-            .bind(token)
-            .fetch_optional(&self.pool)
-            .await?;
+        let admin: Option<i32> = sqlx::query_scalar(
+            "SELECT users.admin FROM login_sessions
+             JOIN users ON users.username = login_sessions.username
+             WHERE login_sessions.token = ?",
+        )
+        .bind(token)
+        .fetch_optional(&mut *self.acquire().await?)
+        .await?;
 
         Ok(admin.map(|a| a == 1).unwrap_or(false))
     }
 
+    /// A user's login sessions, most recently seen first
+    pub async fn list_sessions(&self, username: &str) -> Result<Vec<LoginSession>> {
+        let rows: Vec<(String, i64, i64, Option<String>)> = sqlx::query_as(
+            "SELECT token, created_at, last_seen, user_agent FROM login_sessions
+             WHERE username = ? ORDER BY last_seen DESC, created_at DESC",
+        )
+        .bind(username)
+        .fetch_all(&mut *self.acquire().await?)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(token, created_at, last_seen, user_agent)| LoginSession {
+                token,
+                created_at,
+                last_seen,
+                user_agent,
+            })
+            .collect())
+    }
+
+    /// End every login session of a user but `keep`, returns how many ended
+    pub async fn revoke_sessions(&self, username: &str, keep: Option<&str>) -> Result<u64> {
+        let result =
+            sqlx::query("DELETE FROM login_sessions WHERE username = ? AND token IS NOT ?")
+                .bind(username)
+                .bind(keep)
+                .execute(&mut *self.acquire().await?)
+                .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Delete every app token and API token of a user, returns how many were deleted
+    pub async fn revoke_tokens(&self, username: &str) -> Result<u64> {
+        let name = username.to_string();
+        self.transaction(move |tx| {
+            Box::pin(async move {
+                let mut revoked = 0;
+                for sql in [
+                    "DELETE FROM app_tokens WHERE username = ?",
+                    "DELETE FROM api_tokens WHERE username = ?",
+                ] {
+                    let result = sqlx::query(sql).bind(&name).execute(&mut *tx).await?;
+                    revoked += result.rows_affected();
+                }
+                Ok(revoked)
+            })
+        })
+        .await
+    }
+
     /// Check if username exists
     /// Matches original Storage#username_exists
     pub async fn username_exists(&self, username: &str) -> Result<bool> {
         let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE username = ?")
             .bind(username)
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *self.acquire().await?)
             .await?;
 
         Ok(count > 0)
@@ -191,7 +696,7 @@ impl Storage {
     pub async fn username_is_admin(&self, username: &str) -> Result<bool> {
         let admin: Option<i32> = sqlx::query_scalar("SELECT admin FROM users WHERE username = ?")
             .bind(username)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&mut *self.acquire().await?)
             .await?;
 
         Ok(admin.map(|a| a == 1).unwrap_or(false))
@@ -202,55 +707,154 @@ impl Storage {
         self.username_is_admin(username).await
     }
 
+    /// Whether the user is not read-only (false for unknown users)
+    /// Admins can write whatever their flag says; the `User` extractor accounts for that
+    pub async fn can_write(&self, username: &str) -> Result<bool> {
+        let can_write: Option<i32> =
+            sqlx::query_scalar("SELECT can_write FROM users WHERE username = ?")
+                .bind(username)
+                .fetch_optional(&mut *self.acquire().await?)
+                .await?;
+
+        Ok(can_write.is_some_and(|c| c == 1))
+    }
+
     /// Create a new user
     /// Matches original Storage#new_user
-    pub async fn create_user(&self, username: &str, password: &str, is_admin: bool) -> Result<()> {
+    /// `can_write` false makes a read-only (guest) user
+    /// Fails with Conflict if the username is taken
+    pub async fn create_user(
+        &self,
+        username: &str,
+        password: &str,
+        is_admin: bool,
+        can_write: bool,
+    ) -> Result<()> {
         let password_hash = hash_password(password)?;
+        let name = username.to_string();
+        self.transaction(move |tx| {
+            Box::pin(async move {
+                Self::create_user_tx(tx, &name, &password_hash, is_admin, can_write).await
+            })
+        })
+        .await?;
+
+        tracing::info!(
+            "Created user: {} (admin: {}, can write: {})",
+            username,
+            is_admin,
+            can_write
+        );
+        Ok(())
+    }
+
+    /// Transactional part of `create_user`, taking an already hashed password
+    /// The unique username constraint is the existence check, so two concurrent
+    /// creations of one name cannot both pass it
+    pub async fn create_user_tx(
+        conn: &mut SqliteConnection,
+        username: &str,
+        password_hash: &str,
+        is_admin: bool,
+        can_write: bool,
+    ) -> Result<()> {
         let admin_flag = if is_admin { 1 } else { 0 };
 
-        sqlx::query("INSERT INTO users (username, password, token, admin) VALUES (?, ?, NULL, ?)")
-            .bind(username)
-            .bind(&password_hash)
-            .bind(admin_flag)
-            .execute(&self.pool)
-            .await?;
+        sqlx::query(
+            "INSERT INTO users (username, password, token, admin, can_write)
+             VALUES (?, ?, NULL, ?, ?)",
+        )
+        .bind(username)
+        .bind(password_hash)
+        .bind(admin_flag)
+        .bind(can_write as i32)
+        .execute(conn)
+        .await
+        .map_err(|e| username_taken(e, username))?;
 
-        tracing::info!("Created user: {} (admin: {})", username, is_admin);
         Ok(())
     }
 
     /// Update user information
     /// Matches original Storage#update_user
+    /// Fails with NotFound for an unknown user, and Conflict if the new name is taken or no
+    /// admin would be left
     pub async fn update_user(
         &self,
         original_username: &str,
         new_username: &str,
         password: Option<&str>,
         is_admin: bool,
+        can_write: bool,
+    ) -> Result<()> {
+        let password_hash = password.map(hash_password).transpose()?;
+        let (original, new) = (original_username.to_string(), new_username.to_string());
+        self.transaction(move |tx| {
+            Box::pin(async move {
+                let password_hash = password_hash.as_deref();
+                Self::update_user_tx(tx, &original, &new, password_hash, is_admin, can_write).await
+            })
+        })
+        .await?;
+
+        tracing::info!("Updated user: {} -> {}", original_username, new_username);
+        Ok(())
+    }
+
+    /// Transactional part of `update_user`, taking an already hashed password
+    pub async fn update_user_tx(
+        conn: &mut SqliteConnection,
+        original_username: &str,
+        new_username: &str,
+        password_hash: Option<&str>,
+        is_admin: bool,
+        can_write: bool,
     ) -> Result<()> {
         let admin_flag = if is_admin { 1 } else { 0 };
 
-        if let Some(new_password) = password {
-            let password_hash = hash_password(new_password)?;
-            sqlx::query(
-                "UPDATE users SET username = ?, password = ?, admin = ? WHERE username = ?",
-            )
-            .bind(new_username)
-            .bind(&password_hash)
-            .bind(admin_flag)
-            .bind(original_username)
-            .execute(&self.pool)
-            .await?;
-        } else {
-            sqlx::query("UPDATE users SET username = ?, admin = ? WHERE username = ?")
-                .bind(new_username)
-                .bind(admin_flag)
-                .bind(original_username)
-                .execute(&self.pool)
-                .await?;
+        let result = sqlx::query(
+            "UPDATE users SET username = ?, password = COALESCE(?, password), admin = ?,
+             can_write = ? WHERE username = ?",
+        )
+        .bind(new_username)
+        .bind(password_hash)
+        .bind(admin_flag)
+        .bind(can_write as i32)
+        .bind(original_username)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| username_taken(e, new_username))?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::NotFound(format!(
+                "User '{}' not found",
+                original_username
+            )));
         }
+        Self::ensure_admin_left(conn).await
+    }
 
-        tracing::info!("Updated user: {} -> {}", original_username, new_username);
+    /// Number of admin users
+    pub async fn count_admins(&self) -> Result<i64> {
+        Self::count_admins_tx(&mut *self.acquire().await?).await
+    }
+
+    async fn count_admins_tx(conn: &mut SqliteConnection) -> Result<i64> {
+        Ok(
+            sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE admin = 1")
+                .fetch_one(conn)
+                .await?,
+        )
+    }
+
+    /// Fails with Conflict once a change in the transaction left no admin, which would
+    /// lock everyone out of the admin pages; the caller's transaction is rolled back
+    async fn ensure_admin_left(conn: &mut SqliteConnection) -> Result<()> {
+        if Self::count_admins_tx(conn).await? == 0 {
+            return Err(Error::Conflict(
+                "At least one admin must remain; make another user admin first".to_string(),
+            ));
+        }
         Ok(())
     }
 
@@ -266,7 +870,7 @@ impl Storage {
         let row: Option<(String,)> =
             sqlx::query_as("SELECT password FROM users WHERE username = ?")
                 .bind(username)
-                .fetch_optional(&self.pool)
+                .fetch_optional(&mut *self.acquire().await?)
                 .await?;
 
         let current_hash = row
@@ -287,65 +891,564 @@ impl Storage {
         sqlx::query("UPDATE users SET password = ? WHERE username = ?")
             .bind(&new_hash)
             .bind(username)
-            .execute(&self.pool)
+            .execute(&mut *self.acquire().await?)
             .await?;
 
         tracing::info!("Password changed for user: {}", username);
         Ok(())
     }
 
+    /// Set a user's password without knowing the current one, for admins
+    /// Fails with NotFound for an unknown user
+    pub async fn set_password(&self, username: &str, password: &str) -> Result<()> {
+        let password_hash = hash_password(password)?;
+        let result = sqlx::query("UPDATE users SET password = ? WHERE username = ?")
+            .bind(&password_hash)
+            .bind(username)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::NotFound(format!("User '{}' not found", username)));
+        }
+
+        tracing::info!("Password reset for user: {}", username);
+        Ok(())
+    }
+
     /// Delete a user
     /// Matches original Storage#delete_user
+    /// Fails with NotFound for an unknown user and Conflict for the last admin
     pub async fn delete_user(&self, username: &str) -> Result<()> {
-        sqlx::query("DELETE FROM users WHERE username = ?")
-            .bind(username)
-            .execute(&self.pool)
+        let name = username.to_string();
+        self.transaction(move |tx| Box::pin(async move { Self::delete_user_tx(tx, &name).await }))
             .await?;
 
         tracing::info!("Deleted user: {}", username);
         Ok(())
     }
 
-    /// List all users (returns username and admin status)
-    /// Matches original Storage#list_users
-    pub async fn list_users(&self) -> Result<Vec<(String, bool)>> {
-        let rows = sqlx::query("SELECT username, admin FROM users")
-            .fetch_all(&self.pool)
+    /// Transactional part of `delete_user`
+    /// Removes the user's login sessions and app, API, feed and recovery tokens with the row
+    pub async fn delete_user_tx(conn: &mut SqliteConnection, username: &str) -> Result<()> {
+        for sql in [
+            "DELETE FROM app_tokens WHERE username = ?",
+            "DELETE FROM api_tokens WHERE username = ?",
+            "DELETE FROM login_sessions WHERE username = ?",
+            "DELETE FROM recovery_codes WHERE username = ?",
+            "DELETE FROM feed_tokens WHERE username = ?",
+        ] {
+            sqlx::query(sql).bind(username).execute(&mut *conn).await?;
+        }
+
+        let result = sqlx::query("DELETE FROM users WHERE username = ?")
+            .bind(username)
+            .execute(&mut *conn)
             .await?;
 
-        let users = rows
-            .into_iter()
-            .map(|row| {
-                let username: String = row.get("username");
-                let admin: i32 = row.get("admin");
-                (username, admin == 1)
-            })
-            .collect();
+        if result.rows_affected() == 0 {
+            return Err(Error::NotFound(format!("User '{}' not found", username)));
+        }
+        Self::ensure_admin_left(conn).await
+    }
 
-        Ok(users)
+    /// Whether a user has two-factor authentication enabled
+    pub async fn totp_enabled(&self, username: &str) -> Result<bool> {
+        let enabled: Option<bool> =
+            sqlx::query_scalar("SELECT totp_secret IS NOT NULL FROM users WHERE username = ?")
+                .bind(username)
+                .fetch_optional(&mut *self.acquire().await?)
+                .await?;
+        Ok(enabled.unwrap_or(false))
     }
 
-    /// Logout user (clear session token)
-    /// Matches original Storage#logout
-    pub async fn logout(&self, token: &str) -> Result<()> {
-        sqlx::query("UPDATE users SET token = NULL WHERE token = ?")
-            .bind(token)
-            .execute(&self.pool)
-            .await?;
+    /// Confirmed TOTP secret (base32) and last accepted time step, None if not enabled
+    pub async fn get_totp(&self, username: &str) -> Result<Option<(String, Option<i64>)>> {
+        let row: Option<(Option<String>, Option<i64>)> =
+            sqlx::query_as("SELECT totp_secret, totp_last_step FROM users WHERE username = ?")
+                .bind(username)
+                .fetch_optional(&mut *self.acquire().await?)
+                .await?;
+        Ok(row.and_then(|(secret, last_step)| secret.map(|s| (s, last_step))))
+    }
 
+    /// Store a freshly generated secret until enrollment is confirmed
+    pub async fn set_totp_pending(&self, username: &str, secret: &str) -> Result<()> {
+        sqlx::query("UPDATE users SET totp_pending_secret = ? WHERE username = ?")
+            .bind(secret)
+            .bind(username)
+            .execute(&mut *self.acquire().await?)
+            .await?;
         Ok(())
     }
 
-    /// Get all unavailable (missing) entries
-    /// Matches original Storage#get_missing
-    pub async fn get_missing_entries(&self) -> Result<Vec<MissingEntry>> {
-        // Query both titles and ids tables
-        let title_rows = sqlx::query("SELECT id, path FROM titles WHERE unavailable = 1")
-            .fetch_all(&self.pool)
-            .await?;
+    /// Secret (base32) of an enrollment waiting for its first code
+    pub async fn get_totp_pending(&self, username: &str) -> Result<Option<String>> {
+        let secret: Option<Option<String>> =
+            sqlx::query_scalar("SELECT totp_pending_secret FROM users WHERE username = ?")
+                .bind(username)
+                .fetch_optional(&mut *self.acquire().await?)
+                .await?;
+        Ok(secret.flatten())
+    }
+
+    /// Enable two-factor authentication with the pending secret
+    /// `step` is the time step of the confirming code; recovery codes are replaced
+    pub async fn enable_totp(
+        &self,
+        username: &str,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "UPDATE users SET totp_secret = totp_pending_secret, totp_pending_secret = NULL,
+             totp_last_step = ? WHERE username = ? AND totp_pending_secret IS NOT NULL",
+        )
+        .bind(step)
+        .bind(username)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::BadRequest(
+                "No two-factor enrollment in progress".to_string(),
+            ));
+        }
+
+        sqlx::query("DELETE FROM recovery_codes WHERE username = ?")
+            .bind(username)
+            .execute(&mut *tx)
+            .await?;
+        for hash in recovery_code_hashes {
+            sqlx::query("INSERT INTO recovery_codes (username, code_hash) VALUES (?, ?)")
+                .bind(username)
+                .bind(hash)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        tracing::info!("Two-factor authentication enabled for user: {}", username);
+        Ok(())
+    }
+
+    /// Record an accepted code's time step
+    /// Returns false if a code of this or a later step was already accepted, which
+    /// makes concurrent logins with the same code race safely
+    pub async fn accept_totp_step(&self, username: &str, step: i64) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE users SET totp_last_step = ?
+             WHERE username = ? AND (totp_last_step IS NULL OR totp_last_step < ?)",
+        )
+        .bind(step)
+        .bind(username)
+        .bind(step)
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mark an unused recovery code as used, returns whether it was valid
+    pub async fn use_recovery_code(&self, username: &str, code_hash: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE recovery_codes SET used_at = ?
+             WHERE username = ? AND code_hash = ? AND used_at IS NULL",
+        )
+        .bind(crate::util::time::now_ts())
+        .bind(username)
+        .bind(code_hash)
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Number of unused recovery codes
+    pub async fn recovery_codes_left(&self, username: &str) -> Result<i64> {
+        let count = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM recovery_codes WHERE username = ? AND used_at IS NULL",
+        )
+        .bind(username)
+        .fetch_one(&mut *self.acquire().await?)
+        .await?;
+        Ok(count)
+    }
+
+    /// Turn off two-factor authentication (admin reset)
+    /// Drops the secret, recovery codes and app tokens
+    pub async fn reset_totp(&self, username: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "UPDATE users SET totp_secret = NULL, totp_pending_secret = NULL,
+             totp_last_step = NULL WHERE username = ?",
+        )
+        .bind(username)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::NotFound(format!("User not found: {}", username)));
+        }
+
+        for table in ["recovery_codes", "app_tokens"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE username = ?", table))
+                .bind(username)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        tracing::info!("Two-factor authentication reset for user: {}", username);
+        Ok(())
+    }
+
+    /// Store an app-specific token by its hash, returns the new token's ID
+    pub async fn create_app_token(
+        &self,
+        username: &str,
+        label: &str,
+        token_hash: &str,
+    ) -> Result<String> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO app_tokens (id, username, token_hash, label, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(username)
+        .bind(token_hash)
+        .bind(label)
+        .bind(crate::util::time::now_ts())
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(id)
+    }
+
+    /// A user's app-specific tokens, oldest first
+    pub async fn list_app_tokens(&self, username: &str) -> Result<Vec<AppToken>> {
+        let rows: Vec<(String, String, i64)> = sqlx::query_as(
+            "SELECT id, label, created_at FROM app_tokens WHERE username = ?
+             ORDER BY created_at, id",
+        )
+        .bind(username)
+        .fetch_all(&mut *self.acquire().await?)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, label, created_at)| AppToken {
+                id,
+                label,
+                created_at,
+            })
+            .collect())
+    }
+
+    /// Revoke one of a user's app-specific tokens, returns whether it existed
+    pub async fn delete_app_token(&self, username: &str, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM app_tokens WHERE username = ? AND id = ?")
+            .bind(username)
+            .bind(id)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Whether a token hash belongs to one of the user's app-specific tokens
+    pub async fn verify_app_token(&self, username: &str, token_hash: &str) -> Result<bool> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM app_tokens WHERE username = ? AND token_hash = ?",
+        )
+        .bind(username)
+        .bind(token_hash)
+        .fetch_one(&mut *self.acquire().await?)
+        .await?;
+        Ok(count > 0)
+    }
+
+    /// Store an API token by its hash, returns the new token's ID
+    pub async fn create_api_token(
+        &self,
+        username: &str,
+        name: &str,
+        token_hash: &str,
+    ) -> Result<String> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO api_tokens (id, username, token_hash, name, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(username)
+        .bind(token_hash)
+        .bind(name)
+        .bind(crate::util::time::now_ts())
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(id)
+    }
+
+    /// A user's API tokens, oldest first
+    pub async fn list_api_tokens(&self, username: &str) -> Result<Vec<ApiToken>> {
+        let rows: Vec<(String, String, i64, Option<i64>)> = sqlx::query_as(
+            "SELECT id, name, created_at, last_used FROM api_tokens WHERE username = ?
+             ORDER BY created_at, id",
+        )
+        .bind(username)
+        .fetch_all(&mut *self.acquire().await?)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, created_at, last_used)| ApiToken {
+                id,
+                name,
+                created_at,
+                last_used,
+            })
+            .collect())
+    }
+
+    /// Number of API tokens of every user that has any
+    pub async fn count_api_tokens(&self) -> Result<HashMap<String, usize>> {
+        let rows: Vec<(String, i64)> =
+            sqlx::query_as("SELECT username, COUNT(*) FROM api_tokens GROUP BY username")
+                .fetch_all(&mut *self.acquire().await?)
+                .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(username, count)| (username, count as usize))
+            .collect())
+    }
+
+    /// Revoke one of a user's API tokens, returns whether it existed
+    pub async fn delete_api_token(&self, username: &str, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM api_tokens WHERE username = ? AND id = ?")
+            .bind(username)
+            .bind(id)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Owner of the API token with this hash, recording that it was used
+    /// The token still works when the use cannot be recorded (read-only storage)
+    pub async fn verify_api_token(&self, token_hash: &str) -> Result<Option<String>> {
+        let mut conn = self.acquire().await?;
+        let username: Option<String> =
+            sqlx::query_scalar("SELECT username FROM api_tokens WHERE token_hash = ?")
+                .bind(token_hash)
+                .fetch_optional(&mut *conn)
+                .await?;
+        if username.is_some() {
+            if let Err(e) = sqlx::query("UPDATE api_tokens SET last_used = ? WHERE token_hash = ?")
+                .bind(crate::util::time::now_ts())
+                .bind(token_hash)
+                .execute(&mut *conn)
+                .await
+            {
+                tracing::debug!("Failed to record API token use: {}", e);
+            }
+        }
+        Ok(username)
+    }
+
+    /// A user's feed token, if one was generated
+    pub async fn get_feed_token(&self, username: &str) -> Result<Option<String>> {
+        Ok(
+            sqlx::query_scalar("SELECT token FROM feed_tokens WHERE username = ?")
+                .bind(username)
+                .fetch_optional(&mut *self.acquire().await?)
+                .await?,
+        )
+    }
+
+    /// Store a new feed token for a user, replacing (and so revoking) the previous one
+    pub async fn set_feed_token(&self, username: &str, token: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO feed_tokens (username, token, created_at) VALUES (?, ?, ?)
+             ON CONFLICT(username) DO UPDATE SET token = excluded.token,
+             created_at = excluded.created_at",
+        )
+        .bind(username)
+        .bind(token)
+        .bind(crate::util::time::now_ts())
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Revoke a user's feed token, returns whether there was one
+    pub async fn delete_feed_token(&self, username: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM feed_tokens WHERE username = ?")
+            .bind(username)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The user a feed token belongs to
+    pub async fn verify_feed_token(&self, token: &str) -> Result<Option<String>> {
+        Ok(
+            sqlx::query_scalar("SELECT username FROM feed_tokens WHERE token = ?")
+                .bind(token)
+                .fetch_optional(&mut *self.acquire().await?)
+                .await?,
+        )
+    }
+
+    /// A user's display preferences (all None if never set)
+    pub async fn get_user_preferences(&self, username: &str) -> Result<UserPreferences> {
+        let row: Option<(Option<i64>, Option<String>, bool)> = sqlx::query_as(
+            "SELECT page_size, card_density, hide_completed FROM user_preferences
+             WHERE username = ?",
+        )
+        .bind(username)
+        .fetch_optional(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(row
+            .map(|(page_size, card_density, hide)| UserPreferences {
+                page_size: page_size.map(|size| size.max(0) as usize),
+                card_density: card_density.as_deref().and_then(CardDensity::parse),
+                hide_completed: hide,
+            })
+            .unwrap_or_default())
+    }
+
+    /// Store a user's library layout (page size and card density), replacing the previous
+    /// one; `hide_completed` is set on its own with `set_hide_completed`
+    pub async fn set_user_preferences(
+        &self,
+        username: &str,
+        preferences: &UserPreferences,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO user_preferences (username, page_size, card_density) VALUES (?, ?, ?)
+             ON CONFLICT(username) DO UPDATE SET page_size = excluded.page_size,
+             card_density = excluded.card_density",
+        )
+        .bind(username)
+        .bind(preferences.page_size.map(|size| size as i64))
+        .bind(preferences.card_density.map(|d| d.as_str()))
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Set whether the library hides a user's fully read titles, in a single statement
+    /// that leaves the other preferences as they are
+    pub async fn set_hide_completed(&self, username: &str, hide: bool) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO user_preferences (username, hide_completed) VALUES (?, ?)
+             ON CONFLICT(username) DO UPDATE SET hide_completed = excluded.hide_completed",
+        )
+        .bind(username)
+        .bind(hide)
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// List all users (returns username and admin status)
+    /// Matches original Storage#list_users
+    pub async fn list_users(&self) -> Result<Vec<UserAccount>> {
+        let rows = sqlx::query("SELECT username, admin, can_write, last_login FROM users")
+            .fetch_all(&mut *self.acquire().await?)
+            .await?;
+
+        let users = rows
+            .into_iter()
+            .map(|row| {
+                let admin: i32 = row.get("admin");
+                let can_write: i32 = row.get("can_write");
+                UserAccount {
+                    username: row.get("username"),
+                    is_admin: admin == 1,
+                    can_write: can_write == 1,
+                    last_login: row.get("last_login"),
+                }
+            })
+            .collect();
+
+        Ok(users)
+    }
+
+    /// End one login session
+    /// Matches original Storage#logout, which cleared the user's only token
+    pub async fn logout(&self, token: &str) -> Result<()> {
+        sqlx::query("DELETE FROM login_sessions WHERE token = ?")
+            .bind(token)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    /// List available title rows with their stored signatures
+    pub async fn list_title_signatures(&self) -> Result<Vec<SignatureRow>> {
+        self.list_signatures("SELECT id, path, signature FROM titles WHERE unavailable = 0")
+            .await
+    }
+
+    /// List available entry rows with their stored signatures
+    pub async fn list_entry_signatures(&self) -> Result<Vec<SignatureRow>> {
+        self.list_signatures("SELECT id, path, signature FROM ids WHERE unavailable = 0")
+            .await
+    }
+
+    async fn list_signatures(&self, sql: &str) -> Result<Vec<SignatureRow>> {
+        let rows: Vec<(String, String, Option<String>)> = sqlx::query_as(sql)
+            .fetch_all(&mut *self.acquire().await?)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, path, signature)| SignatureRow {
+                id,
+                path,
+                signature,
+            })
+            .collect())
+    }
+
+    /// Overwrite stored signatures of existing title and entry rows in one transaction
+    /// Takes (id, signature) pairs; IDs are never created or changed
+    pub async fn update_signatures(
+        &self,
+        titles: &[(String, String)],
+        entries: &[(String, String)],
+    ) -> Result<()> {
+        let mut conn = self.acquire().await?;
+        let mut tx = conn.begin().await?;
+
+        for (id, signature) in titles {
+            sqlx::query("UPDATE titles SET signature = ? WHERE id = ?")
+                .bind(signature)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for (id, signature) in entries {
+            sqlx::query("UPDATE ids SET signature = ? WHERE id = ?")
+                .bind(signature)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get all unavailable (missing) entries
+    /// Matches original Storage#get_missing
+    pub async fn get_missing_entries(&self) -> Result<Vec<MissingEntry>> {
+        // Query both titles and ids tables
+        let title_rows = sqlx::query("SELECT id, path FROM titles WHERE unavailable = 1")
+            .fetch_all(&mut *self.acquire().await?)
+            .await?;
 
         let entry_rows = sqlx::query("SELECT id, path FROM ids WHERE unavailable = 1")
-            .fetch_all(&self.pool)
+            .fetch_all(&mut *self.acquire().await?)
             .await?;
 
         let mut entries = Vec::new();
@@ -377,40 +1480,59 @@ impl Storage {
     /// Delete a specific missing entry from database
     /// Matches original Storage#delete_missing
     pub async fn delete_missing_entry(&self, id: &str) -> Result<()> {
+        let row_id = id.to_string();
+        let total = self
+            .transaction(move |tx| {
+                Box::pin(async move { Self::delete_missing_entry_tx(tx, &row_id).await })
+            })
+            .await?;
+
+        if total > 0 {
+            tracing::info!("Deleted missing entry: {}", id);
+        }
+
+        Ok(())
+    }
+
+    /// Transactional part of `delete_missing_entry`; returns the rows deleted
+    pub async fn delete_missing_entry_tx(conn: &mut SqliteConnection, id: &str) -> Result<u64> {
         // Try deleting from titles first
         let result1 = sqlx::query("DELETE FROM titles WHERE id = ? AND unavailable = 1")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
 
         // Then try ids table
         let result2 = sqlx::query("DELETE FROM ids WHERE id = ? AND unavailable = 1")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
 
-        let total = result1.rows_affected() + result2.rows_affected();
-        if total > 0 {
-            tracing::info!("Deleted missing entry: {}", id);
-        }
-
-        Ok(())
+        Ok(result1.rows_affected() + result2.rows_affected())
     }
 
     /// Delete all missing entries from database
     /// Matches original Storage#delete_all_missing (custom implementation)
     pub async fn delete_all_missing_entries(&self) -> Result<u64> {
+        let rows_affected = self
+            .transaction(|tx| Box::pin(Self::delete_all_missing_entries_tx(tx)))
+            .await?;
+
+        tracing::info!("Deleted {} missing entries", rows_affected);
+        Ok(rows_affected)
+    }
+
+    /// Transactional part of `delete_all_missing_entries`; returns the rows deleted
+    pub async fn delete_all_missing_entries_tx(conn: &mut SqliteConnection) -> Result<u64> {
         let result1 = sqlx::query("DELETE FROM titles WHERE unavailable = 1")
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
 
         let result2 = sqlx::query("DELETE FROM ids WHERE unavailable = 1")
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
 
-        let rows_affected = result1.rows_affected() + result2.rows_affected();
-        tracing::info!("Deleted {} missing entries", rows_affected);
-        Ok(rows_affected)
+        Ok(result1.rows_affected() + result2.rows_affected())
     }
 
     /// Get count of unavailable (missing) entries
@@ -418,16 +1540,68 @@ impl Storage {
     pub async fn get_missing_count(&self) -> Result<usize> {
         let title_count: i64 =
             sqlx::query_scalar("SELECT COUNT(*) FROM titles WHERE unavailable = 1")
-                .fetch_one(&self.pool)
+                .fetch_one(&mut *self.acquire().await?)
                 .await?;
 
         let entry_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ids WHERE unavailable = 1")
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *self.acquire().await?)
             .await?;
 
         Ok((title_count + entry_count) as usize)
     }
 
+    /// Delete cover thumbnails and page sprites whose entry is gone from `ids`, or has
+    /// been unavailable since before `unavailable_before` (unix timestamp)
+    pub async fn prune_thumbnails(&self, unavailable_before: i64) -> Result<ThumbnailPrune> {
+        // Entries whose thumbnails are kept: available, or missing for less than the grace period
+        const KEEP: &str = "SELECT id FROM ids \
+             WHERE unavailable = 0 OR unavailable_since IS NULL OR unavailable_since > ?";
+
+        let mut tx = self.pool.begin().await?;
+        let mut pruned = ThumbnailPrune::default();
+        for (table, column) in [("thumbnails", "id"), ("page_sprites", "entry_id")] {
+            let (rows, bytes): (i64, i64) = sqlx::query_as(&format!(
+                "SELECT COUNT(*), COALESCE(SUM(LENGTH(data)), 0) FROM {} WHERE {} NOT IN ({})",
+                table, column, KEEP
+            ))
+            .bind(unavailable_before)
+            .fetch_one(&mut *tx)
+            .await?;
+            if rows == 0 {
+                continue;
+            }
+
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE {} NOT IN ({})",
+                table, column, KEEP
+            ))
+            .bind(unavailable_before)
+            .execute(&mut *tx)
+            .await?;
+            pruned.rows += rows as u64;
+            pruned.bytes += bytes as u64;
+        }
+        tx.commit().await?;
+
+        if pruned.rows > 0 {
+            tracing::info!(
+                "Pruned {} orphaned thumbnail rows ({} bytes)",
+                pruned.rows,
+                pruned.bytes
+            );
+        }
+        Ok(pruned)
+    }
+
+    /// Rebuild the database file to return freed pages to the filesystem
+    /// Blocks every other writer (and readers outside WAL) until it finishes
+    pub async fn vacuum(&self) -> Result<()> {
+        sqlx::query("VACUUM")
+            .execute(&mut *self.acquire().await?)
+            .await?;
+        Ok(())
+    }
+
     // ========== Tags Methods ==========
 
     /// Get all tags for a specific title
@@ -435,7 +1609,7 @@ impl Storage {
     pub async fn get_title_tags(&self, title_id: &str) -> Result<Vec<String>> {
         let rows = sqlx::query("SELECT tag FROM tags WHERE id = ? ORDER BY tag")
             .bind(title_id)
-            .fetch_all(&self.pool)
+            .fetch_all(&mut *self.acquire().await?)
             .await?;
 
         let tags = rows.into_iter().map(|row| row.get("tag")).collect();
@@ -448,7 +1622,7 @@ impl Storage {
     pub async fn get_tag_titles(&self, tag: &str) -> Result<Vec<String>> {
         let rows = sqlx::query("SELECT id FROM tags WHERE tag = ?")
             .bind(tag)
-            .fetch_all(&self.pool)
+            .fetch_all(&mut *self.acquire().await?)
             .await?;
 
         let title_ids = rows.into_iter().map(|row| row.get("id")).collect();
@@ -463,7 +1637,7 @@ impl Storage {
             "SELECT DISTINCT tag FROM tags \
              ORDER BY tag",
         )
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.acquire().await?)
         .await?;
 
         let tags = rows.into_iter().map(|row| row.get("tag")).collect();
@@ -473,12 +1647,28 @@ impl Storage {
 
     /// Add a tag to a title
     /// Matches original Storage#add_tag
+    /// Fails with Conflict if the title already has the tag or is not in the database
     pub async fn add_tag(&self, title_id: &str, tag: &str) -> Result<()> {
+        let (title_id, tag) = (title_id.to_string(), tag.to_string());
+        self.transaction(move |tx| {
+            Box::pin(async move { Self::add_tag_tx(tx, &title_id, &tag).await })
+        })
+        .await
+    }
+
+    /// Transactional part of `add_tag`
+    pub async fn add_tag_tx(conn: &mut SqliteConnection, title_id: &str, tag: &str) -> Result<()> {
         sqlx::query("INSERT INTO tags (id, tag) VALUES (?, ?)")
             .bind(title_id)
             .bind(tag)
-            .execute(&self.pool)
-            .await?;
+            .execute(conn)
+            .await
+            .map_err(|e| match &e {
+                sqlx::Error::Database(db) if db.is_unique_violation() => {
+                    Error::Conflict(format!("Title {} already has tag '{}'", title_id, tag))
+                }
+                _ => e.into(),
+            })?;
 
         Ok(())
     }
@@ -486,38 +1676,409 @@ impl Storage {
     /// Delete a tag from a title
     /// Matches original Storage#delete_tag
     pub async fn delete_tag(&self, title_id: &str, tag: &str) -> Result<()> {
+        let (title_id, tag) = (title_id.to_string(), tag.to_string());
+        self.transaction(move |tx| {
+            Box::pin(async move { Self::delete_tag_tx(tx, &title_id, &tag).await })
+        })
+        .await
+    }
+
+    /// Transactional part of `delete_tag`
+    pub async fn delete_tag_tx(
+        conn: &mut SqliteConnection,
+        title_id: &str,
+        tag: &str,
+    ) -> Result<()> {
         sqlx::query("DELETE FROM tags WHERE id = ? AND tag = ?")
             .bind(title_id)
             .bind(tag)
-            .execute(&self.pool)
+            .execute(conn)
             .await?;
 
         Ok(())
     }
 
-    /// Get database pool for advanced operations
-    pub fn pool(&self) -> &SqlitePool {
-        &self.pool
+    // ========== Aliases Methods ==========
+
+    /// Get the aliases of a title, primary alias first
+    pub async fn get_title_aliases(&self, title_id: &str) -> Result<Vec<String>> {
+        let aliases =
+            sqlx::query_scalar("SELECT alias FROM title_aliases WHERE id = ? ORDER BY position")
+                .bind(title_id)
+                .fetch_all(&mut *self.acquire().await?)
+                .await?;
+
+        Ok(aliases)
     }
 
-    // ========== Display Name / Sort Title Methods ==========
+    /// Get the aliases of every title that has any, keyed by title ID
+    pub async fn list_title_aliases(&self) -> Result<HashMap<String, Vec<String>>> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT id, alias FROM title_aliases ORDER BY id, position")
+                .fetch_all(&mut *self.acquire().await?)
+                .await?;
 
-    /// Update display name for a title
-    pub async fn update_title_display_name(&self, title_id: &str, display_name: &str) -> Result<()> {
-        sqlx::query("UPDATE titles SET display_name = ? WHERE id = ?")
-            .bind(display_name)
-            .bind(title_id)
-            .execute(&self.pool)
-            .await?;
-        Ok(())
+        let mut aliases: HashMap<String, Vec<String>> = HashMap::new();
+        for (title_id, alias) in rows {
+            aliases.entry(title_id).or_default().push(alias);
+        }
+
+        Ok(aliases)
     }
 
-    /// Update display name for an entry
-    pub async fn update_entry_display_name(&self, entry_id: &str, display_name: &str) -> Result<()> {
-        sqlx::query("UPDATE ids SET display_name = ? WHERE id = ?")
+    /// Replace the aliases of a title
+    /// Aliases must already be normalized (see `util::normalize_aliases`)
+    pub async fn set_title_aliases(&self, title_id: &str, aliases: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM title_aliases WHERE id = ?")
+            .bind(title_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for (position, alias) in aliases.iter().enumerate() {
+            sqlx::query("INSERT INTO title_aliases (id, alias, position) VALUES (?, ?, ?)")
+                .bind(title_id)
+                .bind(alias)
+                .bind(position as i64)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // ========== Title Relations Methods ==========
+
+    /// Get how a title relates to others
+    pub async fn get_title_relations(&self, title_id: &str) -> Result<TitleRelations> {
+        let mut conn = self.acquire().await?;
+        let sequel_of =
+            sqlx::query_scalar("SELECT title_id FROM title_sequels WHERE sequel_id = ?")
+                .bind(title_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+        let prequel_of =
+            sqlx::query_scalar("SELECT sequel_id FROM title_sequels WHERE title_id = ?")
+                .bind(title_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+        let related = sqlx::query_scalar(
+            "SELECT related_id FROM title_related WHERE title_id = ?
+             UNION SELECT title_id FROM title_related WHERE related_id = ?
+             ORDER BY 1",
+        )
+        .bind(title_id)
+        .bind(title_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        Ok(TitleRelations {
+            sequel_of,
+            prequel_of,
+            related,
+        })
+    }
+
+    /// Get the sequel of a title
+    pub async fn get_sequel(&self, title_id: &str) -> Result<Option<String>> {
+        let sequel = sqlx::query_scalar("SELECT sequel_id FROM title_sequels WHERE title_id = ?")
+            .bind(title_id)
+            .fetch_optional(&mut *self.acquire().await?)
+            .await?;
+
+        Ok(sequel)
+    }
+
+    /// Replace how a title relates to others
+    /// A title it becomes the sequel (or prequel) of gives up its previous sequel (or prequel)
+    /// Returns: BadRequest if the title is related to itself or the sequels would form a cycle
+    pub async fn set_title_relations(
+        &self,
+        title_id: &str,
+        relations: &TitleRelations,
+    ) -> Result<()> {
+        let mut linked = relations
+            .sequel_of
+            .iter()
+            .chain(&relations.prequel_of)
+            .chain(&relations.related);
+        if linked.any(|id| id == title_id) {
+            return Err(Error::BadRequest(
+                "A title cannot be related to itself".to_string(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM title_sequels WHERE title_id = ? OR sequel_id = ?")
+            .bind(title_id)
+            .bind(title_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM title_related WHERE title_id = ? OR related_id = ?")
+            .bind(title_id)
+            .bind(title_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let links = relations
+            .sequel_of
+            .iter()
+            .map(|prequel| (prequel.as_str(), title_id))
+            .chain(
+                relations
+                    .prequel_of
+                    .iter()
+                    .map(|sequel| (title_id, sequel.as_str())),
+            );
+        for (prequel, sequel) in links {
+            // The other title's previous link in the chain gives way
+            sqlx::query("DELETE FROM title_sequels WHERE title_id = ? OR sequel_id = ?")
+                .bind(prequel)
+                .bind(sequel)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("INSERT INTO title_sequels (title_id, sequel_id) VALUES (?, ?)")
+                .bind(prequel)
+                .bind(sequel)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for related in &relations.related {
+            let (first, second) = if title_id < related.as_str() {
+                (title_id, related.as_str())
+            } else {
+                (related.as_str(), title_id)
+            };
+            sqlx::query(
+                "INSERT INTO title_related (title_id, related_id) VALUES (?, ?)
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(first)
+            .bind(second)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Follow the chain from the title; coming back to it means a cycle
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT title_id, sequel_id FROM title_sequels")
+                .fetch_all(&mut *tx)
+                .await?;
+        let sequels: HashMap<String, String> = rows.into_iter().collect();
+        let mut current = title_id;
+        for _ in 0..sequels.len() {
+            let Some(sequel) = sequels.get(current) else {
+                break;
+            };
+            if sequel == title_id {
+                return Err(Error::BadRequest(
+                    "The sequels would form a cycle".to_string(),
+                ));
+            }
+            current = sequel;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Record `scan_id` as the initial import if the database has no titles yet
+    /// Returns whether `scan_id` is the initial import and has not finished, which
+    /// also holds when an interrupted initial import resumes under the same ID
+    pub async fn begin_initial_import(&self, scan_id: &str, started_at: i64) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let titles: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM titles")
+            .fetch_one(&mut *tx)
+            .await?;
+        if titles == 0 {
+            sqlx::query(
+                "INSERT OR IGNORE INTO initial_import (id, scan_id, started_at) VALUES (1, ?, ?)",
+            )
+            .bind(scan_id)
+            .bind(started_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let current: Option<String> = sqlx::query_scalar(
+            "SELECT scan_id FROM initial_import WHERE id = 1 AND finished_at IS NULL",
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(current.as_deref() == Some(scan_id))
+    }
+
+    /// Mark the initial import as complete
+    pub async fn finish_initial_import(&self, finished_at: i64) -> Result<()> {
+        sqlx::query(
+            "UPDATE initial_import SET finished_at = ? WHERE id = 1 AND finished_at IS NULL",
+        )
+        .bind(finished_at)
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Time window of the initial import as (started_at, finished_at)
+    /// finished_at is None while the import has not completed
+    pub async fn get_initial_import(&self) -> Result<Option<(i64, Option<i64>)>> {
+        let row = sqlx::query_as::<_, (i64, Option<i64>)>(
+            "SELECT started_at, finished_at FROM initial_import WHERE id = 1",
+        )
+        .fetch_optional(&mut *self.acquire().await?)
+        .await?;
+        Ok(row)
+    }
+
+    /// Highest applied migration, i.e. the database schema version
+    pub async fn schema_version(&self) -> Result<i64> {
+        let version = sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1",
+        )
+        .fetch_one(&mut *self.acquire().await?)
+        .await?;
+        Ok(version.unwrap_or(0))
+    }
+
+    /// Get database pool for advanced operations
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Run dependent writes as one transaction: commits when `f` succeeds, rolls
+    /// back when it fails or the caller is dropped mid-way
+    /// `f` gets the transaction's connection for the `*_tx` methods, e.g.
+    /// `storage.transaction(|tx| Box::pin(Storage::delete_all_missing_entries_tx(tx)))`.
+    /// Broken unique and foreign key constraints come back as Conflict.
+    ///
+    /// SQLite transactions start deferred, so write before reading what the writes
+    /// depend on: one that reads first fails with SQLITE_BUSY if another writer
+    /// commits before it writes. Prefer letting constraints do existence checks
+    pub async fn transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send,
+        F: for<'c> FnOnce(&'c mut SqliteConnection) -> TxFuture<'c, T>,
+    {
+        let mut conn = self.acquire().await?;
+        let result: Result<T> = async {
+            let mut tx = conn.begin().await?;
+            let value = f(&mut tx).await.map_err(constraint_conflict)?;
+            tx.commit()
+                .await
+                .map_err(|e| constraint_conflict(e.into()))?;
+            Ok(value)
+        }
+        .await;
+        self.health.record(&result);
+        result
+    }
+
+    /// Acquire a pooled connection, recording slow waits
+    /// Returns ServiceUnavailable (503) instead of a database error when the pool is exhausted
+    pub async fn acquire(&self) -> Result<TimedConnection> {
+        let start = Instant::now();
+
+        let conn = match self.pool.acquire().await {
+            Ok(conn) => conn,
+            Err(sqlx::Error::PoolTimedOut) => {
+                self.metrics
+                    .acquire_timeouts
+                    .fetch_add(1, Ordering::Relaxed);
+                tracing::error!(
+                    "Timed out after {}ms waiting for a database connection (pool size {}, idle {})",
+                    start.elapsed().as_millis(),
+                    self.pool.size(),
+                    self.pool.num_idle()
+                );
+                return Err(Error::ServiceUnavailable(
+                    "The server is busy, please try again in a moment".to_string(),
+                ));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let waited = start.elapsed();
+        if waited >= self.settings.slow_acquire {
+            self.metrics.slow_acquires.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(
+                "Waited {}ms for a database connection (pool size {}, idle {})",
+                waited.as_millis(),
+                self.pool.size(),
+                self.pool.num_idle()
+            );
+        }
+
+        Ok(TimedConnection {
+            conn,
+            acquired_at: Instant::now(),
+            slow_query: self.settings.slow_query,
+            metrics: self.metrics.clone(),
+        })
+    }
+
+    /// Whether writes currently succeed (see `StorageHealth`)
+    pub fn health(&self) -> &StorageHealth {
+        &self.health
+    }
+
+    /// Try a write that changes nothing, to find out whether storage accepts writes again
+    /// Rewrites the database header's user_version with its current value
+    pub async fn check_writable(&self) -> Result<()> {
+        let result: Result<()> = async {
+            let mut conn = self.acquire().await?;
+            let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+                .fetch_one(&mut *conn)
+                .await?;
+            sqlx::query(&format!("PRAGMA user_version = {}", version))
+                .execute(&mut *conn)
+                .await?;
+            Ok(())
+        }
+        .await;
+        self.health.record(&result);
+        result
+    }
+
+    /// Current pool status and health counters
+    pub fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max_connections: self.settings.max_connections,
+            acquire_timeouts: self.metrics.acquire_timeouts.load(Ordering::Relaxed),
+            slow_acquires: self.metrics.slow_acquires.load(Ordering::Relaxed),
+            slow_queries: self.metrics.slow_queries.load(Ordering::Relaxed),
+            slow_acquire_ms: self.settings.slow_acquire.as_millis() as u64,
+            slow_query_ms: self.settings.slow_query.as_millis() as u64,
+            unavailable_since: self.health.unavailable_since(),
+        }
+    }
+
+    // ========== Display Name / Sort Title Methods ==========
+
+    /// Update display name for a title
+    pub async fn update_title_display_name(&self, title_id: &str, display_name: &str) -> Result<()> {
+        sqlx::query("UPDATE titles SET display_name = ? WHERE id = ?")
+            .bind(display_name)
+            .bind(title_id)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Update display name for an entry
+    pub async fn update_entry_display_name(&self, entry_id: &str, display_name: &str) -> Result<()> {
+        sqlx::query("UPDATE ids SET display_name = ? WHERE id = ?")
             .bind(display_name)
             .bind(entry_id)
-            .execute(&self.pool)
+            .execute(&mut *self.acquire().await?)
             .await?;
         Ok(())
     }
@@ -527,7 +2088,7 @@ impl Storage {
         sqlx::query("UPDATE titles SET sort_title = ? WHERE id = ?")
             .bind(sort_title)
             .bind(title_id)
-            .execute(&self.pool)
+            .execute(&mut *self.acquire().await?)
             .await?;
         Ok(())
     }
@@ -537,7 +2098,7 @@ impl Storage {
         sqlx::query("UPDATE ids SET sort_title = ? WHERE id = ?")
             .bind(sort_title)
             .bind(entry_id)
-            .execute(&self.pool)
+            .execute(&mut *self.acquire().await?)
             .await?;
         Ok(())
     }
@@ -552,7 +2113,7 @@ impl Storage {
             "SELECT page_num, width, height FROM dimensions WHERE entry_id = ? ORDER BY page_num"
         )
         .bind(entry_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.acquire().await?)
         .await?;
 
         if rows.is_empty() {
@@ -574,7 +2135,8 @@ impl Storage {
     /// Save dimensions for an entry (replaces existing)
     /// Uses transaction to ensure atomicity
     pub async fn save_dimensions(&self, entry_id: &str, dimensions: &[(usize, u32, u32)]) -> Result<()> {
-        let mut tx = self.pool.begin().await?;
+        let mut conn = self.acquire().await?;
+        let mut tx = conn.begin().await?;
 
         // Delete existing dimensions for this entry
         sqlx::query("DELETE FROM dimensions WHERE entry_id = ?")
@@ -605,50 +2167,1846 @@ impl Storage {
             "SELECT COUNT(*) FROM dimensions WHERE entry_id = ?"
         )
         .bind(entry_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *self.acquire().await?)
         .await?;
 
         Ok(count > 0)
     }
 
+    // ========== Page Validation ==========
+
+    /// Get the page validation result for an entry (None if never checked)
+    pub async fn get_page_check(&self, entry_id: &str) -> Result<Option<PageCheck>> {
+        let row: Option<(String, i64, String, i64)> = sqlx::query_as(
+            "SELECT entry_id, pages, bad_pages, checked_at FROM page_checks WHERE entry_id = ?",
+        )
+        .bind(entry_id)
+        .fetch_optional(&mut *self.acquire().await?)
+        .await?;
+
+        row.map(page_check_from_row).transpose()
+    }
+
+    /// List validation results for entries that have bad pages
+    pub async fn list_bad_pages(&self) -> Result<Vec<PageCheck>> {
+        let rows: Vec<(String, i64, String, i64)> = sqlx::query_as(
+            "SELECT entry_id, pages, bad_pages, checked_at FROM page_checks
+             WHERE bad_pages != '[]' ORDER BY checked_at DESC",
+        )
+        .fetch_all(&mut *self.acquire().await?)
+        .await?;
+
+        rows.into_iter().map(page_check_from_row).collect()
+    }
+
+    /// Save the page validation result for an entry (replaces existing)
+    pub async fn save_page_check(
+        &self,
+        entry_id: &str,
+        pages: usize,
+        bad_pages: &[usize],
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO page_checks (entry_id, pages, bad_pages, checked_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT(entry_id) DO UPDATE SET
+                 pages = excluded.pages,
+                 bad_pages = excluded.bad_pages,
+                 checked_at = excluded.checked_at",
+        )
+        .bind(entry_id)
+        .bind(pages as i64)
+        .bind(serde_json::to_string(bad_pages)?)
+        .bind(crate::util::time::now_ts())
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get the stored page sprite of an entry
+    pub async fn get_page_sprite(&self, entry_id: &str) -> Result<Option<PageSprite>> {
+        let row: Option<PageSpriteRow> = sqlx::query_as(
+            "SELECT entry_id, signature, data, mime, height, step, frames
+             FROM page_sprites WHERE entry_id = ?",
+        )
+        .bind(entry_id)
+        .fetch_optional(&mut *self.acquire().await?)
+        .await?;
+
+        let Some((entry_id, signature, data, mime, height, step, frames)) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(PageSprite {
+            entry_id,
+            signature,
+            data,
+            mime,
+            height: height as u32,
+            step: step as usize,
+            frames: serde_json::from_str(&frames)?,
+        }))
+    }
+
+    /// Save the page sprite of an entry (replaces existing)
+    pub async fn save_page_sprite(&self, sprite: &PageSprite) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO page_sprites
+                 (entry_id, signature, data, mime, height, step, frames, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&sprite.entry_id)
+        .bind(&sprite.signature)
+        .bind(&sprite.data)
+        .bind(&sprite.mime)
+        .bind(sprite.height as i64)
+        .bind(sprite.step as i64)
+        .bind(serde_json::to_string(&sprite.frames)?)
+        .bind(crate::util::time::now_ts())
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
     /// Get dimension count for an entry (to check if cache is stale)
     pub async fn get_dimensions_count(&self, entry_id: &str) -> Result<usize> {
         let count: i64 = sqlx::query_scalar(
             "SELECT COUNT(*) FROM dimensions WHERE entry_id = ?"
         )
         .bind(entry_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *self.acquire().await?)
         .await?;
 
         Ok(count as usize)
     }
-}
 
-/// Hash a password using bcrypt (matches original Mango's hash_password function)
-fn hash_password(password: &str) -> Result<String> {
-    hash(password, DEFAULT_COST)
-        .map_err(|e| Error::Internal(format!("Password hashing failed: {}", e)))
-}
+    // ========== Download Usage ==========
 
-/// Verify a password against a hash (matches original Mango's verify_password function)
-fn verify_password(password: &str, hash: &str) -> Result<bool> {
-    verify(password, hash)
-        .map_err(|e| Error::Internal(format!("Password verification failed: {}", e)))
-}
+    /// Get bytes downloaded by a user on a given UTC day (YYYY-MM-DD)
+    pub async fn get_download_usage(&self, username: &str, day: &str) -> Result<u64> {
+        let bytes: Option<i64> =
+            sqlx::query_scalar("SELECT bytes FROM download_usage WHERE username = ? AND day = ?")
+                .bind(username)
+                .bind(day)
+                .fetch_optional(&mut *self.acquire().await?)
+                .await?;
 
-/// Generate a random password for initial admin (matches original random_str behavior)
-fn generate_random_password() -> String {
-    use rand::Rng;
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
-                             abcdefghijklmnopqrstuvwxyz\
-                             0123456789";
-    const PASSWORD_LEN: usize = 12;
-    let mut rng = rand::thread_rng();
+        Ok(bytes.unwrap_or(0).max(0) as u64)
+    }
 
-    (0..PASSWORD_LEN)
-        .map(|_| {
-            let idx = rng.gen_range(0..CHARSET.len());
-            CHARSET[idx] as char
-        })
-        .collect()
+    /// Add buffered download byte counts: ((username, day), bytes)
+    /// Uses transaction so a flush is applied all-or-nothing
+    pub async fn add_download_usage(&self, usage: &[((String, String), u64)]) -> Result<()> {
+        let mut conn = self.acquire().await?;
+        let result: Result<()> = async {
+            let mut tx = conn.begin().await?;
+
+            for ((username, day), bytes) in usage {
+                sqlx::query(
+                    "INSERT INTO download_usage (username, day, bytes) VALUES (?, ?, ?)
+                     ON CONFLICT(username, day) DO UPDATE SET bytes = bytes + excluded.bytes",
+                )
+                .bind(username)
+                .bind(day)
+                .bind(*bytes as i64)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            tx.commit().await?;
+            Ok(())
+        }
+        .await;
+        // The flushers' writes tell whether storage accepts writes again
+        self.health.record(&result);
+        result
+    }
+
+    /// List bytes downloaded by every user on a given UTC day
+    pub async fn list_download_usage(&self, day: &str) -> Result<Vec<(String, u64)>> {
+        let rows: Vec<(String, i64)> =
+            sqlx::query_as("SELECT username, bytes FROM download_usage WHERE day = ?")
+                .bind(day)
+                .fetch_all(&mut *self.acquire().await?)
+                .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(username, bytes)| (username, bytes.max(0) as u64))
+            .collect())
+    }
+
+    // ========== Title Statistics ==========
+
+    /// Add buffered read counters, keyed by (title_id, day), to the stored totals
+    pub async fn add_title_stats(&self, stats: &[((String, String), TitleCounts)]) -> Result<()> {
+        let mut conn = self.acquire().await?;
+        let result: Result<()> = async {
+            let mut tx = conn.begin().await?;
+
+            for ((title_id, day), counts) in stats {
+                sqlx::query(
+                    "INSERT INTO title_stats (title_id, day, page_views, reads, downloads)
+                     VALUES (?, ?, ?, ?, ?)
+                     ON CONFLICT(title_id, day) DO UPDATE SET
+                        page_views = page_views + excluded.page_views,
+                        reads = reads + excluded.reads,
+                        downloads = downloads + excluded.downloads",
+                )
+                .bind(title_id)
+                .bind(day)
+                .bind(counts.page_views as i64)
+                .bind(counts.reads as i64)
+                .bind(counts.downloads as i64)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            tx.commit().await?;
+            Ok(())
+        }
+        .await;
+        self.health.record(&result);
+        result
+    }
+
+    /// Read counters per title summed over the days from `since_day` (YYYY-MM-DD) on
+    pub async fn title_stats_since(&self, since_day: &str) -> Result<Vec<(String, TitleCounts)>> {
+        let rows: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+            "SELECT title_id, SUM(page_views), SUM(reads), SUM(downloads)
+             FROM title_stats WHERE day >= ? GROUP BY title_id",
+        )
+        .bind(since_day)
+        .fetch_all(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(title_id, page_views, reads, downloads)| {
+                let counts = TitleCounts {
+                    page_views: page_views.max(0) as u64,
+                    reads: reads.max(0) as u64,
+                    downloads: downloads.max(0) as u64,
+                };
+                (title_id, counts)
+            })
+            .collect())
+    }
+
+    // ========== Entry Merges ==========
+
+    /// Store a merge of entries (parts in reading order)
+    /// Fails with Conflict if an entry is already part of another merge
+    pub async fn save_entry_merge(&self, merge: &EntryMerge) -> Result<()> {
+        let merged_id = &merge.parts[0].0;
+        let mut conn = self.acquire().await?;
+        let mut tx = conn.begin().await?;
+
+        for (position, (entry_id, signature)) in merge.parts.iter().enumerate() {
+            let result = sqlx::query(
+                "INSERT INTO entry_merges (merged_id, position, title_id, entry_id, signature)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(merged_id)
+            .bind(position as i64)
+            .bind(&merge.title_id)
+            .bind(entry_id)
+            .bind(signature)
+            .execute(&mut *tx)
+            .await;
+
+            match result {
+                Ok(_) => {}
+                Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                    return Err(Error::Conflict(format!(
+                        "Entry {} is already merged",
+                        entry_id
+                    )))
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// All entry merges, parts in reading order
+    pub async fn list_entry_merges(&self) -> Result<Vec<EntryMerge>> {
+        let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+            "SELECT merged_id, title_id, entry_id, signature FROM entry_merges
+             ORDER BY merged_id, position",
+        )
+        .fetch_all(&mut *self.acquire().await?)
+        .await?;
+
+        let mut merges: Vec<(String, EntryMerge)> = Vec::new();
+        for (merged_id, title_id, entry_id, signature) in rows {
+            match merges.last_mut() {
+                Some((id, merge)) if *id == merged_id => merge.parts.push((entry_id, signature)),
+                _ => merges.push((
+                    merged_id,
+                    EntryMerge {
+                        title_id,
+                        parts: vec![(entry_id, signature)],
+                    },
+                )),
+            }
+        }
+
+        Ok(merges.into_iter().map(|(_, merge)| merge).collect())
+    }
+
+    /// Delete a merge by its merged entry ID
+    /// Returns false if there was no such merge
+    pub async fn delete_entry_merge(&self, merged_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM entry_merges WHERE merged_id = ?")
+            .bind(merged_id)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // ========== Title Pins ==========
+
+    /// Pin a title to the top of a user's library
+    /// Pinning a title again keeps its place among the pins
+    pub async fn pin_title(&self, username: &str, title_id: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO title_pins (username, title_id, pinned_at) VALUES (?, ?, ?)
+             ON CONFLICT(username, title_id) DO NOTHING",
+        )
+        .bind(username)
+        .bind(title_id)
+        .bind(crate::util::time::now_ts())
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Unpin a title; returns false if it was not pinned
+    pub async fn unpin_title(&self, username: &str, title_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM title_pins WHERE username = ? AND title_id = ?")
+            .bind(username)
+            .bind(title_id)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// IDs of a user's pinned titles, in the order they were pinned
+    pub async fn list_pins(&self, username: &str) -> Result<Vec<String>> {
+        let pins = sqlx::query_scalar(
+            "SELECT title_id FROM title_pins WHERE username = ? ORDER BY pinned_at, rowid",
+        )
+        .bind(username)
+        .fetch_all(&mut *self.acquire().await?)
+        .await?;
+        Ok(pins)
+    }
+
+    // ========== Tag Locks ==========
+
+    /// Lock a tag behind a PIN for a user, replacing the tag's PIN if it was locked
+    pub async fn set_tag_lock(&self, username: &str, tag: &str, pin: &str) -> Result<()> {
+        let pin_hash = hash_password(pin)?;
+        sqlx::query(
+            "INSERT INTO tag_locks (username, tag, pin_hash) VALUES (?, ?, ?)
+             ON CONFLICT (username, tag) DO UPDATE SET pin_hash = excluded.pin_hash",
+        )
+        .bind(username)
+        .bind(tag)
+        .bind(&pin_hash)
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Unlock a tag for good; returns false if it was not locked
+    pub async fn remove_tag_lock(&self, username: &str, tag: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM tag_locks WHERE username = ? AND tag = ?")
+            .bind(username)
+            .bind(tag)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Tags a user locked, sorted
+    pub async fn list_tag_locks(&self, username: &str) -> Result<Vec<String>> {
+        let tags = sqlx::query_scalar("SELECT tag FROM tag_locks WHERE username = ? ORDER BY tag")
+            .bind(username)
+            .fetch_all(&mut *self.acquire().await?)
+            .await?;
+        Ok(tags)
+    }
+
+    /// The user's locked tags whose PIN is `pin`, sorted
+    pub async fn verify_tag_pin(&self, username: &str, pin: &str) -> Result<Vec<String>> {
+        let locks: Vec<(String, String)> =
+            sqlx::query_as("SELECT tag, pin_hash FROM tag_locks WHERE username = ? ORDER BY tag")
+                .bind(username)
+                .fetch_all(&mut *self.acquire().await?)
+                .await?;
+
+        let mut unlocked = Vec::new();
+        for (tag, pin_hash) in locks {
+            if verify_password(pin, &pin_hash)? {
+                unlocked.push(tag);
+            }
+        }
+        Ok(unlocked)
+    }
+
+    /// IDs of the titles carrying any of `tags`
+    pub async fn get_titles_with_tags(&self, tags: &[String]) -> Result<Vec<String>> {
+        if tags.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders = vec!["?"; tags.len()].join(", ");
+        let sql = format!(
+            "SELECT DISTINCT id FROM tags WHERE tag IN ({})",
+            placeholders
+        );
+        let mut query = sqlx::query_scalar(&sql);
+        for tag in tags {
+            query = query.bind(tag);
+        }
+        let title_ids = query.fetch_all(&mut *self.acquire().await?).await?;
+        Ok(title_ids)
+    }
+
+    // ========== Collections ==========
+
+    /// Create an empty collection
+    /// Fails with Conflict if the user already has a collection of that name
+    pub async fn create_collection(&self, username: &str, name: &str) -> Result<Collection> {
+        let collection = Collection {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            created_at: crate::util::time::now_ts(),
+            title_ids: Vec::new(),
+        };
+        sqlx::query("INSERT INTO collections (id, username, name, created_at) VALUES (?, ?, ?, ?)")
+            .bind(&collection.id)
+            .bind(username)
+            .bind(name)
+            .bind(collection.created_at)
+            .execute(&mut *self.acquire().await?)
+            .await
+            .map_err(|e| collection_name_taken(e, name))?;
+        Ok(collection)
+    }
+
+    /// A user's collections, oldest first
+    pub async fn list_collections(&self, username: &str) -> Result<Vec<Collection>> {
+        let mut conn = self.acquire().await?;
+        let rows: Vec<(String, String, i64)> = sqlx::query_as(
+            "SELECT id, name, created_at FROM collections WHERE username = ?
+             ORDER BY created_at, rowid",
+        )
+        .bind(username)
+        .fetch_all(&mut *conn)
+        .await?;
+        let items: Vec<(String, String)> = sqlx::query_as(
+            "SELECT i.collection_id, i.title_id FROM collection_items i
+             JOIN collections c ON c.id = i.collection_id
+             WHERE c.username = ? ORDER BY i.position",
+        )
+        .bind(username)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut title_ids: HashMap<String, Vec<String>> = HashMap::new();
+        for (collection_id, title_id) in items {
+            title_ids.entry(collection_id).or_default().push(title_id);
+        }
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, created_at)| Collection {
+                title_ids: title_ids.remove(&id).unwrap_or_default(),
+                id,
+                name,
+                created_at,
+            })
+            .collect())
+    }
+
+    /// One of a user's collections; None if it does not exist or belongs to someone else
+    pub async fn get_collection(&self, username: &str, id: &str) -> Result<Option<Collection>> {
+        let mut conn = self.acquire().await?;
+        let row: Option<(String, i64)> = sqlx::query_as(
+            "SELECT name, created_at FROM collections WHERE id = ? AND username = ?",
+        )
+        .bind(id)
+        .bind(username)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let Some((name, created_at)) = row else {
+            return Ok(None);
+        };
+
+        let title_ids = sqlx::query_scalar(
+            "SELECT title_id FROM collection_items WHERE collection_id = ? ORDER BY position",
+        )
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(Some(Collection {
+            id: id.to_string(),
+            name,
+            created_at,
+            title_ids,
+        }))
+    }
+
+    /// Rename one of a user's collections; returns false if the user has no such collection
+    /// Fails with Conflict if the user already has a collection of that name
+    pub async fn rename_collection(&self, username: &str, id: &str, name: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE collections SET name = ? WHERE id = ? AND username = ?")
+            .bind(name)
+            .bind(id)
+            .bind(username)
+            .execute(&mut *self.acquire().await?)
+            .await
+            .map_err(|e| collection_name_taken(e, name))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete one of a user's collections; returns false if the user has no such collection
+    pub async fn delete_collection(&self, username: &str, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM collections WHERE id = ? AND username = ?")
+            .bind(id)
+            .bind(username)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Add a title to the end of a collection
+    /// Adding a title again keeps its place
+    pub async fn add_to_collection(&self, collection_id: &str, title_id: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO collection_items (collection_id, title_id, position)
+             SELECT ?, ?, COALESCE(MAX(position) + 1, 0) FROM collection_items
+             WHERE collection_id = ?
+             ON CONFLICT(collection_id, title_id) DO NOTHING",
+        )
+        .bind(collection_id)
+        .bind(title_id)
+        .bind(collection_id)
+        .execute(&mut *self.acquire().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Remove a title from a collection; returns false if it was not in it
+    pub async fn remove_from_collection(
+        &self,
+        collection_id: &str,
+        title_id: &str,
+    ) -> Result<bool> {
+        let result =
+            sqlx::query("DELETE FROM collection_items WHERE collection_id = ? AND title_id = ?")
+                .bind(collection_id)
+                .bind(title_id)
+                .execute(&mut *self.acquire().await?)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Put the titles of a collection in the order of `title_ids`
+    /// Fails with BadRequest unless `title_ids` lists every title of the collection once
+    pub async fn reorder_collection(
+        &self,
+        collection_id: &str,
+        title_ids: &[String],
+    ) -> Result<()> {
+        let mut seen = std::collections::HashSet::new();
+        if let Some(duplicate) = title_ids.iter().find(|id| !seen.insert(*id)) {
+            return Err(Error::BadRequest(format!(
+                "Title {} is listed more than once",
+                duplicate
+            )));
+        }
+
+        let (collection_id, title_ids) = (collection_id.to_string(), title_ids.to_vec());
+        self.transaction(move |tx| {
+            Box::pin(
+                async move { Self::reorder_collection_tx(tx, &collection_id, &title_ids).await },
+            )
+        })
+        .await
+    }
+
+    /// Transactional part of `reorder_collection`
+    pub async fn reorder_collection_tx(
+        conn: &mut SqliteConnection,
+        collection_id: &str,
+        title_ids: &[String],
+    ) -> Result<()> {
+        for (position, title_id) in title_ids.iter().enumerate() {
+            let result = sqlx::query(
+                "UPDATE collection_items SET position = ? WHERE collection_id = ? AND title_id = ?",
+            )
+            .bind(position as i64)
+            .bind(collection_id)
+            .bind(title_id)
+            .execute(&mut *conn)
+            .await?;
+            if result.rows_affected() == 0 {
+                return Err(Error::BadRequest(format!(
+                    "Title {} is not in the collection",
+                    title_id
+                )));
+            }
+        }
+
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM collection_items WHERE collection_id = ?")
+                .bind(collection_id)
+                .fetch_one(&mut *conn)
+                .await?;
+        if count as usize != title_ids.len() {
+            return Err(Error::BadRequest(
+                "The order must list every title of the collection".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // ========== Problem Reports ==========
+
+    /// File a problem report for an entry
+    /// Fails with Conflict if the user already has an identical open report
+    pub async fn create_report(
+        &self,
+        title_id: &str,
+        entry_id: &str,
+        username: &str,
+        category: ReportCategory,
+        note: &str,
+    ) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO reports (title_id, entry_id, username, category, note, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(title_id)
+        .bind(entry_id)
+        .bind(username)
+        .bind(category.as_str())
+        .bind(note)
+        .bind(crate::util::time::now_ts())
+        .execute(&mut *self.acquire().await?)
+        .await;
+
+        match result {
+            Ok(result) => Ok(result.last_insert_rowid()),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Err(Error::Conflict(
+                "You already have an open report of this kind for this entry".to_string(),
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// List open reports (oldest first) or resolved reports (most recently resolved first)
+    pub async fn list_reports(&self, resolved: bool) -> Result<Vec<Report>> {
+        let sql = if resolved {
+            "SELECT id, title_id, entry_id, username, category, note, created_at, resolved_at, resolved_by
+             FROM reports WHERE resolved_at IS NOT NULL ORDER BY resolved_at DESC"
+        } else {
+            "SELECT id, title_id, entry_id, username, category, note, created_at, resolved_at, resolved_by
+             FROM reports WHERE resolved_at IS NULL ORDER BY created_at"
+        };
+
+        let rows = sqlx::query(sql)
+            .fetch_all(&mut *self.acquire().await?)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Report {
+                id: row.get("id"),
+                title_id: row.get("title_id"),
+                entry_id: row.get("entry_id"),
+                username: row.get("username"),
+                category: row.get("category"),
+                note: row.get("note"),
+                created_at: row.get("created_at"),
+                resolved_at: row.get("resolved_at"),
+                resolved_by: row.get("resolved_by"),
+            })
+            .collect())
+    }
+
+    /// Get count of open reports
+    /// Used for admin dashboard
+    pub async fn get_open_report_count(&self) -> Result<usize> {
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM reports WHERE resolved_at IS NULL")
+                .fetch_one(&mut *self.acquire().await?)
+                .await?;
+
+        Ok(count as usize)
+    }
+
+    /// Mark an open report as resolved and prune old resolved reports
+    pub async fn resolve_report(&self, id: i64, resolved_by: &str) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE reports SET resolved_at = ?, resolved_by = ? WHERE id = ? AND resolved_at IS NULL",
+        )
+        .bind(crate::util::time::now_ts())
+        .bind(resolved_by)
+        .bind(id)
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::NotFound(format!("Open report not found: {}", id)));
+        }
+
+        // Keep only the most recently resolved reports for history
+        sqlx::query(
+            "DELETE FROM reports WHERE resolved_at IS NOT NULL AND id NOT IN (
+                 SELECT id FROM reports WHERE resolved_at IS NOT NULL
+                 ORDER BY resolved_at DESC, id DESC LIMIT ?
+             )",
+        )
+        .bind(RESOLVED_REPORT_RETENTION)
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record that a user read an entry (upserts the recently read index)
+    pub async fn record_recently_read(
+        &self,
+        username: &str,
+        title_id: &str,
+        entry_id: &str,
+        page: i32,
+        last_read: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO recently_read (username, title_id, entry_id, page, last_read)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(username, title_id, entry_id)
+             DO UPDATE SET page = excluded.page, last_read = excluded.last_read",
+        )
+        .bind(username)
+        .bind(title_id)
+        .bind(entry_id)
+        .bind(page)
+        .bind(last_read)
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Point an entry's recently read rows at the title it moved into
+    pub async fn move_recently_read(&self, entry_id: &str, title_id: &str) -> Result<()> {
+        sqlx::query("UPDATE OR REPLACE recently_read SET title_id = ? WHERE entry_id = ?")
+            .bind(title_id)
+            .bind(entry_id)
+            .execute(&mut *self.acquire().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    /// List a user's most recently read entries, newest first
+    pub async fn list_recently_read(
+        &self,
+        username: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<RecentlyRead>> {
+        let rows = sqlx::query(
+            "SELECT username, title_id, entry_id, page, last_read FROM recently_read
+             WHERE username = ? ORDER BY last_read DESC, entry_id LIMIT ? OFFSET ?",
+        )
+        .bind(username)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| RecentlyRead {
+                username: row.get("username"),
+                title_id: row.get("title_id"),
+                entry_id: row.get("entry_id"),
+                page: row.get("page"),
+                last_read: row.get("last_read"),
+            })
+            .collect())
+    }
+
+    /// Check whether the recently read index has any rows
+    /// An empty index means it has not been backfilled yet
+    pub async fn has_recently_read(&self) -> Result<bool> {
+        let exists: i64 = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM recently_read)")
+            .fetch_one(&mut *self.acquire().await?)
+            .await?;
+
+        Ok(exists != 0)
+    }
+
+    /// Bulk insert rows into the recently read index
+    /// Existing rows are kept, since they come from live progress saves
+    /// Returns the number of rows inserted
+    pub async fn backfill_recently_read(&self, rows: &[RecentlyRead]) -> Result<usize> {
+        let mut conn = self.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let mut inserted = 0;
+
+        for row in rows {
+            let result = sqlx::query(
+                "INSERT OR IGNORE INTO recently_read (username, title_id, entry_id, page, last_read)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(&row.username)
+            .bind(&row.title_id)
+            .bind(&row.entry_id)
+            .bind(row.page)
+            .bind(row.last_read)
+            .execute(&mut *tx)
+            .await?;
+            inserted += result.rows_affected() as usize;
+        }
+
+        tx.commit().await?;
+        Ok(inserted)
+    }
+}
+
+/// Build a PageCheck from a (entry_id, pages, bad_pages, checked_at) row
+fn page_check_from_row(
+    (entry_id, pages, bad_pages, checked_at): (String, i64, String, i64),
+) -> Result<PageCheck> {
+    Ok(PageCheck {
+        entry_id,
+        pages: pages as usize,
+        bad_pages: serde_json::from_str(&bad_pages)?,
+        checked_at,
+    })
+}
+
+/// Report a broken unique or foreign key constraint as a Conflict rather than a
+/// database error (500)
+fn constraint_conflict(e: Error) -> Error {
+    match e {
+        Error::Database(sqlx::Error::Database(db))
+            if db.is_unique_violation() || db.is_foreign_key_violation() =>
+        {
+            Error::Conflict(db.message().to_string())
+        }
+        e => e,
+    }
+}
+
+/// A duplicate collection name as a Conflict naming it
+fn collection_name_taken(e: sqlx::Error, name: &str) -> Error {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            Error::Conflict(format!("You already have a collection named '{}'", name))
+        }
+        _ => e.into(),
+    }
+}
+
+/// A duplicate username as a Conflict naming it
+fn username_taken(e: sqlx::Error, username: &str) -> Error {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            Error::Conflict(format!("Username '{}' already exists", username))
+        }
+        _ => e.into(),
+    }
+}
+
+/// Hash a password using bcrypt (matches original Mango's hash_password function)
+fn hash_password(password: &str) -> Result<String> {
+    hash(password, DEFAULT_COST)
+        .map_err(|e| Error::Internal(format!("Password hashing failed: {}", e)))
+}
+
+/// Verify a password against a hash (matches original Mango's verify_password function)
+fn verify_password(password: &str, hash: &str) -> Result<bool> {
+    verify(password, hash)
+        .map_err(|e| Error::Internal(format!("Password verification failed: {}", e)))
+}
+
+/// Generate a random password for initial admin (matches original random_str behavior)
+fn generate_random_password() -> String {
+    use rand::Rng;
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+                             abcdefghijklmnopqrstuvwxyz\
+                             0123456789";
+    const PASSWORD_LEN: usize = 12;
+    let mut rng = rand::thread_rng();
+
+    (0..PASSWORD_LEN)
+        .map(|_| {
+            let idx = rng.gen_range(0..CHARSET.len());
+            CHARSET[idx] as char
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+
+    fn tiny_pool_settings() -> PoolSettings {
+        PoolSettings {
+            max_connections: 1,
+            min_connections: 1,
+            acquire_timeout: Duration::from_millis(200),
+            ..PoolSettings::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_exhaustion_returns_service_unavailable() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::with_settings(&database_url, tiny_pool_settings())
+            .await
+            .unwrap();
+
+        // Hold the only connection so the next query has to wait for it
+        let held = storage.acquire().await.unwrap();
+
+        let err = storage.list_users().await.unwrap_err();
+        assert!(matches!(err, Error::ServiceUnavailable(_)));
+        assert_eq!(
+            err.into_response().status(),
+            axum::http::StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let stats = storage.pool_stats();
+        assert_eq!(stats.acquire_timeouts, 1);
+        assert_eq!(stats.size, 1);
+        assert_eq!(stats.idle, 0);
+
+        // Releasing the connection makes the pool usable again
+        drop(held);
+        assert!(storage.list_users().await.is_ok());
+        assert_eq!(storage.pool_stats().acquire_timeouts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_query_counter() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let settings = PoolSettings {
+            slow_query: Duration::from_millis(50),
+            ..tiny_pool_settings()
+        };
+        let storage = Storage::with_settings(&database_url, settings)
+            .await
+            .unwrap();
+        let before = storage.pool_stats().slow_queries;
+
+        let conn = storage.acquire().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        drop(conn);
+
+        assert_eq!(storage.pool_stats().slow_queries, before + 1);
+    }
+
+    #[tokio::test]
+    async fn test_recently_read_index() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+
+        assert!(!storage.has_recently_read().await.unwrap());
+
+        storage
+            .record_recently_read("alice", "t1", "e1", 3, 100)
+            .await
+            .unwrap();
+        storage
+            .record_recently_read("alice", "t1", "e2", 7, 200)
+            .await
+            .unwrap();
+        storage
+            .record_recently_read("bob", "t1", "e1", 1, 300)
+            .await
+            .unwrap();
+        assert!(storage.has_recently_read().await.unwrap());
+
+        // Saving again moves the entry to the front
+        storage
+            .record_recently_read("alice", "t1", "e1", 4, 400)
+            .await
+            .unwrap();
+
+        let rows = storage.list_recently_read("alice", 8, 0).await.unwrap();
+        let ids: Vec<&str> = rows.iter().map(|r| r.entry_id.as_str()).collect();
+        assert_eq!(ids, vec!["e1", "e2"]);
+        assert_eq!(rows[0].page, 4);
+        assert_eq!(rows[0].last_read, 400);
+
+        let page = storage.list_recently_read("alice", 1, 1).await.unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].entry_id, "e2");
+
+        // Backfill never overwrites rows written by live progress saves
+        let backfill = vec![
+            RecentlyRead {
+                username: "alice".to_string(),
+                title_id: "t1".to_string(),
+                entry_id: "e1".to_string(),
+                page: 1,
+                last_read: 50,
+            },
+            RecentlyRead {
+                username: "alice".to_string(),
+                title_id: "t2".to_string(),
+                entry_id: "e3".to_string(),
+                page: 2,
+                last_read: 150,
+            },
+        ];
+        assert_eq!(storage.backfill_recently_read(&backfill).await.unwrap(), 1);
+
+        let rows = storage.list_recently_read("alice", 8, 0).await.unwrap();
+        let ids: Vec<&str> = rows.iter().map(|r| r.entry_id.as_str()).collect();
+        assert_eq!(ids, vec!["e1", "e2", "e3"]);
+        assert_eq!(rows[0].page, 4);
+    }
+
+    #[tokio::test]
+    async fn test_two_factor() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        storage
+            .create_user("alice", "password", false, true)
+            .await
+            .unwrap();
+
+        // Nothing to confirm before enrolling
+        assert!(storage.enable_totp("alice", 10, &[]).await.is_err());
+
+        storage.set_totp_pending("alice", "SECRET").await.unwrap();
+        assert!(!storage.totp_enabled("alice").await.unwrap());
+        let hashes = vec!["h1".to_string(), "h2".to_string()];
+        storage.enable_totp("alice", 10, &hashes).await.unwrap();
+        assert!(storage.totp_enabled("alice").await.unwrap());
+        assert_eq!(
+            storage.get_totp("alice").await.unwrap(),
+            Some(("SECRET".to_string(), Some(10)))
+        );
+        assert_eq!(storage.get_totp_pending("alice").await.unwrap(), None);
+
+        // Steps only move forward
+        assert!(!storage.accept_totp_step("alice", 10).await.unwrap());
+        assert!(storage.accept_totp_step("alice", 11).await.unwrap());
+        assert!(!storage.accept_totp_step("alice", 9).await.unwrap());
+
+        // Recovery codes work once each
+        assert!(storage.use_recovery_code("alice", "h1").await.unwrap());
+        assert!(!storage.use_recovery_code("alice", "h1").await.unwrap());
+        assert!(!storage.use_recovery_code("alice", "nope").await.unwrap());
+        assert_eq!(storage.recovery_codes_left("alice").await.unwrap(), 1);
+
+        let id = storage
+            .create_app_token("alice", "Reader", "t1")
+            .await
+            .unwrap();
+        assert!(storage.verify_app_token("alice", "t1").await.unwrap());
+        assert!(!storage.verify_app_token("bob", "t1").await.unwrap());
+        assert_eq!(storage.list_app_tokens("alice").await.unwrap()[0].id, id);
+
+        storage.reset_totp("alice").await.unwrap();
+        assert!(!storage.totp_enabled("alice").await.unwrap());
+        assert_eq!(storage.recovery_codes_left("alice").await.unwrap(), 0);
+        assert!(!storage.verify_app_token("alice", "t1").await.unwrap());
+        assert!(storage.reset_totp("nobody").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_login_sessions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        storage
+            .create_user("alice", "password", true, true)
+            .await
+            .unwrap();
+
+        assert!(storage.verify_user("alice", "password").await.unwrap());
+        assert!(!storage.verify_user("alice", "wrong").await.unwrap());
+        assert!(!storage.verify_user("bob", "password").await.unwrap());
+
+        // Each login is a session of its own
+        let phone = storage
+            .create_session("alice", Some("Phone"))
+            .await
+            .unwrap();
+        let laptop = storage.create_session("alice", None).await.unwrap();
+        let tablet = storage.create_session("alice", None).await.unwrap();
+        assert_ne!(phone, laptop);
+        assert_eq!(
+            storage.verify_token(&phone).await.unwrap(),
+            Some("alice".to_string())
+        );
+        assert!(storage.verify_admin(&laptop).await.unwrap());
+        assert_eq!(storage.list_sessions("alice").await.unwrap().len(), 3);
+
+        storage.logout(&phone).await.unwrap();
+        assert_eq!(storage.verify_token(&phone).await.unwrap(), None);
+        assert!(!storage.verify_admin(&phone).await.unwrap());
+        assert!(storage.verify_token(&laptop).await.unwrap().is_some());
+
+        assert_eq!(
+            storage
+                .revoke_sessions("alice", Some(&laptop))
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(storage.verify_token(&tablet).await.unwrap(), None);
+        assert!(storage.verify_token(&laptop).await.unwrap().is_some());
+        assert_eq!(storage.revoke_sessions("alice", None).await.unwrap(), 1);
+        assert!(storage.list_sessions("alice").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_api_tokens() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        storage
+            .create_user("alice", "password", false, true)
+            .await
+            .unwrap();
+
+        let id = storage
+            .create_api_token("alice", "Backup script", "h1")
+            .await
+            .unwrap();
+        storage
+            .create_api_token("alice", "Other", "h2")
+            .await
+            .unwrap();
+        let tokens = storage.list_api_tokens("alice").await.unwrap();
+        assert!(tokens.iter().all(|t| t.last_used.is_none()));
+
+        assert_eq!(
+            storage.verify_api_token("h1").await.unwrap(),
+            Some("alice".to_string())
+        );
+        assert_eq!(storage.verify_api_token("nope").await.unwrap(), None);
+        let tokens = storage.list_api_tokens("alice").await.unwrap();
+        let used: Vec<&str> = tokens
+            .iter()
+            .filter(|t| t.last_used.is_some())
+            .map(|t| t.id.as_str())
+            .collect();
+        assert_eq!(used, [id.as_str()]);
+        assert_eq!(storage.count_api_tokens().await.unwrap()["alice"], 2);
+
+        // Only the owner can revoke a token
+        assert!(!storage.delete_api_token("bob", &id).await.unwrap());
+        assert!(storage.delete_api_token("alice", &id).await.unwrap());
+        assert_eq!(storage.verify_api_token("h1").await.unwrap(), None);
+
+        storage.delete_user("alice").await.unwrap();
+        assert!(storage.count_api_tokens().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_initial_import() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+
+        assert_eq!(storage.get_initial_import().await.unwrap(), None);
+
+        // The first scan of an empty database is the initial import, also when resumed
+        assert!(storage.begin_initial_import("scan-1", 100).await.unwrap());
+        sqlx::query(
+            "INSERT INTO titles (id, path, signature, unavailable) VALUES ('t1', 'A', '1', 0)",
+        )
+        .execute(storage.pool())
+        .await
+        .unwrap();
+        assert!(storage.begin_initial_import("scan-1", 150).await.unwrap());
+        assert_eq!(
+            storage.get_initial_import().await.unwrap(),
+            Some((100, None))
+        );
+
+        storage.finish_initial_import(200).await.unwrap();
+        assert_eq!(
+            storage.get_initial_import().await.unwrap(),
+            Some((100, Some(200)))
+        );
+
+        // Later scans are not
+        assert!(!storage.begin_initial_import("scan-2", 300).await.unwrap());
+        storage.finish_initial_import(400).await.unwrap();
+        assert_eq!(
+            storage.get_initial_import().await.unwrap(),
+            Some((100, Some(200)))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_title_relations() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        for id in ["t1", "t2", "t3", "t4"] {
+            sqlx::query(
+                "INSERT INTO titles (id, path, signature, unavailable) VALUES (?, ?, '1', 0)",
+            )
+            .bind(id)
+            .bind(id)
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        }
+        let sequel_of = |id: &str| TitleRelations {
+            sequel_of: Some(id.to_string()),
+            ..Default::default()
+        };
+
+        // t1 -> t2 -> t3
+        storage
+            .set_title_relations("t2", &sequel_of("t1"))
+            .await
+            .unwrap();
+        storage
+            .set_title_relations("t3", &sequel_of("t2"))
+            .await
+            .unwrap();
+        let t2 = storage.get_title_relations("t2").await.unwrap();
+        assert_eq!(t2.sequel_of.as_deref(), Some("t1"));
+        assert_eq!(t2.prequel_of.as_deref(), Some("t3"));
+        assert_eq!(
+            storage.get_sequel("t1").await.unwrap().as_deref(),
+            Some("t2")
+        );
+        assert_eq!(storage.get_sequel("t3").await.unwrap(), None);
+
+        // Closing the chain is refused and changes nothing
+        let closed = TitleRelations {
+            sequel_of: Some("t3".to_string()),
+            prequel_of: Some("t2".to_string()),
+            ..Default::default()
+        };
+        let err = storage
+            .set_title_relations("t1", &closed)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::BadRequest(_)));
+        let err = storage
+            .set_title_relations("t1", &sequel_of("t1"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::BadRequest(_)));
+        assert_eq!(
+            storage.get_sequel("t1").await.unwrap().as_deref(),
+            Some("t2")
+        );
+        assert_eq!(storage.get_sequel("t3").await.unwrap(), None);
+
+        // Related titles show up from both sides
+        let related = TitleRelations {
+            related: vec!["t1".to_string(), "t3".to_string()],
+            ..Default::default()
+        };
+        storage.set_title_relations("t4", &related).await.unwrap();
+        assert_eq!(storage.get_title_relations("t4").await.unwrap(), related);
+        assert_eq!(
+            storage.get_title_relations("t1").await.unwrap().related,
+            ["t4"]
+        );
+
+        // A new sequel of t1 takes t2's place; t2 keeps its own sequel
+        storage
+            .set_title_relations("t4", &sequel_of("t1"))
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get_sequel("t1").await.unwrap().as_deref(),
+            Some("t4")
+        );
+        let t2 = storage.get_title_relations("t2").await.unwrap();
+        assert_eq!(t2.sequel_of, None);
+        assert_eq!(t2.prequel_of.as_deref(), Some("t3"));
+        assert!(storage
+            .get_title_relations("t1")
+            .await
+            .unwrap()
+            .related
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_title_aliases() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        sqlx::query(
+            "INSERT INTO titles (id, path, signature, unavailable) VALUES ('t1', 'A', '1', 0)",
+        )
+        .execute(storage.pool())
+        .await
+        .unwrap();
+
+        assert!(storage.get_title_aliases("t1").await.unwrap().is_empty());
+
+        let aliases = vec![
+            "Shingeki no Kyojin".to_string(),
+            "Attack on Titan".to_string(),
+        ];
+        storage.set_title_aliases("t1", &aliases).await.unwrap();
+        assert_eq!(storage.get_title_aliases("t1").await.unwrap(), aliases);
+        assert_eq!(storage.list_title_aliases().await.unwrap()["t1"], aliases);
+
+        // Replacing keeps the new order
+        let reordered = vec!["Attack on Titan".to_string()];
+        storage.set_title_aliases("t1", &reordered).await.unwrap();
+        assert_eq!(storage.get_title_aliases("t1").await.unwrap(), reordered);
+
+        // Aliases are unique within a title regardless of case
+        let duplicate = vec!["Attack on Titan".to_string(), "attack on titan".to_string()];
+        assert!(storage.set_title_aliases("t1", &duplicate).await.is_err());
+        assert_eq!(storage.get_title_aliases("t1").await.unwrap(), reordered);
+    }
+
+    #[tokio::test]
+    async fn test_title_pins() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        storage
+            .create_user("alice", "password", false, true)
+            .await
+            .unwrap();
+        storage
+            .create_user("bob", "password", false, true)
+            .await
+            .unwrap();
+
+        storage.pin_title("alice", "t2").await.unwrap();
+        storage.pin_title("alice", "t1").await.unwrap();
+        // Pinning again keeps the first pin's place
+        storage.pin_title("alice", "t2").await.unwrap();
+        storage.pin_title("bob", "t3").await.unwrap();
+        assert_eq!(storage.list_pins("alice").await.unwrap(), ["t2", "t1"]);
+        assert_eq!(storage.list_pins("bob").await.unwrap(), ["t3"]);
+
+        assert!(storage.unpin_title("alice", "t2").await.unwrap());
+        assert!(!storage.unpin_title("alice", "t2").await.unwrap());
+        assert_eq!(storage.list_pins("alice").await.unwrap(), ["t1"]);
+    }
+
+    #[tokio::test]
+    async fn test_tag_locks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        storage
+            .create_user("alice", "password", false, true)
+            .await
+            .unwrap();
+
+        storage.set_tag_lock("alice", "b", "1111").await.unwrap();
+        storage.set_tag_lock("alice", "a", "1111").await.unwrap();
+        storage.set_tag_lock("alice", "c", "2222").await.unwrap();
+        assert_eq!(
+            storage.list_tag_locks("alice").await.unwrap(),
+            ["a", "b", "c"]
+        );
+        assert_eq!(
+            storage.verify_tag_pin("alice", "1111").await.unwrap(),
+            ["a", "b"]
+        );
+        assert!(storage
+            .verify_tag_pin("alice", "3333")
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Locking again replaces the PIN
+        storage.set_tag_lock("alice", "c", "1111").await.unwrap();
+        assert_eq!(
+            storage.verify_tag_pin("alice", "1111").await.unwrap(),
+            ["a", "b", "c"]
+        );
+        assert!(storage.remove_tag_lock("alice", "c").await.unwrap());
+        assert!(!storage.remove_tag_lock("alice", "c").await.unwrap());
+
+        for id in ["t1", "t2", "t3"] {
+            sqlx::query(
+                "INSERT INTO titles (id, path, signature, unavailable) VALUES (?, ?, '1', 0)",
+            )
+            .bind(id)
+            .bind(id)
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        }
+        storage.add_tag("t1", "a").await.unwrap();
+        storage.add_tag("t2", "b").await.unwrap();
+        storage.add_tag("t2", "a").await.unwrap();
+        storage.add_tag("t3", "c").await.unwrap();
+        let mut titles = storage
+            .get_titles_with_tags(&["a".to_string(), "b".to_string()])
+            .await
+            .unwrap();
+        titles.sort();
+        assert_eq!(titles, ["t1", "t2"]);
+        assert!(storage.get_titles_with_tags(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_collections() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        storage
+            .create_user("alice", "password", false, true)
+            .await
+            .unwrap();
+        storage
+            .create_user("bob", "password", false, true)
+            .await
+            .unwrap();
+        for id in ["t1", "t2", "t3"] {
+            sqlx::query(
+                "INSERT INTO titles (id, path, signature, unavailable) VALUES (?, ?, '1', 0)",
+            )
+            .bind(id)
+            .bind(id)
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        }
+
+        let kids = storage.create_collection("alice", "Kids").await.unwrap();
+        let err = storage
+            .create_collection("alice", "Kids")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Conflict(_)));
+        // Names are per user
+        storage.create_collection("bob", "Kids").await.unwrap();
+
+        for id in ["t2", "t1", "t3", "t2"] {
+            storage.add_to_collection(&kids.id, id).await.unwrap();
+        }
+        let title_ids = |c: Option<Collection>| c.unwrap().title_ids;
+        assert_eq!(
+            title_ids(storage.get_collection("alice", &kids.id).await.unwrap()),
+            ["t2", "t1", "t3"]
+        );
+
+        let order = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        storage
+            .reorder_collection(&kids.id, &order(&["t3", "t2", "t1"]))
+            .await
+            .unwrap();
+        // Orders leaving out, adding or repeating titles change nothing
+        for invalid in [
+            &["t3", "t2"][..],
+            &["t3", "t2", "t1", "t4"],
+            &["t3", "t2", "t2"],
+        ] {
+            let err = storage
+                .reorder_collection(&kids.id, &order(invalid))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, Error::BadRequest(_)), "{:?}", invalid);
+        }
+        assert_eq!(
+            title_ids(storage.get_collection("alice", &kids.id).await.unwrap()),
+            ["t3", "t2", "t1"]
+        );
+
+        // Another user's collection cannot be seen, renamed or deleted
+        assert!(storage
+            .get_collection("bob", &kids.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(!storage
+            .rename_collection("bob", &kids.id, "Mine")
+            .await
+            .unwrap());
+        assert!(!storage.delete_collection("bob", &kids.id).await.unwrap());
+        assert_eq!(storage.list_collections("bob").await.unwrap().len(), 1);
+
+        // Deleted titles drop out of collections
+        assert!(storage
+            .remove_from_collection(&kids.id, "t2")
+            .await
+            .unwrap());
+        sqlx::query("DELETE FROM titles WHERE id = 't3'")
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        let collections = storage.list_collections("alice").await.unwrap();
+        assert_eq!(collections.len(), 1);
+        assert_eq!(collections[0].title_ids, ["t1"]);
+
+        assert!(storage
+            .rename_collection("alice", &kids.id, "Family")
+            .await
+            .unwrap());
+        assert!(storage.delete_collection("alice", &kids.id).await.unwrap());
+        assert!(storage.list_collections("alice").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_page_checks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+
+        assert!(storage.get_page_check("e1").await.unwrap().is_none());
+
+        storage.save_page_check("e1", 10, &[]).await.unwrap();
+        storage.save_page_check("e2", 5, &[2, 5]).await.unwrap();
+
+        let check = storage.get_page_check("e1").await.unwrap().unwrap();
+        assert_eq!(check.pages, 10);
+        assert!(check.bad_pages.is_empty());
+
+        // Only entries with bad pages are listed
+        let bad = storage.list_bad_pages().await.unwrap();
+        assert_eq!(bad.len(), 1);
+        assert_eq!(bad[0].entry_id, "e2");
+        assert_eq!(bad[0].bad_pages, vec![2, 5]);
+
+        // Re-checking replaces the previous result
+        storage.save_page_check("e2", 6, &[]).await.unwrap();
+        assert!(storage.list_bad_pages().await.unwrap().is_empty());
+        assert_eq!(
+            storage.get_page_check("e2").await.unwrap().unwrap().pages,
+            6
+        );
+    }
+
+    #[tokio::test]
+    async fn test_page_sprites() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+
+        assert!(storage.get_page_sprite("e1").await.unwrap().is_none());
+
+        let mut sprite = PageSprite {
+            entry_id: "e1".to_string(),
+            signature: "sig1".to_string(),
+            data: vec![1, 2, 3],
+            mime: "image/jpeg".to_string(),
+            height: 60,
+            step: 1,
+            frames: vec![
+                SpriteFrame {
+                    page: 1,
+                    x: 0,
+                    width: 42,
+                },
+                SpriteFrame {
+                    page: 2,
+                    x: 42,
+                    width: 40,
+                },
+            ],
+        };
+        storage.save_page_sprite(&sprite).await.unwrap();
+
+        let stored = storage.get_page_sprite("e1").await.unwrap().unwrap();
+        assert_eq!(stored.signature, "sig1");
+        assert_eq!(stored.data, vec![1, 2, 3]);
+        assert_eq!(stored.height, 60);
+        assert_eq!(stored.frames, sprite.frames);
+
+        // Regenerating replaces the previous sprite
+        sprite.signature = "sig2".to_string();
+        sprite.step = 2;
+        storage.save_page_sprite(&sprite).await.unwrap();
+        let stored = storage.get_page_sprite("e1").await.unwrap().unwrap();
+        assert_eq!(stored.signature, "sig2");
+        assert_eq!(stored.step, 2);
+    }
+
+    #[tokio::test]
+    async fn test_prune_thumbnails() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+
+        // e1 is available, e2 missing since long ago, e3 missing only recently
+        let entries = [("e1", 0, None), ("e2", 1, Some(100)), ("e3", 1, Some(900))];
+        for (id, unavailable, since) in entries {
+            sqlx::query(
+                "INSERT INTO ids (id, path, signature, unavailable, unavailable_since) \
+                 VALUES (?, ?, '1', ?, ?)",
+            )
+            .bind(id)
+            .bind(format!("T/{}.zip", id))
+            .bind(unavailable)
+            .bind(since)
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        }
+
+        let mut conn = storage.pool().acquire().await.unwrap();
+        // Databases from before the foreign key can hold thumbnails of unknown entries
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        for (id, size) in [("e1", 10), ("e2", 20), ("e3", 30), ("gone", 40)] {
+            sqlx::query(
+                "INSERT INTO thumbnails (id, data, filename, mime, size) \
+                 VALUES (?, ?, 'cover.jpg', 'image/jpeg', ?)",
+            )
+            .bind(id)
+            .bind(vec![0u8; size])
+            .bind(size as i64)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        }
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        drop(conn);
+
+        // Page sprites have no foreign key, so deleting an entry leaves its sprite behind
+        for id in ["e1", "e2"] {
+            sqlx::query(
+                "INSERT INTO page_sprites \
+                 (entry_id, signature, data, mime, height, step, frames, created_at) \
+                 VALUES (?, '1', ?, 'image/jpeg', 10, 1, '[]', 0)",
+            )
+            .bind(id)
+            .bind(vec![0u8; 5])
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        }
+        sqlx::query("DELETE FROM ids WHERE id = 'e1'")
+            .execute(storage.pool())
+            .await
+            .unwrap();
+
+        // e1's sprite, e2's thumbnail and sprite, and the unknown entry's thumbnail
+        let pruned = storage.prune_thumbnails(500).await.unwrap();
+        assert_eq!(
+            pruned,
+            ThumbnailPrune {
+                rows: 4,
+                bytes: 5 + 20 + 5 + 40
+            }
+        );
+
+        let left: Vec<String> = sqlx::query_scalar("SELECT id FROM thumbnails")
+            .fetch_all(storage.pool())
+            .await
+            .unwrap();
+        assert_eq!(left, ["e3"]);
+
+        // Nothing left to do
+        assert_eq!(
+            storage.prune_thumbnails(500).await.unwrap(),
+            ThumbnailPrune::default()
+        );
+        storage.vacuum().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_transaction() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        sqlx::query(
+            "INSERT INTO titles (id, path, signature, unavailable) VALUES ('t1', 'A', '1', 0)",
+        )
+        .execute(storage.pool())
+        .await
+        .unwrap();
+
+        // A failing step rolls back the steps before it
+        let err = storage
+            .transaction(|tx| {
+                Box::pin(async move {
+                    Storage::add_tag_tx(tx, "t1", "a").await?;
+                    Storage::add_tag_tx(tx, "t1", "b").await?;
+                    Storage::add_tag_tx(tx, "t1", "a").await
+                })
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Conflict(_)));
+        assert!(storage.get_title_tags("t1").await.unwrap().is_empty());
+
+        // Broken constraints are conflicts, not database errors
+        storage.add_tag("t1", "a").await.unwrap();
+        assert!(matches!(
+            storage.add_tag("t1", "a").await,
+            Err(Error::Conflict(_))
+        ));
+        assert!(matches!(
+            storage.add_tag("missing", "a").await,
+            Err(Error::Conflict(_))
+        ));
+        assert_eq!(storage.get_title_tags("t1").await.unwrap(), ["a"]);
+
+        assert!(matches!(
+            storage
+                .update_user("nobody", "nobody", None, false, true)
+                .await,
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            storage.delete_user("nobody").await,
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_last_admin_stays() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        // The initial "admin" is the only admin
+        let storage = Storage::new(&database_url).await.unwrap();
+        storage
+            .create_user("alice", "password", false, true)
+            .await
+            .unwrap();
+        assert_eq!(storage.count_admins().await.unwrap(), 1);
+
+        assert!(matches!(
+            storage.delete_user("admin").await,
+            Err(Error::Conflict(_))
+        ));
+        assert!(matches!(
+            storage
+                .update_user("admin", "admin", None, false, true)
+                .await,
+            Err(Error::Conflict(_))
+        ));
+        assert!(matches!(
+            storage
+                .update_user("admin", "root", None, false, true)
+                .await,
+            Err(Error::Conflict(_))
+        ));
+        // Nothing changed
+        assert!(storage.username_exists("admin").await.unwrap());
+        assert!(!storage.username_exists("root").await.unwrap());
+        assert_eq!(storage.count_admins().await.unwrap(), 1);
+
+        // With another admin either can go
+        storage
+            .update_user("alice", "alice", None, true, true)
+            .await
+            .unwrap();
+        storage
+            .update_user("admin", "root", None, false, true)
+            .await
+            .unwrap();
+        storage.delete_user("root").await.unwrap();
+        assert_eq!(storage.count_admins().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_users() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+        let storage = Storage::new(&database_url).await.unwrap();
+        storage
+            .create_user("guest", "password", false, false)
+            .await
+            .unwrap();
+
+        // Users can write unless created or made read-only
+        assert!(storage.can_write("admin").await.unwrap());
+        assert!(!storage.can_write("guest").await.unwrap());
+        assert!(!storage.can_write("nobody").await.unwrap());
+        let users = storage.list_users().await.unwrap();
+        let guest = users.iter().find(|u| u.username == "guest").unwrap();
+        assert!(!guest.can_write);
+
+        storage
+            .update_user("guest", "guest", None, false, true)
+            .await
+            .unwrap();
+        assert!(storage.can_write("guest").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_database_is_storage_unavailable() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let storage = Storage::new(&format!("sqlite://{}?mode=rwc", db_path.display()))
+            .await
+            .unwrap();
+        storage.check_writable().await.unwrap();
+
+        let read_only = Storage::new(&format!("sqlite://{}?mode=ro", db_path.display()))
+            .await
+            .unwrap();
+        assert!(!read_only.health().is_unavailable());
+
+        // Reads work, writes fail as StorageUnavailable (503) and mark it
+        assert!(!read_only.list_users().await.unwrap().is_empty());
+        let err = read_only
+            .transaction(|tx| Box::pin(Storage::add_tag_tx(tx, "t1", "a")))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::StorageUnavailable(_)), "{}", err);
+        assert_eq!(
+            err.into_response().status(),
+            axum::http::StatusCode::SERVICE_UNAVAILABLE
+        );
+        let since = read_only.health().unavailable_since().unwrap();
+        assert!(matches!(
+            read_only.check_writable().await,
+            Err(Error::StorageUnavailable(_))
+        ));
+        assert_eq!(read_only.health().unavailable_since(), Some(since));
+
+        // A write that succeeds clears it
+        read_only.health().record(&Ok(()));
+        assert!(!read_only.health().is_unavailable());
+    }
 }
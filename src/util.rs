@@ -1,7 +1,12 @@
 /// Utility functions shared across the codebase
 use crate::error::{Error, Result};
+use axum::extract::FromRequestParts;
+use axum::http::{header, request::Parts, HeaderMap, HeaderName};
 use serde::Deserialize;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 
 /// Calculate file signature (inode on Unix, CRC32 hash on Windows)
 /// Returns as String for Mango database compatibility
@@ -95,6 +100,133 @@ pub fn dir_signature(path: &Path) -> Result<String> {
     Ok((checksum as u64).to_string())
 }
 
+/// A library path relative to the library root, for user-facing output
+/// Absolute paths reveal where the library is mounted on the server, so paths
+/// outside the root are cut down to their file name rather than shown whole
+pub fn relative_to_root(path: &Path, root: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(relative) => relative.to_string_lossy().to_string(),
+        Err(_) => path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Check whether a path is itself a symlink (without following it)
+pub fn is_symlink(path: &Path) -> bool {
+    std::fs::symlink_metadata(path)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+/// Whether the filesystem holding `dir` tells names apart by case, found by looking up
+/// one of its entries with the case of its name swapped
+/// None when the directory cannot be read or no name in it has letters
+pub fn probe_case_sensitive(dir: &Path) -> Option<bool> {
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let swapped: String = name
+            .chars()
+            .flat_map(|c| {
+                if c.is_uppercase() {
+                    c.to_lowercase().collect::<Vec<_>>()
+                } else {
+                    c.to_uppercase().collect()
+                }
+            })
+            .collect();
+        if swapped == name {
+            continue;
+        }
+        let Ok(original) = std::fs::symlink_metadata(entry.path()) else {
+            continue;
+        };
+        // A different file under the swapped name is a case-sensitive filesystem too
+        return Some(match std::fs::symlink_metadata(dir.join(&swapped)) {
+            Ok(found) => !same_file(&original, &found),
+            Err(_) => true,
+        });
+    }
+    None
+}
+
+#[cfg(unix)]
+fn same_file(a: &std::fs::Metadata, b: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn same_file(a: &std::fs::Metadata, b: &std::fs::Metadata) -> bool {
+    a.len() == b.len() && a.modified().ok() == b.modified().ok()
+}
+
+/// Check that a symlink resolves inside one of the given roots
+/// Regular files always pass; broken links and links escaping every root are refused
+pub fn symlink_within_roots(path: &Path, roots: &[&Path]) -> bool {
+    if !is_symlink(path) {
+        return true;
+    }
+
+    let Ok(target) = std::fs::canonicalize(path) else {
+        return false;
+    };
+
+    roots.iter().any(|root| {
+        std::fs::canonicalize(root)
+            .map(|root| target.starts_with(root))
+            .unwrap_or(false)
+    })
+}
+
+/// Drop title directories that resolve to the same directory as another one
+/// (e.g. a symlink to a series that is also directly under the library root)
+/// The path that is not a symlink is preferred, otherwise the first one found.
+/// Kept paths stay as found under the library root (not the canonical target),
+/// so moving a link target does not orphan the stored IDs
+pub fn dedup_title_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut kept: Vec<(PathBuf, bool)> = Vec::with_capacity(paths.len());
+    let mut seen: HashMap<PathBuf, usize> = HashMap::with_capacity(paths.len());
+
+    for path in paths {
+        let canonical = match std::fs::canonicalize(&path) {
+            Ok(canonical) => canonical,
+            Err(e) => {
+                tracing::warn!("Failed to resolve title path {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let symlink = is_symlink(&path);
+
+        match seen.get(&canonical) {
+            Some(&idx) => {
+                let (existing, existing_symlink) = &kept[idx];
+                if *existing_symlink && !symlink {
+                    tracing::info!(
+                        "Skipping {}: same directory as {}",
+                        existing.display(),
+                        path.display()
+                    );
+                    kept[idx] = (path, symlink);
+                } else {
+                    tracing::info!(
+                        "Skipping {}: same directory as {}",
+                        path.display(),
+                        existing.display()
+                    );
+                }
+            }
+            None => {
+                seen.insert(canonical, kept.len());
+                kept.push((path, symlink));
+            }
+        }
+    }
+
+    kept.into_iter().map(|(path, _)| path).collect()
+}
+
 // ============================================================================
 // File Type Detection Constants
 // ============================================================================
@@ -124,6 +256,59 @@ fn is_supported_file(path: &Path) -> bool {
     }
 }
 
+/// Container format of an entry file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Rar,
+    SevenZip,
+    Unknown,
+}
+
+impl ArchiveKind {
+    /// Detect the format from the file's magic bytes, falling back to the extension
+    /// when the header is unreadable or unrecognized
+    /// Blocking: call from spawn_blocking in async code
+    pub fn detect(path: &Path) -> Self {
+        let mut magic = [0u8; 6];
+        let read = std::fs::File::open(path).and_then(|mut file| {
+            use std::io::Read;
+            file.read(&mut magic)
+        });
+
+        match (read, &magic) {
+            (Ok(6), [b'P', b'K', 3, 4, ..]) | (Ok(6), [b'P', b'K', 5, 6, ..]) => ArchiveKind::Zip,
+            (Ok(6), b"Rar!\x1a\x07") => ArchiveKind::Rar,
+            (Ok(6), [b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C]) => ArchiveKind::SevenZip,
+            _ => Self::from_extension(path),
+        }
+    }
+
+    /// Format implied by the file extension
+    pub fn from_extension(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+        match ext.as_deref() {
+            Some("zip") | Some("cbz") => ArchiveKind::Zip,
+            Some("rar") | Some("cbr") => ArchiveKind::Rar,
+            Some("7z") | Some("cb7") => ArchiveKind::SevenZip,
+            _ => ArchiveKind::Unknown,
+        }
+    }
+
+    /// MIME type for downloads and OPDS acquisition links
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ArchiveKind::Zip => "application/zip",
+            ArchiveKind::Rar => "application/x-rar-compressed",
+            ArchiveKind::SevenZip => "application/x-7z-compressed",
+            ArchiveKind::Unknown => "application/octet-stream",
+        }
+    }
+}
+
 /// Query parameters for sorting
 #[derive(Deserialize)]
 pub struct SortParams {
@@ -133,68 +318,343 @@ pub struct SortParams {
     pub ascend: Option<String>,
 }
 
-/// Navigation state for templates
-/// Tracks which page is currently active in the navigation menu
-/// and user permission level for conditional UI rendering
+/// Library filter by reading progress (`filter=unread|reading|completed`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressFilter {
+    /// Nothing read yet
+    Unread,
+    /// Started but not finished
+    Reading,
+    /// Fully read
+    Completed,
+}
+
+impl ProgressFilter {
+    /// Parse from a query parameter, None for anything unknown
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "unread" => Some(Self::Unread),
+            "reading" => Some(Self::Reading),
+            "completed" => Some(Self::Completed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unread => "unread",
+            Self::Reading => "reading",
+            Self::Completed => "completed",
+        }
+    }
+
+    /// Whether a title with the given weighted progress (0 - 100) passes
+    pub fn matches(self, progress: f32) -> bool {
+        match self {
+            Self::Unread => progress <= 0.0,
+            Self::Reading => progress > 0.0 && !is_completed(progress),
+            Self::Completed => is_completed(progress),
+        }
+    }
+}
+
+/// Whether a title's weighted progress (0 - 100) means it has been fully read
+pub fn is_completed(progress: f32) -> bool {
+    progress >= 100.0
+}
+
+/// Query parameters choosing the library's titles by reading progress
+#[derive(Debug, Default, Deserialize)]
+pub struct ProgressFilterParams {
+    /// unread, reading or completed; unknown values are ignored
+    pub filter: Option<String>,
+    /// 1 to show completed titles although the user hides them
+    #[serde(default)]
+    pub include_completed: u8,
+}
+
+impl ProgressFilterParams {
+    fn progress_filter(&self) -> Option<ProgressFilter> {
+        self.filter.as_deref().and_then(ProgressFilter::parse)
+    }
+
+    /// Keep the titles the parameters and the user's `hide_completed` preference let
+    /// through. An explicit filter decides on its own, so `filter=completed` shows
+    /// completed titles even when they are hidden; otherwise completed titles are left
+    /// out if hidden, unless `include_completed` is set
+    /// Returns how many completed titles were hidden by the preference
+    pub fn apply<T>(
+        &self,
+        titles: &mut Vec<T>,
+        hide_completed: bool,
+        progress: impl Fn(&T) -> f32,
+    ) -> usize {
+        if let Some(filter) = self.progress_filter() {
+            titles.retain(|t| filter.matches(progress(t)));
+            return 0;
+        }
+        if !hide_completed || self.include_completed != 0 {
+            return 0;
+        }
+        let before = titles.len();
+        titles.retain(|t| !is_completed(progress(t)));
+        before - titles.len()
+    }
+
+    /// Whether `apply` needs the titles' progress
+    pub fn needs_progress(&self, hide_completed: bool) -> bool {
+        self.progress_filter().is_some() || (hide_completed && self.include_completed == 0)
+    }
+
+    /// The parameters in effect as a query string for links, "" when there are none
+    pub fn query_string(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(filter) = self.progress_filter() {
+            params.push(format!("filter={}", filter.as_str()));
+        }
+        if self.include_completed != 0 {
+            params.push("include_completed=1".to_string());
+        }
+        params.join("&")
+    }
+}
+
+/// Move a user's pinned titles to the front in the order they were pinned, keeping the
+/// order of the rest; applied after sorting and filtering, so pins count toward page 1
+/// Returns how many titles are pinned
+pub fn pinned_first<T>(titles: &mut Vec<T>, pins: &[String], id: impl Fn(&T) -> &str) -> usize {
+    if pins.is_empty() {
+        return 0;
+    }
+    let pin_index = |title: &T| pins.iter().position(|pin| pin == id(title));
+
+    let (mut pinned, rest): (Vec<T>, Vec<T>) = std::mem::take(titles)
+        .into_iter()
+        .partition(|title| pin_index(title).is_some());
+    pinned.sort_by_key(|title| pin_index(title));
+    let count = pinned.len();
+
+    pinned.extend(rest);
+    *titles = pinned;
+    count
+}
+
+/// Top-level pages shown in the navigation bar
+/// Every match below is exhaustive, so adding a page fails to compile until
+/// its label, URL and visibility are defined (the nav partial renders from these)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum NavPage {
+    Home,
+    Library,
+    Tags,
+    Admin,
+}
+
+impl NavPage {
+    /// Every page, in navigation bar order
+    pub const ALL: [NavPage; 4] = [
+        NavPage::Home,
+        NavPage::Library,
+        NavPage::Tags,
+        NavPage::Admin,
+    ];
+
+    /// Link text in the navigation bar
+    pub fn label(&self) -> &'static str {
+        match self {
+            NavPage::Home => "Home",
+            NavPage::Library => "Library",
+            NavPage::Tags => "Tags",
+            NavPage::Admin => "Admin",
+        }
+    }
+
+    /// Link target in the navigation bar
+    pub fn url(&self) -> String {
+        match self {
+            NavPage::Home => crate::routes::paths::home(),
+            NavPage::Library => crate::routes::paths::library(),
+            NavPage::Tags => crate::routes::paths::tags(),
+            NavPage::Admin => crate::routes::paths::admin(),
+        }
+    }
+
+    /// Whether only admins see this page in the navigation bar
+    pub fn admin_only(&self) -> bool {
+        match self {
+            NavPage::Home | NavPage::Library | NavPage::Tags => false,
+            NavPage::Admin => true,
+        }
+    }
+}
+
+/// Instance branding from config (set once at startup, see `set_branding`)
+static BRANDING: OnceLock<Branding> = OnceLock::new();
+
+/// Instance branding shown in page titles, the nav bar and the login page
 #[derive(Debug, Clone, serde::Serialize)]
-pub struct NavigationState {
-    pub home_active: bool,
-    pub library_active: bool,
-    pub tags_active: bool,
-    pub admin_active: bool,
-    pub is_admin: bool,
+pub struct Branding {
+    pub instance_name: String,
+    /// Hex CSS color, None keeps the default theme
+    pub accent_color: Option<String>,
+    /// URL of the custom logo, None uses the bundled icon
+    pub logo_url: Option<String>,
 }
 
-impl NavigationState {
-    /// Create navigation state with home page active
-    pub fn home() -> Self {
+impl Default for Branding {
+    fn default() -> Self {
         Self {
-            home_active: true,
-            library_active: false,
-            tags_active: false,
-            admin_active: false,
-            is_admin: false,
+            instance_name: crate::config::DEFAULT_INSTANCE_NAME.to_string(),
+            accent_color: None,
+            logo_url: None,
         }
     }
+}
 
-    /// Create navigation state with library page active
-    pub fn library() -> Self {
+impl Branding {
+    /// Build branding from config
+    pub fn from_config(config: &crate::Config) -> Self {
         Self {
-            home_active: false,
-            library_active: true,
-            tags_active: false,
-            admin_active: false,
-            is_admin: false,
+            instance_name: config.instance_name.clone(),
+            accent_color: config.accent_color.clone(),
+            logo_url: config
+                .logo_path
+                .as_ref()
+                .map(|_| crate::routes::paths::branding_logo()),
         }
     }
 
-    /// Create navigation state with tags page active
-    pub fn tags() -> Self {
+    /// Branding configured at startup (defaults if never set)
+    pub fn current() -> Self {
+        BRANDING.get().cloned().unwrap_or_default()
+    }
+}
+
+/// Set the instance branding used by all templates (called once at startup)
+pub fn set_branding(config: &crate::Config) {
+    if BRANDING.set(Branding::from_config(config)).is_err() {
+        tracing::warn!("Branding already set, ignoring {}", config.instance_name);
+    }
+}
+
+tokio::task_local! {
+    /// Whether storage stopped accepting writes, for the request being handled
+    /// Set by `server::track_storage_health`; false outside a request
+    pub static STORAGE_UNAVAILABLE: bool;
+
+    /// Whether the request acts as `default_username` without a login (config
+    /// `disable_login`). Set by `auth::require_auth`; false outside a request
+    pub static LOGIN_DISABLED: bool;
+}
+
+/// Context shared by every page rendered with base.html
+/// Built from the authenticated user, so templates never carry their own nav flags
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BaseContext {
+    /// Page highlighted in the navigation bar
+    pub page: NavPage,
+    pub username: String,
+    pub is_admin: bool,
+    pub branding: Branding,
+    /// Show the banner saying changes cannot be saved (see `storage::StorageHealth`)
+    pub storage_unavailable: bool,
+    /// Prefix of the URLs written into templates and scripts (see `paths::base_url`)
+    pub base_url: String,
+    /// Nobody logs in (config `disable_login`), so there is no logout link
+    pub login_disabled: bool,
+}
+
+impl BaseContext {
+    /// Create the base context for a page viewed by the given user
+    pub fn new(user: &crate::auth::User, page: NavPage) -> Self {
         Self {
-            home_active: false,
-            library_active: false,
-            tags_active: true,
-            admin_active: false,
-            is_admin: false,
+            page,
+            username: user.username.clone(),
+            is_admin: user.is_admin,
+            branding: Branding::current(),
+            storage_unavailable: STORAGE_UNAVAILABLE.try_with(|v| *v).unwrap_or(false),
+            base_url: crate::routes::paths::base_url(),
+            login_disabled: LOGIN_DISABLED.try_with(|v| *v).unwrap_or(false),
         }
     }
 
-    /// Create navigation state with admin page active
-    pub fn admin() -> Self {
+    /// Pages shown in the navigation bar for this user
+    pub fn nav_items(&self) -> Vec<NavPage> {
+        NavPage::ALL
+            .into_iter()
+            .filter(|page| self.is_admin || !page.admin_only())
+            .collect()
+    }
+
+    /// Whether a page is the one currently shown
+    pub fn is_active(&self, page: &NavPage) -> bool {
+        self.page == *page
+    }
+}
+
+/// Value of a cookie from the request headers
+pub fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| {
+            let (key, value) = pair.trim().split_once('=')?;
+            (key == name).then_some(value)
+        })
+}
+
+/// Cover width requested for card grids when the client asks to save data
+pub const SAVE_DATA_COVER_WIDTH: u32 = 100;
+
+/// Cookie that turns data saving on regardless of the Save-Data header
+pub const SAVE_DATA_COOKIE: &str = "save_data";
+
+/// Client hints sent with a request (`Save-Data: on`, or the save_data cookie)
+/// Everything is off when they are absent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClientHints {
+    pub save_data: bool,
+}
+
+impl ClientHints {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header_on = headers
+            .get("save-data")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("on"));
+        let cookie_on = cookie(headers, SAVE_DATA_COOKIE) == Some("on");
+
         Self {
-            home_active: false,
-            library_active: false,
-            tags_active: false,
-            admin_active: true,
-            is_admin: false,
+            save_data: header_on || cookie_on,
         }
     }
 
-    /// Builder method to set admin permission status
-    /// Use this to indicate whether the current user has admin privileges
-    pub fn with_admin(mut self, is_admin: bool) -> Self {
-        self.is_admin = is_admin;
-        self
+    /// Width hint to pass to the cover endpoint, None for full-size covers
+    pub fn cover_width(&self) -> Option<u32> {
+        self.save_data.then_some(SAVE_DATA_COVER_WIDTH)
+    }
+
+    /// Headers for responses whose content depends on the hints, so caches keep
+    /// the variants apart
+    pub fn vary() -> [(HeaderName, &'static str); 1] {
+        [(header::VARY, "Save-Data")]
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for ClientHints
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
     }
 }
 
@@ -204,6 +664,81 @@ pub fn render_error<E: std::fmt::Display>(e: E) -> Error {
     Error::Internal(format!("Template render error: {}", e))
 }
 
+/// Guess MIME type from image data magic bytes
+pub fn guess_mime_type(data: &[u8]) -> &'static str {
+    match data {
+        [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+        [0x89, 0x50, 0x4E, 0x47, ..] => "image/png",
+        [0x47, 0x49, 0x46, 0x38, ..] => "image/gif",
+        // RIFF container is shared with WAV/AVI, so check the WEBP form type too
+        [0x52, 0x49, 0x46, 0x46, _, _, _, _, 0x57, 0x45, 0x42, 0x50, ..] => "image/webp",
+        [0x42, 0x4D, ..] => "image/bmp",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Get image dimensions from raw image data
+pub fn get_image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    // Try to use image crate to get dimensions without full decode
+    use std::io::Cursor;
+
+    let reader = image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?;
+
+    let dims = reader.into_dimensions().ok()?;
+    Some(dims)
+}
+
+/// Check that page data is a known image format whose header decodes
+/// Catches zero-byte, truncated-header and non-image archive members
+pub fn is_decodable_image(data: &[u8]) -> bool {
+    guess_mime_type(data) != "application/octet-stream" && get_image_dimensions(data).is_some()
+}
+
+/// Longest alias accepted for a title
+pub const MAX_ALIAS_CHARS: usize = 200;
+
+/// Normalize title aliases: trim, collapse runs of whitespace and drop blank lines
+/// Rejects aliases that are too long or repeat another alias of the title (ignoring case)
+pub fn normalize_aliases(aliases: &[String]) -> Result<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::with_capacity(aliases.len());
+    for alias in aliases {
+        let alias = alias.split_whitespace().collect::<Vec<_>>().join(" ");
+        if alias.is_empty() {
+            continue;
+        }
+        if alias.chars().count() > MAX_ALIAS_CHARS {
+            return Err(Error::BadRequest(format!(
+                "Alias is longer than {} characters",
+                MAX_ALIAS_CHARS
+            )));
+        }
+        if normalized
+            .iter()
+            .any(|existing| existing.to_lowercase() == alias.to_lowercase())
+        {
+            return Err(Error::BadRequest(format!("Duplicate alias: {}", alias)));
+        }
+        normalized.push(alias);
+    }
+    Ok(normalized)
+}
+
+/// Match a search query against a title name and its aliases (case-insensitive substring)
+/// Returns None when nothing matches, Some(None) for a name match and Some(Some(alias))
+/// when only an alias matches
+pub fn match_title<'a>(query: &str, name: &str, aliases: &'a [String]) -> Option<Option<&'a str>> {
+    let query = query.to_lowercase();
+    if name.to_lowercase().contains(&query) {
+        return Some(None);
+    }
+    aliases
+        .iter()
+        .find(|alias| alias.to_lowercase().contains(&query))
+        .map(|alias| Some(alias.as_str()))
+}
+
 /// Get sort preferences for a user from info.json
 /// If query params are provided, saves them and returns them
 /// Otherwise, returns saved preferences or defaults
@@ -242,67 +777,633 @@ pub async fn get_and_save_sort(
     }
 }
 
+/// Exponential backoff for background tasks that keep failing, e.g. while storage is
+/// read-only or full: they retry less and less often instead of hammering it
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    failures: u32,
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            failures: 0,
+        }
+    }
+
+    /// Delay to wait after another failure: base, twice that, four times... up to max
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self
+            .base
+            .saturating_mul(1 << self.failures.min(16))
+            .min(self.max);
+        self.failures = self.failures.saturating_add(1);
+        delay
+    }
+
+    /// Start over after a success
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+}
+
+/// Timestamp helpers
+/// Timestamps are stored and exposed as UTC unix seconds (i64) everywhere; formatting for
+/// display goes through format_ts so a timezone preference can later hook in one place
+pub mod time {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    /// Process-wide reference point pairing a monotonic Instant with wall-clock time
+    static EPOCH: OnceLock<(Instant, i64)> = OnceLock::new();
+
+    /// Current UTC unix timestamp in seconds
+    pub fn now_ts() -> i64 {
+        chrono::Utc::now().timestamp()
+    }
+
+    /// Convert a monotonic Instant to a UTC unix timestamp
+    /// Uses an epoch recorded on first use, so results are consistent within the process
+    pub fn instant_to_ts(instant: Instant) -> i64 {
+        let (epoch_instant, epoch_ts) = *EPOCH.get_or_init(|| (Instant::now(), now_ts()));
+        instant_to_ts_from(instant, epoch_instant, epoch_ts)
+    }
+
+    /// Convert an Instant to a unix timestamp relative to a known (Instant, timestamp) pair
+    pub(super) fn instant_to_ts_from(
+        instant: Instant,
+        epoch_instant: Instant,
+        epoch_ts: i64,
+    ) -> i64 {
+        if instant >= epoch_instant {
+            epoch_ts + instant.duration_since(epoch_instant).as_secs() as i64
+        } else {
+            epoch_ts - epoch_instant.duration_since(instant).as_secs() as i64
+        }
+    }
+
+    /// Format a UTC unix timestamp for display (e.g. "2024-01-31 12:00:00 UTC")
+    pub fn format_ts(ts: i64) -> String {
+        match chrono::DateTime::from_timestamp(ts, 0) {
+            Some(datetime) => datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            None => "Unknown".to_string(),
+        }
+    }
+
+    /// UTC day of a unix timestamp as YYYY-MM-DD; out of range timestamps fall back to
+    /// the epoch
+    pub fn format_date(ts: i64) -> String {
+        chrono::DateTime::from_timestamp(ts, 0)
+            .unwrap_or_default()
+            .format("%Y-%m-%d")
+            .to_string()
+    }
+
+    /// How long before `now` a UTC unix timestamp was, in its largest whole unit
+    /// (e.g. "3 days ago"); anything under a minute, or in the future, is "just now"
+    pub fn format_relative(ts: i64, now: i64) -> String {
+        const UNITS: [(i64, &str); 6] = [
+            (365 * 24 * 3600, "year"),
+            (30 * 24 * 3600, "month"),
+            (7 * 24 * 3600, "week"),
+            (24 * 3600, "day"),
+            (3600, "hour"),
+            (60, "minute"),
+        ];
+        let elapsed = now - ts;
+        for (seconds, unit) in UNITS {
+            let count = elapsed / seconds;
+            if count >= 1 {
+                let plural = if count == 1 { "" } else { "s" };
+                return format!("{} {}{} ago", count, unit, plural);
+            }
+        }
+        "just now".to_string()
+    }
+
+    /// Format a UTC unix timestamp as RFC 3339, for Atom feeds (e.g. "2024-01-31T12:00:00Z")
+    /// Out of range timestamps fall back to the epoch
+    pub fn format_rfc3339(ts: i64) -> String {
+        chrono::DateTime::from_timestamp(ts, 0)
+            .unwrap_or_default()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    }
+
+    /// Serde helpers for optional timestamps (`#[serde(with = "crate::util::time::opt_ts")]`)
+    /// Serializes as unix seconds or null; deserializes unix seconds, null, or an RFC 3339
+    /// string (the format original Mango writes to info.json)
+    pub mod opt_ts {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            ts: &Option<i64>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match ts {
+                Some(ts) => serializer.serialize_i64(*ts),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<i64>, D::Error> {
+            #[derive(Deserialize)]
+            #[serde(untagged)]
+            enum Raw {
+                Seconds(i64),
+                Rfc3339(String),
+            }
+
+            match Option::<Raw>::deserialize(deserializer)? {
+                None => Ok(None),
+                Some(Raw::Seconds(ts)) => Ok(Some(ts)),
+                Some(Raw::Rfc3339(s)) => chrono::DateTime::parse_from_rfc3339(&s)
+                    .map(|dt| Some(dt.timestamp()))
+                    .map_err(serde::de::Error::custom),
+            }
+        }
+    }
+
+    /// Serializer for a monotonic Instant as a UTC unix timestamp
+    /// (`#[serde(serialize_with = "crate::util::time::serialize_instant")]`)
+    pub fn serialize_instant<S: Serializer>(
+        instant: &Instant,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(instant_to_ts(*instant))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn user(is_admin: bool) -> crate::auth::User {
+        crate::auth::User {
+            username: "alice".to_string(),
+            is_admin,
+            can_write: true,
+        }
+    }
+
+    #[test]
+    fn test_relative_to_root() {
+        let root = Path::new("/mnt/nas/manga");
+        assert_eq!(
+            relative_to_root(Path::new("/mnt/nas/manga/Series/01.cbz"), root),
+            "Series/01.cbz"
+        );
+        assert_eq!(relative_to_root(root, root), "");
+        assert_eq!(
+            relative_to_root(Path::new("/elsewhere/02.cbz"), root),
+            "02.cbz"
+        );
+    }
+
+    #[test]
+    fn test_archive_kind_detect() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+
+        // A zip renamed to .cbr is still a zip
+        let renamed = temp_dir.path().join("renamed.cbr");
+        std::fs::write(&renamed, b"PK\x03\x04rest of the archive").unwrap();
+        assert_eq!(ArchiveKind::detect(&renamed), ArchiveKind::Zip);
+        assert_eq!(ArchiveKind::detect(&renamed).mime_type(), "application/zip");
+
+        let rar = temp_dir.path().join("book.cbz");
+        std::fs::write(&rar, b"Rar!\x1a\x07\x01\x00").unwrap();
+        assert_eq!(ArchiveKind::detect(&rar), ArchiveKind::Rar);
+
+        let seven = temp_dir.path().join("book.bin");
+        std::fs::write(&seven, [b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C, 0, 4]).unwrap();
+        assert_eq!(ArchiveKind::detect(&seven), ArchiveKind::SevenZip);
+
+        // Unrecognized or missing files fall back to the extension
+        let missing = temp_dir.path().join("missing.cbr");
+        assert_eq!(ArchiveKind::detect(&missing), ArchiveKind::Rar);
+        let unknown = temp_dir.path().join("notes.txt");
+        std::fs::write(&unknown, b"plain text").unwrap();
+        assert_eq!(
+            ArchiveKind::detect(&unknown).mime_type(),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn test_normalize_aliases() {
+        let aliases = vec![
+            "  Shingeki   no\tKyojin ".to_string(),
+            "".to_string(),
+            "Attack on Titan".to_string(),
+        ];
+        assert_eq!(
+            normalize_aliases(&aliases).unwrap(),
+            vec!["Shingeki no Kyojin", "Attack on Titan"]
+        );
+
+        let duplicate = vec![
+            "Attack on Titan".to_string(),
+            "attack  on titan".to_string(),
+        ];
+        assert!(matches!(
+            normalize_aliases(&duplicate),
+            Err(Error::BadRequest(_))
+        ));
+
+        let long = vec!["x".repeat(MAX_ALIAS_CHARS + 1)];
+        assert!(normalize_aliases(&long).is_err());
+    }
+
+    #[test]
+    fn test_match_title() {
+        let aliases = vec![
+            "Shingeki no Kyojin".to_string(),
+            "Attack on Titan".to_string(),
+        ];
+        assert_eq!(
+            match_title("kyojin", "進撃の巨人", &aliases),
+            Some(Some("Shingeki no Kyojin"))
+        );
+        assert_eq!(
+            match_title("TITAN", "進撃の巨人", &aliases),
+            Some(Some("Attack on Titan"))
+        );
+        assert_eq!(match_title("進撃", "進撃の巨人", &aliases), Some(None));
+        assert_eq!(match_title("one piece", "進撃の巨人", &aliases), None);
+    }
+
+    #[test]
+    fn test_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            "id=abc; reader_direction=rtl; theme=dark".parse().unwrap(),
+        );
+        assert_eq!(cookie(&headers, "reader_direction"), Some("rtl"));
+        assert_eq!(cookie(&headers, "missing"), None);
+        assert_eq!(cookie(&HeaderMap::new(), "id"), None);
+    }
+
+    #[test]
+    fn test_client_hints_default_off() {
+        let hints = ClientHints::from_headers(&HeaderMap::new());
+        assert!(!hints.save_data);
+        assert_eq!(hints.cover_width(), None);
+    }
+
+    #[test]
+    fn test_client_hints_save_data() {
+        let mut headers = HeaderMap::new();
+        headers.insert("save-data", "on".parse().unwrap());
+        let hints = ClientHints::from_headers(&headers);
+        assert!(hints.save_data);
+        assert_eq!(hints.cover_width(), Some(SAVE_DATA_COVER_WIDTH));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("save-data", "off".parse().unwrap());
+        assert!(!ClientHints::from_headers(&headers).save_data);
+
+        // Forced by the user's cookie
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, "save_data=on".parse().unwrap());
+        assert!(ClientHints::from_headers(&headers).save_data);
+    }
+
     #[test]
-    fn test_navigation_state_home() {
-        let nav = NavigationState::home();
-        assert!(nav.home_active);
-        assert!(!nav.library_active);
-        assert!(!nav.tags_active);
-        assert!(!nav.admin_active);
-        assert!(!nav.is_admin);
+    fn test_base_context_active_page() {
+        let base = BaseContext::new(&user(false), NavPage::Library);
+        assert_eq!(base.username, "alice");
+        assert!(base.is_active(&NavPage::Library));
+        assert!(!base.is_active(&NavPage::Home));
+        assert!(!base.is_active(&NavPage::Tags));
+        assert!(!base.is_active(&NavPage::Admin));
     }
 
     #[test]
-    fn test_navigation_state_library() {
-        let nav = NavigationState::library();
-        assert!(!nav.home_active);
-        assert!(nav.library_active);
-        assert!(!nav.tags_active);
-        assert!(!nav.admin_active);
-        assert!(!nav.is_admin);
+    fn test_base_context_admin_items() {
+        let regular = BaseContext::new(&user(false), NavPage::Home);
+        assert!(!regular.is_admin);
+        assert_eq!(
+            regular.nav_items(),
+            vec![NavPage::Home, NavPage::Library, NavPage::Tags]
+        );
+
+        let admin = BaseContext::new(&user(true), NavPage::Admin);
+        assert!(admin.is_admin);
+        assert_eq!(admin.nav_items(), NavPage::ALL.to_vec());
+    }
+
+    #[test]
+    fn test_nav_page_all_lists_every_variant() {
+        // Exhaustive: a new variant fails to compile here until it is added to ALL
+        let listed = |page: NavPage| match page {
+            NavPage::Home | NavPage::Library | NavPage::Tags | NavPage::Admin => {
+                NavPage::ALL.contains(&page)
+            }
+        };
+        assert!(listed(NavPage::Home));
+        assert!(listed(NavPage::Library));
+        assert!(listed(NavPage::Tags));
+        assert!(listed(NavPage::Admin));
+    }
+
+    #[test]
+    fn test_guess_mime_type_jpeg() {
+        assert_eq!(
+            guess_mime_type(&[0xFF, 0xD8, 0xFF, 0xE0, 0x00]),
+            "image/jpeg"
+        );
+    }
+
+    #[test]
+    fn test_guess_mime_type_png() {
+        let data = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        assert_eq!(guess_mime_type(&data), "image/png");
+    }
+
+    #[test]
+    fn test_guess_mime_type_gif() {
+        assert_eq!(guess_mime_type(b"GIF89a"), "image/gif");
+        assert_eq!(guess_mime_type(b"GIF87a"), "image/gif");
+    }
+
+    #[test]
+    fn test_guess_mime_type_webp() {
+        assert_eq!(
+            guess_mime_type(b"RIFF\x24\x00\x00\x00WEBPVP8 "),
+            "image/webp"
+        );
+    }
+
+    #[test]
+    fn test_guess_mime_type_riff_not_webp() {
+        // WAV and AVI share the RIFF header but are not images
+        assert_eq!(
+            guess_mime_type(b"RIFF\x24\x00\x00\x00WAVEfmt "),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            guess_mime_type(b"RIFF\x24\x00\x00\x00AVI LIST"),
+            "application/octet-stream"
+        );
+        // Truncated RIFF header
+        assert_eq!(guess_mime_type(b"RIFF"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_guess_mime_type_bmp() {
+        assert_eq!(guess_mime_type(b"BM\x36\x00"), "image/bmp");
+    }
+
+    #[test]
+    fn test_guess_mime_type_unknown() {
+        assert_eq!(guess_mime_type(&[]), "application/octet-stream");
+        assert_eq!(guess_mime_type(&[0xFF]), "application/octet-stream");
+        assert_eq!(guess_mime_type(b"%PDF-1.7"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_is_decodable_image() {
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgb8(image::RgbImage::new(2, 3))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        assert!(is_decodable_image(&png));
+
+        // Zero-byte and header-only pages
+        assert!(!is_decodable_image(&[]));
+        assert!(!is_decodable_image(&png[..8]));
+        // Not an image at all
+        assert!(!is_decodable_image(b"%PDF-1.7"));
+    }
+
+    #[test]
+    fn test_instant_to_ts() {
+        use std::time::{Duration, Instant};
+
+        let epoch_instant = Instant::now();
+        let later = epoch_instant + Duration::from_secs(90);
+        assert_eq!(
+            time::instant_to_ts_from(epoch_instant, epoch_instant, 1_000),
+            1_000
+        );
+        assert_eq!(time::instant_to_ts_from(later, epoch_instant, 1_000), 1_090);
+        assert_eq!(time::instant_to_ts_from(epoch_instant, later, 1_000), 910);
+
+        // The process epoch tracks wall-clock time
+        let now = time::now_ts();
+        assert!((time::instant_to_ts(Instant::now()) - now).abs() <= 1);
+    }
+
+    #[test]
+    fn test_format_ts() {
+        assert_eq!(time::format_ts(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(time::format_ts(1_706_702_400), "2024-01-31 12:00:00 UTC");
+        assert_eq!(time::format_ts(i64::MAX), "Unknown");
+        assert_eq!(time::format_rfc3339(1_706_702_400), "2024-01-31T12:00:00Z");
+        assert_eq!(time::format_rfc3339(i64::MAX), "1970-01-01T00:00:00Z");
+        assert_eq!(time::format_date(1_706_745_599), "2024-01-31");
+        assert_eq!(time::format_date(1_706_745_600), "2024-02-01");
     }
 
     #[test]
-    fn test_navigation_state_tags() {
-        let nav = NavigationState::tags();
-        assert!(!nav.home_active);
-        assert!(!nav.library_active);
-        assert!(nav.tags_active);
-        assert!(!nav.admin_active);
-        assert!(!nav.is_admin);
+    fn test_format_relative() {
+        let now = 1_706_702_400;
+        assert_eq!(time::format_relative(now - 30, now), "just now");
+        assert_eq!(time::format_relative(now + 600, now), "just now");
+        assert_eq!(time::format_relative(now - 60, now), "1 minute ago");
+        assert_eq!(
+            time::format_relative(now - 3 * 3600 - 5, now),
+            "3 hours ago"
+        );
+        assert_eq!(
+            time::format_relative(now - 8 * 24 * 3600, now),
+            "1 week ago"
+        );
+        assert_eq!(
+            time::format_relative(now - 400 * 24 * 3600, now),
+            "1 year ago"
+        );
+    }
+
+    #[test]
+    fn test_opt_ts_round_trip() {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Item {
+            #[serde(with = "time::opt_ts")]
+            at: Option<i64>,
+        }
+
+        for item in [
+            Item {
+                at: Some(1_706_702_400),
+            },
+            Item { at: None },
+        ] {
+            let json = serde_json::to_string(&item).unwrap();
+            assert_eq!(serde_json::from_str::<Item>(&json).unwrap(), item);
+        }
+        assert_eq!(
+            serde_json::to_string(&Item { at: None }).unwrap(),
+            r#"{"at":null}"#
+        );
+
+        // RFC 3339 strings (original Mango format) are accepted on input
+        let item: Item = serde_json::from_str(r#"{"at":"2024-01-31T12:00:00+00:00"}"#).unwrap();
+        assert_eq!(item.at, Some(1_706_702_400));
+        assert!(serde_json::from_str::<Item>(r#"{"at":"yesterday"}"#).is_err());
     }
 
     #[test]
-    fn test_navigation_state_admin() {
-        let nav = NavigationState::admin();
-        assert!(!nav.home_active);
-        assert!(!nav.library_active);
-        assert!(!nav.tags_active);
-        assert!(nav.admin_active);
-        assert!(!nav.is_admin);
+    fn test_serialize_instant() {
+        #[derive(serde::Serialize)]
+        struct Item {
+            #[serde(serialize_with = "time::serialize_instant")]
+            at: std::time::Instant,
+        }
+
+        let json = serde_json::to_value(Item {
+            at: std::time::Instant::now(),
+        })
+        .unwrap();
+        assert!((json["at"].as_i64().unwrap() - time::now_ts()).abs() <= 1);
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_navigation_state_with_admin() {
-        let nav = NavigationState::library().with_admin(true);
-        assert!(!nav.home_active);
-        assert!(nav.library_active);
-        assert!(!nav.tags_active);
-        assert!(!nav.admin_active);
-        assert!(nav.is_admin);
+    fn test_dedup_title_paths() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().join("library");
+        let outside = temp_dir.path().join("other-disk");
+        std::fs::create_dir_all(root.join("Series")).unwrap();
+        std::fs::create_dir_all(outside.join("Linked")).unwrap();
+
+        // A link to a series that is also directly under the root, and one from another disk
+        symlink(root.join("Series"), root.join("Series Link")).unwrap();
+        symlink(outside.join("Linked"), root.join("Linked")).unwrap();
+
+        let paths = dedup_title_paths(vec![
+            root.join("Series Link"),
+            root.join("Linked"),
+            root.join("Series"),
+        ]);
+
+        // The real directory wins over the link; paths stay as found under the root
+        assert_eq!(paths, vec![root.join("Series"), root.join("Linked")]);
+    }
+
+    #[test]
+    fn test_probe_case_sensitive() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        assert_eq!(probe_case_sensitive(dir), None);
+        assert_eq!(probe_case_sensitive(&dir.join("missing")), None);
+
+        // Names without letters tell nothing
+        std::fs::create_dir(dir.join("123")).unwrap();
+        assert_eq!(probe_case_sensitive(dir), None);
+
+        std::fs::create_dir(dir.join("Series")).unwrap();
+        let case_sensitive = !dir.join("sERIES").exists();
+        assert_eq!(probe_case_sensitive(dir), Some(case_sensitive));
+
+        // A second directory under the swapped name only exists on case-sensitive ones
+        if case_sensitive {
+            std::fs::create_dir(dir.join("sERIES")).unwrap();
+            assert_eq!(probe_case_sensitive(dir), Some(true));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_within_roots() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().join("library");
+        let title = root.join("Series");
+        let outside = temp_dir.path().join("outside");
+        std::fs::create_dir_all(&title).unwrap();
+        std::fs::create_dir_all(root.join("Other")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(title.join("real.zip"), b"").unwrap();
+        std::fs::write(root.join("Other/shared.zip"), b"").unwrap();
+        std::fs::write(outside.join("secret.zip"), b"").unwrap();
+
+        symlink(root.join("Other/shared.zip"), title.join("inside.zip")).unwrap();
+        symlink(outside.join("secret.zip"), title.join("escape.zip")).unwrap();
+        symlink(title.join("missing.zip"), title.join("broken.zip")).unwrap();
+
+        let roots = [root.as_path(), title.as_path()];
+        assert!(symlink_within_roots(&title.join("real.zip"), &roots));
+        assert!(symlink_within_roots(&title.join("inside.zip"), &roots));
+        assert!(!symlink_within_roots(&title.join("escape.zip"), &roots));
+        assert!(!symlink_within_roots(&title.join("broken.zip"), &roots));
+        assert!(is_symlink(&title.join("escape.zip")));
+        assert!(!is_symlink(&title.join("real.zip")));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new(Duration::from_secs(30), Duration::from_secs(300));
+        let delays: Vec<u64> = (0..6).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![30, 60, 120, 240, 300, 300]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_progress_filter_precedence() {
+        let progress = [0.0, 50.0, 100.0];
+        let params = |filter: Option<&str>, include_completed: u8| ProgressFilterParams {
+            filter: filter.map(str::to_string),
+            include_completed,
+        };
+        let apply = |params: ProgressFilterParams, hide: bool| {
+            let mut titles = progress.to_vec();
+            let hidden = params.apply(&mut titles, hide, |p| *p);
+            (titles, hidden)
+        };
+
+        assert_eq!(apply(params(None, 0), false), (vec![0.0, 50.0, 100.0], 0));
+        assert_eq!(apply(params(None, 0), true), (vec![0.0, 50.0], 1));
+        assert_eq!(apply(params(None, 1), true), (vec![0.0, 50.0, 100.0], 0));
+
+        // A filter decides alone, whatever the preference
+        assert_eq!(apply(params(Some("completed"), 0), true), (vec![100.0], 0));
+        assert_eq!(apply(params(Some("Reading"), 0), true), (vec![50.0], 0));
+        assert_eq!(apply(params(Some("unread"), 1), false), (vec![0.0], 0));
+        // Unknown filters are ignored
+        assert_eq!(apply(params(Some("later"), 0), true), (vec![0.0, 50.0], 1));
+
+        assert!(!params(None, 0).needs_progress(false));
+        assert!(!params(None, 1).needs_progress(true));
+        assert!(params(Some("unread"), 0).needs_progress(false));
+        assert_eq!(
+            params(Some("READING"), 1).query_string(),
+            "filter=reading&include_completed=1"
+        );
+        assert_eq!(params(Some("later"), 0).query_string(), "");
     }
 
     #[test]
-    fn test_navigation_state_builder_chain() {
-        // Test that builder pattern works
-        let nav_admin = NavigationState::home().with_admin(true);
-        assert!(nav_admin.is_admin);
+    fn test_pinned_first() {
+        let pins = vec!["d".to_string(), "b".to_string(), "gone".to_string()];
+        let mut titles = vec!["a", "b", "c", "d", "e"];
+        assert_eq!(pinned_first(&mut titles, &pins, |t| *t), 2);
+        assert_eq!(titles, ["d", "b", "a", "c", "e"]);
 
-        let nav_regular = NavigationState::home().with_admin(false);
-        assert!(!nav_regular.is_admin);
+        let mut titles = vec!["c", "a"];
+        assert_eq!(pinned_first(&mut titles, &pins, |t| *t), 0);
+        assert_eq!(titles, ["c", "a"]);
     }
 }
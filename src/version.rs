@@ -0,0 +1,58 @@
+// Version information for support requests
+//
+// The commit and build time are embedded by build.rs (commit "unknown" when built
+// outside a git checkout without MANGO_GIT_COMMIT). The schema version is read
+// from the database, so it also reflects migrations applied by a newer binary.
+
+use serde::Serialize;
+
+use crate::{error::Result, library::cache::CACHE_FORMAT_VERSION, Storage};
+
+/// Crate version
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Commit the binary was built from
+pub const GIT_COMMIT: &str = match option_env!("MANGO_GIT_COMMIT") {
+    Some(commit) => commit,
+    None => "unknown",
+};
+
+/// Build time as unix seconds
+const BUILD_TIMESTAMP: Option<&str> = option_env!("MANGO_BUILD_TIMESTAMP");
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionInfo {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    /// UTC build time, e.g. "2024-01-31 12:00:00 UTC"
+    pub build_date: String,
+    /// Highest applied database migration
+    pub schema_version: i64,
+    pub cache_format_version: u32,
+}
+
+impl VersionInfo {
+    pub async fn collect(storage: &Storage) -> Result<Self> {
+        Ok(Self {
+            version: VERSION,
+            git_commit: GIT_COMMIT,
+            build_date: BUILD_TIMESTAMP
+                .and_then(|ts| ts.parse().ok())
+                .map_or_else(|| "unknown".to_string(), crate::util::time::format_ts),
+            schema_version: storage.schema_version().await?,
+            cache_format_version: CACHE_FORMAT_VERSION,
+        })
+    }
+
+    /// One-line summary for the startup log
+    pub fn summary(&self) -> String {
+        format!(
+            "Mango-Rust v{} (commit {}, built {}), schema version {}, cache format {}",
+            self.version,
+            self.git_commit,
+            self.build_date,
+            self.schema_version,
+            self.cache_format_version
+        )
+    }
+}
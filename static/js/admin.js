@@ -5,6 +5,8 @@ const component = () => {
 		scanning: false,
 		scanTitles: 0,
 		scanMs: -1,
+		scanUnreadable: 0,
+		scanProgress: '',
 		themeSetting: '',
 
 		init() {
@@ -26,15 +28,41 @@ const component = () => {
 			this.scanning = true;
 			this.scanMs = -1;
 			this.scanTitles = 0;
+			this.scanUnreadable = 0;
+			this.scanProgress = '';
 			$.post(`${base_url}api/admin/scan`)
 				.then(data => {
-					this.scanMs = data.milliseconds;
-					this.scanTitles = data.titles;
+					this.pollScan(data.job_id);
 				})
 				.catch(e => {
 					alert('danger', `Failed to trigger a scan. Error: ${e}`);
+					this.scanning = false;
+				});
+		},
+		pollScan(jobId) {
+			$.get(`${base_url}api/admin/scan/status`)
+				.then(status => {
+					const job = status.job;
+					if (!job || job.job_id !== jobId) {
+						this.scanning = false;
+						return;
+					}
+					if (job.finished_at === null) {
+						this.scanProgress = `${job.titles_discovered} titles found, ${job.directories_remaining} directories left`;
+						setTimeout(() => this.pollScan(jobId), 1000);
+						return;
+					}
+					this.scanning = false;
+					if (job.error) {
+						alert('danger', `Scan failed. Error: ${job.error}`);
+						return;
+					}
+					this.scanMs = job.report.milliseconds;
+					this.scanTitles = job.report.titles;
+					this.scanUnreadable = job.report.unreadable.reduce((n, t) => n + t.files.length, 0);
 				})
-				.always(() => {
+				.catch(e => {
+					alert('danger', `Failed to get the scan status. Error: ${e}`);
 					this.scanning = false;
 				});
 		},
@@ -23,7 +23,7 @@ function cacheDebugData() {
             this.loading = true;
             this.message = '';
             try {
-                const response = await fetch('/api/cache/save-library', {
+                const response = await fetch(`${base_url}api/cache/save-library`, {
                     method: 'POST'
                 });
 
@@ -45,7 +45,7 @@ function cacheDebugData() {
             this.loading = true;
             this.message = '';
             try {
-                const response = await fetch('/api/cache/load-library', {
+                const response = await fetch(`${base_url}api/cache/load-library`, {
                     method: 'POST'
                 });
 
@@ -77,7 +77,7 @@ function cacheDebugData() {
             this.loading = true;
             this.message = '';
             try {
-                const response = await fetch('/api/cache/clear', {
+                const response = await fetch(`${base_url}api/cache/clear`, {
                     method: 'POST'
                 });
 
@@ -0,0 +1,43 @@
+/**
+ * Render the A-Z jump bar above the library grid
+ *
+ * Buckets come from the server in display order, each with the position of its
+ * first title among all titles. When the library is paged, a bucket starting on
+ * another page links there. Nothing is shown when the library is not sorted by
+ * name. Progress filters of the page (filter, include_completed) are passed on
+ * so the offsets match the titles shown.
+ *
+ * @function renderLetterIndex
+ */
+const renderLetterIndex = () => {
+	const grid = $('#library-grid');
+	const filterQuery = grid.attr('data-filter-query') || '';
+	const query = filterQuery ? `?${filterQuery}` : '';
+	$.get(`${base_url}api/library/index_letters${query}`)
+		.done((buckets) => {
+			if (buckets.length < 2) return;
+			const cards = grid.children();
+			const pageSize = parseInt(grid.attr('data-page-size')) || 0;
+			const page = parseInt(grid.attr('data-page')) || 1;
+			const bar = $('#letter-index');
+			buckets.forEach((bucket) => {
+				$('<a href="#" class="uk-margin-small-right"></a>')
+					.text(bucket.letter)
+					.attr('title', `${bucket.count} titles`)
+					.click((e) => {
+						e.preventDefault();
+						const target = pageSize > 0 ? Math.floor(bucket.offset / pageSize) + 1 : 1;
+						if (target !== page) {
+							location.href = `${base_url}library?page=${target}${filterQuery ? `&${filterQuery}` : ''}`;
+							return;
+						}
+						const card = cards.get(pageSize > 0 ? bucket.offset % pageSize : bucket.offset);
+						if (card) card.scrollIntoView({ behavior: 'smooth' });
+					})
+					.appendTo(bar);
+			});
+			bar.removeAttr('hidden');
+		});
+};
+
+$(() => renderLetterIndex());
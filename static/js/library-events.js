@@ -0,0 +1,30 @@
+/**
+ * Subscribe to library change events and offer a refresh when chapters arrive
+ *
+ * @function watchLibraryEvents
+ * @param {string|null} titleID - Only count new chapters of this title, or all
+ * 		titles when null
+ */
+const watchLibraryEvents = (titleID = null) => {
+	if (!window.EventSource) return;
+
+	const source = new EventSource(`${base_url}api/events`);
+	let pending = 0;
+
+	source.addEventListener('title_updated', (e) => {
+		const data = JSON.parse(e.data);
+		if (titleID && data.title_id !== titleID) return;
+		pending += data.new_entries;
+	});
+
+	source.addEventListener('scan_completed', () => {
+		if (pending === 0) return;
+		const noun = pending === 1 ? 'chapter' : 'chapters';
+		UIkit.notification.closeAll();
+		UIkit.notification({
+			message: `${pending} new ${noun} — <a href="#" onclick="location.reload(); return false;">refresh</a>`,
+			pos: 'bottom-right',
+			timeout: 0
+		});
+	});
+};
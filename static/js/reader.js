@@ -15,6 +15,10 @@ const readerComponent = () => {
 		preloadLookahead: 3,
 		enableRightToLeft: false,
 		fitType: 'vert',
+		pageThumbnails: null, // Page sprite metadata, fetched on first slider hover
+		pageThumbnailsRequested: false,
+		previewPage: 0, // 0: no preview; 1: the first page
+		previewX: 0,
 
 		/**
 		 * Initialize the component by fetching the page dimensions
@@ -29,9 +33,11 @@ const readerComponent = () => {
 					this.items = dimensions.map((d, i) => {
 						return {
 							id: i + 1,
-							url: `${base_url}api/page/${tid}/${eid}/${i+1}`,
+							url: `${base_url}api/page/${tid}/${eid}/${i+1}?v=${page_version}`,
 							width: d.width == 0 ? "100%" : d.width,
 							height: d.height == 0 ? "100%" : d.height,
+							bad: !!d.bad,
+							unreadable: false,
 						};
 					});
 
@@ -44,7 +50,8 @@ const readerComponent = () => {
 					console.log(avgRatio);
 					this.longPages = avgRatio > 2;
 					this.loading = false;
-					this.mode = localStorage.getItem('mode') || 'continuous';
+					const readingDirection = this.initialDirection();
+					this.mode = readingDirection === 'vertical' ? 'continuous' : 'paged';
 
 					// Here we save a copy of this.mode, and use the copy as
 					// 	the model-select value. This is because `updateMode`
@@ -74,12 +81,7 @@ const readerComponent = () => {
 					const savedFlipAnimation = localStorage.getItem('enableFlipAnimation');
 					this.enableFlipAnimation = savedFlipAnimation === null || savedFlipAnimation === 'true';
 
-					const savedRightToLeft = localStorage.getItem('enableRightToLeft');
-					if (savedRightToLeft === null) {
-						this.enableRightToLeft = false;
-					} else {
-						this.enableRightToLeft = (savedRightToLeft === 'true');
-					}
+					this.enableRightToLeft = readingDirection === 'rtl';
 				})
 				.catch(e => {
 					const errMsg = `Failed to get the page dimensions. ${e}`;
@@ -88,6 +90,63 @@ const readerComponent = () => {
 					this.msg = errMsg;
 				})
 		},
+		/**
+		 * The reading direction to open with. The server resolves it from the
+		 * 	entry/title overrides, the `reader_direction` cookie and the instance
+		 * 	default. Preferences saved by older versions in localStorage are
+		 * 	migrated to the cookie once.
+		 *
+		 * @return {string} `ltr`, `rtl` or `vertical`
+		 */
+		initialDirection() {
+			const hasCookie = document.cookie.split(';').some(c => c.trim().startsWith('reader_direction='));
+			const legacyMode = localStorage.getItem('mode');
+			if (hasCookie || legacyMode === null) return direction;
+
+			const legacy = legacyMode === 'continuous' ? 'vertical' :
+				(localStorage.getItem('enableRightToLeft') === 'true' ? 'rtl' : 'ltr');
+			this.saveDirection(legacy);
+			localStorage.removeItem('mode');
+			localStorage.removeItem('enableRightToLeft');
+			return direction_overridden ? direction : legacy;
+		},
+		/**
+		 * Saves the user's reading direction preference. It applies to every
+		 * 	entry without a direction override.
+		 *
+		 * @param {string} value - `ltr`, `rtl` or `vertical`
+		 */
+		saveDirection(value) {
+			document.cookie = `reader_direction=${value}; path=/; max-age=31536000; SameSite=Lax`;
+		},
+		/**
+		 * The reading direction picked in the settings modal
+		 *
+		 * @return {string} `ltr`, `rtl` or `vertical`
+		 */
+		currentDirection() {
+			if ($('#mode-select').val() === 'continuous') return 'vertical';
+			return this.enableRightToLeft ? 'rtl' : 'ltr';
+		},
+		/**
+		 * Handles a page image that failed to load. A page the server reports
+		 * 	as corrupted is marked bad, so flipping skips it from now on
+		 *
+		 * @param {object} item - The item of the page
+		 */
+		pageError(item) {
+			if (item.unreadable) return;
+			fetch(item.url, { cache: 'no-store' })
+				.then(resp => resp.json())
+				.then(data => {
+					if (data.code !== 'page_unreadable') return;
+					item.bad = true;
+					item.unreadable = true;
+				})
+				.catch(e => {
+					console.error(`Failed to load page ${item.id}`, e);
+				});
+		},
 		/**
 		 * Preload an image, which is expected to be cached
 		 */
@@ -101,6 +160,57 @@ const readerComponent = () => {
 			const p = parseInt($('#page-select').val());
 			this.toPage(p);
 		},
+		/**
+		 * Handles the `change` event for the page slider
+		 */
+		sliderChanged() {
+			const p = parseInt($('#page-slider').val());
+			this.toPage(p);
+		},
+		/**
+		 * Shows a preview of the page under the cursor on the page slider.
+		 * 	The page thumbnails are fetched the first time the slider is hovered
+		 *
+		 * @param {Event} event - The triggering mousemove event
+		 */
+		sliderHover(event) {
+			this.loadPageThumbnails();
+			const rect = event.currentTarget.getBoundingClientRect();
+			const ratio = Math.min(Math.max((event.clientX - rect.left) / rect.width, 0), 1);
+			this.previewPage = Math.round(ratio * (this.items.length - 1)) + 1;
+			this.previewX = event.clientX - rect.left;
+		},
+		/**
+		 * Fetches the page thumbnail metadata (frame offsets in the page sprite)
+		 */
+		loadPageThumbnails() {
+			if (this.pageThumbnailsRequested) return;
+			this.pageThumbnailsRequested = true;
+			$.get(page_thumbnails_url)
+				.then(data => {
+					if (!data.success) return;
+					this.pageThumbnails = data;
+				})
+				.catch(e => {
+					console.error('Failed to load page thumbnails', e);
+				});
+		},
+		/**
+		 * Inline style of the slider preview: the previewed page's frame
+		 * 	in the page sprite. Long entries only have a frame for every
+		 * 	`step`-th page, so pages in between show the closest earlier frame
+		 */
+		previewStyle() {
+			let style = `left: ${this.previewX}px;`;
+			const thumbs = this.pageThumbnails;
+			if (!thumbs || thumbs.frames.length === 0) return style;
+
+			const idx = Math.min(Math.floor((this.previewPage - 1) / thumbs.step), thumbs.frames.length - 1);
+			const frame = thumbs.frames[idx];
+			style += `width: ${frame.width}px; height: ${thumbs.height}px;`;
+			style += `background-image: url(${page_sprite_url}); background-position: -${frame.x}px 0;`;
+			return style;
+		},
 		/**
 		 * Handles the `change` event for the mode selector
 		 *
@@ -111,6 +221,7 @@ const readerComponent = () => {
 			const curIdx = parseInt($('#page-select').val());
 
 			this.updateMode(mode, curIdx, nextTick);
+			this.saveDirection(this.currentDirection());
 		},
 		/**
 		 * Handles the window `resize` event
@@ -141,7 +252,11 @@ const readerComponent = () => {
 		 */
 		flipPage(isNext) {
 			const idx = parseInt(this.curItem.id);
-			const newIdx = idx + (isNext ? 1 : -1);
+			let newIdx = idx + (isNext ? 1 : -1);
+
+			// Skip pages that failed validation on the server
+			while (newIdx > 0 && newIdx <= this.items.length && this.items[newIdx - 1].bad)
+				newIdx += isNext ? 1 : -1;
 
 			if (newIdx <= 0) return;
 			if (newIdx > this.items.length) {
@@ -205,12 +320,19 @@ const readerComponent = () => {
 		 * 		2) the average height/width ratio of the pages is over 2, or
 		 * 		3) the current page is the first page, or
 		 * 		4) the current page is the last page
+		 * 	and it is past the saved page the reader was opened behind (if any)
 		 *
 		 * @param {number} idx - One-based index of the page
 		 * @param {function} cb - Callback
 		 */
 		saveProgress(idx, cb) {
+			if (!can_write) {
+				if (cb) cb();
+				return;
+			}
 			idx = parseInt(idx);
+			// Opened behind the saved progress: don't roll it back unless asked to
+			if (idx <= saved_page) return;
 			if (Math.abs(idx - this.lastSavedPage) >= 5 ||
 				this.longPages ||
 				idx === 1 || idx === this.items.length
@@ -242,8 +364,6 @@ const readerComponent = () => {
 		 * @param {function} nextTick - Alpine $nextTick magic property
 		 */
 		updateMode(mode, targetPage, nextTick) {
-			localStorage.setItem('mode', mode);
-
 			// The mode to be put into the `mode` prop. It can't be `screen`
 			let propMode = mode;
 
@@ -355,7 +475,7 @@ const readerComponent = () => {
 		},
 
 		enableRightToLeftChanged() {
-			localStorage.setItem('enableRightToLeft', this.enableRightToLeft);
+			this.saveDirection(this.currentDirection());
 		},
 	};
 }
@@ -0,0 +1,49 @@
+/**
+ * Show the report modal for an entry
+ *
+ * @function showReportModal
+ * @param {string} tid - Title ID
+ * @param {string} eid - Entry ID
+ * @param {string} entryName - Entry name shown in the modal header
+ */
+const showReportModal = (tid, eid, entryName) => {
+	$('#report-entry-title').text(entryName || '');
+	$('#report-category').val('corrupt');
+	$('#report-note').val('');
+
+	$('#report-submit-btn').off('click').click(() => {
+		submitReport(tid, eid);
+	});
+
+	UIkit.modal($('#report-modal')).show();
+};
+
+const submitReport = (tid, eid) => {
+	$.ajax({
+			type: 'POST',
+			url: `${base_url}api/report/${tid}/${eid}`,
+			contentType: 'application/json',
+			dataType: 'json',
+			data: JSON.stringify({
+				category: $('#report-category').val(),
+				note: $('#report-note').val()
+			})
+		})
+		.done(data => {
+			UIkit.modal($('#report-modal')).hide();
+			if (data.success) {
+				alert('success', 'Thanks! The problem has been reported to the admins.');
+			} else {
+				alert('danger', `Failed to send report. Error: ${data.error}`);
+			}
+		})
+		.fail((jqXHR, status) => {
+			UIkit.modal($('#report-modal')).hide();
+			alert('danger', `Failed to send report. Error: [${jqXHR.status}] ${jqXHR.responseText || jqXHR.statusText}`);
+		});
+};
+
+$(() => {
+	// Keep keystrokes in the note field away from page-level shortcuts (e.g. reader page flips)
+	$('#report-note').on('keydown', e => e.stopPropagation());
+});
@@ -2,7 +2,8 @@ $(function(){
 	var filter = [];
 	var result = [];
 	$('.uk-card-title').each(function(){
-		filter.push($(this).text());
+		// Aliases are matched too, so a title can be found by any of its names
+		filter.push($(this).text() + '\n' + ($(this).attr('data-aliases') || ''));
 	});
 	$('.uk-search-input').keyup(function(){
 		var input = $('.uk-search-input').val();
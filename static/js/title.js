@@ -57,6 +57,11 @@ function showModal(encodedPath, pages, percentage, encodedeTitle, encodedEntryTi
 
 	$('#modal-download-btn').attr('href', `${base_url}api/download/${titleID}/${entryID}`);
 
+	$('#modal-report-btn').off('click').click(() => {
+		UIkit.modal($('#modal')).hide();
+		showReportModal(titleID, entryID, entry);
+	});
+
 	UIkit.modal($('#modal')).show();
 }
 
@@ -242,8 +247,13 @@ const setupUpload = (eid) => {
 			bar.max = e.total;
 			bar.value = e.loaded;
 		},
-		completeAll: () => {
+		completeAll: (xhr) => {
 			$(bar).attr('hidden', '');
+			const folderCover = JSON.parse(xhr.responseText).folder_cover;
+			if (folderCover && !eid) {
+				alert('warning', `Cover uploaded, but the title card keeps showing ${folderCover} from the title directory. Remove that file to use the uploaded cover.`);
+				return;
+			}
 			location.reload();
 		}
 	});
@@ -390,3 +400,49 @@ const tagsComponent = () => {
 		}
 	};
 };
+
+const readersComponent = (tid) => {
+	return {
+		entries: [],
+		readers: [],
+		loading: false,
+		loaded: false,
+
+		load() {
+			if (this.loading || this.loaded) return;
+			this.loading = true;
+			$.getJSON(`${base_url}api/admin/title/${tid}/readers`)
+				.done(data => {
+					this.entries = data.entries;
+					this.readers = data.readers;
+					this.loaded = true;
+				})
+				.fail(jqXHR => {
+					alert('danger', `Failed to load readers. Error: [${jqXHR.status}] ${jqXHR.statusText}`);
+				})
+				.always(() => {
+					this.loading = false;
+				});
+		}
+	};
+};
+
+const pinComponent = (tid, pinned) => {
+	return {
+		pinned,
+
+		toggle() {
+			$.ajax({
+					url: `${base_url}api/pins/${tid}`,
+					method: this.pinned ? 'DELETE' : 'PUT',
+					dataType: 'json'
+				})
+				.done(data => {
+					this.pinned = data.pins.indexOf(tid) >= 0;
+				})
+				.fail(jqXHR => {
+					alert('danger', `Failed to update the pin. Error: [${jqXHR.status}] ${jqXHR.statusText}`);
+				});
+		}
+	};
+};
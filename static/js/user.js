@@ -11,6 +11,27 @@ const remove = (username) => {
 				alert('danger', data.error);
 		})
 		.fail((jqXHR, status) => {
-			alert('danger', `Failed to delete the user. Error: [${jqXHR.status}] ${jqXHR.statusText}`);
+			const error = jqXHR.responseJSON && jqXHR.responseJSON.error;
+			alert('danger', `Failed to delete the user. Error: ${error || `[${jqXHR.status}] ${jqXHR.statusText}`}`);
+		});
+};
+
+const resetPassword = (username) => {
+	const password = prompt(`New password for ${username}. Their sessions will be logged out and their app and API tokens revoked.`);
+	if (!password)
+		return;
+	$.ajax({
+			url: `${base_url}api/admin/users/${encodeURIComponent(username)}/reset-password`,
+			type: 'POST',
+			contentType: 'application/json',
+			data: JSON.stringify({ password }),
+			dataType: 'json'
+		})
+		.done(data => {
+			alert('success', `Password of ${username} reset, ${data.sessions_revoked} sessions logged out, ${data.tokens_revoked} tokens revoked.`);
+		})
+		.fail((jqXHR) => {
+			const error = jqXHR.responseJSON && jqXHR.responseJSON.error;
+			alert('danger', `Failed to reset the password. Error: ${error || `[${jqXHR.status}] ${jqXHR.statusText}`}`);
 		});
 };
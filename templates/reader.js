@@ -85,7 +85,7 @@ function savePreferences() {
 
 // Get image URL for a page
 function getPageUrl(page) {
-    return `/api/page/${TITLE_ID}/${ENTRY_ID}/${page}`;
+    return `${base_url}api/page/${TITLE_ID}/${ENTRY_ID}/${page}`;
 }
 
 // Update progress display
@@ -98,7 +98,7 @@ function updateProgress() {
 // Save progress to server
 async function saveProgress() {
     try {
-        await fetch(`/api/progress/${TITLE_ID}/${ENTRY_ID}`, {
+        await fetch(`${base_url}api/progress/${TITLE_ID}/${ENTRY_ID}`, {
             method: 'POST',
             headers: { 'Content-Type': 'application/json' },
             body: JSON.stringify({ page: currentPage })
@@ -112,7 +112,7 @@ async function saveProgress() {
 function saveProgressBeacon() {
     const data = JSON.stringify({ page: currentPage });
     const blob = new Blob([data], { type: 'application/json' });
-    navigator.sendBeacon(`/api/progress/${TITLE_ID}/${ENTRY_ID}`, blob);
+    navigator.sendBeacon(`${base_url}api/progress/${TITLE_ID}/${ENTRY_ID}`, blob);
 }
 
 // Preload images
@@ -247,7 +247,7 @@ function setupScrollHandler() {
             updateProgress();
             saveProgress();
             // Update URL to reflect current page
-            const newUrl = `/reader/${TITLE_ID}/${ENTRY_ID}/${currentPage}`;
+            const newUrl = `${base_url}reader/${TITLE_ID}/${ENTRY_ID}/${currentPage}`;
             history.replaceState(null, '', newUrl);
             document.title = `${document.title.split(' - Page')[0]} - Page ${currentPage}`;
         }
@@ -293,7 +293,7 @@ function loadPage(page) {
         saveProgress();
         preloadImages();
         // Update URL to reflect current page
-        const newUrl = `/reader/${TITLE_ID}/${ENTRY_ID}/${currentPage}`;
+        const newUrl = `${base_url}reader/${TITLE_ID}/${ENTRY_ID}/${currentPage}`;
         history.replaceState(null, '', newUrl);
         document.title = `${document.title.split(' - Page')[0]} - Page ${currentPage}`;
     };
@@ -430,7 +430,7 @@ function jumpToPage() {
 
 function jumpToEntry() {
     const entryId = document.getElementById('entry-select').value;
-    window.location.href = `/reader/${TITLE_ID}/${entryId}/1`;
+    window.location.href = `${base_url}reader/${TITLE_ID}/${entryId}/1`;
 }
 
 // Keyboard shortcuts
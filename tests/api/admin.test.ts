@@ -7,14 +7,60 @@ describe('Admin API', () => {
   });
 
   describe('POST /api/admin/scan', () => {
-    it('triggers library scan and returns results', async () => {
+    it('starts a library scan and reports its results', async () => {
       const response = await api.post('/api/admin/scan');
 
+      expect(response.status).toBe(202);
+
+      const { job_id } = await response.json();
+      let job;
+      for (let i = 0; i < 100; i++) {
+        job = (await (await api.get('/api/admin/scan/status')).json()).job;
+        if (job.job_id === job_id && job.finished_at !== null) break;
+        await new Promise((resolve) => setTimeout(resolve, 100));
+      }
+      expect(job.finished_at).not.toBeNull();
+      expect(job.report).toHaveProperty('titles');
+      expect(typeof job.report.titles).toBe('number');
+    });
+  });
+
+  describe('GET /api/admin/scan/status', () => {
+    it('reports no journal after a completed scan', async () => {
+      const response = await api.get('/api/admin/scan/status');
+      expect(response.status).toBe(200);
+
+      const status = await response.json();
+      expect(status.running).toBe(false);
+      expect(status).not.toHaveProperty('percent');
+    });
+
+    it('has nothing to resume', async () => {
+      const response = await api.post('/api/admin/scan/resume');
+      expect(response.status).toBe(404);
+    });
+  });
+
+  describe('POST /api/admin/maintenance/rebuild-signatures', () => {
+    it('reports signature changes without writing on dry run', async () => {
+      const response = await api.post('/api/admin/maintenance/rebuild-signatures?dry_run=true');
+
       expect(response.status).toBe(200);
 
       const result = await response.json();
-      expect(result).toHaveProperty('titles');
-      expect(typeof result.titles).toBe('number');
+      expect(result.dry_run).toBe(true);
+      expect(typeof result.titles_updated).toBe('number');
+      expect(typeof result.entries_updated).toBe('number');
+    });
+
+    it('leaves nothing to update after a rebuild', async () => {
+      const response = await api.post('/api/admin/maintenance/rebuild-signatures');
+      expect(response.status).toBe(200);
+
+      const rerun = await api.post('/api/admin/maintenance/rebuild-signatures?dry_run=true');
+      const result = await rerun.json();
+      expect(result.titles_updated).toBe(0);
+      expect(result.entries_updated).toBe(0);
     });
   });
 
@@ -33,4 +79,161 @@ describe('Admin API', () => {
       }
     });
   });
+
+  describe('GET /api/admin/quota', () => {
+    it('returns download usage for every user', async () => {
+      const response = await api.get('/api/admin/quota');
+
+      expect(response.status).toBe(200);
+
+      const usage = await response.json();
+      expect(Array.isArray(usage)).toBe(true);
+
+      if (usage.length > 0) {
+        expect(usage[0]).toHaveProperty('username');
+        expect(typeof usage[0].used_bytes).toBe('number');
+        expect(usage[0]).toHaveProperty('quota_bytes');
+      }
+    });
+  });
+
+  describe('GET /api/admin/diagnostics', () => {
+    it('returns database pool health counters', async () => {
+      const response = await api.get('/api/admin/diagnostics');
+
+      expect(response.status).toBe(200);
+
+      const diagnostics = await response.json();
+      expect(typeof diagnostics.database.size).toBe('number');
+      expect(typeof diagnostics.database.idle).toBe('number');
+      expect(typeof diagnostics.database.acquire_timeouts).toBe('number');
+      expect(typeof diagnostics.database.slow_queries).toBe('number');
+    });
+  });
+
+  describe('GET /api/admin/bad_pages', () => {
+    it('lists entries with pages that failed validation', async () => {
+      const response = await api.get('/api/admin/bad_pages');
+
+      expect(response.status).toBe(200);
+
+      const entries = await response.json();
+      expect(Array.isArray(entries)).toBe(true);
+      for (const entry of entries) {
+        expect(entry.bad_pages.length).toBeGreaterThan(0);
+        expect(entry.bad_pages.every((p: number) => p >= 1 && p <= entry.pages)).toBe(true);
+      }
+    });
+  });
+
+  describe('PUT/DELETE /api/admin/title/:id/entry_order', () => {
+    it('pins and clears a custom entry order', async () => {
+      const libraryResponse = await api.get('/api/library');
+      const library = await libraryResponse.json();
+      const title = library.find((t: { entries: number }) => t.entries > 1);
+
+      if (title) {
+        const before = await (await api.get(`/api/title/${title.id}`)).json();
+        const reversed = before.entries.map((e: { id: string }) => e.id).reverse();
+
+        const putResponse = await api.put(`/api/admin/title/${title.id}/entry_order`, {
+          ids: reversed,
+        });
+        expect(putResponse.status).toBe(200);
+
+        const pinned = await (await api.get(`/api/title/${title.id}?sort=title&ascend=1`)).json();
+        expect(pinned.custom_order).toBe(true);
+        expect(pinned.entries.map((e: { id: string }) => e.id)).toEqual(reversed);
+
+        const deleteResponse = await api.delete(`/api/admin/title/${title.id}/entry_order`);
+        expect(deleteResponse.status).toBe(200);
+
+        const cleared = await (await api.get(`/api/title/${title.id}`)).json();
+        expect(cleared.custom_order).toBe(false);
+        expect(cleared.entries).toEqual(before.entries);
+      }
+    });
+
+    it('rejects entry IDs from another title', async () => {
+      const libraryResponse = await api.get('/api/library');
+      const library = await libraryResponse.json();
+
+      if (library.length > 0) {
+        const response = await api.put(`/api/admin/title/${library[0].id}/entry_order`, {
+          ids: ['nonexistent-entry'],
+        });
+        expect(response.status).toBe(400);
+      }
+    });
+  });
+
+  describe('PUT /api/admin/title/:id/aliases', () => {
+    it('finds a title by an alias-only query', async () => {
+      const library = await (await api.get('/api/library')).json();
+
+      if (library.length > 0) {
+        const title = library[0];
+        const putResponse = await api.put(`/api/admin/title/${title.id}/aliases`, {
+          aliases: ['  Zzyzx   Alias Test ', 'Second Name'],
+        });
+        expect(putResponse.status).toBe(200);
+        expect((await putResponse.json()).aliases).toEqual(['Zzyzx Alias Test', 'Second Name']);
+
+        const detail = await (await api.get(`/api/title/${title.id}`)).json();
+        expect(detail.aliases).toEqual(['Zzyzx Alias Test', 'Second Name']);
+
+        const results = await (await api.get('/api/search?q=zzyzx')).json();
+        expect(results).toEqual([
+          { id: title.id, title: title.title, matched_alias: 'Zzyzx Alias Test' },
+        ]);
+
+        const book = await (await api.get(`/book/${title.id}`)).text();
+        expect(book).toContain('Zzyzx Alias Test');
+
+        const clearResponse = await api.put(`/api/admin/title/${title.id}/aliases`, { aliases: [] });
+        expect(clearResponse.status).toBe(200);
+        expect(await (await api.get('/api/search?q=zzyzx')).json()).toEqual([]);
+      }
+    });
+
+    it('rejects duplicate aliases', async () => {
+      const library = await (await api.get('/api/library')).json();
+
+      if (library.length > 0) {
+        const response = await api.put(`/api/admin/title/${library[0].id}/aliases`, {
+          aliases: ['Same Name', 'same  name'],
+        });
+        expect(response.status).toBe(400);
+      }
+    });
+  });
+
+  describe('PUT /api/title/:id/direction', () => {
+    it('sets and clears a title direction override', async () => {
+      const library = await (await api.get('/api/library')).json();
+
+      if (library.length > 0) {
+        const title = library[0];
+        const putResponse = await api.put(`/api/title/${title.id}/direction`, { direction: 'rtl' });
+        expect(putResponse.status).toBe(200);
+
+        const book = await (await api.get(`/book/${title.id}`)).text();
+        expect(book).toContain('RTL');
+
+        const clearResponse = await api.put(`/api/title/${title.id}/direction`, { direction: null });
+        expect(clearResponse.status).toBe(200);
+      }
+    });
+
+    it('rejects unknown directions', async () => {
+      const library = await (await api.get('/api/library')).json();
+
+      if (library.length > 0) {
+        const response = await api.put(`/api/title/${library[0].id}/direction`, {
+          direction: 'diagonal',
+        });
+        expect(response.status).toBe(400);
+      }
+    });
+  });
 });
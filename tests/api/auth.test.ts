@@ -62,6 +62,22 @@ describe('Auth API', () => {
       expect(body).toContain('username');
       expect(body).toContain('password');
     });
+
+    it('uses the default branding when none is configured', async () => {
+      const response = await fetch(`${BASE_URL}/login`);
+      const body = await response.text();
+
+      expect(body).toContain('<title>Mango - Login</title>');
+      expect(body).not.toContain('--accent-color');
+    });
+  });
+
+  describe('GET /branding/logo', () => {
+    it('is public and returns 404 without a configured logo', async () => {
+      const response = await fetch(`${BASE_URL}/branding/logo`, { redirect: 'manual' });
+
+      expect(response.status).toBe(404);
+    });
   });
 
   describe('GET /logout', () => {
@@ -195,6 +211,46 @@ describe('Auth API', () => {
     });
   });
 
+  describe('Deep links', () => {
+    it('returns to the requested page after logging in', async () => {
+      const redirect = await fetch(`${BASE_URL}/library?sort=title`, { redirect: 'manual' });
+      expect(redirect.status).toBe(303);
+      const location = redirect.headers.get('location')!;
+      expect(location).toBe('/login?next=%2Flibrary%3Fsort%3Dtitle');
+
+      const page = await (await fetch(`${BASE_URL}${location}`)).text();
+      expect(page).toContain('name="next"');
+      expect(page).toContain('sort=title');
+
+      const response = await fetch(`${BASE_URL}/login`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/x-www-form-urlencoded' },
+        body: new URLSearchParams({
+          username: 'testuser',
+          password: 'testpass123',
+          next: '/library?sort=title',
+        }),
+        redirect: 'manual',
+      });
+      expect(response.status).toBe(303);
+      expect(response.headers.get('location')).toBe('/library?sort=title');
+    });
+
+    it.each(['//evil.example', 'https://evil.example/', '/\\evil.example', 'javascript:alert(1)'])(
+      'ignores an off-site next value (%s)',
+      async (next) => {
+        const response = await fetch(`${BASE_URL}/login`, {
+          method: 'POST',
+          headers: { 'Content-Type': 'application/x-www-form-urlencoded' },
+          body: new URLSearchParams({ username: 'testuser', password: 'testpass123', next }),
+          redirect: 'manual',
+        });
+        expect(response.status).toBe(303);
+        expect(response.headers.get('location')).toBe('/');
+      },
+    );
+  });
+
   describe('Session persistence', () => {
     it('session cookie persists across requests', async () => {
       await login();
@@ -4,7 +4,8 @@ const SERVER_HOST = 'localhost';
 const BASE_URL = `http://${SERVER_HOST}:${SERVER_PORT}`;
 
 export interface ApiClient {
-  get: (path: string) => Promise<Response>;
+  get: (path: string, headers?: Record<string, string>) => Promise<Response>;
+  head: (path: string) => Promise<Response>;
   post: (path: string, body?: unknown) => Promise<Response>;
   put: (path: string, body?: unknown) => Promise<Response>;
   patch: (path: string, body?: unknown) => Promise<Response>;
@@ -48,7 +49,14 @@ function getHeaders(): HeadersInit {
 }
 
 export const api: ApiClient = {
-  get: (path: string) => fetch(`${BASE_URL}${path}`, { headers: getHeaders() }),
+  get: (path: string, headers?: Record<string, string>) => fetch(`${BASE_URL}${path}`, {
+    headers: { ...getHeaders(), ...headers },
+  }),
+
+  head: (path: string) => fetch(`${BASE_URL}${path}`, {
+    method: 'HEAD',
+    headers: getHeaders(),
+  }),
 
   post: (path: string, body?: unknown) => fetch(`${BASE_URL}${path}`, {
     method: 'POST',
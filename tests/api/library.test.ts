@@ -28,6 +28,18 @@ describe('Library API', () => {
       }
     });
 
+    it('title objects have cover metadata fields', async () => {
+      const response = await api.get('/api/library');
+      const data = await response.json();
+
+      if (data.length > 0) {
+        const title = data[0];
+        expect(title).toHaveProperty('cover_width');
+        expect(title).toHaveProperty('cover_height');
+        expect(title).toHaveProperty('cover_mime');
+      }
+    });
+
     it('respects sort parameter', async () => {
       const defaultResponse = await api.get('/api/library');
       const sortedResponse = await api.get('/api/library?sort=title');
@@ -62,6 +74,109 @@ describe('Library API', () => {
     });
   });
 
+  describe('HEAD /api/cover/:tid/:eid', () => {
+    it('returns cover headers without a body', async () => {
+      const libraryResponse = await api.get('/api/library');
+      const library = await libraryResponse.json();
+
+      if (library.length > 0) {
+        const titleId = library[0].id;
+        const titleResponse = await api.get(`/api/title/${titleId}`);
+        const title = await titleResponse.json();
+        const entryId = title.entries[0].id;
+
+        const response = await api.head(`/api/cover/${titleId}/${entryId}`);
+        expect(response.status).toBe(200);
+        expect(response.headers.get('content-type')).toMatch(/^image\//);
+        expect(response.headers.get('etag')).toBeTruthy();
+        expect(Number(response.headers.get('content-length'))).toBeGreaterThan(0);
+
+        const body = await response.text();
+        expect(body).toBe('');
+
+        const getResponse = await api.get(`/api/cover/${titleId}/${entryId}`);
+        expect(getResponse.headers.get('etag')).toBe(response.headers.get('etag'));
+      }
+    });
+  });
+
+  describe('Save-Data', () => {
+    it('links downscaled covers from the library page only when requested', async () => {
+      const library = await (await api.get('/api/library')).json();
+
+      if (library.length > 0) {
+        const plain = await api.get('/library');
+        expect(plain.headers.get('vary')).toMatch(/Save-Data/i);
+        expect(await plain.text()).not.toMatch(/\/api\/cover\/[^"]+\?w=/);
+
+        const saving = await api.get('/library', { 'Save-Data': 'on' });
+        expect(saving.headers.get('vary')).toMatch(/Save-Data/i);
+        expect(await saving.text()).toMatch(/\/api\/cover\/[^"]+\?w=100/);
+      }
+    });
+
+    it('links downscaled covers from the book page only when requested', async () => {
+      const library = await (await api.get('/api/library')).json();
+
+      if (library.length > 0) {
+        const plain = await (await api.get(`/book/${library[0].id}`)).text();
+        expect(plain).not.toMatch(/\/api\/cover\/[^"]+\?w=/);
+
+        const saving = await (await api.get(`/book/${library[0].id}`, { 'Save-Data': 'on' })).text();
+        expect(saving).toMatch(/\/api\/cover\/[^"]+\?w=100/);
+      }
+    });
+
+    it('serves a cover no wider than the width hint', async () => {
+      const library = await (await api.get('/api/library')).json();
+
+      if (library.length > 0) {
+        const title = await (await api.get(`/api/title/${library[0].id}`)).json();
+        const url = `/api/cover/${title.id}/${title.entries[0].id}`;
+
+        const full = await api.get(url);
+        const small = await api.get(`${url}?w=100`);
+        expect(small.status).toBe(200);
+        expect(Number(small.headers.get('content-length'))).toBeLessThanOrEqual(
+          Number(full.headers.get('content-length')),
+        );
+      }
+    });
+  });
+
+  describe('GET /api/entry/:tid/:eid/page_thumbnails', () => {
+    it('returns frame offsets into the page sprite', async () => {
+      const libraryResponse = await api.get('/api/library');
+      const library = await libraryResponse.json();
+
+      if (library.length > 0) {
+        const titleId = library[0].id;
+        const titleResponse = await api.get(`/api/title/${titleId}`);
+        const title = await titleResponse.json();
+        const entryId = title.entries[0].id;
+
+        const response = await api.get(`/api/entry/${titleId}/${entryId}/page_thumbnails`);
+        expect(response.status).toBe(200);
+
+        const data = await response.json();
+        expect(data.success).toBe(true);
+        expect(data.height).toBe(60);
+        expect(data.step).toBeGreaterThanOrEqual(1);
+        expect(data.frames.length).toBeGreaterThan(0);
+        expect(data.frames[0]).toEqual(expect.objectContaining({ page: 1, x: 0 }));
+
+        const sprite = await api.get(data.sprite_url);
+        expect(sprite.status).toBe(200);
+        expect(sprite.headers.get('content-type')).toBe('image/jpeg');
+      }
+    });
+
+    it('returns 404 for an unknown entry', async () => {
+      const response = await api.get('/api/entry/nonexistent/nonexistent/page_thumbnails');
+      expect(response.status).toBe(404);
+    });
+  });
+
   describe('GET /api/stats', () => {
     it('returns library statistics', async () => {
       const response = await api.get('/api/stats');
@@ -73,4 +188,18 @@ describe('Library API', () => {
       expect(typeof stats.pages).toBe('number');
     });
   });
+
+  describe('GET /api/capabilities', () => {
+    it('returns versioned feature flags and limits', async () => {
+      const response = await api.get('/api/capabilities');
+      expect(response.status).toBe(200);
+
+      const capabilities = await response.json();
+      expect(capabilities.api_version).toBe(1);
+      expect(typeof capabilities.server_version).toBe('string');
+      expect(capabilities.features.tags).toBe(true);
+      expect(capabilities.features.favorites).toBe(false);
+      expect(typeof capabilities.limits.max_cover_upload_bytes).toBe('number');
+    });
+  });
 });
@@ -35,6 +35,38 @@ describe('Progress API', () => {
     });
   });
 
+  describe('read counts', () => {
+    it('counts a re-read only after going back to the start', async () => {
+      const library = await (await api.get('/api/library')).json();
+      if (library.length === 0) return;
+
+      const title = await (await api.get(`/api/title/${library[0].id}`)).json();
+      const entry = title.entries.find((e: { pages: number }) => e.pages >= 5);
+      if (!entry) return;
+
+      const url = `/api/progress/${title.id}/${entry.id}`;
+      const readCount = async () => (await (await api.get(url)).json()).read_count;
+      const stats = async () => (await (await api.get('/api/stats')).json()).rereads;
+
+      await api.post(url, { page: 1 });
+      const before = await readCount();
+      const rereadsBefore = await stats();
+
+      // Finishing, then jittering one page back and forth, is a single read
+      await api.post(url, { page: entry.pages });
+      await api.post(url, { page: entry.pages - 1 });
+      await api.post(url, { page: entry.pages });
+      const first = await readCount();
+      expect(first).toBeGreaterThanOrEqual(Math.max(before, 1));
+
+      // Going back to the start and finishing again is a re-read
+      await api.post(url, { page: 1 });
+      await api.post(url, { page: entry.pages });
+      expect(await readCount()).toBe(first + 1);
+      expect(await stats()).toBeGreaterThan(rereadsBefore);
+    });
+  });
+
   describe('GET /api/progress', () => {
     it('returns user progress', async () => {
       const response = await api.get('/api/progress');
@@ -45,4 +77,46 @@ describe('Progress API', () => {
       expect(typeof progress).toBe('object');
     });
   });
+
+  describe('GET /api/library/continue_reading', () => {
+    it('is served from the recently read index without loading info.json', async () => {
+      const libraryResponse = await api.get('/api/library');
+      const library = await libraryResponse.json();
+
+      if (library.length === 0) {
+        console.log('No titles in library, skipping continue reading test');
+        return;
+      }
+
+      const titleId = library[0].id;
+      const title = await (await api.get(`/api/title/${titleId}`)).json();
+
+      if (!title.entries || title.entries.length === 0) {
+        console.log('No entries in title, skipping continue reading test');
+        return;
+      }
+
+      const entryId = title.entries[0].id;
+
+      // Saving progress warms the index
+      await api.post(`/api/progress/${titleId}/${entryId}`, { page: 2 });
+
+      const loadsBefore = (await (await api.get('/api/admin/diagnostics')).json()).library
+        .title_info_loads;
+
+      const response = await api.get('/api/library/continue_reading');
+      expect(response.status).toBe(200);
+
+      const entries = await response.json();
+      expect(entries[0].entry_id).toBe(entryId);
+      expect(entries[0].progress).toBe(2);
+
+      const homeResponse = await api.get('/');
+      expect(homeResponse.status).toBe(200);
+
+      const loadsAfter = (await (await api.get('/api/admin/diagnostics')).json()).library
+        .title_info_loads;
+      expect(loadsAfter).toBe(loadsBefore);
+    });
+  });
 });
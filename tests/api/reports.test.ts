@@ -0,0 +1,80 @@
+import { describe, it, expect, beforeAll } from 'vitest';
+import { api, login } from './client';
+
+describe('Reports API', () => {
+  let titleId: string | null = null;
+  let entryId: string | null = null;
+
+  beforeAll(async () => {
+    await login();
+
+    const library = await (await api.get('/api/library')).json();
+    if (library.length > 0) {
+      titleId = library[0].id;
+      const title = await (await api.get(`/api/title/${titleId}`)).json();
+      entryId = title.entries.length > 0 ? title.entries[0].id : null;
+    }
+  });
+
+  describe('POST /api/report/:tid/:eid', () => {
+    it('returns 404 for an unknown entry', async () => {
+      const response = await api.post('/api/report/nonexistent/nonexistent', {
+        category: 'corrupt',
+      });
+      expect(response.status).toBe(404);
+    });
+
+    it('rejects an unknown category', async () => {
+      if (!titleId || !entryId) return;
+
+      const response = await api.post(`/api/report/${titleId}/${entryId}`, {
+        category: 'boring',
+      });
+      expect(response.status).toBeGreaterThanOrEqual(400);
+      expect(response.status).toBeLessThan(500);
+    });
+  });
+
+  describe('report lifecycle', () => {
+    it('files, dedups, lists and resolves a report', async () => {
+      if (!titleId || !entryId) return;
+
+      const createResponse = await api.post(`/api/report/${titleId}/${entryId}`, {
+        category: 'misordered',
+        note: 'pages 3 and 4 are swapped',
+      });
+      expect(createResponse.status).toBe(200);
+      const created = await createResponse.json();
+      expect(created.success).toBe(true);
+      expect(typeof created.id).toBe('number');
+
+      // Same user, same entry, same category while still open
+      const duplicateResponse = await api.post(`/api/report/${titleId}/${entryId}`, {
+        category: 'misordered',
+      });
+      expect(duplicateResponse.status).toBe(409);
+
+      const open = await (await api.get('/api/admin/reports')).json();
+      const report = open.find((r: { id: number }) => r.id === created.id);
+      expect(report).toBeDefined();
+      expect(report.category).toBe('misordered');
+      expect(report.note).toBe('pages 3 and 4 are swapped');
+      expect(report.resolved_at).toBeNull();
+
+      const resolveResponse = await api.patch(`/api/admin/reports/${created.id}`);
+      expect(resolveResponse.status).toBe(200);
+
+      const stillOpen = await (await api.get('/api/admin/reports')).json();
+      expect(stillOpen.some((r: { id: number }) => r.id === created.id)).toBe(false);
+
+      const resolved = await (await api.get('/api/admin/reports?resolved=true')).json();
+      const history = resolved.find((r: { id: number }) => r.id === created.id);
+      expect(history).toBeDefined();
+      expect(history.resolved_by).toBeTruthy();
+
+      // Already resolved
+      const again = await api.patch(`/api/admin/reports/${created.id}`);
+      expect(again.status).toBe(404);
+    });
+  });
+});
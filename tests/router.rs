@@ -0,0 +1,5448 @@
+// End-to-end tests for the full router
+//
+// Each test builds the real app from `server::build_app` on top of a tempfile SQLite
+// database and a generated fixture library, then drives it with in-process requests.
+
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use axum::{
+    body::{to_bytes, Body},
+    extract::ConnectInfo,
+    http::{header, Method, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use base64::{engine::general_purpose, Engine as _};
+use tower::ServiceExt;
+
+use mango_rust::{
+    auth::LoginThrottle,
+    config::{LooseArchives, TwoFactorBasicAuth},
+    library::{
+        convert::Conversions, ArchiveCache, FolderCovers, LibraryEvents, PageFlights, ScanStatus,
+    },
+    quota::DownloadQuota,
+    routes::{capabilities::FEATURE_ROUTES, paths},
+    server::build_app,
+    stats::TitleStats,
+    totp, AppState, Config, Library, Storage,
+};
+
+const ADMIN: (&str, &str) = ("admin", "admin-password");
+const READER: (&str, &str) = ("reader", "reader-password");
+
+/// A running app with its fixture library
+struct TestApp {
+    app: Router,
+    state: AppState,
+    _temp_dir: tempfile::TempDir,
+}
+
+/// Set a fixture file's modification time (Unix timestamp)
+fn set_mtime(path: &Path, timestamp: u64) {
+    let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(timestamp);
+    std::fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+/// Encode a solid-color PNG so every page has distinct, recognisable bytes
+fn png(shade: u8) -> Vec<u8> {
+    let image = image::RgbImage::from_pixel(4, 6, image::Rgb([shade, shade, shade]));
+    let mut data = Cursor::new(Vec::new());
+    image.write_to(&mut data, image::ImageFormat::Png).unwrap();
+    data.into_inner()
+}
+
+/// Write an uncompressed (stored) ZIP archive
+fn write_stored_zip(path: &Path, files: &[(&str, Vec<u8>)]) {
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, data) in files {
+        let offset = out.len() as u32;
+        let crc = crc32fast::hash(data);
+        let size = data.len() as u32;
+
+        // Local file header
+        out.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        out.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        out.extend_from_slice(&[0; 8]); // flags, method (stored), time, date
+        out.extend_from_slice(&crc.to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // extra length
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        // Central directory header
+        central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        central.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        central.extend_from_slice(&[0; 8]); // flags, method, time, date
+        central.extend_from_slice(&crc.to_le_bytes());
+        central.extend_from_slice(&size.to_le_bytes());
+        central.extend_from_slice(&size.to_le_bytes());
+        central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        central.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = out.len() as u32;
+    let central_size = central.len() as u32;
+    out.extend_from_slice(&central);
+
+    // End of central directory record
+    out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    out.extend_from_slice(&[0; 4]); // disk numbers
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&central_size.to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // comment length
+
+    std::fs::write(path, out).unwrap();
+}
+
+/// Library with two titles: "Alpha" (two chapters) and "Beta" (one chapter)
+fn create_fixture_library(root: &Path) {
+    let alpha = root.join("Alpha");
+    let beta = root.join("Beta");
+    std::fs::create_dir_all(&alpha).unwrap();
+    std::fs::create_dir_all(&beta).unwrap();
+
+    write_stored_zip(
+        &alpha.join("Chapter 1.zip"),
+        &[
+            ("001.png", png(10)),
+            ("002.png", png(20)),
+            ("003.png", png(30)),
+        ],
+    );
+    write_stored_zip(&alpha.join("Chapter 2.zip"), &[("001.png", png(40))]);
+    write_stored_zip(&beta.join("Chapter 1.zip"), &[("001.png", png(50))]);
+}
+
+async fn setup() -> TestApp {
+    setup_with(|_, _| {}).await
+}
+
+/// Like `setup`, with a hook to adjust the config and fixture files before the first scan
+async fn setup_with(prepare: impl FnOnce(&mut Config, &Path)) -> TestApp {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let library_path = temp_dir.path().join("library");
+    create_fixture_library(&library_path);
+
+    let mut config = Config::default_config();
+    config.library_path = library_path.clone();
+    config.db_path = temp_dir.path().join("mango.db");
+    config.library_cache_path = temp_dir.path().join("library.cache");
+    config.library_cache_file_enabled = false;
+    config.memory_cache_enabled = false;
+    prepare(&mut config, &library_path);
+
+    let database_url = format!("sqlite://{}?mode=rwc", config.db_path.display());
+    // `Storage::new` seeds an "admin" account with a random password
+    let storage = Storage::new(&database_url).await.unwrap();
+    storage.set_password(ADMIN.0, ADMIN.1).await.unwrap();
+    storage
+        .create_user(READER.0, READER.1, false, true)
+        .await
+        .unwrap();
+
+    let mut library = Library::new(library_path, storage.clone(), &config);
+    library.scan().await.unwrap();
+
+    let state = AppState {
+        storage: storage.clone(),
+        library: Arc::new(ArcSwap::from_pointee(library)),
+        quota: DownloadQuota::new(storage.clone(), 0),
+        stats: TitleStats::new(storage),
+        archives: ArchiveCache::new(config.archive_cache_entries, 1024 * 1024),
+        page_flights: PageFlights::new(),
+        scan_status: ScanStatus::new(),
+        conversions: Conversions::new(),
+        folder_covers: FolderCovers::new(),
+        config: Arc::new(config),
+        events: LibraryEvents::default(),
+        login_throttle: LoginThrottle::new(),
+        pin_throttle: LoginThrottle::new(),
+    };
+    let app = build_app(state.clone()).await.unwrap();
+
+    TestApp {
+        app,
+        state,
+        _temp_dir: temp_dir,
+    }
+}
+
+impl TestApp {
+    async fn send(&self, request: Request<Body>) -> Response {
+        self.app.clone().oneshot(request).await.unwrap()
+    }
+
+    async fn get(&self, uri: &str, cookie: Option<&str>) -> Response {
+        let mut request = Request::get(uri);
+        if let Some(cookie) = cookie {
+            request = request.header(header::COOKIE, cookie);
+        }
+        self.send(request.body(Body::empty()).unwrap()).await
+    }
+
+    /// Submit the login form with a return address
+    async fn post_login(&self, credentials: (&str, &str), next: &str) -> Response {
+        self.send(login_request(credentials, next)).await
+    }
+
+    /// Log in through the form and return the session cookie
+    async fn login(&self, (username, password): (&str, &str)) -> String {
+        let response = self
+            .send(
+                Request::post("/login")
+                    .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                    .body(Body::from(format!(
+                        "username={}&password={}",
+                        username, password
+                    )))
+                    .unwrap(),
+            )
+            .await;
+        assert!(response.status().is_redirection(), "login should redirect");
+
+        let set_cookie = response
+            .headers()
+            .get(header::SET_COOKIE)
+            .expect("login should set a session cookie")
+            .to_str()
+            .unwrap();
+        set_cookie.split(';').next().unwrap().to_string()
+    }
+
+    /// ID of a fixture title and its first entry
+    fn ids(&self, name: &str) -> (String, String) {
+        let library = self.state.library.load();
+        let title = library
+            .get_titles()
+            .into_iter()
+            .find(|t| t.title == name)
+            .unwrap_or_else(|| panic!("fixture title {} not scanned", name));
+        let entry = title
+            .entries
+            .iter()
+            .find(|e| e.title == "Chapter 1")
+            .unwrap();
+        (title.id.clone(), entry.id.clone())
+    }
+}
+
+async fn body_bytes(response: Response) -> Vec<u8> {
+    to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .to_vec()
+}
+
+async fn body_text(response: Response) -> String {
+    String::from_utf8(body_bytes(response).await).unwrap()
+}
+
+fn location(response: &Response) -> &str {
+    response.headers()[header::LOCATION].to_str().unwrap()
+}
+
+fn basic_auth((username, password): (&str, &str)) -> String {
+    let credentials = general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+    format!("Basic {}", credentials)
+}
+
+/// Check that every element is closed in order
+/// Enough to catch broken templates without pulling in an XML parser
+fn assert_well_formed_xml(xml: &str) {
+    assert!(
+        xml.trim_start().starts_with("<?xml"),
+        "missing XML declaration"
+    );
+
+    let mut open: Vec<&str> = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..].find('>').expect("unterminated tag") + start;
+        let tag = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            assert_eq!(open.pop(), Some(name.trim()), "mismatched closing tag");
+        } else if !tag.ends_with('/') {
+            open.push(tag.split_whitespace().next().unwrap());
+        }
+    }
+    assert!(open.is_empty(), "unclosed elements: {:?}", open);
+}
+
+#[tokio::test]
+async fn test_login_page_is_public() {
+    let app = setup().await;
+    let response = app.get("/login", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_unauthenticated_page_redirects_to_login() {
+    let app = setup().await;
+    let response = app.get("/library", None).await;
+    assert!(response.status().is_redirection());
+    assert_eq!(location(&response), "/login?next=%2Flibrary");
+
+    // The home page needs no return address
+    let response = app.get("/", None).await;
+    assert_eq!(location(&response), "/login");
+}
+
+#[tokio::test]
+async fn test_login_returns_to_deep_link() {
+    let app = setup().await;
+    let (tid, _) = app.ids("Alpha");
+    let deep_link = format!("/book/{}?sort=title", tid);
+
+    let response = app.get(&deep_link, None).await;
+    let login_url = location(&response).to_string();
+    assert!(login_url.starts_with("/login?next="));
+
+    // The login form carries the target through
+    let page = body_text(app.get(&login_url, None).await).await;
+    assert!(page.contains(r#"name="next""#));
+    assert!(page.contains(&tid));
+
+    let response = app.post_login(READER, &deep_link).await;
+    assert!(response.status().is_redirection());
+    assert_eq!(location(&response), deep_link);
+
+    // A page in the reader comes back to that page
+    let (tid, eid) = app.ids("Alpha");
+    let reader_link = format!("/reader/{}/{}/2", tid, eid);
+    let response = app.get(&reader_link, None).await;
+    // `next` is encoded as one segment, so the dashes in the ids are escaped as well
+    assert_eq!(
+        location(&response),
+        format!("/login?next=%2Freader%2F{}%2F{}%2F2", tid, eid).replace('-', "%2D")
+    );
+    let response = app.post_login(READER, &reader_link).await;
+    assert_eq!(location(&response), reader_link);
+}
+
+#[tokio::test]
+async fn test_login_rejects_offsite_next() {
+    let app = setup().await;
+    for next in [
+        "//evil.example",
+        "https://evil.example/",
+        "/\\evil.example",
+        "javascript:alert(1)",
+    ] {
+        let response = app.post_login(READER, next).await;
+        assert!(response.status().is_redirection());
+        assert_eq!(location(&response), "/", "next={:?}", next);
+    }
+
+    // Tampered values never reach the login form either
+    let page = body_text(app.get("/login?next=%2F%2Fevil.example", None).await).await;
+    assert!(!page.contains("evil.example"));
+}
+
+#[tokio::test]
+async fn test_unauthenticated_api_redirects_to_login() {
+    let app = setup().await;
+    let response = app.get("/api/library", None).await;
+    assert!(response.status().is_redirection());
+    assert_eq!(location(&response), "/login");
+}
+
+#[tokio::test]
+async fn test_login_with_wrong_password_is_rejected() {
+    let app = setup().await;
+    let response = app
+        .send(
+            Request::post("/login")
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from("username=reader&password=wrong"))
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response)
+        .await
+        .contains("Invalid username or password"));
+}
+
+#[tokio::test]
+async fn test_login_locks_out_after_failures() {
+    let app = setup().await;
+    for _ in 0..5 {
+        let response = app.post_login((READER.0, "wrong"), "/").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Even the right password is refused until the window ends
+    let response = app.post_login(READER, "/").await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key(header::RETRY_AFTER));
+    assert!(body_text(response).await.contains("Too many failed logins"));
+
+    // Other users are not affected, nor is the user from other clients
+    app.login(ADMIN).await;
+    let response = app
+        .send(from_client(login_request(READER, "/"), [192, 168, 1, 20]))
+        .await;
+    assert!(response.status().is_redirection());
+}
+
+#[tokio::test]
+async fn test_basic_auth_locks_out_after_failures() {
+    let app = setup().await;
+    let opds = |credentials: (&str, &str)| {
+        from_client(
+            Request::get("/opds")
+                .header(header::AUTHORIZATION, basic_auth(credentials))
+                .body(Body::empty())
+                .unwrap(),
+            [192, 168, 1, 10],
+        )
+    };
+    for _ in 0..5 {
+        let response = app.send(opds((READER.0, "wrong"))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    // Even the right password is refused until the window ends
+    let response = app.send(opds(READER)).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+    // The failures count for the login form too, but only from that client
+    let response = app
+        .send(from_client(login_request(READER, "/"), [192, 168, 1, 10]))
+        .await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    app.login(READER).await;
+}
+
+#[tokio::test]
+async fn test_login_session_opens_library() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let response = app.get("/library", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let html = body_text(response).await;
+    assert!(html.contains("Alpha"));
+    assert!(html.contains("Beta"));
+}
+
+#[tokio::test]
+async fn test_logout_ends_session() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let response = app.get("/logout", Some(&cookie)).await;
+    assert!(response.status().is_redirection());
+
+    let response = app.get("/library", Some(&cookie)).await;
+    assert!(response.status().is_redirection());
+    assert_eq!(location(&response), "/login?next=%2Flibrary");
+}
+
+#[tokio::test]
+async fn test_password_change_logs_out_other_sessions() {
+    let app = setup().await;
+    let phone = app.login(READER).await;
+    let laptop = app.login(READER).await;
+    let created_token = |response: Response| async move {
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created: serde_json::Value =
+            serde_json::from_slice(&body_bytes(response).await).unwrap();
+        created["token"].as_str().unwrap().to_string()
+    };
+    let app_token = created_token(
+        post_json(
+            &app,
+            "/api/user/app-tokens",
+            &laptop,
+            serde_json::json!({ "label": "KOReader" }),
+        )
+        .await,
+    )
+    .await;
+    let api_token = created_token(
+        post_json(
+            &app,
+            "/api/user/tokens",
+            &laptop,
+            serde_json::json!({ "name": "Backup script" }),
+        )
+        .await,
+    )
+    .await;
+
+    let response = post_json(
+        &app,
+        "/api/user/change-password",
+        &laptop,
+        serde_json::json!({
+            "current_password": READER.1,
+            "new_password": "new-reader-password",
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let changed: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(changed["sessions_revoked"], 1);
+    assert_eq!(changed["tokens_revoked"], 2);
+
+    let response = app.get("/library", Some(&phone)).await;
+    assert!(response.status().is_redirection());
+    let response = app.get("/library", Some(&laptop)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    app.login((READER.0, "new-reader-password")).await;
+
+    // Tokens made with the old password stop working too
+    let response = app
+        .send(
+            Request::get("/opds")
+                .header(header::AUTHORIZATION, basic_auth((READER.0, &app_token)))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app
+        .send(
+            Request::get("/api/library")
+                .header(header::AUTHORIZATION, format!("Bearer {}", api_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_admin_resets_password() {
+    let app = setup().await;
+    let reader = app.login(READER).await;
+    let admin = app.login(ADMIN).await;
+    async fn reset(app: &TestApp, cookie: &str, user: &str, password: &str) -> Response {
+        let uri = format!("/api/admin/users/{}/reset-password", user);
+        post_json(
+            app,
+            &uri,
+            cookie,
+            serde_json::json!({ "password": password }),
+        )
+        .await
+    }
+
+    let response = reset(&app, &reader, ADMIN.0, "taken-over").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = reset(&app, &admin, READER.0, "short").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = reset(&app, &admin, "nobody", "new-password").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    app.state
+        .storage
+        .create_api_token(READER.0, "Backup script", "0a1b2c")
+        .await
+        .unwrap();
+    let response = reset(&app, &admin, READER.0, "new-password").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let reset_body: serde_json::Value =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(reset_body["sessions_revoked"], 1);
+    assert_eq!(reset_body["tokens_revoked"], 1);
+    assert!(app
+        .state
+        .storage
+        .list_api_tokens(READER.0)
+        .await
+        .unwrap()
+        .is_empty());
+    let response = app.get("/library", Some(&reader)).await;
+    assert!(response.status().is_redirection());
+    app.login((READER.0, "new-password")).await;
+
+    // Admins can reset their own password without logging themselves out
+    let response = reset(&app, &admin, ADMIN.0, "new-admin-password").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.get("/library", Some(&admin)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    app.login((ADMIN.0, "new-admin-password")).await;
+}
+
+#[tokio::test]
+async fn test_admin_user_edit_changes_password_like_reset() {
+    let app = setup().await;
+    let reader = app.login(READER).await;
+    let admin = app.login(ADMIN).await;
+    let edit = |password: &str| {
+        Request::post(format!("/admin/user/edit/{}", READER.0))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .header(header::COOKIE, &admin)
+            .body(Body::from(format!(
+                "username={}&password={}",
+                READER.0, password
+            )))
+            .unwrap()
+    };
+    let patch = |password: &str| {
+        Request::patch(format!("/api/admin/users/{}", READER.0))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::COOKIE, &admin)
+            .body(Body::from(
+                serde_json::json!({"is_admin": false, "password": password}).to_string(),
+            ))
+            .unwrap()
+    };
+
+    // Too short a password is refused by both, and changes nothing
+    let response = app.send(edit("short")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.send(patch("short")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.get("/library", Some(&reader)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A new password logs the user out and revokes their tokens
+    app.state
+        .storage
+        .create_api_token(READER.0, "Backup script", "0a1b2c")
+        .await
+        .unwrap();
+    let response = app.send(edit("edited-password")).await;
+    assert!(response.status().is_redirection());
+    let response = app.get("/library", Some(&reader)).await;
+    assert!(response.status().is_redirection());
+    assert!(app
+        .state
+        .storage
+        .list_api_tokens(READER.0)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let reader = app.login((READER.0, "edited-password")).await;
+    let response = app.send(patch("patched-password")).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.get("/library", Some(&reader)).await;
+    assert!(response.status().is_redirection());
+    app.login((READER.0, "patched-password")).await;
+}
+
+#[tokio::test]
+async fn test_sessions_per_browser() {
+    let app = setup().await;
+    let phone = app.login(READER).await;
+    let laptop = app.login(READER).await;
+    let tablet = app.login(READER).await;
+
+    // Logging out on one browser leaves the others logged in
+    app.get("/logout", Some(&phone)).await;
+    let response = app.get("/library", Some(&phone)).await;
+    assert!(response.status().is_redirection());
+    let response = app.get("/library", Some(&laptop)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.get("/api/user/sessions", Some(&laptop)).await;
+    let sessions: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let sessions = sessions.as_array().unwrap();
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions.iter().filter(|s| s["current"] == true).count(), 1);
+    assert!(sessions.iter().all(|s| s.get("token").is_none()));
+
+    let response = post_json(
+        &app,
+        "/api/user/sessions/revoke-all",
+        &laptop,
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let revoked: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(revoked["revoked"], 1);
+    let response = app.get("/library", Some(&tablet)).await;
+    assert!(response.status().is_redirection());
+    let response = app.get("/library", Some(&laptop)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_api_library_lists_fixture_titles() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let response = app.get("/api/library", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let titles: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let mut names: Vec<&str> = titles
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["title"].as_str().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["Alpha", "Beta"]);
+}
+
+#[tokio::test]
+async fn test_non_admin_blocked_from_admin_api() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let response = app.get("/api/admin/users", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .send(
+            Request::post("/api/admin/scan")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_admin_allowed_admin_api() {
+    let app = setup().await;
+    let cookie = app.login(ADMIN).await;
+
+    let response = app.get("/api/admin/users", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let users: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(users.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_users_last_login() {
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+    let last_login = |users: &serde_json::Value, name: &str| {
+        users
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|u| u["username"] == name)
+            .unwrap()["last_login"]
+            .clone()
+    };
+
+    let response = app.get("/api/admin/users", Some(&admin)).await;
+    let users: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert!(last_login(&users, ADMIN.0).is_i64());
+    assert!(last_login(&users, READER.0).is_null());
+    let page = body_text(app.get("/admin/user", Some(&admin)).await).await;
+    assert!(page.contains("just now"));
+    assert!(page.contains("never"));
+
+    // OPDS readers log in with Basic auth
+    let response = app
+        .send(
+            Request::get("/opds")
+                .header(header::AUTHORIZATION, basic_auth(READER))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.get("/api/admin/users", Some(&admin)).await;
+    let users: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert!(last_login(&users, READER.0).is_i64());
+}
+
+async fn stored_last_login(app: &TestApp, username: &str) -> Option<i64> {
+    sqlx::query_scalar("SELECT last_login FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_one(app.state.storage.pool())
+        .await
+        .unwrap()
+}
+
+async fn set_last_login(app: &TestApp, username: &str, last_login: Option<i64>) {
+    sqlx::query("UPDATE users SET last_login = ? WHERE username = ?")
+        .bind(last_login)
+        .bind(username)
+        .execute(app.state.storage.pool())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_last_login_recorded_once_logged_in() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (secret, _) = enable_two_factor(&app, &cookie).await;
+    set_last_login(&app, READER.0, None).await;
+
+    // The password alone is not a login
+    let pending = start_two_factor_login(&app, READER).await;
+    assert_eq!(stored_last_login(&app, READER.0).await, None);
+    let code = totp::code_at(&secret, totp::step_at(now_ts()) + 1);
+    let response = post_two_factor_code(&app, &pending, &code).await;
+    assert!(response.status().is_redirection());
+    assert!(stored_last_login(&app, READER.0).await.is_some());
+
+    // Basic auth logs in on every request; the time is refreshed at most once a minute
+    let opds = || {
+        app.send(
+            Request::get("/opds")
+                .header(header::AUTHORIZATION, basic_auth(ADMIN))
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+    let recent = now_ts() - 10;
+    set_last_login(&app, ADMIN.0, Some(recent)).await;
+    assert_eq!(opds().await.status(), StatusCode::OK);
+    assert_eq!(stored_last_login(&app, ADMIN.0).await, Some(recent));
+    set_last_login(&app, ADMIN.0, Some(now_ts() - 120)).await;
+    assert_eq!(opds().await.status(), StatusCode::OK);
+    assert!(stored_last_login(&app, ADMIN.0).await.unwrap() >= now_ts() - 1);
+
+    // A refused request records nothing
+    set_last_login(&app, ADMIN.0, None).await;
+    let response = app
+        .send(
+            Request::get("/opds")
+                .header(header::AUTHORIZATION, basic_auth((ADMIN.0, "wrong")))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(stored_last_login(&app, ADMIN.0).await, None);
+}
+
+#[tokio::test]
+async fn test_disable_login() {
+    let app = setup_with(|config, _| {
+        config.disable_login = true;
+        config.default_username = Some(READER.0.to_string());
+    })
+    .await;
+
+    // No session needed, and nothing to log out of
+    let response = app.get("/library", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_text(response).await;
+    assert!(body.contains("Alpha"));
+    assert!(!body.contains("/logout"));
+
+    let response = app.get("/api/library", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The default user is not an admin
+    let response = app.get("/api/admin/users", None).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_disable_login_as_admin() {
+    let app = setup_with(|config, _| {
+        config.disable_login = true;
+        config.default_username = Some(ADMIN.0.to_string());
+    })
+    .await;
+
+    let response = app.get("/api/admin/users", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_auth_proxy_header() {
+    let app = setup_with(|config, _| {
+        config.auth_proxy_header_name = Some("Remote-User".to_string());
+    })
+    .await;
+    let proxied = |user: &str| {
+        Request::get("/api/admin/users")
+            .header("Remote-User", user)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.send(proxied(ADMIN.0)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(header::SET_COOKIE).is_none());
+
+    let response = app.send(proxied(READER.0)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.send(proxied("nobody")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Without the header the session still counts
+    let response = app.get("/api/admin/users", None).await;
+    assert!(response.status().is_redirection());
+    let cookie = app.login(ADMIN).await;
+    let response = app.get("/api/admin/users", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_auth_proxy_header_ignored_when_unset() {
+    let app = setup().await;
+
+    let response = app
+        .send(
+            Request::get("/api/admin/users")
+                .header("Remote-User", ADMIN.0)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert!(response.status().is_redirection());
+}
+
+#[tokio::test]
+async fn test_progress_round_trip() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (tid, eid) = app.ids("Alpha");
+    let uri = format!("/api/progress/{}/{}", tid, eid);
+
+    // Unread entries start on page 1
+    let response = app.get(&uri, Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let progress: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(progress["page"], 1);
+
+    let response = app
+        .send(
+            Request::post(uri.as_str())
+                .header(header::COOKIE, &cookie)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"page":3}"#))
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.get(&uri, Some(&cookie)).await;
+    let progress: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(progress["page"], 3);
+
+    // Progress is per user
+    let admin_cookie = app.login(ADMIN).await;
+    let response = app.get(&uri, Some(&admin_cookie)).await;
+    let progress: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(progress["page"], 1);
+}
+
+#[tokio::test]
+async fn test_read_only_user() {
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+    let guest = ("guest", "guest-password");
+    let response = post_json(
+        &app,
+        "/api/admin/users",
+        &admin,
+        serde_json::json!({
+            "username": guest.0,
+            "password": guest.1,
+            "is_admin": false,
+            "can_write": false,
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let cookie = app.login(guest).await;
+    let (tid, eid) = app.ids("Alpha");
+
+    // Reading works
+    let response = app.get(&format!("/api/title/{}", tid), Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .get(&format!("/reader/{}/{}/3", tid, eid), Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response)
+        .await
+        .contains("const can_write = false;"));
+
+    // Saving progress does not, and viewing a page did not save it either
+    let uri = format!("/api/progress/{}/{}", tid, eid);
+    let response = post_json(&app, &uri, &cookie, serde_json::json!({"page": 3})).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.get(&uri, Some(&cookie)).await;
+    let progress: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(progress["page"], 1);
+
+    let response = post_json(
+        &app,
+        "/api/user/change-password",
+        &cookie,
+        serde_json::json!({"current_password": guest.1, "new_password": "changed-password"}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.get("/api/admin/users", Some(&admin)).await;
+    let users: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let user = |name: &str| {
+        users
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|u| u["username"] == name)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(user(guest.0)["can_write"], false);
+    assert_eq!(user(READER.0)["can_write"], true);
+}
+
+#[tokio::test]
+async fn test_progress_for_unknown_title_is_not_found() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let response = app
+        .get("/api/progress/missing/missing", Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_page_returns_image_bytes_and_mime() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (tid, eid) = app.ids("Alpha");
+
+    for (page, shade) in [(1, 10), (3, 30)] {
+        let response = app
+            .get(
+                &format!("/api/page/{}/{}/{}", tid, eid, page),
+                Some(&cookie),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        assert_eq!(body_bytes(response).await, png(shade));
+    }
+}
+
+#[tokio::test]
+async fn test_page_for_unknown_entry_is_not_found() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (tid, _) = app.ids("Alpha");
+
+    let response = app
+        .get(&format!("/api/page/{}/missing/1", tid), Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_unknown_title_is_not_found() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let response = app.get("/api/title/missing", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_unknown_route_is_not_found() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let response = app.get("/no/such/page", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_opds_index_with_basic_auth() {
+    let app = setup().await;
+    let response = app
+        .send(
+            Request::get("/opds")
+                .header(header::AUTHORIZATION, basic_auth(READER))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let xml = body_text(response).await;
+    assert_well_formed_xml(&xml);
+    assert!(xml.contains("<feed"));
+    assert!(xml.contains("Alpha"));
+    assert!(xml.contains("Beta"));
+}
+
+#[tokio::test]
+async fn test_opds_title_acquisition_link() {
+    let app = setup().await;
+    let (tid, eid) = app.ids("Alpha");
+    let response = app
+        .send(
+            Request::get(format!("/opds/book/{}", tid))
+                .header(header::AUTHORIZATION, basic_auth(READER))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let xml = body_text(response).await;
+    assert_well_formed_xml(&xml);
+
+    let path = {
+        let library = app.state.library.load();
+        library.get_entry(&tid, &eid).unwrap().path.clone()
+    };
+    let size = std::fs::metadata(&path).unwrap().len();
+    let link = format!(
+        "title=\"Read\" type=\"application/zip\" length=\"{}\"",
+        size
+    );
+    assert!(xml.contains(&link), "missing acquisition link in {}", xml);
+}
+
+async fn opds_feed(app: &TestApp, uri: &str, credentials: (&str, &str)) -> String {
+    let response = app
+        .send(
+            Request::get(uri)
+                .header(header::AUTHORIZATION, basic_auth(credentials))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let xml = body_text(response).await;
+    assert_well_formed_xml(&xml);
+    xml
+}
+
+#[tokio::test]
+async fn test_opds_continue_feed_is_per_user() {
+    let app = setup().await;
+    let index = opds_feed(&app, "/opds", READER).await;
+    assert!(index.contains("href=\"/opds/continue\""));
+    assert!(index.contains("href=\"/opds/recent\""));
+
+    // Each user reads a different title
+    let (alpha, alpha_entry) = app.ids("Alpha");
+    let (beta, beta_entry) = app.ids("Beta");
+    for (user, tid, eid) in [(READER, &alpha, &alpha_entry), (ADMIN, &beta, &beta_entry)] {
+        let cookie = app.login(user).await;
+        let uri = format!("/api/progress/{}/{}", tid, eid);
+        let response = post_json(&app, &uri, &cookie, serde_json::json!({"page": 2})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let reader = opds_feed(&app, "/opds/continue", READER).await;
+    assert!(reader.contains(&format!("/opds/book/{}", alpha)));
+    assert!(!reader.contains(&format!("/opds/book/{}", beta)));
+
+    let admin = opds_feed(&app, "/opds/continue", ADMIN).await;
+    assert!(admin.contains(&format!("/opds/book/{}", beta)));
+    assert!(!admin.contains(&format!("/opds/book/{}", alpha)));
+
+    // Titles no longer in the library drop out of the feed
+    std::fs::remove_dir_all(app.state.config.library_path.join("Beta")).unwrap();
+    let cookie = app.login(ADMIN).await;
+    scan_library(&app, &cookie, "/api/admin/scan").await;
+    let admin = opds_feed(&app, "/opds/continue", ADMIN).await;
+    assert!(!admin.contains(&format!("/opds/book/{}", beta)));
+}
+
+#[tokio::test]
+async fn test_opds_recent_feed() {
+    let app = setup().await;
+    let (alpha, _) = app.ids("Alpha");
+    let (beta, _) = app.ids("Beta");
+
+    let xml = opds_feed(&app, "/opds/recent", READER).await;
+    assert!(xml.contains("<title>Recently Added</title>"));
+    assert!(xml.contains(&format!("/opds/book/{}", alpha)));
+    assert!(xml.contains(&format!("/opds/book/{}", beta)));
+
+    std::fs::remove_dir_all(app.state.config.library_path.join("Beta")).unwrap();
+    let cookie = app.login(ADMIN).await;
+    scan_library(&app, &cookie, "/api/admin/scan").await;
+    let xml = opds_feed(&app, "/opds/recent", READER).await;
+    assert!(xml.contains(&format!("/opds/book/{}", alpha)));
+    assert!(!xml.contains(&format!("/opds/book/{}", beta)));
+}
+
+#[tokio::test]
+async fn test_opds_requires_credentials() {
+    let app = setup().await;
+
+    let (tid, eid) = app.ids("Alpha");
+    for uri in [
+        "/opds".to_string(),
+        format!("/api/download/{}/{}", tid, eid),
+    ] {
+        // E-readers get the Basic challenge, not the login page
+        let response = app.get(&uri, None).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            "Basic realm=\"Mango\""
+        );
+    }
+
+    let with_auth = |credentials| {
+        Request::get("/opds")
+            .header(header::AUTHORIZATION, basic_auth(credentials))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app.send(with_auth((READER.0, "wrong"))).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+
+    let response = app.send(with_auth(READER)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Browser pages still redirect to the login page
+    let response = app.get("/library", None).await;
+    assert!(response.status().is_redirection());
+}
+
+#[tokio::test]
+async fn test_search_finds_title_by_alias() {
+    let app = setup().await;
+    let cookie = app.login(ADMIN).await;
+    let (tid, _) = app.ids("Beta");
+
+    let response = app
+        .send(
+            Request::put(format!("/api/admin/title/{}/aliases", tid))
+                .header(header::COOKIE, &cookie)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"aliases":["  Second   Letter ","B"]}"#))
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.get("/api/search?q=letter", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let results: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(
+        results,
+        serde_json::json!([{"id": tid, "title": "Beta", "matched_alias": "Second Letter"}])
+    );
+
+    // A name match does not report an alias
+    let response = app.get("/api/search?q=alp", Some(&cookie)).await;
+    let results: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(results[0]["title"], "Alpha");
+    assert_eq!(results[0]["matched_alias"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn test_catalog_reads_do_not_wait_for_scan() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let gamma = app.state.config.library_path.join("Gamma");
+    std::fs::create_dir_all(&gamma).unwrap();
+    write_stored_zip(&gamma.join("Chapter 1.zip"), &[("001.png", png(60))]);
+
+    // A "scan" that builds the new catalog, then stalls until released before swapping
+    let (release, stalled) = tokio::sync::oneshot::channel::<()>();
+    let state = app.state.clone();
+    let scan = tokio::spawn(async move {
+        let mut new_lib = Library::new(
+            state.config.library_path.clone(),
+            state.storage.clone(),
+            &state.config,
+        );
+        new_lib.share_scan_lock(&state.library.load());
+        new_lib.scan().await.unwrap();
+        stalled.await.unwrap();
+        state.library.store(Arc::new(new_lib));
+    });
+
+    // Readers keep being served from the old snapshot while the scan is in flight
+    for _ in 0..5 {
+        let response = tokio::time::timeout(
+            std::time::Duration::from_millis(500),
+            app.get("/api/library", Some(&cookie)),
+        )
+        .await
+        .expect("catalog read blocked behind the scan");
+        assert_eq!(response.status(), StatusCode::OK);
+        let titles: serde_json::Value =
+            serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(titles.as_array().unwrap().len(), 2);
+    }
+
+    release.send(()).unwrap();
+    scan.await.unwrap();
+
+    let response = app.get("/api/library", Some(&cookie)).await;
+    let titles: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(titles.as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_library_index_etag() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let response = app.get("/api/library/index", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let index: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let (alpha, _) = app.ids("Alpha");
+    let (beta, _) = app.ids("Beta");
+    assert_eq!(
+        index,
+        serde_json::json!([
+            {"id": alpha, "name": "Alpha", "entry_count": 2},
+            {"id": beta, "name": "Beta", "entry_count": 1},
+        ])
+    );
+
+    // The index is built once per snapshot, not per request
+    let library = app.state.library.load();
+    assert!(Arc::ptr_eq(&library.index(), &library.index()));
+
+    let response = app
+        .send(
+            Request::get("/api/library/index")
+                .header(header::COOKIE, &cookie)
+                .header(header::IF_NONE_MATCH, &etag)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert!(body_bytes(response).await.is_empty());
+
+    // A scan that finds a new title swaps in a new index
+    let gamma = app.state.config.library_path.join("Gamma");
+    std::fs::create_dir_all(&gamma).unwrap();
+    write_stored_zip(&gamma.join("Chapter 1.zip"), &[("001.png", png(60))]);
+    let mut new_lib = Library::new(
+        app.state.config.library_path.clone(),
+        app.state.storage.clone(),
+        &app.state.config,
+    );
+    new_lib.share_scan_lock(&app.state.library.load());
+    new_lib.scan().await.unwrap();
+    app.state.library.store(Arc::new(new_lib));
+
+    let response = app
+        .send(
+            Request::get("/api/library/index")
+                .header(header::COOKIE, &cookie)
+                .header(header::IF_NONE_MATCH, &etag)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(response.headers()[header::ETAG], etag.as_str());
+    let index: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(index.as_array().unwrap().len(), 3);
+}
+
+/// 2020-09-13, well outside the "recently added" month
+const OLD_MTIME: u64 = 1_600_000_000;
+
+/// Date added of a fixture entry, from the progress cache
+fn date_added(app: &TestApp, title: &str, entry: &str) -> Option<i64> {
+    let library = app.state.library.load();
+    let title = library
+        .get_titles()
+        .into_iter()
+        .find(|t| t.title == title)
+        .unwrap();
+    let entry = title.entries.iter().find(|e| e.title == entry).unwrap();
+    library
+        .progress_cache()
+        .get_date_added(&title.id, &entry.id)
+}
+
+async fn recently_added_names(app: &TestApp, cookie: &str) -> Vec<String> {
+    let response = app.get("/api/library/recently_added", Some(cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let entries: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    entries
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["title_name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_backfill_date_added_from_mtime() {
+    let app = setup().await;
+    let cookie = app.login(ADMIN).await;
+    assert!(recently_added_names(&app, &cookie)
+        .await
+        .contains(&"Alpha".to_string()));
+
+    let library_path = app.state.config.library_path.clone();
+    for chapter in ["Chapter 1.zip", "Chapter 2.zip"] {
+        set_mtime(&library_path.join("Alpha").join(chapter), OLD_MTIME);
+    }
+
+    let backfill = |query: &str| {
+        Request::post(format!(
+            "/api/admin/maintenance/backfill-date-added{}",
+            query
+        ))
+        .header(header::COOKIE, &cookie)
+        .body(Body::empty())
+        .unwrap()
+    };
+
+    // Dates stamped by the initial import are replaced by the archives' mtimes
+    let response = app.send(backfill("")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(result["titles"], 2);
+    for chapter in ["Chapter 1", "Chapter 2"] {
+        assert_eq!(date_added(&app, "Alpha", chapter), Some(OLD_MTIME as i64));
+    }
+    let beta_mtime = std::fs::metadata(library_path.join("Beta").join("Chapter 1.zip"))
+        .unwrap()
+        .modified()
+        .unwrap()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    assert_eq!(date_added(&app, "Beta", "Chapter 1"), Some(beta_mtime));
+
+    // Recently added is meaningful right away
+    assert_eq!(recently_added_names(&app, &cookie).await, ["Beta"]);
+
+    // Dates outside the import window are kept unless forced
+    let (alpha, _) = app.ids("Alpha");
+    let older = OLD_MTIME - 24 * 60 * 60;
+    set_mtime(&library_path.join("Alpha").join("Chapter 1.zip"), older);
+    let response = app.send(backfill(&format!("?tid={}", alpha))).await;
+    let result: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(result["entries_updated"], 0);
+
+    let response = app.send(backfill(&format!("?tid={}&force=1", alpha))).await;
+    let result: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(
+        result,
+        serde_json::json!({"titles": 1, "entries_updated": 1})
+    );
+    assert_eq!(date_added(&app, "Alpha", "Chapter 1"), Some(older as i64));
+}
+
+#[tokio::test]
+async fn test_initial_import_dates_from_mtime_when_enabled() {
+    let app = setup_with(|config, library_path| {
+        config.date_added_from_mtime = true;
+        set_mtime(&library_path.join("Alpha").join("Chapter 1.zip"), OLD_MTIME);
+    })
+    .await;
+
+    assert_eq!(
+        date_added(&app, "Alpha", "Chapter 1"),
+        Some(OLD_MTIME as i64)
+    );
+
+    // Entries found by later scans are dated by the scan as before
+    let path = app
+        .state
+        .config
+        .library_path
+        .join("Alpha")
+        .join("Chapter 3.zip");
+    write_stored_zip(&path, &[("001.png", png(70))]);
+    set_mtime(&path, OLD_MTIME);
+    let before = mango_rust::util::time::now_ts();
+    let mut new_lib = Library::new(
+        app.state.config.library_path.clone(),
+        app.state.storage.clone(),
+        &app.state.config,
+    );
+    new_lib.share_scan_lock(&app.state.library.load());
+    new_lib.scan().await.unwrap();
+    app.state.library.store(Arc::new(new_lib));
+
+    assert!(date_added(&app, "Alpha", "Chapter 3").unwrap() >= before);
+    assert_eq!(
+        date_added(&app, "Alpha", "Chapter 1"),
+        Some(OLD_MTIME as i64)
+    );
+}
+
+/// POST a JSON body with a session cookie
+async fn post_json(app: &TestApp, uri: &str, cookie: &str, body: serde_json::Value) -> Response {
+    app.send(
+        Request::post(uri)
+            .header(header::COOKIE, cookie)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+    )
+    .await
+}
+
+/// Login form submission
+fn login_request((username, password): (&str, &str), next: &str) -> Request<Body> {
+    let form = format!(
+        "username={}&password={}&next={}",
+        username,
+        password,
+        percent_encoding::utf8_percent_encode(next, percent_encoding::NON_ALPHANUMERIC)
+    );
+    Request::post("/login")
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from(form))
+        .unwrap()
+}
+
+/// `request` as sent from `ip`, like the server passes it on with connect info
+fn from_client(mut request: Request<Body>, ip: [u8; 4]) -> Request<Body> {
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from((ip, 40000))));
+    request
+}
+
+/// Start a scan with `uri` and wait for it to finish, returning its report
+async fn scan_library(app: &TestApp, cookie: &str, uri: &str) -> serde_json::Value {
+    let response = post_json(app, uri, cookie, serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let started: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+
+    for _ in 0..1000 {
+        let response = app.get("/api/admin/scan/status", Some(cookie)).await;
+        let status: serde_json::Value =
+            serde_json::from_slice(&body_bytes(response).await).unwrap();
+        let job = &status["job"];
+        if job["job_id"] == started["job_id"] && !job["finished_at"].is_null() {
+            assert!(job["error"].is_null(), "scan failed: {}", job["error"]);
+            return job["report"].clone();
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    panic!("scan did not finish");
+}
+
+/// Enroll and enable two-factor login, returning the secret and recovery codes
+async fn enable_two_factor(app: &TestApp, cookie: &str) -> (Vec<u8>, Vec<String>) {
+    let response = post_json(
+        app,
+        "/api/user/two-factor/enroll",
+        cookie,
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let enrollment: serde_json::Value =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert!(enrollment["otpauth_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("otpauth://totp/"));
+    let secret = totp::decode_secret(enrollment["secret"].as_str().unwrap()).unwrap();
+
+    let code = totp::code_at(&secret, totp::step_at(now_ts()));
+    let response = post_json(
+        app,
+        "/api/user/two-factor/enable",
+        cookie,
+        serde_json::json!({ "code": code }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let enabled: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let recovery_codes = enabled["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c.as_str().unwrap().to_string())
+        .collect();
+    (secret, recovery_codes)
+}
+
+/// Pass the password step of a two-factor login, returning the pending session cookie
+async fn start_two_factor_login(app: &TestApp, credentials: (&str, &str)) -> String {
+    let response = app.post_login(credentials, "").await;
+    assert_eq!(
+        response.status(),
+        StatusCode::OK,
+        "password step should not redirect"
+    );
+    let cookie = response.headers()[header::SET_COOKIE]
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+    assert!(body_text(response).await.contains("name=\"code\""));
+    cookie
+}
+
+async fn post_two_factor_code(app: &TestApp, cookie: &str, code: &str) -> Response {
+    app.send(
+        Request::post("/login/2fa")
+            .header(header::COOKIE, cookie)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(format!("code={}", code)))
+            .unwrap(),
+    )
+    .await
+}
+
+fn now_ts() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[tokio::test]
+async fn test_two_factor_enrollment() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let response = post_json(
+        &app,
+        "/api/user/two-factor/enroll",
+        &cookie,
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Nothing is enabled until a first code checks out
+    let response = post_json(
+        &app,
+        "/api/user/two-factor/enable",
+        &cookie,
+        serde_json::json!({ "code": "abcdef" }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(!app.state.storage.totp_enabled(READER.0).await.unwrap());
+
+    let (_, recovery_codes) = enable_two_factor(&app, &cookie).await;
+    assert_eq!(recovery_codes.len(), totp::RECOVERY_CODE_COUNT);
+
+    let response = app.get("/api/user/two-factor", Some(&cookie)).await;
+    let status: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(status["enabled"], true);
+    assert_eq!(status["recovery_codes_left"], totp::RECOVERY_CODE_COUNT);
+
+    // Enrolling again needs an admin reset first
+    let response = post_json(
+        &app,
+        "/api/user/two-factor/enroll",
+        &cookie,
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_two_factor_login() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (secret, recovery_codes) = enable_two_factor(&app, &cookie).await;
+
+    // The password alone does not log in
+    let pending = start_two_factor_login(&app, READER).await;
+    let response = app.get("/api/user/two-factor", Some(&pending)).await;
+    assert_ne!(response.status(), StatusCode::OK);
+
+    let response = post_two_factor_code(&app, &pending, "000000").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response).await.contains("Invalid code"));
+
+    // The enrollment code is spent, so use the next one (within the drift window)
+    let code = totp::code_at(&secret, totp::step_at(now_ts()) + 1);
+    let response = post_two_factor_code(&app, &pending, &code).await;
+    assert!(response.status().is_redirection());
+    let response = app.get("/api/user/two-factor", Some(&pending)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The same code cannot be used again
+    let pending = start_two_factor_login(&app, READER).await;
+    let response = post_two_factor_code(&app, &pending, &code).await;
+    assert!(body_text(response).await.contains("Invalid code"));
+
+    // A recovery code works once, however it is typed
+    let recovery = recovery_codes[0].to_uppercase();
+    let response = post_two_factor_code(&app, &pending, &recovery).await;
+    assert!(response.status().is_redirection());
+    let pending = start_two_factor_login(&app, READER).await;
+    let response = post_two_factor_code(&app, &pending, &recovery).await;
+    assert!(body_text(response).await.contains("Invalid code"));
+    assert_eq!(
+        app.state
+            .storage
+            .recovery_codes_left(READER.0)
+            .await
+            .unwrap(),
+        totp::RECOVERY_CODE_COUNT as i64 - 1
+    );
+
+    // Without a password step there is nothing to verify
+    let response = post_two_factor_code(&app, "", &recovery_codes[1]).await;
+    assert_eq!(location(&response), "/login");
+
+    // Basic auth keeps working by default, for OPDS readers
+    let response = app
+        .send(
+            Request::get("/opds")
+                .header(header::AUTHORIZATION, basic_auth(READER))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_two_factor_codes_count_as_failed_logins() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (secret, _) = enable_two_factor(&app, &cookie).await;
+
+    // Entering the password again does not reset the budget of codes
+    let pending = start_two_factor_login(&app, READER).await;
+    for _ in 0..3 {
+        let response = post_two_factor_code(&app, &pending, "000000").await;
+        assert!(body_text(response).await.contains("Invalid code"));
+    }
+    let pending = start_two_factor_login(&app, READER).await;
+    for _ in 0..2 {
+        let response = post_two_factor_code(&app, &pending, "000000").await;
+        assert!(body_text(response).await.contains("Invalid code"));
+    }
+
+    // Not even the right code gets in until the window ends
+    let code = totp::code_at(&secret, totp::step_at(now_ts()) + 1);
+    let response = post_two_factor_code(&app, &pending, &code).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key(header::RETRY_AFTER));
+    let response = app.post_login(READER, "").await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Other clients still get their own budget
+    let response = app
+        .send(from_client(login_request(READER, ""), [192, 168, 1, 30]))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_two_factor_basic_auth_requires_app_token() {
+    let app = setup_with(|config, _| {
+        config.two_factor_basic_auth = TwoFactorBasicAuth::AppToken;
+    })
+    .await;
+    let cookie = app.login(READER).await;
+    enable_two_factor(&app, &cookie).await;
+
+    let opds = |credentials: (&str, &str)| {
+        Request::get("/opds")
+            .header(header::AUTHORIZATION, basic_auth(credentials))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app.send(opds(READER)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = post_json(
+        &app,
+        "/api/user/app-tokens",
+        &cookie,
+        serde_json::json!({ "label": "KOReader" }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let token = created["token"].as_str().unwrap().to_string();
+
+    let response = app.send(opds((READER.0, &token))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Revoked tokens stop working
+    let response = app
+        .send(
+            Request::delete(format!(
+                "/api/user/app-tokens/{}",
+                created["id"].as_str().unwrap()
+            ))
+            .header(header::COOKIE, &cookie)
+            .body(Body::empty())
+            .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.send(opds((READER.0, &token))).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_api_tokens() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let bearer = |uri: &str, token: &str| {
+        Request::get(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = post_json(
+        &app,
+        "/api/user/tokens",
+        &cookie,
+        serde_json::json!({ "name": "Backup script" }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let token = created["token"].as_str().unwrap().to_string();
+
+    let response = app.send(bearer("/api/library", &token)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(header::SET_COOKIE).is_none());
+    let response = app.send(bearer("/api/library", "wrong")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Tokens are for the API only
+    let response = app.send(bearer("/library", &token)).await;
+    assert!(response.status().is_redirection());
+
+    // The list never shows the token again, but does show that it was used
+    let response = app.get("/api/user/tokens", Some(&cookie)).await;
+    let listed = body_text(response).await;
+    assert!(!listed.contains(&token));
+    let listed: serde_json::Value = serde_json::from_str(&listed).unwrap();
+    assert_eq!(listed[0]["name"], "Backup script");
+    assert!(listed[0]["last_used"].is_i64());
+
+    // The admin users page counts them
+    let admin = app.login(ADMIN).await;
+    let page = body_text(app.get("/admin/user", Some(&admin)).await).await;
+    assert!(page.contains("<td>1</td>"));
+
+    // Revoked tokens stop working
+    let response = app
+        .send(
+            Request::delete(format!(
+                "/api/user/tokens/{}",
+                created["id"].as_str().unwrap()
+            ))
+            .header(header::COOKIE, &cookie)
+            .body(Body::empty())
+            .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.send(bearer("/api/library", &token)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_admin_resets_two_factor() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    enable_two_factor(&app, &cookie).await;
+
+    let delete = |cookie: &str| {
+        Request::delete("/api/admin/users/reader/two-factor")
+            .header(header::COOKIE, cookie)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app.send(delete(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let admin_cookie = app.login(ADMIN).await;
+    let response = app.send(delete(&admin_cookie)).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // Back to password-only login
+    app.login(READER).await;
+}
+
+#[tokio::test]
+async fn test_library_events_stream() {
+    use tokio_stream::StreamExt;
+
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (alpha_id, _) = app.ids("Alpha");
+
+    let response = app.get("/api/events", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/event-stream"
+    );
+    let mut stream = response.into_body().into_data_stream();
+
+    // A new chapter shows up in the next scan
+    let alpha = app.state.config.library_path.join("Alpha");
+    write_stored_zip(&alpha.join("Chapter 3.zip"), &[("001.png", png(60))]);
+    let admin_cookie = app.login(ADMIN).await;
+    scan_library(&app, &admin_cookie, "/api/admin/scan").await;
+
+    let mut received = String::new();
+    while !received.contains("event: scan_completed") {
+        let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), stream.next())
+            .await
+            .expect("events should arrive after the scan")
+            .expect("stream should stay open")
+            .unwrap();
+        received.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+    assert!(received.contains("event: title_updated"));
+    assert!(received.contains(&format!(r#""title_id":"{}","new_entries":1"#, alpha_id)));
+    assert!(received.contains(r#""added":1,"removed":0"#));
+}
+
+#[tokio::test]
+async fn test_library_events_capped_per_user() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    // Open streams count against the cap until their responses are dropped
+    let mut open = Vec::new();
+    for _ in 0..mango_rust::library::events::MAX_STREAMS_PER_USER {
+        let response = app.get("/api/events", Some(&cookie)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        open.push(response);
+    }
+    let response = app.get("/api/events", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Other users are not affected
+    let admin_cookie = app.login(ADMIN).await;
+    let response = app.get("/api/events", Some(&admin_cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    open.pop();
+    let response = app.get("/api/events", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_library_index_letters() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    let letters = |response: Response| async move {
+        let buckets: serde_json::Value =
+            serde_json::from_slice(&body_bytes(response).await).unwrap();
+        buckets
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| {
+                (
+                    b["letter"].as_str().unwrap().to_string(),
+                    b["count"].as_u64().unwrap(),
+                    b["offset"].as_u64().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let response = app.get("/api/library/index_letters", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        letters(response).await,
+        [("A".to_string(), 1, 0), ("B".to_string(), 1, 1)]
+    );
+
+    let response = app
+        .get(
+            "/api/library/index_letters?sort=title&ascend=0",
+            Some(&cookie),
+        )
+        .await;
+    assert_eq!(
+        letters(response).await,
+        [("B".to_string(), 1, 0), ("A".to_string(), 1, 1)]
+    );
+
+    // Time order scatters the letters, so there is no index for it
+    let response = app
+        .get("/api/library/index_letters?sort=modified", Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_version_endpoint() {
+    let app = setup().await;
+
+    let response = app.get("/api/version", None).await;
+    assert!(!response.status().is_success(), "version needs a login");
+
+    let cookie = app.login(READER).await;
+    let response = app.get("/api/version", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let version: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+
+    for field in ["version", "git_commit", "build_date"] {
+        let value = version[field].as_str().unwrap();
+        assert!(!value.is_empty(), "{} is empty", field);
+    }
+    assert_eq!(version["version"], env!("CARGO_PKG_VERSION"));
+    assert!(version["cache_format_version"].as_u64().unwrap() > 0);
+
+    // The schema is at the newest migration shipped with this build
+    let newest_migration = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations"))
+        .unwrap()
+        .filter_map(|e| {
+            let name = e.unwrap().file_name().into_string().unwrap();
+            name.split('_').next()?.parse::<i64>().ok()
+        })
+        .max()
+        .unwrap();
+    assert_eq!(version["schema_version"], newest_migration);
+}
+
+#[tokio::test]
+async fn test_prune_thumbnails_endpoint() {
+    let app = setup().await;
+    let cookie = app.login(ADMIN).await;
+    let (_, beta_entry) = app.ids("Beta");
+
+    // A scan that no longer finds an entry starts its grace period
+    std::fs::remove_file(app.state.config.library_path.join("Beta/Chapter 1.zip")).unwrap();
+    scan_library(&app, &cookie, "/api/admin/scan").await;
+    let since: Option<i64> = sqlx::query_scalar("SELECT unavailable_since FROM ids WHERE id = ?")
+        .bind(&beta_entry)
+        .fetch_one(app.state.storage.pool())
+        .await
+        .unwrap();
+    assert!(since.is_some());
+
+    let response = app
+        .send(
+            Request::post("/api/admin/maintenance/prune-thumbnails?vacuum=1")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(result["rows"], 0);
+    assert_eq!(result["bytes"], 0);
+    assert_eq!(result["vacuumed"], true);
+}
+
+#[tokio::test]
+async fn test_popular_titles_and_sort() {
+    let app = setup().await;
+    let reader = app.login(READER).await;
+    let admin = app.login(ADMIN).await;
+    let (alpha, alpha_entry) = app.ids("Alpha");
+    let (beta, beta_entry) = app.ids("Beta");
+
+    // Paging through an entry is one read; each download counts
+    for page in 1..=3 {
+        let uri = format!("/api/page/{}/{}/{}", alpha, alpha_entry, page);
+        let response = app.get(&uri, Some(&reader)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    for _ in 0..2 {
+        let uri = format!("/api/download/{}/{}", beta, beta_entry);
+        let response = app.get(&uri, Some(&reader)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        body_bytes(response).await;
+    }
+    app.state.stats.flush().await.unwrap();
+
+    let response = app.get("/api/admin/stats/popular", Some(&reader)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .get("/api/admin/stats/popular?days=7", Some(&admin))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let popular: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let popular = popular.as_array().unwrap();
+    assert_eq!(popular.len(), 2);
+    assert_eq!(popular[0]["title_name"], "Beta");
+    assert_eq!(popular[0]["downloads"], 2);
+    assert_eq!(popular[1]["title_name"], "Alpha");
+    assert_eq!(popular[1]["page_views"], 3);
+    assert_eq!(popular[1]["reads"], 1);
+
+    let response = app
+        .get("/api/admin/stats/popular?days=0", Some(&admin))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Most popular first for admins; other users keep name order
+    let position = |html: &str, name: &str| html.find(&format!(">{}<", name)).unwrap();
+    let html = body_text(
+        app.get("/library?sort=popularity&ascend=0", Some(&admin))
+            .await,
+    )
+    .await;
+    assert!(position(&html, "Beta") < position(&html, "Alpha"));
+    let html = body_text(
+        app.get("/library?sort=popularity&ascend=0", Some(&reader))
+            .await,
+    )
+    .await;
+    assert!(position(&html, "Alpha") < position(&html, "Beta"));
+}
+
+#[tokio::test]
+async fn test_delete_missing_during_scan() {
+    let app = setup().await;
+    let cookie = app.login(ADMIN).await;
+    let (_, beta_entry) = app.ids("Beta");
+
+    // Beta's chapter goes missing, then a scan and a delete of missing items race
+    std::fs::remove_file(app.state.config.library_path.join("Beta/Chapter 1.zip")).unwrap();
+    let delete = Request::delete("/api/admin/entries/missing")
+        .header(header::COOKIE, &cookie)
+        .body(Body::empty())
+        .unwrap();
+    let (_, deleted) = tokio::join!(
+        scan_library(&app, &cookie, "/api/admin/scan"),
+        app.send(delete)
+    );
+    assert!(
+        matches!(deleted.status(), StatusCode::OK | StatusCode::CONFLICT),
+        "unexpected status {}",
+        deleted.status()
+    );
+
+    // Whatever the order, every entry of the scanned library has its row and the
+    // missing one is never available again
+    let lib = app.state.library.load();
+    for title in lib.titles().values() {
+        for entry in &title.entries {
+            let unavailable: i64 = sqlx::query_scalar("SELECT unavailable FROM ids WHERE id = ?")
+                .bind(&entry.id)
+                .fetch_one(app.state.storage.pool())
+                .await
+                .unwrap();
+            assert_eq!(unavailable, 0, "{} is on disk", entry.path.display());
+        }
+    }
+    let beta: Option<i64> = sqlx::query_scalar("SELECT unavailable FROM ids WHERE id = ?")
+        .bind(&beta_entry)
+        .fetch_optional(app.state.storage.pool())
+        .await
+        .unwrap();
+    assert_ne!(beta, Some(0));
+
+    // With the scan done the delete goes through and leaves the files on disk alone
+    let response = app
+        .send(
+            Request::delete("/api/admin/entries/missing")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let ids: Vec<String> = sqlx::query_scalar("SELECT path FROM ids ORDER BY path")
+        .fetch_all(app.state.storage.pool())
+        .await
+        .unwrap();
+    assert_eq!(ids, ["Alpha/Chapter 1.zip", "Alpha/Chapter 2.zip"]);
+}
+
+#[tokio::test]
+async fn test_scans_only_block_their_own_app() {
+    let app = setup().await;
+    let other = setup().await;
+    let cookie = app.login(ADMIN).await;
+    let other_cookie = other.login(ADMIN).await;
+    let uri = "/api/admin/maintenance/rebuild-signatures?dry_run=true";
+
+    // Stand in for a scan of the first app's library
+    let scanning = app.state.library.load().scan_lock().block_scans().unwrap();
+    let response = post_json(&app, uri, &cookie, serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = post_json(&other, uri, &other_cookie, serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    drop(scanning);
+    let response = post_json(&app, uri, &cookie, serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_merge_and_unmerge_entries() {
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+    let reader = app.login(READER).await;
+    let (tid, first) = app.ids("Alpha");
+    let second = app
+        .state
+        .library
+        .load()
+        .get_title(&tid)
+        .unwrap()
+        .entries
+        .iter()
+        .find(|e| e.title == "Chapter 2")
+        .unwrap()
+        .id
+        .clone();
+    let merge_uri = format!("/api/admin/title/{}/merge_entries", tid);
+
+    // The reader has started the second part
+    let response = post_json(
+        &app,
+        &format!("/api/progress/{}/{}", tid, second),
+        &reader,
+        serde_json::json!({ "page": 1 }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Merging needs two entries of the title, and admin rights
+    let response = post_json(
+        &app,
+        &merge_uri,
+        &admin,
+        serde_json::json!({ "entry_ids": [first] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let (_, beta_entry) = app.ids("Beta");
+    let response = post_json(
+        &app,
+        &merge_uri,
+        &admin,
+        serde_json::json!({ "entry_ids": [first, beta_entry] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = serde_json::json!({ "entry_ids": [first, second] });
+    let response = post_json(&app, &merge_uri, &reader, body.clone()).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = post_json(&app, &merge_uri, &admin, body).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let merged: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(merged["entry_id"], first.as_str());
+    assert_eq!(merged["pages"], 4);
+
+    // One entry is listed, its fourth page is the second part's first
+    let response = app.get(&format!("/api/title/{}", tid), Some(&reader)).await;
+    let title: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let entries = title["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["id"], first.as_str());
+    assert_eq!(entries[0]["pages"], 4);
+    let response = app
+        .get(&format!("/api/page/{}/{}/4", tid, first), Some(&reader))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_bytes(response).await, png(40));
+
+    // Progress on the second part carried over, and a merged entry is not one file
+    let response = app
+        .get(&format!("/api/progress/{}/{}", tid, first), Some(&reader))
+        .await;
+    let progress: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(progress["page"], 4);
+    let response = app
+        .get(&format!("/api/download/{}/{}", tid, first), Some(&reader))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // A rescan keeps the merge
+    scan_library(&app, &admin, "/api/admin/scan").await;
+    assert_eq!(
+        app.state
+            .library
+            .load()
+            .get_title(&tid)
+            .unwrap()
+            .entries
+            .len(),
+        1
+    );
+
+    // Unmerging lists both parts again with progress spread over them
+    let response = app
+        .send(
+            Request::delete(format!("{}/{}", merge_uri, first))
+                .header(header::COOKIE, &admin)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        app.state
+            .library
+            .load()
+            .get_title(&tid)
+            .unwrap()
+            .entries
+            .len(),
+        2
+    );
+    for (eid, page) in [(&first, 3), (&second, 1)] {
+        let response = app
+            .get(&format!("/api/progress/{}/{}", tid, eid), Some(&reader))
+            .await;
+        let progress: serde_json::Value =
+            serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(progress["page"], page);
+    }
+}
+
+/// Upload a cover image as an admin (`query` picks the title and entry)
+async fn upload_cover(app: &TestApp, cookie: &str, query: &str, image: &[u8]) -> Response {
+    let boundary = "cover-boundary";
+    let mut body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"c.png\"\r\n\
+         Content-Type: image/png\r\n\r\n",
+        b = boundary
+    )
+    .into_bytes();
+    body.extend_from_slice(image);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    app.send(
+        Request::post(format!("/api/admin/upload/cover?{}", query))
+            .header(header::COOKIE, cookie)
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(Body::from(body))
+            .unwrap(),
+    )
+    .await
+}
+
+/// Average gray level of a decoded cover
+fn cover_shade(data: &[u8]) -> u8 {
+    let image = image::load_from_memory(data).unwrap().to_luma8();
+    let sum: u64 = image.pixels().map(|p| p.0[0] as u64).sum();
+    (sum / (image.width() * image.height()) as u64) as u8
+}
+
+#[tokio::test]
+async fn test_folder_cover_image() {
+    let app = setup_with(|_, root| {
+        let cover = root.join("Alpha/Cover.PNG");
+        std::fs::write(&cover, png(70)).unwrap();
+        set_mtime(&cover, 1_600_000_000);
+    })
+    .await;
+    let cookie = app.login(ADMIN).await;
+    let (alpha, _) = app.ids("Alpha");
+    let (beta, _) = app.ids("Beta");
+
+    // The image is the cover, not an entry, and not a change of contents
+    let lib = app.state.library.load();
+    let title = lib.get_title(&alpha).unwrap();
+    assert_eq!(title.entries.len(), 2);
+    assert!(title.cover_path.as_ref().unwrap().ends_with("Cover.PNG"));
+    let plain = setup().await;
+    let (plain_alpha, _) = plain.ids("Alpha");
+    assert_eq!(
+        title.contents_signature,
+        plain
+            .state
+            .library
+            .load()
+            .get_title(&plain_alpha)
+            .unwrap()
+            .contents_signature
+    );
+    assert!(lib.get_title(&beta).unwrap().cover_path.is_none());
+    drop(lib);
+
+    // Alpha shows its folder cover, Beta without one its first entry's
+    let title_cover = |tid: &str| format!("/api/title/{}/cover", tid);
+    let response = app.get(&title_cover(&alpha), Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/jpeg");
+    assert!(cover_shade(&body_bytes(response).await).abs_diff(70) <= 2);
+    let response = app.get(&title_cover(&beta), Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(cover_shade(&body_bytes(response).await).abs_diff(50) <= 2);
+
+    // Replacing the file changes the cover without a rescan
+    let cover = app.state.config.library_path.join("Alpha/Cover.PNG");
+    std::fs::write(&cover, png(90)).unwrap();
+    set_mtime(&cover, 1_700_000_000);
+    let response = app.get(&title_cover(&alpha), Some(&cookie)).await;
+    assert!(cover_shade(&body_bytes(response).await).abs_diff(90) <= 2);
+
+    // Uploading a title cover reports that the folder cover still wins
+    let response = upload_cover(&app, &cookie, &format!("tid={}", alpha), &png(10)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(result["folder_cover"], "Cover.PNG");
+
+    // Once the file is gone the next scan falls back to the first entry's cover
+    std::fs::remove_file(&cover).unwrap();
+    scan_library(&app, &cookie, "/api/admin/scan").await;
+    assert!(app
+        .state
+        .library
+        .load()
+        .get_title(&alpha)
+        .unwrap()
+        .cover_path
+        .is_none());
+    let response = app.get(&title_cover(&alpha), Some(&cookie)).await;
+    assert!(cover_shade(&body_bytes(response).await).abs_diff(10) <= 2);
+}
+
+/// Send a request the way Komga clients do: Basic auth, JSON in and out
+async fn komga_request(
+    app: &TestApp,
+    method: &str,
+    uri: &str,
+    body: Option<serde_json::Value>,
+) -> Response {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, basic_auth(READER))
+        .header(header::ACCEPT, "application/json");
+    let request = match body {
+        Some(body) => request
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    };
+    app.send(request.unwrap()).await
+}
+
+async fn komga_book(app: &TestApp, eid: &str) -> serde_json::Value {
+    let response = komga_request(app, "GET", &format!("/api/v1/books/{}", eid), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    serde_json::from_slice(&body_bytes(response).await).unwrap()
+}
+
+#[tokio::test]
+async fn test_komga_read_progress() {
+    let app = setup_with(|config, _| config.komga_compat_enabled = true).await;
+    let (tid, eid) = app.ids("Alpha");
+    let progress_uri = format!("/api/v1/books/{}/read-progress", eid);
+
+    // A book is an entry under the same ID, in a series that is its title
+    let book = komga_book(&app, &eid).await;
+    assert_eq!(book["id"], eid.as_str());
+    assert_eq!(book["seriesId"], tid.as_str());
+    assert_eq!(book["seriesTitle"], "Alpha");
+    assert_eq!(book["name"], "Chapter 1");
+    assert_eq!(book["number"], 1);
+    assert_eq!(book["media"]["pagesCount"], 3);
+    assert!(book["readProgress"].is_null());
+
+    // Page turn: {"page": 2, "completed": false}
+    let response = komga_request(
+        &app,
+        "PATCH",
+        &progress_uri,
+        Some(serde_json::json!({"page": 2, "completed": false})),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let lib = app.state.library.load();
+    assert_eq!(
+        lib.progress_cache().get_progress(&tid, READER.0, &eid),
+        Some(2)
+    );
+    drop(lib);
+    let book = komga_book(&app, &eid).await;
+    assert_eq!(book["readProgress"]["page"], 2);
+    assert_eq!(book["readProgress"]["completed"], false);
+    assert!(book["readProgress"]["readDate"].is_string());
+
+    // Mark as read: {"completed": true}
+    let response = komga_request(
+        &app,
+        "PATCH",
+        &progress_uri,
+        Some(serde_json::json!({"completed": true})),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let book = komga_book(&app, &eid).await;
+    assert_eq!(book["readProgress"]["page"], 3);
+    assert_eq!(book["readProgress"]["completed"], true);
+
+    // Pages past the end and empty updates are rejected
+    for body in [serde_json::json!({"page": 4}), serde_json::json!({})] {
+        let response = komga_request(&app, "PATCH", &progress_uri, Some(body)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let response = komga_request(&app, "GET", "/api/v1/books/nope", None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Without credentials clients get a Basic challenge, not the login page
+    let response = app.get(&format!("/api/v1/books/{}", eid), None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+}
+
+#[tokio::test]
+async fn test_komga_unimplemented_endpoints() {
+    let app = setup_with(|config, _| config.komga_compat_enabled = true).await;
+    let (tid, eid) = app.ids("Alpha");
+
+    for (method, uri) in [
+        ("GET", "/api/v1/series".to_string()),
+        ("GET", format!("/api/v1/series/{}/books", tid)),
+        ("GET", format!("/api/v1/books/{}/pages/1", eid)),
+        ("DELETE", format!("/api/v1/books/{}/read-progress", eid)),
+        ("PATCH", format!("/api/v1/books/{}", eid)),
+    ] {
+        let response = komga_request(&app, method, &uri, None).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::NOT_IMPLEMENTED,
+            "{} {}",
+            method,
+            uri
+        );
+    }
+
+    // Nothing is served under the prefix unless enabled
+    let app = setup().await;
+    let response = komga_request(&app, "GET", &format!("/api/v1/books/{}", eid), None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+/// A two page archive in the library root
+fn write_loose_archive(root: &Path) {
+    write_stored_zip(
+        &root.join("Oneshot.zip"),
+        &[("001.png", png(70)), ("002.png", png(80))],
+    );
+}
+
+/// ID of a title by name and of its only entry
+fn loose_ids(app: &TestApp, name: &str) -> (String, String) {
+    let library = app.state.library.load();
+    let title = library
+        .get_titles()
+        .into_iter()
+        .find(|t| t.title == name)
+        .unwrap_or_else(|| panic!("title {} not scanned", name));
+    assert_eq!(title.entries.len(), 1);
+    (title.id.clone(), title.entries[0].id.clone())
+}
+
+async fn library_names(app: &TestApp, cookie: &str) -> Vec<String> {
+    let response = app.get("/api/library", Some(cookie)).await;
+    let titles: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let mut names: Vec<String> = titles
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["title"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn test_loose_archives_unsorted_title() {
+    let app = setup_with(|_, root| write_loose_archive(root)).await;
+    let cookie = app.login(READER).await;
+
+    assert_eq!(
+        library_names(&app, &cookie).await,
+        ["Alpha", "Beta", "Unsorted"]
+    );
+    let (tid, eid) = loose_ids(&app, "Unsorted");
+    let response = app
+        .get(&format!("/api/page/{}/{}/2", tid, eid), Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_bytes(response).await, png(80));
+
+    // The library page marks the bucket
+    let html = body_text(app.get("/library", Some(&cookie)).await).await;
+    assert_eq!(html.matches(">Unsorted</span>").count(), 1);
+
+    // Progress lives outside the library root's own info.json and survives a rescan
+    let uri = format!("/api/progress/{}/{}", tid, eid);
+    let response = post_json(&app, &uri, &cookie, serde_json::json!({"page": 2})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let admin = app.login(ADMIN).await;
+    scan_library(&app, &admin, "/api/admin/scan").await;
+    assert_eq!(loose_ids(&app, "Unsorted"), (tid, eid));
+    let progress: serde_json::Value =
+        serde_json::from_slice(&body_bytes(app.get(&uri, Some(&cookie)).await).await).unwrap();
+    assert_eq!(progress["page"], 2);
+}
+
+#[tokio::test]
+async fn test_loose_archives_as_titles() {
+    let app = setup_with(|config, root| {
+        config.loose_archives = LooseArchives::Title;
+        write_loose_archive(root);
+    })
+    .await;
+    let cookie = app.login(READER).await;
+
+    assert_eq!(
+        library_names(&app, &cookie).await,
+        ["Alpha", "Beta", "Oneshot"]
+    );
+    let (tid, eid) = loose_ids(&app, "Oneshot");
+    let response = app
+        .get(&format!("/api/page/{}/{}/1", tid, eid), Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_bytes(response).await, png(70));
+
+    let html = body_text(app.get("/library", Some(&cookie)).await).await;
+    assert!(!html.contains(">Unsorted</span>"));
+}
+
+#[tokio::test]
+async fn test_loose_archive_moved_into_title_keeps_progress() {
+    let app = setup_with(|_, root| write_loose_archive(root)).await;
+    let cookie = app.login(READER).await;
+    let (tid, eid) = loose_ids(&app, "Unsorted");
+    let response = post_json(
+        &app,
+        &format!("/api/progress/{}/{}", tid, eid),
+        &cookie,
+        serde_json::json!({"page": 2}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Sorting the archive into a series directory is a move, not a new entry
+    let root = app.state.config.library_path.clone();
+    std::fs::rename(root.join("Oneshot.zip"), root.join("Alpha/Oneshot.zip")).unwrap();
+    let admin = app.login(ADMIN).await;
+    scan_library(&app, &admin, "/api/admin/scan").await;
+
+    assert_eq!(library_names(&app, &cookie).await, ["Alpha", "Beta"]);
+    let (alpha, _) = app.ids("Alpha");
+    let library = app.state.library.load();
+    let entry = library
+        .get_title(&alpha)
+        .unwrap()
+        .entries
+        .iter()
+        .find(|e| e.title == "Oneshot")
+        .unwrap();
+    assert_eq!(entry.id, eid);
+    drop(library);
+
+    let response = app
+        .get(&format!("/api/progress/{}/{}", alpha, eid), Some(&cookie))
+        .await;
+    let progress: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(progress["page"], 2);
+}
+
+#[tokio::test]
+async fn test_concurrent_create_user_conflicts() {
+    let app = setup().await;
+    let cookie = app.login(ADMIN).await;
+    let body =
+        serde_json::json!({"username": "twin", "password": "twin-password", "is_admin": false});
+
+    let (first, second) = tokio::join!(
+        post_json(&app, "/api/admin/users", &cookie, body.clone()),
+        post_json(&app, "/api/admin/users", &cookie, body.clone()),
+    );
+    let mut statuses = [first.status(), second.status()];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::CREATED, StatusCode::CONFLICT]);
+
+    // Creating it again later is a conflict too, not a server error
+    let response = post_json(&app, "/api/admin/users", &cookie, body).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_user_facing_pages_hide_library_root() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (tid, eid) = app.ids("Alpha");
+    let root = app.state.config.library_path.display().to_string();
+
+    for uri in [
+        format!("/book/{}", tid),
+        format!("/api/title/{}", tid),
+        format!("/reader/{}/{}/1", tid, eid),
+        "/".to_string(),
+    ] {
+        let response = app.get(&uri, Some(&cookie)).await;
+        assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        let body = body_text(response).await;
+        assert!(!body.contains(&root), "{} shows the library root", uri);
+    }
+
+    // The book page still tells entries apart by their path within the library
+    let book = body_text(app.get(&format!("/book/{}", tid), Some(&cookie)).await).await;
+    assert!(book.contains("Alpha%2FChapter%201%2Ezip"));
+
+    // Admins can look up where the files are
+    let uri = format!("/api/admin/title/{}/files", tid);
+    let response = app.get(&uri, Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let admin = app.login(ADMIN).await;
+    let files: serde_json::Value =
+        serde_json::from_slice(&body_bytes(app.get(&uri, Some(&admin)).await).await).unwrap();
+    assert_eq!(files["relative_path"], "Alpha");
+    assert!(files["path"].as_str().unwrap().starts_with(&root));
+    let entry = files["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["id"] == eid.as_str())
+        .unwrap();
+    assert_eq!(entry["relative_path"], "Alpha/Chapter 1.zip");
+}
+
+#[tokio::test]
+async fn test_stale_cache_verification_applies_diff() {
+    let app = setup().await;
+    let cookie = app.login(ADMIN).await;
+    let (alpha_id, alpha_entry) = app.ids("Alpha");
+
+    // The library changes while the server is down, after the cache was written
+    let root = app.state.config.library_path.clone();
+    let gamma = root.join("Gamma");
+    std::fs::create_dir_all(&gamma).unwrap();
+    write_stored_zip(&gamma.join("Chapter 1.zip"), &[("001.png", png(60))]);
+    std::fs::remove_dir_all(root.join("Beta")).unwrap();
+
+    // The running library stands in for the one loaded from cache
+    let cached = app.state.library.load_full();
+    let mut library = Library::new(root, app.state.storage.clone(), &app.state.config);
+    let verification = library.verify_cache(&cached).await.unwrap();
+    assert_eq!(verification.checked, 2);
+    assert_eq!(verification.unchanged, 1);
+    assert_eq!(verification.changed, 0);
+    assert_eq!(verification.added, 1);
+    assert_eq!(verification.removed, 1);
+    assert_eq!(verification.stale, 2);
+    app.state.scan_status.set_cache_verification(verification);
+    app.state.library.store(Arc::new(library));
+
+    assert_eq!(library_names(&app, &cookie).await, ["Alpha", "Gamma"]);
+    assert_eq!(app.ids("Alpha"), (alpha_id, alpha_entry));
+
+    let response = app.get("/api/admin/scan/status", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let status: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(status["cache_verification"]["added"], 1);
+    assert_eq!(status["cache_verification"]["removed"], 1);
+    assert_eq!(status["cache_verification"]["stale"], 2);
+
+    // Kept per app
+    let other = setup().await;
+    let other_cookie = other.login(ADMIN).await;
+    let response = other
+        .get("/api/admin/scan/status", Some(&other_cookie))
+        .await;
+    let status: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert!(status["cache_verification"].is_null());
+}
+
+#[tokio::test]
+async fn test_hidden_title_is_unavailable_until_unhidden() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let admin = app.login(ADMIN).await;
+    let (tid, eid) = app.ids("Beta");
+    let progress_uri = format!("/api/progress/{}/{}", tid, eid);
+    let response = post_json(&app, &progress_uri, &cookie, serde_json::json!({"page": 2})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Hide the title, and stash a draft archive in another one
+    let root = app.state.config.library_path.clone();
+    std::fs::rename(root.join("Beta"), root.join("_Beta")).unwrap();
+    write_stored_zip(&root.join("Alpha/.draft.zip"), &[("001.png", png(70))]);
+    scan_library(&app, &admin, "/api/admin/scan").await;
+
+    assert_eq!(library_names(&app, &cookie).await, ["Alpha"]);
+    let (alpha, _) = app.ids("Alpha");
+    let library = app.state.library.load();
+    assert_eq!(library.get_title(&alpha).unwrap().entries.len(), 2);
+    drop(library);
+    for (table, id) in [("titles", &tid), ("ids", &eid)] {
+        let query = format!("SELECT unavailable FROM {} WHERE id = ?", table);
+        let unavailable: i64 = sqlx::query_scalar(&query)
+            .bind(id)
+            .fetch_one(app.state.storage.pool())
+            .await
+            .unwrap();
+        assert_eq!(unavailable, 1, "{} is hidden", id);
+    }
+
+    // Unhidden, it comes back under the same IDs with its progress
+    std::fs::rename(root.join("_Beta"), root.join("Beta")).unwrap();
+    scan_library(&app, &admin, "/api/admin/scan").await;
+
+    assert_eq!(library_names(&app, &cookie).await, ["Alpha", "Beta"]);
+    assert_eq!(app.ids("Beta"), (tid, eid));
+    let response = app.get(&progress_uri, Some(&cookie)).await;
+    let progress: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(progress["page"], 2);
+}
+
+#[tokio::test]
+async fn test_versioned_image_urls_are_immutable() {
+    let app = setup().await;
+    let cookie = app.login(ADMIN).await;
+    let (tid, eid) = app.ids("Alpha");
+    let immutable = "public, max-age=31536000, immutable";
+    let cache_control = |response: &Response| {
+        response.headers()[header::CACHE_CONTROL]
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+    let entry_cover_url = || async {
+        let response = app.get(&format!("/api/title/{}", tid), Some(&cookie)).await;
+        let title: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        let entry = title["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["id"] == eid.as_str())
+            .unwrap()
+            .clone();
+        entry["cover_url"].as_str().unwrap().to_string()
+    };
+
+    // Server-built cover URLs carry a version and are cached for good
+    let versioned = entry_cover_url().await;
+    let unversioned = format!("/api/cover/{}/{}", tid, eid);
+    assert!(versioned.starts_with(&format!("{}?v=", unversioned)));
+    let response = app.get(&versioned, Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(cache_control(&response), immutable);
+
+    // Old URLs keep working, cached briefly and revalidated by ETag
+    let response = app.get(&unversioned, Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(cache_control(&response), "public, max-age=300");
+    let etag = response.headers()[header::ETAG].clone();
+    let response = app
+        .send(
+            Request::get(unversioned.as_str())
+                .header(header::COOKIE, &cookie)
+                .header(header::IF_NONE_MATCH, etag)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    // The reader's page URLs are versioned the same way
+    let response = app
+        .get(&format!("/reader/{}/{}/1", tid, eid), Some(&cookie))
+        .await;
+    let html = body_text(response).await;
+    let version = html
+        .split("const page_version = \"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap()
+        .to_string();
+    let page = format!("/api/page/{}/{}/1", tid, eid);
+    let response = app
+        .get(&format!("{}?v={}", page, version), Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(cache_control(&response), immutable);
+    let response = app.get(&page, Some(&cookie)).await;
+    assert_eq!(cache_control(&response), "public, max-age=300");
+    assert!(response.headers().contains_key(header::ETAG));
+
+    // Uploading a cover gives it a new version; the old one is no longer immutable
+    let query = format!("tid={}&eid={}", tid, eid);
+    let response = upload_cover(&app, &cookie, &query, &png(10)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let overridden = entry_cover_url().await;
+    assert_ne!(overridden, versioned);
+    let response = app.get(&overridden, Some(&cookie)).await;
+    assert_eq!(cache_control(&response), immutable);
+    let response = app.get(&versioned, Some(&cookie)).await;
+    assert_eq!(cache_control(&response), "public, max-age=300");
+}
+
+#[tokio::test]
+async fn test_bulk_read_up_to_chapter() {
+    let app = setup_with(|_, root| {
+        let alpha = root.join("Alpha");
+        write_stored_zip(&alpha.join("Chapter 10.zip"), &[("001.png", png(60))]);
+        write_stored_zip(&alpha.join("Chapter 11.zip"), &[("001.png", png(70))]);
+    })
+    .await;
+    let cookie = app.login(READER).await;
+    let (alpha, _) = app.ids("Alpha");
+    let (beta, beta_entry) = app.ids("Beta");
+    let pages = |tid: &str, name: &str| {
+        let lib = app.state.library.load();
+        let entry = lib
+            .get_title(tid)
+            .unwrap()
+            .entries
+            .iter()
+            .find(|e| e.title == name)
+            .unwrap()
+            .id
+            .clone();
+        lib.progress_cache().get_progress(tid, READER.0, &entry)
+    };
+    let body = serde_json::json!({
+        "title_ids": [beta],
+        "up_to": [{ "title_id": alpha, "key": "10" }],
+        "dry_run": true,
+    });
+
+    // A dry run counts chapters 1, 2 and 10 without saving anything
+    let response = post_json(&app, "/api/progress/bulk_read", &cookie, body.clone()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["total"], 4);
+    assert_eq!(report["titles"][0]["title_id"], beta.as_str());
+    assert_eq!(report["titles"][0]["entries"], 1);
+    assert_eq!(report["titles"][1]["title_id"], alpha.as_str());
+    assert_eq!(report["titles"][1]["entries"], 3);
+    assert_eq!(pages(&alpha, "Chapter 10"), None);
+
+    let mut body = body;
+    body["dry_run"] = serde_json::json!(false);
+    let response = post_json(&app, "/api/progress/bulk_read", &cookie, body.clone()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(report["total"], 4);
+    assert_eq!(pages(&alpha, "Chapter 1"), Some(3));
+    assert_eq!(pages(&alpha, "Chapter 10"), Some(1));
+    assert_eq!(pages(&alpha, "Chapter 11"), None);
+    let progress =
+        app.state
+            .library
+            .load()
+            .progress_cache()
+            .get_progress(&beta, READER.0, &beta_entry);
+    assert_eq!(progress, Some(1));
+
+    // Entries already read are not counted again
+    let response = post_json(&app, "/api/progress/bulk_read", &cookie, body).await;
+    let report: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(report["total"], 0);
+
+    // Unknown titles and empty requests are rejected
+    let response = post_json(
+        &app,
+        "/api/progress/bulk_read",
+        &cookie,
+        serde_json::json!({ "title_ids": ["missing"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = post_json(
+        &app,
+        "/api/progress/bulk_read",
+        &cookie,
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Generate (or regenerate) the user's feed token, returning it
+async fn create_feed_token(app: &TestApp, cookie: &str) -> String {
+    let response = app
+        .send(
+            Request::post("/api/user/feed-token")
+                .header(header::COOKIE, cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(
+        created["recent_url"],
+        format!(
+            "/feed/recent.atom?token={}",
+            created["token"].as_str().unwrap()
+        )
+    );
+    created["token"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_atom_feeds_with_feed_token() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (alpha, _) = app.ids("Alpha");
+    let title_feed = |token: &str| format!("/feed/title/{}.atom?token={}", alpha, token);
+
+    // Feeds take no session, only a valid feed token
+    let response = app.get("/feed/recent.atom", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.get("/feed/recent.atom?token=nope", None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let token = create_feed_token(&app, &cookie).await;
+    let response = app
+        .get(&format!("/feed/recent.atom?token={}", token), None)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("application/atom+xml"));
+    let xml = body_text(response).await;
+    assert_well_formed_xml(&xml);
+    assert!(xml.contains("<title>Alpha - Chapter 2</title>"));
+    assert!(xml.contains("<title>Beta - Chapter 1</title>"));
+    assert!(xml.contains("<updated>"));
+
+    // A title feed lists that title's entries with links into the reader
+    let response = app.get(&title_feed(&token), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let xml = body_text(response).await;
+    assert_well_formed_xml(&xml);
+    assert!(xml.contains("<title>Chapter 1</title>"));
+    assert!(xml.contains(&format!("href=\"/reader/{}/", alpha)));
+    assert!(!xml.contains("Beta"));
+    let response = app
+        .get(&format!("/feed/title/{}?token={}", alpha, token), None)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // The book page links the title feed
+    let response = app.get(&format!("/book/{}", alpha), Some(&cookie)).await;
+    assert!(body_text(response).await.contains(&title_feed(&token)));
+
+    // Regenerating revokes the old token, revoking stops the new one
+    let new_token = create_feed_token(&app, &cookie).await;
+    assert_ne!(new_token, token);
+    let response = app.get(&title_feed(&token), None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.get(&title_feed(&new_token), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .send(
+            Request::delete("/api/user/feed-token")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.get(&title_feed(&new_token), None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.get("/api/user/feed-token", Some(&cookie)).await;
+    let feed: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert!(feed["token"].is_null());
+}
+
+#[tokio::test]
+async fn test_entry_display_titles_are_cleaned_up() {
+    let app = setup_with(|_, root| {
+        let gamma = root.join("Gamma");
+        std::fs::create_dir_all(&gamma).unwrap();
+        write_stored_zip(
+            &gamma.join("[Scans] Gamma - c001 (v01) [x1200] [Digital].zip"),
+            &[("001.png", png(60))],
+        );
+    })
+    .await;
+    let cookie = app.login(READER).await;
+
+    let library = app.state.library.load();
+    let gamma = library
+        .get_titles()
+        .into_iter()
+        .find(|t| t.title == "Gamma")
+        .unwrap();
+    let (tid, eid) = (gamma.id.clone(), gamma.entries[0].id.clone());
+    drop(library);
+
+    // The API keeps the file name for sorting and adds the name to show
+    let response = app.get(&format!("/api/title/{}", tid), Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let title: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let entry = &title["entries"][0];
+    assert_eq!(
+        entry["title"],
+        "[Scans] Gamma - c001 (v01) [x1200] [Digital]"
+    );
+    assert_eq!(entry["display_title"], "c001 (v01)");
+
+    let reader = body_text(
+        app.get(&format!("/reader/{}/{}/1", tid, eid), Some(&cookie))
+            .await,
+    )
+    .await;
+    assert!(reader.contains(">c001 (v01)<"));
+
+    // Names without tags are left alone
+    let (tid, _) = app.ids("Alpha");
+    let response = app.get(&format!("/api/title/{}", tid), Some(&cookie)).await;
+    let title: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(title["entries"][0]["display_title"], "Chapter 1");
+}
+
+#[tokio::test]
+async fn test_read_only_database_keeps_serving_reads() {
+    let mut app = setup().await;
+    let cookie = app.login(READER).await;
+    let (tid, eid) = app.ids("Alpha");
+
+    // The database stops accepting writes under the running server
+    let read_only = Storage::new(&format!(
+        "sqlite://{}?mode=ro",
+        app.state.config.db_path.display()
+    ))
+    .await
+    .unwrap();
+    app.state.storage = read_only;
+    app.app = build_app(app.state.clone()).await.unwrap();
+
+    let reads = [
+        "/api/library".to_string(),
+        format!("/api/title/{}", tid),
+        format!("/api/page/{}/{}/1", tid, eid),
+        format!("/api/cover/{}/{}", tid, eid),
+        "/library".to_string(),
+    ];
+    for uri in &reads {
+        let response = app.get(uri, Some(&cookie)).await;
+        assert_eq!(response.status(), StatusCode::OK, "GET {}", uri);
+    }
+    assert!(!app.state.storage.health().is_unavailable());
+
+    // Writes fail with a clear 503 instead of a raw database error
+    let response = app
+        .send(
+            Request::post("/api/user/feed-token")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body_text(response).await.contains("read-only or full"));
+    assert!(app.state.storage.health().is_unavailable());
+
+    // Pages warn about it, and reads still work
+    let library = body_text(app.get("/library", Some(&cookie)).await).await;
+    assert!(library.contains("cannot be saved right now"));
+    for uri in &reads {
+        let response = app.get(uri, Some(&cookie)).await;
+        assert_eq!(response.status(), StatusCode::OK, "GET {}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_image_directory_is_an_entry() {
+    let app = setup_with(|_, root| {
+        let chapter = root.join("Gamma").join("Chapter 3");
+        std::fs::create_dir_all(&chapter).unwrap();
+        // Natural order: page 2 comes before page 10
+        std::fs::write(chapter.join("10.png"), png(100)).unwrap();
+        std::fs::write(chapter.join("2.png"), png(20)).unwrap();
+        std::fs::write(chapter.join("notes.txt"), b"not a page").unwrap();
+        // Directories without images are not entries
+        std::fs::create_dir_all(root.join("Gamma").join("extras")).unwrap();
+    })
+    .await;
+    let cookie = app.login(READER).await;
+
+    let library = app.state.library.load();
+    let gamma = library
+        .get_titles()
+        .into_iter()
+        .find(|t| t.title == "Gamma")
+        .unwrap();
+    assert_eq!(gamma.entries.len(), 1);
+    let entry = &gamma.entries[0];
+    assert_eq!(entry.title, "Chapter 3");
+    assert_eq!(entry.image_files, vec!["2.png", "10.png"]);
+    let (tid, eid, signature) = (gamma.id.clone(), entry.id.clone(), entry.signature.clone());
+    drop(library);
+
+    let response = app
+        .get(&format!("/api/page/{}/{}/1", tid, eid), Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_bytes(response).await, png(20));
+
+    let response = app
+        .get(&format!("/api/download/{}/{}", tid, eid), Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // The entry keeps its ID across scans
+    let mut library = Library::new(
+        app.state.config.library_path.clone(),
+        app.state.storage.clone(),
+        &app.state.config,
+    );
+    library.scan().await.unwrap();
+    let gamma = library
+        .get_titles()
+        .into_iter()
+        .find(|t| t.title == "Gamma")
+        .unwrap();
+    assert_eq!(gamma.entries[0].id, eid);
+    assert_eq!(gamma.entries[0].signature, signature);
+}
+
+#[tokio::test]
+async fn test_opds_leaves_out_image_directories() {
+    let app = setup_with(|_, root| {
+        let chapter = root.join("Alpha").join("Chapter 3");
+        std::fs::create_dir_all(&chapter).unwrap();
+        std::fs::write(chapter.join("1.png"), png(100)).unwrap();
+    })
+    .await;
+    let (tid, eid) = app.ids("Alpha");
+    let directory = app
+        .state
+        .library
+        .load()
+        .get_title(&tid)
+        .unwrap()
+        .entries
+        .iter()
+        .find(|e| e.title == "Chapter 3")
+        .unwrap()
+        .id
+        .clone();
+
+    // Every acquisition link in the feed can be downloaded
+    let xml = opds_feed(&app, &format!("/opds/book/{}", tid), READER).await;
+    assert!(xml.contains(&format!("/api/download/{}/{}", tid, eid)));
+    assert!(!xml.contains(&directory));
+    let cookie = app.login(READER).await;
+    for (at, _) in xml.match_indices("/api/download/") {
+        let url = &xml[at..];
+        let url = &url[..url.find('"').unwrap()];
+        let response = app.get(url, Some(&cookie)).await;
+        assert_eq!(response.status(), StatusCode::OK, "GET {}", url);
+    }
+}
+
+#[tokio::test]
+async fn test_nested_titles() {
+    let app = setup_with(|_, root| {
+        for volume in ["Volume 10", "Volume 2"] {
+            let dir = root.join("Gamma").join(volume);
+            std::fs::create_dir_all(&dir).unwrap();
+            write_stored_zip(&dir.join("ch1.zip"), &[("001.png", png(30))]);
+        }
+        // Directories without anything to read are not titles
+        std::fs::create_dir_all(root.join("Gamma").join("extras")).unwrap();
+    })
+    .await;
+    let cookie = app.login(READER).await;
+
+    let library = app.state.library.load();
+    let gamma = library
+        .get_titles()
+        .into_iter()
+        .find(|t| t.title == "Gamma")
+        .unwrap();
+    let gamma_id = gamma.id.clone();
+    drop(library);
+
+    let response = app
+        .get(&format!("/api/title/{}", gamma_id), Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let title: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(title["entries"].as_array().unwrap().len(), 0);
+    let nested = title["titles"].as_array().unwrap();
+    let names: Vec<&str> = nested
+        .iter()
+        .map(|t| t["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["Volume 2", "Volume 10"]);
+    let volume_id = nested[0]["id"].as_str().unwrap().to_string();
+
+    // A nested title is reachable by its ID and knows its parent
+    let response = app
+        .get(&format!("/api/title/{}", volume_id), Some(&cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let volume: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(volume["parent_id"], gamma_id.as_str());
+    let entry_id = volume["entries"][0]["id"].as_str().unwrap().to_string();
+
+    let book = body_text(
+        app.get(&format!("/book/{}", volume_id), Some(&cookie))
+            .await,
+    )
+    .await;
+    assert!(book.contains(&format!("/book/{}", gamma_id)));
+    let book = body_text(app.get(&format!("/book/{}", gamma_id), Some(&cookie)).await).await;
+    assert!(book.contains("2 titles"));
+
+    let response = app
+        .get(
+            &format!("/api/page/{}/{}/1", volume_id, entry_id),
+            Some(&cookie),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Progress in a nested title counts for the titles above it
+    let response = app
+        .send(
+            Request::post(format!("/api/progress/{}/{}", volume_id, entry_id).as_str())
+                .header(header::COOKIE, &cookie)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"page":1}"#))
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let library = app.state.library.load();
+    let gamma = library.get_title(&gamma_id).unwrap();
+    assert_eq!(library.get_title_progress(gamma, READER.0), 50.0);
+
+    // Nested titles keep their IDs across scans
+    let mut rescanned = Library::new(
+        app.state.config.library_path.clone(),
+        app.state.storage.clone(),
+        &app.state.config,
+    );
+    rescanned.scan().await.unwrap();
+    assert!(rescanned.get_title(&volume_id).is_some());
+    assert!(rescanned.get_entry(&volume_id, &entry_id).is_some());
+}
+
+async fn put_preferences(app: &TestApp, cookie: &str, body: serde_json::Value) -> Response {
+    app.send(
+        Request::put("/api/user/preferences")
+            .header(header::COOKIE, cookie)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_user_preferences_round_trip() {
+    let app = setup_with(|config, _| {
+        config.library_page_size = 24;
+        config.library_max_page_size = 50;
+    })
+    .await;
+    let cookie = app.login(READER).await;
+
+    // Nothing chosen yet: the instance defaults apply
+    let response = app.get("/api/user/preferences", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let prefs: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert!(prefs["page_size"].is_null());
+    assert_eq!(prefs["effective_page_size"], 24);
+    assert_eq!(prefs["effective_card_density"], "comfortable");
+    assert_eq!(prefs["max_page_size"], 50);
+
+    // Page sizes above the maximum are clamped
+    let response = put_preferences(
+        &app,
+        &cookie,
+        serde_json::json!({"page_size": 1000, "card_density": "list"}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.get("/api/user/preferences", Some(&cookie)).await;
+    let prefs: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(prefs["page_size"], 50);
+    assert_eq!(prefs["card_density"], "list");
+
+    // Invalid values are refused and leave the saved ones alone
+    let response = put_preferences(&app, &cookie, serde_json::json!({"page_size": 0})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response =
+        put_preferences(&app, &cookie, serde_json::json!({"card_density": "dense"})).await;
+    assert!(response.status().is_client_error());
+    let response = app.get("/api/user/preferences", Some(&cookie)).await;
+    let prefs: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(prefs["page_size"], 50);
+
+    // Preferences are per user
+    let admin = app.login(ADMIN).await;
+    let response = app.get("/api/user/preferences", Some(&admin)).await;
+    let prefs: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert!(prefs["card_density"].is_null());
+
+    // Clearing a field goes back to the default
+    put_preferences(&app, &cookie, serde_json::json!({"card_density": "list"})).await;
+    let response = app.get("/api/user/preferences", Some(&cookie)).await;
+    let prefs: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert!(prefs["page_size"].is_null());
+    assert_eq!(prefs["effective_page_size"], 24);
+}
+
+#[tokio::test]
+async fn test_library_page_size_and_density() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+
+    // By default every title is on one page, as cards
+    let page = body_text(app.get("/library", Some(&cookie)).await).await;
+    assert!(page.contains("density-comfortable"));
+    assert!(page.contains(r#"data-title="Alpha""#));
+    assert!(page.contains(r#"data-title="Beta""#));
+    assert!(!page.contains("library-pagination"));
+
+    let response = put_preferences(
+        &app,
+        &cookie,
+        serde_json::json!({"page_size": 1, "card_density": "list"}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let page = body_text(app.get("/library", Some(&cookie)).await).await;
+    assert!(page.contains("density-list"));
+    assert!(page.contains("library-row"));
+    assert!(page.contains("2 titles found"));
+    assert!(page.contains("Page 1 of 2"));
+    assert!(page.contains(r#"data-title="Alpha""#));
+    assert!(!page.contains(r#"data-title="Beta""#));
+
+    let page = body_text(app.get("/library?page=2", Some(&cookie)).await).await;
+    assert!(page.contains(r#"data-title="Beta""#));
+    assert!(!page.contains(r#"data-title="Alpha""#));
+
+    // Pages past the end show the last one
+    let page = body_text(app.get("/library?page=9", Some(&cookie)).await).await;
+    assert!(page.contains("Page 2 of 2"));
+}
+
+#[tokio::test]
+async fn test_hide_completed_titles() {
+    let app = setup_with(|_, root| {
+        let gamma = root.join("Gamma");
+        std::fs::create_dir_all(&gamma).unwrap();
+        write_stored_zip(&gamma.join("Chapter 1.zip"), &[("001.png", png(60))]);
+    })
+    .await;
+    let cookie = app.login(READER).await;
+
+    // Beta is finished, Alpha half read and Gamma untouched
+    let (beta, beta_entry) = app.ids("Beta");
+    let uri = format!("/api/progress/{}/{}", beta, beta_entry);
+    post_json(&app, &uri, &cookie, serde_json::json!({"page": 1})).await;
+    let (alpha, alpha_entry) = app.ids("Alpha");
+    let uri = format!("/api/progress/{}/{}", alpha, alpha_entry);
+    post_json(&app, &uri, &cookie, serde_json::json!({"page": 2})).await;
+
+    let library = |uri: &'static str| {
+        let app = &app;
+        let cookie = &cookie;
+        async move {
+            let response = app.get(uri, Some(cookie)).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let hidden = response.headers()["x-hidden-completed"]
+                .to_str()
+                .unwrap()
+                .parse::<usize>()
+                .unwrap();
+            let titles: serde_json::Value =
+                serde_json::from_slice(&body_bytes(response).await).unwrap();
+            let names = titles
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t["title"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>();
+            (names, hidden)
+        }
+    };
+
+    // Nothing is hidden until the user asks for it
+    let (names, hidden) = library("/api/library").await;
+    assert_eq!(names, ["Alpha", "Beta", "Gamma"]);
+    assert_eq!(hidden, 0);
+
+    let response = app
+        .send(
+            Request::put("/api/user/preferences/hide-completed")
+                .header(header::COOKIE, &cookie)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"hide_completed":true}"#))
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let prefs: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(prefs["hide_completed"], true);
+
+    let (names, hidden) = library("/api/library").await;
+    assert_eq!(names, ["Alpha", "Gamma"]);
+    assert_eq!(hidden, 1);
+    let (names, hidden) = library("/api/library?include_completed=1").await;
+    assert_eq!(names, ["Alpha", "Beta", "Gamma"]);
+    assert_eq!(hidden, 0);
+
+    // An explicit progress filter takes precedence over the preference
+    let (names, hidden) = library("/api/library?filter=completed").await;
+    assert_eq!(names, ["Beta"]);
+    assert_eq!(hidden, 0);
+    let (names, _) = library("/api/library?filter=reading").await;
+    assert_eq!(names, ["Alpha"]);
+    let (names, _) = library("/api/library?filter=unread&include_completed=1").await;
+    assert_eq!(names, ["Gamma"]);
+    let (names, _) = library("/api/library?sort=title&ascend=0").await;
+    assert_eq!(names, ["Gamma", "Alpha"]);
+
+    // The letter index counts only the titles shown
+    let response = app.get("/api/library/index_letters", Some(&cookie)).await;
+    let buckets: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let letters: Vec<_> = buckets
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| (b["letter"].as_str().unwrap(), b["offset"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(letters, [("A", 0), ("G", 1)]);
+
+    // Saving the layout leaves the preference alone, and pages hold the filtered titles
+    let response = put_preferences(&app, &cookie, serde_json::json!({"page_size": 1})).await;
+    let prefs: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(prefs["hide_completed"], true);
+
+    let page = body_text(app.get("/library?page=2", Some(&cookie)).await).await;
+    assert!(page.contains("2 titles found · 1 completed hidden"));
+    assert!(page.contains(r#"data-title="Gamma""#));
+    assert!(page.contains("Page 2 of 2"));
+
+    let page = body_text(app.get("/library?include_completed=1", Some(&cookie)).await).await;
+    assert!(page.contains("3 titles found"));
+    assert!(page.contains(r#"href="?page=2&include_completed=1""#));
+
+    // Other users still see everything
+    let admin = app.login(ADMIN).await;
+    let response = app.get("/api/library", Some(&admin)).await;
+    assert_eq!(response.headers()["x-hidden-completed"], "0");
+}
+
+/// Entry titles of a title in the order the title API sorts them
+async fn entry_order(app: &TestApp, cookie: &str, tid: &str, query: &str) -> Vec<String> {
+    let response = app
+        .get(&format!("/api/title/{}?{}", tid, query), Some(cookie))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let title: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    title["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn opened_at(app: &TestApp, cookie: &str, tid: &str, eid: &str) -> serde_json::Value {
+    let response = app
+        .get(&format!("/api/progress/{}/{}", tid, eid), Some(cookie))
+        .await;
+    let progress: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    progress["opened_at"].clone()
+}
+
+#[tokio::test]
+async fn test_entries_sorted_by_last_opened() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (tid, ch1) = app.ids("Alpha");
+    let ch2 = app
+        .state
+        .library
+        .load()
+        .get_title(&tid)
+        .unwrap()
+        .entries
+        .iter()
+        .find(|e| e.title == "Chapter 2")
+        .unwrap()
+        .id
+        .clone();
+    let open = |eid: &str, page: usize| format!("/reader/{}/{}/{}", tid, eid, page);
+
+    assert!(opened_at(&app, &cookie, &tid, &ch1).await.is_null());
+
+    // Chapter 2 first, then Chapter 1
+    assert_eq!(
+        app.get(&open(&ch2, 1), Some(&cookie)).await.status(),
+        StatusCode::OK
+    );
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    app.get(&open(&ch1, 1), Some(&cookie)).await;
+    assert!(opened_at(&app, &cookie, &tid, &ch1).await.is_i64());
+
+    assert_eq!(
+        entry_order(&app, &cookie, &tid, "sort=opened&ascend=0").await,
+        ["Chapter 1", "Chapter 2"]
+    );
+    assert_eq!(
+        entry_order(&app, &cookie, &tid, "sort=opened&ascend=1").await,
+        ["Chapter 2", "Chapter 1"]
+    );
+
+    // Opening Chapter 2 again within the hour is not recorded
+    let first_open = opened_at(&app, &cookie, &tid, &ch2).await;
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    assert_eq!(
+        app.get(&open(&ch2, 1), Some(&cookie)).await.status(),
+        StatusCode::OK
+    );
+    assert_eq!(opened_at(&app, &cookie, &tid, &ch2).await, first_open);
+    assert_eq!(
+        entry_order(&app, &cookie, &tid, "sort=opened&ascend=0").await,
+        ["Chapter 1", "Chapter 2"]
+    );
+
+    // Opens are per user; entries never opened come last in either direction
+    let admin = app.login(ADMIN).await;
+    app.get(&open(&ch2, 1), Some(&admin)).await;
+    for query in ["sort=opened&ascend=0", "sort=opened&ascend=1"] {
+        assert_eq!(
+            entry_order(&app, &admin, &tid, query).await,
+            ["Chapter 2", "Chapter 1"]
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_pages_stay_responsive_during_scan() {
+    // Slowest page response allowed while the scan runs; blocking file I/O on the
+    // runtime thread would hold pages back for the whole scan
+    const LATENCY_BUDGET: std::time::Duration = std::time::Duration::from_millis(500);
+
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+    let cookie = app.login(READER).await;
+    let (tid, eid) = app.ids("Alpha");
+
+    // New series, so the scan has to read every archive
+    let root = app.state.config.library_path.clone();
+    for series in 0..20 {
+        let dir = root.join(format!("Series {:02}", series));
+        std::fs::create_dir_all(&dir).unwrap();
+        for chapter in 0..20u8 {
+            write_stored_zip(
+                &dir.join(format!("Chapter {}.zip", chapter)),
+                &[("001.png", png(chapter)), ("002.png", png(chapter + 1))],
+            );
+        }
+    }
+
+    let scan = scan_library(&app, &admin, "/api/admin/scan");
+    let pages = async {
+        let mut slowest = std::time::Duration::ZERO;
+        for i in 0..30 {
+            let start = std::time::Instant::now();
+            let response = app
+                .get(
+                    &format!("/api/page/{}/{}/{}", tid, eid, i % 3 + 1),
+                    Some(&cookie),
+                )
+                .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            slowest = slowest.max(start.elapsed());
+            tokio::task::yield_now().await;
+        }
+        slowest
+    };
+    let (_, slowest) = tokio::join!(scan, pages);
+
+    assert!(
+        slowest < LATENCY_BUDGET,
+        "a page took {:?} during the scan",
+        slowest
+    );
+    assert!(app
+        .state
+        .library
+        .load()
+        .get_titles()
+        .iter()
+        .any(|t| t.title == "Series 19"));
+}
+
+#[tokio::test]
+async fn test_progress_csv_export() {
+    let odd_name = "Odd, \"Quoted\"\nName";
+    let app = setup_with(|_, root| {
+        let dir = root.join(odd_name);
+        std::fs::create_dir_all(&dir).unwrap();
+        write_stored_zip(
+            &dir.join("Chapter 1.zip"),
+            &[("001.png", png(60)), ("002.png", png(70))],
+        );
+    })
+    .await;
+    let cookie = app.login(READER).await;
+
+    let (alpha, alpha_eid) = app.ids("Alpha");
+    let (odd, odd_eid) = app.ids(odd_name);
+    for (tid, eid, page) in [(&alpha, &alpha_eid, 2), (&odd, &odd_eid, 2)] {
+        let uri = format!("/api/progress/{}/{}", tid, eid);
+        let response = post_json(&app, &uri, &cookie, serde_json::json!({ "page": page })).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let response = app.get("/api/user/progress.csv", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/csv; charset=utf-8"
+    );
+    let disposition = response.headers()[header::CONTENT_DISPOSITION]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    assert_eq!(
+        disposition,
+        format!("attachment; filename=\"mango-progress-{}.csv\"", today)
+    );
+
+    let text = body_text(response).await;
+    assert!(!text.starts_with('\u{FEFF}'));
+    assert!(text.contains("\"Odd, \"\"Quoted\"\"\nName\""));
+
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    assert_eq!(
+        reader.headers().unwrap(),
+        vec![
+            "title",
+            "entry",
+            "pages",
+            "pages_read",
+            "percent",
+            "last_read",
+            "completed"
+        ]
+    );
+    let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+    // Only entries with progress are exported, ordered by title
+    assert_eq!(rows.len(), 2);
+    assert_eq!(&rows[0][0], "Alpha");
+    assert_eq!(&rows[0][1], "Chapter 1");
+    assert_eq!(&rows[0][2], "3");
+    assert_eq!(&rows[0][3], "2");
+    assert_eq!(&rows[0][4], "66.7");
+    assert!(chrono::DateTime::parse_from_rfc3339(&rows[0][5]).is_ok());
+    assert_eq!(&rows[0][6], "false");
+    assert_eq!(&rows[1][0], odd_name);
+    assert_eq!(&rows[1][4], "100.0");
+    assert_eq!(&rows[1][6], "true");
+
+    // Excel needs a byte order mark to read the file as UTF-8
+    let response = app
+        .get("/api/user/progress.csv?excel=1", Some(&cookie))
+        .await;
+    let bytes = body_bytes(response).await;
+    assert!(bytes.starts_with("\u{FEFF}title,".as_bytes()));
+
+    // Progress is per user
+    let admin_cookie = app.login(ADMIN).await;
+    let response = app.get("/api/user/progress.csv", Some(&admin_cookie)).await;
+    let text = body_text(response).await;
+    assert_eq!(
+        csv::Reader::from_reader(text.as_bytes()).records().count(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_static_assets_served_from_binary() {
+    let app = setup().await;
+
+    // Served without a login, from the copy built into the binary
+    let response = app.get("/static/js/common.js", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/javascript");
+    assert!(response.headers()[header::CACHE_CONTROL]
+        .to_str()
+        .unwrap()
+        .contains("max-age="));
+    let etag = response.headers()[header::ETAG].clone();
+    assert_eq!(
+        body_bytes(response).await,
+        include_bytes!("../static/js/common.js")
+    );
+
+    let response = app.get("/static/favicon.ico", None).await;
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/x-icon");
+
+    let response = app
+        .send(
+            Request::get("/static/js/common.js")
+                .header(header::IF_NONE_MATCH, etag)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    for missing in [
+        "/static/js/missing.js",
+        "/static/js",
+        "/static/../Cargo.toml",
+    ] {
+        let response = app.get(missing, None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{}", missing);
+    }
+}
+
+#[tokio::test]
+async fn test_static_path_serves_from_disk() {
+    let app = setup_with(|config, root| {
+        let dir = root.parent().unwrap().join("theme");
+        std::fs::create_dir_all(dir.join("css")).unwrap();
+        std::fs::write(dir.join("css/mango.css"), "body { color: red; }").unwrap();
+        config.static_path = Some(dir);
+    })
+    .await;
+
+    let response = app.get("/static/css/mango.css", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/css");
+    assert_eq!(body_text(response).await, "body { color: red; }");
+
+    // Only the directory is served, not the built-in assets
+    let response = app.get("/static/js/common.js", None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_unreadable_archive_is_quarantined() {
+    let app = setup_with(|_, root| {
+        let gamma = root.join("Gamma");
+        std::fs::create_dir_all(&gamma).unwrap();
+        write_stored_zip(&gamma.join("Chapter 1.zip"), &[("001.png", png(80))]);
+        std::fs::write(gamma.join("Chapter 2.zip"), b"not an archive").unwrap();
+        write_stored_zip(&gamma.join("Chapter 3.zip"), &[("001.png", png(90))]);
+    })
+    .await;
+
+    // The title keeps its readable entries
+    let (tid, _) = app.ids("Gamma");
+    {
+        let library = app.state.library.load();
+        let title = library.get_title(&tid).unwrap();
+        let names: Vec<&str> = title.entries.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(names, ["Chapter 1", "Chapter 3"]);
+        assert_eq!(title.unreadable.len(), 1);
+        assert!(title.unreadable[0].path.ends_with("Gamma/Chapter 2.zip"));
+    }
+
+    // The scan report lists the file by title
+    let admin = app.login(ADMIN).await;
+    let report = scan_library(&app, &admin, "/api/admin/scan").await;
+    let unreadable = report["unreadable"].as_array().unwrap();
+    assert_eq!(unreadable.len(), 1);
+    assert_eq!(unreadable[0]["title"], "Gamma");
+    assert_eq!(unreadable[0]["files"][0]["path"], "Gamma/Chapter 2.zip");
+    assert!(!unreadable[0]["files"][0]["error"]
+        .as_str()
+        .unwrap()
+        .is_empty());
+
+    // Admins see it on the book page, readers don't
+    let (tid, _) = app.ids("Gamma");
+    let uri = format!("/book/{}", tid);
+    let page = body_text(app.get(&uri, Some(&admin)).await).await;
+    assert!(page.contains("1 file could not be read"));
+    assert!(page.contains("Chapter 2.zip"));
+
+    let reader = app.login(READER).await;
+    let page = body_text(app.get(&uri, Some(&reader)).await).await;
+    assert!(page.contains("Chapter 3"));
+    assert!(!page.contains("could not be read"));
+}
+
+#[tokio::test]
+async fn test_incremental_scan_reuses_unchanged_titles() {
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+    let root = app.state.config.library_path.clone();
+    let scan = |uri: &'static str| {
+        let app = &app;
+        let admin = &admin;
+        async move { scan_library(app, admin, uri).await }
+    };
+
+    let report = scan("/api/admin/scan").await;
+    assert_eq!(report["reused"], 2);
+    assert_eq!(report["rescanned"], 0);
+
+    // A new archive rescans only its title
+    let (alpha, alpha_eid) = app.ids("Alpha");
+    write_stored_zip(&root.join("Alpha/Chapter 3.zip"), &[("001.png", png(60))]);
+    let report = scan("/api/admin/scan").await;
+    assert_eq!(report["reused"], 1);
+    assert_eq!(report["rescanned"], 1);
+    {
+        let library = app.state.library.load();
+        let title = library.get_title(&alpha).unwrap();
+        assert_eq!(title.entries.len(), 3);
+        assert!(title.entries.iter().any(|e| e.id == alpha_eid));
+    }
+
+    // An archive rewritten in place keeps its inode; its modification time gives it away
+    let beta_zip = root.join("Beta/Chapter 1.zip");
+    write_stored_zip(&beta_zip, &[("001.png", png(50)), ("002.png", png(55))]);
+    set_mtime(&beta_zip, 1_700_000_000);
+    let report = scan("/api/admin/scan").await;
+    assert_eq!(report["reused"], 1);
+    assert_eq!(report["rescanned"], 1);
+    let (beta, beta_eid) = app.ids("Beta");
+    assert_eq!(
+        app.state
+            .library
+            .load()
+            .get_entry(&beta, &beta_eid)
+            .unwrap()
+            .pages,
+        2
+    );
+
+    // A full scan reads everything
+    let report = scan("/api/admin/scan?full=true").await;
+    assert_eq!(report["reused"], 0);
+    assert_eq!(report["rescanned"], 2);
+}
+
+#[tokio::test]
+async fn test_convert_directory_entry_to_cbz() {
+    let app = setup_with(|_, root| {
+        let chapter = root.join("Gamma").join("Chapter 1");
+        std::fs::create_dir_all(&chapter).unwrap();
+        std::fs::write(chapter.join("001.png"), png(60)).unwrap();
+        std::fs::write(chapter.join("002.png"), png(70)).unwrap();
+    })
+    .await;
+    let cookie = app.login(READER).await;
+    let (tid, eid) = app.ids("Gamma");
+    let uri = format!("/api/progress/{}/{}", tid, eid);
+    post_json(&app, &uri, &cookie, serde_json::json!({"page": 2})).await;
+
+    // Admins only
+    let response = app
+        .send(
+            Request::post("/api/admin/maintenance/convert")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Refused while a scan runs, such as another test's
+    let admin = app.login(ADMIN).await;
+    let mut status = StatusCode::CONFLICT;
+    for _ in 0..50 {
+        let response = app
+            .send(
+                Request::post("/api/admin/maintenance/convert?filter=directories")
+                    .header(header::COOKIE, &admin)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await;
+        status = response.status();
+        if status != StatusCode::CONFLICT {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    // The job rescans the library when it is done
+    let mut converted = false;
+    for _ in 0..100 {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let library = app.state.library.load();
+        if let Some(entry) = library.get_entry(&tid, &eid) {
+            if entry.path.extension().is_some_and(|ext| ext == "cbz") {
+                converted = true;
+                break;
+            }
+        }
+    }
+    assert!(converted, "the entry was not converted");
+
+    let response = app
+        .get("/api/admin/maintenance/convert", Some(&admin))
+        .await;
+    let report: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(report["converted"], 1);
+    assert_eq!(report["entries"][0]["entry_id"], eid.as_str());
+    assert_eq!(report["entries"][0]["status"], "converted");
+
+    // Same ID, same progress, same pages
+    let response = app.get(&uri, Some(&cookie)).await;
+    let progress: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(progress["page"], 2);
+    let page = body_bytes(
+        app.get(&format!("/api/page/{}/{}/2", tid, eid), Some(&cookie))
+            .await,
+    )
+    .await;
+    assert_eq!(page, png(70));
+}
+
+#[tokio::test]
+async fn test_rescan_single_title() {
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+    let (alpha, chapter_1) = app.ids("Alpha");
+    let (beta, _) = app.ids("Beta");
+    let root = app.state.config.library_path.clone();
+
+    write_stored_zip(
+        &root.join("Alpha").join("Chapter 3.zip"),
+        &[("001.png", png(60))],
+    );
+    // Not picked up: only Alpha is rescanned
+    write_stored_zip(
+        &root.join("Beta").join("Chapter 2.zip"),
+        &[("001.png", png(70))],
+    );
+
+    let uri = format!("/api/admin/scan/{}", alpha);
+    let response = post_json(&app, &uri, &admin, serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["title_id"], alpha);
+    assert_eq!(body["entries"], 3);
+    assert!(body["milliseconds"].is_u64());
+
+    let library = app.state.library.load();
+    let title = library.get_title(&alpha).unwrap();
+    assert_eq!(title.entries.len(), 3);
+    assert!(title.entries.iter().any(|e| e.id == chapter_1));
+    assert_eq!(library.get_title(&beta).unwrap().entries.len(), 1);
+
+    let response = post_json(
+        &app,
+        "/api/admin/scan/missing",
+        &admin,
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_scan_runs_in_background() {
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+
+    let report = scan_library(&app, &admin, "/api/admin/scan").await;
+    assert_eq!(report["titles"], 2);
+    let response = app.get("/api/admin/scan/status", Some(&admin)).await;
+    let status: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(status["job"]["titles_discovered"], 2);
+    assert_eq!(status["job"]["directories_remaining"], 0);
+    assert!(status["job"]["started_at"].is_i64());
+
+    // Only one scan at a time
+    let job_id = app.state.scan_status.start().unwrap();
+    let response = post_json(&app, "/api/admin/scan", &admin, serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app.get("/api/admin/scan/status", Some(&admin)).await;
+    let status: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(status["running"], true);
+    assert_eq!(status["job"]["job_id"], job_id);
+    assert!(status["job"]["finished_at"].is_null());
+}
+
+#[tokio::test]
+async fn test_title_readers() {
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+    let reader = app.login(READER).await;
+    let (alpha, chapter_1) = app.ids("Alpha");
+
+    let response = post_json(
+        &app,
+        &format!("/api/progress/{}/{}", alpha, chapter_1),
+        &reader,
+        serde_json::json!({ "page": 3 }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let uri = format!("/api/admin/title/{}/readers", alpha);
+    let response = app.get(&uri, Some(&admin)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let entries = body["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["id"], chapter_1);
+
+    let readers = body["readers"].as_array().unwrap();
+    let usernames: Vec<&str> = readers
+        .iter()
+        .map(|r| r["username"].as_str().unwrap())
+        .collect();
+    assert_eq!(usernames, [ADMIN.0, READER.0]);
+    assert_eq!(readers[0]["completed"], 0);
+    assert!(readers[0]["entries"][0]["last_read"].is_null());
+
+    assert_eq!(readers[1]["completed"], 1);
+    assert_eq!(readers[1]["percentage"], 50.0);
+    assert_eq!(readers[1]["entries"][0]["percentage"], 100.0);
+    assert!(readers[1]["entries"][0]["last_read"].is_i64());
+    assert_eq!(readers[1]["entries"][1]["percentage"], 0.0);
+
+    let response = app.get(&uri, Some(&reader)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .get("/api/admin/title/missing/readers", Some(&admin))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let app = setup_with(|config, _| config.admin_can_view_user_progress = false).await;
+    let admin = app.login(ADMIN).await;
+    let (alpha, _) = app.ids("Alpha");
+    let uri = format!("/api/admin/title/{}/readers", alpha);
+    let response = app.get(&uri, Some(&admin)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_library_served_during_scan() {
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+    let reader = app.login(READER).await;
+    let (alpha, chapter_1) = app.ids("Alpha");
+
+    // Scans build a new library on the side, so readers keep being served the current one
+    let page_uri = format!("/api/page/{}/{}/1", alpha, chapter_1);
+    let (report, (library, page)) =
+        tokio::join!(scan_library(&app, &admin, "/api/admin/scan"), async {
+            let library = app.get("/api/library", Some(&reader)).await;
+            let page = app.get(&page_uri, Some(&reader)).await;
+            (library.status(), page.status())
+        });
+    assert_eq!(library, StatusCode::OK);
+    assert_eq!(page, StatusCode::OK);
+    assert_eq!(report["titles"], 2);
+}
+
+#[tokio::test]
+async fn test_unreadable_page() {
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+    let reader = app.login(READER).await;
+    let (alpha, _) = app.ids("Alpha");
+    let archive = app
+        .state
+        .config
+        .library_path
+        .join("Alpha")
+        .join("Chapter 3.zip");
+    let broken = png(90);
+    write_stored_zip(
+        &archive,
+        &[("001.png", png(80)), ("002.png", broken.clone())],
+    );
+
+    let uri = format!("/api/admin/scan/{}", alpha);
+    let response = post_json(&app, &uri, &admin, serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let chapter_3 = {
+        let library = app.state.library.load();
+        let title = library.get_title(&alpha).unwrap();
+        let entry = title.entries.iter().find(|e| e.title == "Chapter 3");
+        entry.unwrap().id.clone()
+    };
+
+    // Cut the archive off in the middle of the second page
+    let data = std::fs::read(&archive).unwrap();
+    let offset = data
+        .windows(broken.len())
+        .position(|window| window == broken.as_slice())
+        .unwrap();
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(&archive)
+        .unwrap()
+        .set_len((offset + broken.len() / 2) as u64)
+        .unwrap();
+
+    let page = |n: usize| format!("/api/page/{}/{}/{}", alpha, chapter_3, n);
+    let response = app.get(&page(1), Some(&reader)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.get(&page(2), Some(&reader)).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["code"], "page_unreadable");
+    assert_eq!(body["page"], 2);
+    assert_eq!(body["member"], "002.png");
+    assert_eq!(body["archive"], "Alpha/Chapter 3.zip");
+
+    // The page is recorded as bad, and skipped by the reader from now on
+    let response = app.get("/api/admin/bad_pages", Some(&admin)).await;
+    let bad: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let bad = bad.as_array().unwrap();
+    assert_eq!(bad.len(), 1);
+    assert_eq!(bad[0]["entry_id"], chapter_3);
+    assert_eq!(bad[0]["bad_pages"], serde_json::json!([2]));
+    assert_eq!(bad[0]["pages"], 2);
+    let bad_pages = app.state.library.load().get_bad_pages(&chapter_3);
+    assert_eq!(bad_pages, vec![2]);
+
+    // A missing page is not a damaged one
+    let response = app.get(&page(3), Some(&reader)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+/// Names of the CORS headers of a response, sorted
+fn cors_headers(response: &Response) -> Vec<String> {
+    let mut names: Vec<String> = response
+        .headers()
+        .keys()
+        .map(|name| name.as_str().to_string())
+        .filter(|name| name.starts_with("access-control-"))
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn test_cors() {
+    const CLIENT: &str = "https://reader.example.com";
+    let app = setup_with(|config, _| config.cors_allowed_origins = vec![CLIENT.to_string()]).await;
+    let cookie = app.login(READER).await;
+
+    // Preflight requests carry no session and are answered before authentication
+    let response = app
+        .send(
+            Request::options("/api/library")
+                .header(header::ORIGIN, CLIENT)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+                .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        cors_headers(&response),
+        [
+            "access-control-allow-credentials",
+            "access-control-allow-headers",
+            "access-control-allow-methods",
+            "access-control-allow-origin",
+        ]
+    );
+    let headers = response.headers();
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], CLIENT);
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_METHODS],
+        "GET,POST,PUT,PATCH,DELETE"
+    );
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_HEADERS],
+        "content-type,authorization"
+    );
+
+    // Credentialed request from the client
+    let request = |origin: &str, uri: &str| {
+        Request::get(uri)
+            .header(header::ORIGIN, origin)
+            .header(header::COOKIE, &cookie)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app.send(request(CLIENT, "/api/library")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        cors_headers(&response),
+        [
+            "access-control-allow-credentials",
+            "access-control-allow-origin",
+            "access-control-expose-headers",
+        ]
+    );
+    let headers = response.headers();
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], CLIENT);
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_EXPOSE_HEADERS],
+        "x-hidden-completed,x-request-id"
+    );
+    let vary: Vec<&str> = headers
+        .get_all(header::VARY)
+        .iter()
+        .map(|value| value.to_str().unwrap())
+        .collect();
+    assert!(
+        vary.iter().any(|value| value.contains("origin")),
+        "{:?}",
+        vary
+    );
+
+    // Other origins and pages outside /api are not allowed
+    let response = app
+        .send(request("https://evil.example.com", "/api/library"))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response
+        .headers()
+        .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    let response = app.send(request(CLIENT, "/library")).await;
+    assert!(!response
+        .headers()
+        .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+    // Off by default
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let response = app
+        .send(
+            Request::get("/api/library")
+                .header(header::ORIGIN, CLIENT)
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert!(cors_headers(&response).is_empty());
+}
+
+#[tokio::test]
+async fn test_title_pins() {
+    let app = setup_with(|_, root| {
+        for (series, shade) in [("Gamma", 60), ("Delta", 70)] {
+            std::fs::create_dir(root.join(series)).unwrap();
+            write_stored_zip(
+                &root.join(series).join("Chapter 1.zip"),
+                &[("1.png", png(shade))],
+            );
+        }
+    })
+    .await;
+    let reader = app.login(READER).await;
+    let admin = app.login(ADMIN).await;
+    let (gamma, _) = app.ids("Gamma");
+    let (beta, _) = app.ids("Beta");
+
+    let pin = |method: &str, id: &str| {
+        Request::builder()
+            .method(method)
+            .uri(format!("/api/pins/{id}"))
+            .header(header::COOKIE, &reader)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let library = |uri: String, cookie: String| {
+        let app = &app;
+        async move {
+            let response = app.get(&uri, Some(&cookie)).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let titles: serde_json::Value =
+                serde_json::from_slice(&body_bytes(response).await).unwrap();
+            titles
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| {
+                    (
+                        t["id"].as_str().unwrap().to_string(),
+                        t["is_pinned"].as_bool().unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        }
+    };
+
+    let sorts = [
+        "sort=title&ascend=1",
+        "sort=title&ascend=0",
+        "sort=modified&ascend=1",
+        "sort=modified&ascend=0",
+        "sort=auto&ascend=1",
+        "sort=progress&ascend=0",
+        "sort=title&filter=unread",
+    ];
+    let mut unpinned = Vec::new();
+    for sort in sorts {
+        unpinned.push(library(format!("/api/library?{sort}"), reader.clone()).await);
+    }
+
+    // Pinned in this order; pinning again keeps the original place
+    for id in [&gamma, &beta, &gamma] {
+        let response = app.send(pin("PUT", id)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let response = app.get("/api/pins", Some(&reader)).await;
+    let pins: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(pins["pins"], serde_json::json!([gamma, beta]));
+
+    // Pinned titles lead every sort in pin order, the rest keep their sorted order
+    for (sort, unpinned) in sorts.iter().zip(&unpinned) {
+        let titles = library(format!("/api/library?{sort}"), reader.clone()).await;
+        let mut expected = vec![(gamma.clone(), true), (beta.clone(), true)];
+        expected.extend(
+            unpinned
+                .iter()
+                .filter(|(id, _)| *id != gamma && *id != beta)
+                .cloned(),
+        );
+        assert_eq!(titles, expected, "{sort}");
+    }
+
+    // Pins are per user
+    let titles = library("/api/library?sort=title".to_string(), admin.clone()).await;
+    assert!(titles.iter().all(|(_, pinned)| !pinned));
+
+    // The letter index covers the titles after the pinned ones
+    let response = app
+        .get("/api/library/index_letters?sort=title", Some(&reader))
+        .await;
+    let letters: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(
+        letters,
+        serde_json::json!([
+            {"letter": "A", "count": 1, "offset": 2},
+            {"letter": "D", "count": 1, "offset": 3},
+        ])
+    );
+
+    // Pins survive a rescan, and the first library page shows them
+    scan_library(&app, &admin, "/api/admin/scan").await;
+    let response = put_preferences(&app, &reader, serde_json::json!({"page_size": 2})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.get("/library?sort=title", Some(&reader)).await;
+    let html = String::from_utf8(body_bytes(response).await).unwrap();
+    let position = |name: &str| html.find(&format!("data-title=\"{name}\""));
+    assert!(position("Gamma").unwrap() < position("Beta").unwrap());
+    assert!(position("Alpha").is_none());
+    assert_eq!(html.matches("> Pinned</span>").count(), 2);
+
+    // Unpinning is idempotent; unknown titles cannot be pinned
+    for _ in 0..2 {
+        let response = app.send(pin("DELETE", &gamma)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let titles = library("/api/library?sort=title".to_string(), reader.clone()).await;
+    assert_eq!(titles[0], (beta.clone(), true));
+    assert!(titles[1..].iter().all(|(_, pinned)| !pinned));
+
+    let response = app.send(pin("PUT", "missing")).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_scan_exclude_patterns() {
+    let app = setup_with(|config, root| {
+        config.scan_exclude_patterns = vec!["@eaDir".to_string(), "*.sample.zip".to_string()];
+        // A NAS thumbnail folder in the library root and in a title
+        for dir in [root.join("@eaDir"), root.join("Alpha").join("@eaDir")] {
+            std::fs::create_dir(&dir).unwrap();
+            write_stored_zip(&dir.join("Chapter 1.zip"), &[("1.png", png(80))]);
+        }
+        write_stored_zip(
+            &root.join("Alpha").join("Chapter 3.sample.zip"),
+            &[("1.png", png(90))],
+        );
+    })
+    .await;
+    let cookie = app.login(READER).await;
+
+    let response = app.get("/api/library?sort=title", Some(&cookie)).await;
+    let titles: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let titles: Vec<(&str, u64)> = titles
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| (t["title"].as_str().unwrap(), t["entries"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(titles, [("Alpha", 2), ("Beta", 1)]);
+
+    // Nothing of the thumbnail folder in Alpha became a nested title
+    let (alpha, _) = app.ids("Alpha");
+    let response = app.get(&format!("/api/title/{alpha}"), Some(&cookie)).await;
+    let title: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(title["titles"], serde_json::json!([]));
+    assert_eq!(title["entries"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_cache_stats_by_kind() {
+    let app = setup_with(|config, _| config.memory_cache_enabled = true).await;
+    let reader = app.login(READER).await;
+    let admin = app.login(ADMIN).await;
+
+    // The first library page sorts the titles, the second one reuses the list
+    for _ in 0..2 {
+        let response = app.get("/library", Some(&reader)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let response = app.get("/api/cache/stats", Some(&reader)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.get("/api/cache/stats", Some(&admin)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let stats: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let titles = &stats["kinds"]["sorted_titles"];
+    assert_eq!(titles["miss_count"], 1);
+    assert_eq!(titles["hit_count"], 1);
+    assert_eq!(titles["entry_count"], 1);
+    assert_eq!(stats["hit_count"], titles["hit_count"]);
+
+    let response = app.get("/debug/cache", Some(&admin)).await;
+    let html = String::from_utf8(body_bytes(response).await).unwrap();
+    assert!(html.contains("sorted_titles"));
+}
+
+#[tokio::test]
+async fn test_entries_without_pages() {
+    let app = setup_with(|_, root| {
+        write_stored_zip(
+            &root.join("Alpha").join("Chapter 3.zip"),
+            &[("notes/readme.txt", b"no images here".to_vec())],
+        );
+    })
+    .await;
+    let admin = app.login(ADMIN).await;
+    let reader = app.login(READER).await;
+
+    // Alpha keeps its readable chapters only
+    let (alpha, _) = app.ids("Alpha");
+    let response = app.get(&format!("/api/title/{alpha}"), Some(&reader)).await;
+    let title: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(title["entries"].as_array().unwrap().len(), 2);
+    let feed = opds_feed(&app, &format!("/opds/book/{alpha}"), READER).await;
+    assert!(feed.contains("Chapter 2"));
+    assert!(!feed.contains("Chapter 3"));
+
+    // The admin finds it among the missing items, with nothing to delete
+    let response = app.get("/api/admin/entries/missing", Some(&admin)).await;
+    let items: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let items = items.as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["type"], "broken");
+    assert!(items[0]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("Chapter 3.zip"));
+}
+
+#[tokio::test]
+async fn test_collections() {
+    let app = setup().await;
+    let reader = app.login(READER).await;
+    let admin = app.login(ADMIN).await;
+    let (alpha, _) = app.ids("Alpha");
+    let (beta, _) = app.ids("Beta");
+
+    let request = |method: &str, uri: String, cookie: &str, body: Option<serde_json::Value>| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::COOKIE, cookie)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+            .unwrap()
+    };
+    let title_ids = |collection: &serde_json::Value| -> Vec<String> {
+        collection["title_ids"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|id| id.as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let response = post_json(
+        &app,
+        "/api/collections",
+        &reader,
+        serde_json::json!({"name": " Kids "}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(created["name"], "Kids");
+    let id = created["id"].as_str().unwrap().to_string();
+    let response = post_json(
+        &app,
+        "/api/collections",
+        &reader,
+        serde_json::json!({"name": "Kids"}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = post_json(
+        &app,
+        "/api/collections",
+        &reader,
+        serde_json::json!({"name": "  "}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    for tid in [&alpha, &beta] {
+        let uri = format!("/api/collections/{id}/titles/{tid}");
+        let response = app.send(request("PUT", uri, &reader, None)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let uri = format!("/api/collections/{id}/titles/missing");
+    let response = app.send(request("PUT", uri, &reader, None)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // The order must list every title of the collection once
+    let order = format!("/api/collections/{id}/order");
+    for invalid in [
+        serde_json::json!([&beta]),
+        serde_json::json!([&beta, &alpha, &alpha]),
+    ] {
+        let body = serde_json::json!({ "title_ids": invalid });
+        let response = app
+            .send(request("PUT", order.clone(), &reader, Some(body)))
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    let body = serde_json::json!({ "title_ids": [&beta, &alpha] });
+    let response = app.send(request("PUT", order, &reader, Some(body))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let collection: serde_json::Value =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(title_ids(&collection), [beta.clone(), alpha.clone()]);
+
+    // The page and the OPDS feed list the titles in that order
+    let html = body_text(app.get(&format!("/collections/{id}"), Some(&reader)).await).await;
+    let position = |text: &str, name: &str| text.find(&format!(">{}<", name)).unwrap();
+    assert!(position(&html, "Beta") < position(&html, "Alpha"));
+    let index = opds_feed(&app, "/opds", READER).await;
+    assert!(index.contains(&format!("href=\"/opds/collection/{id}\"")));
+    let feed = opds_feed(&app, &format!("/opds/collection/{id}"), READER).await;
+    assert!(position(&feed, "Beta") < position(&feed, "Alpha"));
+
+    // Other users neither see nor change the collection
+    let index = opds_feed(&app, "/opds", ADMIN).await;
+    assert!(!index.contains("/opds/collection/"));
+    for (method, uri) in [
+        ("GET", format!("/api/collections/{id}")),
+        ("GET", format!("/collections/{id}")),
+        ("PUT", format!("/api/collections/{id}/titles/{alpha}")),
+        ("DELETE", format!("/api/collections/{id}")),
+    ] {
+        let response = app.send(request(method, uri.clone(), &admin, None)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{method} {uri}");
+    }
+    let response = app.get("/api/collections", Some(&admin)).await;
+    let collections: serde_json::Value =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(collections["collections"], serde_json::json!([]));
+
+    // Removing a title keeps the others in order
+    let uri = format!("/api/collections/{id}/titles/{beta}");
+    let response = app.send(request("DELETE", uri, &reader, None)).await;
+    let collection: serde_json::Value =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(title_ids(&collection), std::slice::from_ref(&alpha));
+
+    let uri = format!("/api/collections/{id}");
+    let response = app
+        .send(request("DELETE", uri.clone(), &reader, None))
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.send(request("GET", uri, &reader, None)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_structured_error_bodies() {
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let error = |response: Response| async move {
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/json",
+            "{}",
+            response.status()
+        );
+        let status = response.status().as_u16();
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["code"], status);
+        body["error"].as_str().unwrap().to_string()
+    };
+
+    // Handler errors
+    let response = app.get("/api/title/nope", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(error(response).await.contains("Title not found: nope"));
+
+    let response = post_json(
+        &app,
+        "/api/collections",
+        &cookie,
+        serde_json::json!({ "name": " " }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(!error(response).await.is_empty());
+
+    // Rejected extractors
+    let response = app
+        .send(
+            Request::post("/api/collections")
+                .header(header::COOKIE, &cookie)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from("{not json"))
+                .unwrap(),
+        )
+        .await;
+    assert!(response.status().is_client_error());
+    assert!(!error(response).await.is_empty());
+
+    // OPDS clients get an XML error document
+    let response = app
+        .send(
+            Request::get("/opds/book/nope")
+                .header(header::AUTHORIZATION, basic_auth(READER))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/xml");
+    let xml = body_text(response).await;
+    assert_well_formed_xml(&xml);
+    assert!(xml.contains("<code>404</code>"));
+
+    // Pages keep their plain text errors
+    let response = app.get("/book/nope", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/plain; charset=utf-8"
+    );
+}
+
+#[tokio::test]
+async fn test_title_relations() {
+    let app = setup().await;
+    let admin = app.login(ADMIN).await;
+    let reader = app.login(READER).await;
+    let (alpha, _) = app.ids("Alpha");
+    let (beta, beta_entry) = app.ids("Beta");
+    let put_relations = |tid: &str, cookie: &str, body: serde_json::Value| {
+        Request::builder()
+            .method("PUT")
+            .uri(format!("/api/admin/title/{}/relations", tid))
+            .header(header::COOKIE, cookie)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .send(put_relations(
+            &beta,
+            &reader,
+            serde_json::json!({"sequel_of": alpha}),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .send(put_relations(
+            &beta,
+            &admin,
+            serde_json::json!({"sequel_of": alpha}),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Alpha continues in Beta, so Beta cannot continue in Alpha
+    let response = app
+        .send(put_relations(
+            &beta,
+            &admin,
+            serde_json::json!({"sequel_of": alpha, "prequel_of": alpha}),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .send(put_relations(
+            &alpha,
+            &admin,
+            serde_json::json!({"sequel_of": "nope"}),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .get(&format!("/api/title/{}", alpha), Some(&reader))
+        .await;
+    let detail: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(detail["relations"]["prequel_of"], beta.as_str());
+    let page = body_text(app.get(&format!("/book/{}", beta), Some(&reader)).await).await;
+    assert!(page.contains("Sequel of"));
+
+    // The reader offers Beta after Alpha's last entry
+    let last = {
+        let lib = app.state.library.load();
+        let title = lib.get_title(&alpha).unwrap();
+        title
+            .entries
+            .iter()
+            .find(|e| e.title == "Chapter 2")
+            .unwrap()
+            .id
+            .clone()
+    };
+    let page = body_text(
+        app.get(&format!("/reader/{}/{}/1", alpha, last), Some(&reader))
+            .await,
+    )
+    .await;
+    assert!(page.contains("Continue with Beta"));
+    assert!(page.contains(&format!("/reader/{}/{}/1", beta, beta_entry)));
+
+    // Once Alpha is finished, continue reading points to Beta
+    let alpha_entries: Vec<(String, usize)> = {
+        let lib = app.state.library.load();
+        let title = lib.get_title(&alpha).unwrap();
+        title
+            .entries
+            .iter()
+            .map(|e| (e.id.clone(), e.pages))
+            .collect()
+    };
+    for (eid, pages) in alpha_entries {
+        let response = post_json(
+            &app,
+            &format!("/api/progress/{}/{}", alpha, eid),
+            &reader,
+            serde_json::json!({ "page": pages }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let continue_reading = |uri: &'static str| {
+        let app = &app;
+        let reader = reader.clone();
+        async move {
+            let response = app.get(uri, Some(&reader)).await;
+            let items: serde_json::Value =
+                serde_json::from_slice(&body_bytes(response).await).unwrap();
+            items
+        }
+    };
+    let items = continue_reading("/api/library/continue_reading").await;
+    assert!(items
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|item| item["title_id"] == alpha.as_str()));
+    let items = continue_reading("/api/library/continue_reading?follow_sequels=true").await;
+    assert_eq!(items[0]["title_id"], beta.as_str());
+    assert_eq!(items[0]["entry_id"], beta_entry.as_str());
+    assert_eq!(items[0]["sequel_of"], alpha.as_str());
+    assert_eq!(items.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_builders_are_served_by_the_router() {
+    let app = setup_with(|config, root| {
+        let logo = root.with_file_name("logo.png");
+        std::fs::write(&logo, png(60)).unwrap();
+        config.logo_path = Some(logo);
+    })
+    .await;
+    let cookie = app.login(ADMIN).await;
+    let (tid, eid) = app.ids("Alpha");
+    app.state
+        .storage
+        .add_tag(&tid, "slice of life/comedy")
+        .await
+        .unwrap();
+    let collection = app
+        .state
+        .storage
+        .create_collection(ADMIN.0, "Favorites")
+        .await
+        .unwrap();
+    app.state
+        .storage
+        .add_to_collection(&collection.id, &tid)
+        .await
+        .unwrap();
+    let token = create_feed_token(&app, &cookie).await;
+
+    let built = [
+        paths::home(),
+        paths::login(),
+        paths::branding_logo(),
+        paths::library(),
+        paths::book(&tid),
+        paths::tags(),
+        paths::tag("slice of life/comedy"),
+        paths::collection(&collection.id),
+        paths::admin(),
+        paths::admin_users(),
+        paths::admin_user_edit(None),
+        paths::admin_user_edit(Some((READER.0, false))),
+        paths::reader(&tid, &eid, 3),
+        paths::reader_continue(&tid, &eid),
+        paths::opds(),
+        paths::opds_book(&tid),
+        paths::opds_collection(&collection.id),
+        paths::feed_recent(&token),
+        paths::feed_title(&tid, &token),
+        paths::api_page(&tid, &eid, 1),
+        paths::api_page_thumbnails(&tid, &eid),
+        paths::api_page_thumbnails_sprite(&tid, &eid),
+        paths::cover(&tid, &eid, "0a1b2c3d"),
+        paths::download(&tid, &eid),
+        paths::placeholder_cover(),
+        paths::cover_sized(&tid, &eid, "0a1b2c3d", Some(100)),
+        paths::title_cover(&tid, true, None, None),
+        paths::title_cover(&tid, true, Some("0a1b2c3d"), Some(100)),
+        paths::title_cover(&tid, false, None, Some(100)),
+        // Last, as it ends the session
+        paths::logout(),
+    ];
+
+    for url in &built {
+        let status = app.get(url, Some(&cookie)).await.status();
+        assert!(
+            status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED,
+            "GET {} is not served: {}",
+            url,
+            status
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_enabled_features_have_registered_routes() {
+    let app = setup_with(|config, _| {
+        config.download_quota_mbs = 100;
+        config.komga_compat_enabled = true;
+    })
+    .await;
+    let cookie = app.login(ADMIN).await;
+    let response = app.get("/api/capabilities", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let capabilities: serde_json::Value =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+
+    for (feature, enabled) in capabilities["features"].as_object().unwrap() {
+        if !enabled.as_bool().unwrap() {
+            continue;
+        }
+        let (_, routes) = FEATURE_ROUTES
+            .iter()
+            .find(|(name, _)| name == feature)
+            .unwrap_or_else(|| panic!("feature {} has no routes listed", feature));
+        for route in routes.iter() {
+            let path = route
+                .split('/')
+                .map(|segment| match segment.chars().next() {
+                    Some(':' | '*') => "x",
+                    _ => segment,
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            // No route answers TRACE: a registered path refuses the method (the Komga
+            // routes with 501), an unknown one is not found
+            let response = app
+                .send(
+                    Request::builder()
+                        .method(Method::TRACE)
+                        .uri(&path)
+                        .header(header::COOKIE, &cookie)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await;
+            assert!(
+                matches!(
+                    response.status(),
+                    StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+                ),
+                "feature {} advertises unregistered route {}: {}",
+                feature,
+                route,
+                response.status()
+            );
+        }
+    }
+}
+
+/// Check that every absolute link in `body` points below `base`
+fn assert_links_under(body: &str, base: &str) {
+    for attribute in ["href=\"", "src=\"", "data-src=\"", "action=\""] {
+        for (at, _) in body.match_indices(attribute) {
+            let value = &body[at + attribute.len()..];
+            let value = &value[..value.find('"').unwrap()];
+            assert!(
+                !value.starts_with('/') || value.starts_with(base),
+                "{}{}\" is outside {}",
+                attribute,
+                value,
+                base
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_routes_served_under_base_url() {
+    let app = setup_with(|config, _| config.base_url = "/mango/".to_string()).await;
+    let (tid, _) = app.ids("Alpha");
+
+    let response = app.get("/mango/login", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.get("/mango/static/js/common.js", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        app.get("/login", None).await.status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        app.get("/mangoes/login", None).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    // Authentication applies below the prefix just the same
+    let response = app.get("/mango/library", None).await;
+    assert!(response.status().is_redirection());
+    assert!(location(&response).starts_with("/mango/login"));
+    let response = app
+        .send(
+            Request::post("/mango/login")
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(format!(
+                    "username={}&password={}",
+                    READER.0, READER.1
+                )))
+                .unwrap(),
+        )
+        .await;
+    assert!(response.status().is_redirection());
+    assert!(location(&response).starts_with("/mango/"));
+    let cookie = response.headers()[header::SET_COOKIE]
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+
+    for uri in ["/mango", "/mango/", "/mango/api/library"] {
+        let response = app.get(uri, Some(&cookie)).await;
+        assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+    }
+
+    // Links, covers and OPDS feeds are generated under the prefix
+    let library = body_text(app.get("/mango/library", Some(&cookie)).await).await;
+    assert!(library.contains("data-src=\"/mango/api/title/"));
+    assert_links_under(&library, "/mango/");
+    let book = body_text(
+        app.get(&format!("/mango/book/{}", tid), Some(&cookie))
+            .await,
+    )
+    .await;
+    assert!(book.contains(&format!("data-src=\"/mango/api/cover/{}/", tid)));
+    assert!(book.contains("const base_url = \"/mango/\";"));
+    assert_links_under(&book, "/mango/");
+    let index = opds_feed(&app, "/mango/opds", READER).await;
+    assert!(index.contains("href=\"/mango/opds/continue\""));
+    assert_links_under(&index, "/mango/");
+    let feed = opds_feed(&app, &format!("/mango/opds/book/{}", tid), READER).await;
+    assert!(feed.contains("href=\"/mango/api/cover/"));
+    assert!(feed.contains("href=\"/mango/api/download/"));
+    assert_links_under(&feed, "/mango/");
+
+    // The base_url belongs to the app: another one mounted at / links without it
+    let root = setup().await;
+    let root_cookie = root.login(READER).await;
+    let library = body_text(root.get("/library", Some(&root_cookie)).await).await;
+    assert!(library.contains("data-src=\"/api/title/"));
+    assert!(!library.contains("/mango/"));
+    let response = app.get("/mango/api/title/nope", Some(&cookie)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let error: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(error["code"], 404);
+}
+
+/// Log output of a test's tracing subscriber
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_problem_documents() {
+    let logs = CapturedLogs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer({
+            let logs = logs.clone();
+            move || logs.clone()
+        })
+        .with_ansi(false)
+        .with_max_level(tracing::Level::INFO)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let app = setup().await;
+    let cookie = app.login(READER).await;
+    let (alpha, _) = app.ids("Alpha");
+    let problem = |uri: &str, request_id: Option<&str>| {
+        let mut request = Request::get(uri)
+            .header(header::COOKIE, &cookie)
+            .header(header::ACCEPT, "application/problem+json");
+        if let Some(request_id) = request_id {
+            request = request.header("x-request-id", request_id);
+        }
+        request.body(Body::empty()).unwrap()
+    };
+
+    let response = app.send(problem("/api/title/nope", None)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/problem+json"
+    );
+    let request_id = response.headers()["x-request-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let document: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(document["type"], "urn:mango:problem:not-found");
+    assert_eq!(document["title"], "Not found");
+    assert_eq!(document["status"], 404);
+    assert!(document["detail"].as_str().unwrap().contains("nope"));
+    assert_eq!(
+        document["instance"],
+        format!("urn:mango:request:{}", request_id)
+    );
+
+    // A database failure, correlated by the client's own request ID
+    sqlx::query("DROP TABLE title_related")
+        .execute(app.state.storage.pool())
+        .await
+        .unwrap();
+    let uri = format!("/api/title/{}", alpha);
+    let response = app.send(problem(&uri, Some("client-trace-42"))).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.headers()["x-request-id"], "client-trace-42");
+    let document: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(document["type"], "urn:mango:problem:database");
+    assert_eq!(document["title"], "Database error");
+    assert_eq!(document["status"], 500);
+    assert_eq!(document["instance"], "urn:mango:request:client-trace-42");
+
+    // Unusable inbound IDs are replaced
+    let response = app.send(problem(&uri, Some("not a usable id!"))).await;
+    assert_ne!(response.headers()["x-request-id"], "not a usable id!");
+
+    // Without the Accept header the usual error body is sent
+    let response = app.get(&uri, Some(&cookie)).await;
+    let error: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(error["code"], 500);
+
+    let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    assert!(output.contains(&request_id));
+    assert!(output.contains("client-trace-42"));
+}
+
+/// Lock or unlock a tag for the user of `cookie`
+async fn set_tag_lock(app: &TestApp, cookie: &str, method: &str, tag: &str, pin: &str) -> Response {
+    app.send(
+        Request::builder()
+            .method(method)
+            .uri(format!("/api/user/tag-locks/{}", tag))
+            .header(header::COOKIE, cookie)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::json!({ "pin": pin }).to_string()))
+            .unwrap(),
+    )
+    .await
+}
+
+/// Tag a title as the admin
+async fn tag_title(app: &TestApp, title_id: &str, tag: &str) {
+    let admin = app.login(ADMIN).await;
+    let response = app
+        .send(
+            Request::put(format!("/api/admin/tags/{}/{}", title_id, tag))
+                .header(header::COOKIE, &admin)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_tag_locks() {
+    let app = setup().await;
+    let reader = app.login(READER).await;
+    let admin = app.login(ADMIN).await;
+    let (alpha, alpha_entry) = app.ids("Alpha");
+    let (beta, _) = app.ids("Beta");
+    tag_title(&app, &alpha, "private").await;
+
+    let response = set_tag_lock(&app, &reader, "PUT", "private", "12").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = set_tag_lock(&app, &reader, "PUT", "private", "2468").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let locks: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(locks["tags"], serde_json::json!(["private"]));
+
+    // Listed, but without a cover URL naming the title
+    let hides_alpha_covers = |body: &str| {
+        !body.contains(&format!("{}/cover", alpha)) && !body.contains(&format!("cover/{}", alpha))
+    };
+    let library = body_text(app.get("/api/library", Some(&reader)).await).await;
+    assert!(library.contains(&alpha));
+    assert!(hides_alpha_covers(&library));
+    assert!(library.contains(&format!("/api/title/{}/cover", beta)));
+    let detail = body_text(
+        app.get(&format!("/api/title/{}", alpha), Some(&reader))
+            .await,
+    )
+    .await;
+    assert!(detail.contains(&alpha_entry));
+    assert!(hides_alpha_covers(&detail));
+    // Other users are not affected
+    let library = body_text(app.get("/api/library", Some(&admin)).await).await;
+    assert!(!hides_alpha_covers(&library));
+
+    let response = app.get(&format!("/book/{}", alpha), Some(&reader)).await;
+    assert_eq!(response.status(), StatusCode::LOCKED);
+    assert!(body_text(response).await.contains("PIN"));
+    for uri in [
+        format!("/reader/{}/{}/1", alpha, alpha_entry),
+        format!("/api/page/{}/{}/1", alpha, alpha_entry),
+        format!("/api/download/{}/{}", alpha, alpha_entry),
+        format!("/api/dimensions/{}/{}", alpha, alpha_entry),
+    ] {
+        let response = app.get(&uri, Some(&reader)).await;
+        assert_eq!(response.status(), StatusCode::LOCKED, "{}", uri);
+    }
+    let response = app
+        .get(&format!("/api/title/{}/cover", alpha), Some(&reader))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/jpeg");
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+
+    // The PIN has to be entered before the lock can be changed
+    let response = set_tag_lock(&app, &reader, "DELETE", "private", "").await;
+    assert_eq!(response.status(), StatusCode::LOCKED);
+    let response = post_json(
+        &app,
+        "/api/unlock",
+        &reader,
+        serde_json::json!({"pin": "1357"}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = post_json(
+        &app,
+        "/api/unlock",
+        &reader,
+        serde_json::json!({"pin": "2468"}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let unlocked: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(unlocked["unlocked"], serde_json::json!(["private"]));
+    assert!(unlocked["expires_at"].as_i64().unwrap() > now_ts());
+
+    let response = app.get(&format!("/book/{}", alpha), Some(&reader)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .get(
+            &format!("/api/page/{}/{}/1", alpha, alpha_entry),
+            Some(&reader),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let library = body_text(app.get("/api/library", Some(&reader)).await).await;
+    assert!(library.contains(&format!("/api/title/{}/cover", alpha)));
+
+    let response = set_tag_lock(&app, &reader, "DELETE", "private", "").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let locks: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(locks["tags"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn test_tag_unlock_expires() {
+    // Unlocks lapse as soon as they are made
+    let app = setup_with(|config, _| config.tag_unlock_minutes = 0).await;
+    let reader = app.login(READER).await;
+    let (alpha, alpha_entry) = app.ids("Alpha");
+    tag_title(&app, &alpha, "private").await;
+    let response = set_tag_lock(&app, &reader, "PUT", "private", "2468").await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = post_json(
+        &app,
+        "/api/unlock",
+        &reader,
+        serde_json::json!({"pin": "2468"}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .get(
+            &format!("/api/page/{}/{}/1", alpha, alpha_entry),
+            Some(&reader),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::LOCKED);
+    let library = body_text(app.get("/api/library", Some(&reader)).await).await;
+    assert!(!library.contains(&format!("/api/title/{}/cover", alpha)));
+}
+
+#[tokio::test]
+async fn test_wrong_pins_are_throttled_per_client() {
+    let app = setup().await;
+    let reader = app.login(READER).await;
+    let (alpha, _) = app.ids("Alpha");
+    tag_title(&app, &alpha, "private").await;
+    let response = set_tag_lock(&app, &reader, "PUT", "private", "2468").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let unlock = |cookie: &str, pin: &str| {
+        Request::post("/api/unlock")
+            .header(header::COOKIE, cookie)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::json!({ "pin": pin }).to_string()))
+            .unwrap()
+    };
+
+    for _ in 0..5 {
+        let response = app.send(unlock(&reader, "1357")).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+    let response = app.send(unlock(&reader, "2468")).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // The count is kept by the server, so a fresh session does not start it over
+    let reader = app.login(READER).await;
+    let response = app.send(unlock(&reader, "2468")).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Another client address can still enter the PIN
+    let response = app
+        .send(from_client(unlock(&reader, "2468"), [192, 168, 1, 30]))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_opds_leaves_out_locked_titles() {
+    let app = setup().await;
+    let reader = app.login(READER).await;
+    let (alpha, _) = app.ids("Alpha");
+    let (beta, _) = app.ids("Beta");
+    tag_title(&app, &alpha, "private").await;
+    let response = set_tag_lock(&app, &reader, "PUT", "private", "2468").await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for uri in ["/opds", "/opds/recent"] {
+        let feed = opds_feed(&app, uri, READER).await;
+        assert!(!feed.contains(&alpha), "{}", uri);
+        assert!(feed.contains(&beta), "{}", uri);
+    }
+    let response = app
+        .send(
+            Request::get(format!("/opds/book/{}", alpha))
+                .header(header::AUTHORIZATION, basic_auth(READER))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::LOCKED);
+
+    // Unlocking in a browser does not reach OPDS clients
+    let response = post_json(
+        &app,
+        "/api/unlock",
+        &reader,
+        serde_json::json!({"pin": "2468"}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let feed = opds_feed(&app, "/opds", READER).await;
+    assert!(!feed.contains(&alpha));
+
+    // Other users see the title
+    let feed = opds_feed(&app, &format!("/opds/book/{}", alpha), ADMIN).await;
+    assert!(feed.contains(&format!("/api/cover/{}/", alpha)));
+}